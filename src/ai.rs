@@ -0,0 +1,510 @@
+//! AI backends behind one trait: Anthropic, or any OpenAI-compatible chat completions API
+//! (OpenAI, Azure OpenAI, local gateways).
+//!
+//! Commands never build a client themselves; they call [`client`] with the config, so
+//! switching providers, models or token limits is a matter of `config ai ...`. Model names
+//! live only in this module.
+//!
+//! Every client from [`client`] answers repeated requests from the [`AiCache`], retries rate
+//! limits, overloads and network failures with backoff, fails with an [`AiFailure`] whose
+//! message says what to do about it, and records the tokens it used in the usage file.
+
+use crate::ai_cache::{self, AiCache};
+use crate::commands::call::{parse_retry_after, retry_delay};
+use crate::config::Config;
+use crate::usage::{ModelPrice, TokenUsage, UsageLedger};
+use anthropic::client::ClientBuilder;
+use anthropic::types::{ContentBlock, Message, MessagesRequestBuilder, Role};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+pub const DEFAULT_ANTHROPIC_MODEL: &str = "claude-sonnet-4-5";
+pub const DEFAULT_OPENAI_MODEL: &str = "gpt-4o-mini";
+pub const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com";
+/// Longest answer asked for unless `config ai max-tokens` says otherwise.
+pub const DEFAULT_MAX_TOKENS: usize = 2000;
+/// Tries per AI request, the first included.
+pub const MAX_AI_ATTEMPTS: u32 = 3;
+/// USD per million input and output tokens, matched by model name prefix so dated snapshots
+/// share their family's price. `config ai price` overrides these and prices other models.
+pub const DEFAULT_PRICES: &[(&str, f64, f64)] = &[
+    ("claude-3-haiku", 0.25, 1.25),
+    ("claude-3-opus", 15.0, 75.0),
+    ("claude-3-sonnet", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-haiku-4-5", 1.0, 5.0),
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1", 2.0, 8.0),
+];
+
+/// The built-in price of `model`, from the longest matching prefix in [`DEFAULT_PRICES`].
+pub fn default_price(model: &str) -> Option<ModelPrice> {
+    DEFAULT_PRICES.iter()
+        .filter(|(prefix, _, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _, _)| prefix.len())
+        .map(|&(_, input, output)| ModelPrice { input, output })
+}
+
+pub type AiError = Box<dyn Error>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum AiProvider {
+    #[default]
+    #[serde(rename = "anthropic")]
+    Anthropic,
+    /// Any API speaking OpenAI's `/v1/chat/completions`.
+    #[serde(rename = "openai")]
+    OpenAi,
+}
+
+impl AiProvider {
+    pub fn name(self) -> &'static str {
+        match self {
+            AiProvider::Anthropic => "anthropic",
+            AiProvider::OpenAi => "openai",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [AiProvider::Anthropic, AiProvider::OpenAi].into_iter().find(|provider| provider.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn default_model(self) -> &'static str {
+        match self {
+            AiProvider::Anthropic => DEFAULT_ANTHROPIC_MODEL,
+            AiProvider::OpenAi => DEFAULT_OPENAI_MODEL,
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == AiProvider::Anthropic
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AiRole {
+    User,
+    Assistant,
+}
+
+/// One turn of a conversation with the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AiMessage {
+    pub role: AiRole,
+    pub text: String,
+}
+
+impl AiMessage {
+    pub fn user(text: impl Into<String>) -> Self {
+        Self { role: AiRole::User, text: text.into() }
+    }
+
+    pub fn assistant(text: impl Into<String>) -> Self {
+        Self { role: AiRole::Assistant, text: text.into() }
+    }
+}
+
+/// A text answer and the tokens it cost, when the provider says.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AiAnswer {
+    pub text: String,
+    pub usage: Option<TokenUsage>,
+}
+
+#[async_trait]
+pub trait AiClient: Send + Sync {
+    /// The model's answer to `messages`, with its token usage.
+    async fn answer(&self, messages: &[AiMessage]) -> Result<AiAnswer, AiError>;
+
+    /// The model's text answer to `messages`.
+    async fn complete(&self, messages: &[AiMessage]) -> Result<String, AiError> {
+        self.answer(messages).await.map(|answer| answer.text)
+    }
+
+    /// The answer to a single user prompt.
+    async fn ask(&self, prompt: &str) -> Result<String, AiError> {
+        self.complete(&[AiMessage::user(prompt)]).await
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AiFailureKind {
+    InvalidKey,
+    QuotaExhausted,
+    RateLimited,
+    /// 5xx, or Anthropic's 529 "overloaded".
+    Unavailable,
+    Network,
+    ContentFiltered,
+    UnknownModel,
+    Other,
+}
+
+/// A failed AI request, classified so the user is told what to do rather than given the
+/// raw provider error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AiFailure {
+    pub kind: AiFailureKind,
+    pub provider: AiProvider,
+    pub status: Option<u16>,
+    /// The provider's own explanation.
+    pub message: String,
+    /// How long the provider asked to wait before trying again.
+    pub retry_after: Option<Duration>,
+    /// Tries made before giving up.
+    pub attempts: u32,
+}
+
+impl AiFailure {
+    /// Classify an error answer from either provider: `status` and the JSON body, which is
+    /// `{"error": {"type", "code", "message"}}` for both.
+    pub fn classify(provider: AiProvider, status: Option<u16>, body: &Value) -> Self {
+        let error = body.get("error").unwrap_or(body);
+        let message = error.get("message").and_then(Value::as_str).unwrap_or("no error message").to_string();
+        let codes: Vec<&str> = ["type", "code"].iter().filter_map(|field| error.get(*field).and_then(Value::as_str)).collect();
+        let has = |code: &str| codes.contains(&code);
+        let lower = message.to_ascii_lowercase();
+
+        let kind = if has("authentication_error") || has("invalid_api_key") || has("permission_error") || matches!(status, Some(401 | 403)) {
+            AiFailureKind::InvalidKey
+        } else if has("insufficient_quota") || has("billing_error") || lower.contains("credit balance") || lower.contains("exceeded your current quota") {
+            AiFailureKind::QuotaExhausted
+        } else if has("content_filter") || has("content_policy_violation") || lower.contains("content management policy") {
+            AiFailureKind::ContentFiltered
+        } else if has("rate_limit_error") || has("rate_limit_exceeded") || status == Some(429) {
+            AiFailureKind::RateLimited
+        } else if has("overloaded_error") || has("api_error") || status.is_some_and(|s| s >= 500) {
+            AiFailureKind::Unavailable
+        } else if has("model_not_found") || has("not_found_error") || status == Some(404) {
+            AiFailureKind::UnknownModel
+        } else {
+            AiFailureKind::Other
+        };
+        Self { kind, provider, status, message, retry_after: None, attempts: 1 }
+    }
+
+    /// Classify an error that came as text only, as the Anthropic client reports them: the
+    /// provider's JSON body when the text carries one, a network failure when it says so.
+    pub fn from_text(provider: AiProvider, text: &str) -> Self {
+        let body = text.find('{').and_then(|start| serde_json::from_str::<Value>(&text[start..]).ok());
+        match body {
+            Some(body) if body.get("error").is_some() => Self::classify(provider, None, &body),
+            _ if text.to_ascii_lowercase().contains("network") => Self::network(provider, text),
+            _ => Self { kind: AiFailureKind::Other, provider, status: None, message: text.to_string(), retry_after: None, attempts: 1 },
+        }
+    }
+
+    pub fn network(provider: AiProvider, message: impl fmt::Display) -> Self {
+        Self { kind: AiFailureKind::Network, provider, status: None, message: message.to_string(), retry_after: None, attempts: 1 }
+    }
+
+    pub fn content_filtered(provider: AiProvider) -> Self {
+        let message = "the answer was withheld by the content filter".to_string();
+        Self { kind: AiFailureKind::ContentFiltered, provider, status: None, message, retry_after: None, attempts: 1 }
+    }
+
+    /// Worth trying again: the same request may succeed once the provider recovers.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.kind, AiFailureKind::RateLimited | AiFailureKind::Unavailable | AiFailureKind::Network)
+    }
+
+    fn key_command(&self) -> &'static str {
+        match self.provider {
+            AiProvider::Anthropic => "config api-key",
+            AiProvider::OpenAi => "config ai key <key>",
+        }
+    }
+}
+
+impl fmt::Display for AiFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let provider = self.provider.name();
+        let tries = if self.attempts > 1 { format!(" after {} attempts", self.attempts) } else { String::new() };
+        match self.kind {
+            AiFailureKind::InvalidKey => write!(f, "API key invalid — run '{}'", self.key_command()),
+            AiFailureKind::QuotaExhausted => write!(f, "{} quota exhausted — check the plan and billing of your account", provider),
+            AiFailureKind::RateLimited => write!(f, "Rate limited by {}{} — wait a minute and try again", provider, tries),
+            AiFailureKind::Unavailable => write!(
+                f, "{} is unavailable ({}){} — try again later", provider,
+                self.status.map_or_else(|| self.message.clone(), |status| format!("HTTP {}", status)), tries
+            ),
+            AiFailureKind::Network => write!(f, "Could not reach {}{}: {} — check your connection and 'config ai base-url'", provider, tries, self.message),
+            AiFailureKind::ContentFiltered => write!(f, "{} content filter blocked the request — rephrase it", provider),
+            AiFailureKind::UnknownModel => write!(f, "{} does not know the model — pick another with 'config ai model <name>'", provider),
+            AiFailureKind::Other => match self.status {
+                Some(status) => write!(f, "{} answered {}: {}", provider, status, self.message),
+                None => write!(f, "{} request failed: {}", provider, self.message),
+            },
+        }
+    }
+}
+
+impl Error for AiFailure {}
+
+/// Tries each request up to [`MAX_AI_ATTEMPTS`] times while it fails with a retryable
+/// [`AiFailure`], waiting as the provider's `Retry-After` says or 1s, 2s, ... otherwise.
+pub struct RetryingClient {
+    inner: Box<dyn AiClient>,
+    attempts: u32,
+}
+
+impl RetryingClient {
+    pub fn new(inner: Box<dyn AiClient>) -> Self {
+        Self { inner, attempts: MAX_AI_ATTEMPTS }
+    }
+}
+
+#[async_trait]
+impl AiClient for RetryingClient {
+    async fn answer(&self, messages: &[AiMessage]) -> Result<AiAnswer, AiError> {
+        let mut attempt = 1;
+        loop {
+            let error = match self.inner.answer(messages).await {
+                Ok(answer) => return Ok(answer),
+                Err(error) => error,
+            };
+            let Some(failure) = error.downcast_ref::<AiFailure>() else { return Err(error) };
+            if !failure.is_retryable() || attempt >= self.attempts {
+                return Err(Box::new(AiFailure { attempts: attempt, ..failure.clone() }));
+            }
+            let delay = retry_delay(attempt, failure.retry_after);
+            drop(error);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Answers from the cache when the same model was asked the same thing within the TTL, and
+/// caches every fresh answer. Cached answers cost nothing, so they carry no usage.
+pub struct CachingClient {
+    inner: Box<dyn AiClient>,
+    cache: AiCache,
+    model: String,
+}
+
+impl CachingClient {
+    pub fn new(inner: Box<dyn AiClient>, cache: AiCache, model: &str) -> Self {
+        Self { inner, cache, model: model.to_string() }
+    }
+}
+
+#[async_trait]
+impl AiClient for CachingClient {
+    async fn answer(&self, messages: &[AiMessage]) -> Result<AiAnswer, AiError> {
+        let key = AiCache::key(&self.model, messages);
+        if !ai_cache::is_bypassed() {
+            if let Some(text) = self.cache.get(&key) {
+                return Ok(AiAnswer { text, usage: None });
+            }
+        }
+        let answer = self.inner.answer(messages).await?;
+        let _ = self.cache.put(&key, &self.model, &answer.text);
+        Ok(answer)
+    }
+}
+
+/// Adds the usage of every answer to the usage file under `command`, and warns when that
+/// takes today's estimated cost past the daily budget. Failing to record never fails the
+/// request.
+pub struct MeteredClient {
+    inner: Box<dyn AiClient>,
+    ledger: UsageLedger,
+    command: String,
+    config: Config,
+}
+
+impl MeteredClient {
+    pub fn new(inner: Box<dyn AiClient>, ledger: UsageLedger, command: &str, config: Config) -> Self {
+        Self { inner, ledger, command: command.to_string(), config }
+    }
+}
+
+#[async_trait]
+impl AiClient for MeteredClient {
+    async fn answer(&self, messages: &[AiMessage]) -> Result<AiAnswer, AiError> {
+        let answer = self.inner.answer(messages).await?;
+        if let Some(usage) = answer.usage {
+            if let Ok(book) = self.ledger.record(&self.command, self.config.ai_model(), usage) {
+                let spent = book.cost_on(&crate::usage::today(), |model| self.config.model_price(model));
+                if let Some(budget) = self.config.ai_daily_budget.filter(|budget| spent > *budget) {
+                    println!("⚠️  AI spend today is about ${:.2}, over the ${:.2} daily budget (see 'usage')", spent, budget);
+                }
+            }
+        }
+        Ok(answer)
+    }
+}
+
+/// Take `--model NAME` out of `args`: the model one command should ask instead of the
+/// configured one.
+pub fn take_model_flag(args: &mut Vec<String>) -> Result<Option<String>, String> {
+    let Some(i) = args.iter().position(|arg| arg == "--model") else { return Ok(None) };
+    let model = args.get(i + 1).filter(|model| !model.starts_with("--")).cloned().ok_or("Value required after --model")?;
+    args.drain(i..=i + 1);
+    Ok(Some(model))
+}
+
+/// The client for the configured provider, model and token limit, recording its usage under
+/// `command`, or an error when the provider has no API key.
+pub fn client(config: &Config, command: &str) -> Result<Box<dyn AiClient>, Box<dyn Error>> {
+    let api_key = config.require_ai_key()?.to_string();
+    let (model, max_tokens) = (config.ai_model().to_string(), config.ai_max_tokens());
+    let inner: Box<dyn AiClient> = match config.ai_provider {
+        AiProvider::Anthropic => Box::new(AnthropicClient::new(api_key, model, max_tokens)?),
+        AiProvider::OpenAi => {
+            let base_url = config.ai_base_url.as_deref().unwrap_or(DEFAULT_OPENAI_BASE_URL);
+            Box::new(OpenAiClient::new(base_url, api_key, model, max_tokens))
+        }
+    };
+    let mut client: Box<dyn AiClient> = Box::new(RetryingClient::new(inner));
+    if config.ai_cache_ttl_days() > 0 {
+        client = Box::new(CachingClient::new(client, AiCache::configured(config)?, config.ai_model()));
+    }
+    let ledger = UsageLedger::new(UsageLedger::default_path()?);
+    Ok(Box::new(MeteredClient::new(client, ledger, command, config.clone())))
+}
+
+/// A client for AI output the command can do without, such as insights next to its own
+/// results. With `config ai budget <usd> --enforce-budget` there is none once today's
+/// estimated spend reached the budget.
+pub fn optional_client(config: &Config, command: &str) -> Result<Box<dyn AiClient>, Box<dyn Error>> {
+    if let Some(budget) = config.ai_daily_budget.filter(|_| config.ai_enforce_budget) {
+        let book = UsageLedger::new(UsageLedger::default_path()?).load()?;
+        let spent = book.cost_on(&crate::usage::today(), |model| config.model_price(model));
+        if spent >= budget {
+            return Err(format!(
+                "daily AI budget of ${:.2} reached (about ${:.2} spent today) — raise it with 'config ai budget <usd>'",
+                budget, spent
+            ).into());
+        }
+    }
+    client(config, command)
+}
+
+pub struct AnthropicClient {
+    client: anthropic::Client,
+    model: String,
+    max_tokens: usize,
+}
+
+impl AnthropicClient {
+    pub fn new(api_key: String, model: String, max_tokens: usize) -> Result<Self, Box<dyn Error>> {
+        Ok(Self { client: ClientBuilder::default().api_key(api_key).build()?, model, max_tokens })
+    }
+}
+
+#[async_trait]
+impl AiClient for AnthropicClient {
+    async fn answer(&self, messages: &[AiMessage]) -> Result<AiAnswer, AiError> {
+        let messages = messages.iter()
+            .map(|message| Message {
+                role: match message.role {
+                    AiRole::User => Role::User,
+                    AiRole::Assistant => Role::Assistant,
+                },
+                content: vec![ContentBlock::Text { text: message.text.clone() }],
+            })
+            .collect::<Vec<_>>();
+        let request = MessagesRequestBuilder::default()
+            .messages(messages)
+            .model(self.model.clone())
+            .max_tokens(self.max_tokens)
+            .build()?;
+        let response = self.client.messages(request).await
+            .map_err(|e| AiFailure::from_text(AiProvider::Anthropic, &e.to_string()))?;
+        let usage = TokenUsage { input_tokens: response.usage.input_tokens.into(), output_tokens: response.usage.output_tokens.into() };
+        match response.content.first() {
+            Some(ContentBlock::Text { text }) => Ok(AiAnswer { text: text.clone(), usage: Some(usage) }),
+            _ if response.stop_reason.as_deref() == Some("refusal") => Err(Box::new(AiFailure::content_filtered(AiProvider::Anthropic))),
+            _ => Err("The Anthropic API answered without text".into()),
+        }
+    }
+}
+
+/// Client for OpenAI's chat completions API and compatible ones. Azure OpenAI hosts
+/// (`*.openai.azure.com`) get their `api-key` header instead of a bearer token.
+pub struct OpenAiClient {
+    http: reqwest::Client,
+    endpoint: String,
+    api_key: String,
+    model: String,
+    max_tokens: usize,
+    azure: bool,
+}
+
+impl OpenAiClient {
+    /// `base_url` is the API root (`https://api.openai.com`, with or without `/v1`) or the
+    /// full chat completions URL, as Azure deployments need.
+    pub fn new(base_url: &str, api_key: String, model: String, max_tokens: usize) -> Self {
+        let base = base_url.trim_end_matches('/');
+        let path = base.split('?').next().unwrap_or(base);
+        let endpoint = if path.ends_with("/chat/completions") {
+            base.to_string()
+        } else if path.ends_with("/v1") {
+            format!("{}/chat/completions", base)
+        } else {
+            format!("{}/v1/chat/completions", base)
+        };
+        let azure = url::Url::parse(&endpoint).ok()
+            .and_then(|url| url.host_str().map(|host| host.ends_with(".openai.azure.com")))
+            .unwrap_or(false);
+        Self { http: reqwest::Client::new(), endpoint, api_key, model, max_tokens, azure }
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+#[async_trait]
+impl AiClient for OpenAiClient {
+    async fn answer(&self, messages: &[AiMessage]) -> Result<AiAnswer, AiError> {
+        let messages: Vec<Value> = messages.iter()
+            .map(|message| json!({
+                "role": match message.role {
+                    AiRole::User => "user",
+                    AiRole::Assistant => "assistant",
+                },
+                "content": message.text,
+            }))
+            .collect();
+        let request = self.http.post(&self.endpoint).json(&json!({
+            "model": self.model,
+            "messages": messages,
+            "max_tokens": self.max_tokens,
+        }));
+        let request = if self.azure { request.header("api-key", &self.api_key) } else { request.bearer_auth(&self.api_key) };
+
+        let response = request.send().await.map_err(|e| AiFailure::network(AiProvider::OpenAi, e))?;
+        let status = response.status();
+        let retry_after = response.headers().get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_retry_after(value, chrono::Utc::now()));
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            let failure = AiFailure::classify(AiProvider::OpenAi, Some(status.as_u16()), &body);
+            return Err(Box::new(AiFailure { retry_after, ..failure }));
+        }
+        if body.pointer("/choices/0/finish_reason").and_then(Value::as_str) == Some("content_filter") {
+            return Err(Box::new(AiFailure::content_filtered(AiProvider::OpenAi)));
+        }
+        let text = body.pointer("/choices/0/message/content")
+            .and_then(Value::as_str)
+            .ok_or("The OpenAI-compatible API answered without choices[0].message.content")?;
+        let usage = body.get("usage").map(|usage| TokenUsage {
+            input_tokens: usage["prompt_tokens"].as_u64().unwrap_or_default(),
+            output_tokens: usage["completion_tokens"].as_u64().unwrap_or_default(),
+        });
+        Ok(AiAnswer { text: text.to_string(), usage })
+    }
+}
@@ -0,0 +1,153 @@
+//! Answers to earlier AI requests, so asking the same model the same thing again costs
+//! nothing.
+//!
+//! Entries live in `~/.nuts/cache/ai/<sha256>.json`, keyed by the model and the whole
+//! conversation, and expire after `config ai cache-ttl` days (7 by default; 0 turns the cache
+//! off). `--no-cache` on any command line asks again and refreshes the entry.
+
+use crate::ai::{AiMessage, AiRole};
+use crate::config::Config;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+pub const DEFAULT_CACHE_TTL_DAYS: u64 = 7;
+
+/// Set for the duration of a command given `--no-cache`.
+static BYPASS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_bypass(bypass: bool) {
+    BYPASS.store(bypass, Ordering::SeqCst);
+}
+
+pub fn is_bypassed() -> bool {
+    BYPASS.load(Ordering::SeqCst)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedAnswer {
+    pub model: String,
+    pub created_at: DateTime<Utc>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    pub entries: usize,
+    /// Entries past the TTL, removed on `cache clear` or replaced when asked again.
+    pub expired: usize,
+    pub bytes: u64,
+}
+
+pub struct AiCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl AiCache {
+    pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self { dir: dir.into(), ttl }
+    }
+
+    /// The cache in the default directory, with the configured TTL.
+    pub fn configured(config: &Config) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::new(Self::default_dir()?, Duration::from_secs(config.ai_cache_ttl_days() * 24 * 3600)))
+    }
+
+    /// `~/.nuts/cache/ai`.
+    pub fn default_dir() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(dirs::home_dir()
+            .ok_or("Could not find home directory")?
+            .join(".nuts")
+            .join("cache")
+            .join("ai"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// SHA-256 of the model and every message, hex encoded.
+    pub fn key(model: &str, messages: &[AiMessage]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(model.as_bytes());
+        for message in messages {
+            let role: &[u8] = match message.role {
+                AiRole::User => b"\0user\0",
+                AiRole::Assistant => b"\0assistant\0",
+            };
+            hasher.update(role);
+            hasher.update(message.text.as_bytes());
+        }
+        hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    fn is_fresh(&self, answer: &CachedAnswer) -> bool {
+        (Utc::now() - answer.created_at).to_std().map_or(true, |age| age < self.ttl)
+    }
+
+    /// The cached answer for `key`, unless it is missing, unreadable or expired.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let content = std::fs::read_to_string(self.path(key)).ok()?;
+        let answer: CachedAnswer = serde_json::from_str(&content).ok()?;
+        self.is_fresh(&answer).then_some(answer.text)
+    }
+
+    /// Store an answer. Written to a temporary file first, so a concurrent `get` never reads
+    /// half an entry.
+    pub fn put(&self, key: &str, model: &str, text: &str) -> Result<(), Box<dyn Error>> {
+        std::fs::create_dir_all(&self.dir)?;
+        let answer = CachedAnswer { model: model.to_string(), created_at: Utc::now(), text: text.to_string() };
+        let temporary = self.dir.join(format!("{}.{}.tmp", key, std::process::id()));
+        std::fs::write(&temporary, serde_json::to_string_pretty(&answer)?)?;
+        std::fs::rename(&temporary, self.path(key))?;
+        Ok(())
+    }
+
+    pub fn stats(&self) -> Result<CacheStats, Box<dyn Error>> {
+        let mut stats = CacheStats::default();
+        for (path, size) in self.entries()? {
+            stats.entries += 1;
+            stats.bytes += size;
+            let fresh = std::fs::read_to_string(&path).ok()
+                .and_then(|content| serde_json::from_str::<CachedAnswer>(&content).ok())
+                .is_some_and(|answer| self.is_fresh(&answer));
+            if !fresh {
+                stats.expired += 1;
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Remove every entry; returns how many there were.
+    pub fn clear(&self) -> Result<usize, Box<dyn Error>> {
+        let entries = self.entries()?;
+        for (path, _) in &entries {
+            std::fs::remove_file(path)?;
+        }
+        Ok(entries.len())
+    }
+
+    fn entries(&self) -> Result<Vec<(PathBuf, u64)>, Box<dyn Error>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                entries.push((path, entry.metadata()?.len()));
+            }
+        }
+        Ok(entries)
+    }
+}
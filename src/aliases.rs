@@ -0,0 +1,105 @@
+//! Other names for shell commands: the built-in short ones in [`COMMAND_ALIASES`] and the
+//! macros saved with `alias add`, kept in the config.
+//!
+//! A macro stands for a whole command line. `$1`, `$2`... in it take the arguments typed after
+//! the macro's name; the arguments no placeholder takes are appended. A macro may start with
+//! another macro, but not lead back to itself.
+
+use crate::completer::SHELL_COMMANDS;
+use crate::shell::split_args;
+use std::collections::BTreeMap;
+
+/// Short names the shell accepts for a command.
+pub const COMMAND_ALIASES: &[(&str, &str)] = &[("c", "call"), ("collection", "flow"), ("h", "help"), ("p", "perf"), ("q", "quit")];
+
+/// The command `name` stands for, following [`COMMAND_ALIASES`].
+pub fn resolve_alias(name: &str) -> &str {
+    COMMAND_ALIASES.iter().find(|(alias, _)| *alias == name).map_or(name, |(_, command)| command)
+}
+
+/// Names to offer besides the commands: the built-in aliases longer than a letter, then the
+/// macros.
+pub fn names(macros: &BTreeMap<String, String>) -> Vec<&str> {
+    COMMAND_ALIASES.iter()
+        .map(|(alias, _)| *alias)
+        .filter(|alias| alias.len() > 1)
+        .chain(macros.keys().map(String::as_str))
+        .collect()
+}
+
+/// `parts` with a leading macro replaced by what it stands for, repeatedly, and a built-in
+/// alias by its command.
+pub fn expand(mut parts: Vec<String>, macros: &BTreeMap<String, String>) -> Result<Vec<String>, String> {
+    let mut chain: Vec<String> = Vec::new();
+    while let Some(body) = parts.first().and_then(|name| macros.get(name)) {
+        let name = parts[0].clone();
+        let looped = chain.contains(&name);
+        chain.push(name);
+        if looped {
+            return Err(format!("Alias loop: {}", chain.join(" → ")));
+        }
+        parts = fill(&chain[chain.len() - 1], body, &parts[1..])?;
+    }
+    if let Some(first) = parts.first_mut() {
+        *first = resolve_alias(first).to_string();
+    }
+    Ok(parts)
+}
+
+/// Check that `name` can be saved as a macro for `body`: it must not shadow a command or a
+/// built-in alias, and `body` must not lead back to it.
+pub fn validate(name: &str, body: &str, macros: &BTreeMap<String, String>) -> Result<(), String> {
+    if name.is_empty() || name.starts_with('-') || name.contains(char::is_whitespace) {
+        return Err(format!("'{}' can't be an alias name", name));
+    }
+    if SHELL_COMMANDS.iter().any(|command| command.name == name) || COMMAND_ALIASES.iter().any(|(alias, _)| *alias == name) {
+        return Err(format!("'{}' is a built-in command or alias", name));
+    }
+    if split_args(body).is_empty() {
+        return Err(format!("Alias '{}' needs a command to stand for", name));
+    }
+    let mut chain = vec![name.to_string()];
+    let mut next = split_args(body).into_iter().next();
+    while let Some(word) = next {
+        let Some(next_body) = (word == name).then_some(body).or_else(|| macros.get(&word).map(String::as_str)) else {
+            break;
+        };
+        let looped = chain.contains(&word);
+        chain.push(word);
+        if looped {
+            return Err(format!("Alias loop: {}", chain.join(" → ")));
+        }
+        next = split_args(next_body).into_iter().next();
+    }
+    Ok(())
+}
+
+/// `body` with its placeholders filled from `args`, and the untaken arguments after it.
+fn fill(name: &str, body: &str, args: &[String]) -> Result<Vec<String>, String> {
+    let mut taken = vec![false; args.len()];
+    let mut parts = Vec::new();
+    for word in split_args(body) {
+        let mut filled = String::new();
+        let mut chars = word.chars().peekable();
+        while let Some(c) = chars.next() {
+            let mut digits = String::new();
+            while c == '$' && chars.peek().is_some_and(char::is_ascii_digit) {
+                digits.push(chars.next().unwrap());
+            }
+            match digits.parse::<usize>() {
+                Ok(0) | Err(_) => {
+                    filled.push(c);
+                    filled.push_str(&digits);
+                }
+                Ok(n) => {
+                    let arg = args.get(n - 1).ok_or_else(|| format!("Alias '{}' needs at least {} argument(s): {}", name, n, body))?;
+                    taken[n - 1] = true;
+                    filled.push_str(arg);
+                }
+            }
+        }
+        parts.push(filled);
+    }
+    parts.extend(args.iter().zip(taken).filter(|(_, taken)| !taken).map(|(arg, _)| arg.clone()));
+    Ok(parts)
+}
@@ -0,0 +1,129 @@
+//! OAuth2 client-credentials tokens for `call --auth oauth2` and `perf --auth oauth2`.
+//!
+//! The token is fetched on first use, cached in memory and fetched again once it is within
+//! [`REFRESH_MARGIN`] of its `expires_in`, so long perf runs keep a valid token.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+/// Refresh a cached token once it has less than this left.
+pub const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// Client-credentials settings from `config auth oauth2`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OAuth2Config {
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+impl OAuth2Config {
+    /// `--token-url URL --client-id ID --client-secret SECRET [--scope S]`
+    pub fn from_args(args: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let (mut token_url, mut client_id, mut client_secret, mut scope) = (None, None, None, None);
+        let mut i = 0;
+        while i < args.len() {
+            let value = args.get(i + 1).map(|v| v.to_string());
+            let slot = match args[i] {
+                "--token-url" => &mut token_url,
+                "--client-id" => &mut client_id,
+                "--client-secret" => &mut client_secret,
+                "--scope" => &mut scope,
+                other => return Err(format!("Unknown option: {}", other).into()),
+            };
+            *slot = Some(value.ok_or_else(|| format!("Value required after {}", args[i]))?);
+            i += 2;
+        }
+
+        let token_url = token_url.ok_or("--token-url is required")?;
+        url::Url::parse(&token_url).map_err(|e| format!("Invalid token URL '{}': {}", token_url, e))?;
+        Ok(Self {
+            token_url,
+            client_id: client_id.ok_or("--client-id is required")?,
+            client_secret: client_secret.ok_or("--client-secret is required")?,
+            scope,
+        })
+    }
+}
+
+/// An access token and when it stops being valid.
+#[derive(Debug, Clone)]
+pub struct AccessToken {
+    pub value: String,
+    /// `None` when the endpoint didn't send `expires_in`; such tokens are kept for the session.
+    pub expires_at: Option<Instant>,
+}
+
+impl AccessToken {
+    pub fn needs_refresh(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|at| now + REFRESH_MARGIN >= at)
+    }
+}
+
+/// Fetches and caches the access token for one [`OAuth2Config`].
+pub struct TokenManager {
+    config: OAuth2Config,
+    client: Client,
+    cached: tokio::sync::Mutex<Option<AccessToken>>,
+}
+
+impl TokenManager {
+    pub fn new(config: OAuth2Config) -> Self {
+        Self {
+            config,
+            client: Client::new(),
+            cached: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// A valid access token, fetching a new one when none is cached or it is about to expire.
+    /// Concurrent callers wait for a single fetch.
+    pub async fn token(&self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let mut cached = self.cached.lock().await;
+        if let Some(token) = cached.as_ref().filter(|t| !t.needs_refresh(Instant::now())) {
+            return Ok(token.value.clone());
+        }
+        let token = self.fetch().await?;
+        let value = token.value.clone();
+        *cached = Some(token);
+        Ok(value)
+    }
+
+    async fn fetch(&self) -> Result<AccessToken, Box<dyn Error + Send + Sync>> {
+        let url = &self.config.token_url;
+        let mut form = vec![
+            ("grant_type", "client_credentials"),
+            ("client_id", self.config.client_id.as_str()),
+            ("client_secret", self.config.client_secret.as_str()),
+        ];
+        if let Some(scope) = &self.config.scope {
+            form.push(("scope", scope));
+        }
+
+        let requested = Instant::now();
+        let response = self.client.post(url).form(&form).send().await
+            .map_err(|e| format!("Token request to {} failed: {}", url, e))?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(format!("Token endpoint {} returned {}: {}", url, status, body.trim()).into());
+        }
+
+        let json: Value = serde_json::from_str(&body)
+            .map_err(|_| format!("Token endpoint {} returned a non-JSON body: {}", url, body.trim()))?;
+        let value = json["access_token"].as_str()
+            .ok_or_else(|| format!("Token endpoint {} returned no access_token: {}", url, body.trim()))?;
+        // Some servers send expires_in as a string
+        let expires_in = json["expires_in"].as_u64()
+            .or_else(|| json["expires_in"].as_str().and_then(|s| s.parse().ok()));
+        Ok(AccessToken {
+            value: value.to_string(),
+            expires_at: expires_in.map(|secs| requested + Duration::from_secs(secs)),
+        })
+    }
+}
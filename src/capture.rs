@@ -0,0 +1,248 @@
+//! Session capture: records every request/response made through `CallCommand` into a HAR 1.2 file.
+//!
+//! The file is kept valid after every entry (entries are appended in place and the closing
+//! brackets rewritten), so a crash mid-session still leaves a loadable capture.
+
+use crate::timing::Timings;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Bodies larger than this are truncated in the capture.
+pub const MAX_CAPTURED_BODY: usize = 1024 * 1024;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Headers holding credentials, redacted in captures and left out of recordings.
+pub const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+    "x-auth-token",
+];
+const HAR_FOOTER: &[u8] = b"]}}\n";
+
+static CAPTURE: Mutex<Option<HarWriter>> = Mutex::new(None);
+
+/// Request half of a captured exchange.
+#[derive(Debug, Clone)]
+pub struct RequestRecord {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    pub started: DateTime<Utc>,
+}
+
+/// Response half of a captured exchange.
+#[derive(Debug, Clone)]
+pub struct ResponseRecord {
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub elapsed: Duration,
+    /// Phases from `call -v`; without them the whole time is reported as `wait`.
+    pub timings: Option<Timings>,
+}
+
+struct HarWriter {
+    path: PathBuf,
+    file: File,
+    include_auth: bool,
+    entries: usize,
+    footer_pos: u64,
+}
+
+impl HarWriter {
+    fn create(path: &Path, include_auth: bool) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let mut file = File::create(path)?;
+        let header = format!(
+            "{{\"log\":{{\"version\":\"1.2\",\"creator\":{{\"name\":\"NUTS\",\"version\":\"{}\"}},\"pages\":[],\"entries\":[",
+            env!("CARGO_PKG_VERSION")
+        );
+        file.write_all(header.as_bytes())?;
+        let footer_pos = header.len() as u64;
+        file.write_all(HAR_FOOTER)?;
+        file.flush()?;
+
+        Ok(Self { path: path.to_path_buf(), file, include_auth, entries: 0, footer_pos })
+    }
+
+    fn append(&mut self, entry: &Value) -> std::io::Result<()> {
+        let mut chunk = if self.entries > 0 { String::from(",") } else { String::new() };
+        chunk.push_str(&entry.to_string());
+
+        self.file.seek(SeekFrom::Start(self.footer_pos))?;
+        self.file.write_all(chunk.as_bytes())?;
+        self.footer_pos += chunk.len() as u64;
+        self.file.write_all(HAR_FOOTER)?;
+        self.file.flush()?;
+        self.entries += 1;
+        Ok(())
+    }
+}
+
+/// Begin capturing to `path`, replacing any capture already in progress.
+pub fn start(path: &Path, include_auth: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let writer = HarWriter::create(path, include_auth)?;
+    let mut guard = CAPTURE.lock().map_err(|_| "capture state poisoned")?;
+    *guard = Some(writer);
+    Ok(())
+}
+
+/// Stop capturing; returns the file path and number of recorded entries if a capture was active.
+pub fn stop() -> Option<(PathBuf, usize)> {
+    let mut guard = CAPTURE.lock().ok()?;
+    guard.take().map(|writer| (writer.path, writer.entries))
+}
+
+/// Path and entry count of the active capture, if any.
+pub fn status() -> Option<(PathBuf, usize)> {
+    let guard = CAPTURE.lock().ok()?;
+    guard.as_ref().map(|writer| (writer.path.clone(), writer.entries))
+}
+
+pub fn is_active() -> bool {
+    CAPTURE.lock().map(|guard| guard.is_some()).unwrap_or(false)
+}
+
+/// Append an exchange to the active capture. Capture problems never fail the request itself.
+pub fn record(request: &RequestRecord, response: &ResponseRecord) {
+    let Ok(mut guard) = CAPTURE.lock() else { return };
+    let Some(writer) = guard.as_mut() else { return };
+
+    let entry = har_entry(request, response, writer.include_auth);
+    if let Err(e) = writer.append(&entry) {
+        tracing::warn!(target: "nuts::capture", error = %e, "failed to write HAR entry");
+    }
+}
+
+fn har_entry(request: &RequestRecord, response: &ResponseRecord, include_auth: bool) -> Value {
+    let elapsed_ms = response.elapsed.as_secs_f64() * 1000.0;
+    let request_mime = header_value(&request.headers, "content-type").unwrap_or_default();
+    let response_mime = header_value(&response.headers, "content-type").unwrap_or_default();
+
+    let query: Vec<Value> = url::Url::parse(&request.url)
+        .map(|u| u.query_pairs().map(|(k, v)| json!({ "name": k, "value": v })).collect())
+        .unwrap_or_default();
+
+    let mut har_request = json!({
+        "method": request.method,
+        "url": request.url,
+        "httpVersion": response.http_version,
+        "cookies": [],
+        "headers": har_headers(&request.headers, include_auth),
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": request.body.as_ref().map(|b| b.len() as i64).unwrap_or(0),
+    });
+    if let Some(body) = &request.body {
+        let (text, comment) = truncate(body);
+        let mut post_data = json!({ "mimeType": request_mime, "text": text });
+        if let Some(comment) = comment {
+            post_data["comment"] = json!(comment);
+        }
+        har_request["postData"] = post_data;
+    }
+
+    let (text, comment) = truncate(&response.body);
+    let mut content = json!({
+        "size": response.body.len(),
+        "mimeType": response_mime,
+        "text": text,
+    });
+    if let Some(comment) = comment {
+        content["comment"] = json!(comment);
+    }
+
+    json!({
+        "startedDateTime": request.started.to_rfc3339(),
+        "time": elapsed_ms,
+        "request": har_request,
+        "response": {
+            "status": response.status,
+            "statusText": response.status_text,
+            "httpVersion": response.http_version,
+            "cookies": [],
+            "headers": har_headers(&response.headers, include_auth),
+            "content": content,
+            "redirectURL": header_value(&response.headers, "location").unwrap_or_default(),
+            "headersSize": -1,
+            "bodySize": response.body.len(),
+        },
+        "cache": {},
+        "timings": har_timings(response),
+    })
+}
+
+/// HAR `timings`; phases that weren't measured are -1 as the spec asks.
+fn har_timings(response: &ResponseRecord) -> Value {
+    let elapsed_ms = response.elapsed.as_secs_f64() * 1000.0;
+    let Some(timings) = &response.timings else {
+        return json!({ "blocked": -1, "dns": -1, "connect": -1, "ssl": -1, "send": 0, "wait": elapsed_ms, "receive": 0 });
+    };
+    let phase = |ms: Option<f64>| ms.unwrap_or(-1.0);
+    json!({
+        "blocked": -1,
+        "dns": phase(timings.dns_ms),
+        // HAR counts the TLS handshake as part of connect
+        "connect": timings.connect_ms.map_or(-1.0, |connect| connect + timings.tls_ms.unwrap_or(0.0)),
+        "ssl": phase(timings.tls_ms),
+        "send": 0,
+        "wait": timings.ttfb_ms.unwrap_or(elapsed_ms),
+        "receive": timings.download_ms.unwrap_or(0.0),
+    })
+}
+
+fn har_headers(headers: &[(String, String)], include_auth: bool) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let sensitive = SENSITIVE_HEADERS.contains(&name.to_ascii_lowercase().as_str());
+            let value = if sensitive && !include_auth { REDACTED } else { value.as_str() };
+            json!({ "name": name, "value": value })
+        })
+        .collect()
+}
+
+fn header_value(headers: &[(String, String)], name: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.clone())
+}
+
+fn truncate(body: &str) -> (String, Option<String>) {
+    if body.len() <= MAX_CAPTURED_BODY {
+        return (body.to_string(), None);
+    }
+    let mut end = MAX_CAPTURED_BODY;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    (
+        body[..end].to_string(),
+        Some(format!("Body truncated by NUTS: {} of {} bytes captured", end, body.len())),
+    )
+}
+
+/// Header list helper for reqwest header maps.
+pub fn header_pairs(headers: &reqwest::header::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect()
+}
@@ -1,32 +1,45 @@
-use anthropic::{
-    client::ClientBuilder,
-    types::{Message, ContentBlock, MessagesRequestBuilder, Role},
-};
+use crate::ai::{self, AiClient, AiMessage};
 use crate::config::Config;
-use crate::commands::call::CallCommand;
+use crate::commands::call::{truncate_at, CallCommand, CallResponse};
 use crate::commands::generate::GenerateCommand;
+use crate::confirm::{is_destructive, run_step, ExecutionPolicy, StepPrompt};
 use serde_json::Value;
 
+/// Requests a multi-step plan runs when `config set ask-max-steps` isn't set.
+pub const DEFAULT_MAX_STEPS: usize = 5;
+
+/// Bytes of each step's response body handed back to the AI for the next step.
+const STEP_CONTEXT_LIMIT: usize = 2000;
+
 pub struct AskCommand {
     config: Config,
+    /// Sends the proposed request, with the session's defaults, environment and cookies.
+    call: CallCommand,
+    policy: ExecutionPolicy,
 }
 
 impl AskCommand {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        let policy = ExecutionPolicy { auto_execute: config.auto_execute, ..ExecutionPolicy::default() };
+        Self { config, call: CallCommand::new(), policy }
+    }
+
+    pub fn with_call_command(mut self, call: CallCommand) -> Self {
+        self.call = call;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: ExecutionPolicy) -> Self {
+        self.policy = policy;
+        self
     }
 
     /// AI-Powered Natural Language API Interface
     /// This is the revolutionary CURL killer - just ask in plain English!
-    pub async fn execute(&self, request: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn execute(&self, request: &str, confirm: &mut dyn StepPrompt) -> Result<(), Box<dyn std::error::Error>> {
         println!("🤖 AI Understanding: {}", request);
         
-        let api_key = self.config.anthropic_api_key.as_ref()
-            .ok_or("API key not configured. Use 'config api-key' to set it")?;
-
-        let ai_client = ClientBuilder::default()
-            .api_key(api_key.clone())
-            .build()?;
+        let ai_client = ai::client(&self.config, "ask")?;
 
         let prompt = format!(
             "You are NUTS AI, a revolutionary API testing assistant. The user wants to perform this task:\n\n\
@@ -41,6 +54,10 @@ impl AskCommand {
               \"explanation\": \"what you're doing and why\",\n\
               \"follow_up\": \"suggested next steps\"\n\
             }}\n\n\
+            If the task needs several requests in a row (create something, then fetch it), add\n\
+            \"steps\": [{{\"method\", \"url\", \"headers\", \"body\", \"explanation\"}}, ...] with one entry per\n\
+            request, in order. You will be shown each response and asked for the next step then,\n\
+            so later steps may use placeholders such as {{id}} for values not known yet.\n\n\
             If the request is about generating test data, set action to 'generate'.\n\
             If the request is about monitoring, set action to 'monitor'.\n\
             If the request is about testing workflows, set action to 'test'.\n\
@@ -49,81 +66,61 @@ impl AskCommand {
             request
         );
 
-        let response = ai_client.messages(MessagesRequestBuilder::default()
-            .messages(vec![Message {
-                role: Role::User,
-                content: vec![ContentBlock::Text { text: prompt }],
-            }])
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(1500_usize)
-            .build()?
-        ).await?;
-
-        if let Some(ContentBlock::Text { text }) = response.content.first() {
-            println!("\n🧠 AI Analysis:");
+        let mut messages = vec![AiMessage::user(prompt)];
+        let text = ai_client.complete(&messages).await?;
+        println!("\n🧠 AI Analysis:");
+        
+        // Try to parse as JSON
+        if let Ok(ai_response) = serde_json::from_str::<Value>(text.trim()) {
+            let action = ai_response.get("action").and_then(|v| v.as_str()).unwrap_or("call");
+            let explanation = ai_response.get("explanation").and_then(|v| v.as_str()).unwrap_or("Processing your request");
+            let follow_up = ai_response.get("follow_up").and_then(|v| v.as_str()).unwrap_or("What would you like to do next?");
+            
+            println!("📋 {}", explanation);
             
-            // Try to parse as JSON
-            if let Ok(ai_response) = serde_json::from_str::<Value>(text) {
-                let action = ai_response.get("action").and_then(|v| v.as_str()).unwrap_or("call");
-                let explanation = ai_response.get("explanation").and_then(|v| v.as_str()).unwrap_or("Processing your request");
-                let follow_up = ai_response.get("follow_up").and_then(|v| v.as_str()).unwrap_or("What would you like to do next?");
-                
-                println!("📋 {}", explanation);
-                
-                match action {
-                    "call" => {
-                        self.execute_api_call(&ai_response).await?;
-                    }
-                    "generate" => {
-                        self.execute_generate_data(&ai_response).await?;
-                    }
-                    "test" => {
-                        println!("🧪 Executing intelligent test workflow...");
-                        // Could integrate with test command
-                    }
-                    "monitor" => {
-                        println!("📊 Setting up smart monitoring...");
-                        // Could integrate with monitor command
-                    }
-                    _ => {
-                        println!("🤷 I'm not sure how to handle that request yet.");
-                    }
+            let steps = ai_response.get("steps").and_then(Value::as_array).filter(|steps| !steps.is_empty());
+            match action {
+                _ if steps.is_some() => {
+                    messages.push(AiMessage::assistant(text.clone()));
+                    self.execute_plan(&*ai_client, messages, steps.into_iter().flatten().cloned().collect(), confirm).await?;
+                }
+                "call" => {
+                    self.execute_api_call(&ai_response, confirm).await?;
+                }
+                "generate" => {
+                    self.execute_generate_data(&ai_response).await?;
+                }
+                "test" => {
+                    println!("🧪 Executing intelligent test workflow...");
+                    // Could integrate with test command
+                }
+                "monitor" => {
+                    println!("📊 Setting up smart monitoring...");
+                    // Could integrate with monitor command
+                }
+                _ => {
+                    println!("🤷 I'm not sure how to handle that request yet.");
                 }
-                
-                println!("\n💡 Next: {}", follow_up);
-                
-            } else {
-                // Fallback to showing AI response as text
-                println!("{}", text);
             }
+            
+            println!("\n💡 Next: {}", follow_up);
+            
+        } else {
+            // Fallback to showing AI response as text
+            println!("{}", text);
         }
 
         Ok(())
     }
 
-    async fn execute_api_call(&self, ai_response: &Value) -> Result<(), Box<dyn std::error::Error>> {
+    /// Send the request the AI proposed, after confirmation unless the policy says otherwise.
+    pub async fn execute_api_call(&self, ai_response: &Value, prompt: &mut dyn StepPrompt) -> Result<(), Box<dyn std::error::Error>> {
         let method = ai_response.get("method").and_then(|v| v.as_str()).unwrap_or("GET");
         let url = ai_response.get("url").and_then(|v| v.as_str());
         
         if let Some(url) = url {
-            println!("🚀 Making {} request to {}", method, url);
-            
-            let mut args = vec![method, url];
-            
-            let call_command = CallCommand::new();
-            
-            // Add body if present and execute
-            if let Some(body) = ai_response.get("body") {
-                if !body.is_null() {
-                    let body_str = serde_json::to_string(body)?;
-                    args.push(&body_str);
-                    call_command.execute(&args).await?;
-                } else {
-                    call_command.execute(&args).await?;
-                }
-            } else {
-                call_command.execute(&args).await?;
-            }
+            println!("🚀 Proposed {} request to {}", method, url);
+            run_step(&self.call, &self.policy, call_args(ai_response).unwrap_or_default(), prompt).await?;
         } else {
             println!("❓ I need more information. What URL should I call?");
         }
@@ -131,6 +128,76 @@ impl AskCommand {
         Ok(())
     }
 
+    /// Run a plan of `steps` in order. The plan is shown first and confirmed once; then before
+    /// each step after the first, the AI is shown the previous response (cut at
+    /// [`STEP_CONTEXT_LIMIT`] bytes) and writes the step again with the IDs it needs.
+    /// `messages` is the conversation that produced the plan. Destructive steps are still
+    /// confirmed one by one. A step that is skipped, fails or answers with an error status
+    /// stops the plan; returns how many steps completed.
+    pub async fn execute_plan(&self, ai_client: &dyn AiClient, mut messages: Vec<AiMessage>, mut steps: Vec<Value>, prompt: &mut dyn StepPrompt) -> Result<usize, Box<dyn std::error::Error>> {
+        let limit = self.config.ask_max_steps();
+        if steps.len() > limit {
+            println!("⚠️  The plan has {} steps; only the first {} run (config set ask-max-steps N)", steps.len(), limit);
+            steps.truncate(limit);
+        }
+
+        println!("🗺️  Plan:");
+        for (i, step) in steps.iter().enumerate() {
+            println!("   {}. {}", i + 1, step_summary(step));
+        }
+        if self.policy.dry_run {
+            println!("🔎 Dry run, nothing sent");
+            return Ok(0);
+        }
+        if !self.policy.yes {
+            let destructive = steps.iter().any(|step| is_destructive(step_method(step)));
+            let warning = if destructive { " ⚠️" } else { "" };
+            let answer = prompt.read(&format!("Run these {} steps?{} (y/N): ", steps.len(), warning), "").unwrap_or_default();
+            if !matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes") {
+                println!("⏭️  Plan cancelled, nothing sent");
+                return Ok(0);
+            }
+        }
+
+        // The plan as a whole is confirmed, so only destructive steps are asked about again
+        let policy = ExecutionPolicy { auto_execute: true, ..self.policy };
+        let mut completed: Vec<CallResponse> = Vec::new();
+        for i in 0..steps.len() {
+            if let Some(previous) = completed.last() {
+                messages.push(AiMessage::user(next_step_prompt(previous, i + 1, &steps[i])));
+                let text = match ai_client.complete(&messages).await {
+                    Ok(text) => text,
+                    Err(e) => return Err(report_stopped(&completed, steps.len(), format!("Step {}: {}", i + 1, e))),
+                };
+                match serde_json::from_str::<Value>(text.trim()) {
+                    Ok(step) if step.is_object() => steps[i] = step,
+                    _ => return Err(report_stopped(&completed, steps.len(), format!("Step {}: the AI answer is not a JSON request", i + 1))),
+                }
+                messages.push(AiMessage::assistant(text));
+            }
+
+            let step = &steps[i];
+            println!("\n📍 Step {}/{}: {}", i + 1, steps.len(), step_summary(step));
+            let Some(args) = call_args(step) else {
+                return Err(report_stopped(&completed, steps.len(), format!("Step {} has no URL", i + 1)));
+            };
+            match run_step(&self.call, &policy, args, prompt).await {
+                Err(e) => return Err(report_stopped(&completed, steps.len(), format!("Step {}: {}", i + 1, e))),
+                Ok(None) => {
+                    report_stopped(&completed, steps.len(), format!("Step {} was not sent", i + 1));
+                    return Ok(completed.len());
+                }
+                Ok(Some(response)) if response.status >= 400 => {
+                    let reason = format!("Step {} ({} {}) answered {}", i + 1, response.method, response.url, response.status);
+                    return Err(report_stopped(&completed, steps.len(), reason));
+                }
+                Ok(Some(response)) => completed.push(response),
+            }
+        }
+        println!("\n✅ All {} steps completed", completed.len());
+        Ok(completed.len())
+    }
+
     async fn execute_generate_data(&self, ai_response: &Value) -> Result<(), Box<dyn std::error::Error>> {
         println!("🎲 Generating intelligent test data...");
         
@@ -144,4 +211,57 @@ impl AskCommand {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+/// `call` arguments for a proposed request, `None` without a URL.
+fn call_args(request: &Value) -> Option<Vec<String>> {
+    let url = request.get("url").and_then(Value::as_str)?;
+    let mut args = vec!["call".to_string(), step_method(request).to_string(), url.to_string()];
+    if let Some(headers) = request.get("headers").and_then(Value::as_object) {
+        for (name, value) in headers {
+            let value = value.as_str().map_or_else(|| value.to_string(), str::to_string);
+            args.extend(["-H".to_string(), format!("{}: {}", name, value)]);
+        }
+    }
+    if let Some(body) = request.get("body").filter(|body| !body.is_null()) {
+        args.push(body.as_str().map_or_else(|| body.to_string(), str::to_string));
+    }
+    Some(args)
+}
+
+fn step_method(step: &Value) -> &str {
+    step.get("method").and_then(Value::as_str).unwrap_or("GET")
+}
+
+/// `POST /users - create the user`, for the plan preview.
+fn step_summary(step: &Value) -> String {
+    let url = step.get("url").and_then(Value::as_str).unwrap_or("(no URL)");
+    match step.get("explanation").and_then(Value::as_str).filter(|text| !text.is_empty()) {
+        Some(explanation) => format!("{} {} - {}", step_method(step).to_uppercase(), url, explanation),
+        None => format!("{} {}", step_method(step).to_uppercase(), url),
+    }
+}
+
+/// What the AI is told after a step: its response, and which step of the plan to write next.
+fn next_step_prompt(previous: &CallResponse, number: usize, planned: &Value) -> String {
+    let body = truncate_at(&previous.body, STEP_CONTEXT_LIMIT);
+    let cut = if body.len() < previous.body.len() {
+        format!("\n(cut at {} of {} bytes)", body.len(), previous.body.len())
+    } else {
+        String::new()
+    };
+    format!(
+        "{} {} answered {}:\n{}{}\n\n\
+        Reply with only the JSON for step {} of the plan, planned as: {}\n\
+        Use {{\"method\", \"url\", \"headers\", \"body\", \"explanation\"}}, with IDs and values taken from the responses so far.",
+        previous.method, previous.url, previous.status, body, cut, number, planned
+    )
+}
+
+/// Print which steps completed before the plan stopped, and give `reason` back as the error.
+fn report_stopped(completed: &[CallResponse], total: usize, reason: String) -> Box<dyn std::error::Error> {
+    println!("\n⛔ {}; the plan stopped after {} of {} step(s)", reason, completed.len(), total);
+    for (i, response) in completed.iter().enumerate() {
+        println!("   ✅ {}. {} {} -> {}", i + 1, response.method, response.url, response.status);
+    }
+    reason.into()
+}
@@ -0,0 +1,238 @@
+//! `call --batch FILE`: send every request in a JSON Lines file through a bounded pool and
+//! summarize the results.
+//!
+//! Each line is `{"method": "POST", "url": "...", "headers": {...}, "body": {...}}`; only
+//! `url` is required. Requests go through the same `CallCommand` as single calls, so config
+//! defaults, environments, cookies and `--auth oauth2` apply. A failed request (transport
+//! error or 4xx/5xx) is recorded and the batch carries on; the command fails at the end if
+//! any request did.
+
+use crate::commands::call::{CallCommand, CallOptions};
+use crate::units;
+use console::style;
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// One line of the batch file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BatchRequest {
+    #[serde(default = "default_method")]
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Sent as JSON, or as is when it is a string.
+    #[serde(default)]
+    pub body: Option<Value>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+/// The outcome of one request, written as a line of `--batch-out`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchResult {
+    /// 1-based position of the request among those in the batch file.
+    pub index: usize,
+    pub method: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    /// The response body, as JSON when it parses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BatchResult {
+    pub fn is_failure(&self) -> bool {
+        self.status.is_none_or(|status| status >= 400)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchOptions {
+    pub file: String,
+    pub concurrency: usize,
+    /// `--batch-out`: where to write one [`BatchResult`] per line.
+    pub out: Option<String>,
+    /// `-H` headers added to every request that doesn't set them itself.
+    pub headers: HashMap<String, String>,
+    pub timeout: Option<Duration>,
+}
+
+impl BatchOptions {
+    /// `call --batch FILE [--concurrency N] [--batch-out FILE] [-H 'K: V']... [--timeout SECS]`
+    pub fn from_args(args: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let mut options = Self {
+            file: String::new(),
+            concurrency: 10,
+            out: None,
+            headers: HashMap::new(),
+            timeout: Some(Duration::from_secs(30)),
+        };
+        let mut i = 1; // Skip "call"
+        while i < args.len() {
+            let value = args.get(i + 1).copied().ok_or_else(|| format!("Value required after {}", args[i]))?;
+            match args[i] {
+                "--batch" => options.file = value.to_string(),
+                "--concurrency" => {
+                    options.concurrency = value.parse().ok().filter(|n| *n > 0)
+                        .ok_or_else(|| format!("--concurrency must be a positive number, got '{}'", value))?;
+                }
+                "--batch-out" => options.out = Some(value.to_string()),
+                "-H" | "--header" => {
+                    let (key, value) = value.split_once(':').ok_or("Header must be in format 'Key: Value'")?;
+                    options.headers.insert(key.trim().to_string(), value.trim().to_string());
+                }
+                "--timeout" => {
+                    options.timeout = Some(units::parse_positive_duration(value).map_err(|e| format!("Invalid --timeout: {}", e))?);
+                }
+                other => return Err(format!("Unknown option for --batch: {}", other).into()),
+            }
+            i += 2;
+        }
+        if options.file.is_empty() {
+            return Err("--batch needs a file of JSON lines".into());
+        }
+        Ok(options)
+    }
+}
+
+/// Parse a batch file, skipping blank lines. Errors name the offending line.
+pub fn parse_batch(content: &str) -> Result<Vec<BatchRequest>, Box<dyn Error>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            serde_json::from_str(line).map_err(|e| format!("Line {} of the batch file: {}", number + 1, e).into())
+        })
+        .collect()
+}
+
+impl CallCommand {
+    /// Run a batch, print the summary table and fail if any request failed.
+    pub async fn execute_batch(&self, args: &[&str]) -> Result<Vec<BatchResult>, Box<dyn Error>> {
+        let options = BatchOptions::from_args(args)?;
+        let results = self.run_batch(&options).await?;
+
+        println!("\n{:>5}  {:>6}  {:>9}  Request", "#", "Status", "Latency");
+        for result in &results {
+            let status = match result.status {
+                Some(status) if status < 400 => style(status.to_string()).green(),
+                Some(status) => style(status.to_string()).red(),
+                None => style("error".to_string()).red(),
+            };
+            println!("{:>5}  {:>6}  {:>7}ms  {} {}", result.index, status, result.latency_ms, result.method, result.url);
+            if let Some(error) = &result.error {
+                println!("{:>27}{}", "", style(error).dim());
+            }
+        }
+        if let Some(out) = &options.out {
+            println!("💾 Results saved to: {}", style(out).green());
+        }
+
+        let failed = results.iter().filter(|r| r.is_failure()).count();
+        if failed > 0 {
+            return Err(format!("{} of {} batch request(s) failed", failed, results.len()).into());
+        }
+        println!("✅ {}", style(format!("All {} requests succeeded", results.len())).green());
+        Ok(results)
+    }
+
+    /// Send every request in `options.file`, at most `options.concurrency` at a time,
+    /// showing live progress. Results are in file order.
+    pub async fn run_batch(&self, options: &BatchOptions) -> Result<Vec<BatchResult>, Box<dyn Error>> {
+        let content = std::fs::read_to_string(&options.file)
+            .map_err(|e| format!("Could not read batch file '{}': {}", options.file, e))?;
+        let requests = parse_batch(&content)?;
+        let total = requests.len();
+
+        let mut results = Vec::with_capacity(total);
+        let mut pending = stream::iter(requests.into_iter().enumerate())
+            .map(|(i, request)| self.send_batch_request(i + 1, request, options))
+            .buffer_unordered(options.concurrency);
+
+        let started = Instant::now();
+        while let Some(result) = pending.next().await {
+            results.push(result);
+            let failed = results.iter().filter(|r| r.is_failure()).count();
+            print!(
+                "\r📦 {}/{} | {} ok, {} failed | {:.1}s",
+                style(results.len()).magenta().bold(),
+                total,
+                style(results.len() - failed).green().bold(),
+                style(failed).red().bold(),
+                started.elapsed().as_secs_f64()
+            );
+            std::io::stdout().flush()?;
+        }
+        println!();
+
+        results.sort_by_key(|r| r.index);
+        if let Some(out) = &options.out {
+            let mut lines = String::new();
+            for result in &results {
+                lines.push_str(&serde_json::to_string(result)?);
+                lines.push('\n');
+            }
+            std::fs::write(out, lines).map_err(|e| format!("Could not write '{}': {}", out, e))?;
+        }
+        Ok(results)
+    }
+
+    async fn send_batch_request(&self, index: usize, request: BatchRequest, batch: &BatchOptions) -> BatchResult {
+        let mut headers = request.headers;
+        for (key, value) in &batch.headers {
+            if !headers.keys().any(|k| k.eq_ignore_ascii_case(key)) {
+                headers.insert(key.clone(), value.clone());
+            }
+        }
+        let url = if request.url.contains("://") || request.url.starts_with('/') || request.url.starts_with("{{") {
+            request.url.clone()
+        } else {
+            format!("https://{}", request.url)
+        };
+        let options = CallOptions {
+            method: request.method.to_uppercase(),
+            url,
+            headers,
+            body: request.body.map(|body| match body {
+                Value::String(text) => text,
+                json => json.to_string(),
+            }),
+            timeout: batch.timeout,
+            ..Default::default()
+        };
+
+        let started = Instant::now();
+        let outcome = self.send_quiet(options).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let mut result = BatchResult {
+            index,
+            method: request.method.to_uppercase(),
+            url: request.url,
+            status: None,
+            latency_ms,
+            body: None,
+            error: None,
+        };
+        match outcome {
+            Ok((status, body)) => {
+                result.status = Some(status);
+                result.body = Some(serde_json::from_str(&body).unwrap_or(Value::String(body)));
+            }
+            Err(e) => result.error = Some(e.to_string()),
+        }
+        result
+    }
+}
@@ -1,6 +1,10 @@
 use crate::commands::CommandResult;
 use console::style;
-use crate::config::Config;
+use crate::config::{mask_value, Config};
+use crate::auth::OAuth2Config;
+use crate::ai::{AiProvider, DEFAULT_OPENAI_BASE_URL};
+use crate::usage::ModelPrice;
+use crate::output;
 
 pub struct ConfigCommand {
     config: Config,
@@ -29,10 +33,10 @@ impl ConfigCommand {
                         if loaded.anthropic_api_key.is_some() {
                             println!("✅ {}", style("API key configured successfully").green());
                         } else {
-                            println!("❌ Failed to verify saved API key");
+                            return Err("Failed to verify saved API key".into());
                         }
                     },
-                    Err(e) => println!("❌ Error verifying config: {}", e),
+                    Err(e) => return Err(format!("Error verifying config: {}", e).into()),
                 }
             }
             Some("show") => {
@@ -43,13 +47,386 @@ impl ConfigCommand {
                     .as_ref()
                     .map(|_| "********")
                     .unwrap_or("Not set"));
+                println!("  Environment: {}", config.active_env.as_deref().unwrap_or("None"));
+                println!("  Base URL: {}", config.base_url.as_deref().unwrap_or("Not set"));
+                println!("  Persist Cookies: {}", if config.persist_cookies { "on" } else { "off" });
+                println!("  Auto-execute AI Requests: {}", config.auto_execute);
+                println!("  Color: {}", if output::color_wanted(config.color) { "on" } else { "off" });
+                println!("  AI Provider: {} ({}, up to {} tokens)", config.ai_provider.name(), config.ai_model(), config.ai_max_tokens());
+                println!("  AI Cache: {}", match config.ai_cache_ttl_days() {
+                    0 => "off".to_string(),
+                    days => format!("{} day(s)", days),
+                });
+                if let Some(budget) = config.ai_daily_budget {
+                    println!("  AI Daily Budget: ${:.2}{}", budget, if config.ai_enforce_budget { " (enforced)" } else { "" });
+                }
+                for (model, price) in &config.ai_prices {
+                    println!("  AI Price: {} ${}/${} per million input/output tokens", model, price.input, price.output);
+                }
+                if config.ai_provider == AiProvider::OpenAi {
+                    println!("  AI Base URL: {}", config.ai_base_url.as_deref().unwrap_or(DEFAULT_OPENAI_BASE_URL));
+                    println!("  AI Key: {}", config.ai_api_key.as_deref().map(mask_value).unwrap_or_else(|| "Not set".to_string()));
+                }
+                match (&config.tls.cert, &config.tls.key) {
+                    (Some(cert), Some(key)) => println!("  TLS Client Certificate: {} (key {})", cert, key),
+                    (Some(cert), None) => println!("  TLS Client Certificate: {}", cert),
+                    (None, _) => println!("  TLS Client Certificate: Not set"),
+                }
+                if let Some(cacert) = &config.tls.cacert {
+                    println!("  TLS Trusted CA: {}", cacert);
+                }
+                match &config.oauth2 {
+                    Some(oauth2) => println!(
+                        "  OAuth2: client {} (secret {}) via {}{}",
+                        style(&oauth2.client_id).cyan(),
+                        mask_value(&oauth2.client_secret),
+                        oauth2.token_url,
+                        oauth2.scope.as_ref().map(|s| format!(", scope '{}'", s)).unwrap_or_default()
+                    ),
+                    None => println!("  OAuth2: Not set"),
+                }
+                if config.default_headers.is_empty() {
+                    println!("  Default Headers: None");
+                } else {
+                    println!("  Default Headers:");
+                    let mut headers: Vec<_> = config.default_headers.iter().collect();
+                    headers.sort();
+                    for (name, value) in headers {
+                        println!("    {}: {}", style(name).cyan(), mask_value(value));
+                    }
+                }
+            }
+            Some("env") => self.execute_env(&args[2..])?,
+            Some("set-header") => match args {
+                [_, _, name, value @ ..] if !value.is_empty() => {
+                    let mut config = self.config.clone();
+                    config.set_default_header(name, &value.join(" "));
+                    config.save()?;
+                    println!("✅ Sending {} with every call", style(name).cyan());
+                }
+                _ => return Err("Usage: config set-header <name> <value>".into()),
+            },
+            Some("unset-header") => match args.get(2) {
+                Some(name) => {
+                    let mut config = self.config.clone();
+                    if config.unset_default_header(name) {
+                        config.save()?;
+                        println!("✅ Removed default header {}", style(name).cyan());
+                    } else {
+                        return Err(format!("No default header named {}", name).into());
+                    }
+                }
+                None => return Err("Usage: config unset-header <name>".into()),
+            },
+            Some("auth") => {
+                let mut config = self.config.clone();
+                match args.get(2).copied() {
+                    Some("oauth2") => {
+                        let oauth2 = OAuth2Config::from_args(&args[3..])?;
+                        println!("✅ {} will fetch tokens from {}", style("--auth oauth2").green(), style(&oauth2.token_url).cyan());
+                        config.oauth2 = Some(oauth2);
+                        config.save()?;
+                    }
+                    Some("clear") => {
+                        config.oauth2 = None;
+                        config.save()?;
+                        println!("✅ OAuth2 client removed");
+                    }
+                    _ => return Err("Usage: config auth oauth2 --token-url <url> --client-id <id> --client-secret <secret> [--scope <scope>] | config auth clear".into()),
+                }
+            }
+            Some("redact") => {
+                let mut config = self.config.clone();
+                match (args.get(2).copied(), args.get(3).copied()) {
+                    (Some("add"), Some(name)) => {
+                        if !config.redact_headers.iter().any(|known| known.eq_ignore_ascii_case(name)) {
+                            config.redact_headers.push(name.to_string());
+                            config.save()?;
+                        }
+                        println!("✅ {} values are masked in -v output and trace logs", style(name).cyan());
+                    }
+                    (Some("remove"), Some(name)) => {
+                        let before = config.redact_headers.len();
+                        config.redact_headers.retain(|known| !known.eq_ignore_ascii_case(name));
+                        if config.redact_headers.len() == before {
+                            return Err(format!("{} is not on the redact list", name).into());
+                        } else {
+                            config.save()?;
+                            println!("✅ {} is shown again", style(name).cyan());
+                        }
+                    }
+                    (Some("list"), None) => {
+                        println!("Always masked: {}", crate::capture::SENSITIVE_HEADERS.join(", "));
+                        if !config.redact_headers.is_empty() {
+                            println!("Also masked: {}", config.redact_headers.join(", "));
+                        }
+                    }
+                    _ => return Err("Usage: config redact add|remove <header> | config redact list".into()),
+                }
+            }
+            Some("persist-cookies") => {
+                let persist = match args.get(2).copied() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => {
+                        return Err("Usage: config persist-cookies on|off".into());
+                    }
+                };
+                let mut config = self.config.clone();
+                config.persist_cookies = persist;
+                config.save()?;
+                if persist {
+                    println!("✅ Cookies will be saved to {} on exit", style("~/.nuts/cookies.json").cyan());
+                } else {
+                    println!("✅ Cookies now last for this session only");
+                }
+            }
+            Some("ai") => self.execute_ai(&args[2..])?,
+            Some("tls") => self.execute_tls(&args[2..])?,
+            Some("set") => match (args.get(2).copied(), args.get(3).copied()) {
+                (Some("auto-execute"), Some(value @ ("true" | "false"))) => {
+                    let mut config = self.config.clone();
+                    config.auto_execute = value == "true";
+                    config.save()?;
+                    if config.auto_execute {
+                        println!("✅ AI-proposed requests are sent without asking, except DELETE and PUT");
+                    } else {
+                        println!("✅ Every AI-proposed request is confirmed before it is sent");
+                    }
+                }
+                (Some("ask-max-steps"), Some(value)) => match value.parse::<usize>() {
+                    Ok(steps) if steps > 0 => {
+                        let mut config = self.config.clone();
+                        config.ask_max_steps = Some(steps);
+                        config.save()?;
+                        println!("✅ ask runs plans of up to {} steps", steps);
+                    }
+                    _ => return Err(format!("The step limit must be a whole number above 0, got '{}'", value).into()),
+                },
+                (Some("color"), Some(value @ ("true" | "false"))) => {
+                    let mut config = self.config.clone();
+                    config.color = Some(value == "true");
+                    config.save()?;
+                    if value == "true" {
+                        println!("✅ Output is colored (unless NO_COLOR is set)");
+                    } else {
+                        println!("✅ Output is plain text, without colors");
+                    }
+                }
+                _ => return Err("Usage: config set auto-execute true|false | config set ask-max-steps N | config set color true|false".into()),
+            },
+            Some("base-url") => {
+                let mut config = self.config.clone();
+                match args.get(2).copied() {
+                    Some("--unset") => {
+                        config.base_url = None;
+                        config.save()?;
+                        println!("✅ Base URL removed");
+                    }
+                    Some(base_url) => {
+                        url::Url::parse(base_url).map_err(|e| format!("Invalid base URL '{}': {}", base_url, e))?;
+                        config.base_url = Some(base_url.trim_end_matches('/').to_string());
+                        config.save()?;
+                        println!("✅ Calls to {} now go to {}", style("/path").green(), style(base_url).cyan());
+                    }
+                    None => return Err("Usage: config base-url <url> | config base-url --unset".into()),
+                }
             }
             _ => {
                 println!("Available config commands:");
                 println!("  {} - Configure Anthropic API key", style("config api-key").green());
                 println!("  {} - Show current configuration", style("config show").green());
+                println!("  {} - Set an environment variable", style("config env add <env> <key> <value>").green());
+                println!("  {} - Switch the active environment", style("config env use <env>").green());
+                println!("  {} - List environments and their variables", style("config env list").green());
+                println!("  {} - Send a header with every call", style("config set-header <name> <value>").green());
+                println!("  {} - Stop sending a default header", style("config unset-header <name>").green());
+                println!("  {} - Resolve /path URLs against a base URL", style("config base-url <url>").green());
+                println!("  {} - Mask a header in -v output and --trace-log files", style("config redact add|remove <header> | config redact list").green());
+                println!("  {} - Keep the cookie jar between sessions", style("config persist-cookies on|off").green());
+                println!("  {} - Send AI-proposed requests without confirming", style("config set auto-execute true|false").green());
+                println!("  {} - Most requests a multi-step ask plan runs (5 by default)", style("config set ask-max-steps N").green());
+                println!("  {} - Colored output (NO_COLOR and --no-color turn it off too)", style("config set color true|false").green());
+                println!("  {} - Use Anthropic or an OpenAI-compatible API for AI features", style("config ai provider anthropic|openai").green());
+                println!("  {} - Model, API root and key for the AI provider", style("config ai model|max-tokens|base-url|key <value>").green());
+                println!("  {} - Warn past a daily AI spend; enforced, skip optional AI calls", style("config ai budget <usd>|--unset [--enforce-budget]").green());
+                println!("  {} - USD per million tokens, for 'usage'", style("config ai price <model> <input> <output>|--unset").green());
+                println!("  {} - Reuse AI answers to identical requests for N days (0 = off)", style("config ai cache-ttl <days>").green());
+                println!("  {} - Default client certificate, key and CA for --cert, --key, --cacert", style("config tls cert|key|cacert <file>|--unset").green());
+                println!("  {} - OAuth2 client credentials for --auth oauth2", style("config auth oauth2 --token-url <url> --client-id <id> --client-secret <secret>").green());
             }
         }
         Ok(())
     }
-} 
\ No newline at end of file
+
+    fn execute_env(&self, args: &[&str]) -> CommandResult {
+        let mut config = self.config.clone();
+        match args {
+            ["add", env, key, value @ ..] if !value.is_empty() => {
+                config.set_env_var(env, key, &value.join(" "));
+                config.save()?;
+                println!("✅ Set {} in environment '{}'", style(key).cyan(), env);
+            }
+            ["use", env] => {
+                config.use_env(env)?;
+                config.save()?;
+                println!("✅ Using environment '{}'", style(env).cyan());
+            }
+            ["list"] | [] => {
+                if config.environments.is_empty() {
+                    println!("No environments defined. Add one with {}", style("config env add <env> <key> <value>").green());
+                    return Ok(());
+                }
+                let mut names: Vec<&String> = config.environments.keys().collect();
+                names.sort();
+                for name in names {
+                    let marker = if config.active_env.as_ref() == Some(name) { "*" } else { " " };
+                    println!("{} {}", marker, style(name).bold());
+                    let mut variables: Vec<_> = config.environments[name].iter().collect();
+                    variables.sort();
+                    for (key, value) in variables {
+                        println!("    {} = {}", style(key).cyan(), value);
+                    }
+                }
+            }
+            _ => {
+                return Err("Usage: config env add <env> <key> <value> | config env use <env> | config env list".into());
+            }
+        }
+        Ok(())
+    }
+
+    fn execute_tls(&self, args: &[&str]) -> CommandResult {
+        let mut config = self.config.clone();
+        let (slot, what) = match args.first().copied() {
+            Some("cert") => (&mut config.tls.cert, "Client certificate"),
+            Some("key") => (&mut config.tls.key, "Client key"),
+            Some("cacert") => (&mut config.tls.cacert, "Trusted CA"),
+            Some("clear") if args.len() == 1 => {
+                config.tls = Default::default();
+                config.save()?;
+                println!("✅ No default client certificate or CA");
+                return Ok(());
+            }
+            _ => {
+                return Err("Usage: config tls cert|key|cacert <file>|--unset | config tls clear".into());
+            }
+        };
+        match args.get(1).copied() {
+            Some("--unset") => {
+                *slot = None;
+                println!("✅ {} removed", what);
+            }
+            Some(path) => {
+                *slot = Some(path.to_string());
+                println!("✅ {} for every call, perf and security run: {}", what, style(path).cyan());
+            }
+            None => {
+                return Err(format!("Usage: config tls {} <file>|--unset", args[0]).into());
+            }
+        }
+        config.save()?;
+        // A certificate set before its key doesn't load yet; say so without refusing it
+        if let Err(e) = config.tls.load() {
+            println!("⚠️  {}", e);
+        }
+        Ok(())
+    }
+
+    fn execute_ai(&self, args: &[&str]) -> CommandResult {
+        let mut config = self.config.clone();
+        match args {
+            ["provider", name] => {
+                let Some(provider) = AiProvider::from_name(name) else {
+                    return Err(format!("Unknown AI provider '{}'. Use anthropic or openai", name).into());
+                };
+                config.ai_provider = provider;
+                config.save()?;
+                println!("✅ AI features now use {} ({})", style(provider.name()).cyan(), config.ai_model());
+                if config.ai_key().is_none() {
+                    let hint = match provider {
+                        AiProvider::Anthropic => "config api-key",
+                        AiProvider::OpenAi => "config ai key <key>",
+                    };
+                    println!("💡 No key for it yet. Set one with {}", style(hint).green());
+                }
+            }
+            ["model", "--unset"] => {
+                config.ai_model = None;
+                config.save()?;
+                println!("✅ Using the default model {}", style(config.ai_provider.default_model()).cyan());
+            }
+            ["model", model] => {
+                config.ai_model = Some(model.to_string());
+                config.save()?;
+                println!("✅ AI features now ask {}", style(model).cyan());
+            }
+            ["max-tokens", tokens] => {
+                let Some(tokens) = tokens.parse::<usize>().ok().filter(|&t| t > 0) else {
+                    return Err(format!("max-tokens must be a positive number, got '{}'", tokens).into());
+                };
+                config.ai_max_tokens = Some(tokens);
+                config.save()?;
+                println!("✅ AI answers are capped at {} tokens", style(tokens).cyan());
+            }
+            ["base-url", "--unset"] => {
+                config.ai_base_url = None;
+                config.save()?;
+                println!("✅ OpenAI-compatible requests go to {}", style(DEFAULT_OPENAI_BASE_URL).cyan());
+            }
+            ["base-url", base_url] => {
+                url::Url::parse(base_url).map_err(|e| format!("Invalid base URL '{}': {}", base_url, e))?;
+                config.ai_base_url = Some(base_url.to_string());
+                config.save()?;
+                println!("✅ OpenAI-compatible requests go to {}", style(base_url).cyan());
+            }
+            ["budget", "--unset"] => {
+                config.ai_daily_budget = None;
+                config.ai_enforce_budget = false;
+                config.save()?;
+                println!("✅ No daily AI budget");
+            }
+            ["budget", budget, flags @ ..] if flags.iter().all(|flag| *flag == "--enforce-budget") => {
+                let Some(budget) = budget.trim_start_matches('$').parse::<f64>().ok().filter(|b| *b > 0.0) else {
+                    return Err(format!("The budget must be a positive amount in USD, got '{}'", budget).into());
+                };
+                config.ai_daily_budget = Some(budget);
+                config.ai_enforce_budget = !flags.is_empty();
+                config.save()?;
+                let enforced = if config.ai_enforce_budget { ", then optional AI calls are skipped" } else { "" };
+                println!("✅ Warning when AI spend passes ${:.2} a day{}", budget, enforced);
+            }
+            ["price", model, "--unset"] => {
+                config.ai_prices.remove(*model);
+                config.save()?;
+                println!("✅ {} uses the built-in price again", style(model).cyan());
+            }
+            ["price", model, input, output] => {
+                let (Ok(input), Ok(output)) = (input.parse::<f64>(), output.parse::<f64>()) else {
+                    return Err(format!("Prices are USD per million tokens, e.g. config ai price {} 3 15", model).into());
+                };
+                config.ai_prices.insert(model.to_string(), ModelPrice { input, output });
+                config.save()?;
+                println!("✅ {} costs ${} per million input and ${} per million output tokens", style(model).cyan(), input, output);
+            }
+            ["cache-ttl", days] => {
+                let Ok(days) = days.trim_end_matches('d').parse::<u64>() else {
+                    return Err(format!("cache-ttl is a number of days, e.g. 7 (0 turns the cache off), got '{}'", days).into());
+                };
+                config.ai_cache_ttl_days = Some(days);
+                config.save()?;
+                match days {
+                    0 => println!("✅ AI answers are no longer cached"),
+                    days => println!("✅ AI answers are reused for {} day(s)", style(days).cyan()),
+                }
+            }
+            ["key", key] => {
+                config.ai_api_key = Some(key.to_string());
+                config.save()?;
+                println!("✅ Key for the OpenAI-compatible provider saved ({})", mask_value(key));
+            }
+            _ => return Err("Usage: config ai provider anthropic|openai | config ai model <name>|--unset | config ai max-tokens <n> | config ai base-url <url>|--unset | config ai key <key> | config ai budget <usd>|--unset [--enforce-budget] | config ai price <model> <input> <output>|--unset | config ai cache-ttl <days>".into()),
+        }
+        Ok(())
+    }
+}
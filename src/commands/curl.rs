@@ -0,0 +1,301 @@
+//! `curl` command lines turned into `call` arguments.
+//!
+//! Pasting `curl -X POST -H 'Content-Type: application/json' -d '{"a":1}' https://...` runs
+//! the same request through `call` and shows the equivalent NUTS command. Options NUTS has
+//! no counterpart for are ignored with a warning instead of failing the command.
+
+use std::error::Error;
+
+/// Short options that take a value, as in `-X POST` or `-XPOST`.
+const SHORT_WITH_VALUE: &str = "XHduFAebomxrTwcEKUyYzCD";
+
+/// Options that only change how curl itself reports, not the request it sends.
+const COSMETIC: &[&str] = &[
+    "-s", "--silent", "-S", "--show-error", "-g", "--globoff", "-N", "--no-buffer", "-q", "--disable",
+    "--no-progress-meter", "-#", "--progress-bar",
+];
+
+/// Options NUTS has no counterpart for that take a value, so the value is skipped too.
+const UNSUPPORTED_WITH_VALUE: &[&str] = &[
+    "-r", "--range", "-T", "--upload-file", "-w", "--write-out", "-c", "--cookie-jar",
+    "--cert-type", "--key-type", "--capath", "--pass", "-K", "--config", "-U", "--proxy-user", "-y",
+    "--speed-time", "-Y", "--speed-limit", "-z", "--time-cond",
+    "--connect-to", "--retry-delay", "--retry-max-time", "--limit-rate",
+    "--interface", "--dns-servers", "--trace", "--trace-ascii", "--stderr", "--tls-max", "--ciphers", "--proto",
+    "--proto-redir", "--unix-socket", "--abstract-unix-socket", "--netrc-file", "--local-port",
+    "--keepalive-time", "--expect100-timeout", "--variable", "--url-query", "--request-target",
+];
+
+/// A curl command as `call` arguments, with what couldn't be carried over.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurlTranslation {
+    /// Starts with `call`; ready for [`crate::commands::call::CallCommand::parse_advanced_args`].
+    pub args: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// One `-d`-style piece of the body, joined with `&` like curl does.
+enum Data {
+    /// `-d`: `@file` reads the file.
+    Text(String),
+    /// `--data-raw`: `@` is literal.
+    Raw(String),
+    /// `--data-binary`: sent as is, `@file` included.
+    Binary(String),
+}
+
+/// Translate `curl` arguments (starting with `curl`) into `call` arguments.
+pub fn translate(argv: &[String]) -> Result<CurlTranslation, Box<dyn Error>> {
+    let mut warnings = Vec::new();
+    let mut method: Option<String> = None;
+    let mut url: Option<String> = None;
+    let mut headers: Vec<String> = Vec::new();
+    let mut flags: Vec<String> = Vec::new();
+    let mut data: Vec<Data> = Vec::new();
+    let mut json = false;
+    let mut head = false;
+    let mut get = false;
+
+    let mut args = expand_short_options(argv.get(1..).unwrap_or_default());
+    args.reverse();
+    while let Some(arg) = args.pop() {
+        let takes_value = matches!(arg.as_str(),
+            "-X" | "--request" | "-H" | "--header" | "-d" | "--data" | "--data-ascii" | "--data-raw"
+            | "--data-binary" | "--data-urlencode" | "--json" | "-u" | "--user" | "-F" | "--form" | "--form-string"
+            | "-A" | "--user-agent" | "-e" | "--referer" | "-b" | "--cookie" | "-o" | "--output" | "-m" | "--max-time" | "--connect-timeout" | "--max-redirs"
+            | "-x" | "--proxy" | "--noproxy" | "--retry" | "--url" | "--oauth2-bearer" | "-C" | "--continue-at" | "--resolve" | "-E" | "--cert" | "--key" | "--cacert"
+            | "-D" | "--dump-header" | "--aws-sigv4"
+        ) || UNSUPPORTED_WITH_VALUE.contains(&arg.as_str());
+        let value = if takes_value {
+            args.pop().ok_or_else(|| format!("curl option {} needs a value", arg))?
+        } else {
+            String::new()
+        };
+
+        match arg.as_str() {
+            "-X" | "--request" => method = Some(value.to_uppercase()),
+            "-H" | "--header" => {
+                // `-H 'X-Empty;'` sends the header with no value
+                let header = match value.strip_suffix(';') {
+                    Some(name) if !name.contains(':') => format!("{}:", name),
+                    _ => value,
+                };
+                if !header.contains(':') {
+                    warnings.push(format!("Ignoring header '{}': expected 'Name: value'", header));
+                    continue;
+                }
+                headers.push(header);
+            }
+            "-d" | "--data" | "--data-ascii" => data.push(Data::Text(value)),
+            "--data-raw" => data.push(Data::Raw(value)),
+            "--data-binary" => data.push(Data::Binary(value)),
+            "--data-urlencode" => data.push(Data::Raw(urlencode_data(&value))),
+            "--json" => {
+                json = true;
+                data.push(Data::Text(value));
+            }
+            "-u" | "--user" => {
+                if !value.contains(':') {
+                    warnings.push(format!("No password given for '{}'; sending an empty one instead of asking", value));
+                }
+                let credentials = if value.contains(':') { value } else { format!("{}:", value) };
+                flags.extend(["-u".to_string(), credentials]);
+            }
+            "-F" | "--form" | "--form-string" => flags.extend(["-F".to_string(), value]),
+            "-A" | "--user-agent" => flags.extend(["-A".to_string(), value]),
+            "-e" | "--referer" => headers.push(format!("Referer: {}", value)),
+            "-b" | "--cookie" => {
+                if value.contains('=') {
+                    headers.push(format!("Cookie: {}", value));
+                } else {
+                    warnings.push(format!("Ignoring -b {}: cookie files aren't supported, pass 'name=value' instead", value));
+                }
+            }
+            "--oauth2-bearer" => flags.extend(["--bearer".to_string(), value]),
+            "-o" | "--output" => flags.extend(["-o".to_string(), value]),
+            "-C" | "--continue-at" => flags.extend(["-C".to_string(), value]),
+            "-m" | "--max-time" => match value.parse::<f64>() {
+                Ok(seconds) if seconds > 0.0 => flags.extend(["--timeout".to_string(), (seconds.ceil() as u64).to_string()]),
+                _ => warnings.push(format!("Ignoring --max-time {}: not a number of seconds", value)),
+            },
+            "--connect-timeout" => match value.parse::<f64>() {
+                Ok(seconds) if seconds > 0.0 => flags.extend(["--connect-timeout".to_string(), (seconds.ceil() as u64).to_string()]),
+                _ => warnings.push(format!("Ignoring --connect-timeout {}: not a number of seconds", value)),
+            },
+            // `aws:amz:REGION:SERVICE`; curl can also guess the last two from the host
+            "--aws-sigv4" => match value.split(':').collect::<Vec<_>>()[..] {
+                [_, _, region, service] => flags.extend(["--aws-sigv4".to_string(), format!("{}:{}", region, service)]),
+                _ => warnings.push(format!("Ignoring --aws-sigv4 {}: NUTS needs the region and service, as in aws:amz:us-east-1:execute-api", value)),
+            },
+            "--max-redirs" => match value.parse::<usize>() {
+                Ok(count) => flags.extend(["--max-redirs".to_string(), count.to_string()]),
+                Err(_) => warnings.push(format!("Ignoring --max-redirs {}: only a count of 0 or more is supported", value)),
+            },
+            "-x" | "--proxy" => flags.extend(["-x".to_string(), value]),
+            "--noproxy" => {
+                if value == "*" {
+                    flags.push("--noproxy".to_string());
+                } else {
+                    warnings.push(format!("Ignoring --noproxy {}: only '*' (no proxy at all) is supported", value));
+                }
+            }
+            "--retry" => flags.extend(["--retry".to_string(), value]),
+            "--resolve" => flags.extend(["--resolve".to_string(), value]),
+            // `file:password`, but not a Windows drive letter
+            "-E" | "--cert" if value.split_once(':').is_some_and(|(file, _)| file.len() > 1) => {
+                warnings.push(format!("Ignoring {} {}: certificate passwords aren't supported, use an unencrypted PEM key", arg, value));
+            }
+            "-E" | "--cert" => flags.extend(["--cert".to_string(), value]),
+            "--key" | "--cacert" => flags.extend([arg.clone(), value]),
+            "-D" | "--dump-header" if value == "-" => flags.push("-i".to_string()),
+            "-D" | "--dump-header" => flags.extend(["--dump-headers".to_string(), value]),
+            "--url" => set_url(&mut url, value, &mut warnings),
+            "-L" | "--location" | "-k" | "--insecure" | "-v" | "--verbose" | "-i" | "--include" | "-O" | "--remote-name"
+            | "--http1.1" | "--http2" | "--http2-prior-knowledge" | "--compressed" => {
+                let flag = match arg.as_str() {
+                    "--location" => "-L",
+                    "--insecure" => "-k",
+                    "--verbose" => "-v",
+                    "--include" => "-i",
+                    "--remote-name" => "-O",
+                    other => other,
+                };
+                flags.push(flag.to_string());
+            }
+            "-I" | "--head" => head = true,
+            "-G" | "--get" => get = true,
+            flag if COSMETIC.contains(&flag) => {}
+            flag if takes_value => warnings.push(format!("Ignoring unsupported curl option {} {}", flag, value)),
+            flag if flag.starts_with('-') && flag.len() > 1 => warnings.push(format!("Ignoring unsupported curl option {}", flag)),
+            _ => set_url(&mut url, arg, &mut warnings),
+        }
+    }
+
+    let mut url = url.ok_or("No URL in the curl command")?;
+    if !url.contains("://") {
+        // curl's default scheme
+        url = format!("http://{}", url);
+    }
+
+    // The body as `call` options: `-d`, `--data-binary`, or on its own at the end for a
+    // literal leading `@`
+    let mut body: Option<(Option<&str>, String)> = None;
+    if get && !data.is_empty() {
+        let query = data.iter().map(|piece| match piece {
+            Data::Text(text) | Data::Raw(text) | Data::Binary(text) => text.as_str(),
+        }).collect::<Vec<_>>().join("&");
+        url.push(if url.contains('?') { '&' } else { '?' });
+        url.push_str(&query);
+    } else if data.len() == 1 {
+        // A lone `@file` stays a file reference, which `call` reads the same way
+        body = Some(match data.remove(0) {
+            Data::Text(text) => (Some("-d"), text),
+            Data::Raw(text) if text.starts_with('@') => (None, text),
+            Data::Raw(text) => (Some("-d"), text),
+            Data::Binary(text) => (Some("--data-binary"), text),
+        });
+    } else if !data.is_empty() {
+        let mut pieces = Vec::new();
+        for piece in data {
+            pieces.push(match piece {
+                Data::Text(text) => match text.strip_prefix('@') {
+                    // curl drops newlines from files read with -d
+                    Some(path) => read_file(path)?.replace(['\r', '\n'], ""),
+                    None => text,
+                },
+                Data::Binary(text) => match text.strip_prefix('@') {
+                    Some(path) => read_file(path)?,
+                    None => text,
+                },
+                Data::Raw(text) => text,
+            });
+        }
+        body = Some((Some("-d"), pieces.join(if json { "" } else { "&" })));
+    }
+
+    let has_header = |headers: &[String], name: &str| {
+        headers.iter().any(|h| h.split(':').next().is_some_and(|n| n.trim().eq_ignore_ascii_case(name)))
+    };
+    if json {
+        if !has_header(&headers, "Content-Type") {
+            headers.push("Content-Type: application/json".to_string());
+        }
+        if !has_header(&headers, "Accept") {
+            headers.push("Accept: application/json".to_string());
+        }
+    } else if body.is_some() && !has_header(&headers, "Content-Type") {
+        headers.push("Content-Type: application/x-www-form-urlencoded".to_string());
+    }
+
+    // curl signs with the `-u` keys; NUTS takes them from the environment or ~/.aws/credentials
+    if flags.iter().any(|f| f == "--aws-sigv4") {
+        if let Some(at) = flags.iter().position(|f| f == "-u") {
+            flags.drain(at..at + 2);
+            warnings.push("Dropped -u: --aws-sigv4 signs with AWS_ACCESS_KEY_ID/AWS_SECRET_ACCESS_KEY or ~/.aws/credentials".to_string());
+        }
+    }
+
+    let sends_body = body.is_some() || flags.iter().any(|f| f == "-F");
+    let method = match method {
+        Some(method) => method,
+        None if head => "HEAD".to_string(),
+        None if sends_body => "POST".to_string(),
+        None => "GET".to_string(),
+    };
+
+    let mut args = vec!["call".to_string(), method, url];
+    for header in headers {
+        args.extend(["-H".to_string(), header]);
+    }
+    args.extend(flags);
+    match body {
+        Some((Some(flag), text)) => args.extend([flag.to_string(), text]),
+        Some((None, text)) => args.push(text),
+        None => {}
+    }
+    Ok(CurlTranslation { args, warnings })
+}
+
+/// Split bundled short options: `-sSL` into `-s -S -L`, `-XPOST` into `-X POST`.
+fn expand_short_options(args: &[String]) -> Vec<String> {
+    let mut expanded = Vec::new();
+    for arg in args {
+        let Some(letters) = arg.strip_prefix('-').filter(|rest| rest.len() > 1 && !rest.starts_with('-')) else {
+            expanded.push(arg.clone());
+            continue;
+        };
+        for (i, letter) in letters.char_indices() {
+            expanded.push(format!("-{}", letter));
+            if SHORT_WITH_VALUE.contains(letter) {
+                let value = &letters[i + letter.len_utf8()..];
+                if !value.is_empty() {
+                    expanded.push(value.to_string());
+                }
+                break;
+            }
+        }
+    }
+    expanded
+}
+
+fn set_url(url: &mut Option<String>, value: String, warnings: &mut Vec<String>) {
+    if url.is_some() {
+        warnings.push(format!("Ignoring {}: only the first URL is requested", value));
+    } else {
+        *url = Some(value);
+    }
+}
+
+/// `--data-urlencode` content: `name=value` encodes the value, anything else all of it.
+fn urlencode_data(value: &str) -> String {
+    let encode = |text: &str| url::form_urlencoded::byte_serialize(text.as_bytes()).collect::<String>();
+    match value.split_once('=') {
+        Some(("", content)) => encode(content),
+        Some((name, content)) => format!("{}={}", name, encode(content)),
+        None => encode(value),
+    }
+}
+
+fn read_file(path: &str) -> Result<String, Box<dyn Error>> {
+    std::fs::read_to_string(path).map_err(|e| format!("Could not read file '{}': {}", path, e).into())
+}
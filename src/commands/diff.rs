@@ -0,0 +1,423 @@
+//! `diff`: send the same request to two URLs and compare the responses, so a migration can
+//! be checked with "does staging return what prod returns".
+//!
+//! JSON bodies are compared structurally (key order doesn't matter); anything else falls
+//! back to a line diff. Finding a difference is an error, so the command can gate scripts.
+
+use crate::config::Environment;
+use console::style;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Method};
+use serde_json::Value;
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+/// Response headers that change between any two requests and are never compared.
+const VOLATILE_HEADERS: &[&str] = &[
+    "age", "cf-ray", "content-length", "date", "etag", "expires", "last-modified", "server-timing",
+    "set-cookie", "via", "x-amzn-trace-id", "x-cache", "x-request-id", "x-runtime",
+];
+
+/// One difference between the first (`left`) and second (`right`) response.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    /// Only in the second response.
+    Added { path: String, value: Value },
+    /// Only in the first response.
+    Removed { path: String, value: Value },
+    Changed { path: String, left: Value, right: Value },
+}
+
+impl Difference {
+    pub fn path(&self) -> &str {
+        match self {
+            Difference::Added { path, .. } | Difference::Removed { path, .. } | Difference::Changed { path, .. } => path,
+        }
+    }
+
+    pub(crate) fn print(&self) {
+        match self {
+            Difference::Added { path, value } => println!("  {} {}: {}", style("+").green().bold(), path, style(value).green()),
+            Difference::Removed { path, value } => println!("  {} {}: {}", style("-").red().bold(), path, style(value).red()),
+            Difference::Changed { path, left, right } => println!(
+                "  {} {}: {} → {}",
+                style("~").yellow().bold(),
+                path,
+                style(left).red(),
+                style(right).green()
+            ),
+        }
+    }
+}
+
+/// Structural diff of two JSON documents. Paths look like `$.items[0].name`; objects are
+/// compared by key, arrays by index. Paths matching an `ignore` pattern (see
+/// [`path_matches`]) are skipped along with everything below them.
+pub fn diff_json(left: &Value, right: &Value, ignore: &[String]) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    diff_values("$", left, right, ignore, &mut differences);
+    differences
+}
+
+fn diff_values(path: &str, left: &Value, right: &Value, ignore: &[String], out: &mut Vec<Difference>) {
+    if ignored(path, ignore) {
+        return;
+    }
+    match (left, right) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = format!("{}.{}", path, key);
+                match (a.get(key), b.get(key)) {
+                    (Some(l), Some(r)) => diff_values(&child, l, r, ignore, out),
+                    (Some(l), None) if !ignored(&child, ignore) => out.push(Difference::Removed { path: child, value: l.clone() }),
+                    (None, Some(r)) if !ignored(&child, ignore) => out.push(Difference::Added { path: child, value: r.clone() }),
+                    _ => {}
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                let child = format!("{}[{}]", path, i);
+                match (a.get(i), b.get(i)) {
+                    (Some(l), Some(r)) => diff_values(&child, l, r, ignore, out),
+                    (Some(l), None) if !ignored(&child, ignore) => out.push(Difference::Removed { path: child, value: l.clone() }),
+                    (None, Some(r)) if !ignored(&child, ignore) => out.push(Difference::Added { path: child, value: r.clone() }),
+                    _ => {}
+                }
+            }
+        }
+        _ if left != right => out.push(Difference::Changed { path: path.to_string(), left: left.clone(), right: right.clone() }),
+        _ => {}
+    }
+}
+
+/// `value` with object keys sorted and every path matching an `ignore` pattern removed,
+/// so it can be stored and later compared with [`diff_json`].
+pub fn normalize(value: &Value, ignore: &[String]) -> Value {
+    normalize_at("$", value, ignore)
+}
+
+fn normalize_at(path: &str, value: &Value, ignore: &[String]) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let mut sorted = serde_json::Map::new();
+            for key in keys {
+                let child = format!("{}.{}", path, key);
+                if !ignored(&child, ignore) {
+                    sorted.insert(key.clone(), normalize_at(&child, &map[key], ignore));
+                }
+            }
+            Value::Object(sorted)
+        }
+        Value::Array(items) => Value::Array(
+            items.iter().enumerate()
+                .map(|(i, item)| (format!("{}[{}]", path, i), item))
+                .filter(|(child, _)| !ignored(child, ignore))
+                .map(|(child, item)| normalize_at(&child, item, ignore))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn ignored(path: &str, ignore: &[String]) -> bool {
+    ignore.iter().any(|pattern| path_matches(pattern, path))
+}
+
+/// Whether `path` is `pattern` or lies below it. `*` matches any key and `[*]` any index,
+/// so `$.items[*].updated_at` skips that field in every element.
+pub fn path_matches(pattern: &str, path: &str) -> bool {
+    let pattern = segments(pattern);
+    let path = segments(path);
+    pattern.len() <= path.len()
+        && pattern.iter().zip(&path).all(|(p, s)| p == s || *p == "*" || (*p == "[*]" && s.starts_with('[')))
+}
+
+/// `$.a.b[0]` → `["a", "b", "[0]"]`.
+fn segments(path: &str) -> Vec<&str> {
+    let path = path.trim().trim_start_matches('$');
+    let mut segments = Vec::new();
+    for part in path.split('.').filter(|p| !p.is_empty()) {
+        let mut rest = part;
+        while let Some(start) = rest.find('[') {
+            if start > 0 {
+                segments.push(&rest[..start]);
+            }
+            let end = rest[start..].find(']').map_or(rest.len(), |e| start + e + 1);
+            segments.push(&rest[start..end]);
+            rest = &rest[end..];
+        }
+        if !rest.is_empty() {
+            segments.push(rest);
+        }
+    }
+    segments
+}
+
+/// One line of a line diff.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineChange<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Longest-common-subsequence line diff, used when a body isn't JSON.
+pub fn diff_lines<'a>(left: &'a str, right: &'a str) -> Vec<LineChange<'a>> {
+    let a: Vec<&str> = left.lines().collect();
+    let b: Vec<&str> = right.lines().collect();
+    // lcs[i][j]: length of the LCS of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changes = Vec::new();
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            changes.push(LineChange::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            changes.push(LineChange::Removed(a[i]));
+            i += 1;
+        } else {
+            changes.push(LineChange::Added(b[j]));
+            j += 1;
+        }
+    }
+    changes.extend(a[i..].iter().map(|line| LineChange::Removed(line)));
+    changes.extend(b[j..].iter().map(|line| LineChange::Added(line)));
+    changes
+}
+
+#[derive(Debug, Default)]
+pub struct DiffOptions {
+    pub method: String,
+    pub left_url: String,
+    pub right_url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    /// JSON paths to leave out, e.g. `$.meta.request_id`.
+    pub ignore: Vec<String>,
+    /// Also compare response headers other than [`VOLATILE_HEADERS`].
+    pub headers_too: bool,
+}
+
+/// What `diff` found, for callers that want more than the printed report.
+#[derive(Debug)]
+pub struct DiffReport {
+    pub left_status: u16,
+    pub right_status: u16,
+    /// Status, header (`headers.<name>`) and JSON differences.
+    pub differences: Vec<Difference>,
+    /// Changed lines when either body isn't JSON; 0 otherwise.
+    pub line_changes: usize,
+}
+
+impl DiffReport {
+    pub fn is_identical(&self) -> bool {
+        self.differences.is_empty() && self.line_changes == 0
+    }
+}
+
+pub(crate) struct Fetched {
+    pub status: u16,
+    pub headers: HeaderMap,
+    pub body: String,
+    pub elapsed: Duration,
+}
+
+/// Send one request with `{{name}}` placeholders resolved. JSON bodies get a JSON content type
+/// unless the headers set one.
+pub(crate) async fn fetch(
+    client: &Client,
+    environment: &Environment,
+    method: &str,
+    url: &str,
+    headers: &[(String, String)],
+    body: Option<&str>,
+) -> Result<Fetched, Box<dyn Error>> {
+    let url = environment.substitute(url)?;
+    let url = if url.contains("://") { url } else { format!("https://{}", url) };
+    let method: Method = method.parse()?;
+
+    let mut request = client.request(method, &url);
+    for (key, value) in headers {
+        request = request.header(key.as_str(), environment.substitute(value)?);
+    }
+    if let Some(body) = body {
+        let body = environment.substitute(body)?;
+        if serde_json::from_str::<Value>(&body).is_ok() && !headers.iter().any(|(k, _)| k.eq_ignore_ascii_case("content-type")) {
+            request = request.header(reqwest::header::CONTENT_TYPE, "application/json");
+        }
+        request = request.body(body);
+    }
+
+    let started = Instant::now();
+    let response = request.send().await.map_err(|e| format!("Request to {} failed: {}", url, e))?;
+    let status = response.status().as_u16();
+    let headers = response.headers().clone();
+    let body = response.text().await?;
+    Ok(Fetched { status, headers, body, elapsed: started.elapsed() })
+}
+
+pub struct DiffCommand {
+    client: Client,
+    environment: Environment,
+}
+
+impl Default for DiffCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiffCommand {
+    pub fn new() -> Self {
+        Self {
+            client: Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap(),
+            environment: Environment::default(),
+        }
+    }
+
+    /// Substitute `{{name}}` placeholders in both URLs, the headers and the body.
+    pub fn with_environment(mut self, environment: Environment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// `diff [METHOD] URL1 URL2 [-H 'K: V']... [-d BODY] [--ignore-path PATH]... [--headers-too]`
+    pub async fn execute(&self, args: &[&str]) -> Result<DiffReport, Box<dyn Error>> {
+        let options = Self::parse_args(args)?;
+        let report = self.run(&options).await?;
+        if report.is_identical() {
+            println!("✅ {}", style("Responses are identical").green());
+            return Ok(report);
+        }
+        let count = report.differences.len() + report.line_changes;
+        Err(format!("Found {} difference(s) between {} and {}", count, options.left_url, options.right_url).into())
+    }
+
+    pub fn parse_args(args: &[&str]) -> Result<DiffOptions, Box<dyn Error>> {
+        let mut options = DiffOptions { method: "GET".to_string(), ..Default::default() };
+        let mut positional = Vec::new();
+
+        let mut i = 1; // Skip "diff"
+        while i < args.len() {
+            match (args[i], args.get(i + 1)) {
+                ("-H" | "--header", Some(header)) => {
+                    let (key, value) = header.split_once(':').ok_or("Header must be in format 'Key: Value'")?;
+                    options.headers.push((key.trim().to_string(), value.trim().to_string()));
+                }
+                ("--bearer", Some(token)) => options.headers.push(("Authorization".to_string(), format!("Bearer {}", token))),
+                ("-d" | "--data", Some(body)) => options.body = Some(body.to_string()),
+                ("--ignore-path", Some(path)) => options.ignore.push(path.to_string()),
+                ("--headers-too", _) => {
+                    options.headers_too = true;
+                    i += 1;
+                    continue;
+                }
+                (flag @ ("-H" | "--header" | "--bearer" | "-d" | "--data" | "--ignore-path"), None) => {
+                    return Err(format!("Value required after {}", flag).into());
+                }
+                (flag, _) if flag.starts_with('-') => return Err(format!("Unknown option: {}", flag).into()),
+                (value, _) => {
+                    positional.push(value);
+                    i += 1;
+                    continue;
+                }
+            }
+            i += 2;
+        }
+
+        let urls = match positional.as_slice() {
+            [method, left, right] => {
+                options.method = method.to_uppercase();
+                (left, right)
+            }
+            [left, right] => (left, right),
+            _ => return Err("Usage: diff [METHOD] URL1 URL2 [-H 'K: V'] [-d BODY] [--ignore-path $.path]... [--headers-too]".into()),
+        };
+        options.left_url = urls.0.to_string();
+        options.right_url = urls.1.to_string();
+        Ok(options)
+    }
+
+    /// Send the request to both URLs at once and print every difference.
+    pub async fn run(&self, options: &DiffOptions) -> Result<DiffReport, Box<dyn Error>> {
+        let (left, right) = tokio::join!(
+            fetch(&self.client, &self.environment, &options.method, &options.left_url, &options.headers, options.body.as_deref()),
+            fetch(&self.client, &self.environment, &options.method, &options.right_url, &options.headers, options.body.as_deref())
+        );
+        let (left, right) = (left?, right?);
+        for (url, fetched) in [(&options.left_url, &left), (&options.right_url, &right)] {
+            println!("🔍 {} {} → {} ({}ms)", options.method, style(url).cyan(), style(fetched.status).yellow(), fetched.elapsed.as_millis());
+        }
+        println!();
+
+        let mut differences = Vec::new();
+        if left.status != right.status {
+            differences.push(Difference::Changed { path: "status".to_string(), left: left.status.into(), right: right.status.into() });
+        }
+        if options.headers_too {
+            differences.extend(diff_headers(&left.headers, &right.headers));
+        }
+
+        let line_diff: Vec<LineChange> = match (serde_json::from_str::<Value>(&left.body), serde_json::from_str::<Value>(&right.body)) {
+            (Ok(a), Ok(b)) => {
+                differences.extend(diff_json(&a, &b, &options.ignore));
+                Vec::new()
+            }
+            _ => diff_lines(&left.body, &right.body).into_iter().filter(|c| !matches!(c, LineChange::Same(_))).collect(),
+        };
+
+        for difference in &differences {
+            difference.print();
+        }
+        for change in &line_diff {
+            match change {
+                LineChange::Removed(line) => println!("  {}", style(format!("- {}", line)).red()),
+                LineChange::Added(line) => println!("  {}", style(format!("+ {}", line)).green()),
+                LineChange::Same(_) => {}
+            }
+        }
+        Ok(DiffReport { left_status: left.status, right_status: right.status, differences, line_changes: line_diff.len() })
+    }
+}
+
+/// Differences in response headers, skipping [`VOLATILE_HEADERS`]. Paths are `headers.<name>`.
+pub fn diff_headers(left: &HeaderMap, right: &HeaderMap) -> Vec<Difference> {
+    let value = |headers: &HeaderMap, name: &str| {
+        let values: Vec<&str> = headers.get_all(name).iter().filter_map(|v| v.to_str().ok()).collect();
+        (!values.is_empty()).then(|| Value::String(values.join(", ")))
+    };
+    let mut names: Vec<&str> = left.keys().chain(right.keys()).map(|name| name.as_str()).collect();
+    names.sort_unstable();
+    names.dedup();
+
+    names.into_iter()
+        .filter(|name| !VOLATILE_HEADERS.contains(name))
+        .filter_map(|name| {
+            let path = format!("headers.{}", name);
+            match (value(left, name), value(right, name)) {
+                (Some(l), Some(r)) if l != r => Some(Difference::Changed { path, left: l, right: r }),
+                (Some(l), None) => Some(Difference::Removed { path, value: l }),
+                (None, Some(r)) => Some(Difference::Added { path, value: r }),
+                _ => None,
+            }
+        })
+        .collect()
+}
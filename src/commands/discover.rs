@@ -1,13 +1,67 @@
-use anthropic::{
-    client::ClientBuilder,
-    types::{Message, ContentBlock, MessagesRequestBuilder, Role},
-};
 use reqwest;
 use serde_json::Value;
+use std::collections::{HashSet, VecDeque};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use url::Url;
+use console::style;
+use crate::ai;
 use crate::config::Config;
+use crate::units;
+use crate::commands::{print_ai_failed, print_ai_skipped};
+use crate::flows::remote::{fetch_spec, parse_document, SpecCache};
+use crate::flows::{MediaType, OpenAPISpec, Operation, Parameter, Response, Schema, Server};
 
 pub struct DiscoverCommand {
     config: Config,
+    options: DiscoverOptions,
+    /// Where documentation found on earlier runs is kept and revalidated.
+    cache: Option<SpecCache>,
+}
+
+/// Bounds of the crawl that follows links, sitemaps and HTML from the base URL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoverOptions {
+    /// How many links away from the base URL or sitemap pages are still fetched.
+    pub depth: usize,
+    /// Most requests the crawl sends, sitemap included.
+    pub max_requests: usize,
+    /// Pause between crawl requests.
+    pub delay: Duration,
+}
+
+impl Default for DiscoverOptions {
+    fn default() -> Self {
+        Self { depth: 2, max_requests: 100, delay: Duration::from_millis(100) }
+    }
+}
+
+impl DiscoverOptions {
+    /// `discover BASE_URL [--depth N] [--max-requests N] [--delay 200ms]`
+    pub fn from_args(args: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let mut options = Self::default();
+        let mut i = 2; // Skip "discover BASE_URL"
+        while i < args.len() {
+            let value = args.get(i + 1).copied().ok_or_else(|| format!("Value required after {}", args[i]))?;
+            match args[i] {
+                "--depth" => {
+                    options.depth = value.parse().map_err(|_| format!("--depth must be a number, got '{}'", value))?;
+                }
+                "--max-requests" => {
+                    options.max_requests = value.parse().ok().filter(|n| *n > 0)
+                        .ok_or_else(|| format!("--max-requests must be a positive number, got '{}'", value))?;
+                }
+                "--delay" => {
+                    options.delay = units::parse_duration(value).map_err(|e| format!("Invalid --delay: {}", e))?;
+                }
+                other => return Err(format!("Unknown option for discover: {}", other).into()),
+            }
+            i += 2;
+        }
+        Ok(options)
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -27,11 +81,35 @@ pub struct ApiMap {
     #[allow(dead_code)]
     pub rate_limits: Option<String>,
     pub documentation: Option<String>,
+    /// The OpenAPI document found at `documentation`, if it was one.
+    pub spec: Option<Value>,
+    /// URLs fetched while crawling, in order.
+    pub crawled: Vec<String>,
+}
+
+/// Add `endpoint` unless one with the same method and path is already known.
+fn add_endpoint(api_map: &mut ApiMap, endpoint: ApiEndpoint) -> bool {
+    let known = api_map.endpoints.iter()
+        .any(|e| e.method.eq_ignore_ascii_case(&endpoint.method) && e.path == endpoint.path);
+    if !known {
+        api_map.endpoints.push(endpoint);
+    }
+    !known
 }
 
 impl DiscoverCommand {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        Self { config, options: DiscoverOptions::default(), cache: None }
+    }
+
+    pub fn with_cache(mut self, cache: SpecCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn with_options(mut self, options: DiscoverOptions) -> Self {
+        self.options = options;
+        self
     }
 
     /// Auto-Discovery & API Intelligence
@@ -44,6 +122,8 @@ impl DiscoverCommand {
             authentication: None,
             rate_limits: None,
             documentation: None,
+            spec: None,
+            crawled: Vec::new(),
         };
 
         // Step 1: Try common documentation endpoints
@@ -54,11 +134,16 @@ impl DiscoverCommand {
         println!("🔎 Probing common endpoint patterns...");
         self.discover_common_patterns(&mut api_map).await?;
 
-        // Step 3: Analyze discovered endpoints with AI
+        // Step 3: Follow links, the sitemap and HTML from the base URL
+        println!("🕸️  Crawling links, sitemap and HTML (depth {}, at most {} requests)...",
+            self.options.depth, self.options.max_requests);
+        self.crawl(&mut api_map).await?;
+
+        // Step 4: Analyze discovered endpoints with AI
         println!("🤖 Analyzing discovered endpoints with AI...");
         self.analyze_endpoints_with_ai(&mut api_map).await?;
 
-        // Step 4: Generate test recommendations
+        // Step 5: Generate test recommendations
         println!("💡 Generating test recommendations...");
         self.generate_test_recommendations(&api_map).await?;
 
@@ -83,21 +168,27 @@ impl DiscoverCommand {
         for endpoint in doc_endpoints {
             let url = format!("{}{}", api_map.base_url, endpoint);
             
-            match client.get(&url).send().await {
-                Ok(response) if response.status().is_success() => {
-                    println!("✅ Found documentation at: {}", endpoint);
-                    
-                    let content = response.text().await?;
-                    
-                    // Try to parse as OpenAPI/Swagger
-                    if let Ok(openapi) = serde_json::from_str::<Value>(&content) {
-                        self.parse_openapi_spec(&openapi, api_map)?;
+            let result = fetch_spec(&client, &url, self.cache.as_ref()).await;
+            match &result {
+                Ok(fetched) => tracing::debug!(target: "nuts::discover", %url, from_cache = fetched.from_cache, "documentation probe"),
+                Err(e) => tracing::debug!(target: "nuts::discover", %url, error = %e, "documentation probe failed"),
+            }
+
+            // Missing or unreachable: try the next endpoint
+            if let Ok(fetched) = result {
+                let cached = if fetched.from_cache { " (unchanged since last run)" } else { "" };
+                println!("✅ Found documentation at: {}{}", endpoint, cached);
+                
+                // Try to parse as OpenAPI/Swagger
+                if let Ok(openapi) = parse_document(&fetched.body, fetched.content_type.as_deref()) {
+                    self.parse_openapi_spec(&openapi, api_map)?;
+                    if openapi.get("paths").is_some() {
+                        api_map.spec = Some(openapi);
                     }
-                    
-                    api_map.documentation = Some(url);
-                    break;
                 }
-                _ => {} // Continue trying other endpoints
+                
+                api_map.documentation = Some(url);
+                break;
             }
         }
 
@@ -119,7 +210,7 @@ impl DiscoverCommand {
                                 parameters: self.extract_parameters(operation),
                                 response_type: self.extract_response_type(operation),
                             };
-                            api_map.endpoints.push(endpoint);
+                            add_endpoint(api_map, endpoint);
                         }
                     }
                 }
@@ -182,37 +273,169 @@ impl DiscoverCommand {
                 _ => continue,
             };
 
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
+            // Network errors are skipped, continue with the next pattern
+            let result = request.send().await;
+            if let Err(e) = &result {
+                tracing::debug!(target: "nuts::discover", method, %url, error = %e, "pattern probe failed");
+            }
+            if let Ok(response) = result {
+                let status = response.status();
+                tracing::debug!(target: "nuts::discover", method, %url, status = status.as_u16(), "pattern probe");
+                
+                // Consider it a valid endpoint if it's not 404
+                if status != reqwest::StatusCode::NOT_FOUND {
+                    let endpoint = ApiEndpoint {
+                        path: path.to_string(),
+                        method: method.to_string(),
+                        description: Some("Discovered via pattern matching".to_string()),
+                        parameters: Vec::new(),
+                        response_type: self.detect_response_type(&response).await,
+                    };
                     
-                    // Consider it a valid endpoint if it's not 404
-                    if status != reqwest::StatusCode::NOT_FOUND {
+                    if add_endpoint(api_map, endpoint) {
                         println!("✅ Discovered endpoint: {} {}", method, path);
-                        
-                        let endpoint = ApiEndpoint {
-                            path: path.to_string(),
-                            method: method.to_string(),
-                            description: Some(format!("Discovered via pattern matching")),
-                            parameters: Vec::new(),
-                            response_type: self.detect_response_type(&response).await,
-                        };
-                        
-                        api_map.endpoints.push(endpoint);
-                        
-                        // Try to detect authentication requirements
-                        if status == reqwest::StatusCode::UNAUTHORIZED {
-                            api_map.authentication = Some("Authentication required".to_string());
-                        }
+                    }
+                    
+                    // Try to detect authentication requirements
+                    if status == reqwest::StatusCode::UNAUTHORIZED {
+                        api_map.authentication = Some("Authentication required".to_string());
                     }
                 }
-                Err(_) => {} // Network error, continue
             }
         }
 
         Ok(())
     }
 
+    /// Breadth-first crawl of same-origin pages from the base URL and `/sitemap.xml`.
+    /// Targets under `/api/` and pages answering JSON become endpoints; HTML pages, JSON
+    /// `Link` headers and redirects lead further until `depth` or `max_requests` runs out.
+    async fn crawl(&self, api_map: &mut ApiMap) -> Result<(), Box<dyn std::error::Error>> {
+        let base = Url::parse(&api_map.base_url)?;
+        // Redirects are followed by hand so they can't lead off-origin
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(Duration::from_secs(10))
+            .build()?;
+
+        let mut queue: VecDeque<(Url, usize)> = VecDeque::from([(base.clone(), 0)]);
+        let mut seen: HashSet<String> = HashSet::from([page_key(&base)]);
+        let mut sent = 0;
+
+        if let Ok(sitemap) = base.join("/sitemap.xml") {
+            sent += 1;
+            api_map.crawled.push(sitemap.to_string());
+            match client.get(sitemap.clone()).send().await {
+                Ok(response) if response.status().is_success() => {
+                    let body = response.text().await.unwrap_or_default();
+                    for url in sitemap_urls(&body).iter().filter_map(|loc| Url::parse(loc).ok()) {
+                        self.found(api_map, &mut queue, &mut seen, &base, url, "GET", 1, "sitemap.xml");
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => tracing::debug!(target: "nuts::discover", url = %sitemap, error = %e, "sitemap fetch failed"),
+            }
+        }
+
+        while let Some((url, depth)) = queue.pop_front() {
+            if sent >= self.options.max_requests {
+                println!("⚠️  Stopped crawling after {} requests (--max-requests)", sent);
+                break;
+            }
+            if sent > 0 {
+                tokio::time::sleep(self.options.delay).await;
+            }
+            sent += 1;
+            api_map.crawled.push(url.to_string());
+
+            let response = match client.get(url.clone()).send().await {
+                Ok(response) => response,
+                Err(e) => {
+                    tracing::debug!(target: "nuts::discover", %url, error = %e, "crawl request failed");
+                    continue;
+                }
+            };
+            let status = response.status();
+            tracing::debug!(target: "nuts::discover", %url, status = status.as_u16(), "crawled");
+            let content_type = self.detect_response_type(&response).await;
+            let via = url.path().to_string();
+
+            let mut targets: Vec<(String, String)> = response.headers().get_all(reqwest::header::LINK).iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(link_targets)
+                .map(|target| ("GET".to_string(), target))
+                .collect();
+            if status.is_redirection() {
+                if let Some(location) = response.headers().get(reqwest::header::LOCATION).and_then(|v| v.to_str().ok()) {
+                    targets.push(("GET".to_string(), location.to_string()));
+                }
+            }
+
+            let is_json = content_type.as_deref().is_some_and(|t| t.contains("json"));
+            if status.is_success() && is_json {
+                let endpoint = ApiEndpoint {
+                    path: url.path().to_string(),
+                    method: "GET".to_string(),
+                    description: Some("Discovered by crawling: answers JSON".to_string()),
+                    parameters: url.query_pairs().map(|(name, _)| name.to_string()).collect(),
+                    response_type: content_type.clone(),
+                };
+                if add_endpoint(api_map, endpoint) {
+                    println!("✅ Discovered endpoint: GET {} (JSON)", url.path());
+                }
+            } else if status.is_success() && content_type.as_deref().is_some_and(|t| t.contains("html")) {
+                let body = response.text().await.unwrap_or_default();
+                targets.extend(html_targets(&body));
+            }
+
+            for (method, target) in targets {
+                if let Ok(target) = url.join(&target) {
+                    self.found(api_map, &mut queue, &mut seen, &base, target, &method, depth + 1, &via);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record a link target that looks like an API path and queue it for crawling.
+    /// Only GET targets are fetched; forms are never submitted.
+    #[allow(clippy::too_many_arguments)]
+    fn found(
+        &self,
+        api_map: &mut ApiMap,
+        queue: &mut VecDeque<(Url, usize)>,
+        seen: &mut HashSet<String>,
+        base: &Url,
+        mut url: Url,
+        method: &str,
+        depth: usize,
+        via: &str,
+    ) {
+        if url.origin() != base.origin() {
+            return;
+        }
+        url.set_fragment(None);
+        if url.path().contains("/api/") {
+            let endpoint = ApiEndpoint {
+                path: url.path().to_string(),
+                method: method.to_string(),
+                description: Some(format!("Linked from {}", via)),
+                parameters: url.query_pairs().map(|(name, _)| name.to_string()).collect(),
+                response_type: None,
+            };
+            if add_endpoint(api_map, endpoint) {
+                println!("✅ Discovered endpoint: {} {} (linked from {})", method, url.path(), via);
+            }
+        }
+        let asset = url.path().rsplit_once('.').is_some_and(|(_, ext)| {
+            ["css", "js", "png", "jpg", "jpeg", "gif", "svg", "ico", "woff", "woff2", "pdf"].contains(&ext.to_ascii_lowercase().as_str())
+        });
+        if method == "GET" && !asset && depth <= self.options.depth && seen.insert(page_key(&url)) {
+            queue.push_back((url, depth));
+        }
+    }
+
     async fn detect_response_type(&self, response: &reqwest::Response) -> Option<String> {
         if let Some(content_type) = response.headers().get("content-type") {
             content_type.to_str().ok().map(|s| s.to_string())
@@ -222,12 +445,18 @@ impl DiscoverCommand {
     }
 
     async fn analyze_endpoints_with_ai(&self, api_map: &mut ApiMap) -> Result<(), Box<dyn std::error::Error>> {
-        let api_key = self.config.anthropic_api_key.as_ref()
-            .ok_or("API key not configured for AI analysis")?;
+        if self.config.is_offline() {
+            print_ai_skipped("AI endpoint analysis");
+            return Ok(());
+        }
 
-        let ai_client = ClientBuilder::default()
-            .api_key(api_key.clone())
-            .build()?;
+        let ai_client = match ai::optional_client(&self.config, "discover") {
+            Ok(ai_client) => ai_client,
+            Err(e) => {
+                print_ai_failed("AI endpoint analysis", e.as_ref());
+                return Ok(());
+            }
+        };
 
         let endpoints_json = serde_json::to_string_pretty(&api_map.endpoints)?;
 
@@ -250,20 +479,10 @@ Be specific and actionable in your recommendations.",
             api_map.base_url, endpoints_json
         );
 
-        let response = ai_client.messages(MessagesRequestBuilder::default()
-            .messages(vec![Message {
-                role: Role::User,
-                content: vec![ContentBlock::Text { text: prompt }],
-            }])
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(1500_usize)
-            .build()?
-        ).await?;
-
-        if let Some(ContentBlock::Text { text }) = response.content.first() {
-            println!("\n🤖 AI Analysis:");
-            println!("{}", text);
-        }
+        let text = ai_client.ask(&prompt).await?;
+
+        println!("\n🤖 AI Analysis:");
+        println!("{}", text);
 
         Ok(())
     }
@@ -303,13 +522,217 @@ Be specific and actionable in your recommendations.",
         Ok(())
     }
 
-    /// Generate flow from discovered endpoints
-    pub async fn generate_flow(&self, api_map: &ApiMap, flow_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    /// Save the discovered endpoints as the flow `<flows_dir>/<flow_name>.yaml`. An existing
+    /// flow is extended: its operations and mock data stay, discovered ones fill the gaps.
+    pub async fn generate_flow(&self, api_map: &ApiMap, flows_dir: &Path, flow_name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
         println!("📄 Generating flow '{}' from discovered endpoints...", flow_name);
-        
-        // This would integrate with the existing flow system
-        println!("✅ Flow '{}' generated with {} endpoints", flow_name, api_map.endpoints.len());
-        
-        Ok(())
+
+        std::fs::create_dir_all(flows_dir)?;
+        let path = flows_dir.join(format!("{}.yaml", flow_name));
+        let discovered = to_openapi(api_map, flow_name);
+        let spec = if path.exists() {
+            let mut spec = OpenAPISpec::load(&path)?;
+            merge_spec(&mut spec, discovered);
+            spec
+        } else {
+            discovered
+        };
+        spec.save(&path)?;
+
+        let endpoints: usize = spec.paths.values().map(|item| item.operations().count()).sum();
+        println!("✅ Flow '{}' saved to {} with {} endpoints", flow_name, style(path.display()).green(), endpoints);
+        Ok(path)
+    }
+}
+
+fn page_key(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.to_string()
+}
+
+/// Targets of a `Link` header: `<https://api.example.com/items?page=2>; rel="next", </docs>; rel="help"`.
+pub fn link_targets(header: &str) -> Vec<String> {
+    header.split(',')
+        .filter_map(|link| {
+            let start = link.find('<')?;
+            let end = link[start..].find('>')?;
+            Some(link[start + 1..start + end].trim().to_string())
+        })
+        .collect()
+}
+
+/// `<loc>` entries of a sitemap.
+pub fn sitemap_urls(xml: &str) -> Vec<String> {
+    xml.split("<loc>").skip(1)
+        .filter_map(|rest| rest.split_once("</loc>"))
+        .map(|(loc, _)| loc.trim().replace("&amp;", "&"))
+        .collect()
+}
+
+/// `(method, target)` for every `<a href>` and `<form action>` in an HTML page.
+pub fn html_targets(html: &str) -> Vec<(String, String)> {
+    let lower = html.to_ascii_lowercase();
+    let mut targets = Vec::new();
+    let mut position = 0;
+    while let Some(offset) = lower[position..].find('<') {
+        let start = position + offset + 1;
+        let end = lower[start..].find('>').map_or(lower.len(), |i| start + i);
+        let tag = &html[start..end];
+        position = end;
+
+        let name_end = tag.find(|c: char| c.is_ascii_whitespace()).unwrap_or(tag.len());
+        let attributes = html_attributes(&tag[name_end..]);
+        let attribute = |name: &str| attributes.iter().find(|(n, _)| n == name).map(|(_, v)| v.clone());
+        let target = match tag[..name_end].to_ascii_lowercase().as_str() {
+            "a" => attribute("href").map(|href| ("GET".to_string(), href)),
+            "form" => attribute("action").map(|action| {
+                (attribute("method").unwrap_or_else(|| "GET".to_string()).to_uppercase(), action)
+            }),
+            _ => None,
+        };
+        if let Some((method, target)) = target {
+            let skipped = ["#", "javascript:", "mailto:", "tel:"].iter().any(|p| target.starts_with(p));
+            if !target.is_empty() && !skipped {
+                targets.push((method, target.replace("&amp;", "&")));
+            }
+        }
+    }
+    targets
+}
+
+/// Attribute names (lowercased) and values of a tag, quoted or not.
+fn html_attributes(text: &str) -> Vec<(String, String)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut attributes = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        while i < chars.len() && (chars[i].is_whitespace() || chars[i] == '/') {
+            i += 1;
+        }
+        let name_start = i;
+        while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '=' && chars[i] != '/' {
+            i += 1;
+        }
+        let name: String = chars[name_start..i].iter().collect::<String>().to_ascii_lowercase();
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        let mut value = String::new();
+        if i < chars.len() && chars[i] == '=' {
+            i += 1;
+            while i < chars.len() && chars[i].is_whitespace() {
+                i += 1;
+            }
+            match chars.get(i) {
+                Some(&quote) if quote == '"' || quote == '\'' => {
+                    i += 1;
+                    while i < chars.len() && chars[i] != quote {
+                        value.push(chars[i]);
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                _ => {
+                    while i < chars.len() && !chars[i].is_whitespace() {
+                        value.push(chars[i]);
+                        i += 1;
+                    }
+                }
+            }
+        }
+        if !name.is_empty() {
+            attributes.push((name, value.trim().to_string()));
+        }
+    }
+    attributes
+}
+
+/// The discovered API as a flow: one operation per endpoint, the base URL as server.
+pub fn to_openapi(api_map: &ApiMap, name: &str) -> OpenAPISpec {
+    let mut spec = OpenAPISpec::new(name);
+    spec.info.description = Some(format!("Discovered from {}", api_map.base_url));
+    spec.servers = vec![Server {
+        url: api_map.base_url.trim_end_matches('/').to_string(),
+        description: Some("Discovered API server".to_string()),
+        ..Default::default()
+    }];
+
+    for endpoint in &api_map.endpoints {
+        let Some(slot) = spec.paths.entry(endpoint.path.clone()).or_default().operation_mut(&endpoint.method) else {
+            continue;
+        };
+        let parameters = spec_parameters(api_map.spec.as_ref(), endpoint).unwrap_or_else(|| {
+            endpoint.parameters.iter()
+                .map(|name| Parameter {
+                    name: name.clone(),
+                    in_: "query".to_string(),
+                    schema: Schema { schema_type: "string".into(), ..Default::default() },
+                    ..Default::default()
+                })
+                .collect()
+        });
+        let content = endpoint.response_type.as_deref().map(|content_type| {
+            let media_type = content_type.split(';').next().unwrap_or(content_type).trim().to_string();
+            HashMap::from([(media_type, MediaType::default())])
+        });
+        *slot = Some(Operation {
+            summary: Some(format!("{} {}", endpoint.method, endpoint.path)),
+            description: endpoint.description.clone(),
+            parameters: (!parameters.is_empty()).then_some(parameters),
+            responses: HashMap::from([("200".to_string(), Response {
+                description: "Successful response".to_string(),
+                content,
+                ..Default::default()
+            })]),
+            ..Default::default()
+        });
+    }
+    spec
+}
+
+/// Path- and operation-level parameters the OpenAPI document declares for `endpoint`.
+fn spec_parameters(spec: Option<&Value>, endpoint: &ApiEndpoint) -> Option<Vec<Parameter>> {
+    let path_item = spec?.get("paths")?.get(&endpoint.path)?;
+    let operation = path_item.get(endpoint.method.to_lowercase())?;
+    let parameters = [path_item.get("parameters"), operation.get("parameters")]
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_array)
+        .flatten()
+        .filter_map(|parameter| serde_json::from_value(parameter.clone()).ok())
+        .collect();
+    Some(parameters)
+}
+
+/// Add what `discovered` knows to `spec` without overwriting it: new servers and operations
+/// are added, existing operations only get the fields they lack.
+pub fn merge_spec(spec: &mut OpenAPISpec, discovered: OpenAPISpec) {
+    for server in discovered.servers {
+        if !spec.servers.iter().any(|s| s.url == server.url) {
+            spec.servers.push(server);
+        }
     }
-}
\ No newline at end of file
+    for (path, mut item) in discovered.paths {
+        let existing = spec.paths.entry(path).or_default();
+        for method in ["GET", "POST", "PUT", "DELETE", "PATCH"] {
+            let Some(new) = item.operation_mut(method).and_then(Option::take) else {
+                continue;
+            };
+            let Some(slot) = existing.operation_mut(method) else {
+                continue;
+            };
+            match slot {
+                None => *slot = Some(new),
+                Some(operation) => {
+                    operation.summary = operation.summary.take().or(new.summary);
+                    operation.description = operation.description.take().or(new.description);
+                    operation.parameters = operation.parameters.take().or(new.parameters);
+                    if operation.responses.is_empty() {
+                        operation.responses = new.responses;
+                    }
+                }
+            }
+        }
+    }
+}
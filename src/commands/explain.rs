@@ -1,13 +1,101 @@
-use anthropic::{
-    client::ClientBuilder,
-    types::{Message, ContentBlock, MessagesRequestBuilder, Role},
-};
+use crate::ai;
 use crate::config::Config;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
 
 pub struct ExplainCommand {
     config: Config,
 }
 
+/// What `explain` should explain.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExplainTarget {
+    /// The Nth most recent response of the session, 1 being the last one.
+    Last(usize),
+    /// A response saved to a file, given as `@path`.
+    File(PathBuf),
+    Status(u16),
+}
+
+/// Parsed `explain` arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainArgs {
+    pub target: ExplainTarget,
+    pub context: Option<String>,
+}
+
+impl ExplainArgs {
+    /// `explain [@FILE | --status CODE | --last N] [--context TEXT]`; no target means the last response.
+    pub fn parse(args: &[&str]) -> Result<Self, String> {
+        let mut target = None;
+        let mut context = None;
+        let mut i = 1; // Skip "explain"
+        while i < args.len() {
+            let arg = args[i];
+            let value = || args.get(i + 1).copied().ok_or_else(|| format!("Value required after {}", arg));
+            let next = match arg {
+                "--status" => {
+                    let value = value()?;
+                    let code = value.parse().ok().filter(|code| (100..=599).contains(code))
+                        .ok_or_else(|| format!("--status needs an HTTP status code, got '{}'", value))?;
+                    Some(ExplainTarget::Status(code))
+                }
+                "--last" => {
+                    let value = value()?;
+                    let n = value.parse().ok().filter(|n| *n > 0)
+                        .ok_or_else(|| format!("--last needs a positive number, got '{}'", value))?;
+                    Some(ExplainTarget::Last(n))
+                }
+                "--context" => {
+                    context = Some(value()?.to_string());
+                    None
+                }
+                file if file.starts_with('@') && file.len() > 1 => {
+                    target = target.or(Some(ExplainTarget::File(PathBuf::from(&file[1..]))));
+                    i += 1;
+                    continue;
+                }
+                other => return Err(format!("Unknown argument for explain: {}", other)),
+            };
+            if next.is_some() {
+                if target.is_some() {
+                    return Err("Give only one of @FILE, --status and --last".to_string());
+                }
+                target = next;
+            }
+            i += 2;
+        }
+        Ok(Self { target: target.unwrap_or(ExplainTarget::Last(1)), context })
+    }
+}
+
+/// What to explain from a saved response file. A `call --meta` document (or `call --json`
+/// output) gives the body, read from its `body_file` when it isn't inline, and a context line
+/// naming the request and status; any other file is explained as it is. A relative
+/// `body_file` is looked up next to the document.
+pub fn saved_response(content: String, dir: &Path) -> Result<(String, Option<String>), String> {
+    let Ok(Value::Object(meta)) = serde_json::from_str::<Value>(&content) else {
+        return Ok((content, None));
+    };
+    let (Some(method), Some(url), Some(status)) = (meta.get("method").and_then(Value::as_str), meta.get("url").and_then(Value::as_str), meta.get("status").and_then(Value::as_u64)) else {
+        return Ok((content, None));
+    };
+    let mut context = format!("Response to {} {} with status {}", method, url, status);
+    if let Some(headers) = meta.get("headers").filter(|headers| headers.as_object().is_some_and(|h| !h.is_empty())) {
+        context.push_str(&format!(", headers {}", headers));
+    }
+    let body = match (meta.get("body_file").and_then(Value::as_str), meta.get("body")) {
+        (Some(file), _) => {
+            let path = dir.join(file);
+            std::fs::read_to_string(&path).map_err(|e| format!("could not read the body file {}: {}", path.display(), e))?
+        }
+        (None, Some(Value::String(body))) => body.clone(),
+        (None, Some(body)) => serde_json::to_string_pretty(body).unwrap_or_default(),
+        (None, None) => String::new(),
+    };
+    Ok((body, Some(context)))
+}
+
 impl ExplainCommand {
     pub fn new(config: Config) -> Self {
         Self { config }
@@ -17,12 +105,7 @@ impl ExplainCommand {
     pub async fn explain_response(&self, response: &str, context: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
         println!("🧠 AI explaining your API response...");
         
-        let api_key = self.config.anthropic_api_key.as_ref()
-            .ok_or("API key not configured. Use 'config api-key' to set it")?;
-
-        let ai_client = ClientBuilder::default()
-            .api_key(api_key.clone())
-            .build()?;
+        let ai_client = ai::client(&self.config, "explain")?;
 
         let context_info = context.unwrap_or("No additional context provided");
         
@@ -41,20 +124,10 @@ impl ExplainCommand {
             context_info, response
         );
 
-        let ai_response = ai_client.messages(MessagesRequestBuilder::default()
-            .messages(vec![Message {
-                role: Role::User,
-                content: vec![ContentBlock::Text { text: prompt }],
-            }])
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(1500_usize)
-            .build()?
-        ).await?;
-
-        if let Some(ContentBlock::Text { text }) = ai_response.content.first() {
-            println!("\n📖 AI Explanation:");
-            println!("{}", text);
-        }
+        let text = ai_client.ask(&prompt).await?;
+
+        println!("\n📖 AI Explanation:");
+        println!("{}", text);
 
         Ok(())
     }
@@ -64,12 +137,7 @@ impl ExplainCommand {
     pub async fn explain_error(&self, error: &str, endpoint: &str) -> Result<(), Box<dyn std::error::Error>> {
         println!("🚨 AI analyzing error...");
         
-        let api_key = self.config.anthropic_api_key.as_ref()
-            .ok_or("API key not configured. Use 'config api-key' to set it")?;
-
-        let ai_client = ClientBuilder::default()
-            .api_key(api_key.clone())
-            .build()?;
+        let ai_client = ai::client(&self.config, "explain")?;
 
         let prompt = format!(
             "You are an expert API troubleshooter. Help debug this API error:\n\n\
@@ -86,35 +154,19 @@ impl ExplainCommand {
             endpoint, error
         );
 
-        let ai_response = ai_client.messages(MessagesRequestBuilder::default()
-            .messages(vec![Message {
-                role: Role::User,
-                content: vec![ContentBlock::Text { text: prompt }],
-            }])
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(1500_usize)
-            .build()?
-        ).await?;
-
-        if let Some(ContentBlock::Text { text }) = ai_response.content.first() {
-            println!("\n🔧 AI Troubleshooting:");
-            println!("{}", text);
-        }
+        let text = ai_client.ask(&prompt).await?;
+
+        println!("\n🔧 AI Troubleshooting:");
+        println!("{}", text);
 
         Ok(())
     }
 
     /// Explain HTTP status codes with context
-    #[allow(dead_code)]
     pub async fn explain_status_code(&self, status_code: u16, context: &str) -> Result<(), Box<dyn std::error::Error>> {
         println!("📊 AI explaining status code {}...", status_code);
         
-        let api_key = self.config.anthropic_api_key.as_ref()
-            .ok_or("API key not configured. Use 'config api-key' to set it")?;
-
-        let ai_client = ClientBuilder::default()
-            .api_key(api_key.clone())
-            .build()?;
+        let ai_client = ai::client(&self.config, "explain")?;
 
         let prompt = format!(
             "Explain HTTP status code {} in the context of this API interaction:\n\n\
@@ -130,20 +182,10 @@ impl ExplainCommand {
             status_code, status_code, context
         );
 
-        let ai_response = ai_client.messages(MessagesRequestBuilder::default()
-            .messages(vec![Message {
-                role: Role::User,
-                content: vec![ContentBlock::Text { text: prompt }],
-            }])
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(800_usize)
-            .build()?
-        ).await?;
-
-        if let Some(ContentBlock::Text { text }) = ai_response.content.first() {
-            println!("\n📚 Status Code Explanation:");
-            println!("{}", text);
-        }
+        let text = ai_client.ask(&prompt).await?;
+
+        println!("\n📚 Status Code Explanation:");
+        println!("{}", text);
 
         Ok(())
     }
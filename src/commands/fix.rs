@@ -1,38 +1,289 @@
-use anthropic::{
-    client::ClientBuilder,
-    types::{Message, ContentBlock, MessagesRequestBuilder, Role},
-};
+use crate::ai;
 use crate::config::Config;
 use crate::commands::call::CallCommand;
-use serde_json::Value;
+use crate::commands::diff::{diff_lines, LineChange};
+use crate::commands::{print_ai_failed, print_ai_skipped};
+use crate::confirm::StepPrompt;
+use crate::flows::{OpenAPISpec, Response};
+use console::style;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Methods that change data, and so should require authentication.
+const WRITE_METHODS: &[&str] = &["POST", "PUT", "PATCH", "DELETE"];
+
+/// Security scheme added when a flow defines none, for the user to replace with the real one.
+const PLACEHOLDER_SCHEME: &str = "bearerAuth";
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FixOptions {
+    pub url: Option<String>,
+    /// Local flow to check as well, and to edit with `--apply`.
+    pub flow: Option<String>,
+    pub apply: bool,
+    /// Apply every change without asking.
+    pub yes: bool,
+}
+
+impl FixOptions {
+    /// Parse `fix [URL] [--flow NAME] [--apply] [--yes]`.
+    pub fn from_args(args: &[&str]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut options = Self::default();
+        // Skip "fix"
+        let mut i = 1;
+        while i < args.len() {
+            match args[i] {
+                "--apply" => options.apply = true,
+                "--yes" | "-y" => options.yes = true,
+                "--flow" => {
+                    let name = args.get(i + 1).ok_or("--flow needs a flow name")?;
+                    options.flow = Some(name.to_string());
+                    i += 1;
+                }
+                flag if flag.starts_with('-') => return Err(format!("Unknown option for fix: {}", flag).into()),
+                url if options.url.is_none() => options.url = Some(url.to_string()),
+                extra => return Err(format!("Unexpected argument for fix: {}", extra).into()),
+            }
+            i += 1;
+        }
+        if options.url.is_none() && options.flow.is_none() {
+            return Err("fix needs a URL, a --flow or both".into());
+        }
+        if options.apply && options.flow.is_none() {
+            return Err("--apply needs --flow <name>: only a local flow can be fixed automatically".into());
+        }
+        Ok(options)
+    }
+}
+
+/// Whether a problem is fixed on the server or in the flow describing it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FixScope {
+    /// Behavior of the running API; `fix` can only advise.
+    Server,
+    /// The local OpenAPI flow; `fix --apply` can edit it.
+    Spec,
+}
+
+/// One change `fix --apply` can make to a flow.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecEdit {
+    /// Document a 4xx response on an operation that has none.
+    ErrorResponse { method: String, path: String, status: String, description: String },
+    /// Require `scheme` on a write operation, declaring it under `components.securitySchemes`
+    /// when the flow has no scheme yet.
+    SecurityPlaceholder { method: String, path: String, scheme: String },
+    /// Fill an empty description.
+    Description { method: String, path: String, text: String },
+}
+
+impl SpecEdit {
+    pub fn operation(&self) -> String {
+        let (SpecEdit::ErrorResponse { method, path, .. }
+        | SpecEdit::SecurityPlaceholder { method, path, .. }
+        | SpecEdit::Description { method, path, .. }) = self;
+        format!("{} {}", method, path)
+    }
+
+    pub fn summary(&self) -> String {
+        match self {
+            SpecEdit::ErrorResponse { status, .. } => format!("Add a {} response to {}", status, self.operation()),
+            SpecEdit::SecurityPlaceholder { scheme, .. } => format!("Require '{}' on {}", scheme, self.operation()),
+            SpecEdit::Description { .. } => format!("Describe {}", self.operation()),
+        }
+    }
+
+    /// Make the change in `spec`. Does nothing when the operation is gone.
+    pub fn apply(&self, spec: &mut OpenAPISpec) {
+        if let SpecEdit::SecurityPlaceholder { scheme, .. } = self {
+            let schemes = spec.extras.entry("components".to_string()).or_insert_with(|| json!({}));
+            if let Some(components) = schemes.as_object_mut() {
+                let schemes = components.entry("securitySchemes").or_insert_with(|| json!({}));
+                if let Some(schemes) = schemes.as_object_mut() {
+                    schemes.entry(scheme.clone()).or_insert_with(|| json!({
+                        "type": "http",
+                        "scheme": "bearer",
+                        "description": "Placeholder added by 'fix --apply'; replace with the API's real scheme",
+                    }));
+                }
+            }
+        }
+
+        let (SpecEdit::ErrorResponse { method, path, .. }
+        | SpecEdit::SecurityPlaceholder { method, path, .. }
+        | SpecEdit::Description { method, path, .. }) = self;
+        let Some(operation) = spec.paths.get_mut(path).and_then(|item| item.operation_mut(method)).and_then(Option::as_mut) else {
+            return;
+        };
+        match self {
+            SpecEdit::ErrorResponse { status, description, .. } => {
+                operation.responses.entry(status.clone())
+                    .or_insert_with(|| Response { description: description.clone(), ..Response::default() });
+            }
+            SpecEdit::SecurityPlaceholder { scheme, .. } => {
+                operation.security = Some(vec![HashMap::from([(scheme.clone(), Vec::new())])]);
+            }
+            SpecEdit::Description { text, .. } => operation.description = Some(text.clone()),
+        }
+    }
+}
+
+/// The edits `fix --apply` can make without help: a 4xx response for operations documenting
+/// none, and a security requirement for write operations that have none, neither of their
+/// own nor flow-wide. An explicit `security: []` marks an operation public and is left alone.
+pub fn spec_edits(spec: &OpenAPISpec) -> Vec<SpecEdit> {
+    let secured_globally = spec.extras.get("security").and_then(Value::as_array).is_some_and(|s| !s.is_empty());
+    let scheme = spec.extras.get("components")
+        .and_then(|c| c.get("securitySchemes"))
+        .and_then(Value::as_object)
+        .and_then(|schemes| schemes.keys().next().cloned())
+        .unwrap_or_else(|| PLACEHOLDER_SCHEME.to_string());
+
+    let mut edits = Vec::new();
+    for (path, method, operation) in sorted_operations(spec) {
+        if !operation.responses.keys().any(|status| status.starts_with('4')) {
+            let (status, description) = if path.contains('{') && operation.request_body.is_none() {
+                ("404", "Not found")
+            } else {
+                ("400", "Invalid request")
+            };
+            edits.push(SpecEdit::ErrorResponse {
+                method: method.to_string(),
+                path: path.to_string(),
+                status: status.to_string(),
+                description: description.to_string(),
+            });
+        }
+        if WRITE_METHODS.contains(&method) && operation.security.is_none() && !secured_globally {
+            edits.push(SpecEdit::SecurityPlaceholder { method: method.to_string(), path: path.to_string(), scheme: scheme.clone() });
+        }
+    }
+    edits
+}
+
+/// Operations with neither a description nor a summary, as `(method, path)`.
+pub fn undescribed_operations(spec: &OpenAPISpec) -> Vec<(String, String)> {
+    sorted_operations(spec)
+        .into_iter()
+        .filter(|(_, _, operation)| {
+            operation.description.as_deref().unwrap_or("").trim().is_empty()
+                && operation.summary.as_deref().unwrap_or("").trim().is_empty()
+        })
+        .map(|(path, method, _)| (method.to_string(), path.to_string()))
+        .collect()
+}
+
+fn sorted_operations(spec: &OpenAPISpec) -> Vec<(&str, &str, &crate::flows::Operation)> {
+    let mut paths: Vec<_> = spec.paths.iter().collect();
+    paths.sort_by_key(|(path, _)| path.as_str());
+    paths.into_iter()
+        .flat_map(|(path, item)| item.operations().map(move |(method, operation)| (path.as_str(), method, operation)))
+        .collect()
+}
+
+/// `spec` as YAML with keys sorted, so two versions diff line by line.
+pub fn spec_yaml(spec: &OpenAPISpec) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(serde_yaml::to_string(&serde_json::to_value(spec)?)?)
+}
+
+/// The lines that differ between `before` and `after`, with two lines of context around
+/// each change.
+pub fn yaml_diff(before: &str, after: &str) -> Vec<String> {
+    const CONTEXT: usize = 2;
+    let changes = diff_lines(before, after);
+    let near_change = |i: usize| {
+        changes[i.saturating_sub(CONTEXT)..changes.len().min(i + CONTEXT + 1)]
+            .iter()
+            .any(|change| !matches!(change, LineChange::Same(_)))
+    };
+    let mut lines = Vec::new();
+    let mut skipped = false;
+    for (i, change) in changes.iter().enumerate() {
+        if !near_change(i) {
+            skipped = true;
+            continue;
+        }
+        if skipped && !lines.is_empty() {
+            lines.push("   ...".to_string());
+        }
+        skipped = false;
+        lines.push(match change {
+            LineChange::Same(line) => format!("   {}", line),
+            LineChange::Removed(line) => format!(" - {}", line),
+            LineChange::Added(line) => format!(" + {}", line),
+        });
+    }
+    lines
+}
 
 pub struct FixCommand {
     config: Config,
+    options: FixOptions,
+    flows_dir: Option<PathBuf>,
 }
 
 impl FixCommand {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        Self { config, options: FixOptions::default(), flows_dir: None }
     }
 
-    /// AI-powered API fixing - automatically detect and suggest fixes
-    pub async fn auto_fix(&self, url: &str) -> Result<(), Box<dyn std::error::Error>> {
-        println!("🔧 AI-powered auto-fix starting for: {}", url);
-        
-        // Step 1: Diagnose the API
-        println!("🔍 Step 1: Diagnosing API issues...");
-        let diagnosis = self.diagnose_api(url).await?;
-        
-        // Step 2: Generate AI-powered fix recommendations
-        println!("🧠 Step 2: AI generating fix recommendations...");
-        let fixes = self.generate_fixes(&diagnosis).await?;
-        
+    pub fn with_options(mut self, options: FixOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_flows_dir(mut self, dir: PathBuf) -> Self {
+        self.flows_dir = Some(dir);
+        self
+    }
+
+    fn flow_path(&self, name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let dir = match &self.flows_dir {
+            Some(dir) => dir.clone(),
+            None => dirs::home_dir().ok_or("Could not find home directory")?.join(".nuts").join("flows"),
+        };
+        Ok(dir.join(format!("{}.yaml", name)))
+    }
+
+    /// AI-powered API fixing - automatically detect and suggest fixes. With `--flow`, the
+    /// flow is checked too, and with `--apply` its fixable issues are fixed, each confirmed
+    /// through `prompt` unless `--yes`.
+    pub async fn auto_fix(&self, prompt: &mut dyn StepPrompt) -> Result<(), Box<dyn std::error::Error>> {
+        let mut fixes = Vec::new();
+
+        if let Some(url) = &self.options.url {
+            println!("🔧 AI-powered auto-fix starting for: {}", url);
+
+            // Step 1: Diagnose the API
+            println!("🔍 Step 1: Diagnosing API issues...");
+            let diagnosis = self.diagnose_api(url).await?;
+
+            // Step 2: Generate AI-powered fix recommendations
+            println!("🧠 Step 2: AI generating fix recommendations...");
+            fixes = self.generate_fixes(&diagnosis).await?;
+        }
+
+        let flow = match &self.options.flow {
+            Some(name) => {
+                let path = self.flow_path(name)?;
+                let spec = OpenAPISpec::load(&path).map_err(|e| format!("could not load flow '{}': {}", name, e))?;
+                println!("📄 Checking flow {}...", style(name).green());
+                fixes.extend(self.spec_fixes(&spec));
+                Some((name, path, spec))
+            }
+            None => None,
+        };
+
         // Step 3: Present fixes to user
         self.present_fixes(&fixes)?;
-        
-        // Step 4: Offer to apply automated fixes
-        self.offer_automated_fixes(url, &fixes).await?;
-        
+
+        // Step 4: Apply or offer the automated fixes
+        match flow {
+            Some((_, path, spec)) if self.options.apply => self.apply_spec_fixes(spec, &path, prompt).await?,
+            _ => self.offer_automated_fixes(&fixes).await?,
+        }
+
         Ok(())
     }
 
@@ -73,10 +324,9 @@ impl FixCommand {
                 }
                 
                 // Try to parse as JSON
-                if let Err(_) = serde_json::from_str::<Value>(&response) {
-                    if !response.trim().starts_with('<') { // Not HTML
-                        diagnosis.response_issues.push("Invalid JSON response".to_string());
-                    }
+                if serde_json::from_str::<Value>(&response).is_err()
+                    && !response.trim().starts_with('<') { // Not HTML
+                    diagnosis.response_issues.push("Invalid JSON response".to_string());
                 }
             }
             Err(e) => {
@@ -92,7 +342,7 @@ impl FixCommand {
         // Test common problematic endpoints
         for test_path in &["/admin", "/.env", "/debug", "/test"] {
             let test_url = format!("{}{}", url.trim_end_matches('/'), test_path);
-            if let Ok(_) = call_command.execute_with_response(&["GET", &test_url]).await {
+            if call_command.execute_with_response(&["GET", &test_url]).await.is_ok() {
                 diagnosis.security_issues.push(format!("Exposed sensitive endpoint: {}", test_path));
             }
         }
@@ -101,12 +351,18 @@ impl FixCommand {
     }
 
     async fn generate_fixes(&self, diagnosis: &ApiDiagnosis) -> Result<Vec<Fix>, Box<dyn std::error::Error>> {
-        let api_key = self.config.anthropic_api_key.as_ref()
-            .ok_or("API key not configured for AI fixes")?;
+        if self.config.is_offline() {
+            print_ai_skipped("AI fix recommendations");
+            return Ok(Self::local_fixes(diagnosis));
+        }
 
-        let ai_client = ClientBuilder::default()
-            .api_key(api_key.clone())
-            .build()?;
+        let ai_client = match ai::optional_client(&self.config, "fix") {
+            Ok(ai_client) => ai_client,
+            Err(e) => {
+                print_ai_failed("AI fix recommendations", e.as_ref());
+                return Ok(Self::local_fixes(diagnosis));
+            }
+        };
 
         let diagnosis_json = serde_json::json!({
             "url": diagnosis.url,
@@ -131,126 +387,312 @@ impl FixCommand {
             serde_json::to_string_pretty(&diagnosis_json)?
         );
 
-        let response = ai_client.messages(MessagesRequestBuilder::default()
-            .messages(vec![Message {
-                role: Role::User,
-                content: vec![ContentBlock::Text { text: prompt }],
-            }])
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(2000_usize)
-            .build()?
-        ).await?;
+        let text = match ai_client.ask(&prompt).await {
+            Ok(text) => text,
+            Err(e) => {
+                print_ai_failed("AI fix recommendations", e.as_ref());
+                return Ok(Self::local_fixes(diagnosis));
+            }
+        };
 
         let mut fixes = Vec::new();
 
-        if let Some(ContentBlock::Text { text }) = response.content.first() {
-            // Try to parse AI response as JSON
-            if let Ok(ai_fixes) = serde_json::from_str::<Value>(text) {
-                if let Some(fixes_array) = ai_fixes.as_array() {
-                    for fix_value in fixes_array {
-                        let fix = Fix {
-                            issue: fix_value.get("issue")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("Unknown issue")
-                                .to_string(),
-                            severity: fix_value.get("severity")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("medium")
-                                .to_string(),
-                            solution: fix_value.get("fix")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("Manual investigation needed")
-                                .to_string(),
-                            automated: fix_value.get("automated")
-                                .and_then(|v| v.as_bool())
-                                .unwrap_or(false),
-                            code_example: fix_value.get("code")
-                                .and_then(|v| v.as_str())
-                                .map(|s| s.to_string()),
-                            impact: fix_value.get("impact")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("Unknown impact")
-                                .to_string(),
-                        };
-                        fixes.push(fix);
-                    }
+        // Try to parse AI response as JSON
+        if let Ok(ai_fixes) = serde_json::from_str::<Value>(&text) {
+            if let Some(fixes_array) = ai_fixes.as_array() {
+                for fix_value in fixes_array {
+                    let fix = Fix {
+                        issue: fix_value.get("issue")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("Unknown issue")
+                            .to_string(),
+                        severity: fix_value.get("severity")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("medium")
+                            .to_string(),
+                        solution: fix_value.get("fix")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("Manual investigation needed")
+                            .to_string(),
+                        automated: fix_value.get("automated")
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or(false),
+                        code_example: fix_value.get("code")
+                            .and_then(|v| v.as_str())
+                            .map(|s| s.to_string()),
+                        impact: fix_value.get("impact")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("Unknown impact")
+                            .to_string(),
+                        scope: FixScope::Server,
+                    };
+                    fixes.push(fix);
                 }
             }
         }
 
-        // Add some fallback fixes if AI parsing failed
+        // Fall back to the local diagnostics if AI parsing failed
         if fixes.is_empty() {
-            if !diagnosis.connectivity_issues.is_empty() {
-                fixes.push(Fix {
-                    issue: "Connectivity problems detected".to_string(),
+            fixes = Self::local_fixes(diagnosis);
+        }
+
+        Ok(fixes)
+    }
+
+    /// Fixes derived straight from the diagnosis, used offline or when the AI reply can't be parsed.
+    fn local_fixes(diagnosis: &ApiDiagnosis) -> Vec<Fix> {
+        let mut fixes = Vec::new();
+
+        if !diagnosis.connectivity_issues.is_empty() {
+            fixes.push(Fix {
+                issue: "Connectivity problems detected".to_string(),
+                severity: "high".to_string(),
+                solution: "Check network connectivity and DNS resolution".to_string(),
+                automated: false,
+                code_example: None,
+                impact: "API is unreachable".to_string(),
+                scope: FixScope::Server,
+            });
+        }
+
+        if !diagnosis.security_issues.is_empty() {
+            fixes.push(Fix {
+                issue: "Security vulnerabilities found".to_string(),
+                severity: "critical".to_string(),
+                solution: "Implement HTTPS and secure endpoint configurations".to_string(),
+                automated: false,
+                code_example: Some("Use https:// URLs and disable debug endpoints".to_string()),
+                impact: "Data exposure and security breaches".to_string(),
+                scope: FixScope::Server,
+            });
+        }
+
+        if !diagnosis.performance_issues.is_empty() {
+            fixes.push(Fix {
+                issue: format!("Slow responses ({}ms)", diagnosis.response_time_ms),
+                severity: "medium".to_string(),
+                solution: "Profile the endpoint, add caching and check database queries".to_string(),
+                automated: false,
+                code_example: None,
+                impact: "Poor user experience and timeouts under load".to_string(),
+                scope: FixScope::Server,
+            });
+        }
+
+        for issue in &diagnosis.response_issues {
+            fixes.push(Fix {
+                issue: issue.clone(),
+                severity: "low".to_string(),
+                solution: "Return a well-formed JSON body with an appropriate status code".to_string(),
+                automated: false,
+                code_example: None,
+                impact: "Clients may fail to parse the response".to_string(),
+                scope: FixScope::Server,
+            });
+        }
+
+        fixes
+    }
+
+    /// The flow's fixable issues as fixes.
+    fn spec_fixes(&self, spec: &OpenAPISpec) -> Vec<Fix> {
+        let mut fixes: Vec<Fix> = spec_edits(spec)
+            .into_iter()
+            .map(|edit| match &edit {
+                SpecEdit::ErrorResponse { status, description, .. } => Fix {
+                    issue: format!("{} documents no 4xx response", edit.operation()),
+                    severity: "low".to_string(),
+                    solution: "Document the error responses clients have to handle".to_string(),
+                    automated: true,
+                    code_example: Some(format!("responses: {{ '{}': {{ description: {} }} }}", status, description)),
+                    impact: "Clients and generated SDKs won't expect errors".to_string(),
+                    scope: FixScope::Spec,
+                },
+                SpecEdit::SecurityPlaceholder { scheme, .. } => Fix {
+                    issue: format!("{} changes data but requires no authentication", edit.operation()),
                     severity: "high".to_string(),
-                    solution: "Check network connectivity and DNS resolution".to_string(),
-                    automated: false,
-                    code_example: None,
-                    impact: "API is unreachable".to_string(),
-                });
+                    solution: format!("Declare the security requirement ('{}' as a placeholder)", scheme),
+                    automated: true,
+                    code_example: Some(format!("security: [{{ {}: [] }}]", scheme)),
+                    impact: "Readers of the spec can't tell whether writes are protected".to_string(),
+                    scope: FixScope::Spec,
+                },
+                SpecEdit::Description { .. } => unreachable!("spec_edits never describes operations"),
+            })
+            .collect();
+        for (method, path) in undescribed_operations(spec) {
+            fixes.push(Fix {
+                issue: format!("{} {} has no description", method, path),
+                severity: "low".to_string(),
+                solution: "Describe what the operation does".to_string(),
+                automated: !self.config.is_offline(),
+                code_example: None,
+                impact: "Generated docs and SDKs leave the operation unexplained".to_string(),
+                scope: FixScope::Spec,
+            });
+        }
+        fixes
+    }
+
+    /// Descriptions for the flow's undescribed operations, suggested by the AI.
+    async fn suggest_descriptions(&self, spec: &OpenAPISpec) -> Vec<SpecEdit> {
+        let missing = undescribed_operations(spec);
+        if missing.is_empty() {
+            return Vec::new();
+        }
+        if self.config.is_offline() {
+            print_ai_skipped("AI descriptions");
+            return Vec::new();
+        }
+        let ai_client = match ai::optional_client(&self.config, "fix") {
+            Ok(ai_client) => ai_client,
+            Err(e) => {
+                print_ai_failed("AI descriptions", e.as_ref());
+                return Vec::new();
             }
-            
-            if !diagnosis.security_issues.is_empty() {
-                fixes.push(Fix {
-                    issue: "Security vulnerabilities found".to_string(),
-                    severity: "critical".to_string(),
-                    solution: "Implement HTTPS and secure endpoint configurations".to_string(),
-                    automated: false,
-                    code_example: Some("Use https:// URLs and disable debug endpoints".to_string()),
-                    impact: "Data exposure and security breaches".to_string(),
-                });
+        };
+
+        let operations: Vec<String> = missing.iter().map(|(method, path)| format!("{} {}", method, path)).collect();
+        let prompt = format!(
+            "Write a one-sentence description for each operation of the API '{}'.\n\n\
+            Operations:\n{}\n\n\
+            Return only a JSON object mapping each operation, exactly as written above, to its description.",
+            spec.info.title,
+            operations.join("\n")
+        );
+        let text = match ai_client.ask(&prompt).await {
+            Ok(text) => text,
+            Err(e) => {
+                print_ai_failed("AI descriptions", e.as_ref());
+                return Vec::new();
             }
+        };
+        let json = match (text.find('{'), text.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &text[start..=end],
+            _ => text.as_str(),
+        };
+        let Ok(Value::Object(descriptions)) = serde_json::from_str::<Value>(json) else {
+            println!("{} AI descriptions skipped: the answer wasn't a JSON object", style("⏭️").dim());
+            return Vec::new();
+        };
+
+        missing
+            .into_iter()
+            .filter_map(|(method, path)| {
+                let text = descriptions.get(&format!("{} {}", method, path))?.as_str()?.trim().to_string();
+                (!text.is_empty()).then_some(SpecEdit::Description { method, path, text })
+            })
+            .collect()
+    }
+
+    /// Show each fixable change to the flow as a YAML diff and make the ones confirmed
+    /// (all of them with `--yes`), then save the flow.
+    async fn apply_spec_fixes(&self, mut spec: OpenAPISpec, path: &PathBuf, prompt: &mut dyn StepPrompt) -> Result<(), Box<dyn std::error::Error>> {
+        let mut edits = spec_edits(&spec);
+        edits.extend(self.suggest_descriptions(&spec).await);
+        if edits.is_empty() {
+            println!("\n✅ Nothing in the flow to fix automatically");
+            return Ok(());
         }
 
-        Ok(fixes)
+        println!("\n🛠️  {} change(s) to {}", edits.len(), path.display());
+        let mut applied = 0;
+        for (i, edit) in edits.iter().enumerate() {
+            // Diff a copy, so a declined change leaves nothing behind
+            let before = spec_yaml(&spec)?;
+            let mut candidate: OpenAPISpec = serde_json::from_value(serde_json::to_value(&spec)?)?;
+            edit.apply(&mut candidate);
+            let after = spec_yaml(&candidate)?;
+
+            println!("\n{} {}", style(format!("{}/{}", i + 1, edits.len())).dim(), style(edit.summary()).bold());
+            for line in yaml_diff(&before, &after) {
+                match line.chars().nth(1) {
+                    Some('+') => println!("{}", style(line).green()),
+                    Some('-') => println!("{}", style(line).red()),
+                    _ => println!("{}", style(line).dim()),
+                }
+            }
+
+            let accepted = self.options.yes || {
+                let answer = prompt.read("Apply this change? (y/N): ", "").unwrap_or_default();
+                matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+            };
+            if accepted {
+                edit.apply(&mut spec);
+                applied += 1;
+            } else {
+                println!("⏭️  Skipped");
+            }
+        }
+
+        if applied > 0 {
+            spec.save(path)?;
+        }
+        println!("\n✅ Applied {} of {} change(s) to {}", applied, edits.len(), path.display());
+        Ok(())
     }
 
     fn present_fixes(&self, fixes: &[Fix]) -> Result<(), Box<dyn std::error::Error>> {
         println!("\n🔧 AI DIAGNOSTIC RESULTS");
         println!("═══════════════════════════");
-        
-        for (i, fix) in fixes.iter().enumerate() {
-            let severity_emoji = match fix.severity.as_str() {
-                "critical" => "🚨",
-                "high" => "⚠️",
-                "medium" => "🟡",
-                "low" => "ℹ️",
-                _ => "🔍",
-            };
-            
-            println!("\n{} {}. {} ({})", severity_emoji, i + 1, fix.issue, fix.severity.to_uppercase());
-            println!("   💡 Solution: {}", fix.solution);
-            println!("   📈 Impact: {}", fix.impact);
-            
-            if let Some(code) = &fix.code_example {
-                println!("   📝 Example: {}", code);
+
+        let sections = [
+            (FixScope::Server, "🖥️  Server-side (fix on the server)"),
+            (FixScope::Spec, "📄 Spec-side (fix in the flow)"),
+        ];
+        let mut number = 0;
+        for (scope, title) in sections {
+            if !fixes.iter().any(|fix| fix.scope == scope) {
+                continue;
             }
-            
-            if fix.automated {
-                println!("   🤖 Can be auto-fixed: Yes");
+            println!("\n{}", style(title).bold());
+            for fix in fixes.iter().filter(|fix| fix.scope == scope) {
+                number += 1;
+                let severity_emoji = match fix.severity.as_str() {
+                    "critical" => "🚨",
+                    "high" => "⚠️",
+                    "medium" => "🟡",
+                    "low" => "ℹ️",
+                    _ => "🔍",
+                };
+
+                println!("\n{} {}. {} ({})", severity_emoji, number, fix.issue, fix.severity.to_uppercase());
+                println!("   💡 Solution: {}", fix.solution);
+                println!("   📈 Impact: {}", fix.impact);
+
+                if let Some(code) = &fix.code_example {
+                    println!("   📝 Example: {}", code);
+                }
+
+                if fix.automated {
+                    println!("   🤖 Can be auto-fixed: Yes");
+                }
             }
         }
-        
+
+        if fixes.is_empty() {
+            println!("\n✅ No issues found");
+        }
+
         Ok(())
     }
 
-    async fn offer_automated_fixes(&self, url: &str, fixes: &[Fix]) -> Result<(), Box<dyn std::error::Error>> {
-        let automated_fixes: Vec<&Fix> = fixes.iter().filter(|f| f.automated).collect();
-        
-        if !automated_fixes.is_empty() {
-            println!("\n🤖 Available automated fixes:");
-            for fix in &automated_fixes {
-                println!("   • {}", fix.issue);
-            }
-            
-            println!("\n💡 Manual fixes required for other issues.");
-            println!("🚀 Consider using 'security {}' for detailed security analysis.", url);
-        } else {
-            println!("\n📋 All fixes require manual intervention.");
+    async fn offer_automated_fixes(&self, fixes: &[Fix]) -> Result<(), Box<dyn std::error::Error>> {
+        let spec_fixes = fixes.iter().filter(|f| f.scope == FixScope::Spec && f.automated).count();
+        let server_fixes = fixes.iter().filter(|f| f.scope == FixScope::Server).count();
+
+        if let (Some(flow), true) = (&self.options.flow, spec_fixes > 0) {
+            println!("\n🤖 {} spec issue(s) can be fixed automatically:", spec_fixes);
+            println!("   {}", style(format!("fix --flow {} --apply", flow)).cyan());
+        }
+        if server_fixes > 0 {
+            println!("\n📋 Server-side issues need changes to the API itself.");
             println!("💡 Use the provided solutions and code examples above.");
+            if let Some(url) = &self.options.url {
+                println!("🚀 Consider using 'security {}' for detailed security analysis.", url);
+            }
         }
-        
+
         Ok(())
     }
 }
@@ -275,4 +717,5 @@ struct Fix {
     automated: bool,
     code_example: Option<String>,
     impact: String,
+    scope: FixScope,
 }
\ No newline at end of file
@@ -1,29 +1,334 @@
-use anthropic::{
-    client::ClientBuilder,
-    types::{Message, ContentBlock, MessagesRequestBuilder, Role},
-};
+use crate::ai::{self, AiClient, AiMessage};
+use crate::commands::batch::BatchResult;
+use crate::commands::call::{CallCommand, CallOptions};
 use crate::config::Config;
+use crate::flows::validate;
+use crate::flows::{CollectionManager, MediaType, OpenAPISpec, Operation, Schema};
+use crate::services::faker::Faker;
+use crate::units::parse_duration;
+use console::style;
+use futures_util::stream::{self, StreamExt};
 use serde_json::Value;
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Where generated records go besides the terminal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerateOptions {
+    pub data_type: String,
+    pub count: usize,
+    /// `--post URL`: send each record as its own POST.
+    pub post: Option<String>,
+    /// Pause after each POST before the same slot sends the next.
+    pub delay: Duration,
+    /// POSTs in flight at once.
+    pub concurrency: usize,
+    /// `--into-flow FLOW:/path`: store the records as that operation's mock examples.
+    pub into_flow: Option<(String, String)>,
+    /// `--local`: use the built-in generator even when an API key is configured.
+    pub local: bool,
+    /// `--seed N`: repeatable local records; implies `--local`.
+    pub seed: Option<u64>,
+    /// `--model NAME`: ask this model instead of the configured one.
+    pub model: Option<String>,
+    /// `for FLOW:METHOD /path`: request bodies for that operation, following its schema.
+    pub operation: Option<(String, String, String)>,
+    /// `--save`: store the records as the mock examples of `operation`.
+    pub save: bool,
+}
+
+impl GenerateOptions {
+    /// `generate TYPE [COUNT] [--post URL] [--delay 200ms] [--concurrency N] [--into-flow FLOW:/path] [--local] [--seed N] [--model NAME]`
+    /// or `generate for FLOW:METHOD /path [--count N] [--save] [--post URL ...] [--model NAME]`
+    pub fn from_args(args: &[&str]) -> Result<Self, Box<dyn Error>> {
+        if args.get(1) == Some(&"for") {
+            return Self::for_operation(args);
+        }
+        let data_type = args.get(1).filter(|arg| !arg.starts_with("--")).ok_or("generate needs a data type, e.g. users")?;
+        let mut options = Self {
+            data_type: data_type.to_string(),
+            count: 5,
+            post: None,
+            delay: Duration::ZERO,
+            concurrency: 1,
+            into_flow: None,
+            local: false,
+            seed: None,
+            model: None,
+            operation: None,
+            save: false,
+        };
+        let mut i = 2; // Skip "generate TYPE"
+        if let Some(count) = args.get(i).filter(|arg| !arg.starts_with("--")) {
+            options.count = parse_count(count)?;
+            i += 1;
+        }
+        options.parse_flags(&args[i..])?;
+        if options.save {
+            return Err("--save stores into the operation of generate for FLOW:METHOD /path; use --into-flow FLOW:/path here".into());
+        }
+        Ok(options)
+    }
+
+    /// `generate for FLOW:METHOD /path ...`: records are named after the path's last literal
+    /// segment, e.g. `users` for `/users/{id}`.
+    fn for_operation(args: &[&str]) -> Result<Self, Box<dyn Error>> {
+        const USAGE: &str = "generate for needs FLOW:METHOD /path, e.g. shop:POST /users";
+        let (flow, method) = args.get(2).and_then(|target| target.split_once(':')).filter(|(flow, method)| !flow.is_empty() && !method.is_empty()).ok_or(USAGE)?;
+        let path = args.get(3).filter(|path| path.starts_with('/')).ok_or(USAGE)?;
+        let data_type = path.split('/').rev().find(|segment| !segment.is_empty() && !segment.starts_with('{')).unwrap_or("records");
+        let mut options = Self::from_args(&["generate", data_type])?;
+        options.operation = Some((flow.to_string(), method.to_uppercase(), path.to_string()));
+        options.parse_flags(&args[4..])?;
+        if options.local {
+            return Err("generate for follows the operation's schema with the AI; it has no --local or --seed".into());
+        }
+        if options.save && options.into_flow.is_some() {
+            return Err("Use either --save or --into-flow".into());
+        }
+        Ok(options)
+    }
+
+    fn parse_flags(&mut self, args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let mut i = 0;
+        while i < args.len() {
+            let switch = match args[i] {
+                "--local" => Some(&mut self.local),
+                "--save" => Some(&mut self.save),
+                _ => None,
+            };
+            if let Some(switch) = switch {
+                *switch = true;
+                i += 1;
+                continue;
+            }
+            let value = args.get(i + 1).copied().ok_or_else(|| format!("Value required after {}", args[i]))?;
+            match args[i] {
+                "--seed" => {
+                    self.seed = Some(value.parse().map_err(|_| format!("--seed must be a number, got '{}'", value))?);
+                    self.local = true;
+                }
+                "--count" => self.count = parse_count(value)?,
+                "--post" => self.post = Some(value.to_string()),
+                "--model" => self.model = Some(value.to_string()),
+                "--delay" => {
+                    self.delay = parse_duration(value).map_err(|e| format!("Invalid --delay: {}", e))?;
+                }
+                "--concurrency" => {
+                    self.concurrency = value.parse().ok().filter(|n| *n > 0)
+                        .ok_or_else(|| format!("--concurrency must be a positive number, got '{}'", value))?;
+                }
+                "--into-flow" => {
+                    let (flow, path) = value.split_once(":/")
+                        .ok_or_else(|| format!("--into-flow needs FLOW:/path, e.g. shop:/users, got '{}'", value))?;
+                    self.into_flow = Some((flow.to_string(), format!("/{}", path)));
+                }
+                other => return Err(format!("Unknown option for generate: {}", other).into()),
+            }
+            i += 2;
+        }
+        Ok(())
+    }
+}
+
+fn parse_count(value: &str) -> Result<usize, String> {
+    value.parse().ok().filter(|n| *n > 0)
+        .ok_or_else(|| format!("The record count must be a positive number, got '{}'", value))
+}
+
+/// The records in an AI answer, which must be exactly one JSON array.
+pub fn parse_records(text: &str) -> Result<Vec<Value>, String> {
+    match serde_json::from_str::<Value>(text.trim()) {
+        Ok(Value::Array(records)) => Ok(records),
+        Ok(other) => Err(format!("expected a JSON array, got {}", crate::flows::validate::type_name(&other))),
+        Err(e) => Err(format!("not valid JSON: {}", e)),
+    }
+}
+
+/// What AI-generated records must look like: a schema from a flow, with the examples the
+/// flow gives for it.
+pub struct RecordShape<'a> {
+    pub schema: &'a Schema,
+    pub examples: Vec<Value>,
+    /// The flow's `components`, for `$ref`s in `schema`.
+    pub components: Option<&'a Value>,
+}
+
+impl<'a> RecordShape<'a> {
+    /// The JSON request body of `operation`.
+    pub fn request(operation: &'a Operation, components: Option<&'a Value>) -> Option<Self> {
+        let media = validate::json_media(&operation.request_body.as_ref()?.content)?;
+        Some(Self::from_media(media, components))
+    }
+
+    /// The JSON body of `operation`'s successful response.
+    pub fn response(operation: &'a Operation, components: Option<&'a Value>) -> Option<Self> {
+        Some(Self::from_media(validate::response_media(operation, 200)?, components))
+    }
+
+    fn from_media(media: &'a MediaType, components: Option<&'a Value>) -> Self {
+        // `example`, then the values of the named `examples`
+        let named = media.extras.get("examples").and_then(Value::as_object).into_iter().flatten()
+            .filter_map(|(_, example)| example.get("value"));
+        Self {
+            schema: &media.schema,
+            examples: media.example.iter().chain(named).cloned().collect(),
+            components,
+        }
+    }
+
+    /// What's wrong with each record, as `record N: $.path: problem`.
+    pub fn violations(&self, records: &[Value]) -> Vec<String> {
+        records.iter().enumerate()
+            .flat_map(|(i, record)| {
+                validate::validate(record, self.schema, self.components).into_iter()
+                    .map(move |violation| format!("record {}: {}", i + 1, violation))
+            })
+            .collect()
+    }
+}
+
+/// The prompt for `count` records of `what`, following `shape` when there is one.
+pub fn records_prompt(what: &str, count: usize, shape: Option<&RecordShape>) -> String {
+    let mut prompt = format!(
+        "Generate {} realistic {} for API testing. Make the data diverse: typical values, edge cases \
+        (empty strings, special characters, long values) and boundary values.\n\n",
+        count, what
+    );
+    if let Some(shape) = shape {
+        prompt.push_str(&format!(
+            "Every record must be valid against this JSON schema:\n{}\n\n",
+            serde_json::to_string_pretty(shape.schema).unwrap_or_default()
+        ));
+        if let Some(components) = shape.components {
+            prompt.push_str(&format!("Its $refs point into these components:\n{}\n\n", components));
+        }
+        if !shape.examples.is_empty() {
+            let examples: Vec<String> = shape.examples.iter().map(Value::to_string).collect();
+            prompt.push_str(&format!("Examples from the API description:\n{}\n\n", examples.join("\n")));
+        }
+    }
+    prompt.push_str(&format!("Return only a JSON array of {} records, no other text.", count));
+    prompt
+}
+
+/// Send `prompt` and read the answer as a JSON array of records matching `shape`. An answer
+/// that isn't one gets one request to fix it, listing what was wrong, before giving up.
+pub async fn ask_for_records(ai_client: &dyn AiClient, prompt: String, count: usize, shape: Option<&RecordShape<'_>>) -> Result<Vec<Value>, Box<dyn Error>> {
+    let mut messages = vec![AiMessage::user(prompt)];
+    for attempt in 0..2 {
+        let text = ai_client.complete(&messages).await?;
+        let problem = match parse_records(&text) {
+            Ok(records) => {
+                let violations = shape.map(|shape| shape.violations(&records)).unwrap_or_default();
+                if violations.is_empty() {
+                    return Ok(records);
+                }
+                if attempt == 1 {
+                    return Err(format!("The AI records still don't match the schema: {}", violations.join("; ")).into());
+                }
+                println!("⚠️  {} schema violation(s) in the AI answer, asking it to fix them...", violations.len());
+                format!("These records don't match the schema:\n{}", violations.join("\n"))
+            }
+            Err(e) if attempt == 0 => {
+                println!("⚠️  The AI answer wasn't a JSON array ({}), asking it to fix that...", e);
+                format!("That is not a valid JSON array: {}.", e)
+            }
+            Err(e) => return Err(format!("The AI answer is still not a JSON array: {}", e).into()),
+        };
+        messages.push(AiMessage::assistant(text));
+        messages.push(AiMessage::user(format!(
+            "{}\nReply with only the corrected JSON array of {} records, no other text.", problem, count
+        )));
+    }
+    unreachable!("the second attempt always returns")
+}
 
 pub struct GenerateCommand {
     config: Config,
+    /// Sends `--post` requests, with the session's defaults, environment and cookies.
+    call: CallCommand,
+    /// Where `--into-flow` looks for flows.
+    flows_dir: Option<PathBuf>,
 }
 
 impl GenerateCommand {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        Self { config, call: CallCommand::new(), flows_dir: None }
     }
 
-    /// Generate realistic test data with AI
+    pub fn with_call_command(mut self, call: CallCommand) -> Self {
+        self.call = call;
+        self
+    }
+
+    pub fn with_flows_dir(mut self, dir: PathBuf) -> Self {
+        self.flows_dir = Some(dir);
+        self
+    }
+
+    /// Generate realistic test data: with AI when a key is configured, locally otherwise
     pub async fn generate(&self, data_type: &str, count: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let args = ["generate", data_type, &count.to_string()];
+        self.execute(&GenerateOptions::from_args(&args)?).await
+    }
+
+    /// Generate records, then save them to a file, POST them and/or store them in a flow.
+    pub async fn execute(&self, options: &GenerateOptions) -> Result<(), Box<dyn std::error::Error>> {
+        let records = if let Some((flow, method, path)) = &options.operation {
+            self.generate_for_endpoint(flow, method, path, options.count).await?
+        } else if options.local || self.config.ai_key().is_none() {
+            if !options.local {
+                println!("💡 No Anthropic API key configured (offline mode), using the built-in generator");
+            }
+            println!("🎲 Generating {} {} records locally...", options.count, options.data_type);
+            Faker::new(options.seed).records(&options.data_type, options.count)
+        } else {
+            self.generate_records(&options.data_type, options.count).await?
+        };
+
+        if options.post.is_none() && options.into_flow.is_none() && !options.save {
+            println!("\n✅ Generated test data:");
+            println!("{}", serde_json::to_string_pretty(&records)?);
+
+            // Save to file for reuse
+            let filename = format!("nuts_generated_{}_{}.json", options.data_type, options.count);
+            std::fs::write(&filename, serde_json::to_string_pretty(&records)?)?;
+            println!("\n💾 Saved to: {}", filename);
+
+            // Show usage examples
+            println!("\n🚀 Usage examples:");
+            println!("  call POST https://api.example.com/{} @{}", options.data_type, filename);
+            println!("  cat {} | jq '.[0]'", filename);
+            return Ok(());
+        }
+
+        if let Some((flow, method, path)) = options.operation.as_ref().filter(|_| options.save) {
+            let operation = self.flows()?.store_operation_examples(flow, Some(method), path, &records, &format!("Generated {} request bodies", options.data_type))?;
+            println!("✅ Stored {} record(s) as mock examples of {} in flow '{}'", records.len(), operation, flow);
+        }
+        if let Some((flow, path)) = &options.into_flow {
+            let operation = self.store_in_flow(flow, path, &records, &options.data_type)?;
+            println!("✅ Stored {} {} record(s) as mock examples of {} in flow '{}'", records.len(), options.data_type, operation, flow);
+        }
+        if let Some(url) = &options.post {
+            let results = self.post_records(&records, url, options).await?;
+            let failed = results.iter().filter(|r| r.is_failure()).count();
+            if failed > 0 {
+                return Err(format!("{} of {} record(s) failed to post", failed, results.len()).into());
+            }
+            println!("✅ {}", style(format!("Posted all {} records", results.len())).green());
+        }
+        Ok(())
+    }
+
+    /// Ask the AI for `count` records of `data_type`.
+    async fn generate_records(&self, data_type: &str, count: usize) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
         println!("🎲 Generating {} realistic {} records...", count, data_type);
         
-        let api_key = self.config.anthropic_api_key.as_ref()
-            .ok_or("API key not configured. Use 'config api-key' to set it")?;
-
-        let ai_client = ClientBuilder::default()
-            .api_key(api_key.clone())
-            .build()?;
+        let ai_client = ai::client(&self.config, "generate")?;
 
         let prompt = format!(
             "Generate {} realistic {} records for API testing. Make the data diverse and realistic.\n\n\
@@ -40,84 +345,109 @@ impl GenerateCommand {
             count, data_type
         );
 
-        let response = ai_client.messages(MessagesRequestBuilder::default()
-            .messages(vec![Message {
-                role: Role::User,
-                content: vec![ContentBlock::Text { text: prompt }],
-            }])
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(2000_usize)
-            .build()?
-        ).await?;
-
-        if let Some(ContentBlock::Text { text }) = response.content.first() {
-            // Try to parse as JSON
-            if let Ok(data) = serde_json::from_str::<Value>(text) {
-                println!("\n✅ Generated test data:");
-                println!("{}", serde_json::to_string_pretty(&data)?);
-                
-                // Save to file for reuse
-                let filename = format!("nuts_generated_{}_{}.json", data_type, count);
-                std::fs::write(&filename, serde_json::to_string_pretty(&data)?)?;
-                println!("\n💾 Saved to: {}", filename);
-                
-                // Show usage examples
-                println!("\n🚀 Usage examples:");
-                println!("  call POST https://api.example.com/{} @{}", data_type, filename);
-                println!("  cat {} | jq '.[0]'", filename);
-                
-            } else {
-                // Fallback - show as text
-                println!("📄 Generated data:\n{}", text);
-            }
-        }
+        ask_for_records(&*ai_client, prompt, count, None).await
+    }
 
-        Ok(())
+    /// Store `records` as the mock examples for `path` in `flow`.
+    pub fn store_in_flow(&self, flow: &str, path: &str, records: &[Value], data_type: &str) -> Result<String, Box<dyn std::error::Error>> {
+        self.flows()?.store_mock_examples(flow, path, records, &format!("Generated {} records", data_type))
     }
 
-    /// Generate data for specific API endpoint testing
-    #[allow(dead_code)]
-    pub async fn generate_for_endpoint(&self, endpoint: &str, method: &str) -> Result<Value, Box<dyn std::error::Error>> {
-        let api_key = self.config.anthropic_api_key.as_ref()
-            .ok_or("API key not configured. Use 'config api-key' to set it")?;
+    fn flows(&self) -> Result<CollectionManager, Box<dyn std::error::Error>> {
+        Ok(CollectionManager::new(self.flows_dir()?, self.config.clone()))
+    }
 
-        let ai_client = ClientBuilder::default()
-            .api_key(api_key.clone())
-            .build()?;
+    fn flows_dir(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        match &self.flows_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(dirs::home_dir().ok_or("Could not find home directory")?.join(".nuts").join("flows")),
+        }
+    }
 
-        let prompt = format!(
-            "Generate realistic test data for this API endpoint:\n\n\
-            Method: {}\n\
-            Endpoint: {}\n\n\
-            Based on the endpoint path and method, generate appropriate test data:\n\
-            - For POST/PUT: Generate request body data\n\
-            - For GET: Generate query parameters if needed\n\
-            - Make the data realistic and suitable for testing\n\
-            - Include edge cases and variations\n\n\
-            Return as JSON object with the test data.",
-            method, endpoint
-        );
+    /// POST every record to `url` as JSON, `options.concurrency` at a time, with live progress
+    /// and a line per record. Results are in record order.
+    pub async fn post_records(&self, records: &[Value], url: &str, options: &GenerateOptions) -> Result<Vec<BatchResult>, Box<dyn std::error::Error>> {
+        let total = records.len();
+        let mut results = Vec::with_capacity(total);
+        let mut pending = stream::iter(records.iter().enumerate())
+            .map(|(i, record)| async move {
+                let started = Instant::now();
+                let outcome = self.call.send_quiet(CallOptions {
+                    method: "POST".to_string(),
+                    url: url.to_string(),
+                    body: Some(record.to_string()),
+                    ..Default::default()
+                }).await;
+                let mut result = BatchResult {
+                    index: i + 1,
+                    method: "POST".to_string(),
+                    url: url.to_string(),
+                    status: None,
+                    latency_ms: started.elapsed().as_millis() as u64,
+                    body: None,
+                    error: None,
+                };
+                match outcome {
+                    Ok((status, body)) => {
+                        result.status = Some(status);
+                        result.body = Some(serde_json::from_str(&body).unwrap_or(Value::String(body)));
+                    }
+                    Err(e) => result.error = Some(e.to_string()),
+                }
+                // The slot waits before its next record; nothing follows the last one
+                if i + 1 < total {
+                    tokio::time::sleep(options.delay).await;
+                }
+                result
+            })
+            .buffer_unordered(options.concurrency);
 
-        let response = ai_client.messages(MessagesRequestBuilder::default()
-            .messages(vec![Message {
-                role: Role::User,
-                content: vec![ContentBlock::Text { text: prompt }],
-            }])
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(1000_usize)
-            .build()?
-        ).await?;
-
-        if let Some(ContentBlock::Text { text }) = response.content.first() {
-            if let Ok(data) = serde_json::from_str::<Value>(text) {
-                return Ok(data);
+        while let Some(result) = pending.next().await {
+            results.push(result);
+            let failed = results.iter().filter(|r| r.is_failure()).count();
+            print!(
+                "\r📤 {}/{} | {} ok, {} failed",
+                style(results.len()).magenta().bold(),
+                total,
+                style(results.len() - failed).green().bold(),
+                style(failed).red().bold(),
+            );
+            std::io::stdout().flush()?;
+        }
+        println!();
+
+        results.sort_by_key(|r| r.index);
+        for result in &results {
+            match (result.status, &result.error) {
+                (Some(status), _) if status < 400 => println!("  #{:<4} {} {}ms", result.index, style(status).green(), result.latency_ms),
+                (Some(status), _) => println!("  #{:<4} {} {}ms", result.index, style(status).red(), result.latency_ms),
+                (None, error) => println!("  #{:<4} {} {}", result.index, style("error").red(), style(error.as_deref().unwrap_or_default()).dim()),
             }
         }
+        let failed = results.iter().filter(|r| r.is_failure()).count();
+        println!("📊 {} succeeded, {} failed", style(total - failed).green(), style(failed).red());
+        Ok(results)
+    }
+
+    /// Ask the AI for `count` request bodies for `METHOD path`. When `flow` has the operation
+    /// with a JSON request schema, the prompt carries the schema and its examples and every
+    /// record is checked against it; otherwise only the method and path guide the AI.
+    pub async fn generate_for_endpoint(&self, flow: &str, method: &str, path: &str, count: usize) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+        let ai_client = ai::client(&self.config, "generate")?;
+        let file = self.flows_dir()?.join(format!("{}.yaml", flow));
+        let spec = OpenAPISpec::load(&file).map_err(|e| format!("Could not load flow '{}': {}", flow, e))?;
+        let operation = spec.paths.get(path)
+            .and_then(|item| item.operations().find(|(m, _)| m.eq_ignore_ascii_case(method)))
+            .map(|(_, operation)| operation);
+        let shape = operation.and_then(|operation| RecordShape::request(operation, spec.extras.get("components")));
+        match (&operation, &shape) {
+            (None, _) => println!("💡 {} {} isn't in flow '{}', generating from the method and path alone", method, path, flow),
+            (Some(_), None) => println!("💡 {} {} declares no JSON request schema, generating from the method and path alone", method, path),
+            (Some(_), Some(_)) => {}
+        }
+        println!("🎲 Generating {} request bodies for {} {}...", count, method, path);
 
-        // Fallback to basic data generation
-        Ok(serde_json::json!({
-            "test_data": "generated",
-            "timestamp": chrono::Utc::now().to_rfc3339()
-        }))
+        let what = format!("JSON request bodies for {} {}", method, path);
+        ask_for_records(&*ai_client, records_prompt(&what, count, shape.as_ref()), count, shape.as_ref()).await
     }
 }
\ No newline at end of file
@@ -1,24 +1,295 @@
-use crate::flows::{OpenAPISpec, Operation};
+use crate::flows::{OpenAPISpec, Operation, Schema};
+use crate::units::parse_duration;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::net::SocketAddr;
 use axum::{
     Router,
-    routing::{get, post},
     Json,
-    http::StatusCode,
+    http::{HeaderMap, Method, StatusCode, Uri},
 };
+use axum::body::Bytes;
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
-use std::collections::HashMap;
-use std::sync::Arc;
-use axum::extract::Path;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
 use axum_server::Server;
 use tokio::signal::ctrl_c;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use console::style;
+
+/// Request header that forces the status of a single response.
+pub const FORCE_STATUS_HEADER: &str = "x-nuts-mock-status";
+
+/// Paths under this prefix are the mock server's own endpoints, unless `--no-admin` is given.
+pub const ADMIN_PREFIX: &str = "/__nuts/";
+
+/// How many requests `/__nuts/requests` remembers.
+pub const RECENT_REQUESTS: usize = 100;
+
+/// Fault injection for exercising client retry and timeout handling, and what the server
+/// records about the requests it gets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockOptions {
+    /// Delay added before every response.
+    pub latency: Duration,
+    /// Random spread around `latency`, in both directions.
+    pub jitter: Duration,
+    /// Share of requests (0.0-1.0) answered with `error_status` instead of the example.
+    pub error_rate: f64,
+    pub error_status: u16,
+    /// JSONL file that requests to unknown paths are appended to.
+    pub record_unmatched: Option<PathBuf>,
+    /// Serve the endpoints under [`ADMIN_PREFIX`].
+    pub admin: bool,
+    /// Keep what clients create, update and delete; see [`MockStore`].
+    pub stateful: bool,
+}
+
+impl Default for MockOptions {
+    fn default() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            error_rate: 0.0,
+            error_status: 503,
+            record_unmatched: None,
+            admin: true,
+            stateful: false,
+        }
+    }
+}
+
+impl MockOptions {
+    /// Parse `--latency 200ms --jitter 100ms --error-rate 0.1 --error-status 503
+    /// --record-unmatched FILE --no-admin --stateful`.
+    pub fn from_args(args: &[String]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut options = Self::default();
+        let mut i = 0;
+        while i < args.len() {
+            let flag = args[i].as_str();
+            match flag {
+                "--no-admin" | "--stateful" => {
+                    options.admin &= flag != "--no-admin";
+                    options.stateful |= flag == "--stateful";
+                    i += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            let value = args.get(i + 1).ok_or_else(|| format!("{} needs a value", flag))?;
+            match flag {
+                "--record-unmatched" => options.record_unmatched = Some(PathBuf::from(value)),
+                "--latency" => options.latency = parse_duration(value).map_err(|e| format!("Invalid --latency: {}", e))?,
+                "--jitter" => options.jitter = parse_duration(value).map_err(|e| format!("Invalid --jitter: {}", e))?,
+                "--error-rate" => {
+                    options.error_rate = value.trim_end_matches('%').parse::<f64>().ok()
+                        .map(|rate| if value.ends_with('%') { rate / 100.0 } else { rate })
+                        .filter(|rate| (0.0..=1.0).contains(rate))
+                        .ok_or_else(|| format!("Invalid --error-rate '{}': use a number between 0 and 1", value))?;
+                }
+                "--error-status" => {
+                    options.error_status = value.parse().ok()
+                        .filter(|status| StatusCode::from_u16(*status).is_ok())
+                        .ok_or_else(|| format!("Invalid --error-status '{}'", value))?;
+                }
+                other => return Err(format!("Unknown mock option: {}", other).into()),
+            }
+            i += 2;
+        }
+        Ok(options)
+    }
+
+    /// Whether responses are slowed down or failed on purpose.
+    pub fn simulates_faults(&self) -> bool {
+        !self.latency.is_zero() || !self.jitter.is_zero() || self.error_rate > 0.0
+    }
+
+    fn delay(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return self.latency;
+        }
+        let jitter = self.jitter.as_secs_f64();
+        let offset = rand::thread_rng().gen_range(-jitter..=jitter);
+        Duration::from_secs_f64((self.latency.as_secs_f64() + offset).max(0.0))
+    }
+}
+
+/// How often `flow mock` checks the flow file for changes.
+const RELOAD_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What a route answers with.
+struct MockEndpoint {
+    examples: Vec<String>,
+    /// Synthesized from the schema when there are no examples.
+    generated: Option<Value>,
+    options: MockOptions,
+    /// Set for collection-like paths under `--stateful`.
+    resource: Option<Resource>,
+}
+
+/// Which side of a collection a `--stateful` route is.
+#[derive(Debug, Clone, PartialEq)]
+enum Resource {
+    /// `/users`: list and create items.
+    Collection(String),
+    /// `/users/{id}`: read, update and delete one item of the collection at the parent path.
+    Item { collection: String, param: String },
+}
+
+/// Items kept under `--stateful`, per collection path (`/users`). Seeded from the `mock_data`
+/// examples of each collection's and item's GET; new items get the next numeric `id`.
+#[derive(Debug, Default)]
+pub struct MockStore {
+    collections: HashMap<String, StoredCollection>,
+}
+
+#[derive(Debug, Default)]
+struct StoredCollection {
+    items: Vec<Value>,
+    last_id: u64,
+}
+
+impl StoredCollection {
+    /// Store `item` with a fresh id and return it.
+    fn insert(&mut self, mut item: serde_json::Map<String, Value>) -> Value {
+        self.last_id += 1;
+        item.insert("id".to_string(), Value::from(self.last_id));
+        let item = Value::Object(item);
+        self.items.push(item.clone());
+        item
+    }
+
+    /// Keep an example as it is, unless an item with its id is already stored.
+    fn seed(&mut self, item: Value) {
+        let Value::Object(object) = item else {
+            return;
+        };
+        match object.get("id").map(id_string) {
+            Some(id) if self.position(&id).is_some() => {}
+            Some(id) => {
+                self.last_id = self.last_id.max(id.parse().unwrap_or(0));
+                self.items.push(Value::Object(object));
+            }
+            None => {
+                self.insert(object);
+            }
+        }
+    }
+
+    fn position(&self, id: &str) -> Option<usize> {
+        self.items.iter().position(|item| item.get("id").map(id_string).as_deref() == Some(id))
+    }
+}
+
+/// An id as it appears in a path: strings unquoted, numbers as written.
+fn id_string(id: &Value) -> String {
+    match id {
+        Value::String(id) => id.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// One request the mock server handled, as logged and listed by `/__nuts/requests`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockRequest {
+    pub timestamp: chrono::DateTime<chrono::Local>,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    /// Whether an endpoint of the spec answered; false for unknown paths and methods.
+    pub matched: bool,
+    /// Index of the `mock_data` example sent back.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub example: Option<usize>,
+    /// Why the response was a simulated fault.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fault: Option<String>,
+    /// Answered from the `--stateful` store.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stored: bool,
+    pub latency_ms: u64,
+}
+
+impl MockRequest {
+    fn log_line(&self) -> String {
+        let status = if self.status >= 400 { style(self.status).red() } else { style(self.status).green() };
+        let detail = match (&self.fault, self.example, self.matched) {
+            (Some(fault), _, _) => format!("💥 {}", fault),
+            (None, _, _) if self.stored => "stored".to_string(),
+            (None, Some(index), _) => format!("example #{}", index),
+            (None, None, true) => "generated".to_string(),
+            (None, None, false) => style("unmatched").yellow().to_string(),
+        };
+        format!(
+            "{} {} {} → {} {} ({}ms)",
+            style(self.timestamp.format("%H:%M:%S%.3f")).dim(),
+            self.method,
+            self.path,
+            status,
+            detail,
+            self.latency_ms
+        )
+    }
+}
+
+/// A request to an unknown path, as appended to the `--record-unmatched` file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnmatchedRequest {
+    pub timestamp: chrono::DateTime<chrono::Local>,
+    pub method: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+/// What [`MockServer::handle_request`] answered.
+struct MockReply {
+    status: StatusCode,
+    body: Value,
+    example: Option<usize>,
+    fault: Option<String>,
+    stored: bool,
+}
+
+impl MockReply {
+    fn error(status: StatusCode, message: &str) -> Self {
+        Self { status, body: json!({ "error": message }), example: None, fault: None, stored: false }
+    }
+
+    fn stored(status: StatusCode, body: Value) -> Self {
+        Self { status, body, example: None, fault: None, stored: true }
+    }
+}
+
+/// One path of the spec, as `/`-separated segments (`{name}` captures one), and the endpoint
+/// for each of its methods.
+struct MockRoute {
+    segments: Vec<String>,
+    methods: HashMap<String, Arc<MockEndpoint>>,
+}
 
 #[allow(dead_code)]
 pub struct MockServer {
     spec: OpenAPISpec,
+    /// The file `spec` came from; watched for changes while the server runs.
+    spec_file: Option<PathBuf>,
+    /// What requests are matched against, replaced as a whole on reload.
+    routes: Arc<RwLock<Vec<MockRoute>>>,
+    /// The last [`RECENT_REQUESTS`] requests, oldest first.
+    recent: Arc<Mutex<VecDeque<MockRequest>>>,
+    /// Kept across reloads of the spec file.
+    store: Arc<RwLock<MockStore>>,
     port: u16,
     running: Arc<AtomicBool>,
+    /// Seed for responses synthesized from schemas, so they are the same on every run.
+    seed: u64,
+    options: MockOptions,
 }
 
 #[allow(dead_code)]
@@ -26,33 +297,93 @@ impl MockServer {
     pub fn new(spec: OpenAPISpec, port: u16) -> Self {
         Self { 
             spec, 
+            spec_file: None,
+            routes: Arc::new(RwLock::new(Vec::new())),
+            recent: Arc::new(Mutex::new(VecDeque::new())),
+            store: Arc::new(RwLock::new(MockStore::default())),
             port,
             running: Arc::new(AtomicBool::new(true)),
+            seed: 0,
+            options: MockOptions::default(),
         }
     }
 
-    pub async fn start(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let mut router = Router::new();
+    pub fn with_options(mut self, options: MockOptions) -> Self {
+        self.options = options;
+        self
+    }
 
-        // Add routes for each path in the spec
-        for (path, item) in &self.spec.paths {
-            let clean_path = path.replace("{id}", ":id");
-            println!("Adding mock endpoint: {}", clean_path);
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
 
-            // Handle each HTTP method
-            if let Some(op) = &item.get {
-                let examples = Arc::new(Self::get_mock_examples(op));
-                router = router.route(&clean_path, get(move |params| Self::handle_request(examples.clone(), params)));
-            }
-            if let Some(op) = &item.post {
-                let examples = Arc::new(Self::get_mock_examples(op));
-                router = router.route(&clean_path, post(move |params| Self::handle_request(examples.clone(), params)));
+    /// Reload the spec from `path` whenever the file changes, without restarting the server.
+    pub fn with_spec_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.spec_file = Some(path.into());
+        self
+    }
+
+    /// Start polling the spec file (see [`with_spec_file`](Self::with_spec_file)) for changes.
+    /// A changed file that fails to load is reported and the previous routes are kept.
+    pub fn watch(&self) -> Option<tokio::task::JoinHandle<()>> {
+        let path = self.spec_file.clone()?;
+        let routes = self.routes.clone();
+        let (seed, options) = (self.seed, self.options.clone());
+        let fingerprint = |path: &PathBuf| std::fs::metadata(path).ok().map(|meta| (meta.modified().ok(), meta.len()));
+        let mut last = fingerprint(&path);
+        Some(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(RELOAD_POLL_INTERVAL).await;
+                let current = fingerprint(&path);
+                if current == last {
+                    continue;
+                }
+                last = current;
+                match OpenAPISpec::load(&path) {
+                    Ok(spec) => {
+                        let reloaded = Self::build_routes(&spec, seed, &options);
+                        *routes.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = reloaded;
+                        println!("🔄 Reloaded {} endpoints from {}", spec.paths.len(), path.display());
+                    }
+                    Err(e) => println!("⚠️  Keeping the previous endpoints, {} did not load: {}", path.display(), e),
+                }
             }
-            // Add other methods similarly
-        }
+        }))
+    }
+
+    pub async fn start(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let router = self.router();
 
         println!("🎭 Starting mock server on http://127.0.0.1:{}", self.port);
         println!("📚 Loaded {} endpoints from OpenAPI spec", self.spec.paths.len());
+        if self.options.simulates_faults() {
+            println!(
+                "💥 Simulating latency {}ms ±{}ms, {:.0}% errors with status {}",
+                self.options.latency.as_millis(),
+                self.options.jitter.as_millis(),
+                self.options.error_rate * 100.0,
+                self.options.error_status
+            );
+        }
+        let watcher = self.watch();
+        if let Some(path) = &self.spec_file {
+            println!("👀 Watching {} for changes", path.display());
+        }
+        if self.options.stateful {
+            let store = self.store.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let mut collections: Vec<String> = store.collections.iter()
+                .map(|(path, collection)| format!("{} ({})", path, collection.items.len()))
+                .collect();
+            collections.sort();
+            println!("🗃️  Stateful collections: {}", if collections.is_empty() { "none".to_string() } else { collections.join(", ") });
+        }
+        if let Some(path) = &self.options.record_unmatched {
+            println!("📝 Recording requests to unknown paths in {}", path.display());
+        }
+        if self.options.admin {
+            println!("🔧 Recent requests: http://127.0.0.1:{}{}requests", self.port, ADMIN_PREFIX);
+        }
         println!("Press Ctrl+C to stop the server");
 
         let addr = SocketAddr::from(([127, 0, 0, 1], self.port));
@@ -70,10 +401,251 @@ impl MockServer {
                 running.store(false, Ordering::SeqCst);
             }
         }
+        if let Some(watcher) = watcher {
+            watcher.abort();
+        }
 
         Ok(())
     }
 
+    /// Build the axum router serving every operation in the spec. Requests are matched
+    /// against the current routes when they arrive, so a reloaded spec takes effect at once.
+    pub fn router(&self) -> Router {
+        let routes = Self::build_routes(&self.spec, self.seed, &self.options);
+        Self::seed_store(&routes, &self.store);
+        *self.routes.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = routes;
+
+        let routes = self.routes.clone();
+        let recent = self.recent.clone();
+        let store = self.store.clone();
+        let options = Arc::new(self.options.clone());
+        Router::new().fallback(move |method: Method, uri: Uri, headers: HeaderMap, body: Bytes| {
+            let started = Instant::now();
+            let recent = recent.clone();
+            let options = options.clone();
+            let store = store.clone();
+            // Resolved before awaiting, so the lock isn't held while the response is prepared
+            let found = Self::find_route(&routes, &method, uri.path());
+            async move {
+                if options.admin && uri.path().starts_with(ADMIN_PREFIX) {
+                    return Self::admin(&recent, &method, uri.path());
+                }
+                let matched = found.is_ok();
+                let reply = match found {
+                    Ok((endpoint, params)) => Self::handle_request(endpoint, &store, &method, &uri, &headers, params, &body).await,
+                    Err(StatusCode::NOT_FOUND) => {
+                        if let Some(file) = &options.record_unmatched {
+                            if let Err(e) = Self::record_unmatched(file, &method, &uri, &headers, &body) {
+                                println!("⚠️  Could not record the request in {}: {}", file.display(), e);
+                            }
+                        }
+                        MockReply::error(StatusCode::NOT_FOUND, "No mock endpoint for this path")
+                    }
+                    Err(status) => MockReply::error(status, "Method not allowed"),
+                };
+
+                let request = MockRequest {
+                    timestamp: chrono::Local::now(),
+                    method: method.to_string(),
+                    path: uri.to_string(),
+                    status: reply.status.as_u16(),
+                    matched,
+                    example: reply.example,
+                    fault: reply.fault,
+                    stored: reply.stored,
+                    latency_ms: started.elapsed().as_millis() as u64,
+                };
+                println!("{}", request.log_line());
+                let mut recent = recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                if recent.len() == RECENT_REQUESTS {
+                    recent.pop_front();
+                }
+                recent.push_back(request);
+                (reply.status, Json(reply.body))
+            }
+        })
+    }
+
+    /// Routes for every operation in `spec`, most specific first: at the first segment where
+    /// two paths differ, a literal beats a `{param}`.
+    fn build_routes(spec: &OpenAPISpec, seed: u64, options: &MockOptions) -> Vec<MockRoute> {
+        let paths: Vec<Vec<String>> = spec.paths.keys().map(|path| Self::route_segments(path)).collect();
+        let mut routes: Vec<MockRoute> = spec.paths.iter()
+            .map(|(path, item)| {
+                let resource = options.stateful.then(|| Self::resource(&Self::route_segments(path), &paths)).flatten();
+                let methods = item.operations()
+                    .map(|(method, op)| {
+                        let examples = Self::get_mock_examples(op);
+                        // Without examples, answer with a value synthesized from the success response schema
+                        let generated = examples.is_empty().then(|| {
+                            let schema = Self::success_schema(op);
+                            SchemaSampler::new(seed).sample(schema.unwrap_or(&Schema::default()))
+                        });
+                        (method.to_uppercase(), Arc::new(MockEndpoint { examples, generated, options: options.clone(), resource: resource.clone() }))
+                    })
+                    .collect();
+                MockRoute { segments: Self::route_segments(path), methods }
+            })
+            .collect();
+        routes.sort_by_key(|route| route.segments.iter().map(|segment| segment.starts_with('{')).collect::<Vec<_>>());
+        routes
+    }
+
+    /// `/users/{id}` is an item of `/users`; `/users` is a collection when the spec also has
+    /// a `/users/{param}` path.
+    fn resource(segments: &[String], paths: &[Vec<String>]) -> Option<Resource> {
+        let (last, parent) = segments.split_last()?;
+        let join = |segments: &[String]| format!("/{}", segments.join("/"));
+        if let Some(param) = last.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            return Some(Resource::Item { collection: join(parent), param: param.to_string() });
+        }
+        let has_items = paths.iter().any(|path| {
+            path.len() == segments.len() + 1 && path.starts_with(segments) && path.last().is_some_and(|s| s.starts_with('{'))
+        });
+        has_items.then(|| Resource::Collection(join(segments)))
+    }
+
+    /// Fill the store from the GET examples of stateful routes: arrays for collections,
+    /// objects for items.
+    fn seed_store(routes: &[MockRoute], store: &RwLock<MockStore>) {
+        let mut store = store.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        for route in routes {
+            let Some(endpoint) = route.methods.get("GET") else {
+                continue;
+            };
+            let collection = match &endpoint.resource {
+                Some(Resource::Collection(collection)) | Some(Resource::Item { collection, .. }) => collection,
+                None => continue,
+            };
+            let stored = store.collections.entry(collection.clone()).or_default();
+            for example in &endpoint.examples {
+                match serde_json::from_str::<Value>(example) {
+                    Ok(Value::Array(items)) => items.into_iter().for_each(|item| stored.seed(item)),
+                    Ok(item) => stored.seed(item),
+                    Err(_) => {}
+                }
+            }
+        }
+    }
+
+    /// Answer a CRUD request from the store: POST and GET on collections, GET, PUT, PATCH and
+    /// DELETE on items. `None` for other methods, which are answered from the examples.
+    fn handle_stateful(resource: &Resource, store: &RwLock<MockStore>, method: &Method, uri: &Uri, params: &HashMap<String, String>, body: &[u8]) -> Option<MockReply> {
+        let object = || match serde_json::from_slice::<Value>(body) {
+            Ok(Value::Object(object)) => Ok(object),
+            _ => Err(MockReply::error(StatusCode::BAD_REQUEST, "Request body must be a JSON object")),
+        };
+        let mut store = store.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let reply = match resource {
+            Resource::Collection(collection) => {
+                let stored = store.collections.entry(collection.clone()).or_default();
+                match *method {
+                    Method::GET | Method::HEAD => {
+                        let query: HashMap<String, String> = url::form_urlencoded::parse(uri.query().unwrap_or("").as_bytes()).into_owned().collect();
+                        let number = |name: &str| query.get(name).and_then(|value| value.parse::<usize>().ok());
+                        let page = stored.items.iter()
+                            .skip(number("offset").unwrap_or(0))
+                            .take(number("limit").unwrap_or(usize::MAX))
+                            .cloned()
+                            .collect();
+                        MockReply::stored(StatusCode::OK, Value::Array(page))
+                    }
+                    Method::POST => match object() {
+                        Ok(object) => MockReply::stored(StatusCode::CREATED, stored.insert(object)),
+                        Err(reply) => reply,
+                    },
+                    _ => return None,
+                }
+            }
+            Resource::Item { collection, param } => {
+                let stored = store.collections.entry(collection.clone()).or_default();
+                let id = params.get(param).cloned().unwrap_or_default();
+                let Some(index) = stored.position(&id) else {
+                    return Some(MockReply::error(StatusCode::NOT_FOUND, &format!("No item with id {} in {}", id, collection)));
+                };
+                match *method {
+                    Method::GET | Method::HEAD => MockReply::stored(StatusCode::OK, stored.items[index].clone()),
+                    Method::PUT | Method::PATCH => match object() {
+                        Ok(changes) => {
+                            let item = &mut stored.items[index];
+                            for (key, value) in changes.into_iter().filter(|(key, _)| key != "id") {
+                                item[key] = value;
+                            }
+                            MockReply::stored(StatusCode::OK, item.clone())
+                        }
+                        Err(reply) => reply,
+                    },
+                    Method::DELETE => {
+                        stored.items.remove(index);
+                        MockReply::stored(StatusCode::NO_CONTENT, Value::Null)
+                    }
+                    _ => return None,
+                }
+            }
+        };
+        Some(reply)
+    }
+
+    /// The endpoint answering `method` on `path` and the captured path parameters; `404` when
+    /// no route matches and `405` when the route has no such method. `HEAD` falls back to `GET`.
+    fn find_route(routes: &RwLock<Vec<MockRoute>>, method: &Method, path: &str) -> Result<(Arc<MockEndpoint>, HashMap<String, String>), StatusCode> {
+        let routes = routes.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let segments = Self::route_segments(path);
+        let (route, params) = routes.iter()
+            .find_map(|route| Self::match_segments(&route.segments, &segments).map(|params| (route, params)))
+            .ok_or(StatusCode::NOT_FOUND)?;
+        let endpoint = route.methods.get(method.as_str())
+            .or_else(|| (method == Method::HEAD).then(|| route.methods.get("GET")).flatten())
+            .ok_or(StatusCode::METHOD_NOT_ALLOWED)?;
+        Ok((endpoint.clone(), params))
+    }
+
+    fn match_segments(pattern: &[String], segments: &[String]) -> Option<HashMap<String, String>> {
+        if pattern.len() != segments.len() {
+            return None;
+        }
+        let mut params = HashMap::new();
+        for (expected, actual) in pattern.iter().zip(segments) {
+            match expected.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                Some(name) if !actual.is_empty() => {
+                    params.insert(name.to_string(), Self::decode_segment(actual));
+                }
+                Some(_) => return None,
+                None if expected == actual => {}
+                None => return None,
+            }
+        }
+        Some(params)
+    }
+
+    fn route_segments(path: &str) -> Vec<String> {
+        path.trim_matches('/').split('/').map(str::to_string).collect()
+    }
+
+    /// Percent-decode a path segment; malformed escapes are kept as they are.
+    fn decode_segment(segment: &str) -> String {
+        let bytes = segment.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let escaped = (bytes[i] == b'%')
+                .then(|| segment.get(i + 1..i + 3))
+                .flatten()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match escaped {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 3;
+                }
+                None => {
+                    decoded.push(bytes[i]);
+                    i += 1;
+                }
+            }
+        }
+        String::from_utf8_lossy(&decoded).into_owned()
+    }
+
     fn get_mock_examples(op: &Operation) -> Vec<String> {
         op.mock_data.as_ref()
             .and_then(|m| m.examples.as_ref())
@@ -81,20 +653,206 @@ impl MockServer {
             .unwrap_or_default()
     }
 
-    async fn handle_request(examples: Arc<Vec<String>>, _params: Path<HashMap<String, String>>) -> (StatusCode, Json<Value>) {
-        if examples.is_empty() {
-            (StatusCode::NOT_IMPLEMENTED, Json(json!({
-                "error": "No mock examples found"
-            })))
+    /// Schema of the 200 response (or the first other 2xx, then `default`), preferring JSON content.
+    fn success_schema(op: &Operation) -> Option<&Schema> {
+        let mut codes: Vec<&String> = op.responses.keys().filter(|code| code.starts_with('2')).collect();
+        codes.sort();
+        let response = op.responses.get("200")
+            .or_else(|| codes.first().and_then(|code| op.responses.get(*code)))
+            .or_else(|| op.responses.get("default"))?;
+        let content = response.content.as_ref()?;
+        content.get("application/json")
+            .or_else(|| content.values().next())
+            .map(|media| &media.schema)
+    }
+
+    async fn handle_request(
+        endpoint: Arc<MockEndpoint>,
+        store: &RwLock<MockStore>,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        params: HashMap<String, String>,
+        body: &[u8],
+    ) -> MockReply {
+        let delay = endpoint.options.delay();
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
+        // A forced status wins over the random error rate, so tests can trigger failures deterministically
+        let forced = headers.get(FORCE_STATUS_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u16>().ok())
+            .and_then(|status| StatusCode::from_u16(status).ok());
+        let fault = match forced {
+            Some(status) => Some((status, format!("forced via {}", FORCE_STATUS_HEADER))),
+            None if endpoint.options.error_rate > 0.0 && rand::random::<f64>() < endpoint.options.error_rate => {
+                let status = StatusCode::from_u16(endpoint.options.error_status).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+                Some((status, format!("error rate {:.0}%", endpoint.options.error_rate * 100.0)))
+            }
+            None => None,
+        };
+        if let Some((status, reason)) = &fault {
+            if status.is_client_error() || status.is_server_error() {
+                return MockReply {
+                    status: *status,
+                    body: json!({
+                        "error": status.canonical_reason().unwrap_or("Simulated error"),
+                        "status": status.as_u16(),
+                        "simulated": true
+                    }),
+                    example: None,
+                    fault: Some(format!("{}, delay {}ms", reason, delay.as_millis())),
+                    stored: false,
+                };
+            }
+        }
+        if let Some(reply) = endpoint.resource.as_ref()
+            .and_then(|resource| Self::handle_stateful(resource, store, method, uri, &params, body))
+        {
+            return reply;
+        }
+        let (status, fault) = match fault {
+            Some((status, reason)) => (status, Some(format!("{}, delay {}ms", reason, delay.as_millis()))),
+            None => (StatusCode::OK, None),
+        };
+
+        let (json, example) = if let Some(generated) = endpoint.generated.as_ref() {
+            (Ok(generated.clone()), None)
+        } else if endpoint.examples.is_empty() {
+            return MockReply::error(StatusCode::NOT_IMPLEMENTED, "No mock examples found");
         } else {
-            let idx = rand::random::<usize>() % examples.len();
-            let example = &examples[idx];
-            match serde_json::from_str(example) {
-                Ok(json) => (StatusCode::OK, Json(json)),
-                Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
-                    "error": "Invalid JSON in mock data"
-                })))
+            let idx = rand::random::<usize>() % endpoint.examples.len();
+            (serde_json::from_str(&endpoint.examples[idx]), Some(idx))
+        };
+        match json {
+            Ok(mut json) => {
+                Self::apply_path_params(&mut json, &params);
+                MockReply { status, body: json, example, fault, stored: false }
+            }
+            Err(_) => MockReply::error(StatusCode::INTERNAL_SERVER_ERROR, "Invalid JSON in mock data"),
+        }
+    }
+
+    /// Append a request to an unknown path to the `--record-unmatched` file.
+    fn record_unmatched(path: &PathBuf, method: &Method, uri: &Uri, headers: &HeaderMap, body: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let request = UnmatchedRequest {
+            timestamp: chrono::Local::now(),
+            method: method.to_string(),
+            path: uri.path().to_string(),
+            query: uri.query().map(str::to_string),
+            headers: headers.iter()
+                .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+                .collect(),
+            body: String::from_utf8_lossy(body).into_owned(),
+        };
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(&request)?)?;
+        Ok(())
+    }
+
+    /// The endpoints under [`ADMIN_PREFIX`]: `GET /__nuts/requests` lists the recent requests.
+    fn admin(recent: &Mutex<VecDeque<MockRequest>>, method: &Method, path: &str) -> (StatusCode, Json<Value>) {
+        match (method, path.strip_prefix(ADMIN_PREFIX)) {
+            (&Method::GET, Some("requests")) => {
+                let recent = recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                (StatusCode::OK, Json(json!(recent.iter().collect::<Vec<_>>())))
+            }
+            _ => (StatusCode::NOT_FOUND, Json(json!({ "error": "Unknown admin endpoint; try GET /__nuts/requests" }))),
+        }
+    }
+
+    /// Overwrite fields named after captured path parameters, so `GET /users/42` returns `"id": 42`.
+    fn apply_path_params(example: &mut Value, params: &HashMap<String, String>) {
+        let Some(object) = example.as_object_mut() else {
+            return;
+        };
+        for (name, raw) in params {
+            if let Some(field) = object.get_mut(name) {
+                *field = raw.parse::<i64>().map(Value::from)
+                    .ok()
+                    .or_else(|| raw.parse::<f64>().ok().filter(|n| n.is_finite()).map(Value::from))
+                    .unwrap_or_else(|| Value::String(raw.clone()));
             }
         }
     }
 }
+
+/// Builds example values from a schema. The same seed always produces the same value.
+pub struct SchemaSampler {
+    rng: StdRng,
+    strings: usize,
+    booleans: usize,
+}
+
+impl SchemaSampler {
+    /// Nesting limit, so self-similar schemas can't recurse forever.
+    const MAX_DEPTH: usize = 8;
+
+    pub fn new(seed: u64) -> Self {
+        Self { rng: StdRng::seed_from_u64(seed), strings: 0, booleans: 0 }
+    }
+
+    /// A value for `schema`; an empty or missing schema yields `{}`.
+    pub fn sample(&mut self, schema: &Schema) -> Value {
+        self.sample_at(schema, 0)
+    }
+
+    fn sample_at(&mut self, schema: &Schema, depth: usize) -> Value {
+        if let Some(value) = schema.const_value.as_ref()
+            .or(schema.example.as_ref())
+            .or_else(|| schema.examples.as_ref().and_then(|e| e.first()))
+            .or_else(|| schema.enum_values.as_ref().and_then(|e| e.first()))
+        {
+            return value.clone();
+        }
+
+        let schema_type = match schema.schema_type.primary() {
+            "" if schema.properties.is_some() => "object",
+            "" if schema.items.is_some() => "array",
+            other => other,
+        };
+        match schema_type {
+            "string" => self.string(schema.format.as_deref()),
+            "integer" => Value::from(self.rng.gen_range(1..=100)),
+            "number" => Value::from((self.rng.gen_range(100..=10_000) as f64) / 100.0),
+            "boolean" => {
+                self.booleans += 1;
+                Value::Bool(self.booleans % 2 == 1)
+            }
+            "array" if depth < Self::MAX_DEPTH => {
+                let items = schema.items.as_deref();
+                Value::Array((0..3).map(|_| items.map(|i| self.sample_at(i, depth + 1)).unwrap_or(Value::Null)).collect())
+            }
+            "array" => Value::Array(Vec::new()),
+            _ => {
+                let mut object = serde_json::Map::new();
+                if let Some(properties) = schema.properties.as_ref().filter(|_| depth < Self::MAX_DEPTH) {
+                    // Sorted so the random sequence is consumed in the same order every time
+                    let mut names: Vec<&String> = properties.keys().collect();
+                    names.sort();
+                    for name in names {
+                        let value = self.sample_at(&properties[name], depth + 1);
+                        object.insert(name.clone(), value);
+                    }
+                }
+                Value::Object(object)
+            }
+        }
+    }
+
+    fn string(&mut self, format: Option<&str>) -> Value {
+        self.strings += 1;
+        let n = self.strings;
+        Value::String(match format {
+            Some("email") => format!("user{}@example.com", n),
+            Some("uuid") => format!("00000000-0000-4000-8000-{:012x}", n),
+            Some("date") => format!("2024-01-{:02}", (n - 1) % 28 + 1),
+            Some("date-time") => format!("2024-01-{:02}T12:00:00Z", (n - 1) % 28 + 1),
+            Some("uri") | Some("url") => format!("https://example.com/{}", n),
+            Some("ipv4") => format!("192.0.2.{}", n % 255),
+            _ => format!("string-{}", n),
+        })
+    }
+}
@@ -13,6 +13,26 @@ pub mod generate;
 pub mod monitor;
 pub mod explain;
 pub mod fix;
+pub mod ws;
+pub mod proxy;
+pub mod diff;
+pub mod batch;
+pub mod snapshot;
+pub mod curl;
+
+/// Label printed in place of an AI-only section when running in offline mode.
+pub fn print_ai_skipped(section: &str) {
+    crate::outln!(
+        "{} {} skipped (offline mode: no Anthropic API key, use 'config api-key' to enable)",
+        console::style("⏭️").dim(),
+        section
+    );
+}
+
+/// Label printed in place of an AI section whose request failed, with what to do about it.
+pub fn print_ai_failed(section: &str, error: &dyn std::error::Error) {
+    crate::outln!("{} {} skipped: {}", console::style("⏭️").dim(), section, error);
+}
 
 // Add shared command result type
 pub type CommandResult = Result<(), Box<dyn std::error::Error>>;
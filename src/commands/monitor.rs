@@ -1,15 +1,24 @@
-use anthropic::{
-    client::ClientBuilder,
-    types::{Message, ContentBlock, MessagesRequestBuilder, Role},
-};
+use crate::ai;
 use crate::config::Config;
-use crate::commands::call::CallCommand;
-use std::time::{Duration, SystemTime};
+use crate::commands::{print_ai_failed, print_ai_skipped};
+use crate::commands::call::{CallCommand, CallOptions};
+use crate::flows::OpenAPISpec;
+use crate::units::parse_positive_duration;
+use chrono::{DateTime, Utc};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use serde_json::json;
-use tokio::time::interval;
+use tokio::time::{interval, MissedTickBehavior};
 
 pub struct MonitorCommand {
     config: Config,
+    history_dir: Option<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -20,114 +29,686 @@ pub struct MonitorResult {
     pub issues: Vec<String>,
     #[allow(dead_code)]
     pub recommendations: Vec<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl MonitorResult {
+    /// Statuses that raise an alert; `healthy` and `slow` don't.
+    pub fn is_alerting(&self) -> bool {
+        is_alerting(&self.status)
+    }
+}
+
+fn is_alerting(status: &str) -> bool {
+    matches!(status, "warning" | "error")
+}
+
+/// How long and how often to check, and where to send alerts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorOptions {
+    pub interval: Duration,
+    /// Stop after this long; with neither this nor `count`, run until Ctrl+C.
+    pub duration: Option<Duration>,
+    /// Stop after this many checks.
+    pub count: Option<usize>,
+    /// Slack-compatible webhook told about every change into or out of warning/error.
+    pub alert_webhook: Option<String>,
+    /// AI analysis of the recent checks every third check.
+    pub smart: bool,
+    /// Check every GET endpoint of this flow instead of a single URL.
+    pub flow: Option<String>,
+}
+
+impl Default for MonitorOptions {
+    fn default() -> Self {
+        Self { interval: Duration::from_secs(30), duration: None, count: None, alert_webhook: None, smart: false, flow: None }
+    }
+}
+
+impl MonitorOptions {
+    /// `monitor (URL | --flow NAME) [--interval 10s] [--duration 1h | --count N] [--alert-webhook URL] [--smart]`
+    pub fn from_args(args: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let mut options = Self::default();
+        // Skip "monitor" and the URL, if there is one
+        let mut i = if args.get(1).is_some_and(|arg| !arg.starts_with("--")) { 2 } else { 1 };
+        while i < args.len() {
+            if args[i] == "--smart" {
+                options.smart = true;
+                i += 1;
+                continue;
+            }
+            let value = args.get(i + 1).copied().ok_or_else(|| format!("Value required after {}", args[i]))?;
+            match args[i] {
+                "--interval" => {
+                    options.interval = parse_positive_duration(value).map_err(|e| format!("Invalid --interval: {}", e))?;
+                }
+                "--duration" => {
+                    options.duration = Some(parse_positive_duration(value).map_err(|e| format!("Invalid --duration: {}", e))?);
+                }
+                "--count" => {
+                    options.count = Some(value.parse().ok().filter(|n| *n > 0)
+                        .ok_or_else(|| format!("--count must be a positive number, got '{}'", value))?);
+                }
+                "--alert-webhook" => options.alert_webhook = Some(value.to_string()),
+                "--flow" => options.flow = Some(value.to_string()),
+                other => return Err(format!("Unknown option for monitor: {}", other).into()),
+            }
+            i += 2;
+        }
+        Ok(options)
+    }
+}
+
+/// One check as kept in `~/.nuts/monitor/<host>.jsonl`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorRecord {
+    pub timestamp: DateTime<Utc>,
+    pub url: String,
+    pub status: String,
+    pub latency_ms: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<String>,
+}
+
+impl From<&MonitorResult> for MonitorRecord {
+    fn from(result: &MonitorResult) -> Self {
+        Self {
+            timestamp: result.checked_at,
+            url: result.url.clone(),
+            status: result.status.clone(),
+            latency_ms: result.response_time.as_millis() as u64,
+            issues: result.issues.clone(),
+        }
+    }
+}
+
+/// Checks appended per host as JSON lines, for `monitor report`.
+pub struct MonitorHistory {
+    dir: PathBuf,
+}
+
+impl MonitorHistory {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `~/.nuts/monitor`.
+    pub fn default_dir() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(dirs::home_dir()
+            .ok_or("Could not find home directory")?
+            .join(".nuts")
+            .join("monitor"))
+    }
+
+    /// The history file name for a monitored URL: its host, and port when one is given.
+    pub fn host_key(url: &str) -> String {
+        match url::Url::parse(url) {
+            Ok(parsed) => match (parsed.host_str(), parsed.port()) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                (Some(host), None) => host.to_string(),
+                (None, _) => "unknown".to_string(),
+            },
+            Err(_) => url.split('/').next().unwrap_or("unknown").to_string(),
+        }
+    }
+
+    fn path(&self, host: &str) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", host))
+    }
+
+    pub fn append(&self, host: &str, record: &MonitorRecord) -> Result<(), Box<dyn Error>> {
+        std::fs::create_dir_all(&self.dir)?;
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(self.path(host))?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+
+    /// Every recorded check for `host`, oldest first. Unreadable lines are skipped.
+    pub fn load(&self, host: &str) -> Result<Vec<MonitorRecord>, Box<dyn Error>> {
+        let path = self.path(host);
+        let content = std::fs::read_to_string(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => format!("No monitoring history for '{}' in {}", host, self.dir.display()),
+            _ => format!("Could not read {}: {}", path.display(), e),
+        })?;
+        Ok(content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+    }
+}
+
+/// Uptime and latency over a set of checks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorReport {
+    pub checks: usize,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Share of checks that got an answer without an error, 0-100.
+    pub uptime_pct: f64,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    /// Number of checks per status, in order of first appearance.
+    pub statuses: Vec<(String, usize)>,
+}
+
+impl MonitorReport {
+    pub fn new(records: &[MonitorRecord]) -> Self {
+        let up = records.iter().filter(|r| r.status != "error").count();
+        let mut latencies: Vec<u64> = records.iter().filter(|r| r.status != "error").map(|r| r.latency_ms).collect();
+        latencies.sort_unstable();
+        let percentile = |p: usize| {
+            let index = (p * latencies.len()).div_ceil(100).saturating_sub(1);
+            Duration::from_millis(latencies.get(index).copied().unwrap_or(0))
+        };
+        let mut statuses: Vec<(String, usize)> = Vec::new();
+        for record in records {
+            match statuses.iter_mut().find(|(status, _)| *status == record.status) {
+                Some((_, count)) => *count += 1,
+                None => statuses.push((record.status.clone(), 1)),
+            }
+        }
+        Self {
+            checks: records.len(),
+            from: records.iter().map(|r| r.timestamp).min(),
+            to: records.iter().map(|r| r.timestamp).max(),
+            uptime_pct: if records.is_empty() { 0.0 } else { up as f64 * 100.0 / records.len() as f64 },
+            p50: percentile(50),
+            p95: percentile(95),
+            p99: percentile(99),
+            statuses,
+        }
+    }
+
+    pub fn print(&self) {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            println!("   • Window: {} → {}", from.format("%Y-%m-%d %H:%M:%S"), to.format("%Y-%m-%d %H:%M:%S"));
+        }
+        println!("   • Checks: {}", style(self.checks).magenta().bold());
+        let uptime = format!("{:.2}%", self.uptime_pct);
+        let uptime = if self.uptime_pct >= 99.0 { style(uptime).green() } else { style(uptime).red() };
+        println!("   • Uptime: {}", uptime.bold());
+        println!("   • Latency: p50 {}ms | p95 {}ms | p99 {}ms",
+            style(self.p50.as_millis()).yellow(),
+            style(self.p95.as_millis()).yellow(),
+            style(self.p99.as_millis()).magenta()
+        );
+        let statuses: Vec<String> = self.statuses.iter().map(|(status, count)| format!("{} {}", status, count)).collect();
+        println!("   • Statuses: {}", statuses.join(", "));
+    }
+}
+
+/// What changed between two checks, if it's worth an alert: a move into warning or error
+/// (including between the two), or back out of them.
+pub fn status_change(previous: Option<&str>, current: &str) -> Option<&'static str> {
+    let was_alerting = previous.is_some_and(is_alerting);
+    match (was_alerting, is_alerting(current)) {
+        (_, true) if previous != Some(current) => Some("alert"),
+        (true, false) => Some("recovered"),
+        _ => None,
+    }
+}
+
+/// Slack-compatible payload: `text` for Slack, the rest for anything else reading it.
+pub fn alert_payload(result: &MonitorResult, previous: Option<&str>) -> serde_json::Value {
+    let text = match status_change(previous, &result.status) {
+        Some("recovered") => format!("✅ {} recovered: {} ({}ms)", result.url, result.status, result.response_time.as_millis()),
+        _ => format!(
+            "🚨 {} is {} ({}ms){}",
+            result.url,
+            result.status,
+            result.response_time.as_millis(),
+            if result.issues.is_empty() { String::new() } else { format!(": {}", result.issues.join("; ")) }
+        ),
+    };
+    json!({
+        "text": text,
+        "url": result.url,
+        "status": result.status,
+        "previous_status": previous,
+        "latency_ms": result.response_time.as_millis() as u64,
+        "issues": result.issues,
+        "timestamp": result.checked_at.to_rfc3339(),
+    })
+}
+
+/// POST an alert to `webhook` if the status changed in a way worth one. Returns a line
+/// saying what happened, or `None` when there was nothing to send.
+async fn send_alert(client: &reqwest::Client, webhook: &str, result: &MonitorResult, previous: Option<&str>) -> Option<String> {
+    status_change(previous, &result.status)?;
+    let payload = alert_payload(result, previous);
+    Some(match client.post(webhook).json(&payload).timeout(Duration::from_secs(10)).send().await {
+        Ok(response) if response.status().is_success() => format!("📣 Alert sent: {}", payload["text"].as_str().unwrap_or_default()),
+        Ok(response) => format!("⚠️  Alert webhook answered {}", response.status()),
+        Err(e) => format!("⚠️  Could not send alert: {}", e),
+    })
+}
+
+/// An endpoint checked by `monitor --flow`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorTarget {
+    /// `GET /users`.
+    pub name: String,
+    pub url: String,
+}
+
+/// Every GET endpoint of `spec` on its first server, sorted by path. Paths with parameters
+/// can't be checked without values and are returned separately.
+pub fn flow_targets(spec: &OpenAPISpec) -> (Vec<MonitorTarget>, Vec<String>) {
+    let base_url = spec.servers.first().map(|s| s.url.as_str()).unwrap_or("http://localhost:3000").trim_end_matches('/');
+    let mut paths: Vec<&String> = spec.paths.iter().filter(|(_, item)| item.get.is_some()).map(|(path, _)| path).collect();
+    paths.sort();
+    let (templated, plain): (Vec<&String>, Vec<&String>) = paths.into_iter().partition(|path| path.contains('{'));
+    let targets = plain.into_iter()
+        .map(|path| MonitorTarget { name: format!("GET {}", path), url: format!("{}{}", base_url, path) })
+        .collect();
+    (targets, templated.into_iter().map(|path| format!("GET {}", path)).collect())
+}
+
+fn status_emoji(status: &str) -> &'static str {
+    match status {
+        "healthy" => "💚",
+        "warning" => "🟡",
+        "slow" => "🟠",
+        "error" => "🔴",
+        _ => "⚪",
+    }
+}
+
+/// One endpoint's row in the dashboard.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DashboardRow {
+    pub name: String,
+    pub status: Option<String>,
+    pub latency: Option<Duration>,
+    pub checks: usize,
+    /// Checks that were neither warning nor error.
+    pub successes: usize,
+}
+
+/// Live table of `monitor --flow`, redrawn in place. It owns the terminal while monitoring:
+/// the lines it drew last, and raw mode for reading `q` when stdin is a terminal.
+pub struct Dashboard {
+    pub rows: Vec<DashboardRow>,
+    /// Lines written by the previous draw, to move back over.
+    drawn: usize,
+    interactive: bool,
+    raw_mode: bool,
+}
+
+impl Dashboard {
+    pub fn new(targets: &[MonitorTarget]) -> Self {
+        let rows = targets.iter().map(|t| DashboardRow { name: t.name.clone(), ..Default::default() }).collect();
+        Self { rows, drawn: 0, interactive: false, raw_mode: false }
+    }
+
+    /// Redraw in place when stdout is a terminal, and take raw mode so `q` can be read
+    /// when stdin is one too. Otherwise every check is printed as a line.
+    fn attach(&mut self) {
+        use std::io::IsTerminal;
+        self.interactive = std::io::stdout().is_terminal();
+        self.raw_mode = self.interactive && std::io::stdin().is_terminal() && crossterm::terminal::enable_raw_mode().is_ok();
+    }
+
+    pub fn update(&mut self, index: usize, result: &MonitorResult) {
+        let row = &mut self.rows[index];
+        row.status = Some(result.status.clone());
+        row.latency = Some(result.response_time);
+        row.checks += 1;
+        if !result.is_alerting() {
+            row.successes += 1;
+        }
+    }
+
+    /// The table as plain lines, at most 80 columns wide.
+    pub fn render(&self) -> Vec<String> {
+        let mut lines = vec![format!("   {:<44} {:>9} {:>9} {:>7}", "Endpoint", "Last", "Success", "Checks")];
+        for row in &self.rows {
+            let name = if row.name.chars().count() > 44 {
+                format!("{}…", row.name.chars().take(43).collect::<String>())
+            } else {
+                row.name.clone()
+            };
+            let latency = row.latency.map_or("-".to_string(), |l| format!("{}ms", l.as_millis()));
+            let success = match row.checks {
+                0 => "-".to_string(),
+                checks => format!("{:.1}%", row.successes as f64 * 100.0 / checks as f64),
+            };
+            let emoji = row.status.as_deref().map_or("⏳", status_emoji);
+            lines.push(format!("{} {:<44} {:>9} {:>9} {:>7}", emoji, name, latency, success, row.checks));
+        }
+        lines.push(style(if self.raw_mode { "Press q to stop" } else { "Press Ctrl+C to stop" }).dim().to_string());
+        lines
+    }
+
+    /// Move back over the last drawn table and clear it.
+    fn erase(&mut self, stdout: &mut std::io::Stdout) -> std::io::Result<()> {
+        use crossterm::{cursor, queue, terminal};
+        if self.drawn > 0 {
+            queue!(stdout, cursor::MoveUp(self.drawn as u16), cursor::MoveToColumn(0))?;
+        }
+        self.drawn = 0;
+        queue!(stdout, terminal::Clear(terminal::ClearType::FromCursorDown))
+    }
+
+    fn draw(&mut self) -> std::io::Result<()> {
+        let mut stdout = std::io::stdout();
+        self.erase(&mut stdout)?;
+        let lines = self.render();
+        for line in &lines {
+            // Raw mode doesn't turn \n into \r\n
+            write!(stdout, "{}\r\n", line)?;
+        }
+        self.drawn = lines.len();
+        stdout.flush()
+    }
+
+    /// Show `result` for the target at `index`: redraw the table, or print one line.
+    fn show(&mut self, index: usize, result: &MonitorResult) {
+        self.update(index, result);
+        if self.interactive {
+            let _ = self.draw();
+        } else {
+            println!("{} {} → {} ({}ms)", status_emoji(&result.status), self.rows[index].name, result.status, result.response_time.as_millis());
+        }
+    }
+
+    /// Print a line above the table, e.g. about an alert.
+    fn note(&mut self, message: &str) {
+        if self.interactive {
+            let mut stdout = std::io::stdout();
+            let _ = self.erase(&mut stdout)
+                .and_then(|_| write!(stdout, "{}\r\n", message))
+                .and_then(|_| self.draw());
+        } else {
+            println!("{}", message);
+        }
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        if self.raw_mode {
+            let _ = crossterm::terminal::disable_raw_mode();
+        }
+    }
+}
+
+/// Wait on a blocking thread for `q`, Esc or Ctrl+C (raw mode swallows the signal).
+/// Resolves when one is pressed; never resolves when `enabled` is false.
+async fn quit_key(enabled: bool, stop: Arc<AtomicBool>) {
+    if !enabled {
+        return std::future::pending().await;
+    }
+    let pressed = tokio::task::spawn_blocking(move || {
+        use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+        while !stop.load(Ordering::Relaxed) {
+            if !event::poll(Duration::from_millis(100)).unwrap_or(false) {
+                continue;
+            }
+            if let Ok(Event::Key(key)) = event::read() {
+                let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if ctrl_c || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                    return true;
+                }
+            }
+        }
+        false
+    }).await;
+    if !matches!(pressed, Ok(true)) {
+        std::future::pending::<()>().await;
+    }
 }
 
 impl MonitorCommand {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        Self { config, history_dir: None }
     }
 
-    /// Smart API monitoring with AI insights
-    pub async fn monitor(&self, url: &str, smart: bool) -> Result<(), Box<dyn std::error::Error>> {
-        println!("📊 Starting {} monitoring for: {}", 
-            if smart { "smart AI" } else { "basic" }, url);
-        
-        let mut interval = interval(Duration::from_secs(30));
+    /// Keep history in `dir` instead of `~/.nuts/monitor`.
+    pub fn with_history_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.history_dir = Some(dir.into());
+        self
+    }
+
+    /// Check `url` every `options.interval` until the duration or count runs out or Ctrl+C,
+    /// appending each check to the host's history, then print a summary of the session.
+    pub async fn monitor(&self, url: &str, options: &MonitorOptions) -> Result<MonitorReport, Box<dyn std::error::Error>> {
+        println!("📊 Starting {} monitoring for: {}",
+            if options.smart { "smart AI" } else { "basic" }, url);
+        let mut plan = format!("every {:.0?}", options.interval);
+        if let Some(duration) = options.duration {
+            plan.push_str(&format!(" for {:.0?}", duration));
+        }
+        if let Some(count) = options.count {
+            plan.push_str(&format!(", {} checks", count));
+        }
+        if options.duration.is_none() && options.count.is_none() {
+            plan.push_str(" until Ctrl+C");
+        }
+        println!("⏱️  Checking {}", plan);
+
+        let history_dir = match &self.history_dir {
+            Some(dir) => dir.clone(),
+            None => MonitorHistory::default_dir()?,
+        };
+        let history = MonitorHistory::new(history_dir);
+        let host = MonitorHistory::host_key(url);
+        let webhook_client = reqwest::Client::new();
+
+        let mut ticker = interval(options.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let deadline = options.duration.map(|duration| Instant::now() + duration);
+        let stop = tokio::signal::ctrl_c();
+        tokio::pin!(stop);
+
+        let mut session = Vec::new();
+        let mut historical_data: Vec<MonitorResult> = Vec::new();
+        let mut previous: Option<String> = None;
         let mut check_count = 0;
-        let mut historical_data = Vec::new();
-        
+
         loop {
+            let until_deadline = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = tokio::time::sleep(until_deadline.unwrap_or_default()), if until_deadline.is_some() => break,
+                _ = &mut stop => {
+                    println!("\n🛑 Stopping monitor...");
+                    break;
+                }
+            }
+
             check_count += 1;
             println!("\n🔍 Health check #{}", check_count);
-            
-            let result = self.perform_health_check(url).await?;
+            let result = tokio::select! {
+                result = self.perform_health_check(url) => result?,
+                _ = &mut stop => {
+                    println!("\n🛑 Stopping monitor...");
+                    break;
+                }
+            };
+
+            let record = MonitorRecord::from(&result);
+            if let Err(e) = history.append(&host, &record) {
+                println!("⚠️  Could not save history: {}", e);
+            }
+            if let Some(webhook) = &options.alert_webhook {
+                if let Some(message) = send_alert(&webhook_client, webhook, &result, previous.as_deref()).await {
+                    println!("{}", message);
+                }
+            }
+            previous = Some(result.status.clone());
+            session.push(record);
             historical_data.push(result);
-            
-            if smart && check_count % 3 == 0 {
+
+            if options.smart && check_count % 3 == 0 {
                 // Every 3rd check, do AI analysis
                 self.ai_analysis(&historical_data).await?;
             }
-            
+
             // Keep only last 10 results
             if historical_data.len() > 10 {
                 historical_data.drain(0..1);
             }
-            
-            interval.tick().await;
-            
-            // For demo purposes, break after 5 checks
-            if check_count >= 5 {
+
+            if options.count.is_some_and(|count| check_count >= count) {
                 break;
             }
         }
-        
-        println!("\n✅ Monitoring session complete!");
-        Ok(())
+
+        let report = MonitorReport::new(&session);
+        println!("\n{}", style("Monitoring session summary").cyan().bold());
+        report.print();
+        println!("💾 History: {}", style(history.path(&host).display()).green());
+        Ok(report)
     }
-    
-    async fn perform_health_check(&self, url: &str) -> Result<MonitorResult, Box<dyn std::error::Error>> {
+
+    /// Check every target on its own schedule, staggered across the interval so they don't
+    /// all fire at once, showing a live [`Dashboard`] until the duration or count runs out,
+    /// `q` or Ctrl+C. History and alerts work as for a single URL. Returns each target's summary.
+    pub async fn monitor_targets(&self, targets: &[MonitorTarget], options: &MonitorOptions) -> Result<Vec<MonitorReport>, Box<dyn std::error::Error>> {
+        if targets.is_empty() {
+            return Err("Nothing to monitor".into());
+        }
+        println!("📊 Monitoring {} endpoints every {:.0?}", targets.len(), options.interval);
+        let history_dir = match &self.history_dir {
+            Some(dir) => dir.clone(),
+            None => MonitorHistory::default_dir()?,
+        };
+        let history = MonitorHistory::new(history_dir);
+        let webhook_client = reqwest::Client::new();
+
+        let (sender, mut results) = tokio::sync::mpsc::unbounded_channel::<(usize, MonitorResult)>();
+        let start = tokio::time::Instant::now();
+        let deadline = options.duration.map(|duration| start + duration);
+        let stagger = options.interval / targets.len() as u32;
+        let mut checkers = tokio::task::JoinSet::new();
+        for (index, target) in targets.iter().enumerate() {
+            let (sender, url, count) = (sender.clone(), target.url.clone(), options.count);
+            let mut ticker = tokio::time::interval_at(start + stagger * index as u32, options.interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            checkers.spawn(async move {
+                let mut checks = 0;
+                while count.is_none_or(|count| checks < count) {
+                    tokio::select! {
+                        _ = ticker.tick() => {}
+                        _ = tokio::time::sleep_until(deadline.unwrap_or(start)), if deadline.is_some() => break,
+                    }
+                    checks += 1;
+                    if sender.send((index, Self::check(&url).await)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        let mut dashboard = Dashboard::new(targets);
+        dashboard.attach();
+        let stop_keys = Arc::new(AtomicBool::new(false));
+        let quit = quit_key(dashboard.raw_mode, stop_keys.clone());
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(quit, ctrl_c);
+
+        let mut sessions: Vec<Vec<MonitorRecord>> = vec![Vec::new(); targets.len()];
+        let mut previous: Vec<Option<String>> = vec![None; targets.len()];
+        loop {
+            tokio::select! {
+                received = results.recv() => {
+                    let Some((index, result)) = received else { break };
+                    let record = MonitorRecord::from(&result);
+                    if let Err(e) = history.append(&MonitorHistory::host_key(&result.url), &record) {
+                        dashboard.note(&format!("⚠️  Could not save history: {}", e));
+                    }
+                    dashboard.show(index, &result);
+                    if let Some(webhook) = &options.alert_webhook {
+                        if let Some(message) = send_alert(&webhook_client, webhook, &result, previous[index].as_deref()).await {
+                            dashboard.note(&message);
+                        }
+                    }
+                    previous[index] = Some(result.status);
+                    sessions[index].push(record);
+                }
+                _ = &mut quit => break,
+                _ = &mut ctrl_c => break,
+            }
+        }
+        stop_keys.store(true, Ordering::Relaxed);
+        checkers.abort_all();
+        drop(dashboard);
+
+        println!("\n{}", style("Monitoring session summary").cyan().bold());
+        println!("   {:<44} {:>7} {:>8} {:>7} {:>7}", "Endpoint", "Checks", "Uptime", "p50", "p95");
+        let reports: Vec<MonitorReport> = sessions.iter().map(|records| MonitorReport::new(records)).collect();
+        for (target, report) in targets.iter().zip(&reports) {
+            let uptime = format!("{:>8}", format!("{:.1}%", report.uptime_pct));
+            let uptime = if report.uptime_pct >= 99.0 { style(uptime).green() } else { style(uptime).red() };
+            println!("   {:<44} {:>7} {} {:>5}ms {:>5}ms",
+                target.name, report.checks, uptime, report.p50.as_millis(), report.p95.as_millis());
+        }
+        Ok(reports)
+    }
+
+    /// Run a single health check, print and return its structured result.
+    pub async fn perform_health_check(&self, url: &str) -> Result<MonitorResult, Box<dyn std::error::Error>> {
+        let result = Self::check(url).await;
+        self.print_health_status(&result);
+        Ok(result)
+    }
+
+    /// Run a single health check without printing anything.
+    pub async fn check(url: &str) -> MonitorResult {
+        let checked_at = Utc::now();
         let start_time = SystemTime::now();
         let call_command = CallCommand::new();
-        
-        // Try to make the request
+        let url = if url.contains("://") { url.to_string() } else { format!("http://{}", url) };
+
         let mut status = "healthy".to_string();
         let mut issues = Vec::new();
-        
-        match call_command.execute_with_response(&["GET", url]).await {
-            Ok(response) => {
-                let response_time = start_time.elapsed()?;
-                
+
+        let options = CallOptions { method: "GET".to_string(), url: url.clone(), ..Default::default() };
+        let result = match call_command.send_quiet(options).await {
+            Ok((code, response)) => {
+                let response_time = start_time.elapsed().unwrap_or_default();
+
                 // Check response time
                 if response_time > Duration::from_millis(1000) {
                     status = "slow".to_string();
                     issues.push(format!("Slow response: {}ms", response_time.as_millis()));
                 }
-                
+
                 // Check response content
                 if response.contains("error") || response.contains("Error") {
                     status = "warning".to_string();
                     issues.push("Response contains error messages".to_string());
                 }
-                
-                if response.len() == 0 {
+
+                if response.is_empty() {
                     status = "warning".to_string();
                     issues.push("Empty response body".to_string());
                 }
-                
-                let result = MonitorResult {
-                    url: url.to_string(),
-                    status,
-                    response_time,
-                    issues,
-                    recommendations: vec![],
-                };
-                
-                self.print_health_status(&result);
-                Ok(result)
-            }
-            Err(e) => {
-                let result = MonitorResult {
-                    url: url.to_string(),
-                    status: "error".to_string(),
-                    response_time: Duration::from_millis(0),
-                    issues: vec![format!("Request failed: {}", e)],
-                    recommendations: vec![],
-                };
-                
-                self.print_health_status(&result);
-                Ok(result)
+
+                if code >= 500 {
+                    status = "error".to_string();
+                    issues.push(format!("Server error: HTTP {}", code));
+                } else if code >= 400 {
+                    status = "warning".to_string();
+                    issues.push(format!("Client error: HTTP {}", code));
+                }
+
+                MonitorResult { url, status, response_time, issues, recommendations: vec![], checked_at }
             }
-        }
+            Err(e) => MonitorResult {
+                url,
+                status: "error".to_string(),
+                response_time: Duration::from_millis(0),
+                issues: vec![format!("Request failed: {}", e)],
+                recommendations: vec![],
+                checked_at,
+            },
+        };
+        result
     }
-    
+
     fn print_health_status(&self, result: &MonitorResult) {
-        let emoji = match result.status.as_str() {
-            "healthy" => "💚",
-            "warning" => "🟡",
-            "slow" => "🟠",
-            "error" => "🔴",
-            _ => "⚪",
-        };
+        let emoji = status_emoji(&result.status);
         
         println!("{} Status: {} ({}ms)", 
             emoji, result.status, result.response_time.as_millis());
@@ -141,14 +722,20 @@ impl MonitorCommand {
     }
     
     async fn ai_analysis(&self, historical_data: &[MonitorResult]) -> Result<(), Box<dyn std::error::Error>> {
+        if self.config.is_offline() {
+            print_ai_skipped("AI monitoring analysis");
+            return Ok(());
+        }
+
         println!("\n🤖 AI Analysis of monitoring data...");
-        
-        let api_key = self.config.anthropic_api_key.as_ref()
-            .ok_or("API key not configured for AI analysis")?;
 
-        let ai_client = ClientBuilder::default()
-            .api_key(api_key.clone())
-            .build()?;
+        let ai_client = match ai::optional_client(&self.config, "monitor") {
+            Ok(ai_client) => ai_client,
+            Err(e) => {
+                print_ai_failed("AI monitoring analysis", e.as_ref());
+                return Ok(());
+            }
+        };
 
         let analysis_data = json!({
             "monitoring_results": historical_data.iter().map(|r| {
@@ -174,20 +761,10 @@ impl MonitorCommand {
             serde_json::to_string_pretty(&analysis_data)?
         );
 
-        let response = ai_client.messages(MessagesRequestBuilder::default()
-            .messages(vec![Message {
-                role: Role::User,
-                content: vec![ContentBlock::Text { text: prompt }],
-            }])
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(1000_usize)
-            .build()?
-        ).await?;
+        let text = ai_client.ask(&prompt).await?;
 
-        if let Some(ContentBlock::Text { text }) = response.content.first() {
-            println!("📈 AI Insights:");
-            println!("{}", text);
-        }
+        println!("📈 AI Insights:");
+        println!("{}", text);
 
         Ok(())
     }
@@ -1,33 +1,388 @@
-use crate::models::metrics::{Metrics, RequestMetric, MetricsSummary};
-use reqwest::Client;
+use crate::models::metrics::{classify_error, error_chain, sparkline, ErrorKind, Metrics, RequestMetric, MetricsSummary};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::{Client, Method};
+use hyper_014::client::connect::HttpInfo;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::io::Write;
 use console::style;
-use anthropic::client::{Client as AnthropicClient, ClientBuilder};
-use anthropic::types::{ContentBlock, Message, MessagesRequestBuilder, Role};
-use crate::config::Config;
+use crate::ai::{self, AiClient};
+use crate::config::{Config, Environment};
+use crate::commands::{print_ai_failed, print_ai_skipped};
+use crate::auth::TokenManager;
+use crate::commands::call::{HttpProtocol, ResolveOverride};
+use crate::tls::ClientTls;
+use crate::units::parse_positive_duration;
+use crate::output;
+use crate::{noteln, out, outln};
+
+/// Limits that turn a perf run into a pass/fail gate.
+#[derive(Debug, Clone, Default)]
+pub struct PerfThresholds {
+    pub max_p95: Option<Duration>,
+    pub max_avg: Option<Duration>,
+    /// Maximum error rate as a percentage (0-100).
+    pub max_error_rate_pct: Option<f64>,
+}
+
+impl PerfThresholds {
+    pub fn is_empty(&self) -> bool {
+        self.max_p95.is_none() && self.max_avg.is_none() && self.max_error_rate_pct.is_none()
+    }
+
+    /// Every violated threshold, described with actual vs expected values.
+    pub fn check(&self, summary: &MetricsSummary) -> Vec<String> {
+        let mut violations = Vec::new();
+        if let Some(max) = self.max_p95 {
+            if summary.p95_latency > max {
+                violations.push(format!("p95 latency {}ms exceeds {}ms", summary.p95_latency.as_millis(), max.as_millis()));
+            }
+        }
+        if let Some(max) = self.max_avg {
+            if summary.avg_latency > max {
+                violations.push(format!("average latency {}ms exceeds {}ms", summary.avg_latency.as_millis(), max.as_millis()));
+            }
+        }
+        if let Some(max) = self.max_error_rate_pct {
+            let actual = summary.error_rate * 100.0;
+            if actual > max {
+                violations.push(format!("error rate {:.1}% exceeds {}%", actual, max));
+            }
+        }
+        violations
+    }
+}
+
+/// A period of the test run at a fixed number of concurrent users.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stage {
+    pub users: u32,
+    pub duration: Duration,
+}
+
+/// Parse `--stages` syntax: `USERS:DURATION` pairs separated by commas, e.g. `10:30s,50:1m`.
+pub fn parse_stages(spec: &str) -> Result<Vec<Stage>, Box<dyn std::error::Error>> {
+    let stages = spec.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|stage| {
+            let (users, duration) = stage.split_once(':')
+                .ok_or_else(|| format!("Invalid stage '{}': expected USERS:DURATION like 10:30s", stage))?;
+            let users: u32 = users.trim().parse()
+                .map_err(|_| format!("Invalid user count in stage '{}'", stage))?;
+            let duration = parse_positive_duration(duration)
+                .map_err(|e| format!("Invalid duration in stage '{}': {}", stage, e))?;
+            Ok(Stage { users, duration })
+        })
+        .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+    if stages.is_empty() {
+        return Err("--stages needs at least one USERS:DURATION stage".into());
+    }
+    Ok(stages)
+}
+
+/// How many users should be active at any point of a run.
+#[derive(Debug, Clone)]
+pub struct LoadSchedule {
+    pub stages: Vec<Stage>,
+    /// Linear warm-up from zero to the first stage's users.
+    pub ramp: Duration,
+}
+
+impl LoadSchedule {
+    pub fn total_duration(&self) -> Duration {
+        self.stages.iter().map(|s| s.duration).sum()
+    }
+
+    /// Index of the stage running at `elapsed` (the last stage once the run is over).
+    pub fn stage_at(&self, elapsed: Duration) -> usize {
+        let mut end = Duration::ZERO;
+        for (i, stage) in self.stages.iter().enumerate() {
+            end += stage.duration;
+            if elapsed < end {
+                return i;
+            }
+        }
+        self.stages.len().saturating_sub(1)
+    }
+
+    /// Target concurrency at `elapsed`.
+    pub fn target_at(&self, elapsed: Duration) -> u32 {
+        let Some(stage) = self.stages.get(self.stage_at(elapsed)) else {
+            return 0;
+        };
+        if elapsed < self.ramp && !self.ramp.is_zero() {
+            let first = self.stages[0].users;
+            let ramped = (first as f64 * elapsed.as_secs_f64() / self.ramp.as_secs_f64()).ceil() as u32;
+            return ramped.clamp(1, first.max(1)).min(stage.users.max(1));
+        }
+        stage.users
+    }
+}
+
+/// Open-loop pacing for `--rps`: request `n` is due `n / rps` seconds into the run, whether or
+/// not earlier requests have finished. Times are offsets from the start of the run, so the
+/// scheduler never reads a clock itself.
+#[derive(Debug, Clone)]
+pub struct RateScheduler {
+    rps: f64,
+    duration: Duration,
+    dispatched: u64,
+    /// How late each dispatched request left, compared to when it was due.
+    lags: Vec<Duration>,
+}
+
+impl RateScheduler {
+    pub fn new(rps: f64, duration: Duration) -> Self {
+        Self { rps, duration, dispatched: 0, lags: Vec::new() }
+    }
+
+    /// When the next request is due, or `None` once every request of the run has been sent.
+    pub fn next_due(&self) -> Option<Duration> {
+        let due = Duration::from_secs_f64(self.dispatched as f64 / self.rps);
+        (due < self.duration).then_some(due)
+    }
+
+    /// Record that the next request left at `now` and return how late it was.
+    pub fn dispatch(&mut self, now: Duration) -> Duration {
+        let lag = self.next_due().map_or(Duration::ZERO, |due| now.saturating_sub(due));
+        self.dispatched += 1;
+        self.lags.push(lag);
+        lag
+    }
+
+    /// Requests the run should send in total.
+    pub fn scheduled(&self) -> u64 {
+        (self.rps * self.duration.as_secs_f64()).ceil() as u64
+    }
+
+    pub fn dispatched(&self) -> u64 {
+        self.dispatched
+    }
+
+    /// Dispatch lag below which `percentile`% of requests left.
+    pub fn lag_percentile(&self, percentile: usize) -> Duration {
+        let mut sorted = self.lags.clone();
+        sorted.sort();
+        let index = (percentile * sorted.len() / 100).saturating_sub(1);
+        sorted.get(index).copied().unwrap_or_default()
+    }
+
+    pub fn summary(&self, completed: usize) -> RateSummary {
+        RateSummary {
+            target_rps: self.rps,
+            achieved_rps: completed as f64 / self.duration.as_secs_f64(),
+            scheduled: self.scheduled(),
+            dispatched: self.dispatched,
+            lag_p50: self.lag_percentile(50),
+            lag_p95: self.lag_percentile(95),
+            lag_p99: self.lag_percentile(99),
+            lag_max: self.lags.iter().max().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// How closely a `--rps` run kept to its target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateSummary {
+    pub target_rps: f64,
+    /// Completed responses (errors included) per second of the run.
+    pub achieved_rps: f64,
+    pub scheduled: u64,
+    pub dispatched: u64,
+    pub lag_p50: Duration,
+    pub lag_p95: Duration,
+    pub lag_p99: Duration,
+    pub lag_max: Duration,
+}
+
+impl RateSummary {
+    /// Every scheduled request went out and at least 95% of the target rate completed.
+    pub fn target_met(&self) -> bool {
+        self.dispatched >= self.scheduled && self.achieved_rps >= self.target_rps * 0.95
+    }
+}
 
 pub struct PerfCommand {
     client: Client,
-    metrics: Arc<Metrics>,
-    ai_client: AnthropicClient,
+    /// Metrics of the current (or last) run; replaced at the start of every run.
+    metrics: std::sync::Mutex<Arc<Metrics>>,
+    /// AI settings for the insights; none are asked for offline.
+    config: Config,
+    thresholds: PerfThresholds,
+    environment: Environment,
+    ramp: Duration,
+    stages: Vec<Stage>,
+    /// Per-stage metrics from the last staged run.
+    stage_metrics: std::sync::Mutex<Vec<Arc<Metrics>>>,
+    tokens: Option<Arc<TokenManager>>,
+    protocol: HttpProtocol,
+    /// `--rps` target and the cap on requests in flight.
+    rate: Option<(f64, usize)>,
+    /// Pacing results of the last `--rps` run.
+    rate_summary: std::sync::Mutex<Option<RateSummary>>,
+    warmup: Duration,
+    trim_outliers: Option<f64>,
+    /// Off for `--no-keepalive`: every request opens its own connection.
+    keepalive: bool,
+    /// `--resolve` pins; the one matching the URL is applied when the run starts.
+    resolve: Vec<ResolveOverride>,
+    tls: ClientTls,
+    /// Longest a request may take before it counts as timed out.
+    timeout: Duration,
+    connect_timeout: Option<Duration>,
 }
 
+/// Per-request timeout when `--timeout` isn't given, so a hung server shows up as timeouts
+/// instead of stalling users until the run ends.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
 impl PerfCommand {
     pub fn new(config: &Config) -> Self {
-        let api_key = config.anthropic_api_key.clone()
-            .unwrap_or_default();
-
-        Self {
+        let mut perf = Self {
             client: Client::new(),
-            metrics: Arc::new(Metrics::new()),
-            ai_client: ClientBuilder::default()
-                .api_key(api_key)
-                .build()
-                .unwrap(),
+            metrics: std::sync::Mutex::new(Arc::new(Metrics::new())),
+            config: config.clone(),
+            thresholds: PerfThresholds::default(),
+            environment: config.active_environment(),
+            ramp: Duration::ZERO,
+            stages: Vec::new(),
+            stage_metrics: std::sync::Mutex::new(Vec::new()),
+            tokens: None,
+            protocol: HttpProtocol::Auto,
+            rate: None,
+            rate_summary: std::sync::Mutex::new(None),
+            warmup: Duration::ZERO,
+            trim_outliers: None,
+            keepalive: true,
+            resolve: Vec::new(),
+            tls: ClientTls::default(),
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+            connect_timeout: None,
+        };
+        perf.client = perf.build_client();
+        perf
+    }
+
+    /// Load the API for `warmup` before measuring; those requests are sent at the first
+    /// stage's concurrency (or the target rate) but left out of the results.
+    pub fn with_warmup(mut self, warmup: Duration) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Also report average and spread without the slowest `fraction` of responses.
+    pub fn with_trimmed_outliers(mut self, fraction: f64) -> Self {
+        self.trim_outliers = Some(fraction);
+        self
+    }
+
+    /// Send `rps` requests per second for the whole run instead of keeping users busy,
+    /// with at most `max_in_flight` requests waiting for a response at once.
+    pub fn with_rate(mut self, rps: f64, max_in_flight: usize) -> Self {
+        self.rate = Some((rps, max_in_flight.max(1)));
+        self
+    }
+
+    /// Target vs achieved rate and dispatch lag of the last `--rps` run.
+    pub fn rate_summary(&self) -> Option<RateSummary> {
+        self.rate_summary.lock().unwrap().clone()
+    }
+
+    /// Send every request over this HTTP version; responses in another version count as errors.
+    pub fn with_protocol(mut self, protocol: HttpProtocol) -> Self {
+        self.protocol = protocol;
+        self.client = self.build_client();
+        self
+    }
+
+    /// With `false`, close every connection after its response instead of reusing it, to
+    /// measure what connection setup costs.
+    pub fn with_keepalive(mut self, keepalive: bool) -> Self {
+        self.keepalive = keepalive;
+        self.client = self.build_client();
+        self
+    }
+
+    /// Connect to the pinned address instead of asking DNS for the URL's host and port.
+    pub fn with_resolve(mut self, resolve: Vec<ResolveOverride>) -> Self {
+        self.resolve = resolve;
+        self
+    }
+
+    /// Present a client certificate and trust extra CAs (`--cert`, `--key`, `--cacert`).
+    pub fn with_tls(mut self, tls: ClientTls) -> Self {
+        self.tls = tls;
+        self.client = self.build_client();
+        self
+    }
+
+    /// Give up on a request after `timeout`, or on its connection after `connect_timeout`;
+    /// either is recorded as a timeout.
+    pub fn with_timeouts(mut self, timeout: Duration, connect_timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self.connect_timeout = connect_timeout;
+        self.client = self.build_client();
+        self
+    }
+
+    fn build_client(&self) -> Client {
+        self.client_builder().build().expect("HTTP client")
+    }
+
+    fn client_builder(&self) -> reqwest::ClientBuilder {
+        let mut builder = self.protocol.configure(self.tls.apply(Client::builder())).timeout(self.timeout);
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
         }
+        if self.keepalive { builder } else { builder.pool_max_idle_per_host(0) }
+    }
+
+    /// Authenticate every request with an OAuth2 bearer token, refreshed as it nears expiry.
+    pub fn with_token_manager(mut self, tokens: Arc<TokenManager>) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+
+    /// Start users gradually, spreading them linearly over `ramp`.
+    pub fn with_ramp(mut self, ramp: Duration) -> Self {
+        self.ramp = ramp;
+        self
+    }
+
+    /// Vary concurrency over time; replaces the `users`/`duration` passed to `run`.
+    pub fn with_stages(mut self, stages: Vec<Stage>) -> Self {
+        self.stages = stages;
+        self
+    }
+
+    /// Latency stats for each stage of the last run, when `with_stages` was used.
+    pub fn stage_summaries(&self) -> Vec<(Stage, MetricsSummary)> {
+        let metrics = self.stage_metrics.lock().unwrap();
+        self.stages.iter().cloned().zip(metrics.iter().map(|m| m.summary())).collect()
+    }
+
+    /// Fail the run when the final summary violates any of these thresholds.
+    pub fn with_thresholds(mut self, thresholds: PerfThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Build the AI client on demand so offline runs never touch a provider.
+    fn ai_client(&self) -> Result<Box<dyn AiClient>, Box<dyn std::error::Error>> {
+        ai::optional_client(&self.config, "perf")
+    }
+
+    /// Snapshot of the metrics collected so far by the current or last run.
+    pub fn summary(&self) -> MetricsSummary {
+        self.metrics.lock().unwrap().summary()
+    }
+
+    /// Responses per negotiated HTTP version in the current or last run.
+    pub fn protocols(&self) -> std::collections::BTreeMap<String, usize> {
+        self.metrics.lock().unwrap().protocols()
     }
 
     async fn get_performance_analysis(&self, summary: &MetricsSummary, duration: Duration) -> Result<String, Box<dyn std::error::Error>> {
@@ -56,208 +411,534 @@ impl PerfCommand {
             summary.peak_rps
         );
 
-        let messages = vec![Message {
-            role: Role::User,
-            content: vec![ContentBlock::Text { text: prompt }],
-        }];
+        let text = self.ai_client()?.ask(&prompt).await?;
+        Ok(text.trim().to_string())
+    }
 
-        let message_request = MessagesRequestBuilder::default()
-            .messages(messages)
-            .model("claude-3-haiku-20240307".to_string())
-            .max_tokens(300_usize)
-            .build()?;
+    pub async fn run(
+        &self,
+        url: &str,
+        users: u32,
+        duration: Duration,
+        method: &str,
+        body: Option<&str>,
+        headers: &HashMap<String, String>,
+    ) -> Result<MetricsSummary, Box<dyn std::error::Error>> {
+        // Resolve `{{name}}` placeholders from the active environment before anything is sent
+        let url = &self.environment.substitute(url)?;
+        let body = body.map(|b| self.environment.substitute(b)).transpose()?;
+        let body = body.as_deref();
+        let method: Method = method.to_uppercase().parse()?;
+        let mut header_map = HeaderMap::new();
+        for (key, value) in headers {
+            let value = self.environment.substitute(value)?;
+            header_map.insert(HeaderName::from_bytes(key.trim().as_bytes())?, HeaderValue::from_str(value.trim())?);
+        }
+        if body.is_some() && !header_map.contains_key(CONTENT_TYPE) {
+            header_map.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        }
 
-        let response = self.ai_client.messages(message_request).await?;
-        
-        if let Some(ContentBlock::Text { text }) = response.content.first() {
-            Ok(text.trim().to_string())
-        } else {
-            Ok("Analysis not available.".to_string())
+        // Fetch the first token up front so bad credentials fail the run instead of every request
+        if let Some(tokens) = &self.tokens {
+            tokens.token().await.map_err(|e| e.to_string())?;
         }
-    }
 
-    pub async fn run(&self, url: &str, users: u32, duration: Duration, method: &str, body: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
-        println!("\n🚀 Performance Test Configuration");
-        println!("═══════════════════════════════");
-        println!("URL: {}", style(url).cyan());
-        println!("Method: {}", style(method).cyan());
-        println!("Concurrent Users: {}", style(users).cyan());
-        println!("Duration: {}s", style(duration.as_secs()).cyan());
+        let schedule = LoadSchedule {
+            stages: if self.stages.is_empty() { vec![Stage { users, duration }] } else { self.stages.clone() },
+            ramp: self.ramp,
+        };
+        let duration = schedule.total_duration();
+
+        noteln!("\n🚀 Performance Test Configuration");
+        noteln!("═══════════════════════════════");
+        noteln!("URL: {}", style(url).cyan());
+        noteln!("Method: {}", style(&method).cyan());
+        if self.protocol != HttpProtocol::Auto {
+            noteln!("Protocol: {}", style(self.protocol.label()).cyan());
+        }
+        if let Some((rps, max_in_flight)) = self.rate {
+            noteln!("Target Rate: {} req/s (up to {} in flight)", style(rps).cyan(), max_in_flight);
+        } else if self.stages.is_empty() {
+            noteln!("Concurrent Users: {}", style(users).cyan());
+        } else {
+            let stages: Vec<String> = self.stages.iter()
+                .map(|s| format!("{} users for {:.0}s", s.users, s.duration.as_secs_f64()))
+                .collect();
+            noteln!("Stages: {}", style(stages.join(" → ")).cyan());
+        }
+        let pin = ResolveOverride::matching(&self.resolve, url);
+        if let Some(pin) = pin {
+            noteln!("Resolve: {}", style(pin).cyan());
+        }
+        if !self.keepalive {
+            noteln!("Keep-alive: {}", style("off (a new connection per request)").cyan());
+        }
+        if !self.ramp.is_zero() {
+            noteln!("Ramp-up: {:.1}s", style(self.ramp.as_secs_f64()).cyan());
+        }
+        noteln!("Duration: {}", style(format!("{:?}", duration)).cyan());
+        if !self.warmup.is_zero() {
+            noteln!("Warmup: {:.1}s (not measured)", style(self.warmup.as_secs_f64()).cyan());
+        }
+        if !headers.is_empty() {
+            let mut names: Vec<&String> = headers.keys().collect();
+            names.sort();
+            noteln!("Headers: {}", style(names.iter().map(|n| n.as_str()).collect::<Vec<_>>().join(", ")).cyan());
+        }
+        if self.tokens.is_some() {
+            noteln!("Auth: {}", style("OAuth2 bearer token").cyan());
+        }
         if let Some(body) = body {
-            println!("Body: {}", style(body).cyan());
+            noteln!("Body: {}", style(body).cyan());
         }
-        println!();
+        noteln!();
 
-        let metrics = self.metrics.clone();
-        let running = Arc::new(AtomicBool::new(true));
-        let mut handles = Vec::new();
+        let measured_from = SystemTime::now() + self.warmup;
+        let new_metrics = || {
+            let metrics = Metrics::new().with_warmup(measured_from);
+            match self.trim_outliers {
+                Some(fraction) => metrics.with_trimmed_outliers(fraction),
+                None => metrics,
+            }
+        };
+        let metrics = Arc::new(new_metrics());
+        *self.metrics.lock().unwrap() = metrics.clone();
+        let stage_metrics: Vec<Arc<Metrics>> = schedule.stages.iter().map(|_| Arc::new(new_metrics())).collect();
+        *self.stage_metrics.lock().unwrap() = stage_metrics.clone();
+        let target = Arc::new(AtomicU32::new(0));
         let start_time = Instant::now();
 
-        // Spawn user tasks
-        for _ in 0..users {
-            let client = self.client.clone();
-            let url = url.to_string();
-            let metrics = metrics.clone();
-            let method = method.to_string();
-            let body = body.map(String::from);
-            let running = running.clone();
-
-            let handle = tokio::spawn(async move {
-                while running.load(Ordering::Relaxed) && start_time.elapsed() < duration {
-                    let request_start = SystemTime::now();
-                    
-                    let result = match method.as_str() {
-                        "POST" => {
-                            let req = client.post(&url);
-                            if let Some(body_content) = &body {
-                                req.header("Content-Type", "application/json")
-                                   .body(body_content.clone())
-                                   .send()
-                                   .await
-                            } else {
-                                req.send().await
-                            }
-                        },
-                        _ => client.get(&url).send().await,
-                    };
-
-                    match result {
-                        Ok(response) => {
-                            let duration = request_start.elapsed().unwrap();
-                            metrics.record(RequestMetric {
-                                duration,
-                                status: response.status().as_u16(),
-                                timestamp: request_start,
-                            });
-                        },
-                        Err(e) => {
-                            metrics.record_error(e.to_string());
-                        }
-                    }
-                }
-            });
-            handles.push(handle);
-        }
+        // The coordinator owns every worker and its stop flag, and adds or stops workers
+        // to follow the schedule's target concurrency.
+        let client = match pin {
+            Some(pin) => self.client_builder().resolve(&pin.host, pin.socket_addr()).build()?,
+            None => self.client.clone(),
+        };
+        let worker = Worker {
+            client,
+            protocol: self.protocol,
+            url: url.to_string(),
+            method,
+            headers: header_map,
+            tokens: self.tokens.clone(),
+            body: body.map(String::from),
+            metrics: metrics.clone(),
+            stage_metrics: Arc::new(stage_metrics),
+            schedule: Arc::new(schedule),
+            warmup: self.warmup,
+            start_time,
+        };
+        let run_length = self.warmup + duration;
+        let coordinator = match self.rate {
+            Some((rps, max_in_flight)) => {
+                let scheduler = RateScheduler::new(rps, run_length);
+                tokio::spawn(Self::pace(worker, scheduler, max_in_flight, target.clone()))
+            }
+            None => {
+                let target = target.clone();
+                tokio::spawn(async move {
+                    Self::coordinate(worker, target).await;
+                    None
+                })
+            }
+        };
 
-        // Progress reporting
-        while start_time.elapsed() < duration {
-            let summary = metrics.summary();
-            let current_rps = summary.total_requests as f64 / start_time.elapsed().as_secs_f64();
-            let ok_requests = (summary.total_requests as f64 * (1.0 - summary.error_rate)) as usize;
-            let ko_requests = summary.total_requests - ok_requests;
+        // Progress reporting, on a terminal only
+        let live = output::progress_visible();
+        while start_time.elapsed() < run_length {
+            if live {
+                let summary = metrics.summary();
+                let measuring = start_time.elapsed().saturating_sub(self.warmup);
+                let current_rps = summary.total_requests as f64 / measuring.as_secs_f64().max(0.001);
+                let ok_requests = (summary.total_requests as f64 * (1.0 - summary.error_rate)) as usize;
+                let ko_requests = summary.total_requests - ok_requests;
             
-            print!("\r⚡ {} req ({} ok, {} ko) | {} req/s | lat: avg {}ms p95 {}ms | {}", 
-                style(summary.total_requests).magenta().bold(),
-                style(ok_requests).green().bold(),
-                style(ko_requests).red().bold(),
-                style(format!("{:.1}", current_rps)).cyan().bold(),
-                style(summary.avg_latency.as_millis()).yellow().bold(),
-                style(summary.p95_latency.as_millis()).yellow().bold(),
-                if summary.error_rate > 0.0 { 
-                    style(format!("errors: {:.1}%", summary.error_rate * 100.0)).red().bold().to_string()
-                } else {
-                    style("✓").green().bold().to_string()
-                }
-            );
-            std::io::stdout().flush()?;
+                out!("\r👥 {} | ⚡ {} req ({} ok, {} ko) | {} req/s | lat: avg {}ms p95 {}ms | {}", 
+                    style(target.load(Ordering::Relaxed)).blue().bold(),
+                    style(summary.total_requests).magenta().bold(),
+                    style(ok_requests).green().bold(),
+                    style(ko_requests).red().bold(),
+                    style(format!("{:.1}", current_rps)).cyan().bold(),
+                    style(summary.avg_latency.as_millis()).yellow().bold(),
+                    style(summary.p95_latency.as_millis()).yellow().bold(),
+                    if measuring.is_zero() {
+                        style(format!("🔥 warming up ({} req)", metrics.warmup_requests())).yellow().to_string()
+                    } else if summary.timeouts > 0 {
+                        style(format!("⏱️  {} timed out", summary.timeouts)).red().bold().to_string()
+                    } else if summary.error_rate > 0.0 { 
+                        style(format!("errors: {:.1}%", summary.error_rate * 100.0)).red().bold().to_string()
+                    } else {
+                        style("✓").green().bold().to_string()
+                    }
+                );
+                std::io::stdout().flush()?;
+            }
 
             tokio::time::sleep(Duration::from_millis(100)).await;
         }
 
-        println!();  // New line after progress
-        running.store(false, Ordering::SeqCst);
-
-        // Wait for all handles to complete
-        for handle in handles {
-            handle.await?;
+        if live {
+            outln!();  // New line after progress
         }
 
+        // Wait for the coordinator to stop and join its workers
+        let scheduler = coordinator.await?;
+
         // Print final summary
         let final_summary = metrics.summary();
+        // Pacing covers the whole run, warmup included
+        let rate_summary = scheduler.map(|scheduler| scheduler.summary(final_summary.total_requests + final_summary.warmup_requests));
+        *self.rate_summary.lock().unwrap() = rate_summary.clone();
         let ok_requests = (final_summary.total_requests as f64 * (1.0 - final_summary.error_rate)) as usize;
         let ko_requests = final_summary.total_requests - ok_requests;
 
-        println!("\n{}", style("Performance Results").cyan().bold());
-        println!("{}", style("═════════════════").cyan());
+        outln!("\n{}", style("Performance Results").cyan().bold());
+        outln!("{}", style("═════════════════").cyan());
         
         // Request statistics
-        println!("\n{}  {}", style("📊").cyan(), style("Requests").bold());
-        println!("   • Total: {}", style(final_summary.total_requests).magenta().bold());
+        outln!("\n{}  {}", style("📊").cyan(), style("Requests").bold());
+        outln!("   • Total: {}", style(final_summary.total_requests).magenta().bold());
+        if final_summary.warmup_requests > 0 {
+            outln!("   • Warmup: {} (not counted)", style(final_summary.warmup_requests).dim());
+        }
+        let protocols: Vec<String> = metrics.protocols().iter()
+            .map(|(version, count)| format!("{} ({})", version, count))
+            .collect();
+        if !protocols.is_empty() {
+            outln!("   • Protocol: {}", style(protocols.join(", ")).cyan());
+        }
         if final_summary.error_rate == 0.0 {
-            println!("   • OK: {} (100%)", style(ok_requests).green().bold());
-            println!("   • KO: {}", style("0").dim());
+            outln!("   • OK: {} (100%)", style(ok_requests).green().bold());
+            outln!("   • KO: {}", style("0").dim());
         } else {
-            println!("   • OK: {} ({:.1}%)", 
+            outln!("   • OK: {} ({:.1}%)", 
                 style(ok_requests).green().bold(),
                 style(format!("{:.1}", (1.0 - final_summary.error_rate) * 100.0)).green().bold().to_string()
             );
-            println!("   • KO: {} ({:.1}%)", 
+            outln!("   • KO: {} ({:.1}%)", 
                 style(ko_requests).red().bold(),
                 style(format!("{:.1}", final_summary.error_rate * 100.0)).red().bold().to_string()
             );
         }
+        if final_summary.timeouts > 0 {
+            outln!("   • Timed out: {} (no response within {}s, not counted above)",
+                style(final_summary.timeouts).red().bold(),
+                self.timeout.as_secs_f64()
+            );
+        }
 
         // Throughput metrics
-        println!("\n{}  {}", style("⚡").cyan(), style("Throughput").bold());
-        println!("   • Average: {} req/s", 
+        outln!("\n{}  {}", style("⚡").cyan(), style("Throughput").bold());
+        outln!("   • Average: {} req/s", 
             style(format!("{:.1}", final_summary.total_requests as f64 / duration.as_secs_f64())).yellow().bold()
         );
-        println!("   • Peak: {} req/s", style(final_summary.peak_rps).magenta().bold());
+        outln!("   • Peak: {} req/s", style(final_summary.peak_rps).magenta().bold());
+        let timeline = sparkline(&final_summary.requests_per_second, 60);
+        if !timeline.is_empty() {
+            outln!("   • Over time: {}", style(timeline).cyan());
+        }
+        if let Some(rate) = &rate_summary {
+            outln!("   • Target: {} req/s, achieved {} req/s ({} of {} requests sent)",
+                rate.target_rps,
+                style(format!("{:.1}", rate.achieved_rps)).yellow().bold(),
+                rate.dispatched,
+                rate.scheduled
+            );
+            outln!("   • Dispatch lag: p50 {}ms | p95 {}ms | p99 {}ms | max {}ms",
+                rate.lag_p50.as_millis(),
+                rate.lag_p95.as_millis(),
+                rate.lag_p99.as_millis(),
+                style(rate.lag_max.as_millis()).magenta()
+            );
+            if !rate.target_met() {
+                outln!("   {} {}", style("⚠️").yellow(), style(format!(
+                    "Target of {} req/s not met: the API or this machine couldn't keep up. Dispatch lag shows how far behind schedule requests left",
+                    rate.target_rps
+                )).yellow().bold());
+            }
+        }
         
         // Response time distribution
-        println!("\n{}  {}", style("⏱️").cyan(), style("Response Time Distribution").bold());
-        for (range, count) in &final_summary.response_time_ranges {
-            let percentage = (*count as f64 / final_summary.total_requests as f64) * 100.0;
-            println!("   • {}: {} ({:.1}%)", 
-                style(range).dim(),
-                style(count).yellow().bold(),
-                style(format!("{:.1}", percentage)).yellow().bold()
-            );
+        outln!("\n{}  {}", style("⏱️").cyan(), style("Response Time Distribution").bold());
+        for line in final_summary.histogram.render(40).lines() {
+            outln!("   {}", style(line).yellow());
         }
 
         // Detailed latency metrics
-        println!("\n{}  {}", style("📈").cyan(), style("Response Time Details").bold());
-        println!("   • Min: {}ms", style(final_summary.response_time_ranges.keys().next().unwrap_or(&"N/A".to_string())).yellow().bold());
-        println!("   • Average: {}ms", style(final_summary.avg_latency.as_millis()).yellow().bold());
-        println!("   • Median (p50): {}ms", style(final_summary.median_latency.as_millis()).yellow().bold());
-        println!("   • p95: {}ms", style(final_summary.p95_latency.as_millis()).yellow().bold());
-        println!("   • p99: {}ms", style(final_summary.p99_latency.as_millis()).magenta().bold());
-        println!("   • Max: {}ms", style(final_summary.response_time_ranges.keys().last().unwrap_or(&"N/A".to_string())).yellow().bold());
-        println!("   • Std Dev: {}ms", style(format!("±{:.1}", final_summary.std_dev_latency)).dim());
+        outln!("\n{}  {}", style("📈").cyan(), style("Response Time Details").bold());
+        outln!("   • Min: {}ms", style(final_summary.min_latency.as_millis()).yellow().bold());
+        outln!("   • Average: {}ms", style(final_summary.avg_latency.as_millis()).yellow().bold());
+        outln!("   • Median (p50): {}ms", style(final_summary.median_latency.as_millis()).yellow().bold());
+        outln!("   • p95: {}ms", style(final_summary.p95_latency.as_millis()).yellow().bold());
+        outln!("   • p99: {}ms", style(final_summary.p99_latency.as_millis()).magenta().bold());
+        outln!("   • Max: {}ms", style(final_summary.max_latency.as_millis()).yellow().bold());
+        outln!("   • Std Dev: {}ms", style(format!("±{:.1}", final_summary.std_dev_latency * 1000.0)).dim());
+        if let Some(trimmed) = &final_summary.trimmed {
+            let label = format!("trimmed, slowest {:.0}% ({}) dropped", trimmed.fraction * 100.0, trimmed.dropped);
+            outln!("   • Average ({}): {}ms", label, style(trimmed.avg_latency.as_millis()).yellow().bold());
+            outln!("   • Std Dev ({}): {}ms", label, style(format!("±{:.1}", trimmed.std_dev_latency * 1000.0)).dim());
+            outln!("   • Average and Std Dev above are raw; percentiles always use every response");
+        }
+
+        // Connection reuse: what keep-alive saves shows as the gap between the two averages
+        let connections = final_summary.connections;
+        if connections.new + connections.reused > 0 {
+            outln!("\n{}  {}", style("🔌").cyan(), style("Connections").bold());
+            outln!("   • New: {} (avg {}ms)", style(connections.new).magenta().bold(), style(connections.avg_new_latency.as_millis()).yellow());
+            outln!("   • Reused: {} (avg {}ms)", style(connections.reused).green().bold(), style(connections.avg_reused_latency.as_millis()).yellow());
+            if !self.keepalive {
+                outln!("   • Keep-alive was off: every request paid for connection setup");
+            } else if connections.new > 0 && connections.reused > 0 {
+                let saved = connections.avg_new_latency.as_secs_f64() - connections.avg_reused_latency.as_secs_f64();
+                outln!("   • A reused connection saved {:.1}ms per request", saved.max(0.0) * 1000.0);
+            }
+        }
 
         // Status code distribution
         if final_summary.error_rate > 0.0 {
-            println!("\n{}  {}", style("🔍").cyan(), style("Status Codes").bold());
+            outln!("\n{}  {}", style("🔍").cyan(), style("Status Codes").bold());
             let total = final_summary.total_requests as f64;
             let ok_perc = (ok_requests as f64 / total) * 100.0;
             let ko_perc = (ko_requests as f64 / total) * 100.0;
-            println!("   • 2xx: {} ({:.1}%)", 
+            outln!("   • 2xx: {} ({:.1}%)", 
                 style(ok_requests).green().bold(),
                 style(format!("{:.1}", ok_perc)).green().bold()
             );
             if ko_requests > 0 {
-                println!("   • Non-2xx: {} ({:.1}%)", 
+                outln!("   • Non-2xx: {} ({:.1}%)", 
                     style(ko_requests).red().bold(),
                     style(format!("{:.1}", ko_perc)).red().bold()
                 );
             }
         }
         
+        // Requests that got no response
+        if !final_summary.errors.is_empty() {
+            outln!("\n{}  {}", style("🧯").cyan(), style("Errors by type").bold());
+            let failed: usize = final_summary.errors.values().map(|e| e.count).sum();
+            for (kind, errors) in &final_summary.errors {
+                outln!("   • {}: {} ({:.1}%)",
+                    kind.label(),
+                    style(errors.count).red().bold(),
+                    errors.count as f64 * 100.0 / failed as f64
+                );
+                outln!("     {}", style(&errors.example).dim());
+            }
+        }
+
+        // Per-stage breakdown
+        if self.stages.len() > 1 {
+            outln!("\n{}  {}", style("🪜").cyan(), style("Stages").bold());
+            for (i, (stage, summary)) in self.stage_summaries().iter().enumerate() {
+                outln!("   • #{} {} users/{:.0}s: {} req | avg {}ms | p95 {}ms | p99 {}ms | errors {:.1}%",
+                    i + 1,
+                    style(stage.users).cyan(),
+                    stage.duration.as_secs_f64(),
+                    style(summary.total_requests).magenta(),
+                    style(summary.avg_latency.as_millis()).yellow(),
+                    style(summary.p95_latency.as_millis()).yellow(),
+                    style(summary.p99_latency.as_millis()).magenta(),
+                    summary.error_rate * 100.0
+                );
+            }
+        }
+
         // AI Analysis
-        println!("\n{}  {}", style("🤖").cyan(), style("AI Insights").bold());
-        match self.get_performance_analysis(&final_summary, duration).await {
-            Ok(analysis) => {
-                for (_i, line) in analysis.lines().enumerate() {
-                    if !line.trim().is_empty() {
-                        println!("   {} {}", style("•").dim(), style(line.trim()).dim());
+        outln!("\n{}  {}", style("🤖").cyan(), style("AI Insights").bold());
+        if self.config.is_offline() {
+            out!("   ");
+            print_ai_skipped("AI insights");
+        } else {
+            match self.get_performance_analysis(&final_summary, duration).await {
+                Ok(analysis) => {
+                    for line in analysis.lines() {
+                        if !line.trim().is_empty() {
+                            outln!("   {} {}", style("•").dim(), style(line.trim()).dim());
+                        }
                     }
                 }
+                Err(e) => {
+                    out!("   ");
+                    print_ai_failed("AI insights", e.as_ref());
+                }
             }
-            Err(_) => println!("   {} Analysis not available", style("•").dim()),
         }
+        outln!();
+
+        // Threshold gate, evaluated last so the full report is always shown
+        let violations = self.thresholds.check(&final_summary);
+        if !violations.is_empty() {
+            return Err(format!("Performance thresholds violated:\n  • {}", violations.join("\n  • ")).into());
+        }
+        if !self.thresholds.is_empty() {
+            outln!("{} All performance thresholds met", style("✅").green());
+        }
+        Ok(final_summary)
+    }
+
+    /// Spawn and stop workers until the schedule ends, then join them all.
+    async fn coordinate(worker: Worker, target: Arc<AtomicU32>) {
+        let worker = Arc::new(worker);
+        let total = worker.run_length();
+        let mut active: Vec<(tokio::task::JoinHandle<()>, Arc<AtomicBool>)> = Vec::new();
+        let mut stopped = Vec::new();
+
+        loop {
+            let elapsed = worker.start_time.elapsed();
+            if elapsed >= total {
+                break;
+            }
+            let wanted = match elapsed < worker.warmup {
+                true => worker.schedule.stages.first().map_or(0, |stage| stage.users) as usize,
+                false => worker.schedule.target_at(worker.scheduled_elapsed()) as usize,
+            };
+            target.store(wanted as u32, Ordering::Relaxed);
+
+            while active.len() < wanted {
+                let running = Arc::new(AtomicBool::new(true));
+                let handle = tokio::spawn(worker.clone().run(running.clone()));
+                active.push((handle, running));
+            }
+            while active.len() > wanted {
+                let (handle, running) = active.pop().unwrap();
+                running.store(false, Ordering::SeqCst);
+                stopped.push(handle);
+            }
+
+            tokio::time::sleep(Duration::from_millis(20).min(total.saturating_sub(elapsed))).await;
+        }
+
+        for (_, running) in &active {
+            running.store(false, Ordering::SeqCst);
+        }
+        for handle in active.into_iter().map(|(handle, _)| handle).chain(stopped) {
+            let _ = handle.await;
+        }
+    }
+
+    /// Send requests on the scheduler's clock, each in its own task, until the run is over.
+    /// When `max_in_flight` requests are outstanding the next one waits, which shows up as lag.
+    async fn pace(worker: Worker, mut scheduler: RateScheduler, max_in_flight: usize, in_flight: Arc<AtomicU32>) -> Option<RateScheduler> {
+        let worker = Arc::new(worker);
+        let permits = Arc::new(tokio::sync::Semaphore::new(max_in_flight));
+        let total = worker.run_length();
+        let mut tasks = tokio::task::JoinSet::new();
+
+        while let Some(due) = scheduler.next_due() {
+            if let Some(wait) = due.checked_sub(worker.start_time.elapsed()) {
+                tokio::time::sleep(wait).await;
+            }
+            let Ok(permit) = permits.clone().acquire_owned().await else {
+                break;
+            };
+            let now = worker.start_time.elapsed();
+            if now >= total {
+                break;
+            }
+            scheduler.dispatch(now);
+            in_flight.store((max_in_flight - permits.available_permits()) as u32, Ordering::Relaxed);
+
+            let worker = worker.clone();
+            tasks.spawn(async move {
+                worker.send(0).await;
+                drop(permit);
+            });
+            // Reap finished tasks so the set doesn't grow for the whole run
+            while tasks.try_join_next().is_some() {}
+        }
+
+        while tasks.join_next().await.is_some() {}
+        in_flight.store(0, Ordering::Relaxed);
+        Some(scheduler)
+    }
 
-        println!();
-        Ok(())
+    /// The thresholds this command checks against.
+    pub fn thresholds(&self) -> &PerfThresholds {
+        &self.thresholds
     }
 }
 
+/// Everything a virtual user needs to send requests and record their metrics.
+struct Worker {
+    client: Client,
+    protocol: HttpProtocol,
+    url: String,
+    method: Method,
+    headers: HeaderMap,
+    tokens: Option<Arc<TokenManager>>,
+    body: Option<String>,
+    metrics: Arc<Metrics>,
+    stage_metrics: Arc<Vec<Arc<Metrics>>>,
+    schedule: Arc<LoadSchedule>,
+    /// Time before the schedule starts, spent at the first stage's concurrency.
+    warmup: Duration,
+    start_time: Instant,
+}
+
+impl Worker {
+    fn run_length(&self) -> Duration {
+        self.warmup + self.schedule.total_duration()
+    }
+
+    /// Time into the schedule; zero during warmup.
+    fn scheduled_elapsed(&self) -> Duration {
+        self.start_time.elapsed().saturating_sub(self.warmup)
+    }
+
+    /// Send requests back to back until stopped or the schedule ends.
+    async fn run(self: Arc<Self>, running: Arc<AtomicBool>) {
+        let total = self.run_length();
+        while running.load(Ordering::Relaxed) && self.start_time.elapsed() < total {
+            self.send(self.schedule.stage_at(self.scheduled_elapsed())).await;
+        }
+    }
+
+    /// Send one request and record its outcome under `stage`.
+    async fn send(&self, stage: usize) {
+        let request_start = SystemTime::now();
+
+        let mut req = self.client.request(self.method.clone(), &self.url).headers(self.headers.clone());
+        if let Some(tokens) = &self.tokens {
+            match tokens.token().await {
+                Ok(token) => req = req.bearer_auth(token),
+                Err(e) => {
+                    let (kind, message) = (classify_error(e.as_ref()), error_chain(e.as_ref()));
+                    self.metrics.record_error(kind, message.clone());
+                    self.stage_metrics[stage].record_error(kind, message);
+                    // Don't hammer a failing token endpoint
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    return;
+                }
+            }
+        }
+        if let Some(body_content) = &self.body {
+            req = req.body(body_content.clone());
+        }
+
+        let response = req.send().await
+            .map_err(|e| (classify_error(&e), error_chain(&e)))
+            .and_then(|response| {
+                self.metrics.record_protocol(&format!("{:?}", response.version()));
+                self.protocol.check(response.version()).map(|_| response).map_err(|e| (ErrorKind::Other, e))
+            });
+        match response {
+            Ok(response) => {
+                let latency = request_start.elapsed().unwrap_or_default();
+                if let Some(info) = response.extensions().get::<HttpInfo>() {
+                    self.metrics.record_connection(info.local_addr(), request_start, latency);
+                }
+                let metric = || RequestMetric {
+                    duration: latency,
+                    status: response.status().as_u16(),
+                    timestamp: request_start,
+                };
+                self.metrics.record(metric());
+                self.stage_metrics[stage].record(metric());
+            }
+            Err((kind, message)) => {
+                self.metrics.record_error(kind, message.clone());
+                self.stage_metrics[stage].record_error(kind, message);
+            }
+        }
+    }
+}
@@ -1,138 +1,353 @@
 use std::collections::HashMap;
-use std::time::{Duration, SystemTime};
-use anthropic::{
-    client::ClientBuilder,
-    types::{Message, ContentBlock, MessagesRequestBuilder, Role},
-};
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use crate::ai;
 use crate::config::Config;
+use crate::commands::{print_ai_failed, print_ai_skipped};
 use crate::commands::call::CallCommand;
+use crate::commands::monitor::MonitorHistory;
 use crate::commands::perf::PerfCommand;
+use crate::models::metrics::bars;
+use crate::units::parse_positive_duration;
+use crate::{output, outln};
+
+/// Response headers the security posture is scored on.
+const SECURITY_HEADERS: [&str; 5] = [
+    "Strict-Transport-Security",
+    "Content-Security-Policy",
+    "X-Frame-Options",
+    "X-Content-Type-Options",
+    "X-XSS-Protection",
+];
 
 pub struct PredictCommand {
     config: Config,
+    options: PredictOptions,
 }
 
-#[derive(Debug)]
+/// A p95 up to this earns every latency point...
+const LATENCY_GOOD: Duration = Duration::from_millis(200);
+/// ...and from this on none.
+const LATENCY_BAD: Duration = Duration::from_secs(2);
+/// An error rate from this on earns no error points.
+const ERROR_RATE_BAD: f64 = 0.10;
+
+/// How hard the performance probe pushes the API, or which past runs `--trend` shows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PredictOptions {
+    pub probe_users: u32,
+    pub probe_duration: Duration,
+    /// Show this many recorded runs instead of predicting.
+    pub trend: Option<usize>,
+}
+
+impl Default for PredictOptions {
+    fn default() -> Self {
+        Self { probe_users: 10, probe_duration: Duration::from_secs(10), trend: None }
+    }
+}
+
+impl PredictOptions {
+    /// `predict URL [--probe-users 10] [--probe-duration 10s] | --trend [N]`
+    pub fn from_args(args: &[&str]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut options = Self::default();
+        // Skip "predict" and the URL
+        let mut i = 2;
+        while i < args.len() {
+            if args[i] == "--trend" {
+                let last = args.get(i + 1).and_then(|n| n.parse::<usize>().ok());
+                options.trend = Some(last.unwrap_or(10).max(1));
+                i += if last.is_some() { 2 } else { 1 };
+                continue;
+            }
+            let value = args.get(i + 1).copied().ok_or_else(|| format!("Value required after {}", args[i]))?;
+            match args[i] {
+                "--probe-users" => {
+                    options.probe_users = value.parse().ok().filter(|n| *n > 0)
+                        .ok_or_else(|| format!("--probe-users must be a positive number, got '{}'", value))?;
+                }
+                "--probe-duration" => {
+                    options.probe_duration = parse_positive_duration(value).map_err(|e| format!("Invalid --probe-duration: {}", e))?;
+                }
+                other => return Err(format!("Unknown option for predict: {}", other).into()),
+            }
+            i += 2;
+        }
+        Ok(options)
+    }
+}
+
+/// Measured inputs of [`health_score`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HealthSignals {
+    pub p95_latency: Duration,
+    /// 0.0-1.0.
+    pub error_rate: f64,
+    pub https: bool,
+    /// How many of the five scored security headers the API sends.
+    pub security_headers: usize,
+    /// Whether responses carry `RateLimit-*` or `X-RateLimit-*` headers.
+    pub rate_limited: bool,
+}
+
+/// A 0-100 score from measured signals only, the same for the same inputs:
+///
+/// - latency, 30 points: all for a p95 up to 200ms, none from 2s, linear in between;
+/// - errors, 30 points: all at 0%, none from 10%, linear in between;
+/// - HTTPS, 15 points;
+/// - security headers, 15 points: 3 for each of the five scored ones;
+/// - rate limiting, 10 points, when rate-limit headers are sent.
+pub fn health_score(signals: &HealthSignals) -> f64 {
+    let latency = signals.p95_latency.as_secs_f64();
+    let (good, bad) = (LATENCY_GOOD.as_secs_f64(), LATENCY_BAD.as_secs_f64());
+    let latency_points = 30.0 * ((bad - latency) / (bad - good)).clamp(0.0, 1.0);
+    let error_points = 30.0 * (1.0 - signals.error_rate / ERROR_RATE_BAD).clamp(0.0, 1.0);
+    let https_points = if signals.https { 15.0 } else { 0.0 };
+    let header_points = 3.0 * signals.security_headers.min(SECURITY_HEADERS.len()) as f64;
+    let rate_limit_points = if signals.rate_limited { 10.0 } else { 0.0 };
+    latency_points + error_points + https_points + header_points + rate_limit_points
+}
+
+/// Whether a response header announces rate limits: `RateLimit`, `RateLimit-Limit`,
+/// `X-RateLimit-Remaining`, `X-Rate-Limit-Limit` and the like.
+pub fn is_rate_limit_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    let name = name.strip_prefix("x-").unwrap_or(&name);
+    name.starts_with("ratelimit") || name.starts_with("rate-limit")
+}
+
+/// One `predict` run, as kept in `~/.nuts/predictions/<host>.jsonl`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PredictionRecord {
+    pub timestamp: DateTime<Utc>,
+    pub url: String,
+    pub health_score: f64,
+    pub measured_score: f64,
+    pub avg_ms: u64,
+    pub p95_ms: u64,
+    pub error_rate: f64,
+    pub rps: f64,
+}
+
+/// Past `predict` runs, one JSONL file per host.
+pub struct PredictionHistory {
+    dir: PathBuf,
+}
+
+impl PredictionHistory {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `~/.nuts/predictions`.
+    pub fn default_dir() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(dirs::home_dir()
+            .ok_or("Could not find home directory")?
+            .join(".nuts")
+            .join("predictions"))
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", MonitorHistory::host_key(url)))
+    }
+
+    pub fn append(&self, record: &PredictionRecord) -> Result<(), Box<dyn Error>> {
+        std::fs::create_dir_all(&self.dir)?;
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(self.path(&record.url))?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+
+    /// Every recorded run for the host of `url`, oldest first; none when there is no file yet.
+    /// Unreadable lines are skipped.
+    pub fn load(&self, url: &str) -> Result<Vec<PredictionRecord>, Box<dyn Error>> {
+        let path = self.path(url);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(&path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        Ok(content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+    }
+}
+
+/// The `predict --trend` table of the last `last` runs, with bar charts of the score and p95.
+pub fn trend_lines(host: &str, records: &[PredictionRecord], last: usize) -> Vec<String> {
+    if records.is_empty() {
+        return vec![format!("📭 No predictions recorded for {} yet; run 'predict <url>' first", host)];
+    }
+    let recent = &records[records.len().saturating_sub(last)..];
+    let mut lines = vec![
+        format!("📈 Health trend for {}, last {} run(s)", host, recent.len()),
+        format!("   {:<17} {:>6} {:>9} {:>8} {:>8} {:>7}", "When", "Score", "Measured", "Avg", "p95", "Errors"),
+    ];
+    for record in recent {
+        lines.push(format!(
+            "   {:<17} {:>6.1} {:>9.1} {:>6}ms {:>6}ms {:>6.1}%",
+            record.timestamp.format("%Y-%m-%d %H:%M"),
+            record.health_score,
+            record.measured_score,
+            record.avg_ms,
+            record.p95_ms,
+            record.error_rate * 100.0
+        ));
+    }
+    let scores: Vec<f64> = recent.iter().map(|record| record.health_score).collect();
+    let p95s: Vec<f64> = recent.iter().map(|record| record.p95_ms as f64).collect();
+    lines.push(format!("   Score {}   p95 {}", bars(&scores), bars(&p95s)));
+    lines
+}
+
+#[derive(Debug, Serialize)]
 pub struct PredictionResult {
     pub health_score: f64,
+    /// From [`health_score`], whatever the AI says.
+    pub measured_score: f64,
     pub predicted_issues: Vec<String>,
     pub recommendations: Vec<String>,
     pub performance_forecast: PerformanceForecast,
     pub security_alerts: Vec<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct PerformanceForecast {
+    #[serde(rename = "expected_response_time_ms", serialize_with = "as_millis")]
     pub expected_response_time: Duration,
     pub capacity_limit: u32,
     pub bottlenecks: Vec<String>,
 }
 
+fn as_millis<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
 impl PredictCommand {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        Self { config, options: PredictOptions::default() }
+    }
+
+    pub fn with_options(mut self, options: PredictOptions) -> Self {
+        self.options = options;
+        self
     }
 
     /// Predictive API Health Analysis
     pub async fn predict_health(&self, base_url: &str) -> Result<PredictionResult, Box<dyn std::error::Error>> {
-        println!("🔮 Performing predictive analysis for: {}", base_url);
+        outln!("🔮 Performing predictive analysis for: {}", base_url);
         
         // Step 1: Collect current API metrics
-        println!("📊 Collecting baseline metrics...");
+        outln!("📊 Collecting baseline metrics...");
         let baseline_metrics = self.collect_baseline_metrics(base_url).await?;
         
         // Step 2: Run mini performance test
-        println!("⚡ Running quick performance probe...");
+        outln!(
+            "⚡ Probing performance with {} users for {:.1}s...",
+            self.options.probe_users,
+            self.options.probe_duration.as_secs_f64()
+        );
         let performance_data = self.probe_performance(base_url).await?;
         
         // Step 3: Analyze security headers and configuration
-        println!("🔒 Analyzing security posture...");
-        let security_analysis = self.analyze_security_posture(base_url).await?;
+        outln!("🔒 Analyzing security posture...");
+        let security_analysis = Self::analyze_security_posture(base_url, &baseline_metrics);
         
         // Step 4: AI-powered predictive analysis
-        println!("🤖 Generating AI predictions...");
-        let prediction = self.generate_ai_predictions(&baseline_metrics, &performance_data, &security_analysis).await?;
+        let prediction = if self.config.is_offline() {
+            print_ai_skipped("AI predictions");
+            Self::local_predictions(&baseline_metrics, &performance_data, &security_analysis)
+        } else {
+            outln!("🤖 Generating AI predictions...");
+            match self.generate_ai_predictions(&baseline_metrics, &performance_data, &security_analysis).await {
+                Ok(prediction) => prediction,
+                Err(e) => {
+                    print_ai_failed("AI predictions", e.as_ref());
+                    Self::local_predictions(&baseline_metrics, &performance_data, &security_analysis)
+                }
+            }
+        };
         
         // Step 5: Present actionable insights
         self.present_predictions(&prediction)?;
+
+        let record = PredictionRecord {
+            timestamp: Utc::now(),
+            url: base_url.to_string(),
+            health_score: prediction.health_score,
+            measured_score: prediction.measured_score,
+            avg_ms: performance_data.avg_response_time.as_millis() as u64,
+            p95_ms: performance_data.p95_response_time.as_millis() as u64,
+            error_rate: performance_data.error_rate,
+            rps: performance_data.requests_per_second,
+        };
+        if let Err(e) = PredictionHistory::default_dir().and_then(|dir| PredictionHistory::new(dir).append(&record)) {
+            outln!("⚠️  Could not record this run for --trend: {}", e);
+        }
         
         Ok(prediction)
     }
 
     async fn collect_baseline_metrics(&self, base_url: &str) -> Result<BaselineMetrics, Box<dyn std::error::Error>> {
-        let call_command = CallCommand::new();
-        
-        // Test basic connectivity
-        let start_time = SystemTime::now();
-        let response = call_command.execute_with_response(&["GET", base_url]).await?;
-        let response_time = start_time.elapsed()?;
-        
-        // Extract metrics from response
-        let mut metrics = BaselineMetrics {
-            response_time,
-            status_code: 200, // Default, would parse from response
-            content_length: response.len(),
-            server_info: None,
-            headers: HashMap::new(),
-        };
-        
-        // Parse response for server information (simplified)
-        if response.contains("Server:") {
-            metrics.server_info = Some("Detected".to_string());
-        }
-        
-        Ok(metrics)
+        // Only the measurements are wanted here, not the usual call output
+        let (response, _) = output::capture(CallCommand::new().execute(&["GET", base_url])).await;
+        let response = response?;
+        let headers: HashMap<String, String> = response.headers.iter()
+            .map(|(name, value)| (name.to_lowercase(), value.clone()))
+            .collect();
+
+        Ok(BaselineMetrics {
+            response_time: response.elapsed,
+            status_code: response.status,
+            content_length: response.body.len(),
+            server_info: headers.get("server").cloned(),
+            headers,
+        })
     }
 
-    async fn probe_performance(&self, _base_url: &str) -> Result<PerformanceData, Box<dyn std::error::Error>> {
-        // Run a quick mini load test
-        let _perf_command = PerfCommand::new(&self.config);
-        
-        // This would integrate with the existing perf command
-        // For now, simulate some performance data
-        let performance_data = PerformanceData {
-            avg_response_time: Duration::from_millis(250),
-            p95_response_time: Duration::from_millis(450),
-            requests_per_second: 100.0,
-            error_rate: 0.02,
-            concurrent_users_tested: 10,
-        };
-        
-        Ok(performance_data)
+    /// A short load test of `base_url` with the configured probe users and duration.
+    async fn probe_performance(&self, base_url: &str) -> Result<PerformanceData, Box<dyn std::error::Error>> {
+        let perf = PerfCommand::new(&self.config);
+        let users = self.options.probe_users;
+        let duration = self.options.probe_duration;
+        let (summary, _) = output::capture(perf.run(base_url, users, duration, "GET", None, &HashMap::new())).await;
+        let summary = summary?;
+
+        Ok(PerformanceData {
+            avg_response_time: summary.avg_latency,
+            p95_response_time: summary.p95_latency,
+            requests_per_second: summary.total_requests as f64 / duration.as_secs_f64().max(0.001),
+            error_rate: summary.error_rate,
+            concurrent_users_tested: users,
+        })
     }
 
-    async fn analyze_security_posture(&self, base_url: &str) -> Result<SecurityAnalysis, Box<dyn std::error::Error>> {
-        let call_command = CallCommand::new();
-        let response = call_command.execute_with_response(&["GET", base_url]).await?;
-        
-        let mut security_analysis = SecurityAnalysis {
+    fn analyze_security_posture(base_url: &str, baseline: &BaselineMetrics) -> SecurityAnalysis {
+        let present: Vec<String> = SECURITY_HEADERS.iter()
+            .filter(|header| baseline.headers.contains_key(&header.to_lowercase()))
+            .map(|header| header.to_string())
+            .collect();
+        let compliance_score = present.len() as f64 / SECURITY_HEADERS.len() as f64 * 100.0;
+
+        SecurityAnalysis {
             https_enabled: base_url.starts_with("https://"),
-            security_headers: Vec::new(),
+            security_headers: present,
             vulnerabilities: Vec::new(),
-            compliance_score: 0.0,
-        };
-        
-        // Analyze common security headers (simplified)
-        let security_headers = vec![
-            "Strict-Transport-Security",
-            "Content-Security-Policy", 
-            "X-Frame-Options",
-            "X-Content-Type-Options",
-            "X-XSS-Protection",
-        ];
-        
-        for header in security_headers {
-            if response.contains(header) {
-                security_analysis.security_headers.push(header.to_string());
-            }
+            compliance_score,
+            rate_limited: baseline.headers.keys().any(|name| is_rate_limit_header(name)),
         }
-        
-        // Calculate compliance score
-        security_analysis.compliance_score = 
-            (security_analysis.security_headers.len() as f64 / 5.0) * 100.0;
-        
-        Ok(security_analysis)
+    }
+
+    fn measured_score(performance: &PerformanceData, security: &SecurityAnalysis) -> f64 {
+        health_score(&HealthSignals {
+            p95_latency: performance.p95_response_time,
+            error_rate: performance.error_rate,
+            https: security.https_enabled,
+            security_headers: security.security_headers.len(),
+            rate_limited: security.rate_limited,
+        })
     }
 
     async fn generate_ai_predictions(
@@ -141,20 +356,19 @@ impl PredictCommand {
         performance: &PerformanceData,
         security: &SecurityAnalysis,
     ) -> Result<PredictionResult, Box<dyn std::error::Error>> {
-        let api_key = self.config.anthropic_api_key.as_ref()
-            .ok_or("API key not configured for AI predictions")?;
-
-        let ai_client = ClientBuilder::default()
-            .api_key(api_key.clone())
-            .build()?;
+        let ai_client = ai::optional_client(&self.config, "predict")?;
 
         let analysis_data = json!({
             "baseline_metrics": {
+                "status_code": baseline.status_code,
                 "response_time_ms": baseline.response_time.as_millis(),
                 "content_length": baseline.content_length,
-                "server_info": baseline.server_info
+                "server_info": baseline.server_info,
+                "response_headers": baseline.headers.keys().collect::<std::collections::BTreeSet<_>>()
             },
             "performance_data": {
+                "concurrent_users": performance.concurrent_users_tested,
+                "duration_s": self.options.probe_duration.as_secs_f64(),
                 "avg_response_time_ms": performance.avg_response_time.as_millis(),
                 "p95_response_time_ms": performance.p95_response_time.as_millis(),
                 "rps": performance.requests_per_second,
@@ -162,17 +376,20 @@ impl PredictCommand {
             },
             "security_analysis": {
                 "https_enabled": security.https_enabled,
-                "security_headers_count": security.security_headers.len(),
-                "compliance_score": security.compliance_score
-            }
+                "security_headers": security.security_headers,
+                "compliance_score": security.compliance_score,
+                "rate_limit_headers": security.rate_limited
+            },
+            "measured_score": Self::measured_score(performance, security)
         });
 
         let prompt = format!(
             "You are an expert API reliability engineer with predictive analytics capabilities. 
             
-Analyze this API's current metrics and predict potential issues:
+Analyze this API's metrics and predict potential issues. They were measured just now: one GET for the \
+baseline and its response headers, then a short load test with the given number of concurrent users.
 
-Current Metrics:
+Measured Metrics:
 {}
 
 Based on this data, provide:
@@ -205,87 +422,118 @@ Format as JSON with these sections:
             serde_json::to_string_pretty(&analysis_data)?
         );
 
-        let response = ai_client.messages(MessagesRequestBuilder::default()
-            .messages(vec![Message {
-                role: Role::User,
-                content: vec![ContentBlock::Text { text: prompt }],
-            }])
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(2000_usize)
-            .build()?
-        ).await?;
-
-        if let Some(ContentBlock::Text { text }) = response.content.first() {
-            // Try to parse AI response as JSON
-            if let Ok(ai_prediction) = serde_json::from_str::<serde_json::Value>(text) {
-                let prediction = PredictionResult {
-                    health_score: ai_prediction.get("health_score")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(75.0),
-                    predicted_issues: ai_prediction.get("predicted_issues")
-                        .and_then(|v| v.as_array())
-                        .map(|arr| arr.iter()
-                            .filter_map(|v| v.as_str())
-                            .map(|s| s.to_string())
-                            .collect())
-                        .unwrap_or_default(),
-                    recommendations: ai_prediction.get("recommendations")
-                        .and_then(|v| v.as_array())
-                        .map(|arr| arr.iter()
-                            .filter_map(|v| v.as_str())
-                            .map(|s| s.to_string())
-                            .collect())
-                        .unwrap_or_default(),
-                    performance_forecast: PerformanceForecast {
-                        expected_response_time: Duration::from_millis(
-                            ai_prediction.get("performance_forecast")
-                                .and_then(|pf| pf.get("expected_response_time_ms"))
-                                .and_then(|v| v.as_u64())
-                                .unwrap_or(200)
-                        ),
-                        capacity_limit: ai_prediction.get("performance_forecast")
-                            .and_then(|pf| pf.get("capacity_limit_rps"))
+        let text = ai_client.ask(&prompt).await?;
+
+        // Try to parse AI response as JSON
+        if let Ok(ai_prediction) = serde_json::from_str::<serde_json::Value>(&text) {
+            let measured_score = Self::measured_score(performance, security);
+            let prediction = PredictionResult {
+                health_score: ai_prediction.get("health_score")
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or(measured_score),
+                measured_score,
+                predicted_issues: ai_prediction.get("predicted_issues")
+                    .and_then(|v| v.as_array())
+                    .map(|arr| arr.iter()
+                        .filter_map(|v| v.as_str())
+                        .map(|s| s.to_string())
+                        .collect())
+                    .unwrap_or_default(),
+                recommendations: ai_prediction.get("recommendations")
+                    .and_then(|v| v.as_array())
+                    .map(|arr| arr.iter()
+                        .filter_map(|v| v.as_str())
+                        .map(|s| s.to_string())
+                        .collect())
+                    .unwrap_or_default(),
+                performance_forecast: PerformanceForecast {
+                    expected_response_time: Duration::from_millis(
+                        ai_prediction.get("performance_forecast")
+                            .and_then(|pf| pf.get("expected_response_time_ms"))
                             .and_then(|v| v.as_u64())
-                            .unwrap_or(500) as u32,
-                        bottlenecks: ai_prediction.get("performance_forecast")
-                            .and_then(|pf| pf.get("bottlenecks"))
-                            .and_then(|v| v.as_array())
-                            .map(|arr| arr.iter()
-                                .filter_map(|v| v.as_str())
-                                .map(|s| s.to_string())
-                                .collect())
-                            .unwrap_or_default(),
-                    },
-                    security_alerts: ai_prediction.get("security_alerts")
+                            .unwrap_or(200)
+                    ),
+                    capacity_limit: ai_prediction.get("performance_forecast")
+                        .and_then(|pf| pf.get("capacity_limit_rps"))
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(500) as u32,
+                    bottlenecks: ai_prediction.get("performance_forecast")
+                        .and_then(|pf| pf.get("bottlenecks"))
                         .and_then(|v| v.as_array())
                         .map(|arr| arr.iter()
                             .filter_map(|v| v.as_str())
                             .map(|s| s.to_string())
                             .collect())
                         .unwrap_or_default(),
-                };
-                
-                return Ok(prediction);
-            }
+                },
+                security_alerts: ai_prediction.get("security_alerts")
+                    .and_then(|v| v.as_array())
+                    .map(|arr| arr.iter()
+                        .filter_map(|v| v.as_str())
+                        .map(|s| s.to_string())
+                        .collect())
+                    .unwrap_or_default(),
+            };
+            
+            return Ok(prediction);
+        }
+
+        // An answer that isn't JSON is no better than none
+        Ok(Self::local_predictions(baseline, performance, security))
+    }
+
+    /// Rule-based prediction from the collected metrics, used without AI or a usable AI answer.
+    fn local_predictions(
+        baseline: &BaselineMetrics,
+        performance: &PerformanceData,
+        security: &SecurityAnalysis,
+    ) -> PredictionResult {
+        let mut predicted_issues = Vec::new();
+        let mut recommendations = Vec::new();
+        let mut security_alerts = Vec::new();
+
+        if baseline.status_code >= 400 {
+            predicted_issues.push(format!("The base URL answers {} to a plain GET", baseline.status_code));
+            recommendations.push("Point predict at an endpoint that answers 2xx, such as a health check".to_string());
+        }
+        if baseline.response_time > Duration::from_millis(1000) {
+            predicted_issues.push("Slow baseline response time will degrade under load".to_string());
+            recommendations.push("Investigate slow handlers and add caching".to_string());
+        }
+        if performance.error_rate > 0.01 {
+            predicted_issues.push(format!("Error rate of {:.1}% may grow with traffic", performance.error_rate * 100.0));
+            recommendations.push("Add alerting on 5xx responses".to_string());
+        }
+        if !security.https_enabled {
+            security_alerts.push("API is not served over HTTPS".to_string());
+            recommendations.push("Serve the API over HTTPS only".to_string());
+        }
+        if security.compliance_score < 60.0 {
+            security_alerts.push(format!("Only {} of 5 recommended security headers present", security.security_headers.len()));
+            recommendations.push("Add the missing security headers".to_string());
+        }
+        if !security.rate_limited {
+            recommendations.push("Announce rate limits with RateLimit headers so clients can back off".to_string());
         }
 
-        // Fallback if AI response can't be parsed
-        Ok(PredictionResult {
-            health_score: 75.0,
-            predicted_issues: vec!["Unable to generate specific predictions".to_string()],
-            recommendations: vec!["Configure API monitoring".to_string()],
+        let measured_score = Self::measured_score(performance, security);
+        PredictionResult {
+            health_score: measured_score,
+            measured_score,
+            predicted_issues,
+            recommendations,
             performance_forecast: PerformanceForecast {
-                expected_response_time: Duration::from_millis(200),
-                capacity_limit: 500,
-                bottlenecks: vec!["Unknown".to_string()],
+                expected_response_time: performance.p95_response_time,
+                capacity_limit: performance.requests_per_second as u32,
+                bottlenecks: vec![],
             },
-            security_alerts: vec![],
-        })
+            security_alerts,
+        }
     }
 
     fn present_predictions(&self, prediction: &PredictionResult) -> Result<(), Box<dyn std::error::Error>> {
-        println!("\n🔮 PREDICTIVE ANALYSIS RESULTS");
-        println!("═══════════════════════════════");
+        outln!("\n🔮 PREDICTIVE ANALYSIS RESULTS");
+        outln!("═══════════════════════════════");
         
         // Health Score with color coding
         let health_emoji = match prediction.health_score as u8 {
@@ -295,41 +543,44 @@ Format as JSON with these sections:
             _ => "❤️",
         };
         
-        println!("{} Health Score: {:.1}%", health_emoji, prediction.health_score);
+        outln!("{} Health Score: {:.1}%", health_emoji, prediction.health_score);
+        if prediction.measured_score != prediction.health_score {
+            outln!("   Measured score: {:.1}% (latency, errors, HTTPS, security and rate-limit headers)", prediction.measured_score);
+        }
         
         // Predicted Issues
         if !prediction.predicted_issues.is_empty() {
-            println!("\n⚠️  PREDICTED ISSUES:");
+            outln!("\n⚠️  PREDICTED ISSUES:");
             for issue in &prediction.predicted_issues {
-                println!("   • {}", issue);
+                outln!("   • {}", issue);
             }
         }
         
         // Performance Forecast
-        println!("\n📈 PERFORMANCE FORECAST:");
-        println!("   Expected Response Time: {}ms", prediction.performance_forecast.expected_response_time.as_millis());
-        println!("   Estimated Capacity: {} req/s", prediction.performance_forecast.capacity_limit);
+        outln!("\n📈 PERFORMANCE FORECAST:");
+        outln!("   Expected Response Time: {}ms", prediction.performance_forecast.expected_response_time.as_millis());
+        outln!("   Estimated Capacity: {} req/s", prediction.performance_forecast.capacity_limit);
         if !prediction.performance_forecast.bottlenecks.is_empty() {
-            println!("   Potential Bottlenecks: {}", prediction.performance_forecast.bottlenecks.join(", "));
+            outln!("   Potential Bottlenecks: {}", prediction.performance_forecast.bottlenecks.join(", "));
         }
         
         // Security Alerts
         if !prediction.security_alerts.is_empty() {
-            println!("\n🚨 SECURITY ALERTS:");
+            outln!("\n🚨 SECURITY ALERTS:");
             for alert in &prediction.security_alerts {
-                println!("   • {}", alert);
+                outln!("   • {}", alert);
             }
         }
         
         // Recommendations
         if !prediction.recommendations.is_empty() {
-            println!("\n💡 RECOMMENDATIONS:");
+            outln!("\n💡 RECOMMENDATIONS:");
             for (i, recommendation) in prediction.recommendations.iter().enumerate() {
-                println!("   {}. {}", i + 1, recommendation);
+                outln!("   {}. {}", i + 1, recommendation);
             }
         }
         
-        println!("\n🎯 Use these insights to prevent issues before they happen!");
+        outln!("\n🎯 Use these insights to prevent issues before they happen!");
         
         Ok(())
     }
@@ -338,11 +589,10 @@ Format as JSON with these sections:
 #[derive(Debug)]
 struct BaselineMetrics {
     response_time: Duration,
-    #[allow(dead_code)]
     status_code: u16,
     content_length: usize,
     server_info: Option<String>,
-    #[allow(dead_code)]
+    /// Response headers, names lowercased.
     headers: HashMap<String, String>,
 }
 
@@ -352,7 +602,6 @@ struct PerformanceData {
     p95_response_time: Duration,
     requests_per_second: f64,
     error_rate: f64,
-    #[allow(dead_code)]
     concurrent_users_tested: u32,
 }
 
@@ -363,4 +612,5 @@ struct SecurityAnalysis {
     #[allow(dead_code)]
     vulnerabilities: Vec<String>,
     compliance_score: f64,
+    rate_limited: bool,
 }
\ No newline at end of file
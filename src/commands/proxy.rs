@@ -0,0 +1,185 @@
+//! Recording reverse proxy: forwards every request to a target API and records each
+//! exchange into a flow, so a real app session bootstraps a collection without AI.
+
+use crate::flows::record::{self, Exchange};
+use crate::flows::OpenAPISpec;
+use axum::body::{to_bytes, Body};
+use axum::extract::Request;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use axum::response::Response;
+use axum::Router;
+use console::style;
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Hop-by-hop headers, plus ones reqwest or hyper set themselves.
+const SKIPPED_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+    "te",
+    "trailer",
+    "host",
+    "content-length",
+];
+
+/// What the proxy has recorded so far.
+#[derive(Debug)]
+pub struct Recording {
+    pub spec: OpenAPISpec,
+    /// `METHOD /path` of every distinct endpoint seen.
+    pub endpoints: BTreeSet<String>,
+    pub requests: usize,
+}
+
+pub struct RecordingProxy {
+    target: String,
+    client: reqwest::Client,
+    recording: Arc<Mutex<Recording>>,
+}
+
+impl RecordingProxy {
+    /// Forward to `target`, recording into `spec` (a new or existing flow).
+    pub fn new(target: &str, spec: OpenAPISpec) -> Result<Self, Box<dyn std::error::Error>> {
+        let target = if target.contains("://") { target.to_string() } else { format!("http://{}", target) };
+        url::Url::parse(&target).map_err(|e| format!("Invalid target URL '{}': {}", target, e))?;
+
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        Ok(Self {
+            target: target.trim_end_matches('/').to_string(),
+            client,
+            recording: Arc::new(Mutex::new(Recording { spec, endpoints: BTreeSet::new(), requests: 0 })),
+        })
+    }
+
+    pub fn recording(&self) -> Arc<Mutex<Recording>> {
+        self.recording.clone()
+    }
+
+    /// A router that forwards every path and method to the target.
+    pub fn router(&self) -> Router {
+        let target = self.target.clone();
+        let client = self.client.clone();
+        let recording = self.recording.clone();
+        Router::new().fallback(move |request: Request| {
+            forward(target.clone(), client.clone(), recording.clone(), request)
+        })
+    }
+
+    /// Serve on `port` until Ctrl+C, then write the flow to `out`.
+    pub async fn start(&self, port: u16, out: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let addr = SocketAddr::from(([127, 0, 0, 1], port));
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        println!("📼 Recording proxy on http://{} → {}", listener.local_addr()?, style(&self.target).cyan());
+        println!("Point your app at the proxy, then press Ctrl+C to save the flow");
+
+        axum::serve(listener, self.router())
+            .with_graceful_shutdown(async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await?;
+
+        let recording = self.recording.lock().map_err(|_| "recording state poisoned")?;
+        println!("\n🛑 Proxy stopped");
+        println!(
+            "📊 Captured {} unique endpoint(s) from {} request(s)",
+            style(recording.endpoints.len()).green().bold(),
+            recording.requests
+        );
+        for endpoint in &recording.endpoints {
+            println!("   • {}", endpoint);
+        }
+        recording.spec.save(&out.to_path_buf())?;
+        println!("✅ Saved flow to {}", style(out.display()).green());
+        Ok(())
+    }
+}
+
+async fn forward(target: String, client: reqwest::Client, recording: Arc<Mutex<Recording>>, request: Request) -> Response {
+    let started = Instant::now();
+    let (parts, body) = request.into_parts();
+    let path_and_query = parts.uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    let url = format!("{}{}", target, path_and_query);
+
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, format!("Could not read request body: {}", e)),
+    };
+    let Ok(method) = reqwest::Method::from_bytes(parts.method.as_str().as_bytes()) else {
+        return error_response(StatusCode::METHOD_NOT_ALLOWED, format!("Unsupported method {}", parts.method));
+    };
+
+    let mut upstream = client.request(method.clone(), &url);
+    for (name, value) in &parts.headers {
+        // Ask for identity encoding so recorded bodies are readable
+        if SKIPPED_HEADERS.contains(&name.as_str()) || name.as_str() == "accept-encoding" {
+            continue;
+        }
+        upstream = upstream.header(name.as_str(), value.as_bytes());
+    }
+    if !body.is_empty() {
+        upstream = upstream.body(body.to_vec());
+    }
+
+    let response = match upstream.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            println!("{} {} {} → {}", style("⇄").dim(), method, path_and_query, style("502").red());
+            return error_response(StatusCode::BAD_GATEWAY, format!("Upstream request to {} failed: {}", url, e));
+        }
+    };
+    let status = response.status().as_u16();
+    let headers = response.headers().clone();
+    let response_body = match response.bytes().await {
+        Ok(bytes) => bytes,
+        Err(e) => return error_response(StatusCode::BAD_GATEWAY, format!("Could not read upstream response: {}", e)),
+    };
+    println!("{} {} {} → {} ({}ms)", style("⇄").cyan(), method, path_and_query, status, started.elapsed().as_millis());
+
+    let exchange = Exchange {
+        method: method.to_string(),
+        url,
+        request_body: (!body.is_empty()).then(|| String::from_utf8_lossy(&body).into_owned()),
+        status,
+        // Compressed or binary bodies can't be used as examples
+        response_body: (!headers.contains_key(reqwest::header::CONTENT_ENCODING))
+            .then(|| String::from_utf8(response_body.to_vec()).ok())
+            .flatten(),
+    };
+    if let Ok(mut recording) = recording.lock() {
+        recording.requests += 1;
+        match record::record(&mut recording.spec, &exchange) {
+            Ok(endpoint) => {
+                recording.endpoints.insert(endpoint);
+            }
+            Err(e) => println!("⚠️  Not recorded: {}", e),
+        }
+    }
+
+    let mut builder = Response::builder().status(status);
+    for (name, value) in &headers {
+        if SKIPPED_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_str().as_bytes()), HeaderValue::from_bytes(value.as_bytes())) {
+            builder = builder.header(name, value);
+        }
+    }
+    builder.body(Body::from(response_body))
+        .unwrap_or_else(|e| error_response(StatusCode::BAD_GATEWAY, e.to_string()))
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::json!({ "error": message }).to_string()))
+        .unwrap_or_default()
+}
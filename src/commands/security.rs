@@ -1,43 +1,431 @@
 use console::{style, Term};
-use anthropic::client::{Client as AnthropicClient, ClientBuilder};
-use anthropic::types::{ContentBlock, Message, MessagesRequestBuilder, Role};
-use reqwest::header;
+use crate::ai::{self, AiClient};
 use reqwest::Client;
 use crate::config::Config;
+use crate::tls::ClientTls;
+use crate::commands::{print_ai_failed, print_ai_skipped};
+use crate::{noteln, outln};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// Headers every API response is expected to carry: (header, rule id, severity, remediation).
+const EXPECTED_SECURITY_HEADERS: &[(&str, &str, Severity, &str)] = &[
+    ("Strict-Transport-Security", "missing-hsts", Severity::Medium,
+        "Send `Strict-Transport-Security: max-age=31536000; includeSubDomains` on every HTTPS response."),
+    ("Content-Security-Policy", "missing-csp", Severity::Medium,
+        "Send a restrictive policy, e.g. `Content-Security-Policy: default-src 'none'` for JSON APIs."),
+    ("X-Frame-Options", "missing-x-frame-options", Severity::Low,
+        "Send `X-Frame-Options: DENY` (or `frame-ancestors 'none'` in the CSP)."),
+    ("X-Content-Type-Options", "missing-x-content-type-options", Severity::Low,
+        "Send `X-Content-Type-Options: nosniff`."),
+];
+
+/// Body fragments of auto-generated directory indexes (Apache, nginx, Python, IIS).
+const DIRECTORY_LISTING_SIGNATURES: &[&str] = &[
+    "<title>index of /",
+    "<h1>index of /",
+    "<title>directory listing for",
+    "[to parent directory]",
+];
+
+/// Upper bound on the requests `--active` sends, however many parameters the target has.
+pub const MAX_ACTIVE_PROBES: usize = 12;
+
+/// Pause between two active probes, so a scan never floods the target.
+const ACTIVE_PROBE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Query parameters tried per scan; targets without any get a `q` parameter.
+const MAX_PROBED_PARAMETERS: usize = 2;
+
+/// Payloads that must never come back unescaped.
+const XSS_PAYLOADS: &[&str] = &["<script>alert('nuts')</script>", "\"><svg onload=alert('nuts')>"];
+
+/// Payloads that break a query built by string concatenation.
+const SQLI_PAYLOADS: &[&str] = &["'", "' OR '1'='1"];
+
+/// Body fragments (lowercase) of error pages that leak internals: (signature, what leaks).
+const VERBOSE_ERROR_SIGNATURES: &[(&str, &str)] = &[
+    ("you have an error in your sql syntax", "SQL error"),
+    ("sqlstate[", "SQL error"),
+    ("unclosed quotation mark after the character string", "SQL error"),
+    ("syntax error at or near", "SQL error"),
+    ("sqlite3::", "SQL error"),
+    ("ora-00933", "SQL error"),
+    ("traceback (most recent call last)", "stack trace"),
+    ("exception in thread \"", "stack trace"),
+    ("at java.", "stack trace"),
+    ("system.nullreferenceexception", "stack trace"),
+    ("at object.<anonymous>", "stack trace"),
+    (".php on line", "stack trace"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Critical,
+    High,
+    Medium,
+    Low,
+    Info,
+}
+
+impl Severity {
+    /// Severity named in a line of free text, e.g. an AI bullet like "- High: tokens in URLs".
+    pub fn from_text(text: &str) -> Option<Self> {
+        let text = text.to_lowercase();
+        [("critical", Self::Critical), ("high", Self::High), ("medium", Self::Medium), ("moderate", Self::Medium), ("low", Self::Low)]
+            .into_iter()
+            .find(|(keyword, _)| text.split(|c: char| !c.is_ascii_alphanumeric()).any(|word| word == *keyword))
+            .map(|(_, severity)| severity)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Critical => "CRITICAL",
+            Self::High => "HIGH",
+            Self::Medium => "MEDIUM",
+            Self::Low => "LOW",
+            Self::Info => "INFO",
+        }
+    }
+}
+
+/// Result of a deterministic rule: the same response always yields the same findings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub id: String,
+    pub severity: Severity,
+    pub title: String,
+    pub evidence: String,
+    pub remediation: String,
+    /// The request that triggered an active probe, to reproduce it by hand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<String>,
+}
+
+impl Finding {
+    fn new(id: &str, severity: Severity, title: impl Into<String>, evidence: impl Into<String>, remediation: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            severity,
+            title: title.into(),
+            evidence: evidence.into(),
+            remediation: remediation.to_string(),
+            request: None,
+        }
+    }
+
+    fn with_request(mut self, request: &str) -> Self {
+        self.request = Some(request.to_string());
+        self
+    }
+}
+
+/// One response received during the scan.
+#[derive(Debug, Clone)]
+pub struct ScannedResponse {
+    pub url: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl ScannedResponse {
+    /// All values of a header, matched case-insensitively.
+    fn header_values(&self, name: &str) -> impl Iterator<Item = &str> {
+        let name = name.to_string();
+        self.headers.iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(&name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.header_values(name).next()
+    }
+
+    /// Text block handed to the AI and stored in saved reports.
+    pub fn evidence(&self) -> String {
+        let status = reqwest::StatusCode::from_u16(self.status)
+            .map(|s| s.to_string())
+            .unwrap_or_else(|_| self.status.to_string());
+        let headers: Vec<String> = self.headers.iter().map(|(k, v)| format!("{}: {}", k, v)).collect();
+        format!("URL: {}\nStatus: {}\nHeaders:\n{}\nBody:\n{}\n", self.url, status, headers.join("\n"), self.body)
+    }
+}
+
+/// Run every rule against one response.
+pub fn check_response(response: &ScannedResponse) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if response.url.starts_with("http://") {
+        findings.push(Finding::new(
+            "plain-http", Severity::High, "Endpoint served over plain HTTP", &response.url,
+            "Serve the API over HTTPS only and redirect or refuse plain HTTP.",
+        ));
+    }
+
+    for (header, id, severity, remediation) in EXPECTED_SECURITY_HEADERS {
+        if response.header(header).is_none() {
+            findings.push(Finding::new(id, *severity, format!("Missing {} header", header), format!("no {} in response", header), remediation));
+        }
+    }
+
+    if let Some(server) = response.header("server").filter(|value| value.chars().any(|c| c.is_ascii_digit())) {
+        findings.push(Finding::new(
+            "server-version-disclosure", Severity::Low, "Server header discloses a version", format!("Server: {}", server),
+            "Strip the version from the Server header (e.g. `server_tokens off` in nginx).",
+        ));
+    }
+    if let Some(powered_by) = response.header("x-powered-by") {
+        findings.push(Finding::new(
+            "x-powered-by-disclosure", Severity::Low, "X-Powered-By header discloses the stack", format!("X-Powered-By: {}", powered_by),
+            "Remove the X-Powered-By header (e.g. `app.disable('x-powered-by')` in Express).",
+        ));
+    }
+
+    for cookie in response.header_values("set-cookie") {
+        let name = cookie.split(['=', ';']).next().unwrap_or_default().trim();
+        let attributes: Vec<String> = cookie.split(';').skip(1)
+            .map(|a| a.split('=').next().unwrap_or_default().trim().to_ascii_lowercase())
+            .collect();
+        let missing: Vec<&str> = [("secure", "Secure"), ("httponly", "HttpOnly"), ("samesite", "SameSite")]
+            .into_iter()
+            .filter(|(attribute, _)| !attributes.iter().any(|a| a == attribute))
+            .map(|(_, label)| label)
+            .collect();
+        if !missing.is_empty() {
+            let severity = if missing.contains(&"Secure") || missing.contains(&"HttpOnly") { Severity::Medium } else { Severity::Low };
+            findings.push(Finding::new(
+                "insecure-cookie", severity, format!("Cookie '{}' is missing {}", name, missing.join(", ")), format!("Set-Cookie: {}", cookie),
+                "Set cookies with `Secure; HttpOnly; SameSite=Lax` (or Strict).",
+            ));
+        }
+    }
+
+    if response.header("access-control-allow-origin").map(str::trim) == Some("*") {
+        let credentials = response.header("access-control-allow-credentials")
+            .is_some_and(|value| value.trim().eq_ignore_ascii_case("true"));
+        if credentials {
+            findings.push(Finding::new(
+                "cors-wildcard-credentials", Severity::High, "CORS allows any origin together with credentials",
+                "Access-Control-Allow-Origin: * with Access-Control-Allow-Credentials: true",
+                "Echo only allow-listed origins when credentials are allowed.",
+            ));
+        } else {
+            findings.push(Finding::new(
+                "cors-wildcard", Severity::Low, "CORS allows any origin", "Access-Control-Allow-Origin: *",
+                "Restrict Access-Control-Allow-Origin to the origins that need the API.",
+            ));
+        }
+    }
+
+    let body = response.body.to_lowercase();
+    if let Some(signature) = DIRECTORY_LISTING_SIGNATURES.iter().find(|s| body.contains(*s)) {
+        findings.push(Finding::new(
+            "directory-listing", Severity::Medium, "Directory listing is enabled", format!("{}: body contains '{}'", response.url, signature),
+            "Disable automatic directory indexes on the web server.",
+        ));
+    }
+
+    findings
+}
+
+/// Rules over every response of a scan; a rule that fires on several responses is reported once.
+pub fn check_responses(responses: &[ScannedResponse]) -> Vec<Finding> {
+    let mut findings = dedupe_findings(responses.iter().flat_map(check_response));
+    findings.sort_by_key(|f| f.severity);
+    findings
+}
+
+/// First finding per rule and title, in order.
+fn dedupe_findings(all: impl IntoIterator<Item = Finding>) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+    for finding in all {
+        if !findings.iter().any(|f| f.id == finding.id && f.title == finding.title) {
+            findings.push(finding);
+        }
+    }
+    findings
+}
+
+/// What an error page leaks, if its body matches a known signature.
+fn verbose_error(body: &str) -> Option<(&'static str, &'static str)> {
+    let body = body.to_lowercase();
+    VERBOSE_ERROR_SIGNATURES.iter().find(|(signature, _)| body.contains(signature)).copied()
+}
+
+/// Judge the response to one active probe. `payload` is set for reflection probes; the
+/// verbose-error rule runs on every probe response.
+pub fn check_probe(request: &str, parameter: &str, payload: Option<&str>, response: &ScannedResponse, baseline: &ScannedResponse) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    if let Some(payload) = payload {
+        if XSS_PAYLOADS.contains(&payload) && response.body.contains(payload) {
+            findings.push(Finding::new(
+                "reflected-xss", Severity::High, format!("Parameter '{}' is reflected without encoding", parameter),
+                format!("response body contains {}", payload),
+                "HTML-encode user input before writing it into a response, and send a restrictive Content-Security-Policy.",
+            ).with_request(request));
+        }
+        if SQLI_PAYLOADS.contains(&payload) && verbose_error(&baseline.body).is_none() {
+            if let Some((signature, "SQL error")) = verbose_error(&response.body) {
+                findings.push(Finding::new(
+                    "sql-injection", Severity::Critical, format!("Parameter '{}' breaks the SQL query", parameter),
+                    format!("HTTP {} with '{}' in the body", response.status, signature),
+                    "Use parameterized queries (prepared statements) instead of building SQL from input.",
+                ).with_request(request));
+            }
+        }
+    }
+    if let Some((signature, leak)) = verbose_error(&response.body) {
+        findings.push(Finding::new(
+            "verbose-error", Severity::Medium, format!("Error page reveals a {}", leak),
+            format!("HTTP {} with '{}' in the body", response.status, signature),
+            "Return a generic error body and keep stack traces and database errors in the server logs.",
+        ).with_request(request));
+    }
+    findings
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SecurityIssue {
+    pub severity: Severity,
+    pub title: String,
+}
+
+impl SecurityIssue {
+    /// Key used to match the same issue across two scans.
+    fn key(&self) -> String {
+        self.title.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+    }
+}
+
+/// Raw evidence collected from one request of the scan.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointFindings {
+    pub url: String,
+    pub evidence: String,
+}
+
+/// A finished scan as written by `--save` and read back by `--diff`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityReport {
+    pub url: String,
+    pub timestamp: DateTime<Utc>,
+    pub endpoints: Vec<EndpointFindings>,
+    /// Results of the deterministic rules.
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<String>,
+    pub issues: Vec<SecurityIssue>,
+}
+
+impl SecurityReport {
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read report '{}': {}", path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("'{}' is not a security report: {}", path.display(), e).into())
+    }
+}
+
+/// Issues of a new scan compared to an earlier one.
+#[derive(Debug, Default, PartialEq)]
+pub struct SecurityDiff {
+    pub new: Vec<SecurityIssue>,
+    pub resolved: Vec<SecurityIssue>,
+    pub unchanged: Vec<SecurityIssue>,
+}
+
+/// Match issues by title (case and whitespace insensitive); each list keeps the most severe first.
+pub fn diff_issues(old: &[SecurityIssue], new: &[SecurityIssue]) -> SecurityDiff {
+    let old_keys: Vec<String> = old.iter().map(SecurityIssue::key).collect();
+    let new_keys: Vec<String> = new.iter().map(SecurityIssue::key).collect();
+
+    let mut diff = SecurityDiff::default();
+    for (issue, key) in new.iter().zip(&new_keys) {
+        if old_keys.contains(key) {
+            diff.unchanged.push(issue.clone());
+        } else {
+            diff.new.push(issue.clone());
+        }
+    }
+    diff.resolved = old.iter().zip(&old_keys)
+        .filter(|(_, key)| !new_keys.contains(key))
+        .map(|(issue, _)| issue.clone())
+        .collect();
+    for list in [&mut diff.new, &mut diff.resolved, &mut diff.unchanged] {
+        list.sort_by_key(|issue| issue.severity);
+    }
+    diff
+}
+
+/// Bullet points of the AI analysis that name a severity, e.g. "- Critical: SQL error leaked".
+pub fn parse_ai_issues(analysis: &str) -> Vec<SecurityIssue> {
+    analysis.lines()
+        .map(str::trim)
+        .filter_map(|line| {
+            line.strip_prefix("- ")
+                .or_else(|| line.strip_prefix("* "))
+                .or_else(|| line.strip_prefix("• "))
+        })
+        .filter_map(|bullet| {
+            let severity = Severity::from_text(bullet)?;
+            let title = bullet.replace("**", "");
+            Some(SecurityIssue { severity, title: title.trim().to_string() })
+        })
+        .collect()
+}
 
 pub struct SecurityCommand {
-    #[allow(dead_code)]
     config: Config,
     deep_scan: bool,
+    active: bool,
     auth_token: Option<String>,
     save_file: Option<String>,
+    diff_file: Option<String>,
     http_client: Client,
-    ai_client: AnthropicClient,
 }
 
 impl SecurityCommand {
     pub fn new(config: Config) -> Self {
-        let api_key = config.anthropic_api_key.clone()
-            .unwrap_or_default();
-
         Self {
             config,
             deep_scan: false,
+            active: false,
             auth_token: None,
             save_file: None,
+            diff_file: None,
             http_client: Client::new(),
-            ai_client: ClientBuilder::default()
-                .api_key(api_key)
-                .build()
-                .unwrap(),
         }
     }
 
+    /// Build the AI client on demand so offline scans never touch a provider.
+    fn ai_client(&self) -> Result<Box<dyn AiClient>, Box<dyn std::error::Error>> {
+        ai::optional_client(&self.config, "security")
+    }
+
     pub fn with_deep_scan(mut self, deep_scan: bool) -> Self {
         self.deep_scan = deep_scan;
         self
     }
 
+    /// Send attack payloads, an unauthenticated replay and a POST to the target (`--active`).
+    pub fn with_active(mut self, active: bool) -> Self {
+        self.active = active;
+        self
+    }
+
     pub fn with_auth(mut self, auth_token: Option<String>) -> Self {
         self.auth_token = auth_token;
         self
@@ -48,12 +436,24 @@ impl SecurityCommand {
         self
     }
 
+    /// Present a client certificate and trust extra CAs (`--cert`, `--key`, `--cacert`).
+    pub fn with_tls(mut self, tls: &ClientTls) -> Self {
+        self.http_client = tls.apply(Client::builder()).build().expect("HTTP client");
+        self
+    }
+
+    /// Compare the scan against a report saved earlier with `--save`.
+    pub fn with_diff_file(mut self, diff_file: Option<String>) -> Self {
+        self.diff_file = diff_file;
+        self
+    }
+
     async fn display_security_analysis(&self, analysis: &str) {
         let term = Term::stdout();
         let width = term.size().1 as usize;
         
-        println!("\n{}", style("📊 Security Analysis").bold().cyan());
-        println!("{}\n", style("═".repeat(width.min(80))).cyan());
+        outln!("\n{}", style("📊 Security Analysis").bold().cyan());
+        outln!("{}\n", style("═".repeat(width.min(80))).cyan());
 
         // Split analysis into sections based on numbered items
         let sections: Vec<&str> = analysis.split("\n\n").collect();
@@ -62,45 +462,48 @@ impl SecurityCommand {
             if section.starts_with(|c: char| c.is_ascii_digit()) {
                 // Main section headers
                 let (header, content) = section.split_once(":\n").unwrap_or((section, ""));
-                println!("{}", style(header).yellow().bold());
+                outln!("{}", style(header).yellow().bold());
                 
                 // Process bullet points and sub-sections
                 for line in content.lines() {
                     if line.trim().is_empty() { continue; }
                     
                     if line.starts_with("- ") {
-                        println!("  {} {}", 
+                        outln!("  {} {}", 
                             style("•").cyan(),
                             style(line.strip_prefix("- ").unwrap_or(line)).white()
                         );
                     } else if line.starts_with("`") {
                         // Format code/technical items
-                        println!("    {}", style(line).blue());
+                        outln!("    {}", style(line).blue());
                     } else {
-                        println!("  {}", style(line).white());
+                        outln!("  {}", style(line).white());
                     }
                 }
-                println!(); // Add spacing between sections
+                outln!(); // Add spacing between sections
             }
         }
 
         // Add a summary box at the end
-        println!("{}", style("─".repeat(width.min(80))).cyan());
+        outln!("{}", style("─".repeat(width.min(80))).cyan());
         let summary = style("ℹ️  This analysis is based on the API response only. A comprehensive security audit would require additional context.").dim();
-        println!("{}\n", summary);
+        outln!("{}\n", summary);
     }
 
-    pub async fn execute(&self, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
-        println!("{}", style("🔒 Starting security scan...").bold());
+    /// Run the scan and return its report, the same one `--save` writes.
+    pub async fn execute(&self, args: &[String]) -> Result<SecurityReport, Box<dyn std::error::Error>> {
+        noteln!("{}", style("🔒 Starting security scan...").bold());
         
         if self.deep_scan {
-            println!("{}", style("📋 Deep scan enabled - this may take a few minutes").yellow());
+            noteln!("{}", style("📋 Deep scan enabled - this may take a few minutes").yellow());
+        }
+        if self.active {
+            outln!("{}", style("⚠️  ACTIVE PROBING: attack payloads, an unauthenticated replay and a POST will be sent to the target.").red().bold());
+            outln!("{}", style("   Only scan APIs you own or are authorized to test.").red());
         }
 
         if args.len() < 2 {
-            println!("❌ Usage: security <url>");
-            println!("Example: security api.example.com/v1/users");
-            return Ok(());
+            return Err("Usage: security <url>, e.g. security api.example.com/v1/users".into());
         }
 
         let url = if args[1].starts_with("http") {
@@ -109,35 +512,105 @@ impl SecurityCommand {
             format!("http://{}", args[1])
         };
 
-        println!("🔒 Running security analysis on {}", style(&url).cyan());
+        outln!("🔒 Running security analysis on {}", style(&url).cyan());
         
-        let mut analysis_data = Vec::new();
-
-        // Basic scan - check main endpoint
-        let response = self.http_client.get(&url).send().await?;
-        analysis_data.push(self.analyze_response(response).await?);
+        let responses = self.collect_responses(&url).await?;
+        let mut findings = check_responses(&responses);
+        if self.active {
+            findings = dedupe_findings(findings.into_iter().chain(self.active_probes(&url, &responses).await?));
+            findings.sort_by_key(|f| f.severity);
+        }
+        Self::display_findings(&findings);
 
-        // Deep scan - additional checks
-        if self.deep_scan {
-            // Check common security endpoints
-            for endpoint in ["/security.txt", "/.well-known/security.txt", "/robots.txt"] {
-                let sec_url = format!("{}{}", url, endpoint);
-                if let Ok(resp) = self.http_client.get(&sec_url).send().await {
-                    analysis_data.push(self.analyze_response(resp).await?);
+        let analysis = if self.config.is_offline() {
+            print_ai_skipped("AI security analysis");
+            None
+        } else {
+            let evidence: Vec<String> = responses.iter().map(ScannedResponse::evidence).collect();
+            match self.ai_analysis(&evidence).await {
+                Ok(analysis) => analysis,
+                Err(e) => {
+                    print_ai_failed("AI security analysis", e.as_ref());
+                    None
                 }
             }
+        };
+
+        let report = Self::build_report(&url, &responses, findings, analysis);
+        if let Some(path) = &self.save_file {
+            report.save(Path::new(path))?;
+            outln!("📄 Report saved to: {}", style(path).green());
+        }
+        if let Some(path) = &self.diff_file {
+            let previous = SecurityReport::load(Path::new(path))?;
+            Self::display_diff(&previous, &diff_issues(&previous.issues, &report.issues));
+        }
+
+        Ok(report)
+    }
+
+    /// Rule findings and AI issues of a scan, together with its raw evidence.
+    pub fn build_report(url: &str, responses: &[ScannedResponse], findings: Vec<Finding>, analysis: Option<String>) -> SecurityReport {
+        let mut issues: Vec<SecurityIssue> = findings.iter()
+            .map(|f| SecurityIssue { severity: f.severity, title: f.title.clone() })
+            .collect();
+        issues.extend(analysis.as_deref().map(parse_ai_issues).unwrap_or_default());
+
+        SecurityReport {
+            url: url.to_string(),
+            timestamp: Utc::now(),
+            endpoints: responses.iter()
+                .map(|response| EndpointFindings { url: response.url.clone(), evidence: response.evidence() })
+                .collect(),
+            findings,
+            analysis,
+            issues,
+        }
+    }
+
+    fn display_findings(findings: &[Finding]) {
+        outln!("\n{}", style("🧾 Security Checks").bold().cyan());
+        if findings.is_empty() {
+            outln!("  {} No issues found by the built-in rules", style("✓").green());
+            outln!();
+            return;
+        }
+
+        let id_width = findings.iter().map(|f| f.id.len()).max().unwrap_or(0);
+        outln!("  {:<8}  {:<id_width$}  {}", style("SEVERITY").bold(), style("RULE").bold(), style("FINDING").bold());
+        for finding in findings {
+            let severity = match finding.severity {
+                Severity::Critical | Severity::High => style(format!("{:<8}", finding.severity.label())).red().bold(),
+                Severity::Medium => style(format!("{:<8}", finding.severity.label())).yellow(),
+                Severity::Low | Severity::Info => style(format!("{:<8}", finding.severity.label())).dim(),
+            };
+            outln!("  {}  {:<id_width$}  {}", severity, finding.id, finding.title);
+            outln!("  {:<8}  {:<id_width$}  {}", "", "", style(format!("↳ {}", finding.evidence)).dim());
+            if let Some(request) = &finding.request {
+                outln!("  {:<8}  {:<id_width$}  {}", "", "", style(format!("request: {}", request)).dim());
+            }
+            outln!("  {:<8}  {:<id_width$}  {}", "", "", style(format!("fix: {}", finding.remediation)).green());
+        }
+        outln!();
+    }
 
-            // Check HTTP methods
-            for method in ["HEAD", "OPTIONS", "TRACE"] {
-                if let Ok(resp) = self.http_client
-                    .request(reqwest::Method::from_bytes(method.as_bytes()).unwrap(), &url)
-                    .send()
-                    .await 
-                {
-                    analysis_data.push(self.analyze_response(resp).await?);
-                }
+    fn display_diff(previous: &SecurityReport, diff: &SecurityDiff) {
+        outln!("\n{} {}", style("🔀 Changes since").bold().cyan(), style(previous.timestamp.format("%Y-%m-%d %H:%M UTC")).dim());
+        let sections = [
+            ("🆕 New issues", &diff.new, console::Color::Red),
+            ("✅ Resolved issues", &diff.resolved, console::Color::Green),
+            ("➖ Unchanged issues", &diff.unchanged, console::Color::White),
+        ];
+        for (title, issues, color) in sections {
+            outln!("\n{} ({})", style(title).fg(color).bold(), issues.len());
+            for issue in issues {
+                outln!("  {} {}", style(format!("[{}]", issue.severity.label())).fg(color), issue.title);
             }
         }
+        outln!();
+    }
+
+    async fn ai_analysis(&self, analysis_data: &[String]) -> Result<Option<String>, Box<dyn std::error::Error>> {
 
         // Combine all analyses for AI processing
         let analysis_prompt = if self.deep_scan {
@@ -167,52 +640,154 @@ impl SecurityCommand {
             )
         };
 
-        println!("🤖 Analyzing response with Claude AI...\n");
+        noteln!("🤖 Analyzing response with Claude AI...\n");
 
         // Get AI analysis
-        let messages = vec![Message {
-            role: Role::User,
-            content: vec![ContentBlock::Text { text: analysis_prompt }]
-        }];
+        let text = self.ai_client()?.ask(&analysis_prompt).await?;
 
-        let messages_request = MessagesRequestBuilder::default()
-            .messages(messages)
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(1000_usize)
-            .build()?;
+        // Print the analysis
+        self.display_security_analysis(&text).await;
+        Ok(Some(text))
+    }
 
-        let messages_response = self.ai_client.messages(messages_request).await?;
+    /// Fetch the target (plus the deep-scan extras) and return one formatted evidence block per response.
+    pub async fn collect_evidence(&self, url: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(self.collect_responses(url).await?.iter().map(ScannedResponse::evidence).collect())
+    }
 
-        // Print the analysis
-        if let Some(ContentBlock::Text { text }) = messages_response.content.first() {
-            self.display_security_analysis(text).await;
-        } else {
-            println!("❌ Error: Could not parse AI response");
+    /// Fetch the target, plus well-known files and extra methods for a deep scan.
+    pub async fn collect_responses(&self, url: &str) -> Result<Vec<ScannedResponse>, Box<dyn std::error::Error>> {
+        let mut responses = Vec::new();
+
+        // Basic scan - check main endpoint
+        let response = self.authorized(self.http_client.get(url)).send().await?;
+        responses.push(Self::scanned(url, response).await?);
+
+        // Deep scan - additional checks
+        if self.deep_scan {
+            // Check common security endpoints
+            for endpoint in ["/security.txt", "/.well-known/security.txt", "/robots.txt"] {
+                let sec_url = format!("{}{}", url, endpoint);
+                if let Ok(resp) = self.authorized(self.http_client.get(&sec_url)).send().await {
+                    responses.push(Self::scanned(&sec_url, resp).await?);
+                }
+            }
+
+            // Check HTTP methods
+            for method in ["HEAD", "OPTIONS", "TRACE"] {
+                if let Ok(resp) = self.authorized(self.http_client
+                    .request(reqwest::Method::from_bytes(method.as_bytes()).unwrap(), url))
+                    .send()
+                    .await 
+                {
+                    responses.push(Self::scanned(url, resp).await?);
+                }
+            }
         }
 
-        Ok(())
+        Ok(responses)
     }
 
-    async fn analyze_response(&self, response: reqwest::Response) -> Result<String, Box<dyn std::error::Error>> {
-        let url = response.url().to_string();
-        let status = response.status();
-        let headers = response.headers().clone();
-        let body = response.text().await?;
+    /// Add the `--auth` token; a bare token is sent as a bearer token.
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.auth_token {
+            Some(token) if token.contains(' ') => request.header(reqwest::header::AUTHORIZATION, token),
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
 
-        Ok(format!(
-            "URL: {}\nStatus: {}\nHeaders:\n{}\nBody:\n{}\n",
-            url,
-            status,
-            self.format_headers(&headers),
-            body
-        ))
+    /// Send one active probe, unless the probe budget is spent. Probes are spaced by
+    /// [`ACTIVE_PROBE_INTERVAL`]; a probe the target fails to answer is skipped.
+    async fn probe(&self, sent: &mut usize, method: reqwest::Method, url: &str, authorized: bool) -> Option<ScannedResponse> {
+        if *sent >= MAX_ACTIVE_PROBES {
+            return None;
+        }
+        if *sent > 0 {
+            tokio::time::sleep(ACTIVE_PROBE_INTERVAL).await;
+        }
+        *sent += 1;
+        let request = self.http_client.request(method, url);
+        let request = if authorized { self.authorized(request) } else { request };
+        let response = request.send().await.ok()?;
+        Self::scanned(url, response).await.ok()
     }
 
-    fn format_headers(&self, headers: &header::HeaderMap) -> String {
-        headers
-            .iter()
-            .map(|(k, v)| format!("{}: {}", k, v.to_str().unwrap_or("")))
-            .collect::<Vec<String>>()
-            .join("\n")
+    /// Active probes against `url`: payload reflection and SQL errors in query parameters,
+    /// authentication enforcement and verb tampering. At most [`MAX_ACTIVE_PROBES`] requests.
+    pub async fn active_probes(&self, url: &str, responses: &[ScannedResponse]) -> Result<Vec<Finding>, Box<dyn std::error::Error>> {
+        let baseline = responses.first().ok_or("active probes need the response of the main request")?;
+        let target = url::Url::parse(url)?;
+        let mut parameters: Vec<String> = target.query_pairs().map(|(name, _)| name.into_owned()).collect();
+        parameters.dedup();
+        if parameters.is_empty() {
+            parameters.push("q".to_string());
+        }
+        parameters.truncate(MAX_PROBED_PARAMETERS);
+
+        outln!("\n{}", style("🧨 Active probes").bold().cyan());
+        let mut sent = 0;
+        let mut findings = Vec::new();
+
+        for parameter in &parameters {
+            for payload in XSS_PAYLOADS.iter().chain(SQLI_PAYLOADS) {
+                let mut probe_url = target.clone();
+                let pairs: Vec<(String, String)> = target.query_pairs()
+                    .filter(|(name, _)| name != parameter)
+                    .map(|(name, value)| (name.into_owned(), value.into_owned()))
+                    .collect();
+                probe_url.query_pairs_mut().clear().extend_pairs(pairs).append_pair(parameter, payload);
+                let request = format!("GET {}", probe_url);
+                if let Some(response) = self.probe(&mut sent, reqwest::Method::GET, probe_url.as_str(), true).await {
+                    findings.extend(check_probe(&request, parameter, Some(payload), &response, baseline));
+                }
+            }
+        }
+
+        let accepted = |status: u16| (200..300).contains(&status);
+        match &self.auth_token {
+            Some(_) if accepted(baseline.status) => {
+                let request = format!("GET {} without the Authorization header", url);
+                if let Some(response) = self.probe(&mut sent, reqwest::Method::GET, url, false).await {
+                    if accepted(response.status) {
+                        findings.push(Finding::new(
+                            "auth-not-enforced", Severity::Critical, "Endpoint answers without the --auth credentials",
+                            format!("HTTP {} without credentials, HTTP {} with them", response.status, baseline.status),
+                            "Reject requests without valid credentials with 401 before doing any work.",
+                        ).with_request(&request));
+                    }
+                    findings.extend(check_probe(&request, "", None, &response, baseline));
+                }
+            }
+            Some(_) => outln!("  {} Authentication replay skipped: HTTP {} even with --auth", style("–").dim(), baseline.status),
+            None => outln!("  {} Authentication replay skipped: no --auth given", style("–").dim()),
+        }
+
+        let allowed_post = responses.iter()
+            .filter_map(|response| response.header("allow"))
+            .any(|allow| allow.split(',').any(|method| method.trim().eq_ignore_ascii_case("POST")));
+        let request = format!("POST {} with an empty body", url);
+        if let Some(response) = self.probe(&mut sent, reqwest::Method::POST, url, true).await {
+            if accepted(response.status) && !allowed_post {
+                findings.push(Finding::new(
+                    "verb-tampering", Severity::Medium, "POST is accepted where only GET is expected",
+                    format!("HTTP {} for POST; no Allow header lists POST", response.status),
+                    "Route each method explicitly and answer 405 Method Not Allowed for the rest.",
+                ).with_request(&request));
+            }
+            findings.extend(check_probe(&request, "", None, &response, baseline));
+        }
+
+        outln!("  {} {} probe(s) sent", style("✓").green(), sent);
+        Ok(dedupe_findings(findings))
     }
-} 
\ No newline at end of file
+
+    async fn scanned(url: &str, response: reqwest::Response) -> Result<ScannedResponse, Box<dyn std::error::Error>> {
+        let status = response.status().as_u16();
+        let headers = response.headers().iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+            .collect();
+        let body = response.text().await?;
+        Ok(ScannedResponse { url: url.to_string(), status, headers, body })
+    }
+}
@@ -0,0 +1,262 @@
+//! `snapshot`: save an endpoint's JSON response once and check later responses against it,
+//! so an unintended change in a payload shows up as a failing check.
+//!
+//! Snapshots live in `~/.nuts/snapshots/<name>.json` together with the request that made
+//! them and the `--ignore-path` rules, so `check` and `update` need only the name. The
+//! comparison is the same structural diff `diff` uses.
+
+use crate::commands::diff::{diff_json, fetch, normalize, Difference};
+use crate::config::Environment;
+use console::style;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A saved response and the request that produced it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub method: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// JSON paths left out of the stored response and of every check, e.g. `$.meta.request_id`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    pub status: u16,
+    /// The normalized response body; a non-JSON body is stored as a string.
+    pub response: Value,
+    pub saved_at: String,
+}
+
+/// The directory snapshots are kept in.
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `~/.nuts/snapshots`.
+    pub fn default_dir() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(dirs::home_dir()
+            .ok_or("Could not find home directory")?
+            .join(".nuts")
+            .join("snapshots"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) || name.starts_with('.') {
+            return Err(format!("Invalid snapshot name '{}': use letters, digits, '-', '_' and '.'", name).into());
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    pub fn save(&self, snapshot: &Snapshot) -> Result<PathBuf, Box<dyn Error>> {
+        let path = self.path(&snapshot.name)?;
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(&path, serde_json::to_string_pretty(snapshot)?)?;
+        Ok(path)
+    }
+
+    pub fn load(&self, name: &str) -> Result<Snapshot, Box<dyn Error>> {
+        let path = self.path(name)?;
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(format!("No snapshot named '{}'. Save one with: snapshot save {} <METHOD> <URL>", name, name).into());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&content).map_err(|e| format!("Could not read snapshot {}: {}", path.display(), e).into())
+    }
+
+    /// Every saved snapshot, sorted by name.
+    pub fn list(&self) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut snapshots = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                    snapshots.push(self.load(name)?);
+                }
+            }
+        }
+        snapshots.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(snapshots)
+    }
+}
+
+pub struct SnapshotCommand {
+    client: Client,
+    environment: Environment,
+    store: SnapshotStore,
+}
+
+impl SnapshotCommand {
+    pub fn new(store: SnapshotStore) -> Self {
+        Self {
+            client: Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap(),
+            environment: Environment::default(),
+            store,
+        }
+    }
+
+    /// Substitute `{{name}}` placeholders in the URL, headers and body when fetching.
+    pub fn with_environment(mut self, environment: Environment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// `snapshot save|check|update|list ...`. A check that finds changes is an error.
+    pub async fn execute(&self, args: &[&str]) -> Result<(), Box<dyn Error>> {
+        match (args.get(1).copied(), args.get(2).copied()) {
+            (Some("save"), Some(name)) => {
+                let snapshot = self.save(&Self::parse_request(name, &args[3..])?).await?;
+                println!("✅ Saved snapshot {} ({} {} → {})", style(&snapshot.name).cyan(), snapshot.method, snapshot.url, snapshot.status);
+            }
+            (Some("check"), Some(name)) => {
+                let differences = self.check(name).await?;
+                if !differences.is_empty() {
+                    return Err(format!(
+                        "Snapshot '{}' changed: {} difference(s). Run 'snapshot update {}' if this is expected",
+                        name, differences.len(), name
+                    ).into());
+                }
+                println!("✅ {}", style(format!("Response matches snapshot '{}'", name)).green());
+            }
+            (Some("update"), Some(name)) => {
+                let mut snapshot = self.store.load(name)?;
+                for pair in args[3..].chunks(2) {
+                    match pair {
+                        ["--ignore-path", path] => snapshot.ignore.push(path.to_string()),
+                        ["--ignore-path"] => return Err("Value required after --ignore-path".into()),
+                        [flag, ..] => return Err(format!("Unknown option: {}", flag).into()),
+                        [] => {}
+                    }
+                }
+                let snapshot = self.save(&snapshot).await?;
+                println!("✅ Updated snapshot {} ({} {} → {})", style(&snapshot.name).cyan(), snapshot.method, snapshot.url, snapshot.status);
+            }
+            (Some("list"), _) => {
+                let snapshots = self.store.list()?;
+                if snapshots.is_empty() {
+                    println!("No snapshots saved in {}", self.store.dir().display());
+                }
+                for snapshot in snapshots {
+                    println!("  {} {} {} ({})", style(&snapshot.name).cyan(), snapshot.method, snapshot.url, style(&snapshot.saved_at).dim());
+                }
+            }
+            _ => return Err("Usage: snapshot save <name> [METHOD] <URL> [-H 'K: V'] [-d BODY] [--ignore-path $.path]... | check <name> | update <name> | list".into()),
+        }
+        Ok(())
+    }
+
+    /// The request part of a new snapshot from `[METHOD] URL [-H 'K: V']... [-d BODY] [--ignore-path PATH]...`.
+    pub fn parse_request(name: &str, args: &[&str]) -> Result<Snapshot, Box<dyn Error>> {
+        let mut snapshot = Snapshot {
+            name: name.to_string(),
+            method: "GET".to_string(),
+            url: String::new(),
+            headers: Vec::new(),
+            body: None,
+            ignore: Vec::new(),
+            status: 0,
+            response: Value::Null,
+            saved_at: String::new(),
+        };
+        let mut positional = Vec::new();
+
+        let mut i = 0;
+        while i < args.len() {
+            match (args[i], args.get(i + 1)) {
+                ("-H" | "--header", Some(header)) => {
+                    let (key, value) = header.split_once(':').ok_or("Header must be in format 'Key: Value'")?;
+                    snapshot.headers.push((key.trim().to_string(), value.trim().to_string()));
+                }
+                ("--bearer", Some(token)) => snapshot.headers.push(("Authorization".to_string(), format!("Bearer {}", token))),
+                ("-d" | "--data", Some(body)) => snapshot.body = Some(body.to_string()),
+                ("--ignore-path", Some(path)) => snapshot.ignore.push(path.to_string()),
+                (flag @ ("-H" | "--header" | "--bearer" | "-d" | "--data" | "--ignore-path"), None) => {
+                    return Err(format!("Value required after {}", flag).into());
+                }
+                (flag, _) if flag.starts_with('-') => return Err(format!("Unknown option: {}", flag).into()),
+                (value, _) => {
+                    positional.push(value);
+                    i += 1;
+                    continue;
+                }
+            }
+            i += 2;
+        }
+
+        match positional.as_slice() {
+            [method, url] => {
+                snapshot.method = method.to_uppercase();
+                snapshot.url = url.to_string();
+            }
+            [url] => snapshot.url = url.to_string(),
+            _ => return Err("Usage: snapshot save <name> [METHOD] <URL> [-H 'K: V'] [-d BODY] [--ignore-path $.path]...".into()),
+        }
+        Ok(snapshot)
+    }
+
+    /// Send the snapshot's request and store the normalized response under its name,
+    /// replacing any earlier one.
+    pub async fn save(&self, request: &Snapshot) -> Result<Snapshot, Box<dyn Error>> {
+        let (status, response) = self.fetch(request).await?;
+        let snapshot = Snapshot {
+            status,
+            response,
+            saved_at: chrono::Utc::now().to_rfc3339(),
+            ..request.clone()
+        };
+        self.store.save(&snapshot)?;
+        Ok(snapshot)
+    }
+
+    /// Send the saved request again and print how the response differs from the snapshot.
+    pub async fn check(&self, name: &str) -> Result<Vec<Difference>, Box<dyn Error>> {
+        let snapshot = self.store.load(name)?;
+        let (status, response) = self.fetch(&snapshot).await?;
+        println!("🔍 {} {} → {} (snapshot from {})", snapshot.method, style(&snapshot.url).cyan(), style(status).yellow(), snapshot.saved_at);
+
+        let mut differences = Vec::new();
+        if status != snapshot.status {
+            differences.push(Difference::Changed { path: "status".to_string(), left: snapshot.status.into(), right: status.into() });
+        }
+        differences.extend(diff_json(&snapshot.response, &response, &snapshot.ignore));
+        for difference in &differences {
+            difference.print();
+        }
+        Ok(differences)
+    }
+
+    async fn fetch(&self, request: &Snapshot) -> Result<(u16, Value), Box<dyn Error>> {
+        let fetched = fetch(&self.client, &self.environment, &request.method, &request.url, &request.headers, request.body.as_deref()).await?;
+        let response = serde_json::from_str::<Value>(&fetched.body)
+            .map(|json| normalize(&json, &request.ignore))
+            .unwrap_or(Value::String(fetched.body));
+        Ok((fetched.status, response))
+    }
+}
@@ -1,7 +1,4 @@
-use anthropic::{
-    client::ClientBuilder,
-    types::{Message, ContentBlock, MessagesRequestBuilder, Role},
-};
+use crate::ai;
 use crate::config::Config;
 use crate::commands::call::CallCommand;
 
@@ -31,12 +28,7 @@ impl TestCommand {
     }
 
     async fn generate_test_plan(&self, description: &str, base_url: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
-        let api_key = self.config.anthropic_api_key.as_ref()
-            .ok_or("API key not configured. Use 'config api-key' to set it")?;
-
-        let ai_client = ClientBuilder::default()
-            .api_key(api_key.clone())
-            .build()?;
+        let ai_client = ai::client(&self.config, "test")?;
 
         let base_url_context = base_url
             .map(|url| format!("Base URL: {}", url))
@@ -82,21 +74,7 @@ Validation:
             description, base_url_context
         );
 
-        let response = ai_client.messages(MessagesRequestBuilder::default()
-            .messages(vec![Message {
-                role: Role::User,
-                content: vec![ContentBlock::Text { text: prompt }],
-            }])
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(2000_usize)
-            .build()?
-        ).await?;
-
-        if let Some(ContentBlock::Text { text }) = response.content.first() {
-            Ok(text.clone())
-        } else {
-            Err("Failed to generate test plan".into())
-        }
+        ai_client.ask(&prompt).await
     }
 
     async fn execute_test_plan(&self, test_plan: &str) -> Result<(), Box<dyn std::error::Error>> {
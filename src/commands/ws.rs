@@ -0,0 +1,285 @@
+//! WebSocket testing: send messages and watch frames arrive, either one-shot with `--send`
+//! or interactively from a `ws>` prompt.
+
+use base64::Engine as _;
+use crate::units;
+use console::style;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::error::Error;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Something that happened on the connection, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WsEvent {
+    Sent(String),
+    Text(String),
+    Binary(Vec<u8>),
+    /// Ping from the server; the pong is sent automatically.
+    Ping(Vec<u8>),
+    Closed { code: u16, reason: String },
+}
+
+#[derive(Debug)]
+pub struct WsOptions {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// Messages to send on connect; none means interactive mode.
+    pub send: Vec<String>,
+    /// How long to keep listening after the last message was sent.
+    pub timeout: Duration,
+}
+
+pub struct WsCommand;
+
+impl Default for WsCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WsCommand {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// `ws URL [-H 'K: V'] [--send MSG]... [--timeout SECS]`
+    pub async fn execute(&self, args: &[&str]) -> Result<Vec<WsEvent>, Box<dyn Error>> {
+        let options = Self::parse_args(args)?;
+        if options.send.is_empty() {
+            self.interactive(&options).await?;
+            Ok(Vec::new())
+        } else {
+            self.send_and_listen(&options).await
+        }
+    }
+
+    fn parse_args(args: &[&str]) -> Result<WsOptions, Box<dyn Error>> {
+        let mut options = WsOptions {
+            url: String::new(),
+            headers: Vec::new(),
+            send: Vec::new(),
+            timeout: Duration::from_secs(5),
+        };
+
+        let mut i = 1; // Skip "ws"
+        while i < args.len() {
+            match (args[i], args.get(i + 1)) {
+                ("--send" | "-s", Some(message)) => options.send.push(message.to_string()),
+                ("--timeout", Some(secs)) => {
+                    options.timeout = units::parse_positive_duration(secs).map_err(|e| format!("Invalid --timeout: {}", e))?;
+                }
+                ("-H" | "--header", Some(header)) => {
+                    let (key, value) = header.split_once(':').ok_or("Header must be in format 'Key: Value'")?;
+                    options.headers.push((key.trim().to_string(), value.trim().to_string()));
+                }
+                (flag @ ("--send" | "-s" | "--timeout" | "-H" | "--header"), None) => {
+                    return Err(format!("Value required after {}", flag).into());
+                }
+                (flag, _) if flag.starts_with('-') => return Err(format!("Unknown option: {}", flag).into()),
+                (url, _) if options.url.is_empty() => {
+                    options.url = url.to_string();
+                    i += 1;
+                    continue;
+                }
+                (extra, _) => return Err(format!("Unexpected argument: {}", extra).into()),
+            }
+            i += 2;
+        }
+
+        if options.url.is_empty() {
+            return Err("Usage: ws URL [--send MESSAGE]... [--timeout SECS] [-H 'Key: Value']".into());
+        }
+        if !options.url.contains("://") {
+            options.url = format!("ws://{}", options.url);
+        }
+        Ok(options)
+    }
+
+    async fn connect(options: &WsOptions) -> Result<Socket, Box<dyn Error>> {
+        let mut request = options.url.as_str().into_client_request()?;
+        for (key, value) in &options.headers {
+            request.headers_mut().insert(HeaderName::from_bytes(key.as_bytes())?, HeaderValue::from_str(value)?);
+        }
+
+        let (socket, response) = tokio_tungstenite::connect_async(request)
+            .await
+            .map_err(|e| format!("WebSocket connection to {} failed: {}", options.url, e))?;
+        println!("🔌 Connected to {} ({})", style(&options.url).cyan(), style(response.status()).dim());
+        Ok(socket)
+    }
+
+    /// Send every `--send` message, then print incoming frames until the timeout or a close.
+    pub async fn send_and_listen(&self, options: &WsOptions) -> Result<Vec<WsEvent>, Box<dyn Error>> {
+        let mut socket = Self::connect(options).await?;
+        let mut events = Vec::new();
+
+        for message in &options.send {
+            socket.send(Message::Text(message.clone())).await?;
+            let event = WsEvent::Sent(message.clone());
+            print_event(&event);
+            events.push(event);
+        }
+
+        let deadline = tokio::time::Instant::now() + options.timeout;
+        loop {
+            let frame = tokio::select! {
+                frame = socket.next() => frame,
+                _ = tokio::time::sleep_until(deadline) => {
+                    println!("⏱️  No more frames after {:.1}s, closing", options.timeout.as_secs_f64());
+                    let _ = socket.close(None).await;
+                    break;
+                }
+            };
+            match frame {
+                Some(Ok(message)) => {
+                    if let Some(event) = received(message) {
+                        print_event(&event);
+                        let closed = matches!(event, WsEvent::Closed { .. });
+                        events.push(event);
+                        if closed {
+                            break;
+                        }
+                    }
+                }
+                Some(Err(e)) => return Err(format!("WebSocket error: {}", e).into()),
+                None => break,
+            }
+        }
+        Ok(events)
+    }
+
+    /// Lines typed at the `ws>` prompt are sent as text frames; `/quit` closes the connection.
+    pub async fn interactive(&self, options: &WsOptions) -> Result<(), Box<dyn Error>> {
+        let mut socket = Self::connect(options).await?;
+        println!("💬 Type a message and press Enter to send. {} or Ctrl+D closes.", style("/quit").green());
+
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut stdout = tokio::io::stdout();
+        stdout.write_all(b"ws> ").await?;
+        stdout.flush().await?;
+
+        loop {
+            tokio::select! {
+                line = lines.next_line() => {
+                    match line?.as_deref().map(str::trim) {
+                        None | Some("/quit") | Some("/close") => {
+                            socket.close(Some(CloseFrame { code: CloseCode::Normal, reason: "".into() })).await?;
+                            println!("👋 Closed");
+                            break;
+                        }
+                        Some("") => {}
+                        Some(message) => {
+                            socket.send(Message::Text(message.to_string())).await?;
+                            print_event(&WsEvent::Sent(message.to_string()));
+                        }
+                    }
+                }
+                frame = socket.next() => {
+                    match frame {
+                        Some(Ok(message)) => {
+                            if let Some(event) = received(message) {
+                                print!("\r");
+                                print_event(&event);
+                                if matches!(event, WsEvent::Closed { .. }) {
+                                    // A read on stdin is still pending; let it finish so the
+                                    // line isn't swallowed from the nuts prompt.
+                                    println!("Press Enter to return to nuts");
+                                    let _ = lines.next_line().await;
+                                    break;
+                                }
+                            }
+                        }
+                        Some(Err(e)) => return Err(format!("WebSocket error: {}", e).into()),
+                        None => break,
+                    }
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    let _ = socket.close(None).await;
+                    println!("\n👋 Closed");
+                    break;
+                }
+            }
+            stdout.write_all(b"ws> ").await?;
+            stdout.flush().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Map an incoming frame to an event; pongs and raw frames aren't interesting.
+fn received(message: Message) -> Option<WsEvent> {
+    match message {
+        Message::Text(text) => Some(WsEvent::Text(text)),
+        Message::Binary(data) => Some(WsEvent::Binary(data)),
+        Message::Ping(data) => Some(WsEvent::Ping(data)),
+        Message::Close(frame) => Some(match frame {
+            Some(frame) => WsEvent::Closed { code: frame.code.into(), reason: frame.reason.to_string() },
+            // No close frame payload means "no status received"
+            None => WsEvent::Closed { code: 1005, reason: String::new() },
+        }),
+        Message::Pong(_) | Message::Frame(_) => None,
+    }
+}
+
+fn print_event(event: &WsEvent) {
+    match event {
+        WsEvent::Sent(text) => println!("{} {}", style("→").cyan().bold(), text),
+        WsEvent::Text(text) => match serde_json::from_str::<Value>(text) {
+            Ok(json) if json.is_object() || json.is_array() => {
+                println!("{} {}", style("←").green().bold(), style(serde_json::to_string_pretty(&json).unwrap_or_default()).green())
+            }
+            _ => println!("{} {}", style("←").green().bold(), style(text).green()),
+        },
+        WsEvent::Binary(data) => println!(
+            "{} {} {}",
+            style("←").green().bold(),
+            style(format!("[binary, {} bytes]", data.len())).yellow(),
+            base64::engine::general_purpose::STANDARD.encode(data)
+        ),
+        WsEvent::Ping(_) => println!("{} {}", style("←").dim(), style("ping (pong sent)").dim()),
+        WsEvent::Closed { code, reason } => {
+            let label = close_code_label(*code);
+            let reason = if reason.is_empty() { String::new() } else { format!(": {}", reason) };
+            let line = format!("⛔ Closed by server with code {} ({}){}", code, label, reason);
+            if matches!(code, 1000 | 1001 | 1005) {
+                println!("{}", style(line).yellow());
+            } else {
+                println!("{}", style(line).red());
+            }
+        }
+    }
+}
+
+/// Human name for a close code (RFC 6455 section 7.4.1, plus the application range).
+pub fn close_code_label(code: u16) -> &'static str {
+    match code {
+        1000 => "normal closure",
+        1001 => "going away",
+        1002 => "protocol error",
+        1003 => "unsupported data",
+        1005 => "no status received",
+        1006 => "abnormal closure",
+        1007 => "invalid payload",
+        1008 => "policy violation",
+        1009 => "message too big",
+        1010 => "missing extension",
+        1011 => "internal server error",
+        1012 => "service restart",
+        1013 => "try again later",
+        1015 => "TLS handshake failure",
+        3000..=3999 => "registered",
+        4000..=4999 => "application-defined",
+        _ => "unknown",
+    }
+}
@@ -3,113 +3,268 @@ use rustyline::highlight::Highlighter;
 use rustyline::hint::Hinter;
 use rustyline::validate::Validator;
 use rustyline::{Context, Helper, Result};
-use std::collections::HashMap;
+use crate::config::Config;
+pub use crate::aliases::{resolve_alias, COMMAND_ALIASES};
+use crate::aliases;
+use crate::shell::split_args;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Closest known command to a mistyped one, without needing AI.
+pub fn closest_command(input: &str) -> Option<&'static str> {
+    let word = input.split_whitespace().next()?.to_lowercase();
+    SHELL_COMMANDS.iter()
+        .map(|command| (command.name, edit_distance(&word, command.name)))
+        .filter(|&(cmd, distance)| distance <= (cmd.len() / 3).max(1) || cmd.starts_with(word.as_str()))
+        .min_by_key(|&(_, distance)| distance)
+        .map(|(cmd, _)| cmd)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push((previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// What the word after a command or subcommand completes to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Argument {
+    None,
+    /// An HTTP method.
+    Method,
+    /// A flow saved in the flows directory.
+    Flow,
+    /// An environment from the config.
+    Environment,
+    /// A macro saved with `alias add`.
+    Alias,
+}
+
+/// A command the shell dispatches, as the completer and the "did you mean" hint see it.
+pub struct ShellCommand {
+    pub name: &'static str,
+    /// Subcommands, with nested ones written as paths like `"env use"`.
+    pub subcommands: &'static [(&'static str, Argument)],
+    /// Completion for the first argument of a command without subcommands.
+    pub argument: Argument,
+    pub options: &'static [&'static str],
+}
+
+const fn command(name: &'static str) -> ShellCommand {
+    ShellCommand { name, subcommands: &[], argument: Argument::None, options: &[] }
+}
+
+/// Every top-level command `NutsShell::process_args` dispatches, sorted by name.
+pub const SHELL_COMMANDS: &[ShellCommand] = &[
+    ShellCommand { subcommands: &[("add", Argument::None), ("list", Argument::None), ("rm", Argument::Alias)], ..command("alias") },
+    ShellCommand { options: &["--dry-run", "--model", "--no-cache", "--yes"], ..command("ask") },
+    ShellCommand { subcommands: &[("clear", Argument::None), ("stats", Argument::None)], ..command("cache") },
+    ShellCommand {
+        argument: Argument::Method,
+        options: &[
+            "--analyze", "--auth", "--aws-profile", "--aws-sigv4", "--batch", "--batch-out", "--bearer", "--cacert", "--cert", "--compressed", "--concurrency", "--connect-timeout", "--continue-at", "--csv", "--data", "--data-binary", "--data-file", "--download", "--dump-headers", "--filter", "--form",
+            "--full", "--graphql", "--header", "--http1.1", "--http2", "--http2-prior-knowledge", "--include", "--insecure", "--jq", "--json", "--key", "--location", "--max-col-width", "--max-display", "--max-events", "--max-redirs", "--meta", "--no-color", "--no-cookies",
+            "--noproxy", "--out-format", "--output", "--proxy", "--query", "--query-file", "--quiet", "--raw", "--resolve", "--retry", "--retry-on-status",
+            "--sign", "--sign-timestamp", "--stream", "--table", "--timeout", "--trace-log", "--user", "--user-agent", "--validate-against", "--variables", "--verbose",
+        ],
+        ..command("call")
+    },
+    ShellCommand { subcommands: &[("start", Argument::None), ("stop", Argument::None)], ..command("capture") },
+    ShellCommand {
+        subcommands: &[
+            ("ai base-url", Argument::None), ("ai budget", Argument::None), ("ai key", Argument::None), ("ai max-tokens", Argument::None),
+            ("ai model", Argument::None), ("ai price", Argument::None), ("ai provider", Argument::None),
+            ("api-key", Argument::None), ("auth oauth2", Argument::None), ("auth clear", Argument::None),
+            ("base-url", Argument::None), ("env add", Argument::Environment), ("env list", Argument::None),
+            ("env use", Argument::Environment), ("persist-cookies", Argument::None), ("redact add", Argument::None), ("redact list", Argument::None), ("redact remove", Argument::None), ("set ask-max-steps", Argument::None), ("set auto-execute", Argument::None), ("set color", Argument::None), ("set-header", Argument::None),
+            ("show", Argument::None), ("tls cacert", Argument::None), ("tls cert", Argument::None), ("tls clear", Argument::None),
+            ("tls key", Argument::None), ("unset-header", Argument::None),
+        ],
+        ..command("config")
+    },
+    ShellCommand { subcommands: &[("api-key", Argument::None), ("show", Argument::None)], ..command("configure") },
+    ShellCommand { subcommands: &[("clear", Argument::None), ("list", Argument::None)], ..command("cookies") },
+    command("curl"),
+    ShellCommand { argument: Argument::Method, options: &["--bearer", "--data", "--header", "--headers-too", "--ignore-path"], ..command("diff") },
+    ShellCommand { options: &["--delay", "--depth", "--max-requests"], ..command("discover") },
+    command("exit"),
+    ShellCommand { options: &["--context", "--last", "--status"], ..command("explain") },
+    ShellCommand { options: &["--apply", "--flow", "--yes"], ..command("fix") },
+    ShellCommand {
+        subcommands: &[("check", Argument::Flow), ("contract", Argument::Flow), ("docs", Argument::Flow), ("export", Argument::Flow), ("from-url", Argument::None), ("import postman", Argument::None), ("mock", Argument::Flow), ("run", Argument::Flow), ("story", Argument::Flow), ("validate", Argument::Flow), ("vars list", Argument::Flow), ("vars set", Argument::Flow)],
+        options: &["--all", "--base-url", "--dry-run", "--error-rate", "--error-status", "--fail-fast", "--format", "--jitter", "--latency", "--no-admin", "--out", "--record-unmatched", "--server", "--stateful", "--yes"],
+        ..command("flow")
+    },
+    ShellCommand { options: &["--concurrency", "--count", "--delay", "--into-flow", "--local", "--model", "--no-cache", "--post", "--save", "--seed"], ..command("generate") },
+    command("help"),
+    ShellCommand { subcommands: &[("search", Argument::None)], ..command("history") },
+    ShellCommand {
+        subcommands: &[("report", Argument::None)],
+        options: &["--alert-webhook", "--count", "--duration", "--flow", "--interval", "--smart"],
+        ..command("monitor")
+    },
+    ShellCommand {
+        argument: Argument::Method,
+        options: &[
+            "--auth", "--bearer", "--cacert", "--cert", "--connect-timeout", "--duration", "--fail-avg", "--fail-error-rate", "--fail-p95", "--header", "--http1.1",
+            "--http2", "--http2-prior-knowledge", "--key", "--max-in-flight", "--no-color", "--no-keepalive", "--quiet", "--ramp", "--report", "--resolve", "--rps", "--stages", "--timeout", "--trim-outliers", "--users", "--warmup",
+        ],
+        ..command("perf")
+    },
+    ShellCommand { options: &["--json", "--probe-duration", "--probe-users", "--trend"], ..command("predict") },
+    ShellCommand { options: &["--name"], ..command("proxy") },
+    command("quit"),
+    ShellCommand {
+        subcommands: &[("list", Argument::None), ("replay", Argument::None), ("start", Argument::None), ("stop", Argument::None)],
+        ..command("record")
+    },
+    command("replay"),
+    ShellCommand { argument: Argument::Flow, ..command("save") },
+    ShellCommand { options: &["--active", "--auth", "--cacert", "--cert", "--deep", "--diff", "--json", "--key", "--no-color", "--quiet", "--save"], ..command("security") },
+    ShellCommand {
+        subcommands: &[("check", Argument::None), ("list", Argument::None), ("save", Argument::None), ("update", Argument::None)],
+        options: &["--bearer", "--data", "--header", "--ignore-path"],
+        ..command("snapshot")
+    },
+    ShellCommand {
+        subcommands: &[("curl", Argument::None), ("javascript-fetch", Argument::None), ("python-requests", Argument::None), ("rust-reqwest", Argument::None)],
+        options: &["--from-history", "--out"],
+        ..command("snippet")
+    },
+    ShellCommand {
+        subcommands: &[("list", Argument::None), ("run", Argument::None), ("save", Argument::None)],
+        ..command("template")
+    },
+    command("test"),
+    ShellCommand { options: &["--days"], ..command("usage") },
+    command("verify-signature"),
+    ShellCommand { options: &["--header", "--send", "--timeout"], ..command("ws") },
+];
+
+fn find_command(name: &str) -> Option<&'static ShellCommand> {
+    let name = resolve_alias(name);
+    SHELL_COMMANDS.iter().find(|command| command.name == name)
+}
+
+/// The command a macro runs, for completing its options.
+fn macro_command(name: &str, macros: &BTreeMap<String, String>) -> Option<&'static ShellCommand> {
+    let mut word = name.to_string();
+    // Bounded, in case the config was edited into a loop
+    for _ in 0..=macros.len() {
+        match macros.get(&word) {
+            Some(body) => word = split_args(body).into_iter().next()?,
+            None => return find_command(&word),
+        }
+    }
+    None
+}
+
+const HTTP_METHODS: &[&str] = &["DELETE", "GET", "HEAD", "OPTIONS", "PATCH", "POST", "PURGE", "PUT", "TRACE"];
 
 #[derive(Clone, Default)]
 pub struct NutsCompleter {
-    #[allow(dead_code)]
-    commands: HashMap<String, String>,
-    aliases: HashMap<String, String>,
+    flows_dir: PathBuf,
+    environments: Vec<String>,
+    macros: BTreeMap<String, String>,
 }
 
 impl NutsCompleter {
-    pub fn new() -> Self {
-        let mut commands = HashMap::new();
-        
-        // Core API Testing
-        commands.insert("call".to_string(), "Examples:\n  call GET https://api.example.com/users\n  call POST https://api.example.com/users '{\"name\":\"test\"}'".to_string());
-        commands.insert("perf".to_string(), "Examples:\n  perf GET https://api.example.com/users --users 100 --duration 30s".to_string());
-        commands.insert("security".to_string(), "Security analysis: security <URL> [OPTIONS]".to_string());
-        
-        // Flow Management
-        commands.insert("flow new".to_string(), "Create new flow: flow new <name>".to_string());
-        commands.insert("flow add".to_string(), "Add endpoint: flow add <name> <METHOD> <path>".to_string());
-        commands.insert("flow run".to_string(), "Run endpoint: flow run <name> <endpoint>".to_string());
-        commands.insert("flow docs".to_string(), "Generate docs: flow docs <name> [format]".to_string());
-        commands.insert("flow mock".to_string(), "Start mock server: flow mock <name> [port]".to_string());
-        commands.insert("flow list".to_string(), "List all flows".to_string());
-        commands.insert("flow configure_mock_data".to_string(), "Configure mock data: flow configure_mock_data <name> <endpoint>".to_string());
-        commands.insert("flow story".to_string(), "Start AI-guided API workflow: flow story <name>".to_string());
-        commands.insert("flow s".to_string(), "Quick story mode alias: flow s <name>".to_string());
-        commands.insert("save".to_string(), "Save last request: save <flow> <name>".to_string());
-        
-        // Configuration
-        commands.insert("config api-key".to_string(), "Configure API key".to_string());
-        commands.insert("config show".to_string(), "Show current configuration".to_string());
-        commands.insert("help".to_string(), "Show this help message".to_string());
-        commands.insert("exit".to_string(), "Exit NUTS".to_string());
-
-        // Add aliases
-        let mut aliases = HashMap::new();
-        aliases.insert("c".to_string(), "call".to_string());
-        aliases.insert("p".to_string(), "perf".to_string());
-        aliases.insert("s".to_string(), "flow story".to_string());
-        aliases.insert("h".to_string(), "help".to_string());
-        aliases.insert("q".to_string(), "quit".to_string());
-
-        Self { commands, aliases }
+    /// Completes flow names from the `*.yaml` files in `flows_dir` and environment names from `config`.
+    pub fn new(flows_dir: PathBuf, config: &Config) -> Self {
+        let mut completer = Self { flows_dir, environments: Vec::new(), macros: BTreeMap::new() };
+        completer.update_config(config);
+        completer
     }
 
-    fn get_command_completions(&self, line: &str) -> Vec<String> {
-        let mut completions = Vec::new();
-        
-        // Check aliases first
-        if let Some(expanded) = self.aliases.get(line) {
-            completions.push(expanded.clone());
-        }
-
-        // Base commands
-        let base_commands = vec![
-            "call", "perf", "mock", "security", "flow", "configure", "help", "exit"
-        ];
+    /// Pick up environments and aliases added or removed since the completer was made.
+    pub fn update_config(&mut self, config: &Config) {
+        self.environments = config.environments.keys().cloned().collect();
+        self.environments.sort();
+        self.macros = config.aliases.clone();
+    }
 
-        // HTTP methods
-        let http_methods = vec!["GET", "POST", "PUT", "DELETE", "PATCH"];
+    fn flow_names(&self) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.flows_dir) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "yaml"))
+            .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(str::to_string))
+            .collect();
+        names.sort();
+        names
+    }
 
-        // Flow subcommands
-        let collection_commands = vec![
-            "flow new", "flow add", "flow run",
-            "flow mock", "flow perf", "flow docs",
-            "flow list"
-        ];
+    /// Replacements for the last (possibly empty) word of `line`.
+    pub fn candidates(&self, line: &str) -> Vec<String> {
+        let mut words: Vec<&str> = line.split_whitespace().collect();
+        let current = if line.is_empty() || line.ends_with(char::is_whitespace) { "" } else { words.pop().unwrap_or("") };
+        let matching = |options: &[&str]| -> Vec<String> {
+            options.iter().filter(|option| option.starts_with(current)).map(|option| option.to_string()).collect()
+        };
 
-        // Options
-        let options = vec!["--analyze", "--users", "--duration", "--deep"];
+        let Some((&first, arguments)) = words.split_first() else {
+            let mut names: Vec<&str> = SHELL_COMMANDS.iter().map(|command| command.name).collect();
+            names.extend(aliases::names(&self.macros));
+            names.sort_unstable();
+            names.dedup();
+            return matching(&names);
+        };
+        if self.macros.contains_key(first) {
+            // Only options: a macro's own arguments are already part of it
+            return match macro_command(first, &self.macros) {
+                Some(command) if current.starts_with('-') => matching(command.options),
+                _ => Vec::new(),
+            };
+        }
+        let Some(command) = find_command(first) else {
+            return Vec::new();
+        };
+        if current.starts_with('-') {
+            return matching(command.options);
+        }
 
-        // Add base commands
-        completions.extend(base_commands.iter().map(|&cmd| {
-            if cmd.starts_with(line) {
-                Some(cmd.to_string())
-            } else {
-                None
+        let argument = if command.subcommands.is_empty() {
+            if !arguments.is_empty() {
+                return Vec::new();
             }
-        }).flatten());
-
-        // Add flow commands
-        completions.extend(collection_commands.iter().map(|&cmd| {
-            if cmd.starts_with(line) {
-                Some(cmd.to_string())
+            command.argument
+        } else {
+            let typed = arguments.join(" ");
+            if let Some((_, argument)) = command.subcommands.iter().find(|(path, _)| *path == typed) {
+                *argument
             } else {
-                None
+                // The next word of every subcommand path that starts with what's been typed
+                let mut next: Vec<&str> = command.subcommands.iter()
+                    .filter_map(|(path, _)| {
+                        let rest = if typed.is_empty() { Some(*path) } else { path.strip_prefix(typed.as_str())?.strip_prefix(' ') };
+                        rest?.split(' ').next()
+                    })
+                    .collect();
+                next.dedup();
+                return matching(&next);
             }
-        }).flatten());
-
-        // Add HTTP methods for relevant commands
-        if line.starts_with("call ") || line.starts_with("perf ") {
-            let method_part = &line[line.find(' ').unwrap_or(0) + 1..];
-            completions.extend(http_methods.iter()
-                .filter(|&m| m.starts_with(method_part))
-                .map(|m| format!("{} {}", line.split_whitespace().next().unwrap_or(""), m)));
-        }
+        };
 
-        // Add options where relevant
-        if line.contains("perf") || line.contains("security") {
-            completions.extend(options.iter()
-                .filter(|&opt| opt.starts_with(line.split_whitespace().last().unwrap_or("")))
-                .map(|&s| s.to_string()));
+        match argument {
+            Argument::None => Vec::new(),
+            Argument::Method => matching(HTTP_METHODS),
+            Argument::Flow => {
+                let names = self.flow_names();
+                matching(&names.iter().map(String::as_str).collect::<Vec<_>>())
+            }
+            Argument::Environment => matching(&self.environments.iter().map(String::as_str).collect::<Vec<_>>()),
+            Argument::Alias => matching(&self.macros.keys().map(String::as_str).collect::<Vec<_>>()),
         }
-
-        completions
     }
 }
 
@@ -118,7 +273,7 @@ impl Completer for NutsCompleter {
 
     fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Result<(usize, Vec<Pair>)> {
         let line_up_to_pos = &line[..pos];
-        let completions = self.get_command_completions(line_up_to_pos);
+        let completions = self.candidates(line_up_to_pos);
 
         let start_pos = line_up_to_pos.rfind(' ').map_or(0, |i| i + 1);
 
@@ -0,0 +1,77 @@
+//! Undoing the `Content-Encoding` of a response: gzip, deflate and brotli.
+//!
+//! reqwest can decompress on its own, but it then drops `Content-Encoding` and
+//! `Content-Length`, and with them any way to tell how many bytes came over the wire. Bodies
+//! are decoded here instead, so `call -v` can show both sizes and `-i` the headers as sent.
+
+use reqwest::header::{HeaderMap, CONTENT_ENCODING};
+use std::io::{self, Write};
+
+/// What `call --compressed` asks for.
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, br";
+
+/// A streaming decoder: compressed chunks go in, the decoded bytes come out.
+pub enum Decoder {
+    Gzip(flate2::write::GzDecoder<Vec<u8>>),
+    Deflate(flate2::write::ZlibDecoder<Vec<u8>>),
+    Brotli(Box<brotli_decompressor::DecompressorWriter<Vec<u8>>>),
+}
+
+impl Decoder {
+    /// The decoder for `headers`' `Content-Encoding`, or `None` for an identity or unknown one.
+    pub fn for_headers(headers: &HeaderMap) -> Option<Self> {
+        let encoding = headers.get(CONTENT_ENCODING)?.to_str().ok()?.trim().to_ascii_lowercase();
+        match encoding.as_str() {
+            "gzip" | "x-gzip" => Some(Self::Gzip(flate2::write::GzDecoder::new(Vec::new()))),
+            "deflate" => Some(Self::Deflate(flate2::write::ZlibDecoder::new(Vec::new()))),
+            "br" => Some(Self::Brotli(Box::new(brotli_decompressor::DecompressorWriter::new(Vec::new(), 4096)))),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gzip(_) => "gzip",
+            Self::Deflate(_) => "deflate",
+            Self::Brotli(_) => "br",
+        }
+    }
+
+    /// Feed one compressed chunk; returns what could be decoded so far.
+    pub fn push(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        let out = match self {
+            Self::Gzip(decoder) => {
+                decoder.write_all(chunk)?;
+                decoder.get_mut()
+            }
+            Self::Deflate(decoder) => {
+                decoder.write_all(chunk)?;
+                decoder.get_mut()
+            }
+            Self::Brotli(decoder) => {
+                decoder.write_all(chunk)?;
+                decoder.get_mut()
+            }
+        };
+        Ok(std::mem::take(out))
+    }
+
+    /// End of the body: the last decoded bytes, or an error for a truncated stream.
+    pub fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Gzip(decoder) => decoder.finish(),
+            Self::Deflate(decoder) => decoder.finish(),
+            Self::Brotli(mut decoder) => {
+                decoder.close()?;
+                Ok(std::mem::take(decoder.get_mut()))
+            }
+        }
+    }
+
+    /// Decode a whole body at once.
+    pub fn decode(mut self, body: &[u8]) -> io::Result<Vec<u8>> {
+        let mut decoded = self.push(body)?;
+        decoded.extend(self.finish()?);
+        Ok(decoded)
+    }
+}
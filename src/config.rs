@@ -1,10 +1,125 @@
 use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
+use crate::hooks::HookConfig;
+use crate::auth::OAuth2Config;
+use crate::ai::{default_price, AiProvider, DEFAULT_MAX_TOKENS};
+use crate::ai_cache::DEFAULT_CACHE_TTL_DAYS;
+use crate::commands::ask::DEFAULT_MAX_STEPS;
+use crate::usage::ModelPrice;
+use crate::tls::TlsOptions;
 
 #[derive(Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     pub api_key: Option<String>,
     pub anthropic_api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "HookConfig::is_empty")]
+    pub hooks: HookConfig,
+    /// Named variable sets (`dev`, `staging`, ...) substituted into `{{name}}` placeholders.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub environments: HashMap<String, HashMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_env: Option<String>,
+    /// Headers sent with every `call`; explicit `-H` flags win.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub default_headers: HashMap<String, String>,
+    /// Prefix for `call` URLs that start with `/`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// Keep the shell's cookie jar in `~/.nuts/cookies.json` between sessions.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub persist_cookies: bool,
+    /// Client credentials used by `--auth oauth2`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oauth2: Option<OAuth2Config>,
+    /// Send the requests `ask` and story mode propose without asking, except `DELETE` and `PUT`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_execute: bool,
+    /// Most requests a multi-step `ask` plan runs; 5 when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ask_max_steps: Option<usize>,
+    /// ANSI colors in output; on when unset. `NO_COLOR` turns them off regardless.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<bool>,
+    /// Backend for AI features; Anthropic unless set with `config ai provider`.
+    #[serde(default, skip_serializing_if = "AiProvider::is_default")]
+    pub ai_provider: AiProvider,
+    /// Model to ask; the provider's default when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_model: Option<String>,
+    /// Longest AI answer, in tokens; 2000 when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_max_tokens: Option<usize>,
+    /// Model prices (USD per million tokens) used for `usage`, over the built-in ones.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub ai_prices: BTreeMap<String, ModelPrice>,
+    /// Estimated AI spend per day, in USD, past which NUTS warns.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_daily_budget: Option<f64>,
+    /// Over budget, skip optional AI calls such as perf insights.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ai_enforce_budget: bool,
+    /// Days an AI answer is reused for the same request; 7 when unset, 0 turns caching off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_cache_ttl_days: Option<u64>,
+    /// Root (or chat completions URL) of an OpenAI-compatible API.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_base_url: Option<String>,
+    /// Key for the OpenAI-compatible provider; Anthropic keeps `anthropic_api_key`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_api_key: Option<String>,
+    /// Client certificate and CAs used when a command doesn't pass `--cert`/`--cacert`.
+    #[serde(default, skip_serializing_if = "TlsOptions::is_empty")]
+    pub tls: TlsOptions,
+    /// Macros saved with `alias add`: name to the command line it stands for.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+    /// Headers masked in `-v` output and `--trace-log` files, besides Authorization, Cookie
+    /// and Set-Cookie; set with `config redact add`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact_headers: Vec<String>,
+}
+
+/// Variables of the active environment, as handed to `call` and `perf`.
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    pub name: Option<String>,
+    pub variables: HashMap<String, String>,
+}
+
+impl Environment {
+    /// Replace every `{{name}}` in `text`, failing on placeholders the environment doesn't define.
+    pub fn substitute(&self, text: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else { break };
+            let name = rest[start + 2..start + 2 + len].trim();
+            result.push_str(&rest[..start]);
+            match self.variables.get(name) {
+                Some(value) => result.push_str(value),
+                None => return Err(self.unknown_variable(name).into()),
+            }
+            rest = &rest[start + 2 + len + 2..];
+        }
+
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    fn unknown_variable(&self, name: &str) -> String {
+        let Some(env) = &self.name else {
+            return format!("Unknown variable '{{{{{}}}}}': no environment is active. Use 'config env use <name>' to pick one", name);
+        };
+        let mut available: Vec<&str> = self.variables.keys().map(String::as_str).collect();
+        available.sort_unstable();
+        if available.is_empty() {
+            format!("Unknown variable '{{{{{}}}}}': environment '{}' has no variables", name, env)
+        } else {
+            format!("Unknown variable '{{{{{}}}}}' in environment '{}'. Available: {}", name, env, available.join(", "))
+        }
+    }
 }
 
 impl Config {
@@ -40,6 +155,95 @@ impl Config {
         Self::load_from_file()
     }
 
+    /// The key for the configured AI provider, ignoring blank values.
+    pub fn ai_key(&self) -> Option<&str> {
+        let key = match self.ai_provider {
+            AiProvider::Anthropic => &self.anthropic_api_key,
+            AiProvider::OpenAi => &self.ai_api_key,
+        };
+        key.as_deref()
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+    }
+
+    /// The model AI features ask: `ai_model`, or the provider's default.
+    pub fn ai_model(&self) -> &str {
+        self.ai_model.as_deref().filter(|model| !model.trim().is_empty()).unwrap_or(self.ai_provider.default_model())
+    }
+
+    pub fn ai_max_tokens(&self) -> usize {
+        self.ai_max_tokens.filter(|&tokens| tokens > 0).unwrap_or(DEFAULT_MAX_TOKENS)
+    }
+
+    pub fn ask_max_steps(&self) -> usize {
+        self.ask_max_steps.filter(|&steps| steps > 0).unwrap_or(DEFAULT_MAX_STEPS)
+    }
+
+    pub fn ai_cache_ttl_days(&self) -> u64 {
+        self.ai_cache_ttl_days.unwrap_or(DEFAULT_CACHE_TTL_DAYS)
+    }
+
+    /// What `model` costs: the configured price, or the built-in one.
+    pub fn model_price(&self, model: &str) -> Option<ModelPrice> {
+        self.ai_prices.get(model).copied().or_else(|| default_price(model))
+    }
+
+    /// Offline mode: no usable AI key, so AI sections are skipped.
+    pub fn is_offline(&self) -> bool {
+        self.ai_key().is_none()
+    }
+
+    /// The AI provider's key, or an error explaining that the feature needs one.
+    pub fn require_ai_key(&self) -> Result<&str, Box<dyn std::error::Error>> {
+        self.ai_key().ok_or_else(|| match self.ai_provider {
+            AiProvider::Anthropic => "This feature needs AI but no Anthropic API key is configured (offline mode). Use 'config api-key' to set it".into(),
+            AiProvider::OpenAi => "This feature needs AI but no key is configured for the OpenAI-compatible provider (offline mode). Use 'config ai key <key>' to set it".into(),
+        })
+    }
+
+    /// The active environment's variables; empty when none is selected.
+    pub fn active_environment(&self) -> Environment {
+        let name = self.active_env.clone().filter(|name| self.environments.contains_key(name));
+        let variables = name.as_ref()
+            .and_then(|name| self.environments.get(name))
+            .cloned()
+            .unwrap_or_default();
+        Environment { name, variables }
+    }
+
+    /// Set `key` in environment `env`, creating the environment if needed.
+    pub fn set_env_var(&mut self, env: &str, key: &str, value: &str) {
+        self.environments
+            .entry(env.to_string())
+            .or_default()
+            .insert(key.to_string(), value.to_string());
+    }
+
+    /// Make `env` the active environment.
+    pub fn use_env(&mut self, env: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.environments.contains_key(env) {
+            let mut known: Vec<&str> = self.environments.keys().map(String::as_str).collect();
+            known.sort_unstable();
+            let known = if known.is_empty() { "none yet".to_string() } else { known.join(", ") };
+            return Err(format!("Unknown environment '{}'. Defined environments: {}", env, known).into());
+        }
+        self.active_env = Some(env.to_string());
+        Ok(())
+    }
+
+    /// Send `name: value` with every call, replacing a default with the same name in any case.
+    pub fn set_default_header(&mut self, name: &str, value: &str) {
+        self.unset_default_header(name);
+        self.default_headers.insert(name.to_string(), value.to_string());
+    }
+
+    /// Stop sending the default header `name`; false if it wasn't set.
+    pub fn unset_default_header(&mut self, name: &str) -> bool {
+        let before = self.default_headers.len();
+        self.default_headers.retain(|key, _| !key.eq_ignore_ascii_case(name));
+        self.default_headers.len() != before
+    }
+
     #[allow(dead_code)]
     pub fn merge(mut self, other: Config) -> Self {
         if self.anthropic_api_key.is_none() {
@@ -48,6 +252,64 @@ impl Config {
         if self.api_key.is_none() {
             self.api_key = other.api_key;
         }
+        if self.hooks.is_empty() {
+            self.hooks = other.hooks;
+        }
+        for (name, variables) in other.environments {
+            self.environments.entry(name).or_insert(variables);
+        }
+        if self.active_env.is_none() {
+            self.active_env = other.active_env;
+        }
+        for (name, value) in other.default_headers {
+            if !self.default_headers.keys().any(|key| key.eq_ignore_ascii_case(&name)) {
+                self.default_headers.insert(name, value);
+            }
+        }
+        if self.base_url.is_none() {
+            self.base_url = other.base_url;
+        }
+        self.persist_cookies |= other.persist_cookies;
+        if self.oauth2.is_none() {
+            self.oauth2 = other.oauth2;
+        }
+        self.auto_execute |= other.auto_execute;
+        if self.color.is_none() {
+            self.color = other.color;
+        }
+        if self.ai_provider.is_default() {
+            self.ai_provider = other.ai_provider;
+        }
+        if self.ai_model.is_none() {
+            self.ai_model = other.ai_model;
+        }
+        if self.ai_max_tokens.is_none() {
+            self.ai_max_tokens = other.ai_max_tokens;
+        }
+        if self.ai_base_url.is_none() {
+            self.ai_base_url = other.ai_base_url;
+        }
+        if self.ai_api_key.is_none() {
+            self.ai_api_key = other.ai_api_key;
+        }
+        for (model, price) in other.ai_prices {
+            self.ai_prices.entry(model).or_insert(price);
+        }
+        if self.ai_daily_budget.is_none() {
+            self.ai_daily_budget = other.ai_daily_budget;
+        }
+        self.ai_enforce_budget |= other.ai_enforce_budget;
+        if self.ai_cache_ttl_days.is_none() {
+            self.ai_cache_ttl_days = other.ai_cache_ttl_days;
+        }
         self
     }
 }
+
+/// Show enough of a secret to recognise it: the first few characters, the rest starred.
+pub fn mask_value(value: &str) -> String {
+    let chars = value.chars().count();
+    let visible = (chars / 4).min(6);
+    let shown: String = value.chars().take(visible).collect();
+    format!("{}{}", shown, "*".repeat((chars - visible).clamp(4, 12)))
+}
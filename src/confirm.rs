@@ -0,0 +1,181 @@
+//! Safety layer for requests the AI proposes in `ask` and story mode.
+//!
+//! Every proposed request is either sent, shown and confirmed (`y/N/edit`), or only printed
+//! (`--dry-run`). `DELETE` and `PUT` are always confirmed unless `--yes` is given; other
+//! methods go out unconfirmed only with `--yes` or `config set auto-execute true`.
+
+use crate::commands::call::{CallCommand, CallOptions, CallResponse};
+use crate::config::mask_value;
+use crate::flows::export::shell_quote;
+use crate::shell::split_args;
+use console::style;
+use std::error::Error;
+
+/// Methods confirmed even with `auto-execute` on.
+const DESTRUCTIVE_METHODS: &[&str] = &["DELETE", "PUT"];
+
+/// What to do with one proposed request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepDecision {
+    Send,
+    Confirm,
+    Print,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExecutionPolicy {
+    /// `--dry-run`: print every request, send none.
+    pub dry_run: bool,
+    /// `--yes`: send every request without asking.
+    pub yes: bool,
+    /// `config set auto-execute true`: send non-destructive requests without asking.
+    pub auto_execute: bool,
+}
+
+impl ExecutionPolicy {
+    /// Take `--dry-run` and `--yes`/`-y` out of `args`.
+    pub fn take_flags(args: &mut Vec<String>, auto_execute: bool) -> Self {
+        let mut policy = Self { auto_execute, ..Self::default() };
+        args.retain(|arg| match arg.as_str() {
+            "--dry-run" => {
+                policy.dry_run = true;
+                false
+            }
+            "--yes" | "-y" => {
+                policy.yes = true;
+                false
+            }
+            _ => true,
+        });
+        policy
+    }
+
+    pub fn decide(&self, method: &str) -> StepDecision {
+        if self.dry_run {
+            StepDecision::Print
+        } else if self.yes {
+            StepDecision::Send
+        } else if is_destructive(method) || !self.auto_execute {
+            StepDecision::Confirm
+        } else {
+            StepDecision::Send
+        }
+    }
+}
+
+pub fn is_destructive(method: &str) -> bool {
+    DESTRUCTIVE_METHODS.iter().any(|m| m.eq_ignore_ascii_case(method))
+}
+
+/// Where confirmation answers come from: the shell's editor, or a script in tests.
+pub trait StepPrompt {
+    /// Read a line, starting from `initial`. `None` when input ends.
+    fn read(&mut self, prompt: &str, initial: &str) -> Option<String>;
+}
+
+impl<H: rustyline::Helper, I: rustyline::history::History> StepPrompt for rustyline::Editor<H, I> {
+    fn read(&mut self, prompt: &str, initial: &str) -> Option<String> {
+        self.readline_with_initial(prompt, (initial, "")).ok()
+    }
+}
+
+/// The request as it would go out: method, URL, headers (credentials masked) and body.
+pub fn describe(options: &CallOptions) -> String {
+    let mut lines = vec![format!("{} {}", options.method.to_uppercase(), options.url)];
+    let mut headers: Vec<(&String, &String)> = options.headers.iter().collect();
+    headers.sort_by_key(|(name, _)| name.to_ascii_lowercase());
+    for (name, value) in headers {
+        lines.push(format!("{}: {}", name, value));
+    }
+    if let Some((user, password)) = &options.auth {
+        lines.push(format!("Authorization: Basic {}:{}", user, mask_value(password)));
+    }
+    if let Some(token) = &options.bearer_token {
+        lines.push(format!("Authorization: Bearer {}", mask_value(token)));
+    }
+    let mut fields: Vec<_> = options.form_data.iter().collect();
+    fields.sort();
+    for (name, value) in fields {
+        lines.push(format!("-F {}={}", name, value));
+    }
+    if let Some(body) = &options.body {
+        lines.push(String::new());
+        lines.push(body.clone());
+    } else if let Some(body) = &options.binary_body {
+        lines.push(String::new());
+        lines.push(format!("<{} bytes of binary data>", body.len()));
+    } else if let Some(path) = &options.body_file {
+        lines.push(String::new());
+        lines.push(format!("<contents of {}>", path));
+    }
+    lines.join("\n")
+}
+
+/// `call` arguments as one editable line. JSON arguments are compacted so a body fits on it.
+pub fn command_line(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            let arg = match serde_json::from_str::<serde_json::Value>(arg) {
+                Ok(json) if json.is_object() || json.is_array() => json.to_string(),
+                _ => arg.clone(),
+            };
+            let plain = !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,?&#~".contains(c));
+            if plain { arg } else { shell_quote(&arg) }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parse `args` and send, confirm or print the request as `policy` says. Returns the
+/// response, or `None` when the request was skipped or only printed.
+pub async fn run_step(
+    call: &CallCommand,
+    policy: &ExecutionPolicy,
+    mut args: Vec<String>,
+    prompt: &mut dyn StepPrompt,
+) -> Result<Option<CallResponse>, Box<dyn Error>> {
+    loop {
+        let parts: Vec<&str> = args.iter().map(String::as_str).collect();
+        let options = call.parse_advanced_args(&parts)?;
+        let mut shown = options.clone();
+        call.resolve(&mut shown)?;
+
+        match policy.decide(&shown.method) {
+            StepDecision::Send => return call.execute_with_options(options).await.map(Some),
+            StepDecision::Print => {
+                println!("{}", style("🔎 Dry run, not sent:").yellow());
+                println!("{}", indent(&describe(&shown)));
+                return Ok(None);
+            }
+            StepDecision::Confirm => {
+                println!("{}", indent(&describe(&shown)));
+                let warning = if is_destructive(&shown.method) { " ⚠️" } else { "" };
+                let answer = prompt.read(&format!("Send this request?{} (y/N/edit): ", warning), "").unwrap_or_default();
+                match answer.trim().to_ascii_lowercase().as_str() {
+                    "y" | "yes" => return call.execute_with_options(options).await.map(Some),
+                    "e" | "edit" => {
+                        let Some(line) = prompt.read("✏️  ", &command_line(&args)) else { return Ok(None) };
+                        let edited = split_args(&line);
+                        if edited.first().map(String::as_str) != Some("call") || edited.len() < 2 {
+                            println!("❌ The edited request must start with 'call'");
+                            continue;
+                        }
+                        if let Err(e) = call.parse_advanced_args(&edited.iter().map(String::as_str).collect::<Vec<_>>()) {
+                            println!("❌ {}", e);
+                            continue;
+                        }
+                        args = edited;
+                    }
+                    _ => {
+                        println!("⏭️  Skipped");
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn indent(text: &str) -> String {
+    text.lines().map(|line| format!("   {}", line)).collect::<Vec<_>>().join("\n")
+}
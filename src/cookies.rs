@@ -0,0 +1,128 @@
+//! Session cookie jar shared by every `call` in a shell, so `Set-Cookie` from a login is
+//! sent on the requests that follow it.
+//!
+//! Domain, path, expiry and `Secure` matching are delegated to the `cookie_store` crate,
+//! the same store reqwest's own `Jar` wraps. With `persist_cookies` enabled in the config
+//! the jar is kept in `~/.nuts/cookies.json` between sessions.
+
+use reqwest::header::HeaderValue;
+use std::fs::OpenOptions;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use url::Url;
+
+/// A cookie as shown by `cookies list`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredCookie {
+    pub domain: String,
+    pub path: String,
+    pub name: String,
+    pub value: String,
+    /// RFC 3339 expiry; `None` for session cookies.
+    pub expires: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    store: RwLock<cookie_store::CookieStore>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `~/.nuts/cookies.json`.
+    pub fn default_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(dirs::home_dir()
+            .ok_or("Could not find home directory")?
+            .join(".nuts")
+            .join("cookies.json"))
+    }
+
+    /// Jar saved at `path`, or an empty one if nothing was saved yet. Expired cookies are dropped.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let store = cookie_store::CookieStore::load_json(BufReader::new(file))
+            .map_err(|e| format!("Could not read cookies from {}: {}", path.display(), e))?;
+        Ok(Self { store: RwLock::new(store) })
+    }
+
+    /// Write every cookie, session cookies included, so a session can be picked up later.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut content = Vec::new();
+        self.store.read().unwrap()
+            .save_incl_expired_and_nonpersistent_json(&mut content)
+            .map_err(|e| format!("Could not serialize cookies: {}", e))?;
+
+        let mut options = OpenOptions::new();
+        options.create(true).write(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            // Session cookies are credentials; keep them private like the history.
+            options.mode(0o600);
+        }
+        options.open(path)?.write_all(&content)?;
+        Ok(())
+    }
+
+    /// Unexpired cookies, sorted by domain, path and name.
+    pub fn list(&self) -> Vec<StoredCookie> {
+        let store = self.store.read().unwrap();
+        let mut cookies: Vec<StoredCookie> = store
+            .iter_unexpired()
+            .map(|cookie| StoredCookie {
+                domain: String::from(&cookie.domain),
+                path: String::from(&cookie.path),
+                name: cookie.name().to_string(),
+                value: cookie.value().to_string(),
+                expires: match cookie.expires {
+                    cookie_store::CookieExpiration::AtUtc(at) => {
+                        chrono::DateTime::from_timestamp(at.unix_timestamp(), 0).map(|at| at.to_rfc3339())
+                    }
+                    cookie_store::CookieExpiration::SessionEnd => None,
+                },
+            })
+            .collect();
+        cookies.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+        cookies
+    }
+
+    pub fn clear(&self) {
+        self.store.write().unwrap().clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.store.read().unwrap().iter_unexpired().next().is_none()
+    }
+}
+
+impl reqwest::cookie::CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let cookies = cookie_headers
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| cookie_store::RawCookie::parse(value.to_string()).ok());
+        self.store.write().unwrap().store_response_cookies(cookies, url);
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let header = self.store.read().unwrap()
+            .get_request_values(url)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if header.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&header).ok()
+    }
+}
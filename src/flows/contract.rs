@@ -0,0 +1,149 @@
+//! `flow contract`: call every operation of a flow on a live server and check each response
+//! against what the spec documents for it.
+//!
+//! Each difference is a [`ContractViolation`] with a severity: an undocumented status, a body
+//! that breaks its schema or isn't JSON are `high`; a Content-Type the response doesn't
+//! document is `medium`; fields the schema doesn't list are `low`. Any `high` violation fails
+//! the command.
+
+use super::{validate, Operation};
+use serde_json::Value;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractViolation {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl ContractViolation {
+    fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self { severity, message: message.into() }
+    }
+}
+
+impl fmt::Display for ContractViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.severity, self.message)
+    }
+}
+
+/// Outcome of calling one operation during `flow contract`.
+#[derive(Debug)]
+pub struct ContractCheck {
+    pub endpoint: String,
+    /// `None` when the request itself failed.
+    pub status: Option<u16>,
+    pub violations: Vec<ContractViolation>,
+}
+
+impl ContractCheck {
+    /// The most severe violation, `None` when the response honours the contract.
+    pub fn worst(&self) -> Option<Severity> {
+        self.violations.iter().map(|v| v.severity).max()
+    }
+}
+
+/// The body to send for `operation`, as `(Content-Type, body)`: the example of its JSON
+/// request content (`example`, else the first of `examples`, else the schema's), or the
+/// example of any other media type. `None` when the spec gives none.
+pub fn request_example(operation: &Operation) -> Option<(String, String)> {
+    let content = &operation.request_body.as_ref()?.content;
+    let mut media_types: Vec<_> = content.iter().collect();
+    // JSON first, then by name, so the choice is the same on every run
+    media_types.sort_by_key(|(media_type, _)| (!media_type.contains("json"), media_type.as_str()));
+    media_types.into_iter().find_map(|(media_type, media)| {
+        let example = media.example.as_ref()
+            .or_else(|| {
+                media.extras.get("examples")
+                    .and_then(Value::as_object)
+                    .and_then(|examples| examples.values().next())
+                    .and_then(|example| example.get("value"))
+            })
+            .or(media.schema.example.as_ref())?;
+        let body = match example {
+            Value::String(text) if !media_type.contains("json") => text.clone(),
+            other => other.to_string(),
+        };
+        Some((media_type.clone(), body))
+    })
+}
+
+/// Whether the documented `media_type` (which may be `*/*` or `application/*`) covers the
+/// response's `content_type`, parameters such as `charset` ignored.
+pub fn media_type_matches(media_type: &str, content_type: &str) -> bool {
+    let essence = |value: &str| value.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    let (documented, actual) = (essence(media_type), essence(content_type));
+    match documented.split_once('/') {
+        Some(("*", "*")) => true,
+        Some((kind, "*")) => actual.split_once('/').is_some_and(|(actual_kind, _)| actual_kind == kind),
+        _ => documented == actual,
+    }
+}
+
+/// Everything that differs between a live response and what `operation` documents.
+/// `components` is the flow's `components` object, for `$ref`s.
+pub fn check_response(operation: &Operation, status: u16, content_type: Option<&str>, body: &str, components: Option<&Value>) -> Vec<ContractViolation> {
+    let mut violations = Vec::new();
+    let Some((code, response)) = validate::documented_response(operation, status) else {
+        let mut documented: Vec<&str> = operation.responses.keys().map(String::as_str).collect();
+        documented.sort();
+        let message = match documented.as_slice() {
+            [] => format!("status {} is undocumented: the operation documents no responses", status),
+            codes => format!("status {} is undocumented (documented: {})", status, codes.join(", ")),
+        };
+        violations.push(ContractViolation::new(Severity::High, message));
+        return violations;
+    };
+
+    let Some(content) = response.content.as_ref().filter(|content| !content.is_empty()) else {
+        return violations;
+    };
+    if body.trim().is_empty() {
+        return violations;
+    }
+    let mut documented: Vec<&str> = content.keys().map(String::as_str).collect();
+    documented.sort();
+    match content_type {
+        Some(content_type) if documented.iter().any(|media_type| media_type_matches(media_type, content_type)) => {}
+        actual => violations.push(ContractViolation::new(
+            Severity::Medium,
+            format!("Content-Type {} is not documented for {} (documented: {})", actual.unwrap_or("(none)"), code, documented.join(", ")),
+        )),
+    }
+
+    let Some(media) = validate::json_media(content) else {
+        return violations;
+    };
+    let value: Value = match serde_json::from_str(body) {
+        Ok(value) => value,
+        Err(e) => {
+            violations.push(ContractViolation::new(Severity::High, format!("response is not JSON: {}", e)));
+            return violations;
+        }
+    };
+    violations.extend(validate::validate(&value, &media.schema, components).into_iter().map(|v| ContractViolation::new(Severity::High, v.to_string())));
+    violations.extend(
+        validate::undocumented_fields(&value, &media.schema, components)
+            .into_iter()
+            .map(|field| ContractViolation::new(Severity::Low, format!("{}: field is not in the schema", field))),
+    );
+    violations
+}
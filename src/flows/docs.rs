@@ -0,0 +1,229 @@
+//! `flow docs`: a static HTML site for a flow, one index page plus one page per endpoint.
+//!
+//! Pages are rendered from the templates in `templates/docs` with their CSS inline and
+//! relative links only, so the directory opens straight from `file://` with no build step.
+
+use super::{MediaType, OpenAPISpec, Operation};
+use crate::ai::AiClient;
+use crate::commands::mock::SchemaSampler;
+use askama::Template;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+#[derive(Template)]
+#[template(path = "docs/index.html")]
+struct IndexPage<'a> {
+    title: &'a str,
+    version: &'a str,
+    description: Option<&'a str>,
+    servers: Vec<ServerView<'a>>,
+    endpoints: &'a [EndpointView],
+}
+
+#[derive(Template)]
+#[template(path = "docs/endpoint.html")]
+struct EndpointPage<'a> {
+    title: &'a str,
+    endpoint: &'a EndpointView,
+}
+
+struct ServerView<'a> {
+    url: &'a str,
+    description: &'a str,
+}
+
+/// What a page shows about one operation.
+struct EndpointView {
+    method: String,
+    method_class: String,
+    path: String,
+    /// Page name, e.g. `get-users-id.html`.
+    file: String,
+    summary: String,
+    description: Option<String>,
+    parameters: Vec<ParameterView>,
+    request: Option<RequestView>,
+    responses: Vec<ResponseView>,
+    mock_description: String,
+    mock_examples: Vec<String>,
+}
+
+struct ParameterView {
+    name: String,
+    location: String,
+    required: bool,
+    type_name: String,
+    description: String,
+}
+
+struct RequestView {
+    required: bool,
+    description: String,
+    examples: Vec<ExampleView>,
+}
+
+struct ResponseView {
+    status: String,
+    description: String,
+    examples: Vec<ExampleView>,
+}
+
+struct ExampleView {
+    media_type: String,
+    body: String,
+}
+
+/// Every page of the site as `(file name, HTML)`, the index first.
+pub fn render(spec: &OpenAPISpec) -> Result<Vec<(String, String)>, askama::Error> {
+    let mut paths: Vec<_> = spec.paths.iter().collect();
+    paths.sort_by(|a, b| a.0.cmp(b.0));
+    let mut files = HashSet::new();
+    let endpoints: Vec<EndpointView> = paths.into_iter()
+        .flat_map(|(path, item)| item.operations().map(move |(method, operation)| (path, item, method, operation)))
+        .map(|(path, item, method, operation)| {
+            let file = page_name(method, path, &mut files);
+            endpoint_view(method, path, operation, item.mock_data.as_ref(), file)
+        })
+        .collect();
+
+    let index = IndexPage {
+        title: &spec.info.title,
+        version: &spec.info.version,
+        description: spec.info.description.as_deref(),
+        servers: spec.servers.iter()
+            .map(|server| ServerView { url: &server.url, description: server.description.as_deref().unwrap_or("") })
+            .collect(),
+        endpoints: &endpoints,
+    };
+    let mut pages = vec![("index.html".to_string(), index.render()?)];
+    for endpoint in &endpoints {
+        pages.push((endpoint.file.clone(), EndpointPage { title: &spec.info.title, endpoint }.render()?));
+    }
+    Ok(pages)
+}
+
+/// Render the site into `dir`, creating it if needed; returns how many pages were written.
+pub fn write_site(spec: &OpenAPISpec, dir: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+    let pages = render(spec)?;
+    fs::create_dir_all(dir)?;
+    for (file, html) in &pages {
+        fs::write(dir.join(file), html)?;
+    }
+    Ok(pages.len())
+}
+
+/// Have the AI write a description for each operation that has none. Only the spec in
+/// memory changes; returns how many descriptions were added.
+pub async fn describe_operations(spec: &mut OpenAPISpec, ai: &dyn AiClient) -> Result<usize, Box<dyn std::error::Error>> {
+    let title = spec.info.title.clone();
+    let mut added = 0;
+    for (path, item) in spec.paths.iter_mut() {
+        let methods: Vec<String> = item.operations().map(|(method, _)| method.to_string()).collect();
+        for method in methods {
+            let Some(Some(operation)) = item.operation_mut(&method) else {
+                continue;
+            };
+            if operation.description.as_deref().is_some_and(|d| !d.trim().is_empty()) {
+                continue;
+            }
+            let prompt = format!(
+                "Write the description for the {} {} endpoint of the '{}' API documentation: two or three plain sentences on what it does and when to use it. Reply with the description only.\n\nOperation (OpenAPI):\n{}",
+                method,
+                path,
+                title,
+                serde_json::to_string_pretty(&operation)?
+            );
+            let description = ai.ask(&prompt).await?;
+            operation.description = Some(description.trim().to_string());
+            added += 1;
+        }
+    }
+    Ok(added)
+}
+
+/// `GET /users/{id}` -> `get-users-id.html`, numbered when two endpoints would share a name.
+fn page_name(method: &str, path: &str, taken: &mut HashSet<String>) -> String {
+    let slug: Vec<&str> = path.split(|c: char| !c.is_ascii_alphanumeric()).filter(|part| !part.is_empty()).collect();
+    let slug = if slug.is_empty() { "root".to_string() } else { slug.join("-").to_lowercase() };
+    let base = format!("{}-{}", method.to_lowercase(), slug);
+    let mut name = format!("{}.html", base);
+    let mut n = 2;
+    while !taken.insert(name.clone()) {
+        name = format!("{}-{}.html", base, n);
+        n += 1;
+    }
+    name
+}
+
+fn endpoint_view(method: &str, path: &str, operation: &Operation, path_mock: Option<&super::MockDataConfig>, file: String) -> EndpointView {
+    let parameters = operation.parameters.iter().flatten()
+        .map(|parameter| ParameterView {
+            name: parameter.name.clone(),
+            location: parameter.in_.clone(),
+            required: parameter.required,
+            type_name: match (parameter.schema.schema_type.primary(), &parameter.schema.format) {
+                (name, Some(format)) if !name.is_empty() => format!("{} ({})", name, format),
+                (name, _) => name.to_string(),
+            },
+            description: parameter.description.clone().unwrap_or_default(),
+        })
+        .collect();
+
+    let request = operation.request_body.as_ref().map(|body| RequestView {
+        required: body.required.unwrap_or(false),
+        description: body.description.clone().unwrap_or_default(),
+        examples: examples(&body.content),
+    });
+
+    let mut responses: Vec<ResponseView> = operation.responses.iter()
+        .map(|(status, response)| ResponseView {
+            status: status.clone(),
+            description: response.description.clone(),
+            examples: response.content.as_ref().map(examples).unwrap_or_default(),
+        })
+        .collect();
+    responses.sort_by(|a, b| a.status.cmp(&b.status));
+
+    let mock = operation.mock_data.as_ref().or(path_mock);
+    EndpointView {
+        method: method.to_string(),
+        method_class: method.to_lowercase(),
+        path: path.to_string(),
+        file,
+        summary: operation.summary.clone().unwrap_or_default(),
+        description: operation.description.clone().filter(|d| !d.trim().is_empty()),
+        parameters,
+        request,
+        responses,
+        mock_description: mock.map(|mock| mock.description.clone()).unwrap_or_default(),
+        mock_examples: mock.and_then(|mock| mock.examples.as_ref()).into_iter().flatten()
+            .map(|example| match serde_json::from_str::<Value>(example) {
+                Ok(value) => pretty(&value),
+                Err(_) => example.clone(),
+            })
+            .collect(),
+    }
+}
+
+/// One example per media type, by name: the one written in the spec, or else a value sampled
+/// from the schema.
+fn examples(content: &HashMap<String, MediaType>) -> Vec<ExampleView> {
+    let mut media_types: Vec<_> = content.iter().collect();
+    media_types.sort_by(|a, b| a.0.cmp(b.0));
+    media_types.into_iter()
+        .filter_map(|(media_type, media)| {
+            let example = media.example.clone()
+                .or_else(|| (!media.schema.is_empty()).then(|| SchemaSampler::new(0).sample(&media.schema)))?;
+            Some(ExampleView { media_type: media_type.clone(), body: pretty(&example) })
+        })
+        .collect()
+}
+
+fn pretty(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    }
+}
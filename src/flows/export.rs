@@ -0,0 +1,94 @@
+//! Flow exporters: a standalone OpenAPI JSON document and an executable curl smoke script.
+
+use super::{OpenAPISpec, Operation};
+use serde_json::Value;
+use std::fmt::Write as _;
+
+/// Serialize the spec as pretty JSON. NUTS' `mock_data` blocks are emitted as the
+/// `x-mock-data` extension so other OpenAPI tools accept the document.
+pub fn to_openapi_json(spec: &OpenAPISpec) -> Result<String, serde_json::Error> {
+    let mut document = serde_json::to_value(spec)?;
+    if let Some(paths) = document.get_mut("paths").and_then(Value::as_object_mut) {
+        for item in paths.values_mut().filter_map(Value::as_object_mut) {
+            rename_mock_data(item);
+            for operation in item.values_mut().filter_map(Value::as_object_mut) {
+                rename_mock_data(operation);
+            }
+        }
+    }
+    serde_json::to_string_pretty(&document)
+}
+
+fn rename_mock_data(object: &mut serde_json::Map<String, Value>) {
+    if let Some(mock_data) = object.remove("mock_data") {
+        object.insert("x-mock-data".to_string(), mock_data);
+    }
+}
+
+/// A `set -e` shell script with one curl command per operation, against the first server.
+pub fn to_curl_script(spec: &OpenAPISpec) -> String {
+    let base_url = spec.servers.first()
+        .map(|s| s.url.trim_end_matches('/'))
+        .unwrap_or("http://localhost:3000");
+
+    let mut script = String::from("#!/bin/sh\n");
+    let _ = writeln!(script, "# {} {} - generated by nuts flow export", spec.info.title, spec.info.version);
+    script.push_str("set -e\n");
+
+    let mut paths: Vec<_> = spec.paths.iter().collect();
+    paths.sort_by(|a, b| a.0.cmp(b.0));
+    for (path, item) in paths {
+        for (method, operation) in item.operations() {
+            script.push('\n');
+            if let Some(summary) = &operation.summary {
+                let _ = writeln!(script, "# {}", summary.lines().next().unwrap_or_default());
+            }
+            script.push_str(&curl_command(base_url, path, method, operation));
+            script.push('\n');
+        }
+    }
+    script
+}
+
+fn curl_command(base_url: &str, path: &str, method: &str, operation: &Operation) -> String {
+    let parameters: Vec<_> = operation.parameters.iter().flatten().collect();
+
+    let mut url = format!("{}{}", base_url, path);
+    for param in parameters.iter().filter(|p| p.in_ == "path") {
+        if let Some(example) = parameter_example(&param.extras, &param.schema.example) {
+            url = url.replace(&format!("{{{}}}", param.name), &example);
+        }
+    }
+    let query: Vec<String> = parameters.iter()
+        .filter(|p| p.in_ == "query")
+        .filter_map(|p| parameter_example(&p.extras, &p.schema.example).map(|value| format!("{}={}", p.name, value)))
+        .collect();
+    if !query.is_empty() {
+        url = format!("{}?{}", url, query.join("&"));
+    }
+
+    let mut command = format!("curl -sS -f -X {} {}", method, shell_quote(&url));
+    for param in parameters.iter().filter(|p| p.in_ == "header") {
+        if let Some(value) = parameter_example(&param.extras, &param.schema.example) {
+            let _ = write!(command, " \\\n  -H {}", shell_quote(&format!("{}: {}", param.name, value)));
+        }
+    }
+    let body = operation.request_body.as_ref()
+        .and_then(|body| body.content.get("application/json"))
+        .and_then(|media| media.example.as_ref().or(media.schema.example.as_ref()));
+    if let Some(body) = body {
+        let _ = write!(command, " \\\n  -H 'Content-Type: application/json' \\\n  -d {}", shell_quote(&body.to_string()));
+    }
+    command
+}
+
+fn parameter_example(extras: &super::Extras, schema_example: &Option<Value>) -> Option<String> {
+    extras.get("example")
+        .or(schema_example.as_ref())
+        .map(|value| value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string()))
+}
+
+/// Single-quote `value` for POSIX shells.
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
@@ -1,41 +1,73 @@
 use crate::flows::*;
 use crate::commands::perf::PerfCommand;
+use crate::models::metrics::MetricsSummary;
 use rustyline::Editor;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::time::Duration;
-use std::collections::HashMap;
-use crate::commands::call::CallCommand;
-use crate::commands::mock::MockServer;
-use anthropic::client::{Client as AnthropicClient, ClientBuilder};
-use anthropic::types::{ContentBlock, Message, MessagesRequestBuilder, Role};
+use std::collections::{BTreeMap, HashMap};
+use crate::commands::call::{CallCommand, CallOptions, CallResponse};
+use crate::flows::record::{self, template_path, Exchange};
+use crate::flows::contract::{self, ContractCheck, Severity};
+use crate::flows::variables::SharedVariables;
+use crate::commands::mock::{MockOptions, MockServer};
+use crate::commands::proxy::RecordingProxy;
+use crate::ai::{self, AiClient};
 use console::style;
 use crate::config::Config;
+use crate::units;
+use crate::commands::{print_ai_failed, print_ai_skipped};
+use crate::commands::generate::{ask_for_records, records_prompt, RecordShape};
+use crate::commands::discover::merge_spec;
 use url;
 
 #[allow(dead_code)]
 pub struct CollectionManager {
     collections_dir: PathBuf,
     config: Config,
-    ai_client: AnthropicClient,
+    /// Values captured via `x-extract`, shared by every step run through this manager.
+    variables: SharedVariables,
+    /// Name of the spec server steps are sent to; the first one when unset.
+    server: Option<String>,
+    /// `flow contract --base-url`: where steps are sent, over the spec's servers.
+    base_url: Option<String>,
 }
 
 #[allow(dead_code)]
 impl CollectionManager {
     pub fn new(collections_dir: PathBuf, config: Config) -> Self {
-        let api_key = config.anthropic_api_key.clone()
-            .unwrap_or_default();
-
         Self {
             collections_dir,
             config,
-            ai_client: ClientBuilder::default()
-                .api_key(api_key)
-                .build()
-                .unwrap(),
+            variables: SharedVariables::default(),
+            server: None,
+            base_url: None,
         }
     }
 
+    /// Send steps to `url` rather than to one of the spec's servers.
+    pub fn with_base_url(mut self, url: Option<String>) -> Self {
+        self.base_url = url.map(|url| url.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Send steps to the spec server called `name` instead of the first one.
+    pub fn with_server(mut self, name: Option<String>) -> Self {
+        self.server = name;
+        self
+    }
+
+    /// Capture into and read from `variables`, which outlive this manager.
+    pub fn with_variables(mut self, variables: SharedVariables) -> Self {
+        self.variables = variables;
+        self
+    }
+
+    /// Build the AI client on demand so offline use never touches a provider.
+    fn ai_client(&self) -> Result<Box<dyn AiClient>, Box<dyn std::error::Error>> {
+        ai::client(&self.config, "flow")
+    }
+
     fn get_collection_path(&self, name: &str) -> PathBuf {
         self.collections_dir.join(format!("{}.yaml", name))
     }
@@ -74,6 +106,7 @@ impl CollectionManager {
             spec.servers.push(Server {
                 url: server_url,
                 description: Some("API Server".to_string()),
+                ..Default::default()
             });
         }
 
@@ -93,15 +126,15 @@ impl CollectionManager {
                         let mut content = HashMap::new();
                         content.insert("application/json".to_string(), MediaType {
                             schema: Schema {
-                                schema_type: "object".to_string(),
-                                format: None,
-                                properties: None,
-                                items: None,
+                                schema_type: "object".into(),
+                                ..Default::default()
                             },
                             example: Some(serde_json::json!({})),
+                            ..Default::default()
                         });
                         content
                     },
+                    ..Default::default()
                 })
             } else {
                 None
@@ -114,78 +147,350 @@ impl CollectionManager {
                         let mut content = HashMap::new();
                         content.insert("application/json".to_string(), MediaType {
                             schema: Schema {
-                                schema_type: "object".to_string(),
-                                format: None,
-                                properties: None,
-                                items: None,
+                                schema_type: "object".into(),
+                                ..Default::default()
                             },
                             example: None,
+                            ..Default::default()
                         });
                         content
                     }),
+                    ..Default::default()
                 });
                 responses
             },
             security: None,
             tags: Some(vec![clean_path.split('/').nth(1).unwrap_or("default").to_string()]),
             mock_data: None,
+            ..Default::default()
         };
 
         // Add operation to path item
-        match method {
-            "GET" => path_item.get = Some(operation),
-            "POST" => path_item.post = Some(operation),
-            "PUT" => path_item.put = Some(operation),
-            "DELETE" => path_item.delete = Some(operation),
-            "PATCH" => path_item.patch = Some(operation),
-            _ => return Err("Unsupported HTTP method".into()),
-        }
+        *path_item.operation_mut(method).ok_or_else(|| format!("Unsupported HTTP method: {}", method))? = Some(operation);
 
         spec.save(&spec_path)?;
         println!("✅ Added {} endpoint {} to flow", method, clean_path);
         Ok(())
     }
 
+    /// Set `key` for every step of `flow`, kept under `x-variables` in its spec.
+    pub fn set_variable(&self, flow: &str, key: &str, value: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if key.is_empty() || key.contains(['{', '}']) || key.contains(char::is_whitespace) {
+            return Err(format!("'{}' can't be a variable name", key).into());
+        }
+        let spec_path = self.get_collection_path(flow);
+        let mut spec = OpenAPISpec::load(&spec_path)?;
+        spec.variables.insert(key.to_string(), value.to_string());
+        spec.save(&spec_path)
+    }
+
+    /// The `x-variables` of `flow`, by name.
+    pub fn variables(&self, flow: &str) -> Result<BTreeMap<String, String>, Box<dyn std::error::Error>> {
+        Ok(OpenAPISpec::load(&self.get_collection_path(flow))?.variables)
+    }
+
     pub async fn run_endpoint(
         &self,
         flow: &str,
         endpoint: &str,
-        _args: &[String]
+        args: &[String]
     ) -> Result<(), Box<dyn std::error::Error>> {
         let spec_path = self.get_collection_path(flow);
         let spec = OpenAPISpec::load(&spec_path)?;
 
-        // Find the endpoint in the spec
+        // Find the endpoint in the spec, preferring an exact path match
         let (path, item) = spec.paths.iter()
-            .find(|(p, _)| p.contains(endpoint))
+            .find(|(p, _)| p.as_str() == endpoint)
+            .or_else(|| spec.paths.iter().find(|(p, _)| p.contains(endpoint)))
             .ok_or("Endpoint not found in flow")?;
 
-        // Determine method and operation
-        let (method, _operation) = item.get_operation()
-            .ok_or("No operation found for endpoint")?;
+        // Determine method and operation; an explicit method argument picks among several
+        let requested = args.first().map(|m| m.to_uppercase());
+        let (method, operation) = match requested {
+            Some(requested) => item.operations()
+                .find(|(m, _)| *m == requested)
+                .ok_or_else(|| format!("No {} operation found for {}", requested, path))?,
+            None => item.get_operation()
+                .ok_or("No operation found for endpoint")?,
+        };
+        self.call_operation(&spec, path, method, operation).await?;
+        Ok(())
+    }
 
-        // Build the full URL
-        let base_url = spec.servers.first()
-            .map(|s| s.url.as_str())
-            .unwrap_or("http://localhost:3000");
-        let full_url = format!("{}{}", base_url, path);
+    /// Run every operation of a flow in [`run_order`], carrying captured variables from step to
+    /// step, and print a summary table. A failed step doesn't stop the run unless `fail_fast`
+    /// is set; the run fails when any step did.
+    pub async fn run_all(&self, flow: &str, fail_fast: bool) -> Result<Vec<RunStep>, Box<dyn std::error::Error>> {
+        let spec_path = self.get_collection_path(flow);
+        let spec = OpenAPISpec::load(&spec_path)?;
+        let components = spec.extras.get("components");
+
+        let order = run_order(&spec);
+        let mut steps = Vec::new();
+        for (path, method, operation) in &order {
+            let started = std::time::Instant::now();
+            let result = self.call_operation(&spec, path, method, operation).await;
+            let latency_ms = started.elapsed().as_millis() as u64;
+            let mut step = RunStep { endpoint: format!("{} {}", method, path), status: None, latency_ms, failures: Vec::new() };
+            match result {
+                Ok(response) => {
+                    step.status = Some(response.status);
+                    if !expected_status(operation, response.status) {
+                        step.failures.push(format!("status {} is not a documented response", response.status));
+                    }
+                    if let Some(budget) = &operation.budget {
+                        step.failures.extend(budget.violations(response.status, latency_ms));
+                    }
+                    if let Some(schema) = validate::response_schema(operation, response.status) {
+                        match serde_json::from_str(&response.body) {
+                            Ok(body) => step.failures.extend(validate::validate(&body, schema, components).iter().map(ToString::to_string)),
+                            Err(e) => step.failures.push(format!("response is not JSON: {}", e)),
+                        }
+                    }
+                }
+                Err(e) => step.failures.push(format!("request failed: {}", e)),
+            }
+            let failed = !step.failures.is_empty();
+            steps.push(step);
+            if failed && fail_fast {
+                break;
+            }
+        }
+
+        println!("\n{}", style("🏁 Flow Run").cyan().bold());
+        print!("{}", run_table(&steps));
+        let skipped = order.len() - steps.len();
+        if skipped > 0 {
+            println!("⏭️  {} step(s) skipped after the first failure (--fail-fast)", skipped);
+        }
+        let failed = steps.iter().filter(|s| !s.failures.is_empty()).count();
+        if failed > 0 {
+            return Err(format!("{} of {} step(s) in '{}' failed", failed, order.len(), flow).into());
+        }
+        println!("{} All {} step(s) passed", style("✅").green(), steps.len());
+        Ok(steps)
+    }
+
+    /// Send one operation, substituting captured variables, and capture its `x-extract` values.
+    async fn call_operation(
+        &self,
+        spec: &OpenAPISpec,
+        path: &str,
+        method: &str,
+        operation: &Operation,
+    ) -> Result<CallResponse, Box<dyn std::error::Error>> {
+        let step = format!("{} {}", method, path);
+
+        // Build the request, substituting variables captured by earlier steps, then the
+        // flow's own and the active environment's
+        let base_url = match &self.base_url {
+            Some(url) => url.as_str(),
+            None => spec.server_url(self.server.as_deref())?,
+        };
+        let environment = self.config.active_environment();
+        let mut variables = self.variables.lock().await;
+        variables.set_defaults(
+            environment.variables.iter().map(|(name, value)| (name, value, "environment"))
+                .chain(spec.variables.iter().map(|(name, value)| (name, value, "x-variables"))),
+        );
+        let producer = |name: &str| Self::variable_producer(spec, name);
+
+        let full_url = format!(
+            "{}{}",
+            variables.substitute(base_url, &step, producer)?,
+            variables.substitute_path(&variables.fill_path_params(path), &step, producer)?,
+        );
+
+        let mut headers = HashMap::new();
+        for param in operation.parameters.iter().flatten().filter(|p| p.in_ == "header") {
+            if let Some(example) = param.extras.get("example") {
+                let value = example.as_str().map(str::to_string).unwrap_or_else(|| example.to_string());
+                headers.insert(param.name.clone(), variables.substitute(&value, &step, producer)?);
+            }
+        }
+
+        let body = match contract::request_example(operation) {
+            Some((content_type, example)) => {
+                headers.entry("Content-Type".to_string()).or_insert(content_type);
+                Some(variables.substitute_body(&example, &step, producer)?)
+            }
+            None => None,
+        };
 
         // Execute the request
         println!(" Executing {} {}", method, full_url);
-        CallCommand::new().execute(&[method, &full_url]).await?;
-        Ok(())
+        let hooks = self.config.hooks.overridden_by(spec.hooks.as_ref());
+        let options = CallOptions {
+            method: method.to_string(),
+            url: full_url,
+            headers,
+            body,
+            ..Default::default()
+        };
+        let response = CallCommand::new().with_hooks(hooks).execute_with_options(options).await?;
+
+        for name in variables.extract_all(&operation.extract, &response.body, &step)? {
+            println!("🔗 Captured {} = {}", style(&name).cyan(), variables.get(&name).map(|v| v.value.as_str()).unwrap_or(""));
+        }
+        Ok(response)
+    }
+
+    /// Call every operation of a flow once and compare it against its `x-budget`.
+    /// Fails when any endpoint is over budget or could not be called.
+    pub async fn check_budgets(&self, flow: &str) -> Result<Vec<BudgetCheck>, Box<dyn std::error::Error>> {
+        let spec_path = self.get_collection_path(flow);
+        let spec = OpenAPISpec::load(&spec_path)?;
+
+        let mut paths: Vec<_> = spec.paths.iter().collect();
+        paths.sort_by(|a, b| a.0.cmp(b.0));
+        let mut checks = Vec::new();
+        for (path, item) in paths {
+            for (method, operation) in item.operations() {
+                let started = std::time::Instant::now();
+                let result = self.call_operation(&spec, path, method, operation).await;
+                let latency_ms = started.elapsed().as_millis() as u64;
+                let (status, failures) = match result {
+                    Ok(response) => {
+                        let failures = operation.budget.as_ref()
+                            .map(|budget| budget.violations(response.status, latency_ms))
+                            .unwrap_or_default();
+                        (Some(response.status), failures)
+                    }
+                    Err(e) => (None, vec![format!("request failed: {}", e)]),
+                };
+                checks.push(BudgetCheck {
+                    endpoint: format!("{} {}", method, path),
+                    status,
+                    latency_ms,
+                    budget: operation.budget.clone(),
+                    failures,
+                });
+            }
+        }
+
+        println!("\n{}", style("📏 Budget Check").cyan().bold());
+        print!("{}", budget_table(&checks));
+        let failed = checks.iter().filter(|c| !c.failures.is_empty()).count();
+        if failed > 0 {
+            return Err(format!("{} of {} endpoint(s) in '{}' failed their budget", failed, checks.len(), flow).into());
+        }
+        println!("{} All {} endpoint(s) within budget", style("✅").green(), checks.len());
+        Ok(checks)
+    }
+
+    /// Call the flow's operations (those under `endpoint` only, when given) and validate each
+    /// JSON response against the schema declared for its status. Fails when a response
+    /// breaks its schema or a request fails.
+    pub async fn validate_schemas(&self, flow: &str, endpoint: Option<&str>) -> Result<Vec<SchemaCheck>, Box<dyn std::error::Error>> {
+        let spec_path = self.get_collection_path(flow);
+        let spec = OpenAPISpec::load(&spec_path)?;
+        let components = spec.extras.get("components");
+
+        let mut paths: Vec<_> = spec.paths.iter()
+            .filter(|(path, _)| match endpoint {
+                Some(endpoint) if spec.paths.contains_key(endpoint) => path.as_str() == endpoint,
+                Some(endpoint) => path.contains(endpoint),
+                None => true,
+            })
+            .collect();
+        if paths.is_empty() {
+            return Err(format!("No endpoint matching '{}' in flow '{}'", endpoint.unwrap_or_default(), flow).into());
+        }
+        paths.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut checks = Vec::new();
+        for (path, item) in paths {
+            for (method, operation) in item.operations() {
+                let mut check = SchemaCheck { endpoint: format!("{} {}", method, path), status: None, schema: false, failures: Vec::new() };
+                match self.call_operation(&spec, path, method, operation).await {
+                    Ok(response) => {
+                        check.status = Some(response.status);
+                        if let Some(schema) = validate::response_schema(operation, response.status) {
+                            check.schema = true;
+                            match serde_json::from_str(&response.body) {
+                                Ok(body) => check.failures = validate::validate(&body, schema, components)
+                                    .iter()
+                                    .map(ToString::to_string)
+                                    .collect(),
+                                Err(e) => check.failures.push(format!("response is not JSON: {}", e)),
+                            }
+                        }
+                    }
+                    Err(e) => check.failures.push(format!("request failed: {}", e)),
+                }
+                checks.push(check);
+            }
+        }
+
+        println!("\n{}", style("🧾 Schema Validation").cyan().bold());
+        print!("{}", schema_table(&checks));
+        let failed = checks.iter().filter(|c| !c.failures.is_empty()).count();
+        if failed > 0 {
+            return Err(format!("{} of {} endpoint(s) in '{}' don't match their schema", failed, checks.len(), flow).into());
+        }
+        println!("{} All {} endpoint(s) match their schema", style("✅").green(), checks.len());
+        Ok(checks)
+    }
+
+    /// `flow contract`: call every operation and check its response against the spec, with
+    /// the request body examples where the spec has them. Fails on any high-severity violation.
+    pub async fn contract_test(&self, flow: &str) -> Result<Vec<ContractCheck>, Box<dyn std::error::Error>> {
+        let spec = OpenAPISpec::load(&self.get_collection_path(flow))?;
+        let components = spec.extras.get("components");
+        let mut paths: Vec<_> = spec.paths.iter().collect();
+        paths.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut checks = Vec::new();
+        for (path, item) in paths {
+            for (method, operation) in item.operations() {
+                let mut check = ContractCheck { endpoint: format!("{} {}", method, path), status: None, violations: Vec::new() };
+                match self.call_operation(&spec, path, method, operation).await {
+                    Ok(response) => {
+                        check.status = Some(response.status);
+                        let content_type = response.headers.iter()
+                            .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+                            .map(|(_, value)| value.as_str());
+                        check.violations = contract::check_response(operation, response.status, content_type, &response.body, components);
+                    }
+                    Err(e) => check.violations.push(contract::ContractViolation { severity: Severity::High, message: format!("request failed: {}", e) }),
+                }
+                checks.push(check);
+            }
+        }
+
+        println!("\n{}", style("📜 Contract").cyan().bold());
+        print!("{}", contract_table(&checks));
+        let broken = checks.iter().filter(|c| c.worst() == Some(Severity::High)).count();
+        if broken > 0 {
+            return Err(format!("{} of {} endpoint(s) in '{}' break the contract", broken, checks.len(), flow).into());
+        }
+        let warned = checks.iter().filter(|c| c.worst().is_some()).count();
+        match warned {
+            0 => println!("{} All {} endpoint(s) honour the contract", style("✅").green(), checks.len()),
+            n => println!("{} No high-severity violations; {} endpoint(s) with minor differences", style("⚠️").yellow(), n),
+        }
+        Ok(checks)
+    }
+
+    /// The step whose `x-extract` rules define `name`, if any.
+    fn variable_producer(spec: &OpenAPISpec, name: &str) -> Option<String> {
+        spec.paths.iter()
+            .flat_map(|(path, item)| item.operations().map(move |(method, op)| (path, method, op)))
+            .find(|(_, _, op)| op.extract.contains_key(name))
+            .map(|(path, method, _)| format!("{} {}", method, path))
     }
 
     pub async fn start_mock_server(
         &self,
         name: &str,
-        port: u16
+        port: u16,
+        options: MockOptions
     ) -> Result<(), Box<dyn std::error::Error>> {
         let spec_path = self.get_collection_path(name);
         let spec = OpenAPISpec::load(&spec_path)?;
         
         println!("Starting mock server for {} on port {}", name, port);
-        MockServer::new(spec, port).start().await?;
+        MockServer::new(spec, port).with_options(options).with_spec_file(spec_path).start().await?;
         Ok(())
     }
 
@@ -195,92 +500,39 @@ impl CollectionManager {
         endpoint: &str,
         _editor: &mut Editor<impl rustyline::Helper, impl rustyline::history::History>
     ) -> Result<(), Box<dyn std::error::Error>> {
-        // Check for API key
-        let api_key = self.config.anthropic_api_key.clone()
-            .ok_or("API key not configured. Use 'config api-key' to set it")?;
+        // Mock data generation is AI-only
+        let ai_client = self.ai_client()?;
 
-        // Verify API key is not empty
-        if api_key.trim().is_empty() {
-            return Err("API key is empty. Use 'config api-key' to set it".into());
-        }
-
-        let spec_path = self.get_collection_path(flow);
-        let mut spec = OpenAPISpec::load(&spec_path)?;
-
-        println!("Available endpoints:");
-        for path in spec.paths.keys() {
-            println!("  • {}", path);
-        }
-
-        // Find the endpoint by exact path match
-        if let Some((_path, item)) = spec.paths.iter_mut().find(|(path, _)| path == &endpoint) {
-            println!("⚙️  Analyzing endpoint and generating mock data...");
-
-            // Improve the prompt with actual schema information
-            let prompt = format!(
-                "You are a mock data generator for API testing. Generate diverse test data examples for this endpoint.\n\
-                URL: {}\n\
-                Response Schema: {}\n\n\
-                Generate 10 different examples in this format:\n\
-                Description: <what this example tests>\n\
-                {{\n  // JSON response example\n}}\n\n\
-                Include examples for:\n\
-                1. Happy path with typical data\n\
-                2. Edge cases (empty values, very long values)\n\
-                3. Special characters and Unicode\n\
-                4. Error responses (404, 500)\n\
-                5. Boundary testing\n\
-                Make each example valid JSON.",
-                endpoint,
-                serde_json::to_string_pretty(&item.get.as_ref()
-                    .and_then(|op| op.responses.get("200"))
-                    .and_then(|resp| resp.content.as_ref())
-                    .unwrap_or(&HashMap::new()))?
-            );
-
-            // Get AI response
-            let messages = vec![Message {
-                role: Role::User,
-                content: vec![ContentBlock::Text { text: prompt.into() }]
-            }];
-
-            let messages_request = MessagesRequestBuilder::default()
-                .messages(messages)
-                .model("claude-3-sonnet-20240229".to_string())
-                .max_tokens(2000_usize)
-                .build()?;
-
-            let response = self.ai_client.messages(messages_request).await?;
-            
-            // Debug the AI response
-            if let Some(ContentBlock::Text { text }) = response.content.first() {
-                println!("AI Response:\n{}", text);  // Debug print
-                let examples = Self::parse_mock_examples(&text)?;
-                if examples.is_empty() {
-                    println!("⚠️  No valid examples could be parsed from AI response");
-                } else {
-                    // Save examples to the OpenAPI spec
-                    let examples_clone = examples.clone();
-                    item.mock_data = Some(MockDataConfig {
-                        description: "AI-generated mock responses".to_string(),
-                        schema: None,
-                        examples: Some(examples),
-                    });
-
-                    spec.save(&spec_path)?;
-                    println!("✅ Generated and saved {} mock examples", examples_clone.len());
-                    
-                    // Print example summaries
-                    println!("\n📋 Generated mock examples:");
-                    for (i, example) in examples_clone.iter().enumerate() {
-                        println!("  {}. {}", i + 1, style(example).cyan());
-                    }
-                }
-            }
-        } else {
+        let spec = OpenAPISpec::load(&self.get_collection_path(flow))?;
+        let Some(item) = spec.paths.get(endpoint) else {
             println!("❌ Endpoint not found in flow: {}", endpoint);
+            println!("Available endpoints:");
+            for path in spec.paths.keys() {
+                println!("  • {}", path);
+            }
+            return Ok(());
+        };
+        let Some((method, operation)) = item.get_operation() else {
+            println!("❌ {} has no operations in flow {}", endpoint, flow);
+            return Ok(());
+        };
+
+        println!("⚙️  Analyzing endpoint and generating mock data...");
+        let shape = RecordShape::response(operation, spec.extras.get("components"));
+        let prompt = records_prompt(&format!("JSON responses of {} {}", method, endpoint), 10, shape.as_ref());
+        let examples = ask_for_records(&*ai_client, prompt, 10, shape.as_ref()).await?;
+        tracing::debug!(target: "nuts::flows", endpoint, examples = examples.len(), "mock data AI response");
+        if examples.is_empty() {
+            println!("⚠️  The AI answer had no examples");
+            return Ok(());
         }
 
+        let operation = self.store_operation_examples(flow, Some(method), endpoint, &examples, "AI-generated mock responses")?;
+        println!("✅ Generated and saved {} mock examples for {}", examples.len(), operation);
+        println!("\n📋 Generated mock examples:");
+        for (i, example) in examples.iter().enumerate() {
+            println!("  {}. {}", i + 1, style(example).cyan());
+        }
         Ok(())
     }
 
@@ -296,7 +548,7 @@ impl CollectionManager {
                 in_json = true;
                 current_json = line.to_string();
             } else if in_json {
-                current_json.push_str("\n");
+                current_json.push('\n');
                 current_json.push_str(line);
                 
                 if line.contains("}") {
@@ -346,45 +598,30 @@ impl CollectionManager {
             endpoints.join("\n")
         );
 
-        let messages = vec![Message {
-            role: Role::User,
-            content: vec![ContentBlock::Text { text: prompt }],
-        }];
-
-        let message_request = MessagesRequestBuilder::default()
-            .messages(messages)
-            .model("claude-3-haiku-20240307".to_string())
-            .max_tokens(800_usize)
-            .build()?;
-
-        let response = self.ai_client.messages(message_request).await?;
+        let text = self.ai_client()?.ask(&prompt).await?;
         
-        if let Some(ContentBlock::Text { text }) = response.content.first() {
-            let mut flow = Vec::new();
-            for line in text.lines() {
-                if let Some((call, explanation)) = line.split_once('|') {
-                    let parts: Vec<&str> = call.trim().split_whitespace().collect();
-                    if parts.len() >= 2 {
-                        let method = parts[0].to_string();
-                        let path = parts[1].to_string();
-                        let body = if parts.len() > 2 {
-                            Some(parts[2..].join(" "))
-                        } else {
-                            None
-                        };
-                        println!("   • {} {} | {}", 
-                            style(&method).cyan().to_string(),
-                            style(&path).green().to_string(),
-                            style(explanation.trim()).dim().to_string()
-                        );
-                        flow.push((method, path, body));
-                    }
+        let mut flow = Vec::new();
+        for line in text.lines() {
+            if let Some((call, explanation)) = line.split_once('|') {
+                let parts: Vec<&str> = call.split_whitespace().collect();
+                if parts.len() >= 2 {
+                    let method = parts[0].to_string();
+                    let path = parts[1].to_string();
+                    let body = if parts.len() > 2 {
+                        Some(parts[2..].join(" "))
+                    } else {
+                        None
+                    };
+                    println!("   • {} {} | {}", 
+                        style(&method).cyan(),
+                        style(&path).green(),
+                        style(explanation.trim()).dim()
+                    );
+                    flow.push((method, path, body));
                 }
             }
-            Ok(flow)
-        } else {
-            Ok(Vec::new())
         }
+        Ok(flow)
     }
 
     async fn parse_options(options: &[String]) -> Result<(u32, Duration), Box<dyn std::error::Error>> {
@@ -394,12 +631,11 @@ impl CollectionManager {
             .and_then(|u| u.parse().ok())
             .unwrap_or(10);
 
-        let duration = options.iter()
-            .position(|x| x == "--duration")
-            .and_then(|i| options.get(i + 1))
-            .and_then(|d| d.trim_end_matches('s').parse().ok())
-            .map(Duration::from_secs)
-            .unwrap_or(Duration::from_secs(30));
+        let duration = match options.iter().position(|x| x == "--duration").map(|i| options.get(i + 1)) {
+            Some(value) => units::parse_positive_duration(value.map(String::as_str).unwrap_or_default())
+                .map_err(|e| format!("Invalid --duration: {}", e))?,
+            None => Duration::from_secs(30),
+        };
 
         Ok((users, duration))
     }
@@ -420,46 +656,54 @@ impl CollectionManager {
         // If no specific endpoint is provided, analyze all endpoints
         if endpoint.is_none() {
             println!("🔍 Analyzing flow endpoints...");
-            
+            let perf = PerfCommand::new(&self.config);
+            let mut results = Vec::new();
+
             // Try AI flow generation if API key is available
-            if self.config.api_key.is_some() {
+            if !self.config.is_offline() {
                 println!("🤖 Generating realistic test scenarios...\n");
                 if let Ok(flow) = self.generate_user_flow(&spec).await {
-                    if !flow.is_empty() {
-                        let perf = PerfCommand::new(&self.config);
-                        for (method, path, body) in flow {
-                            println!("\n🚀 Testing {} {}", style(&method).cyan(), style(&path).green());
-                            let url = if path.starts_with("http://") || path.starts_with("https://") {
-                                path.to_string()
-                            } else {
-                                format!("{}{}", &base_url, &path)
-                            };
-                            perf.run(
-                                &url,
-                                users,
-                                duration,
-                                &method,
-                                body.as_deref()
-                            ).await?;
-                        }
-                        return Ok(());
+                    for (method, path, body) in flow {
+                        println!("\n🚀 Testing {} {}", style(&method).cyan(), style(&path).green());
+                        let url = if path.starts_with("http://") || path.starts_with("https://") {
+                            path.to_string()
+                        } else {
+                            format!("{}{}", &base_url, &path)
+                        };
+                        let summary = perf.run(
+                            &url,
+                            users,
+                            duration,
+                            &method,
+                            body.as_deref(),
+                            &HashMap::new()
+                        ).await?;
+                        results.push(EndpointPerf { method: method.to_uppercase(), path, summary });
                     }
                 }
             }
 
-            // Fallback to testing all GET endpoints
-            println!("ℹ️  Testing all GET endpoints...");
-            let _perf = PerfCommand::new(&self.config);
-            for (path, item) in &spec.paths {
-                if let Some(_op) = &item.get {
-                    println!("\n🚀 Testing GET {}", style(path).green());
-                    self.run_single_endpoint_test(path, "GET", users, duration, base_url).await?;
-                }
-                if let Some(_op) = &item.post {
-                    println!("\n🚀 Testing POST {}", style(path).green());
-                    self.run_single_endpoint_test(path, "POST", users, duration, base_url).await?;
+            // Fallback to testing all GET and POST endpoints
+            if results.is_empty() {
+                println!("ℹ️  Testing all GET endpoints...");
+                let mut paths: Vec<_> = spec.paths.iter().collect();
+                paths.sort_by(|a, b| a.0.cmp(b.0));
+                for (path, item) in paths {
+                    for (method, present) in [("GET", item.get.is_some()), ("POST", item.post.is_some())] {
+                        if !present {
+                            continue;
+                        }
+                        println!("\n🚀 Testing {} {}", method, style(path).green());
+                        let summary = self.run_single_endpoint_test(&perf, path, method, users, duration, base_url).await?;
+                        results.push(EndpointPerf { method: method.to_string(), path: path.clone(), summary });
+                    }
                 }
             }
+
+            if !results.is_empty() {
+                println!("\n{}", style("📋 Endpoint Comparison (slowest p95 first)").cyan().bold());
+                print!("{}", endpoint_perf_table(&mut results));
+            }
             return Ok(());
         }
 
@@ -478,7 +722,9 @@ impl CollectionManager {
         } else {
             format!("{}{}", base_url, endpoint)
         };
-        self.run_single_endpoint_test(&url, method, users, duration, base_url).await
+        let perf = PerfCommand::new(&self.config);
+        self.run_single_endpoint_test(&perf, &url, method, users, duration, base_url).await?;
+        Ok(())
     }
 
     pub async fn generate_openapi(
@@ -489,19 +735,18 @@ impl CollectionManager {
         let spec_path = self.get_collection_path(name);
         let mut spec = OpenAPISpec::load(&spec_path)?;
 
-        println!("🤖 Analyzing API endpoints and generating documentation...");
-
-        // Get API key from config
-        let api_key = self.config.anthropic_api_key.clone()
-            .ok_or("API key not configured. Use 'config api-key' to set it")?;
-
-        // Verify API key is not empty
-        if api_key.trim().is_empty() {
-            return Err("API key is empty. Use 'config api-key' to set it".into());
-        }
+        // Without a key the spec is written as-is; AI only enhances descriptions
+        let ai_client = if self.config.is_offline() {
+            print_ai_skipped("AI documentation enhancement");
+            None
+        } else {
+            println!("🤖 Analyzing API endpoints and generating documentation...");
+            Some(self.ai_client()?)
+        };
 
         // Generate documentation for each endpoint
         for (path, item) in spec.paths.iter_mut() {
+            let Some(ai_client) = &ai_client else { break };
             if let Some(operation) = &mut item.get {
                 let prompt = format!(
                     "You are a technical writer creating OpenAPI documentation. \
@@ -520,26 +765,13 @@ impl CollectionManager {
                     operation.responses.get("200").and_then(|r| r.content.as_ref())
                 );
 
-                let messages = vec![Message {
-                    role: Role::User,
-                    content: vec![ContentBlock::Text { text: prompt.into() }]
-                }];
-
-                let messages_request = MessagesRequestBuilder::default()
-                    .messages(messages)
-                    .model("claude-3-sonnet-20240229".to_string())
-                    .max_tokens(1000_usize)
-                    .build()?;
-
-                let response = self.ai_client.messages(messages_request).await?;
+                let text = ai_client.ask(&prompt).await?;
                 
-                if let Some(ContentBlock::Text { text }) = response.content.first() {
-                    // Parse AI response into summary and description
-                    let lines: Vec<&str> = text.lines().collect();
-                    if let Some((summary, description)) = lines.split_first() {
-                        operation.summary = Some(summary.trim().to_string());
-                        operation.description = Some(description.join("\n").trim().to_string());
-                    }
+                // Parse AI response into summary and description
+                let lines: Vec<&str> = text.lines().collect();
+                if let Some((summary, description)) = lines.split_first() {
+                    operation.summary = Some(summary.trim().to_string());
+                    operation.description = Some(description.join("\n").trim().to_string());
                 }
             }
         }
@@ -566,6 +798,166 @@ impl CollectionManager {
         Ok(())
     }
 
+    /// Render flow `name` as a static HTML site in `out`. With an AI key, operations without a
+    /// description get one written for the pages (the flow file is left as it is).
+    pub async fn generate_docs(&self, name: &str, out: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut spec = OpenAPISpec::load(&self.get_collection_path(name))?;
+        if self.config.is_offline() {
+            print_ai_skipped("AI descriptions");
+        } else {
+            let described = match self.ai_client() {
+                Ok(ai_client) => docs::describe_operations(&mut spec, ai_client.as_ref()).await,
+                Err(e) => Err(e),
+            };
+            match described {
+                Ok(0) => {}
+                Ok(n) => println!("🤖 Described {} endpoint{}", n, if n == 1 { "" } else { "s" }),
+                Err(e) => print_ai_failed("AI descriptions", e.as_ref()),
+            }
+        }
+
+        let pages = docs::write_site(&spec, out)?;
+        println!("✅ Wrote {} pages for {}: {}", pages, name, style(out.join("index.html").display()).green());
+        Ok(())
+    }
+
+    /// Write flow `name` to `out` as an OpenAPI JSON document (`openapi-json`) or a curl script (`curl`).
+    pub fn export_flow(&self, name: &str, format: &str, out: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let spec = OpenAPISpec::load(&self.get_collection_path(name))?;
+        let content = match format {
+            "openapi-json" | "json" => export::to_openapi_json(&spec)?,
+            "curl" => export::to_curl_script(&spec),
+            other => return Err(format!("Unsupported export format '{}': use openapi-json or curl", other).into()),
+        };
+
+        if let Some(parent) = out.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(out, content)?;
+        #[cfg(unix)]
+        if format == "curl" {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(out, fs::Permissions::from_mode(0o755))?;
+        }
+
+        println!("✅ Exported {} as {}: {}", name, format, style(out.display()).green());
+        Ok(())
+    }
+
+    /// Convert a Postman collection into `<name>.yaml`, named after the collection unless `name` is given.
+    /// An existing flow is extended the way `discover` extends one, keeping its operations,
+    /// unless `replace` is set.
+    pub fn import_postman(&self, file: &Path, name: Option<&str>, replace: bool) -> Result<postman::PostmanImport, Box<dyn std::error::Error>> {
+        let json = fs::read_to_string(file)
+            .map_err(|e| format!("Could not read {}: {}", file.display(), e))?;
+        let import = postman::convert(&json)?;
+
+        let name = name.map(str::to_string).unwrap_or_else(|| flow_name(&import.spec.info.title));
+        fs::create_dir_all(&self.collections_dir)?;
+        let path = self.get_collection_path(&name);
+        let outcome = match path.exists() {
+            true if !replace => {
+                let mut spec = OpenAPISpec::load(&path).map_err(|e| format!("Could not load flow '{}' to merge into: {}; use --force to replace it", name, e))?;
+                merge_spec(&mut spec, import.spec.clone());
+                spec.save(&path)?;
+                "Merged into existing flow"
+            }
+            true => {
+                import.spec.save(&path)?;
+                "Replaced existing flow"
+            }
+            false => {
+                import.spec.save(&path)?;
+                "Imported into"
+            }
+        };
+
+        println!("✅ {} {}: '{}' from {}", outcome, style(path.display()).green(), import.spec.info.title, file.display());
+        println!("   {} converted, {} skipped", style(import.converted.len()).green(), style(import.skipped.len()).yellow());
+        for route in &import.converted {
+            println!("   • {}", route);
+        }
+        for (request, reason) in &import.skipped {
+            println!("   ⚠️  {}: {}", request, style(reason).yellow());
+        }
+        if !import.variables.is_empty() {
+            println!(
+                "💡 Collection variables ({}) can be defined with {}",
+                import.variables.join(", "),
+                style("config env add <env> <key> <value>").green()
+            );
+        }
+        Ok(import)
+    }
+
+    /// Download an OpenAPI/Swagger document (JSON or YAML) and save it as `<name>.yaml`,
+    /// named after its title unless `name` is given. Revalidates against `cache` when given.
+    pub async fn import_url(&self, spec_url: &str, name: Option<&str>, cache: Option<&remote::SpecCache>) -> Result<OpenAPISpec, Box<dyn std::error::Error>> {
+        let fetched = remote::fetch_spec(&reqwest::Client::new(), spec_url, cache).await?;
+        let document = remote::parse_document(&fetched.body, fetched.content_type.as_deref())?;
+        let spec = remote::spec_from_document(document, spec_url)?;
+
+        let name = name.map(str::to_string).unwrap_or_else(|| flow_name(&spec.info.title));
+        fs::create_dir_all(&self.collections_dir)?;
+        let path = self.get_collection_path(&name);
+        spec.save(&path)?;
+
+        let operations: usize = spec.paths.values().map(|item| item.operations().count()).sum();
+        let source = if fetched.from_cache { " (not modified, cached copy)" } else { "" };
+        println!("✅ Imported '{}' from {}{} into {}", spec.info.title, spec_url, source, style(path.display()).green());
+        println!("   {} operation(s) across {} path(s)", style(operations).green(), spec.paths.len());
+        for server in &spec.servers {
+            println!("   🌐 {}", server.url);
+        }
+        Ok(spec)
+    }
+
+    /// Make `records` the mock examples of the operation on `path` in `flow`: its GET when it
+    /// has one, else its first operation. Returns the operation, e.g. `GET /users`.
+    pub fn store_mock_examples(&self, flow: &str, path: &str, records: &[serde_json::Value], description: &str) -> Result<String, Box<dyn std::error::Error>> {
+        self.store_operation_examples(flow, None, path, records, description)
+    }
+
+    /// [`Self::store_mock_examples`] for `method` on `path`, when given.
+    pub fn store_operation_examples(&self, flow: &str, method: Option<&str>, path: &str, records: &[serde_json::Value], description: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let spec_path = self.get_collection_path(flow);
+        let mut spec = OpenAPISpec::load(&spec_path).map_err(|e| format!("Could not load flow '{}': {}", flow, e))?;
+        let item = spec.paths.get_mut(path).ok_or_else(|| format!("No path {} in flow '{}'", path, flow))?;
+        let method = match method {
+            Some(method) => item.operations().map(|(m, _)| m.to_string()).find(|m| m.eq_ignore_ascii_case(method))
+                .ok_or_else(|| format!("No {} operation on {} in flow '{}'", method.to_uppercase(), path, flow))?,
+            None => item.get_operation().map(|(method, _)| method.to_string())
+                .ok_or_else(|| format!("{} in flow '{}' has no operations", path, flow))?,
+        };
+        let operation = item.operation_mut(&method).and_then(Option::as_mut).expect("method listed by operations()");
+        operation.mock_data = Some(MockDataConfig {
+            description: description.to_string(),
+            schema: None,
+            examples: Some(records.iter().map(serde_json::Value::to_string).collect()),
+        });
+        spec.save(&spec_path)?;
+        Ok(format!("{} {}", method, path))
+    }
+
+    /// Run the recording proxy in front of `target` until Ctrl+C, then save the traffic into
+    /// `<name>.yaml` (named after the target host unless given), extending it if it exists.
+    pub async fn record_proxy(&self, target: &str, port: u16, name: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let host = url::Url::parse(target).ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_else(|| target.split(['/', ':']).next().unwrap_or_default().to_string());
+        let name = name.map(str::to_string).unwrap_or_else(|| flow_name(&host));
+        fs::create_dir_all(&self.collections_dir)?;
+        let path = self.get_collection_path(&name);
+        let spec = if path.exists() {
+            println!("📂 Adding to existing flow {}", style(&name).green());
+            OpenAPISpec::load(&path)?
+        } else {
+            OpenAPISpec::new(&name)
+        };
+
+        RecordingProxy::new(target, spec)?.start(port, &path).await
+    }
+
     pub async fn list_collections(&self) -> Result<(), Box<dyn std::error::Error>> {
         for entry in fs::read_dir(&self.collections_dir)? {
             let entry = entry?;
@@ -585,29 +977,21 @@ impl CollectionManager {
         request: &(String, String, Option<String>),
         response: Option<String>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let (method, url, _body) = request;
+        let (method, url, body) = request;
         let spec_path = self.get_collection_path(flow);
         let mut spec = OpenAPISpec::load(&spec_path)?;
 
         // Parse URL and setup servers
-        let url = url::Url::parse(&url)?;
+        let exchange = Exchange {
+            method: method.to_uppercase(),
+            url: url.clone(),
+            request_body: body.clone(),
+            status: 200,
+            response_body: response.clone(),
+        };
+        let url = url::Url::parse(url)?;
         let base_url = format!("{}://{}", url.scheme(), url.host_str().unwrap_or("localhost"));
-        
-        // Extract path parameters and clean path
-        let path_segments: Vec<&str> = url.path().split('/').collect();
-        let mut path_params = Vec::new();
-        let clean_path = path_segments.iter().enumerate()
-            .map(|(i, segment)| {
-                if segment.parse::<i64>().is_ok() {
-                    let param_name = if i == path_segments.len() - 1 { "id" } else { &format!("id_{}", i) };
-                    path_params.push(param_name.to_string());
-                    format!("{{{}}}", param_name)
-                } else {
-                    segment.to_string()
-                }
-            })
-            .collect::<Vec<String>>()
-            .join("/");
+        let (clean_path, _) = template_path(url.path());
 
         // Update servers
         if !spec.servers.iter().any(|s| s.url == base_url) {
@@ -615,6 +999,7 @@ impl CollectionManager {
             spec.servers.push(Server {
                 url: base_url,
                 description: Some("API Server".to_string()),
+                ..Default::default()
             });
         }
 
@@ -636,8 +1021,14 @@ impl CollectionManager {
             response.as_deref().unwrap_or("{}")
         );
 
-        let doc_response = self.get_ai_response(&doc_prompt).await?;
-        let (summary, description) = Self::parse_ai_doc_response(&doc_response)?;
+        let offline = self.config.is_offline();
+        let (summary, description) = if offline {
+            print_ai_skipped("AI documentation and mock data");
+            (format!("{} {}", method.to_uppercase(), clean_path), "Saved from a NUTS session".to_string())
+        } else {
+            let doc_response = self.get_ai_response(&doc_prompt).await?;
+            Self::parse_ai_doc_response(&doc_response)?
+        };
 
         // Generate mock data
         let mock_prompt = format!(
@@ -656,95 +1047,33 @@ impl CollectionManager {
             response.as_deref().unwrap_or("{}")
         );
 
-        let mock_response = self.get_ai_response(&mock_prompt).await?;
-        let mock_examples = Self::parse_mock_examples(&mock_response)?;
+        let mock_examples = if offline {
+            // The recorded response is the only example we have
+            response.iter()
+                .filter(|r| serde_json::from_str::<serde_json::Value>(r).is_ok())
+                .cloned()
+                .collect()
+        } else {
+            let mock_response = self.get_ai_response(&mock_prompt).await?;
+            Self::parse_mock_examples(&mock_response)?
+        };
 
         // Create operation with all the generated content
-        let operation = Operation {
-            summary: Some(summary),
-            description: Some(description),
-            parameters: if !path_params.is_empty() {
-                Some(path_params.iter().map(|param| Parameter {
-                    name: param.to_string(),
-                    in_: "path".to_string(),
-                    description: Some(format!("Path parameter {}", param)),
-                    required: true,
-                    schema: Schema {
-                        schema_type: "integer".to_string(),
-                        format: Some("int64".to_string()),
-                        properties: None,
-                        items: None,
-                    },
-                }).collect())
-            } else {
-                None
-            },
-            responses: {
-                let mut responses = HashMap::new();
-                if let Some(resp) = response {
-                    if let Ok(json) = serde_json::from_str::<serde_json::Value>(&resp) {
-                        responses.insert("200".to_string(), Response {
-                            description: "Successful response".to_string(),
-                            content: Some({
-                                let mut content = HashMap::new();
-                                content.insert("application/json".to_string(), MediaType {
-                                    schema: Schema {
-                                        schema_type: "object".to_string(),
-                                        properties: None,
-                                        items: None,
-                                        format: None,
-                                    },
-                                    example: Some(json),
-                                });
-                                content
-                            }),
-                        });
-                    }
-                }
-                responses
-            },
-            mock_data: Some(MockDataConfig {
-                description: "AI-generated mock responses".to_string(),
-                schema: None,
-                examples: Some(mock_examples),
-            }),
-            ..Default::default()
-        };
+        let (_, mut operation) = record::operation(&exchange, summary, description, mock_examples)?;
+        if let Some(mock_data) = operation.mock_data.as_mut().filter(|_| !offline) {
+            mock_data.description = "AI-generated mock responses".to_string();
+        }
 
         // Add operation to path item
         let path_item = spec.paths.entry(clean_path.clone()).or_insert(PathItem::new());
-        match method.to_uppercase().as_str() {
-            "GET" => path_item.get = Some(operation),
-            "POST" => path_item.post = Some(operation),
-            "PUT" => path_item.put = Some(operation),
-            "DELETE" => path_item.delete = Some(operation),
-            "PATCH" => path_item.patch = Some(operation),
-            _ => return Err("Unsupported HTTP method".into()),
-        }
+        *path_item.operation_mut(method).ok_or_else(|| format!("Unsupported HTTP method: {}", method))? = Some(operation);
 
         spec.save(&spec_path)?;
         println!("✅ Saved {} {} to flow {} with documentation and mock data", method, url, flow);
         Ok(())
     }
     async fn get_ai_response(&self, prompt: &str) -> Result<String, Box<dyn std::error::Error>> {
-        let messages = vec![Message {
-            role: Role::User,
-            content: vec![ContentBlock::Text { text: prompt.into() }]
-        }];
-
-        let request = MessagesRequestBuilder::default()
-            .messages(messages)
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(2000_usize)
-            .build()?;
-
-        let response = self.ai_client.messages(request).await?;
-        
-        if let Some(ContentBlock::Text { text }) = response.content.first() {
-            Ok(text.clone())
-        } else {
-            Err("No response from AI".into())
-        }
+        self.ai_client()?.ask(prompt).await
     }
 
     fn parse_ai_doc_response(response: &str) -> Result<(String, String), Box<dyn std::error::Error>> {
@@ -765,14 +1094,14 @@ impl CollectionManager {
 // Add a fallback for when AI is not available
 async fn run_single_endpoint_test(
     &self,
+    perf: &PerfCommand,
     endpoint: &str,
     method: &str,
     users: u32,
     duration: Duration,
     base_url: &str
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<MetricsSummary, Box<dyn std::error::Error>> {
     println!("Running single endpoint test...");
-    let perf = PerfCommand::new(&self.config);
     let url = if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
         endpoint.to_string()
     } else {
@@ -783,11 +1112,208 @@ async fn run_single_endpoint_test(
         users,
         duration,
         method,
-        None
+        None,
+        &HashMap::new()
     ).await
 }
 
     pub fn get_collections_dir(&self) -> PathBuf {
         self.collections_dir.clone()
     }
-}
\ No newline at end of file
+}
+
+/// Operations of a flow in the order `flow run --all` calls them. Those with an `x-order` come
+/// first, lowest first; the rest follow the CRUD lifecycle: creates (POST), collection reads,
+/// reads by id (path ends in a `{param}`), updates (PUT, PATCH), then deletes. Ties go to the
+/// shorter path, then alphabetically.
+pub fn run_order(spec: &OpenAPISpec) -> Vec<(&str, &str, &Operation)> {
+    let stage = |path: &str, method: &str| match method {
+        "POST" => 0,
+        "GET" if !path.trim_end_matches('/').ends_with('}') => 1,
+        "GET" => 2,
+        "PUT" | "PATCH" => 3,
+        _ => 4,
+    };
+    let mut order: Vec<_> = spec.paths.iter()
+        .flat_map(|(path, item)| item.operations().map(move |(method, operation)| (path.as_str(), method, operation)))
+        .collect();
+    order.sort_by_key(|(path, method, operation)| {
+        (operation.order.is_none(), operation.order, stage(path, method), path.matches('/').count(), *path, *method)
+    });
+    order
+}
+
+/// Whether `status` is among the operation's documented responses (`201`, `2XX`, `default`).
+/// Operations without any only need a status below 400.
+fn expected_status(operation: &Operation, status: u16) -> bool {
+    if operation.responses.is_empty() {
+        return status < 400;
+    }
+    let code = status.to_string();
+    operation.responses.keys().any(|key| {
+        let key = key.to_uppercase();
+        key == "DEFAULT" || key == code || (key.len() == 3 && key.ends_with("XX") && key.as_bytes()[0] == code.as_bytes()[0])
+    })
+}
+
+/// Outcome of one step of `flow run --all`.
+#[derive(Debug)]
+pub struct RunStep {
+    pub endpoint: String,
+    /// `None` when the request itself failed.
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    /// Unexpected status, budget or schema violations, or why the request failed.
+    pub failures: Vec<String>,
+}
+
+/// One row per step in the order they ran.
+pub fn run_table(steps: &[RunStep]) -> String {
+    let width = steps.iter().map(|s| s.endpoint.chars().count()).max().unwrap_or(0).max("ENDPOINT".len());
+    let mut table = format!("{:<width$}  {:>6}  {:>8}  {}\n", "ENDPOINT", "STATUS", "LATENCY", "RESULT");
+    for step in steps {
+        let status = step.status.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string());
+        let result = if step.failures.is_empty() { "PASS".to_string() } else { format!("FAIL: {}", step.failures.join("; ")) };
+        table.push_str(&format!("{:<width$}  {:>6}  {:>8}  {}\n", step.endpoint, status, format!("{}ms", step.latency_ms), result));
+    }
+    table
+}
+
+/// Outcome of calling one endpoint during `flow check`.
+#[derive(Debug)]
+pub struct BudgetCheck {
+    pub endpoint: String,
+    /// `None` when the request itself failed.
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    pub budget: Option<Budget>,
+    pub failures: Vec<String>,
+}
+
+/// Actual vs budget for every endpoint, one row each.
+pub fn budget_table(checks: &[BudgetCheck]) -> String {
+    let width = checks.iter().map(|c| c.endpoint.chars().count()).max().unwrap_or(0).max("ENDPOINT".len());
+    let mut table = format!("{:<width$}  {:>12}  {:>16}  {}\n", "ENDPOINT", "STATUS", "LATENCY", "RESULT");
+    for check in checks {
+        let status = check.status.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string());
+        let max_status = check.budget.as_ref().and_then(|b| b.max_status);
+        let max_latency = check.budget.as_ref().and_then(|b| b.max_latency_ms);
+        let status = match max_status {
+            Some(max) => format!("{} / ≤{}", status, max),
+            None => status,
+        };
+        let latency = match max_latency {
+            Some(max) => format!("{}ms / {}ms", check.latency_ms, max),
+            None => format!("{}ms", check.latency_ms),
+        };
+        let result = if !check.failures.is_empty() {
+            format!("FAIL: {}", check.failures.join("; "))
+        } else if check.budget.is_none() {
+            "no budget".to_string()
+        } else {
+            "PASS".to_string()
+        };
+        table.push_str(&format!("{:<width$}  {:>12}  {:>16}  {}\n", check.endpoint, status, latency, result));
+    }
+    table
+}
+
+/// Outcome of calling one endpoint during `flow validate`.
+#[derive(Debug)]
+pub struct SchemaCheck {
+    pub endpoint: String,
+    /// `None` when the request itself failed.
+    pub status: Option<u16>,
+    /// Whether the flow declares a JSON schema for the status received.
+    pub schema: bool,
+    /// Violations as `$.path: message`, or why the response couldn't be checked.
+    pub failures: Vec<String>,
+}
+
+/// One row per endpoint, each violation on its own line below it.
+pub fn schema_table(checks: &[SchemaCheck]) -> String {
+    check_table(checks.iter().map(|check| {
+        let result = match (check.failures.len(), check.schema) {
+            (0, true) => "PASS".to_string(),
+            (0, false) => "no schema".to_string(),
+            (n, true) => format!("FAIL: {} violation(s)", n),
+            (_, false) => "FAIL".to_string(),
+        };
+        (check.endpoint.as_str(), check.status, result, check.failures.clone())
+    }))
+}
+
+/// [`schema_table`] for `flow contract`: each violation is listed with its severity.
+pub fn contract_table(checks: &[ContractCheck]) -> String {
+    check_table(checks.iter().map(|check| {
+        let result = match check.worst() {
+            None => "PASS".to_string(),
+            Some(Severity::High) => format!("FAIL: {} violation(s)", check.violations.len()),
+            Some(_) => format!("WARN: {} violation(s)", check.violations.len()),
+        };
+        let mut violations = check.violations.clone();
+        violations.sort_by_key(|v| std::cmp::Reverse(v.severity));
+        (check.endpoint.as_str(), check.status, result, violations.iter().map(ToString::to_string).collect())
+    }))
+}
+
+/// Rows of `(endpoint, status, result, details)`, the details indented below their row.
+fn check_table<'a>(rows: impl Iterator<Item = (&'a str, Option<u16>, String, Vec<String>)>) -> String {
+    let rows: Vec<_> = rows.collect();
+    let width = rows.iter().map(|row| row.0.chars().count()).max().unwrap_or(0).max("ENDPOINT".len());
+    let mut table = format!("{:<width$}  {:>6}  {}\n", "ENDPOINT", "STATUS", "RESULT");
+    for (endpoint, status, result, details) in rows {
+        let status = status.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string());
+        table.push_str(&format!("{:<width$}  {:>6}  {}\n", endpoint, status, result));
+        for detail in details {
+            table.push_str(&format!("    • {}\n", detail));
+        }
+    }
+    table
+}
+
+/// Perf results for one operation of a flow.
+#[derive(Debug)]
+pub struct EndpointPerf {
+    pub method: String,
+    pub path: String,
+    pub summary: MetricsSummary,
+}
+
+/// Comparative table of per-endpoint perf results, sorted by p95 latency (slowest first).
+pub fn endpoint_perf_table(results: &mut [EndpointPerf]) -> String {
+    results.sort_by_key(|r| std::cmp::Reverse(r.summary.p95_latency));
+
+    let endpoints: Vec<String> = results.iter().map(|r| format!("{} {}", r.method, r.path)).collect();
+    let width = endpoints.iter().map(|e| e.chars().count()).max().unwrap_or(0).max("ENDPOINT".len());
+
+    let mut table = format!(
+        "{:<width$}  {:>8}  {:>7}  {:>8}  {:>8}  {:>8}  {:>8}\n",
+        "ENDPOINT", "REQUESTS", "ERRORS", "AVG", "P95", "P99", "PEAK RPS"
+    );
+    for (endpoint, result) in endpoints.iter().zip(results.iter()) {
+        let summary = &result.summary;
+        table.push_str(&format!(
+            "{:<width$}  {:>8}  {:>6.1}%  {:>6}ms  {:>6}ms  {:>6}ms  {:>8}\n",
+            endpoint,
+            summary.total_requests,
+            summary.error_rate * 100.0,
+            summary.avg_latency.as_millis(),
+            summary.p95_latency.as_millis(),
+            summary.p99_latency.as_millis(),
+            summary.peak_rps,
+        ));
+    }
+    table
+}
+
+/// File-friendly flow name for an imported collection: `My API (v2)` -> `my-api-v2`.
+fn flow_name(title: &str) -> String {
+    let name = title
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if name.is_empty() { "imported".to_string() } else { name }
+}
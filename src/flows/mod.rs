@@ -1,33 +1,67 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use crate::hooks::HookConfig;
 
+pub mod contract;
+pub mod docs;
+pub mod export;
 pub mod manager;
+pub mod postman;
+pub mod record;
+pub mod remote;
+pub mod validate;
+pub mod variables;
 pub use manager::CollectionManager;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Fields we don't model explicitly (3.1 `webhooks`, `components`, `x-*` extensions, ...)
+/// are kept here so they survive a load/save round-trip.
+pub type Extras = BTreeMap<String, serde_json::Value>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenAPISpec {
+    /// Version string as found in the source document; never rewritten on save.
     pub openapi: String,
     pub info: Info,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub servers: Vec<Server>,
+    #[serde(default)]
     pub paths: HashMap<String, PathItem>,
+    #[serde(rename = "x-nuts-hooks", default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<HookConfig>,
+    /// `{{name}}` values for every step, over the active environment's; set with `flow vars`.
+    #[serde(rename = "x-variables", default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Info {
+    #[serde(default)]
     pub title: String,
+    #[serde(default)]
     pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Server {
     pub url: String,
+    /// What `flow run --server` picks this server by, e.g. `staging`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PathItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub get: Option<Operation>,
@@ -40,10 +74,22 @@ pub struct PathItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub patch: Option<Operation>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub head: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Operation>,
+    /// Methods OpenAPI has no field for (`PURGE`, `PROPFIND`), by upper-case name. Empty
+    /// slots left by [`PathItem::operation_mut`] are not written.
+    #[serde(rename = "x-methods", default, skip_serializing_if = "no_custom_methods", serialize_with = "serialize_custom_methods")]
+    pub custom: BTreeMap<String, Option<Operation>>,
+    #[serde(alias = "x-mock-data", skip_serializing_if = "Option::is_none")]
     pub mock_data: Option<MockDataConfig>,
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Operation {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub summary: Option<String>,
@@ -51,61 +97,205 @@ pub struct Operation {
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parameters: Option<Vec<Parameter>>,
-    #[serde(rename = "requestBody", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "requestBody", alias = "request_body", skip_serializing_if = "Option::is_none")]
     pub request_body: Option<RequestBody>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub responses: HashMap<String, Response>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub security: Option<Vec<HashMap<String, Vec<String>>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(alias = "x-mock-data", skip_serializing_if = "Option::is_none")]
     pub mock_data: Option<MockDataConfig>,
+    /// Variables to capture from this operation's JSON response: name -> path (`$.data.token`).
+    #[serde(rename = "x-extract", default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extract: BTreeMap<String, String>,
+    /// Service-level budget checked by `flow check`.
+    #[serde(rename = "x-budget", default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<Budget>,
+    /// Position in `flow run --all`; operations with one run first, lowest first.
+    #[serde(rename = "x-order", default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<i64>,
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+/// Limits an endpoint must stay within: `x-budget: { max_latency_ms: 300, max_status: 299 }`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Budget {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_latency_ms: Option<u64>,
+    /// Highest acceptable status code, e.g. 299 for "any 2xx".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_status: Option<u16>,
+}
+
+impl Budget {
+    /// Reasons an observed response breaks this budget; empty when it passes.
+    pub fn violations(&self, status: u16, latency_ms: u64) -> Vec<String> {
+        let mut violations = Vec::new();
+        if let Some(max) = self.max_latency_ms.filter(|max| latency_ms > *max) {
+            violations.push(format!("latency {}ms exceeds {}ms", latency_ms, max));
+        }
+        if let Some(max) = self.max_status.filter(|max| status > *max) {
+            violations.push(format!("status {} exceeds {}", status, max));
+        }
+        violations
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Parameter {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub name: String,
-    #[serde(rename = "in")]
+    #[serde(rename = "in", default, skip_serializing_if = "String::is_empty")]
     pub in_: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Written as `required: null` by older flow files, which reads as not required.
+    #[serde(default, deserialize_with = "null_as_false", skip_serializing_if = "is_false")]
     pub required: bool,
+    #[serde(default, skip_serializing_if = "Schema::is_empty")]
     pub schema: Schema,
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RequestBody {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub required: Option<bool>,
+    #[serde(default)]
     pub content: HashMap<String, MediaType>,
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct MediaType {
+    #[serde(default, skip_serializing_if = "Schema::is_empty")]
     pub schema: Schema,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub example: Option<serde_json::Value>,
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Response {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub description: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<HashMap<String, MediaType>>,
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+fn null_as_false<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Ok(Option::<bool>::deserialize(deserializer)?.unwrap_or(false))
+}
+
+/// JSON Schema `type`: a single name (3.0) or a list of names (3.1, e.g. `["string", "null"]`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum SchemaType {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl Default for SchemaType {
+    fn default() -> Self {
+        SchemaType::Single(String::new())
+    }
+}
+
+impl From<&str> for SchemaType {
+    fn from(value: &str) -> Self {
+        SchemaType::Single(value.to_string())
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+impl SchemaType {
+    pub fn is_empty(&self) -> bool {
+        match self {
+            SchemaType::Single(name) => name.is_empty(),
+            SchemaType::Multiple(names) => names.is_empty(),
+        }
+    }
+
+    /// The first non-null type name, or "" when the schema has no type.
+    pub fn primary(&self) -> &str {
+        match self {
+            SchemaType::Single(name) => name,
+            SchemaType::Multiple(names) => names.iter()
+                .map(String::as_str)
+                .find(|name| *name != "null")
+                .unwrap_or(""),
+        }
+    }
+
+    pub fn allows_null(&self) -> bool {
+        match self {
+            SchemaType::Single(name) => name == "null",
+            SchemaType::Multiple(names) => names.iter().any(|name| name == "null"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Schema {
-    #[serde(rename = "type")]
-    pub schema_type: String,
+    #[serde(rename = "type", default, skip_serializing_if = "SchemaType::is_empty")]
+    pub schema_type: SchemaType,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub format: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub properties: Option<HashMap<String, Schema>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub items: Option<Box<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<serde_json::Value>,
+    /// 3.1 / JSON Schema `examples` array.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub examples: Option<Vec<serde_json::Value>>,
+    #[serde(rename = "const", skip_serializing_if = "Option::is_none")]
+    pub const_value: Option<serde_json::Value>,
+    #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
+    pub enum_values: Option<Vec<serde_json::Value>>,
+    /// Properties an object must have.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required: Vec<String>,
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Schema {
+    pub fn is_empty(&self) -> bool {
+        self.schema_type.is_empty()
+            && self.format.is_none()
+            && self.properties.is_none()
+            && self.items.is_none()
+            && self.example.is_none()
+            && self.examples.is_none()
+            && self.const_value.is_none()
+            && self.enum_values.is_none()
+            && self.required.is_empty()
+            && self.extras.is_empty()
+    }
+
+    /// Nullable either through a 3.1 type list or the 3.0 `nullable: true` keyword.
+    pub fn is_nullable(&self) -> bool {
+        self.schema_type.allows_null()
+            || self.extras.get("nullable").and_then(|v| v.as_bool()).unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MockDataConfig {
     pub description: String,
     pub schema: Option<String>,
@@ -121,13 +311,34 @@ impl OpenAPISpec {
                 title: name.to_string(),
                 version: "1.0.0".to_string(),
                 description: Some(format!("API flow for {}", name)),
+                ..Default::default()
             },
             servers: vec![Server {
                 url: "http://localhost:3000".to_string(),
                 description: Some("Default server".to_string()),
+                ..Default::default()
             }],
             paths: HashMap::new(),
+            hooks: None,
+            variables: BTreeMap::new(),
+            extras: Extras::new(),
+        }
+    }
+
+    /// Where requests go: the server called `name`, or without one the first server
+    /// (`http://localhost:3000` when there is none).
+    pub fn server_url(&self, name: Option<&str>) -> Result<&str, String> {
+        let Some(name) = name else {
+            return Ok(self.servers.first().map_or("http://localhost:3000", |s| s.url.as_str()));
+        };
+        if let Some(server) = self.servers.iter().find(|s| s.name.as_deref() == Some(name)) {
+            return Ok(&server.url);
         }
+        let names: Vec<&str> = self.servers.iter().filter_map(|s| s.name.as_deref()).collect();
+        Err(match names.as_slice() {
+            [] => format!("No server named '{}': none of this flow's servers has a name", name),
+            _ => format!("No server named '{}'; this flow has {}", name, names.join(", ")),
+        })
     }
 
     #[allow(dead_code)]
@@ -148,24 +359,58 @@ impl OpenAPISpec {
 impl PathItem {
     #[allow(dead_code)]
     pub fn new() -> Self {
-        Self {
-            get: None,
-            post: None,
-            put: None,
-            delete: None,
-            patch: None,
-            mock_data: None,
-        }
+        Self::default()
     }
 
+    /// The operation a path runs by default: the first of [`PathItem::operations`].
     #[allow(dead_code)]
-    pub fn get_operation(&self) -> Option<(&'static str, &Operation)> {
-        if let Some(op) = &self.get { return Some(("GET", op)) }
-        if let Some(op) = &self.post { return Some(("POST", op)) }
-        if let Some(op) = &self.put { return Some(("PUT", op)) }
-        if let Some(op) = &self.delete { return Some(("DELETE", op)) }
-        if let Some(op) = &self.patch { return Some(("PATCH", op)) }
-        None
+    pub fn get_operation(&self) -> Option<(&str, &Operation)> {
+        self.operations().next()
+    }
+
+    /// Every operation defined on this path: GET, POST, PUT, DELETE, PATCH, HEAD, OPTIONS,
+    /// TRACE, then custom methods by name.
+    pub fn operations(&self) -> impl Iterator<Item = (&str, &Operation)> {
+        [
+            ("GET", &self.get),
+            ("POST", &self.post),
+            ("PUT", &self.put),
+            ("DELETE", &self.delete),
+            ("PATCH", &self.patch),
+            ("HEAD", &self.head),
+            ("OPTIONS", &self.options),
+            ("TRACE", &self.trace),
+        ]
+        .into_iter()
+        .chain(self.custom.iter().map(|(method, op)| (method.as_str(), op)))
+        .filter_map(|(method, op)| op.as_ref().map(|op| (method, op)))
+    }
+
+    /// The slot holding the operation for `method` (any case), or `None` when `method` is not a
+    /// valid HTTP method.
+    pub fn operation_mut(&mut self, method: &str) -> Option<&mut Option<Operation>> {
+        let method = method.to_uppercase();
+        match method.as_str() {
+            "GET" => Some(&mut self.get),
+            "POST" => Some(&mut self.post),
+            "PUT" => Some(&mut self.put),
+            "DELETE" => Some(&mut self.delete),
+            "PATCH" => Some(&mut self.patch),
+            "HEAD" => Some(&mut self.head),
+            "OPTIONS" => Some(&mut self.options),
+            "TRACE" => Some(&mut self.trace),
+            "CONNECT" => None,
+            _ if crate::commands::call::is_method_token(&method) => Some(self.custom.entry(method).or_default()),
+            _ => None,
+        }
     }
 }
 
+fn no_custom_methods(custom: &BTreeMap<String, Option<Operation>>) -> bool {
+    custom.values().all(Option::is_none)
+}
+
+fn serialize_custom_methods<S: serde::Serializer>(custom: &BTreeMap<String, Option<Operation>>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(custom.iter().filter_map(|(method, op)| op.as_ref().map(|op| (method, op))))
+}
+
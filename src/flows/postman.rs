@@ -0,0 +1,391 @@
+//! Postman collection (v2.0 / v2.1) import.
+//!
+//! Requests become OpenAPI operations: folders turn into tags, headers and query strings into
+//! parameters with examples, and raw JSON bodies into request body examples. `{{variables}}`
+//! are left untouched so environments can resolve them when the flow runs. Requests we can't
+//! represent (GraphQL, file uploads, duplicate routes, ...) are skipped and reported.
+
+use super::{Info, MediaType, OpenAPISpec, Operation, Parameter, RequestBody, Server};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Result of converting a collection.
+pub struct PostmanImport {
+    pub spec: OpenAPISpec,
+    /// `METHOD /path` of every converted request, in collection order.
+    pub converted: Vec<String>,
+    /// Request name (with its folder path) and the reason it was skipped.
+    pub skipped: Vec<(String, String)>,
+    /// Collection-level variables, which usually need an environment to resolve.
+    pub variables: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Collection {
+    info: CollectionInfo,
+    #[serde(default)]
+    item: Vec<Item>,
+    #[serde(default)]
+    variable: Vec<KeyValue>,
+}
+
+#[derive(Deserialize)]
+struct CollectionInfo {
+    name: String,
+    description: Option<Description>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Description {
+    Text(String),
+    Object { content: Option<String> },
+}
+
+impl Description {
+    fn text(&self) -> Option<String> {
+        match self {
+            Description::Text(text) => Some(text.clone()),
+            Description::Object { content } => content.clone(),
+        }
+        .filter(|text| !text.trim().is_empty())
+    }
+}
+
+#[derive(Deserialize)]
+struct Item {
+    #[serde(default)]
+    name: String,
+    /// Present on folders.
+    item: Option<Vec<Item>>,
+    request: Option<RequestDef>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RequestDef {
+    Url(String),
+    Full(Box<Request>),
+}
+
+#[derive(Deserialize)]
+struct Request {
+    method: Option<String>,
+    url: Option<UrlDef>,
+    #[serde(default)]
+    header: Vec<KeyValue>,
+    body: Option<Body>,
+    description: Option<Description>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum UrlDef {
+    Raw(String),
+    Parts(UrlParts),
+}
+
+#[derive(Deserialize)]
+struct UrlParts {
+    raw: Option<String>,
+    protocol: Option<String>,
+    host: Option<StringOrList>,
+    port: Option<String>,
+    path: Option<StringOrList>,
+    #[serde(default)]
+    query: Vec<KeyValue>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl StringOrList {
+    fn join(&self, separator: &str) -> String {
+        match self {
+            StringOrList::One(value) => value.clone(),
+            StringOrList::Many(values) => values.join(separator),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct KeyValue {
+    #[serde(default)]
+    key: String,
+    #[serde(default)]
+    value: Value,
+    #[serde(default)]
+    disabled: bool,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+impl KeyValue {
+    fn value_string(&self) -> String {
+        match &self.value {
+            Value::Null => String::new(),
+            Value::String(value) => value.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Body {
+    mode: Option<String>,
+    raw: Option<String>,
+    #[serde(default)]
+    urlencoded: Vec<KeyValue>,
+    #[serde(default)]
+    formdata: Vec<KeyValue>,
+    options: Option<Value>,
+}
+
+/// Where a request URL points: server base, OpenAPI path and query parameters.
+struct ParsedUrl {
+    base: String,
+    path: String,
+    path_params: Vec<String>,
+    query: Vec<(String, String)>,
+}
+
+/// Convert a Postman collection document into an OpenAPI flow.
+pub fn convert(json: &str) -> Result<PostmanImport, Box<dyn std::error::Error>> {
+    let collection: Collection = serde_json::from_str(json)
+        .map_err(|e| format!("Not a Postman v2 collection: {}", e))?;
+
+    let mut spec = OpenAPISpec::new(&collection.info.name);
+    spec.info = Info {
+        title: collection.info.name.clone(),
+        version: "1.0.0".to_string(),
+        description: collection.info.description.as_ref().and_then(Description::text),
+        ..Default::default()
+    };
+    spec.servers.clear();
+
+    let mut import = PostmanImport {
+        spec,
+        converted: Vec::new(),
+        skipped: Vec::new(),
+        variables: collection.variable.iter()
+            .filter(|v| !v.key.is_empty())
+            .map(|v| v.key.clone())
+            .collect(),
+    };
+    convert_items(&collection.item, &[], &mut import);
+    Ok(import)
+}
+
+fn convert_items(items: &[Item], folders: &[String], import: &mut PostmanImport) {
+    for item in items {
+        if let Some(children) = &item.item {
+            let mut path = folders.to_vec();
+            path.push(item.name.clone());
+            convert_items(children, &path, import);
+            continue;
+        }
+
+        let label = folders.iter().chain(std::iter::once(&item.name)).cloned().collect::<Vec<_>>().join(" / ");
+        let Some(request) = &item.request else {
+            import.skipped.push((label, "no request".to_string()));
+            continue;
+        };
+        match convert_request(item, request, folders, import) {
+            Ok(route) => import.converted.push(route),
+            Err(reason) => import.skipped.push((label, reason)),
+        }
+    }
+}
+
+/// Add one request to the spec, returning `METHOD /path` or the reason it can't be represented.
+fn convert_request(item: &Item, request: &RequestDef, folders: &[String], import: &mut PostmanImport) -> Result<String, String> {
+    let (method, url, headers, body, description) = match request {
+        RequestDef::Url(url) => ("GET".to_string(), Some(url.clone()), &[][..], None, None),
+        RequestDef::Full(request) => (
+            request.method.as_deref().unwrap_or("GET").to_uppercase(),
+            request.url.as_ref().and_then(raw_url),
+            &request.header[..],
+            request.body.as_ref(),
+            request.description.as_ref().and_then(Description::text),
+        ),
+    };
+    if !matches!(method.as_str(), "GET" | "POST" | "PUT" | "DELETE" | "PATCH") {
+        return Err(format!("{} requests are not supported in flows", method));
+    }
+    let url = url.filter(|url| !url.trim().is_empty()).ok_or("request has no URL")?;
+    let mut parsed = parse_url(&url);
+    // Structured query entries carry `disabled` flags the raw URL doesn't
+    if let RequestDef::Full(full) = request {
+        if let Some(UrlDef::Parts(parts)) = &full.url {
+            if !parts.query.is_empty() {
+                parsed.query = parts.query.iter()
+                    .filter(|q| !q.disabled)
+                    .map(|q| (q.key.clone(), q.value_string()))
+                    .collect();
+            }
+        }
+    }
+
+    let mut operation = Operation {
+        summary: Some(item.name.clone()).filter(|name| !name.is_empty()),
+        description,
+        tags: (!folders.is_empty()).then(|| folders.to_vec()),
+        ..Default::default()
+    };
+
+    if let Some(body) = body {
+        operation.request_body = convert_body(body)?;
+    }
+
+    let mut parameters: Vec<Parameter> = Vec::new();
+    for name in &parsed.path_params {
+        parameters.push(parameter(name, "path", None, true));
+    }
+    for (name, value) in &parsed.query {
+        parameters.push(parameter(name, "query", Some(value), false));
+    }
+    for header in headers.iter().filter(|h| !h.disabled && !h.key.is_empty()) {
+        parameters.push(parameter(&header.key, "header", Some(&header.value_string()), false));
+    }
+    operation.parameters = (!parameters.is_empty()).then_some(parameters);
+
+    let path_item = import.spec.paths.entry(parsed.path.clone()).or_default();
+    let slot = match method.as_str() {
+        "GET" => &mut path_item.get,
+        "POST" => &mut path_item.post,
+        "PUT" => &mut path_item.put,
+        "DELETE" => &mut path_item.delete,
+        _ => &mut path_item.patch,
+    };
+    if slot.is_some() {
+        return Err(format!("duplicate {} {}", method, parsed.path));
+    }
+    *slot = Some(operation);
+
+    if !parsed.base.is_empty() && !import.spec.servers.iter().any(|s| s.url == parsed.base) {
+        import.spec.servers.push(Server { url: parsed.base.clone(), ..Default::default() });
+    }
+    Ok(format!("{} {}", method, parsed.path))
+}
+
+fn convert_body(body: &Body) -> Result<Option<RequestBody>, String> {
+    let (content_type, example) = match body.mode.as_deref() {
+        Some("raw") => {
+            let raw = body.raw.clone().unwrap_or_default();
+            if raw.trim().is_empty() {
+                return Ok(None);
+            }
+            let language = body.options.as_ref()
+                .and_then(|o| o.pointer("/raw/language"))
+                .and_then(Value::as_str)
+                .unwrap_or("");
+            match serde_json::from_str::<Value>(&raw) {
+                Ok(json) => ("application/json", json),
+                Err(_) if language == "xml" => ("application/xml", Value::String(raw)),
+                Err(_) => ("text/plain", Value::String(raw)),
+            }
+        }
+        Some("urlencoded") => ("application/x-www-form-urlencoded", form_example(&body.urlencoded)),
+        Some("formdata") => {
+            if body.formdata.iter().any(|f| !f.disabled && f.kind.as_deref() == Some("file")) {
+                return Err("file upload bodies are not supported yet".to_string());
+            }
+            ("multipart/form-data", form_example(&body.formdata))
+        }
+        Some("graphql") => return Err("GraphQL requests are not supported yet".to_string()),
+        Some("file") => return Err("binary file bodies are not supported yet".to_string()),
+        _ => return Ok(None),
+    };
+
+    let mut content = HashMap::new();
+    content.insert(content_type.to_string(), MediaType { example: Some(example), ..Default::default() });
+    Ok(Some(RequestBody { content, ..Default::default() }))
+}
+
+fn form_example(fields: &[KeyValue]) -> Value {
+    Value::Object(
+        fields.iter()
+            .filter(|f| !f.disabled && !f.key.is_empty())
+            .map(|f| (f.key.clone(), Value::String(f.value_string())))
+            .collect(),
+    )
+}
+
+fn parameter(name: &str, location: &str, example: Option<&str>, required: bool) -> Parameter {
+    let mut parameter = Parameter {
+        name: name.to_string(),
+        in_: location.to_string(),
+        required,
+        ..Default::default()
+    };
+    if let Some(example) = example {
+        parameter.extras.insert("example".to_string(), Value::String(example.to_string()));
+    }
+    parameter
+}
+
+/// The request URL as a single string, rebuilding it from its parts when `raw` is missing.
+fn raw_url(url: &UrlDef) -> Option<String> {
+    match url {
+        UrlDef::Raw(raw) => Some(raw.clone()),
+        UrlDef::Parts(parts) => parts.raw.clone().or_else(|| {
+            let mut url = String::new();
+            if let Some(protocol) = &parts.protocol {
+                url.push_str(&format!("{}://", protocol));
+            }
+            url.push_str(&parts.host.as_ref()?.join("."));
+            if let Some(port) = &parts.port {
+                url.push_str(&format!(":{}", port));
+            }
+            if let Some(path) = &parts.path {
+                url.push('/');
+                url.push_str(path.join("/").trim_start_matches('/'));
+            }
+            Some(url)
+        }),
+    }
+}
+
+/// Split a Postman URL into server base and OpenAPI path. `:name` segments become `{name}`
+/// path parameters; `{{variables}}` are kept verbatim.
+fn parse_url(raw: &str) -> ParsedUrl {
+    let (without_query, query) = match raw.split_once('?') {
+        Some((url, query)) => (url, query),
+        None => (raw, ""),
+    };
+    let after_scheme = without_query.find("://").map(|i| i + 3).unwrap_or(0);
+    let (base, path) = match without_query[after_scheme..].find('/') {
+        Some(i) => without_query.split_at(after_scheme + i),
+        None => (without_query, ""),
+    };
+
+    let mut path_params = Vec::new();
+    let segments: Vec<String> = path.split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name) if !name.is_empty() => {
+                path_params.push(name.to_string());
+                format!("{{{}}}", name)
+            }
+            _ => segment.to_string(),
+        })
+        .collect();
+    let path = match segments.join("/") {
+        path if path.is_empty() => "/".to_string(),
+        path => path,
+    };
+
+    let query = query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (key.to_string(), value.to_string()),
+            None => (pair.to_string(), String::new()),
+        })
+        .collect();
+
+    ParsedUrl { base: base.to_string(), path, path_params, query }
+}
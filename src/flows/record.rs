@@ -0,0 +1,184 @@
+//! Turning observed traffic into flow operations: numeric path segments become `{id}`
+//! parameters, query strings become query parameters and JSON bodies become examples.
+//! Used by `save` and by the recording proxy.
+
+use super::{MediaType, MockDataConfig, OpenAPISpec, Operation, Parameter, RequestBody, Response, Schema, Server};
+use crate::capture::MAX_CAPTURED_BODY;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Most response examples kept per recorded operation.
+const MAX_RECORDED_EXAMPLES: usize = 5;
+
+/// One request/response pair to record.
+#[derive(Debug, Clone)]
+pub struct Exchange {
+    pub method: String,
+    pub url: String,
+    pub request_body: Option<String>,
+    pub status: u16,
+    pub response_body: Option<String>,
+}
+
+/// `/users/42/posts/7` -> (`/users/{id_2}/posts/{id}`, [`id_2`, `id`]).
+pub fn template_path(path: &str) -> (String, Vec<String>) {
+    let segments: Vec<&str> = path.split('/').collect();
+    let mut params = Vec::new();
+    let templated = segments.iter().enumerate()
+        .map(|(i, segment)| {
+            if segment.parse::<i64>().is_ok() {
+                let name = if i == segments.len() - 1 { "id".to_string() } else { format!("id_{}", i) };
+                let templated = format!("{{{}}}", name);
+                params.push(name);
+                templated
+            } else {
+                segment.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("/");
+    (templated, params)
+}
+
+/// Cut bodies over the capture limit, leaving a marker with the original size.
+pub fn truncate_body(body: &str) -> String {
+    if body.len() <= MAX_CAPTURED_BODY {
+        return body.to_string();
+    }
+    let mut end = MAX_CAPTURED_BODY;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…[truncated by NUTS: {} of {} bytes]", &body[..end], end, body.len())
+}
+
+/// JSON bodies become JSON examples; anything else is kept as (possibly truncated) text.
+fn example(body: &str) -> Option<Value> {
+    if body.trim().is_empty() {
+        return None;
+    }
+    if body.len() <= MAX_CAPTURED_BODY {
+        if let Ok(json) = serde_json::from_str::<Value>(body) {
+            return Some(json);
+        }
+    }
+    Some(Value::String(truncate_body(body)))
+}
+
+fn json_content(example: Value) -> HashMap<String, MediaType> {
+    let schema_type = match &example {
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+        _ => "string",
+    };
+    HashMap::from([("application/json".to_string(), MediaType {
+        schema: Schema { schema_type: schema_type.into(), ..Default::default() },
+        example: Some(example),
+        ..Default::default()
+    })])
+}
+
+/// Templated path of `exchange` and an operation describing it.
+pub fn operation(exchange: &Exchange, summary: String, description: String, mock_examples: Vec<String>) -> Result<(String, Operation), url::ParseError> {
+    let url = url::Url::parse(&exchange.url)?;
+    let (path, path_params) = template_path(url.path());
+
+    let mut parameters: Vec<Parameter> = path_params.iter().map(|param| Parameter {
+        name: param.to_string(),
+        in_: "path".to_string(),
+        description: Some(format!("Path parameter {}", param)),
+        required: true,
+        schema: Schema {
+            schema_type: "integer".into(),
+            format: Some("int64".to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    }).collect();
+    for (name, value) in url.query_pairs() {
+        if parameters.iter().any(|p| p.in_ == "query" && p.name == name) {
+            continue;
+        }
+        let mut parameter = Parameter {
+            name: name.to_string(),
+            in_: "query".to_string(),
+            schema: Schema { schema_type: "string".into(), ..Default::default() },
+            ..Default::default()
+        };
+        parameter.extras.insert("example".to_string(), Value::String(value.to_string()));
+        parameters.push(parameter);
+    }
+
+    let mut responses = HashMap::new();
+    if let Some(example) = exchange.response_body.as_deref().and_then(example) {
+        responses.insert(exchange.status.to_string(), Response {
+            description: if exchange.status < 400 { "Successful response" } else { "Error response" }.to_string(),
+            content: Some(json_content(example)),
+            ..Default::default()
+        });
+    }
+
+    let operation = Operation {
+        summary: Some(summary),
+        description: Some(description),
+        parameters: (!parameters.is_empty()).then_some(parameters),
+        request_body: exchange.request_body.as_deref().and_then(example).map(|example| RequestBody {
+            content: json_content(example),
+            ..Default::default()
+        }),
+        responses,
+        mock_data: Some(MockDataConfig {
+            description: "Recorded responses".to_string(),
+            schema: None,
+            examples: Some(mock_examples),
+        }),
+        ..Default::default()
+    };
+    Ok((path, operation))
+}
+
+/// Record `exchange` into `spec` without AI: the first exchange defines the operation, later ones
+/// add distinct JSON responses as mock examples. Returns the `METHOD /path` key of the endpoint.
+pub fn record(spec: &mut OpenAPISpec, exchange: &Exchange) -> Result<String, Box<dyn std::error::Error>> {
+    let url = url::Url::parse(&exchange.url)?;
+    let base_url = url.origin().ascii_serialization();
+    if !spec.servers.iter().any(|s| s.url == base_url) {
+        spec.servers.insert(0, Server {
+            url: base_url,
+            description: Some("Recorded API server".to_string()),
+            ..Default::default()
+        });
+    }
+
+    let method = exchange.method.to_uppercase();
+    let json_response = exchange.response_body.as_deref()
+        .filter(|body| body.len() <= MAX_CAPTURED_BODY && serde_json::from_str::<Value>(body).is_ok())
+        .map(str::to_string);
+    let (path, operation) = operation(
+        exchange,
+        format!("{} {}", method, template_path(url.path()).0),
+        "Recorded by the NUTS proxy".to_string(),
+        json_response.iter().cloned().collect(),
+    )?;
+
+    let key = format!("{} {}", method, path);
+    let slot = spec.paths.entry(path).or_default()
+        .operation_mut(&method)
+        .ok_or_else(|| format!("Unsupported HTTP method: {}", method))?;
+    match slot {
+        None => *slot = Some(operation),
+        Some(existing) => {
+            let examples = existing.mock_data.get_or_insert_with(|| MockDataConfig {
+                description: "Recorded responses".to_string(),
+                schema: None,
+                examples: None,
+            }).examples.get_or_insert_with(Vec::new);
+            if let Some(response) = json_response {
+                if examples.len() < MAX_RECORDED_EXAMPLES && !examples.contains(&response) {
+                    examples.push(response);
+                }
+            }
+        }
+    }
+    Ok(key)
+}
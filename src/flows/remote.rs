@@ -0,0 +1,206 @@
+//! OpenAPI / Swagger documents fetched over HTTP, for `flow from-url` and `discover`.
+//!
+//! Downloads are kept under `~/.nuts/cache/` as `<url hash>.body` next to a `.meta.json`
+//! holding the `ETag`/`Last-Modified` they came with. The next fetch of the same URL sends
+//! `If-None-Match`/`If-Modified-Since` and reuses the cached copy on `304 Not Modified`.
+
+use super::OpenAPISpec;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::path::PathBuf;
+
+/// A spec document as downloaded (or revalidated).
+#[derive(Debug, Clone)]
+pub struct FetchedSpec {
+    pub url: String,
+    pub body: String,
+    pub content_type: Option<String>,
+    /// The server answered `304 Not Modified` and the cached copy was used.
+    pub from_cache: bool,
+}
+
+/// What we remember about a cached download.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheMeta {
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+}
+
+/// Downloaded specs kept in `dir`, keyed by a hash of their URL.
+pub struct SpecCache {
+    dir: PathBuf,
+}
+
+impl SpecCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `~/.nuts/cache`.
+    pub fn default_dir() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(dirs::home_dir()
+            .ok_or("Could not find home directory")?
+            .join(".nuts")
+            .join("cache"))
+    }
+
+    /// 64-bit FNV-1a of the URL: stable across runs and builds, unlike `DefaultHasher`.
+    fn key(url: &str) -> String {
+        let hash = url.bytes().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+        format!("{:016x}", hash)
+    }
+
+    fn body_path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.body", Self::key(url)))
+    }
+
+    fn meta_path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.meta.json", Self::key(url)))
+    }
+
+    fn load(&self, url: &str) -> Option<(CacheMeta, String)> {
+        let meta: CacheMeta = serde_json::from_str(&std::fs::read_to_string(self.meta_path(url)).ok()?).ok()?;
+        let body = std::fs::read_to_string(self.body_path(url)).ok()?;
+        // A hash collision would hand back another URL's document
+        (meta.url == url).then_some((meta, body))
+    }
+
+    fn store(&self, meta: &CacheMeta, body: &str) -> Result<(), Box<dyn Error>> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.body_path(&meta.url), body)?;
+        std::fs::write(self.meta_path(&meta.url), serde_json::to_string_pretty(meta)?)?;
+        Ok(())
+    }
+}
+
+/// GET `url`, revalidating against `cache` when there is one. Anything but a success or a
+/// `304` for a cached copy is an error.
+pub async fn fetch_spec(client: &reqwest::Client, url: &str, cache: Option<&SpecCache>) -> Result<FetchedSpec, Box<dyn Error>> {
+    let cached = cache.and_then(|cache| cache.load(url));
+    let mut request = client.get(url);
+    if let Some((meta, _)) = &cached {
+        if let Some(etag) = &meta.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &meta.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some((meta, body)) = cached {
+            return Ok(FetchedSpec { url: url.to_string(), body, content_type: meta.content_type, from_cache: true });
+        }
+    }
+    if !status.is_success() {
+        return Err(format!("{} returned {}", url, status).into());
+    }
+
+    let header = |name| response.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let meta = CacheMeta {
+        url: url.to_string(),
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+        content_type: header(CONTENT_TYPE),
+    };
+    let body = response.text().await?;
+    if let Some(cache) = cache {
+        // A cache we can't write only costs a full download next time
+        if let Err(e) = cache.store(&meta, &body) {
+            tracing::debug!(target: "nuts::flows", %url, error = %e, "could not cache spec");
+        }
+    }
+    Ok(FetchedSpec { url: url.to_string(), body, content_type: meta.content_type, from_cache: false })
+}
+
+/// Parse a downloaded document, as JSON or YAML depending on its content type (or its first
+/// character when the type says neither).
+pub fn parse_document(text: &str, content_type: Option<&str>) -> Result<Value, Box<dyn Error>> {
+    let content_type = content_type.unwrap_or_default().to_ascii_lowercase();
+    let is_json = if content_type.contains("json") {
+        true
+    } else if content_type.contains("yaml") || content_type.contains("yml") {
+        false
+    } else {
+        text.trim_start().starts_with('{')
+    };
+    if is_json {
+        serde_json::from_str(text).map_err(|e| format!("Not valid JSON: {}", e).into())
+    } else {
+        serde_yaml::from_str(text).map_err(|e| format!("Not valid YAML: {}", e).into())
+    }
+}
+
+/// Turn an OpenAPI 3.x or Swagger 2.0 document fetched from `source` into a flow.
+///
+/// Swagger's `schemes`/`host`/`basePath` become a server entry, and relative server URLs are
+/// resolved against `source`. Shape errors name the failing field as a JSON pointer.
+pub fn spec_from_document(mut document: Value, source: &str) -> Result<OpenAPISpec, Box<dyn Error>> {
+    let Some(object) = document.as_object_mut() else {
+        return Err("Not an OpenAPI document: expected an object at the top level".into());
+    };
+    if !object.get("info").is_some_and(Value::is_object) {
+        return Err("Not an OpenAPI document: missing the `info` object".into());
+    }
+    if !object.get("paths").is_some_and(Value::is_object) {
+        return Err("Not an OpenAPI document: missing the `paths` object".into());
+    }
+
+    let base = url::Url::parse(source).ok();
+    if let Some(version) = object.remove("swagger") {
+        let scheme = object.remove("schemes")
+            .and_then(|schemes| schemes.get(0).and_then(Value::as_str).map(str::to_string))
+            .or_else(|| base.as_ref().map(|base| base.scheme().to_string()))
+            .unwrap_or_else(|| "https".to_string());
+        let host = object.remove("host").and_then(|host| host.as_str().map(str::to_string));
+        let base_path = object.remove("basePath").and_then(|path| path.as_str().map(str::to_string)).unwrap_or_default();
+        let url = match host {
+            Some(host) => format!("{}://{}{}", scheme, host, base_path),
+            // Without a host, the API is served by the host serving the document
+            None => base_path,
+        };
+        if !url.is_empty() {
+            object.insert("servers".to_string(), json!([{ "url": url }]));
+        }
+        object.entry("openapi").or_insert(version);
+    } else if !object.contains_key("openapi") {
+        return Err("Not an OpenAPI document: missing the `openapi` (or `swagger`) version".into());
+    }
+
+    if let (Some(base), Some(Value::Array(servers))) = (&base, object.get_mut("servers")) {
+        for server in servers {
+            if let Some(Value::String(url)) = server.get_mut("url") {
+                if url.starts_with('/') {
+                    if let Ok(resolved) = base.join(url) {
+                        *url = resolved.to_string().trim_end_matches('/').to_string();
+                    }
+                }
+            }
+        }
+    }
+
+    serde_path_to_error::deserialize(document).map_err(|e| {
+        let pointer: String = e.path().iter()
+            .map(|segment| {
+                let segment = match segment {
+                    serde_path_to_error::Segment::Seq { index } => index.to_string(),
+                    serde_path_to_error::Segment::Map { key } => key.clone(),
+                    serde_path_to_error::Segment::Enum { variant } => variant.clone(),
+                    serde_path_to_error::Segment::Unknown => "?".to_string(),
+                };
+                format!("/{}", segment.replace('~', "~0").replace('/', "~1"))
+            })
+            .collect();
+        let pointer = if pointer.is_empty() { "/".to_string() } else { pointer };
+        format!("Document doesn't match a NUTS flow at {}: {}", pointer, e.inner()).into()
+    })
+}
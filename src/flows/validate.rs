@@ -0,0 +1,270 @@
+//! Checking JSON responses against the schemas stored in a flow.
+//!
+//! Covers what our [`Schema`] model describes: `type` (including 3.1 type lists and 3.0
+//! `nullable`), `required`, nested `properties` and `items`, `enum`/`const`, `allOf`,
+//! `anyOf`/`oneOf`, and `$ref`s into `#/components/`. Every violation names the failing
+//! value as a JSONPath, e.g. `$.items[3].price: expected number, got string`.
+
+use super::{MediaType, Operation, Response, Schema, SchemaType};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// `$ref`s followed before giving up on a (probably recursive) schema.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// JSONPath of the offending value, `$` for the document itself.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Everything wrong with `value` according to `schema`. `components` is the flow's
+/// `components` object, used to resolve `$ref`s; unresolvable references accept anything.
+pub fn validate(value: &Value, schema: &Schema, components: Option<&Value>) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    validate_at(value, schema, "$", components, 0, &mut violations);
+    violations
+}
+
+/// The schema a response with `status` should match: the one declared for that exact code,
+/// then its `2XX`-style range, then `default`. A 2xx response also falls back to `200`.
+pub fn response_schema(operation: &Operation, status: u16) -> Option<&Schema> {
+    response_media(operation, status).map(|media| &media.schema)
+}
+
+/// The JSON content [`response_schema`] takes its schema from, examples included.
+pub fn response_media(operation: &Operation, status: u16) -> Option<&MediaType> {
+    let range = format!("{}XX", status / 100);
+    let mut codes = vec![status.to_string(), range.clone(), range.to_lowercase(), "default".to_string()];
+    if (200..300).contains(&status) {
+        codes.push("200".to_string());
+    }
+    codes.iter()
+        .filter_map(|code| operation.responses.get(code))
+        .filter_map(|response| response.content.as_ref())
+        .find_map(json_media)
+}
+
+/// The JSON entry of a `content` map, if it declares a schema.
+pub fn json_media(content: &HashMap<String, MediaType>) -> Option<&MediaType> {
+    content.iter()
+        .find(|(media_type, _)| media_type.contains("json"))
+        .map(|(_, media)| media)
+        .filter(|media| !media.schema.is_empty())
+}
+
+/// The response an operation documents for `status`: the exact code, then its `2XX`-style
+/// range, then `default`. Returns the code it was found under.
+pub fn documented_response(operation: &Operation, status: u16) -> Option<(&str, &Response)> {
+    let range = format!("{}XX", status / 100);
+    [status.to_string(), range.clone(), range.to_lowercase(), "default".to_string()]
+        .iter()
+        .find_map(|code| operation.responses.get_key_value(code))
+        .map(|(code, response)| (code.as_str(), response))
+}
+
+/// JSONPaths of object fields in `value` that `schema` doesn't list under `properties`.
+/// Objects whose schema lists no properties, or allows `additionalProperties`, accept any.
+pub fn undocumented_fields(value: &Value, schema: &Schema, components: Option<&Value>) -> Vec<String> {
+    let mut fields = Vec::new();
+    undocumented_at(value, schema, "$", components, 0, &mut fields);
+    fields
+}
+
+fn undocumented_at(value: &Value, schema: &Schema, path: &str, components: Option<&Value>, depth: usize, fields: &mut Vec<String>) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    if let Some(reference) = schema.extras.get("$ref").and_then(Value::as_str) {
+        if let Some(target) = resolve(reference, components) {
+            undocumented_at(value, &target, path, components, depth + 1, fields);
+        }
+        return;
+    }
+    match value {
+        Value::Object(object) => {
+            // `allOf` parts each contribute properties
+            let parts = subschemas(schema, "allOf");
+            let mut properties: HashMap<&str, &Schema> = HashMap::new();
+            let mut resolved = Vec::new();
+            for part in &parts {
+                match part.extras.get("$ref").and_then(Value::as_str).and_then(|r| resolve(r, components)) {
+                    Some(target) => resolved.push(target),
+                    None => properties.extend(part.properties.iter().flatten().map(|(name, s)| (name.as_str(), s))),
+                }
+            }
+            for target in &resolved {
+                properties.extend(target.properties.iter().flatten().map(|(name, s)| (name.as_str(), s)));
+            }
+            properties.extend(schema.properties.iter().flatten().map(|(name, s)| (name.as_str(), s)));
+            let open = properties.is_empty()
+                || [schema].into_iter().chain(&parts).chain(&resolved).any(|s| s.extras.get("additionalProperties").is_some_and(|a| a != &Value::Bool(false)));
+
+            let mut names: Vec<&String> = object.keys().collect();
+            names.sort();
+            for name in names {
+                let child = format!("{}.{}", path, name);
+                match properties.get(name.as_str()) {
+                    Some(property) => undocumented_at(&object[name], property, &child, components, depth, fields),
+                    None if !open => fields.push(child),
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = &schema.items {
+                for (i, item) in items.iter().enumerate() {
+                    undocumented_at(item, item_schema, &format!("{}[{}]", path, i), components, depth, fields);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Name of a JSON value's type as schemas spell it.
+pub fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match (expected, value) {
+        ("number", Value::Number(_)) => true,
+        // `2.0` is an integer to JSON Schema
+        ("integer", Value::Number(n)) => n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0),
+        _ => type_name(value) == expected,
+    }
+}
+
+fn resolve(reference: &str, components: Option<&Value>) -> Option<Schema> {
+    let pointer = reference.strip_prefix("#/components")?;
+    serde_json::from_value(components?.pointer(pointer)?.clone()).ok()
+}
+
+fn subschemas(schema: &Schema, keyword: &str) -> Vec<Schema> {
+    schema.extras.get(keyword)
+        .and_then(Value::as_array)
+        .map(|schemas| schemas.iter().filter_map(|s| serde_json::from_value(s.clone()).ok()).collect())
+        .unwrap_or_default()
+}
+
+fn fail(violations: &mut Vec<Violation>, path: &str, message: String) {
+    violations.push(Violation { path: path.to_string(), message });
+}
+
+fn validate_at(value: &Value, schema: &Schema, path: &str, components: Option<&Value>, depth: usize, violations: &mut Vec<Violation>) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+
+    if let Some(reference) = schema.extras.get("$ref").and_then(Value::as_str) {
+        if let Some(target) = resolve(reference, components) {
+            validate_at(value, &target, path, components, depth + 1, violations);
+        }
+        return;
+    }
+
+    for part in subschemas(schema, "allOf") {
+        validate_at(value, &part, path, components, depth + 1, violations);
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        let options = subschemas(schema, keyword);
+        let matches = |option: &Schema| {
+            let mut found = Vec::new();
+            validate_at(value, option, path, components, depth + 1, &mut found);
+            found.is_empty()
+        };
+        if !options.is_empty() && !options.iter().any(matches) {
+            fail(violations, path, format!("matches none of the {} {} schemas", options.len(), keyword));
+        }
+    }
+
+    if value.is_null() && schema.is_nullable() {
+        return;
+    }
+    let expected: Vec<&str> = match &schema.schema_type {
+        SchemaType::Single(name) if name.is_empty() => Vec::new(),
+        SchemaType::Single(name) => vec![name.as_str()],
+        SchemaType::Multiple(names) => names.iter().map(String::as_str).collect(),
+    };
+    if !expected.is_empty() && !expected.iter().any(|name| matches_type(value, name)) {
+        fail(violations, path, format!("expected {}, got {}", expected.join(" or "), type_name(value)));
+        return;
+    }
+
+    if let Some(allowed) = &schema.enum_values {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            fail(violations, path, format!("{} is not one of {}", value, allowed.join(", ")));
+        }
+    }
+    if let Some(constant) = schema.const_value.as_ref().filter(|constant| *constant != value) {
+        fail(violations, path, format!("expected {}, got {}", constant, value));
+    }
+
+    match value {
+        Value::Object(object) => {
+            for name in &schema.required {
+                if !object.contains_key(name) {
+                    fail(violations, &format!("{}.{}", path, name), "required property is missing".to_string());
+                }
+            }
+            let mut properties: Vec<_> = schema.properties.iter().flatten().collect();
+            properties.sort_by(|a, b| a.0.cmp(b.0));
+            for (name, property) in properties {
+                if let Some(child) = object.get(name) {
+                    validate_at(child, property, &format!("{}.{}", path, name), components, depth, violations);
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = &schema.items {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(item, item_schema, &format!("{}[{}]", path, i), components, depth, violations);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `call --validate-against FLOW:PATH`: check a response against the operation for `method`
+/// on `PATH` in `FLOW`, a flow name in `~/.nuts/flows` or a spec file. Returns the violations.
+pub fn validate_against(target: &str, method: &str, status: u16, body: &str) -> Result<Vec<Violation>, Box<dyn std::error::Error>> {
+    let (flow, path) = target.split_once(":/")
+        .map(|(flow, path)| (flow, format!("/{}", path)))
+        .ok_or_else(|| format!("--validate-against needs FLOW:PATH, e.g. users:/users/{{id}}, got '{}'", target))?;
+    let file = std::path::PathBuf::from(flow);
+    let file = if file.is_file() {
+        file
+    } else {
+        dirs::home_dir().ok_or("Could not find home directory")?.join(".nuts").join("flows").join(format!("{}.yaml", flow))
+    };
+    let spec = super::OpenAPISpec::load(&file).map_err(|e| format!("Could not load flow {}: {}", file.display(), e))?;
+
+    let item = spec.paths.get(&path).ok_or_else(|| format!("No path {} in {}", path, file.display()))?;
+    let operation = item.operations()
+        .find(|(m, _)| m.eq_ignore_ascii_case(method))
+        .ok_or_else(|| format!("No {} operation on {} in {}", method.to_uppercase(), path, file.display()))?
+        .1;
+    let schema = response_schema(operation, status)
+        .ok_or_else(|| format!("{} {} declares no JSON schema for status {}", method.to_uppercase(), path, status))?;
+    let value: Value = serde_json::from_str(body).map_err(|e| format!("Response is not JSON: {}", e))?;
+    Ok(validate(&value, schema, spec.extras.get("components")))
+}
@@ -0,0 +1,219 @@
+//! Variable chaining between flow steps.
+//!
+//! An operation can declare `x-extract` rules mapping a variable name to a path into its
+//! JSON response (`$.data.token`, `items[0].id`). Extracted values are substituted into
+//! later requests wherever `{{name}}` appears, and into `{name}` path parameters. Values
+//! are percent-encoded in URL paths and JSON-escaped in bodies, so a token with a quote or a
+//! name with a space can't break the request.
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Everything but RFC 3986's unreserved characters, so a value stays one path segment.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+fn encode_segment(value: &str) -> String {
+    utf8_percent_encode(value, PATH_SEGMENT).to_string()
+}
+
+/// Variables kept across commands: the shell holds one per flow, so `flow run` steps typed
+/// one at a time chain like the steps of `flow run --all`.
+pub type SharedVariables = Arc<tokio::sync::Mutex<FlowVariables>>;
+
+/// A value captured from a response, remembering which step produced it.
+#[derive(Debug, Clone)]
+pub struct FlowVariable {
+    pub value: String,
+    pub step: String,
+}
+
+/// Variables captured so far in a flow session, over the values set before it starts.
+#[derive(Debug, Default)]
+pub struct FlowVariables {
+    values: HashMap<String, FlowVariable>,
+    defaults: HashMap<String, FlowVariable>,
+}
+
+impl FlowVariables {
+    /// A captured value, or else a default.
+    pub fn get(&self, name: &str) -> Option<&FlowVariable> {
+        self.values.get(name).or_else(|| self.defaults.get(name))
+    }
+
+    /// Replace the values used until a step captures its own: `(name, value, source)`, where
+    /// later ones win, e.g. the environment's and then the flow's `x-variables`.
+    pub fn set_defaults<'a>(&mut self, defaults: impl IntoIterator<Item = (&'a String, &'a String, &'a str)>) {
+        self.defaults = defaults.into_iter()
+            .map(|(name, value, source)| (name.clone(), FlowVariable { value: value.clone(), step: source.to_string() }))
+            .collect();
+    }
+
+    pub fn set(&mut self, name: &str, value: String, step: &str) {
+        self.values.insert(name.to_string(), FlowVariable { value, step: step.to_string() });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Apply `rules` (variable name -> path) to a response body.
+    pub fn extract_all<'a>(
+        &mut self,
+        rules: impl IntoIterator<Item = (&'a String, &'a String)>,
+        body: &str,
+        step: &str,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut rules = rules.into_iter().peekable();
+        if rules.peek().is_none() {
+            return Ok(Vec::new());
+        }
+
+        let json: Value = serde_json::from_str(body)
+            .map_err(|_| format!("Step '{}' has x-extract rules but its response is not JSON", step))?;
+
+        let mut extracted = Vec::new();
+        for (name, path) in rules {
+            let value = extract(&json, path).ok_or_else(|| {
+                format!("Step '{}' could not extract '{}': path '{}' not found in response", step, name, path)
+            })?;
+            self.set(name, value_to_string(value), step);
+            extracted.push(name.clone());
+        }
+        Ok(extracted)
+    }
+
+    /// Replace every `{{name}}` in `text`. `producer` names the step expected to set a
+    /// variable, so a missing one can be reported with where it should have come from.
+    pub fn substitute(
+        &self,
+        text: &str,
+        step: &str,
+        producer: impl Fn(&str) -> Option<String>,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        self.substitute_with(text, step, producer, str::to_string)
+    }
+
+    /// [`substitute`](Self::substitute) for a URL path: values are percent-encoded.
+    pub fn substitute_path(
+        &self,
+        path: &str,
+        step: &str,
+        producer: impl Fn(&str) -> Option<String>,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        self.substitute_with(path, step, producer, encode_segment)
+    }
+
+    /// [`substitute`](Self::substitute) for a request body. A JSON body has its string values
+    /// substituted one by one and is serialized again, so quotes, backslashes and newlines in
+    /// a value are escaped; any other body is substituted as text.
+    pub fn substitute_body(
+        &self,
+        body: &str,
+        step: &str,
+        producer: impl Fn(&str) -> Option<String>,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        match serde_json::from_str::<Value>(body) {
+            Ok(mut json) => {
+                self.substitute_strings(&mut json, step, &producer)?;
+                Ok(json.to_string())
+            }
+            Err(_) => self.substitute(body, step, producer),
+        }
+    }
+
+    fn substitute_strings(&self, value: &mut Value, step: &str, producer: &impl Fn(&str) -> Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+        match value {
+            Value::String(text) => *text = self.substitute(text, step, producer)?,
+            Value::Array(items) => {
+                for item in items {
+                    self.substitute_strings(item, step, producer)?;
+                }
+            }
+            Value::Object(fields) => {
+                for field in fields.values_mut() {
+                    self.substitute_strings(field, step, producer)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn substitute_with(
+        &self,
+        text: &str,
+        step: &str,
+        producer: impl Fn(&str) -> Option<String>,
+        encode: fn(&str) -> String,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else { break };
+            let name = rest[start + 2..start + 2 + len].trim();
+            result.push_str(&rest[..start]);
+
+            match self.get(name) {
+                Some(variable) => result.push_str(&encode(&variable.value)),
+                None => {
+                    let message = match producer(name) {
+                        Some(origin) => format!(
+                            "Variable '{}' used by step '{}' is not set yet; it is extracted by step '{}', run that first",
+                            name, step, origin
+                        ),
+                        None => format!(
+                            "Variable '{}' used by step '{}' is not defined by any x-extract rule or x-variables entry in this flow",
+                            name, step
+                        ),
+                    };
+                    return Err(message.into());
+                }
+            }
+            rest = &rest[start + 2 + len + 2..];
+        }
+
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    /// Fill OpenAPI `{param}` path segments that have a captured variable of the same name,
+    /// percent-encoded.
+    pub fn fill_path_params(&self, path: &str) -> String {
+        let mut result = path.to_string();
+        for (name, variable) in self.values.iter().chain(&self.defaults) {
+            result = result.replace(&format!("{{{}}}", name), &encode_segment(&variable.value));
+        }
+        result
+    }
+}
+
+/// Resolve a JSONPath-like expression: `$.a.b`, `a.b[0].c`, `[1].id`.
+pub fn extract<'a>(json: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim().trim_start_matches('$').trim_start_matches('.');
+    let mut current = json;
+
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        let (key, indexes) = match segment.find('[') {
+            Some(pos) => (&segment[..pos], &segment[pos..]),
+            None => (segment, ""),
+        };
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in indexes.split('[').filter(|s| !s.is_empty()) {
+            let index: usize = index.trim_end_matches(']').parse().ok()?;
+            current = current.get(index)?;
+        }
+    }
+    Some(current)
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,204 @@
+//! Structured request history: one JSON line per `call` made from the shell, kept in
+//! `~/.nuts/history.jsonl` so earlier requests can be listed, searched and replayed.
+//!
+//! Each entry is written with a single `write` on a file opened in append mode, so shells
+//! running side by side interleave whole lines instead of corrupting each other's entries.
+//!
+//! Credentials among the arguments (`-H "Authorization: ..."`, `--bearer`, the password of
+//! `-u`, `--sign` secrets, proxy passwords) are stored as [`MASKED`], and the entry lists
+//! them so `replay` can ask for them again instead of sending the placeholder.
+
+use crate::capture::SENSITIVE_HEADERS;
+use crate::timing::Timings;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// One executed `call`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    pub duration_ms: u64,
+    /// FNV-1a hash of the request body, so identical payloads can be spotted without storing them twice.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_hash: Option<String>,
+    /// The `call` arguments as typed, credentials masked; replayed by `replay`.
+    pub args: Vec<String>,
+    /// The credentials masked in `args`, like `-H Authorization` or `--bearer`; a replay
+    /// needs them supplied again.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub masked: Vec<String>,
+    /// Phase timings of a verbose call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<Timings>,
+}
+
+/// Stands in for a credential in a stored argument.
+pub const MASKED: &str = "***";
+
+impl HistoryEntry {
+    pub fn new(mut args: Vec<String>, method: &str, url: &str, body: Option<&str>, status: u16, duration: Duration) -> Self {
+        let masked = mask_credentials(&mut args);
+        Self {
+            timestamp: Utc::now(),
+            method: method.to_uppercase(),
+            url: url.to_string(),
+            status,
+            duration_ms: duration.as_millis() as u64,
+            body_hash: body.map(body_hash),
+            args,
+            masked,
+            timings: None,
+        }
+    }
+
+    pub fn with_timings(mut self, timings: Option<Timings>) -> Self {
+        self.timings = timings;
+        self
+    }
+
+    /// Single-line summary used by `history` and `replay`.
+    pub fn summary(&self) -> String {
+        format!(
+            "{}  {} {} → {} ({}ms)",
+            self.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+            self.method,
+            self.url,
+            self.status,
+            self.duration_ms
+        )
+    }
+}
+
+/// Append-only history file.
+#[derive(Debug, Clone)]
+pub struct RequestHistory {
+    path: PathBuf,
+}
+
+impl RequestHistory {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `~/.nuts/history.jsonl`.
+    pub fn default_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(dirs::home_dir()
+            .ok_or("Could not find home directory")?
+            .join(".nuts")
+            .join("history.jsonl"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, entry: &HistoryEntry) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            // Arguments may carry credentials, so keep the file private like the config.
+            options.mode(0o600);
+        }
+        options.open(&self.path)?.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// All entries, oldest first. Lines that fail to parse are skipped rather than failing the listing.
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>, Box<dyn std::error::Error>> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Entry by its 1-based index, as printed by `history`.
+    pub fn get(&self, index: usize) -> Result<HistoryEntry, Box<dyn std::error::Error>> {
+        let entries = self.entries()?;
+        index
+            .checked_sub(1)
+            .and_then(|i| entries.get(i).cloned())
+            .ok_or_else(|| format!("No history entry #{} ({} entries recorded)", index, entries.len()).into())
+    }
+
+    /// Entries whose method, URL or arguments contain `term` (case-insensitive), with their indexes.
+    pub fn search(&self, term: &str) -> Result<Vec<(usize, HistoryEntry)>, Box<dyn std::error::Error>> {
+        let term = term.to_lowercase();
+        Ok(self
+            .entries()?
+            .into_iter()
+            .enumerate()
+            .map(|(i, entry)| (i + 1, entry))
+            .filter(|(_, entry)| {
+                entry.method.to_lowercase().contains(&term)
+                    || entry.url.to_lowercase().contains(&term)
+                    || entry.args.iter().any(|arg| arg.to_lowercase().contains(&term))
+            })
+            .collect())
+    }
+}
+
+/// Mask the credential values in `args`, returning what was masked.
+fn mask_credentials(args: &mut [String]) -> Vec<String> {
+    let mut masked = Vec::new();
+    for i in 1..args.len() {
+        let flag = args[i - 1].as_str();
+        let value = args[i].as_str();
+        let (label, replacement) = match flag {
+            "-H" | "--header" => match value.split_once(':') {
+                Some((name, _)) if SENSITIVE_HEADERS.contains(&name.trim().to_ascii_lowercase().as_str()) => {
+                    (format!("{} {}", flag, name.trim()), format!("{}: {}", name.trim(), MASKED))
+                }
+                _ => continue,
+            },
+            "-u" | "--user" => match value.split_once(':') {
+                Some((user, _)) => (flag.to_string(), format!("{}:{}", user, MASKED)),
+                None => continue,
+            },
+            "-x" | "--proxy" => match url::Url::parse(value) {
+                Ok(mut proxy) if proxy.password().is_some() => {
+                    let _ = proxy.set_password(Some(MASKED));
+                    (format!("{} password", flag), proxy.to_string())
+                }
+                _ => continue,
+            },
+            "--bearer" | "--sign" => (flag.to_string(), MASKED.to_string()),
+            // Any future secret flag, like --aws-secret-access-key or --aws-session-token
+            _ if flag.starts_with("--") && ["secret", "token", "password"].iter().any(|word| flag.contains(word)) => {
+                (flag.to_string(), MASKED.to_string())
+            }
+            _ => continue,
+        };
+        args[i] = replacement;
+        masked.push(label);
+    }
+    masked
+}
+
+/// 64-bit FNV-1a, hex encoded. Stable across runs and platforms, unlike `DefaultHasher`.
+fn body_hash(body: &str) -> String {
+    let hash = body.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,141 @@
+//! Pre-request and post-response hooks.
+//!
+//! A hook is an external command run through the system shell. NUTS writes a JSON
+//! document to its stdin:
+//!
+//! * `pre_request` receives `{"method", "url", "headers", "body"}` and may print a JSON
+//!   object with any of the same fields on stdout; printed fields replace the originals
+//!   (empty output leaves the request untouched).
+//! * `post_response` receives `{"request": {...}, "response": {"status", "headers",
+//!   "body", "elapsed_ms"}}`; anything it prints is shown after the response.
+//!
+//! A non-zero exit status fails the request and surfaces the hook's stderr.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Hook commands, configurable globally in `~/.nuts/config.json` or per flow via `x-nuts-hooks`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HookConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_request: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_response: Option<String>,
+}
+
+impl HookConfig {
+    pub fn is_empty(&self) -> bool {
+        self.pre_request.is_none() && self.post_response.is_none()
+    }
+
+    /// Combine with a more specific configuration; hooks set in `other` win.
+    pub fn overridden_by(&self, other: Option<&HookConfig>) -> HookConfig {
+        match other {
+            Some(other) => HookConfig {
+                pre_request: other.pre_request.clone().or_else(|| self.pre_request.clone()),
+                post_response: other.post_response.clone().or_else(|| self.post_response.clone()),
+            },
+            None => self.clone(),
+        }
+    }
+}
+
+/// Request as seen by hooks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+/// Response as seen by the post-response hook.
+#[derive(Debug, Clone, Serialize)]
+pub struct HookResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+    pub elapsed_ms: u128,
+}
+
+/// Fields a pre-request hook may override.
+#[derive(Debug, Default, Deserialize)]
+struct HookRequestPatch {
+    method: Option<String>,
+    url: Option<String>,
+    headers: Option<HashMap<String, String>>,
+    body: Option<Option<String>>,
+}
+
+/// Run the pre-request hook and return the (possibly modified) request.
+pub async fn run_pre_request(command: &str, request: HookRequest) -> Result<HookRequest, Box<dyn std::error::Error>> {
+    let input = serde_json::to_string(&request)?;
+    let stdout = run_hook("pre_request", command, &input).await?;
+    if stdout.trim().is_empty() {
+        return Ok(request);
+    }
+
+    let patch: HookRequestPatch = serde_json::from_str(&stdout)
+        .map_err(|e| format!("pre-request hook returned invalid JSON: {}", e))?;
+    Ok(HookRequest {
+        method: patch.method.unwrap_or(request.method),
+        url: patch.url.unwrap_or(request.url),
+        headers: patch.headers.unwrap_or(request.headers),
+        body: patch.body.unwrap_or(request.body),
+    })
+}
+
+/// Run the post-response hook and return whatever it printed.
+pub async fn run_post_response(
+    command: &str,
+    request: &HookRequest,
+    response: &HookResponse,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let input = serde_json::to_string(&serde_json::json!({
+        "request": request,
+        "response": response,
+    }))?;
+    run_hook("post_response", command, &input).await
+}
+
+async fn run_hook(kind: &str, command: &str, input: &str) -> Result<String, Box<dyn std::error::Error>> {
+    tracing::debug!(target: "nuts::hooks", kind, command, "running hook");
+
+    let mut child = shell_command(command)
+        .env("NUTS_HOOK", kind)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("{} hook could not start '{}': {}", kind, command, e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that ignores its input may close stdin early; that's not an error.
+        let _ = stdin.write_all(input.as_bytes()).await;
+    }
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} hook failed ({}): {}", kind, output.status, stderr.trim()).into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
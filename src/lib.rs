@@ -0,0 +1,37 @@
+//! NUTS - Network Universal Testing Suite.
+//!
+//! The binary in `main.rs` is a thin wrapper around [`shell::NutsShell`]; the modules are
+//! exposed here so integration tests can drive commands directly.
+
+pub mod commands;
+pub mod shell;
+pub mod completer;
+pub mod aliases;
+pub mod models;
+pub mod config;
+pub mod ai;
+pub mod ai_cache;
+pub mod usage;
+pub mod flows;
+pub mod story;
+pub mod logging;
+pub mod hooks;
+pub mod capture;
+pub mod report;
+pub mod history;
+pub mod cookies;
+pub mod auth;
+pub mod timing;
+pub mod compression;
+pub mod sigv4;
+pub mod webhook;
+pub mod trace_log;
+pub mod tls;
+pub mod recording;
+pub mod templates;
+pub mod snippet;
+pub mod confirm;
+pub mod services;
+pub mod output;
+pub mod query;
+pub mod units;
@@ -0,0 +1,263 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Environment variable that selects the diagnostic log level (error, warn, info, debug, trace).
+pub const LOG_ENV: &str = "NUTS_LOG";
+
+const MASK: &str = "[REDACTED]";
+
+/// Header names and key fragments whose values are always masked in log output.
+const SENSITIVE_KEYS: &[&str] = &[
+    "authorization",
+    "x-api-key",
+    "api_key",
+    "api-key",
+    "apikey",
+    "anthropic_api_key",
+    "access_token",
+    "client_secret",
+    "password",
+];
+
+/// Settings collected from the command line that drive logger initialization.
+#[derive(Debug, Default)]
+pub struct LogOptions {
+    pub debug: bool,
+    pub verbosity: u8,
+    pub log_file: Option<String>,
+    pub secrets: Vec<String>,
+}
+
+/// Resolve the log level: `--debug`/`-vv` win, then NUTS_LOG, and a log file alone implies debug.
+fn resolve_level(options: &LogOptions) -> LevelFilter {
+    if options.verbosity >= 3 {
+        return LevelFilter::TRACE;
+    }
+    if options.debug || options.verbosity >= 2 {
+        return LevelFilter::DEBUG;
+    }
+    if let Ok(value) = std::env::var(LOG_ENV) {
+        if let Ok(level) = value.trim().parse::<LevelFilter>() {
+            return level;
+        }
+    }
+    if options.log_file.is_some() {
+        LevelFilter::DEBUG
+    } else {
+        LevelFilter::OFF
+    }
+}
+
+/// Install the global tracing subscriber. Logging stays off unless explicitly requested,
+/// so regular console output is unaffected.
+pub fn init(options: LogOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let level = resolve_level(&options);
+    if level == LevelFilter::OFF {
+        return Ok(());
+    }
+
+    let sink = match &options.log_file {
+        Some(path) => Sink::File(Arc::new(Mutex::new(open_log_file(path)?))),
+        None => Sink::Stderr,
+    };
+    let writer = RedactingMakeWriter {
+        sink,
+        secrets: Arc::new(
+            options.secrets.into_iter()
+                .filter(|s| s.len() >= 4)
+                .collect(),
+        ),
+    };
+
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_target(true)
+        .with_ansi(options.log_file.is_none())
+        .with_writer(writer)
+        .try_init()
+        .map_err(|e| format!("Failed to initialize logging: {}", e))?;
+
+    tracing::debug!(target: "nuts::logging", level = %level, "diagnostic logging enabled");
+    Ok(())
+}
+
+fn open_log_file(path: &str) -> io::Result<File> {
+    if let Some(parent) = Path::new(path).parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[derive(Clone)]
+enum Sink {
+    Stderr,
+    File(Arc<Mutex<File>>),
+}
+
+/// Writer factory that scrubs secrets out of every formatted event before it is written.
+#[derive(Clone)]
+struct RedactingMakeWriter {
+    sink: Sink,
+    secrets: Arc<Vec<String>>,
+}
+
+impl<'a> MakeWriter<'a> for RedactingMakeWriter {
+    type Writer = RedactingWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            sink: self.sink.clone(),
+            secrets: Arc::clone(&self.secrets),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// Buffers a single event and redacts it as a whole when flushed or dropped.
+struct RedactingWriter {
+    sink: Sink,
+    secrets: Arc<Vec<String>>,
+    buffer: Vec<u8>,
+}
+
+impl Write for RedactingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let text = String::from_utf8_lossy(&self.buffer);
+        let cleaned = redact(&text, &self.secrets);
+        self.buffer.clear();
+
+        match &self.sink {
+            Sink::Stderr => io::stderr().write_all(cleaned.as_bytes()),
+            Sink::File(file) => {
+                let mut file = file.lock().map_err(|_| io::Error::other("log file lock poisoned"))?;
+                file.write_all(cleaned.as_bytes())
+            }
+        }
+    }
+}
+
+impl Drop for RedactingWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Mask known secret values, Anthropic-style keys, bearer tokens and sensitive key/value pairs.
+pub fn redact(text: &str, secrets: &[String]) -> String {
+    let mut out = text.to_string();
+
+    for secret in secrets {
+        if !secret.is_empty() {
+            out = out.replace(secret.as_str(), MASK);
+        }
+    }
+
+    out = mask_after(&out, "sk-ant-", false);
+    out = mask_after(&out, "Bearer ", true);
+    out = mask_after(&out, "bearer ", true);
+
+    for key in SENSITIVE_KEYS {
+        out = mask_key_values(&out, key);
+    }
+
+    out
+}
+
+/// Replace the token that follows `marker`; `keep_marker` keeps the marker text itself.
+fn mask_after(text: &str, marker: &str, keep_marker: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(idx) = rest.find(marker) {
+        let after = &rest[idx + marker.len()..];
+        let token_len = token_length(after);
+        out.push_str(&rest[..idx]);
+        if keep_marker {
+            out.push_str(marker);
+        }
+        if token_len == 0 && keep_marker {
+            rest = after;
+            continue;
+        }
+        out.push_str(MASK);
+        rest = &after[token_len..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Mask the value in `key: value`, `key=value` or `"key": "value"` forms (case-insensitive key).
+fn mask_key_values(text: &str, key: &str) -> String {
+    let lower = text.to_ascii_lowercase();
+    let mut out = String::with_capacity(text.len());
+    let mut cursor = 0;
+
+    while let Some(rel) = lower[cursor..].find(key) {
+        let key_end = cursor + rel + key.len();
+        let tail = &text[key_end..];
+
+        // Skip closing quote, whitespace and the separator before the value.
+        let sep_len = tail
+            .char_indices()
+            .find(|(_, c)| !matches!(c, '"' | '\'' | ' ' | ':' | '='))
+            .map(|(i, _)| i)
+            .unwrap_or(tail.len());
+        let separator = &tail[..sep_len];
+        if !separator.contains(':') && !separator.contains('=') {
+            out.push_str(&text[cursor..key_end]);
+            cursor = key_end;
+            continue;
+        }
+
+        let value = &tail[sep_len..];
+        let value_len = token_length(value);
+        out.push_str(&text[cursor..key_end]);
+        out.push_str(separator);
+        if value_len > 0 && !value.starts_with(MASK) {
+            // Authorization values usually carry a scheme word followed by the credential.
+            let scheme = &value[..value_len];
+            if scheme.eq_ignore_ascii_case("bearer") || scheme.eq_ignore_ascii_case("basic") {
+                let credential = value[value_len..].trim_start_matches(' ');
+                let gap = value.len() - value_len - credential.len();
+                let credential_len = token_length(credential);
+                out.push_str(&value[..value_len + gap]);
+                if credential_len > 0 && !credential.starts_with(MASK) {
+                    out.push_str(MASK);
+                } else {
+                    out.push_str(&credential[..credential_len]);
+                }
+                cursor = key_end + sep_len + value_len + gap + credential_len;
+                continue;
+            }
+            out.push_str(MASK);
+        } else {
+            out.push_str(&value[..value_len]);
+        }
+        cursor = key_end + sep_len + value_len;
+    }
+
+    out.push_str(&text[cursor..]);
+    out
+}
+
+fn token_length(s: &str) -> usize {
+    s.char_indices()
+        .find(|(_, c)| c.is_whitespace() || matches!(c, '"' | '\'' | ',' | '}' | ']' | ';' | '&'))
+        .map(|(i, _)| i)
+        .unwrap_or(s.len())
+}
@@ -1,11 +1,5 @@
-mod commands;
-mod shell;
-mod completer;
-mod models;
-mod config;
-mod flows;
-mod story;
-use shell::NutsShell;
+use nuts::shell::NutsShell;
+use nuts::{config, logging, output};
 use clap::{Command, Arg};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -17,15 +11,69 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .arg(Arg::new("version")
             .short('v')
             .long("version")
-            .help("Print version info")
+            .help("Print version info (repeat as -vv for debug logging, -vvv for trace)")
+            .action(clap::ArgAction::Count))
+        .arg(Arg::new("debug")
+            .long("debug")
+            .help("Enable diagnostic logging (same as NUTS_LOG=debug)")
             .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("log-file")
+            .long("log-file")
+            .value_name("FILE")
+            .help("Write diagnostic logs to FILE instead of stderr"))
+        .arg(Arg::new("json")
+            .long("json")
+            .help("Print the result of call, security or predict as JSON on stdout")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("no-color")
+            .long("no-color")
+            .help("Plain output without ANSI colors (also NO_COLOR=1 or `config set color false`)")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("quiet")
+            .long("quiet")
+            .help("Leave out banners, emoji and spinners; print results only")
+            .action(clap::ArgAction::SetTrue))
+        .arg(Arg::new("command")
+            .value_name("COMMAND")
+            .help("Run a single shell command and exit, e.g. `nuts call GET https://api.example.com`")
+            .num_args(1..)
+            .trailing_var_arg(true)
+            .allow_hyphen_values(true))
         .get_matches();
 
-    if matches.get_flag("version") {
+    let verbosity = matches.get_count("version");
+    if verbosity == 1 {
         println!("NUTS v0.1.0");
         return Ok(());
     }
 
-    let mut shell = NutsShell::new();
+    let config = config::Config::load().unwrap_or_default();
+    logging::init(logging::LogOptions {
+        debug: matches.get_flag("debug"),
+        verbosity,
+        log_file: matches.get_one::<String>("log-file").cloned(),
+        secrets: config.anthropic_api_key.iter().chain(config.api_key.iter()).cloned().collect(),
+    })?;
+
+    let mut shell = NutsShell::with_config(config);
+    if matches.get_flag("no-color") {
+        output::set_color(false);
+    }
+    output::set_quiet(matches.get_flag("quiet"));
+
+    // Non-interactive mode: run the given command once and exit with its status
+    if let Some(command) = matches.get_many::<String>("command") {
+        let mut args: Vec<String> = command.cloned().collect();
+        if matches.get_flag("json") {
+            args.push("--json".to_string());
+        }
+        let runtime = tokio::runtime::Runtime::new()?;
+        if let Err(e) = runtime.block_on(shell.process_args(args)) {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     shell.run()
 }
\ No newline at end of file
@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::net::SocketAddr;
 use std::time::{Duration, SystemTime};
 use std::sync::Mutex;
 
@@ -9,27 +12,340 @@ pub struct RequestMetric {
     pub timestamp: SystemTime,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct MetricsSummary {
+    #[serde(rename = "avg_latency_ms", serialize_with = "as_millis")]
     pub avg_latency: Duration,
+    #[serde(rename = "p95_latency_ms", serialize_with = "as_millis")]
     pub p95_latency: Duration,
+    #[serde(rename = "p99_latency_ms", serialize_with = "as_millis")]
     pub p99_latency: Duration,
+    #[serde(rename = "median_latency_ms", serialize_with = "as_millis")]
     pub median_latency: Duration,
+    #[serde(rename = "std_dev_latency_secs")]
     pub std_dev_latency: f64,
     pub total_requests: usize,
     pub error_rate: f64,
-    pub response_time_ranges: HashMap<String, usize>,
-    #[allow(dead_code)]
+    /// Number of responses per HTTP status code.
+    pub status_codes: BTreeMap<u16, usize>,
+    #[serde(rename = "min_latency_ms", serialize_with = "as_millis")]
+    pub min_latency: Duration,
+    #[serde(rename = "max_latency_ms", serialize_with = "as_millis")]
+    pub max_latency: Duration,
+    /// Response times on a log scale from 1ms to 60s.
+    pub histogram: LatencyHistogram,
+    /// Requests that got no response, by cause.
+    pub errors: BTreeMap<ErrorKind, ErrorCount>,
+    /// Of those, the ones that ran out of time: `errors` under [`ErrorKind::Timeout`].
+    pub timeouts: usize,
+    #[serde(skip)]
     pub requests_per_second: Vec<(SystemTime, usize)>,
     pub peak_rps: usize,
+    /// Requests sent during warmup, left out of every other number.
+    pub warmup_requests: usize,
+    /// Average and spread without the slowest responses, when outlier trimming is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trimmed: Option<TrimmedLatency>,
+    /// Responses on a freshly opened connection versus one kept alive from before.
+    pub connections: ConnectionStats,
+}
+
+/// How many responses needed a new connection, and what that cost in latency.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ConnectionStats {
+    pub new: usize,
+    pub reused: usize,
+    #[serde(rename = "avg_new_latency_ms", serialize_with = "as_millis")]
+    pub avg_new_latency: Duration,
+    #[serde(rename = "avg_reused_latency_ms", serialize_with = "as_millis")]
+    pub avg_reused_latency: Duration,
+}
+
+/// Local addresses seen so far, with the latency of each kind of response.
+#[derive(Default)]
+struct ConnectionLog {
+    seen: HashSet<SocketAddr>,
+    new: (usize, Duration),
+    reused: (usize, Duration),
+}
+
+impl ConnectionLog {
+    fn stats(&self) -> ConnectionStats {
+        let average = |(count, total): (usize, Duration)| if count == 0 { Duration::ZERO } else { total / count as u32 };
+        ConnectionStats {
+            new: self.new.0,
+            reused: self.reused.0,
+            avg_new_latency: average(self.new),
+            avg_reused_latency: average(self.reused),
+        }
+    }
+}
+
+/// Latency stats over what is left after dropping the slowest `fraction` of responses.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrimmedLatency {
+    pub fraction: f64,
+    /// Responses dropped as outliers.
+    pub dropped: usize,
+    #[serde(rename = "avg_latency_ms", serialize_with = "as_millis")]
+    pub avg_latency: Duration,
+    #[serde(rename = "std_dev_latency_secs")]
+    pub std_dev_latency: f64,
+}
+
+/// Average and standard deviation of `latencies` without the slowest `fraction` (0.1 = 10%).
+pub fn trim_latencies(latencies: &[Duration], fraction: f64) -> TrimmedLatency {
+    let mut sorted = latencies.to_vec();
+    sorted.sort();
+    let dropped = ((sorted.len() as f64) * fraction.clamp(0.0, 1.0)).floor() as usize;
+    sorted.truncate(sorted.len() - dropped);
+
+    let count = sorted.len().max(1) as f64;
+    let mean = sorted.iter().map(Duration::as_secs_f64).sum::<f64>() / count;
+    let variance = sorted.iter().map(|d| (d.as_secs_f64() - mean).powi(2)).sum::<f64>() / count;
+    TrimmedLatency {
+        fraction,
+        dropped,
+        avg_latency: Duration::from_secs_f64(mean),
+        std_dev_latency: variance.sqrt(),
+    }
+}
+
+/// Why a request got no response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Dns,
+    ConnectionRefused,
+    ConnectionReset,
+    Tls,
+    Timeout,
+    TooManyRedirects,
+    Other,
+}
+
+impl ErrorKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorKind::Dns => "DNS failure",
+            ErrorKind::ConnectionRefused => "Connection refused",
+            ErrorKind::ConnectionReset => "Connection reset",
+            ErrorKind::Tls => "TLS error",
+            ErrorKind::Timeout => "Timeout",
+            ErrorKind::TooManyRedirects => "Too many redirects",
+            ErrorKind::Other => "Other",
+        }
+    }
+}
+
+/// How often one kind of error happened, with the first message seen.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ErrorCount {
+    pub count: usize,
+    pub example: String,
+}
+
+/// Classify a failed request by walking its error chain, outermost error first.
+pub fn classify_error(error: &(dyn Error + 'static)) -> ErrorKind {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+            if e.is_timeout() {
+                return ErrorKind::Timeout;
+            }
+            if e.is_redirect() {
+                return ErrorKind::TooManyRedirects;
+            }
+        }
+        if let Some(e) = error.downcast_ref::<std::io::Error>() {
+            match e.kind() {
+                std::io::ErrorKind::ConnectionRefused => return ErrorKind::ConnectionRefused,
+                std::io::ErrorKind::ConnectionReset
+                | std::io::ErrorKind::ConnectionAborted
+                | std::io::ErrorKind::BrokenPipe
+                | std::io::ErrorKind::UnexpectedEof => return ErrorKind::ConnectionReset,
+                std::io::ErrorKind::TimedOut => return ErrorKind::Timeout,
+                _ => {}
+            }
+        }
+        // Resolver and TLS errors come from crates we don't depend on directly
+        let message = error.to_string().to_lowercase();
+        if ["dns error", "failed to lookup address", "name or service not known", "nodename nor servname"]
+            .iter().any(|m| message.contains(m))
+        {
+            return ErrorKind::Dns;
+        }
+        if ["tls", "ssl", "certificate", "handshake"].iter().any(|m| message.contains(m)) {
+            return ErrorKind::Tls;
+        }
+        current = error.source();
+    }
+    ErrorKind::Other
+}
+
+/// The error and its causes joined with `: `, skipping causes already in the message.
+pub fn error_chain(error: &(dyn Error + 'static)) -> String {
+    let mut text = error.to_string();
+    let mut current = error.source();
+    while let Some(cause) = current {
+        let cause_text = cause.to_string();
+        if !text.contains(&cause_text) {
+            text = format!("{}: {}", text, cause_text);
+        }
+        current = cause.source();
+    }
+    text
+}
+
+/// Upper bounds of the histogram buckets in milliseconds; a last bucket takes everything slower.
+const HISTOGRAM_BOUNDS_MS: &[u64] = &[
+    1, 2, 5, 10, 20, 50, 100, 200, 500, 1_000, 2_000, 5_000, 10_000, 20_000, 30_000, 60_000,
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistogramBucket {
+    /// Responses in this bucket took less than this; `None` for the overflow bucket.
+    pub below_ms: Option<u64>,
+    pub count: usize,
+}
+
+impl HistogramBucket {
+    pub fn label(&self) -> String {
+        match self.below_ms {
+            Some(ms) => format!("<{}", format_ms(ms)),
+            None => format!("≥{}", format_ms(*HISTOGRAM_BOUNDS_MS.last().unwrap_or(&0))),
+        }
+    }
+}
+
+fn format_ms(ms: u64) -> String {
+    match ms {
+        ms if ms >= 1_000 && ms % 1_000 == 0 => format!("{}s", ms / 1_000),
+        ms if ms >= 1_000 => format!("{:.1}s", ms as f64 / 1_000.0),
+        ms => format!("{}ms", ms),
+    }
+}
+
+/// Response time counts in log-scale buckets.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct LatencyHistogram {
+    pub buckets: Vec<HistogramBucket>,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        let buckets = HISTOGRAM_BOUNDS_MS.iter()
+            .map(|&ms| Some(ms))
+            .chain([None])
+            .map(|below_ms| HistogramBucket { below_ms, count: 0 })
+            .collect();
+        Self { buckets }
+    }
+}
+
+impl LatencyHistogram {
+    pub fn from_latencies(latencies: &[Duration]) -> Self {
+        let mut histogram = Self::default();
+        for latency in latencies {
+            let index = HISTOGRAM_BOUNDS_MS.iter()
+                .position(|&ms| *latency < Duration::from_millis(ms))
+                .unwrap_or(HISTOGRAM_BOUNDS_MS.len());
+            histogram.buckets[index].count += 1;
+        }
+        histogram
+    }
+
+    pub fn total(&self) -> usize {
+        self.buckets.iter().map(|b| b.count).sum()
+    }
+
+    /// One bar per bucket from the fastest to the slowest non-empty one, with counts and
+    /// percentages, `bar_width` characters for the fullest bucket. Empty when there are no responses.
+    pub fn render(&self, bar_width: usize) -> String {
+        let total = self.total();
+        let (Some(first), Some(last)) = (
+            self.buckets.iter().position(|b| b.count > 0),
+            self.buckets.iter().rposition(|b| b.count > 0),
+        ) else {
+            return String::new();
+        };
+        let max = self.buckets.iter().map(|b| b.count).max().unwrap_or(1);
+        let count_width = max.to_string().len();
+
+        self.buckets[first..=last].iter()
+            .map(|bucket| {
+                let mut bar = "█".repeat(bucket.count * bar_width / max);
+                if bar.is_empty() && bucket.count > 0 {
+                    bar.push('▏');
+                }
+                let padding = " ".repeat(bar_width - bar.chars().count());
+                format!(
+                    "{:>7} │{}{} {:>count_width$} {:>5.1}%",
+                    bucket.label(), bar, padding, bucket.count, bucket.count as f64 * 100.0 / total as f64
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// One-line chart of per-second request counts, at most `width` characters. Seconds without a
+/// sample count as zero; long runs are averaged down to fit.
+pub fn sparkline(samples: &[(SystemTime, usize)], width: usize) -> String {
+    let Some((first, _)) = samples.first() else {
+        return String::new();
+    };
+    let mut per_second: Vec<f64> = Vec::new();
+    for (at, count) in samples {
+        let second = at.duration_since(*first).unwrap_or_default().as_secs() as usize;
+        if per_second.len() <= second {
+            per_second.resize(second + 1, 0.0);
+        }
+        per_second[second] += *count as f64;
+    }
+
+    let chunk = per_second.len().div_ceil(width.max(1));
+    let points: Vec<f64> = per_second.chunks(chunk).map(|c| c.iter().sum::<f64>() / c.len() as f64).collect();
+    bars(&points)
+}
+
+/// One bar character per value, scaled to the largest.
+pub fn bars(points: &[f64]) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = points.iter().cloned().fold(0.0, f64::max);
+    points.iter()
+        .map(|&value| match max > 0.0 {
+            true => LEVELS[((value / max) * (LEVELS.len() - 1) as f64).round() as usize],
+            false => LEVELS[0],
+        })
+        .collect()
+}
+
+fn as_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
 }
 
 pub struct Metrics {
     latencies: Mutex<Vec<Duration>>,
     status_codes: Mutex<HashMap<u16, usize>>,
     requests_per_second: Mutex<Vec<(SystemTime, usize)>>,
-    errors: Mutex<Vec<String>>,
+    errors: Mutex<BTreeMap<ErrorKind, ErrorCount>>,
+    /// Responses per negotiated HTTP version.
+    protocols: Mutex<BTreeMap<String, usize>>,
+    connections: Mutex<ConnectionLog>,
     start_time: SystemTime,
+    /// Requests started before this are counted as warmup only.
+    warmup_until: Option<SystemTime>,
+    warmup_requests: Mutex<usize>,
+    /// Fraction of the slowest responses left out of the trimmed stats.
+    trim_outliers: Option<f64>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Metrics {
@@ -38,12 +354,46 @@ impl Metrics {
             latencies: Mutex::new(Vec::new()),
             status_codes: Mutex::new(HashMap::new()),
             requests_per_second: Mutex::new(Vec::new()),
-            errors: Mutex::new(Vec::new()),
+            errors: Mutex::new(BTreeMap::new()),
+            protocols: Mutex::new(BTreeMap::new()),
+            connections: Mutex::new(ConnectionLog::default()),
             start_time: SystemTime::now(),
+            warmup_until: None,
+            warmup_requests: Mutex::new(0),
+            trim_outliers: None,
+        }
+    }
+
+    /// Keep requests started before `until` out of the summary.
+    pub fn with_warmup(mut self, until: SystemTime) -> Self {
+        self.warmup_until = Some(until);
+        self
+    }
+
+    /// Add average and standard deviation without the slowest `fraction` of responses
+    /// to the summary. Percentiles always cover every response.
+    pub fn with_trimmed_outliers(mut self, fraction: f64) -> Self {
+        self.trim_outliers = Some(fraction);
+        self
+    }
+
+    /// Counts the request as warmup if it started before the cutoff.
+    fn warmup(&self, started: SystemTime) -> bool {
+        let warming = self.warmup_until.is_some_and(|until| started < until);
+        if warming {
+            *self.warmup_requests.lock().unwrap() += 1;
         }
+        warming
+    }
+
+    pub fn warmup_requests(&self) -> usize {
+        *self.warmup_requests.lock().unwrap()
     }
 
     pub fn record(&self, metric: RequestMetric) {
+        if self.warmup(metric.timestamp) {
+            return;
+        }
         let mut latencies = self.latencies.lock().unwrap();
         let mut status_codes = self.status_codes.lock().unwrap();
         let mut rps = self.requests_per_second.lock().unwrap();
@@ -73,38 +423,30 @@ impl Metrics {
         let latencies = self.latencies.lock().unwrap();
         let rps = self.requests_per_second.lock().unwrap();
         
+        let errors = self.errors.lock().unwrap().clone();
         MetricsSummary {
+            timeouts: errors.get(&ErrorKind::Timeout).map_or(0, |timeouts| timeouts.count),
             avg_latency: self.calculate_average(&latencies),
             p95_latency: self.calculate_percentile(&latencies, 95),
             p99_latency: self.calculate_percentile(&latencies, 99),
             total_requests: latencies.len(),
             error_rate: self.calculate_error_rate(),
-            response_time_ranges: self.calculate_response_time_ranges(&latencies),
+            status_codes: self.status_codes.lock().unwrap().iter().map(|(&code, &count)| (code, count)).collect(),
+            min_latency: latencies.iter().min().copied().unwrap_or_default(),
+            max_latency: latencies.iter().max().copied().unwrap_or_default(),
+            histogram: LatencyHistogram::from_latencies(&latencies),
+            errors,
             requests_per_second: rps.clone(),
             peak_rps: rps.iter().map(|(_, count)| *count).max().unwrap_or(0),
             median_latency: self.calculate_percentile(&latencies, 50),
             std_dev_latency: self.calculate_std_dev(&latencies),
+            warmup_requests: self.warmup_requests(),
+            trimmed: self.trim_outliers.map(|fraction| trim_latencies(&latencies, fraction)),
+            connections: self.connections.lock().unwrap().stats(),
         }
     }
 
-    fn calculate_response_time_ranges(&self, latencies: &Vec<Duration>) -> HashMap<String, usize> {
-        let mut ranges = HashMap::new();
-        
-        for &latency in latencies {
-            let ms = latency.as_millis();
-            let range = match ms {
-                ms if ms < 800 => "<800ms",
-                ms if ms < 1200 => "<1.2s",
-                ms if ms < 2000 => "<2s",
-                _ => ">2s",
-            };
-            *ranges.entry(range.to_string()).or_insert(0) += 1;
-        }
-        
-        ranges
-    }
-
-    fn calculate_std_dev(&self, latencies: &Vec<Duration>) -> f64 {
+    fn calculate_std_dev(&self, latencies: &[Duration]) -> f64 {
         if latencies.is_empty() {
             return 0.0;
         }
@@ -120,7 +462,7 @@ impl Metrics {
         variance.sqrt()
     }
 
-    fn calculate_average(&self, latencies: &Vec<Duration>) -> Duration {
+    fn calculate_average(&self, latencies: &[Duration]) -> Duration {
         if latencies.is_empty() {
             return Duration::from_secs(0);
         }
@@ -128,11 +470,11 @@ impl Metrics {
         sum / latencies.len() as u32
     }
 
-    fn calculate_percentile(&self, latencies: &Vec<Duration>, percentile: usize) -> Duration {
+    fn calculate_percentile(&self, latencies: &[Duration], percentile: usize) -> Duration {
         if latencies.is_empty() {
             return Duration::from_secs(0);
         }
-        let mut sorted = latencies.clone();
+        let mut sorted = latencies.to_vec();
         sorted.sort();
         let index = (percentile * sorted.len() / 100).saturating_sub(1);
         sorted[index]
@@ -152,8 +494,35 @@ impl Metrics {
         errors as f64 / total as f64
     }
 
-    pub fn record_error(&self, error: String) {
-        let mut errors = self.errors.lock().unwrap();
-        errors.push(error);
+    pub fn record_error(&self, kind: ErrorKind, message: String) {
+        if self.warmup(SystemTime::now()) {
+            return;
+        }
+        self.errors.lock().unwrap()
+            .entry(kind)
+            .and_modify(|errors| errors.count += 1)
+            .or_insert(ErrorCount { count: 1, example: message });
+    }
+
+    pub fn record_protocol(&self, version: &str) {
+        *self.protocols.lock().unwrap().entry(version.to_string()).or_insert(0) += 1;
+    }
+
+    /// Note the local address a response came in on: one not seen before means the request
+    /// opened a new connection. Warmup responses still mark their connection as seen.
+    pub fn record_connection(&self, local_addr: SocketAddr, started: SystemTime, latency: Duration) {
+        let mut log = self.connections.lock().unwrap();
+        let new = log.seen.insert(local_addr);
+        if self.warmup_until.is_some_and(|until| started < until) {
+            return;
+        }
+        let (count, total) = if new { &mut log.new } else { &mut log.reused };
+        *count += 1;
+        *total += latency;
+    }
+
+    /// Number of responses per HTTP version, e.g. `{"HTTP/2.0": 120}`.
+    pub fn protocols(&self) -> BTreeMap<String, usize> {
+        self.protocols.lock().unwrap().clone()
     }
 }
@@ -0,0 +1,292 @@
+//! Where command output goes.
+//!
+//! With `--json` a command's result is printed as one JSON document on stdout, so it can be
+//! piped into `jq`. Everything meant for people (progress, tables, the response as usually
+//! shown) goes through [`outln!`]/[`out!`] and moves to stderr while that mode is on.
+//!
+//! `--quiet` leaves out decoration: lines printed with [`noteln!`] (banners, progress notes),
+//! emoji at the start of the rest, and spinners. Colors follow `NO_COLOR`, `config set color`
+//! and `--no-color`. [`capture`] collects a command's output instead of printing it.
+//!
+//! [`Table`] lays out JSON arrays of objects for `call --table` and `--csv`.
+
+use console::{measure_text_width, pad_str, truncate_str, Alignment};
+use indicatif::{ProgressBar, ProgressDrawTarget};
+use serde::de::{Deserializer, MapAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::future::Future;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+static JSON: AtomicBool = AtomicBool::new(false);
+static QUIET: AtomicBool = AtomicBool::new(false);
+/// Whether stdout and stderr support colors at all, as `console` detected it at startup.
+static COLOR_SUPPORT: OnceLock<(bool, bool)> = OnceLock::new();
+
+tokio::task_local! {
+    static CAPTURED: RefCell<String>;
+}
+
+pub fn set_json(enabled: bool) {
+    JSON.store(enabled, Ordering::SeqCst);
+}
+
+/// Whether `--json` is in effect for the running command.
+pub fn is_json() -> bool {
+    JSON.load(Ordering::SeqCst)
+}
+
+pub fn set_quiet(enabled: bool) {
+    QUIET.store(enabled, Ordering::SeqCst);
+}
+
+/// Whether `--quiet` is in effect for the running command.
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::SeqCst)
+}
+
+/// Turn ANSI colors on or off, on stdout and stderr alike. Never turns them on for a
+/// stream that isn't a color terminal.
+pub fn set_color(enabled: bool) {
+    let (stdout, stderr) = *COLOR_SUPPORT.get_or_init(|| (console::colors_enabled(), console::colors_enabled_stderr()));
+    console::set_colors_enabled(enabled && stdout);
+    console::set_colors_enabled_stderr(enabled && stderr);
+}
+
+pub fn is_color() -> bool {
+    console::colors_enabled()
+}
+
+/// Whether colors are wanted: never when `NO_COLOR` is set to anything but an empty string,
+/// otherwise as `config set color` says (on by default, for terminals).
+pub fn color_wanted(configured: Option<bool>) -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    !no_color && configured.unwrap_or(true)
+}
+
+/// Whether spinners and live progress lines should be drawn: only on a terminal, and not
+/// under `--quiet` or `--json`.
+pub fn progress_visible() -> bool {
+    !is_quiet() && !is_json() && std::io::stdout().is_terminal()
+}
+
+/// `bar`, hidden unless [`progress_visible`].
+pub fn progress(bar: ProgressBar) -> ProgressBar {
+    if !progress_visible() {
+        bar.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    bar
+}
+
+/// Run `future`, collecting everything it prints through this module instead of printing it.
+pub async fn capture<F: Future>(future: F) -> (F::Output, String) {
+    CAPTURED.scope(RefCell::new(String::new()), async {
+        let output = future.await;
+        (output, CAPTURED.with(|captured| captured.take()))
+    }).await
+}
+
+/// Print human-readable text: stdout normally, stderr under `--json`, without leading emoji
+/// under `--quiet`. Used by [`outln!`] and [`out!`].
+pub fn print(text: &str) {
+    let text = if is_quiet() { strip_emoji(text) } else { text.to_string() };
+    write(&text, is_json());
+}
+
+/// Print the command's result on stdout, pretty-printed.
+pub fn emit_json(value: &impl Serialize) -> Result<(), Box<dyn std::error::Error>> {
+    write(&format!("{}\n", serde_json::to_string_pretty(value)?), false);
+    Ok(())
+}
+
+fn write(text: &str, to_stderr: bool) {
+    let captured = CAPTURED.try_with(|captured| captured.borrow_mut().push_str(text));
+    if captured.is_err() {
+        if to_stderr {
+            eprint!("{}", text);
+        } else {
+            print!("{}", text);
+        }
+    }
+}
+
+/// `text` without emoji and the space that follows each one.
+pub fn strip_emoji(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut after_emoji = false;
+    for c in text.chars() {
+        if is_emoji(c) {
+            after_emoji = true;
+            continue;
+        }
+        if !(after_emoji && c == ' ') {
+            stripped.push(c);
+        }
+        after_emoji = false;
+    }
+    stripped
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF     // pictographs, emoticons, transport, symbols
+        | 0x2600..=0x27BF     // miscellaneous symbols and dingbats (⚡ ✅ ✓ ❌)
+        | 0x23E9..=0x23FA     // media controls and clocks (⏱ ⏳ ⏭)
+        | 0x2B50 | 0x2B55 | 0x2139
+        | 0xFE0F | 0x200D)    // variation selector, zero-width joiner
+}
+
+/// A JSON array of objects as rows, for `--table` and `--csv`. Columns are the union of the
+/// objects' keys, in the order they first appear; nested values are kept as JSON text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub columns: Vec<String>,
+    /// One cell per column; `None` where the object has no such key or it is `null`.
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+/// The fields of one object, in the order written (`Value` would sort them).
+struct Row(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for Row {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RowVisitor;
+
+        impl<'de> Visitor<'de> for RowVisitor {
+            type Value = Row;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Row, A::Error> {
+                let mut fields = Vec::new();
+                while let Some(field) = map.next_entry::<String, Value>()? {
+                    fields.push(field);
+                }
+                Ok(Row(fields))
+            }
+        }
+
+        deserializer.deserialize_map(RowVisitor)
+    }
+}
+
+impl Table {
+    /// The rows of `json`, or `None` unless it is an array whose items are all objects.
+    pub fn from_json(json: &str) -> Option<Table> {
+        let objects: Vec<Row> = serde_json::from_str(json).ok()?;
+        let mut columns: Vec<String> = Vec::new();
+        for Row(fields) in &objects {
+            for (key, _) in fields {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+        let rows = objects.into_iter()
+            .map(|Row(fields)| {
+                let mut row = vec![None; columns.len()];
+                for (key, value) in fields {
+                    let column = columns.iter().position(|column| *column == key).unwrap();
+                    row[column] = match value {
+                        Value::Null => None,
+                        Value::String(text) => Some(text),
+                        other => Some(other.to_string()),
+                    };
+                }
+                row
+            })
+            .collect();
+        Some(Table { columns, rows })
+    }
+
+    /// Columns padded to their widest cell as it shows on a terminal, so CJK text and emoji
+    /// line up, and cut with `…` past `max_col_width` (no limit when `None`).
+    pub fn render(&self, max_col_width: Option<usize>) -> String {
+        if self.rows.is_empty() {
+            return "(no rows)".to_string();
+        }
+        let fit = |text: &str| -> String {
+            // Line breaks would split the row
+            let text = text.replace(['\n', '\r', '\t'], " ");
+            match max_col_width {
+                Some(max) if measure_text_width(&text) > max => truncate_str(&text, max.max(1), "…").into_owned(),
+                _ => text,
+            }
+        };
+        let header: Vec<String> = self.columns.iter().map(|column| fit(column)).collect();
+        let cells: Vec<Vec<String>> = self.rows.iter()
+            .map(|row| row.iter().map(|cell| fit(cell.as_deref().unwrap_or(""))).collect())
+            .collect();
+        let widths: Vec<usize> = (0..header.len())
+            .map(|i| cells.iter().map(|row| &row[i]).chain([&header[i]]).map(|cell| measure_text_width(cell)).max().unwrap_or(0))
+            .collect();
+
+        let line = |cells: Vec<String>| -> String {
+            let padded: Vec<String> = cells.iter().zip(&widths)
+                .map(|(cell, &width)| pad_str(cell, width, Alignment::Left, None).into_owned())
+                .collect();
+            padded.join("  ").trim_end().to_string()
+        };
+        let mut lines = vec![line(header), line(widths.iter().map(|&width| "─".repeat(width)).collect())];
+        lines.extend(cells.into_iter().map(line));
+        lines.join("\n")
+    }
+
+    /// RFC 4180 CSV with a header row. Cells holding commas, quotes, line breaks or
+    /// surrounding spaces are quoted; missing and `null` values are empty.
+    pub fn to_csv(&self) -> String {
+        let record = |cells: Vec<&str>| -> String {
+            cells.into_iter().map(csv_field).collect::<Vec<_>>().join(",")
+        };
+        let mut csv = record(self.columns.iter().map(String::as_str).collect());
+        csv.push('\n');
+        for row in &self.rows {
+            csv.push_str(&record(row.iter().map(|cell| cell.as_deref().unwrap_or("")).collect()));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    let quote = text.contains([',', '"', '\n', '\r']) || text.starts_with(' ') || text.ends_with(' ');
+    if quote {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// `println!` for human-readable output: stdout normally, stderr under `--json`.
+#[macro_export]
+macro_rules! outln {
+    () => {
+        $crate::output::print("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::output::print(&format!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+/// `print!` counterpart of [`outln!`].
+#[macro_export]
+macro_rules! out {
+    ($($arg:tt)*) => {
+        $crate::output::print(&format!($($arg)*))
+    };
+}
+
+/// [`outln!`] for decoration such as banners and progress notes, left out under `--quiet`.
+#[macro_export]
+macro_rules! noteln {
+    ($($arg:tt)*) => {
+        if !$crate::output::is_quiet() {
+            $crate::outln!($($arg)*)
+        }
+    };
+}
@@ -0,0 +1,378 @@
+//! The part of jq that `call --jq` understands, for picking pieces out of a JSON response.
+//!
+//! Filters: `.` and `.field` (or `."any key"`, `.["any key"]`), `.[2]` and `.[-1]`, slices
+//! like `.[2:5]` on arrays and strings, `.[]` over array items or object values, `?` after a
+//! step to skip values it doesn't apply to, `,` for several results, `|` to feed results into
+//! the next filter, parentheses, and the functions `length` and `keys`.
+
+use serde_json::Value;
+use std::fmt;
+
+/// A parsed filter, checked before any request is sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    source: String,
+    filter: Filter,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Pipe(Vec<Filter>),
+    Comma(Vec<Filter>),
+    Term(Base, Vec<Suffix>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Base {
+    Identity,
+    Group(Box<Filter>),
+    Length,
+    Keys,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Suffix {
+    step: Step,
+    /// Followed by `?`: values the step can't apply to are dropped instead of failing.
+    optional: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Field(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Iterate,
+}
+
+impl Query {
+    pub fn parse(source: &str) -> Result<Query, String> {
+        let mut parser = Parser { chars: source.chars().collect(), pos: 0 };
+        let filter = parser.pipe()?;
+        parser.skip_whitespace();
+        if let Some(c) = parser.peek() {
+            return Err(parser.error(&format!("unexpected '{}'", c)));
+        }
+        Ok(Query { source: source.to_string(), filter })
+    }
+
+    /// Every result of the filter on `input`, in order.
+    pub fn apply(&self, input: &Value) -> Result<Vec<Value>, String> {
+        eval(&self.filter, input)
+    }
+
+    /// The filter run on a JSON response body, with the results printed as jq does: each
+    /// one pretty-printed, on lines of its own.
+    pub fn apply_to_text(&self, body: &str) -> Result<String, String> {
+        let input: Value = serde_json::from_str(body).map_err(|e| format!("--jq needs a JSON response, but it didn't parse: {}", e))?;
+        let results = self.apply(&input).map_err(|e| format!("--jq '{}' failed: {}", self.source, e))?;
+        Ok(results.iter()
+            .map(|value| serde_json::to_string_pretty(value).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skip whitespace, then take `c` if it comes next.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at column {}", message, self.pos + 1)
+    }
+
+    fn pipe(&mut self) -> Result<Filter, String> {
+        let mut filters = vec![self.comma()?];
+        while self.eat('|') {
+            filters.push(self.comma()?);
+        }
+        Ok(if filters.len() == 1 { filters.remove(0) } else { Filter::Pipe(filters) })
+    }
+
+    fn comma(&mut self) -> Result<Filter, String> {
+        let mut filters = vec![self.term()?];
+        while self.eat(',') {
+            filters.push(self.term()?);
+        }
+        Ok(if filters.len() == 1 { filters.remove(0) } else { Filter::Comma(filters) })
+    }
+
+    fn term(&mut self) -> Result<Filter, String> {
+        self.skip_whitespace();
+        let mut suffixes = Vec::new();
+        let base = match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.pipe()?;
+                self.expect(')')?;
+                Base::Group(Box::new(inner))
+            }
+            Some('.') => {
+                self.pos += 1;
+                match self.peek() {
+                    Some('.') => return Err(self.error("recursive descent '..' isn't supported")),
+                    Some(c) if is_ident_start(c) => suffixes.push(Suffix { step: Step::Field(self.ident()), optional: false }),
+                    Some('"') => suffixes.push(Suffix { step: Step::Field(self.string()?), optional: false }),
+                    _ => {}
+                }
+                Base::Identity
+            }
+            Some(c) if is_ident_start(c) => match self.ident().as_str() {
+                "length" => Base::Length,
+                "keys" => Base::Keys,
+                other => return Err(self.error(&format!("unknown function '{}', only length and keys are supported", other))),
+            },
+            Some(c) => return Err(self.error(&format!("unexpected '{}'", c))),
+            None => return Err(self.error("expected a filter")),
+        };
+
+        loop {
+            match self.peek() {
+                Some('.') if self.chars.get(self.pos + 1).is_some_and(|&c| is_ident_start(c) || c == '"' || c == '[') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('[') => suffixes.push(self.bracket()?),
+                        Some('"') => suffixes.push(Suffix { step: Step::Field(self.string()?), optional: false }),
+                        _ => suffixes.push(Suffix { step: Step::Field(self.ident()), optional: false }),
+                    }
+                }
+                Some('[') => suffixes.push(self.bracket()?),
+                Some('?') => {
+                    self.pos += 1;
+                    match suffixes.last_mut() {
+                        Some(last) => last.optional = true,
+                        None => return Err(self.error("'?' needs a step before it, as in .items[]?")),
+                    }
+                }
+                _ => break,
+            }
+        }
+        Ok(Filter::Term(base, suffixes))
+    }
+
+    /// `[]`, `["key"]`, `[n]` or `[from:to]`, starting at the `[`.
+    fn bracket(&mut self) -> Result<Suffix, String> {
+        self.pos += 1;
+        if self.eat(']') {
+            return Ok(Suffix { step: Step::Iterate, optional: false });
+        }
+        let step = if self.peek() == Some('"') {
+            Step::Field(self.string()?)
+        } else {
+            let from = self.integer()?;
+            if self.eat(':') {
+                let to = self.integer()?;
+                if from.is_none() && to.is_none() {
+                    return Err(self.error("a slice needs a start or an end"));
+                }
+                Step::Slice(from, to)
+            } else {
+                Step::Index(from.ok_or_else(|| self.error("expected an index, a slice or a quoted key"))?)
+            }
+        };
+        self.expect(']')?;
+        Ok(Suffix { step, optional: false })
+    }
+
+    fn integer(&mut self) -> Result<Option<i64>, String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        text.parse().map(Some).map_err(|_| self.error(&format!("'{}' isn't an index", text)))
+    }
+
+    fn ident(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// A double-quoted key with JSON escapes, starting at the opening quote.
+    fn string(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut escaped = false;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => {
+                    let literal: String = self.chars[start..self.pos].iter().collect();
+                    return serde_json::from_str(&literal).map_err(|e| self.error(&format!("invalid string: {}", e)));
+                }
+                _ => escaped = false,
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn eval(filter: &Filter, input: &Value) -> Result<Vec<Value>, String> {
+    match filter {
+        Filter::Pipe(filters) => {
+            let mut values = vec![input.clone()];
+            for filter in filters {
+                let mut next = Vec::new();
+                for value in &values {
+                    next.extend(eval(filter, value)?);
+                }
+                values = next;
+            }
+            Ok(values)
+        }
+        Filter::Comma(filters) => {
+            let mut values = Vec::new();
+            for filter in filters {
+                values.extend(eval(filter, input)?);
+            }
+            Ok(values)
+        }
+        Filter::Term(base, suffixes) => {
+            let mut values = match base {
+                Base::Identity => vec![input.clone()],
+                Base::Group(inner) => eval(inner, input)?,
+                Base::Length => vec![length(input)?],
+                Base::Keys => vec![keys(input)?],
+            };
+            for suffix in suffixes {
+                let mut next = Vec::new();
+                for value in &values {
+                    match step(&suffix.step, value) {
+                        Ok(results) => next.extend(results),
+                        Err(_) if suffix.optional => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+                values = next;
+            }
+            Ok(values)
+        }
+    }
+}
+
+fn step(step: &Step, value: &Value) -> Result<Vec<Value>, String> {
+    match (step, value) {
+        (Step::Iterate, Value::Array(items)) => Ok(items.clone()),
+        (Step::Iterate, Value::Object(fields)) => Ok(fields.values().cloned().collect()),
+        (Step::Iterate, other) => Err(format!("cannot iterate over {}", kind(other))),
+        // Like jq, any step on null gives null
+        (_, Value::Null) => Ok(vec![Value::Null]),
+        (Step::Field(name), Value::Object(fields)) => Ok(vec![fields.get(name).cloned().unwrap_or(Value::Null)]),
+        (Step::Field(name), other) => Err(format!("cannot index {} with \"{}\"", kind(other), name)),
+        (Step::Index(index), Value::Array(items)) => {
+            Ok(vec![position(*index, items.len()).and_then(|i| items.get(i)).cloned().unwrap_or(Value::Null)])
+        }
+        (Step::Index(_), other) => Err(format!("cannot index {} with a number", kind(other))),
+        (Step::Slice(from, to), Value::Array(items)) => {
+            let (from, to) = bounds(*from, *to, items.len());
+            Ok(vec![Value::Array(items[from..to].to_vec())])
+        }
+        (Step::Slice(from, to), Value::String(text)) => {
+            let chars: Vec<char> = text.chars().collect();
+            let (from, to) = bounds(*from, *to, chars.len());
+            Ok(vec![Value::String(chars[from..to].iter().collect())])
+        }
+        (Step::Slice(..), other) => Err(format!("cannot slice {}", kind(other))),
+    }
+}
+
+/// Where `index` points in a sequence of `len`, counting from the end when negative.
+fn position(index: i64, len: usize) -> Option<usize> {
+    if index < 0 {
+        len.checked_sub(index.unsigned_abs() as usize)
+    } else {
+        Some(index as usize)
+    }
+}
+
+/// Slice bounds clamped to the sequence, never crossing.
+fn bounds(from: Option<i64>, to: Option<i64>, len: usize) -> (usize, usize) {
+    let clamp = |index: Option<i64>, default: usize| index.map_or(default, |index| position(index, len).unwrap_or(0).min(len));
+    let from = clamp(from, 0);
+    (from, clamp(to, len).max(from))
+}
+
+fn length(value: &Value) -> Result<Value, String> {
+    Ok(match value {
+        Value::Null => 0.into(),
+        // The absolute value, as in jq
+        Value::Number(n) => n.as_u64().map(Value::from)
+            .or_else(|| n.as_i64().map(|i| i.unsigned_abs().into()))
+            .unwrap_or_else(|| n.as_f64().map_or(Value::Null, |f| f.abs().into())),
+        Value::String(text) => text.chars().count().into(),
+        Value::Array(items) => items.len().into(),
+        Value::Object(fields) => fields.len().into(),
+        Value::Bool(_) => return Err("boolean has no length".to_string()),
+    })
+}
+
+fn keys(value: &Value) -> Result<Value, String> {
+    match value {
+        Value::Object(fields) => Ok(fields.keys().cloned().collect::<Vec<_>>().into()),
+        Value::Array(items) => Ok((0..items.len()).collect::<Vec<_>>().into()),
+        other => Err(format!("{} has no keys", kind(other))),
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
@@ -0,0 +1,282 @@
+//! Session recordings: `record start <name>` keeps every `call` made in the shell until
+//! `record stop`, and `record replay <name>` runs them again as a test, expecting each step
+//! to return the status it returned while recording.
+//!
+//! IDs created along the way are not replayed verbatim. When a path segment of a later URL
+//! equals a value in an earlier JSON response, the segment becomes a `{{stepN_field}}`
+//! variable extracted from that response on replay, so the replay follows the new IDs.
+//! Credentials (`Authorization`, cookies, API keys) are never written to the file.
+
+use crate::capture::SENSITIVE_HEADERS;
+use crate::commands::call::{CallCommand, CallOptions, CallResponse};
+use crate::flows::variables::FlowVariables;
+use chrono::{DateTime, Utc};
+use console::style;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// One recorded call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedStep {
+    pub method: String,
+    /// May contain `{{variable}}` placeholders filled from earlier steps.
+    pub url: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    pub expect_status: u16,
+    /// Variables later steps need, as name -> path into this step's JSON response.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extract: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    pub name: String,
+    pub recorded_at: DateTime<Utc>,
+    pub steps: Vec<RecordedStep>,
+}
+
+/// A recording in progress.
+#[derive(Debug)]
+pub struct Recorder {
+    name: String,
+    started: DateTime<Utc>,
+    steps: Vec<RecordedStep>,
+    /// Parsed response of each step, for spotting IDs reused by later steps.
+    responses: Vec<Option<Value>>,
+}
+
+impl Recorder {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), started: Utc::now(), steps: Vec::new(), responses: Vec::new() }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Add a completed call. Path segments matching a value from an earlier response
+    /// are replaced with a variable extracted from that response.
+    pub fn record(&mut self, response: &CallResponse) {
+        let headers = response.request_headers.iter()
+            .filter(|(name, _)| !SENSITIVE_HEADERS.contains(&name.to_ascii_lowercase().as_str()))
+            .cloned()
+            .collect();
+        let url = self.templated_url(&response.url);
+
+        self.steps.push(RecordedStep {
+            method: response.method.to_uppercase(),
+            url,
+            headers,
+            body: response.request_body.clone(),
+            expect_status: response.status,
+            extract: BTreeMap::new(),
+        });
+        self.responses.push(serde_json::from_str(&response.body).ok());
+    }
+
+    fn templated_url(&mut self, url: &str) -> String {
+        let Ok(parsed) = url::Url::parse(url) else {
+            return url.to_string();
+        };
+        let segments: Vec<String> = parsed.path().split('/').map(str::to_string).collect();
+        let mut changed = false;
+        let templated: Vec<String> = segments.iter()
+            .map(|segment| {
+                // Only segments that look like IDs; `users` matching a "users" value is noise
+                if !segment.chars().any(|c| c.is_ascii_digit()) {
+                    return segment.clone();
+                }
+                match self.variable_for(segment) {
+                    Some(variable) => {
+                        changed = true;
+                        format!("{{{{{}}}}}", variable)
+                    }
+                    None => segment.clone(),
+                }
+            })
+            .collect();
+        if !changed {
+            return url.to_string();
+        }
+        // Url would percent-encode the braces, so the templated path is spliced in as text
+        format!(
+            "{}{}{}",
+            &parsed[..url::Position::BeforePath],
+            templated.join("/"),
+            &parsed[url::Position::AfterPath..]
+        )
+    }
+
+    /// The variable holding `value` in the most recent response that contains it,
+    /// registering the extraction on that step.
+    fn variable_for(&mut self, value: &str) -> Option<String> {
+        let (index, path) = self.responses.iter().enumerate().rev()
+            .find_map(|(i, response)| find_value(response.as_ref()?, value, "$").map(|path| (i, path)))?;
+        let field = path.rsplit(['.', '[']).next().unwrap_or("value").trim_end_matches(']');
+        let name = format!("step{}_{}", index + 1, field);
+        self.steps[index].extract.insert(name.clone(), path);
+        Some(name)
+    }
+
+    pub fn finish(self) -> Recording {
+        Recording { name: self.name, recorded_at: self.started, steps: self.steps }
+    }
+}
+
+/// Path to the first scalar in `json` (keys in sorted order) whose text equals `value`.
+pub fn find_value(json: &Value, value: &str, path: &str) -> Option<String> {
+    match json {
+        Value::Object(map) => map.iter().find_map(|(key, child)| find_value(child, value, &format!("{}.{}", path, key))),
+        Value::Array(items) => items.iter().enumerate().find_map(|(i, child)| find_value(child, value, &format!("{}[{}]", path, i))),
+        Value::String(s) if s == value => Some(path.to_string()),
+        Value::Number(n) if n.to_string() == value => Some(path.to_string()),
+        _ => None,
+    }
+}
+
+/// Recordings kept as `<dir>/<name>.yaml`.
+pub struct RecordingStore {
+    dir: PathBuf,
+}
+
+impl RecordingStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `~/.nuts/recordings`.
+    pub fn default_dir() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(dirs::home_dir()
+            .ok_or("Could not find home directory")?
+            .join(".nuts")
+            .join("recordings"))
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.yaml", name))
+    }
+
+    pub fn save(&self, recording: &Recording) -> Result<PathBuf, Box<dyn Error>> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(&recording.name);
+        std::fs::write(&path, serde_yaml::to_string(recording)?)?;
+        Ok(path)
+    }
+
+    pub fn load(&self, name: &str) -> Result<Recording, Box<dyn Error>> {
+        let path = self.path(name);
+        let content = std::fs::read_to_string(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => format!("No recording named '{}' in {}", name, self.dir.display()),
+            _ => format!("Could not read {}: {}", path.display(), e),
+        })?;
+        serde_yaml::from_str(&content).map_err(|e| format!("Invalid recording {}: {}", path.display(), e).into())
+    }
+
+    /// Names of saved recordings, sorted.
+    pub fn list(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "yaml"))
+            .filter_map(|path| path.file_stem().and_then(|s| s.to_str()).map(str::to_string))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+}
+
+/// How one step went on replay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepOutcome {
+    pub method: String,
+    /// The URL actually requested, variables filled in.
+    pub url: String,
+    pub expected: u16,
+    pub actual: Option<u16>,
+    pub error: Option<String>,
+}
+
+impl StepOutcome {
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.actual == Some(self.expected)
+    }
+}
+
+/// Run every step of `recording` through `call`, printing a pass/fail line per step.
+/// A failed step doesn't stop the replay; steps that need its variables fail in turn.
+pub async fn replay(call: &CallCommand, recording: &Recording) -> Vec<StepOutcome> {
+    let mut variables = FlowVariables::default();
+    let mut outcomes = Vec::new();
+    let producer = |name: &str| {
+        recording.steps.iter().position(|step| step.extract.contains_key(name)).map(|i| format!("{}", i + 1))
+    };
+
+    for (i, step) in recording.steps.iter().enumerate() {
+        let label = format!("{}", i + 1);
+        let mut outcome = StepOutcome {
+            method: step.method.clone(),
+            url: step.url.clone(),
+            expected: step.expect_status,
+            actual: None,
+            error: None,
+        };
+
+        let request = (|| -> Result<CallOptions, Box<dyn Error>> {
+            let mut headers = std::collections::HashMap::new();
+            for (name, value) in &step.headers {
+                headers.insert(name.clone(), variables.substitute(value, &label, producer)?);
+            }
+            Ok(CallOptions {
+                method: step.method.clone(),
+                url: variables.substitute(&step.url, &label, producer)?,
+                headers,
+                body: step.body.as_deref().map(|body| variables.substitute(body, &label, producer)).transpose()?,
+                ..Default::default()
+            })
+        })();
+
+        match request {
+            Ok(options) => {
+                outcome.url = options.url.clone();
+                match call.send_quiet(options).await {
+                    Ok((status, body)) => {
+                        outcome.actual = Some(status);
+                        if let Err(e) = variables.extract_all(&step.extract, &body, &label) {
+                            outcome.error = Some(e.to_string());
+                        }
+                    }
+                    Err(e) => outcome.error = Some(e.to_string()),
+                }
+            }
+            Err(e) => outcome.error = Some(e.to_string()),
+        }
+
+        let line = format!("{:>3}. {} {}", i + 1, outcome.method, outcome.url);
+        match (&outcome.error, outcome.actual) {
+            _ if outcome.passed() => println!("✅ {} → {}", line, style(outcome.expected).green()),
+            (Some(error), _) => println!("❌ {} → {}", line, style(error).red()),
+            (None, Some(actual)) => println!("❌ {} → {} (expected {})", line, style(actual).red(), outcome.expected),
+            (None, None) => {}
+        }
+        outcomes.push(outcome);
+    }
+    outcomes
+}
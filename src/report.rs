@@ -0,0 +1,136 @@
+//! Machine-readable performance reports: JSON, or JUnit XML for CI systems.
+
+use crate::models::metrics::MetricsSummary;
+use chrono::Utc;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::Duration;
+
+/// Test configuration recorded at the top of every report.
+#[derive(Debug, Clone, Serialize)]
+pub struct PerfConfig {
+    pub url: String,
+    pub method: String,
+    pub users: u32,
+    pub duration_secs: f64,
+}
+
+/// A finished perf run, ready to be written to disk.
+#[derive(Debug, Serialize)]
+pub struct PerfReport {
+    pub tool: String,
+    pub generated_at: String,
+    pub config: PerfConfig,
+    pub summary: MetricsSummary,
+    /// Reasons the run should be considered failed; rendered as JUnit failures.
+    pub failures: Vec<String>,
+}
+
+impl PerfReport {
+    pub fn new(url: &str, method: &str, users: u32, duration: Duration, summary: MetricsSummary) -> Self {
+        Self {
+            tool: format!("nuts {}", env!("CARGO_PKG_VERSION")),
+            generated_at: Utc::now().to_rfc3339(),
+            config: PerfConfig {
+                url: url.to_string(),
+                method: method.to_uppercase(),
+                users,
+                duration_secs: duration.as_secs_f64(),
+            },
+            summary,
+            failures: Vec::new(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// One test suite with a single test case for the run; each failure becomes a `<failure>`.
+    pub fn to_junit(&self) -> String {
+        let summary = &self.summary;
+        let name = format!("{} {}", self.config.method, self.config.url);
+        let time = format!("{:.3}", self.config.duration_secs);
+
+        let mut properties = vec![
+            ("url".to_string(), self.config.url.clone()),
+            ("method".to_string(), self.config.method.clone()),
+            ("users".to_string(), self.config.users.to_string()),
+            ("duration_secs".to_string(), self.config.duration_secs.to_string()),
+            ("total_requests".to_string(), summary.total_requests.to_string()),
+            ("error_rate".to_string(), format!("{:.4}", summary.error_rate)),
+            ("avg_latency_ms".to_string(), summary.avg_latency.as_millis().to_string()),
+            ("median_latency_ms".to_string(), summary.median_latency.as_millis().to_string()),
+            ("p95_latency_ms".to_string(), summary.p95_latency.as_millis().to_string()),
+            ("p99_latency_ms".to_string(), summary.p99_latency.as_millis().to_string()),
+            ("peak_rps".to_string(), summary.peak_rps.to_string()),
+            ("timeouts".to_string(), summary.timeouts.to_string()),
+            ("connections.new".to_string(), summary.connections.new.to_string()),
+            ("connections.reused".to_string(), summary.connections.reused.to_string()),
+            ("connections.avg_new_latency_ms".to_string(), summary.connections.avg_new_latency.as_millis().to_string()),
+            ("connections.avg_reused_latency_ms".to_string(), summary.connections.avg_reused_latency.as_millis().to_string()),
+        ];
+        properties.extend(summary.status_codes.iter().map(|(code, count)| (format!("status.{}", code), count.to_string())));
+        properties.extend(summary.histogram.buckets.iter()
+            .filter(|bucket| bucket.count > 0)
+            .map(|bucket| (format!("latency.{}", bucket.label()), bucket.count.to_string())));
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(xml, "<testsuites name=\"nuts-perf\" tests=\"1\" failures=\"{}\" time=\"{}\">", self.failures.len().min(1), time);
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"1\" failures=\"{}\" errors=\"0\" time=\"{}\" timestamp=\"{}\">",
+            escape(&name), self.failures.len().min(1), time, escape(&self.generated_at)
+        );
+        xml.push_str("    <properties>\n");
+        for (key, value) in &properties {
+            let _ = writeln!(xml, "      <property name=\"{}\" value=\"{}\"/>", escape(key), escape(value));
+        }
+        xml.push_str("    </properties>\n");
+        let _ = write!(xml, "    <testcase classname=\"nuts.perf\" name=\"{}\" time=\"{}\"", escape(&name), time);
+        if self.failures.is_empty() {
+            xml.push_str("/>\n");
+        } else {
+            xml.push_str(">\n");
+            for failure in &self.failures {
+                let _ = writeln!(xml, "      <failure message=\"{}\" type=\"threshold\"/>", escape(failure));
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+
+    /// Write the report, picking the format from the extension (`.json` or `.xml`).
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let content = match extension(path).as_deref() {
+            Some("json") => self.to_json()?,
+            Some("xml") => self.to_junit(),
+            _ => return Err(format!("Unsupported report format for '{}': use a .json or .xml file", path.display()).into()),
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+/// Whether `path` has an extension `write` knows how to produce.
+pub fn is_supported(path: &Path) -> bool {
+    matches!(extension(path).as_deref(), Some("json") | Some("xml"))
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase)
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,151 @@
+//! Local test data for `generate`, used without an Anthropic key or with `--local`.
+//!
+//! Users, products and orders have the same fields the AI is asked for; any other type gets
+//! `id`, `name`, `description` and `created_at`. The same seed always produces the same records.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde_json::{json, Value};
+
+const FIRST_NAMES: &[&str] = &[
+    "Ada", "Amara", "Ben", "Carlos", "Chloé", "Dmitri", "Elena", "Farah", "Grace", "Hiroshi", "Ines", "Jamal",
+    "Kofi", "Lena", "Mateo", "Mei", "Noah", "Olivia", "Priya", "Quinn", "Rosa", "Sven", "Tariq", "Yuki", "Zoe",
+];
+const LAST_NAMES: &[&str] = &[
+    "Adeyemi", "Berg", "Chen", "Dubois", "Esposito", "Fischer", "García", "Hughes", "Ivanova", "Johansson",
+    "Kowalski", "Lopez", "Müller", "Nakamura", "O'Brien", "Patel", "Rossi", "Silva", "Tanaka", "Walker",
+];
+const STREETS: &[&str] = &["Oak Street", "Maple Avenue", "Station Road", "High Street", "Rue de la Paix", "Elm Drive", "Harbour Lane", "Mill Road"];
+const CITIES: &[(&str, &str)] = &[
+    ("Lisbon", "PT"), ("Berlin", "DE"), ("Austin", "US"), ("Lyon", "FR"), ("Osaka", "JP"),
+    ("Toronto", "CA"), ("Nairobi", "KE"), ("Melbourne", "AU"), ("Porto", "PT"), ("Denver", "US"),
+];
+const EMAIL_DOMAINS: &[&str] = &["example.com", "example.org", "example.net", "mail.example.com"];
+const ADJECTIVES: &[&str] = &["Classic", "Compact", "Deluxe", "Eco", "Ergonomic", "Portable", "Smart", "Vintage", "Wireless", "Rugged"];
+const PRODUCTS: &[(&str, &str)] = &[
+    ("Headphones", "electronics"), ("Keyboard", "electronics"), ("Desk Lamp", "home"), ("Backpack", "accessories"),
+    ("Water Bottle", "outdoors"), ("Coffee Grinder", "kitchen"), ("Running Shoes", "sports"), ("Notebook", "office"),
+    ("Tent", "outdoors"), ("Blender", "kitchen"), ("Yoga Mat", "sports"), ("Monitor Stand", "office"),
+];
+const ORDER_STATUSES: &[&str] = &["pending", "paid", "shipped", "delivered", "cancelled"];
+
+/// Seeded generator for local records.
+pub struct Faker {
+    rng: StdRng,
+}
+
+impl Faker {
+    /// A generator repeating its records for a given `seed`, or a random one without.
+    pub fn new(seed: Option<u64>) -> Self {
+        Self { rng: seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64) }
+    }
+
+    /// `count` records of `data_type` (`users`, `products`, `orders`, singular or plural),
+    /// with ids from 1.
+    pub fn records(&mut self, data_type: &str, count: usize) -> Vec<Value> {
+        let kind = data_type.to_ascii_lowercase();
+        (1..=count as u64)
+            .map(|id| match kind.trim_end_matches('s') {
+                "user" => self.user(id),
+                "product" => self.product(id),
+                "order" => self.order(id),
+                _ => self.record(id),
+            })
+            .collect()
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        items.choose(&mut self.rng).expect("word lists are not empty")
+    }
+
+    fn name(&mut self) -> (String, String) {
+        (self.pick(FIRST_NAMES).to_string(), self.pick(LAST_NAMES).to_string())
+    }
+
+    /// An RFC 3339 timestamp between 2020 and the end of 2024, whole seconds.
+    fn date(&mut self) -> String {
+        let start: DateTime<Utc> = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let offset = self.rng.gen_range(0..5 * 365 * 24 * 3600);
+        (start + Duration::seconds(offset)).format("%Y-%m-%dT%H:%M:%SZ").to_string()
+    }
+
+    fn price(&mut self) -> f64 {
+        self.rng.gen_range(199..=49_999) as f64 / 100.0
+    }
+
+    fn user(&mut self, id: u64) -> Value {
+        let (first, last) = self.name();
+        let local: String = format!("{}.{}", first, last).chars()
+            .filter_map(|c| match c {
+                'é' => Some('e'),
+                'í' => Some('i'),
+                'ü' => Some('u'),
+                c if c.is_ascii_alphanumeric() || c == '.' => Some(c.to_ascii_lowercase()),
+                _ => None,
+            })
+            .collect();
+        let (city, country) = *self.pick(CITIES);
+        json!({
+            "id": id,
+            "name": format!("{} {}", first, last),
+            "email": format!("{}{}@{}", local, self.rng.gen_range(1..100), self.pick(EMAIL_DOMAINS)),
+            "age": self.rng.gen_range(18..=90),
+            "address": {
+                "street": format!("{} {}", self.rng.gen_range(1..=250), self.pick(STREETS)),
+                "city": city,
+                "postal_code": format!("{:05}", self.rng.gen_range(1000..99_999)),
+                "country": country,
+            },
+            "phone": format!("+1-555-{:03}-{:04}", self.rng.gen_range(100..1000), self.rng.gen_range(0..10_000)),
+            "registration_date": self.date(),
+        })
+    }
+
+    fn product(&mut self, id: u64) -> Value {
+        let adjective = *self.pick(ADJECTIVES);
+        let (product, category) = *self.pick(PRODUCTS);
+        json!({
+            "id": id,
+            "name": format!("{} {}", adjective, product),
+            "price": self.price(),
+            "category": category,
+            "description": format!("{} {} for everyday use.", adjective, product.to_lowercase()),
+            "in_stock": self.rng.gen_bool(0.8),
+            "created_at": self.date(),
+        })
+    }
+
+    fn order(&mut self, id: u64) -> Value {
+        let lines: Vec<Value> = (0..self.rng.gen_range(1..=4))
+            .map(|_| json!({
+                "product_id": self.rng.gen_range(1..=100),
+                "quantity": self.rng.gen_range(1..=5),
+                "unit_price": self.price(),
+            }))
+            .collect();
+        let cents: i64 = lines.iter()
+            .map(|line| (line["unit_price"].as_f64().unwrap_or_default() * 100.0).round() as i64 * line["quantity"].as_i64().unwrap_or_default())
+            .sum();
+        json!({
+            "id": id,
+            "user_id": self.rng.gen_range(1..=100),
+            "products": lines,
+            "total": cents as f64 / 100.0,
+            "status": self.pick(ORDER_STATUSES),
+            "order_date": self.date(),
+        })
+    }
+
+    fn record(&mut self, id: u64) -> Value {
+        let adjective = *self.pick(ADJECTIVES);
+        let (noun, _) = *self.pick(PRODUCTS);
+        json!({
+            "id": id,
+            "name": format!("{} {}", adjective, noun),
+            "description": format!("Sample record {}", id),
+            "created_at": self.date(),
+        })
+    }
+}
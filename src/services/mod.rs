@@ -0,0 +1,3 @@
+//! Helpers shared by commands.
+
+pub mod faker;
@@ -0,0 +1,318 @@
+//! AWS Signature Version 4 for `call --aws-sigv4 REGION:SERVICE`.
+//!
+//! [`Signer::sign`] works on plain parts (method, URL, headers, payload hash) so any sender
+//! can use it; [`Signer::sign_request`] applies it to a built `reqwest::Request`. Credentials
+//! come from `AWS_ACCESS_KEY_ID`/`AWS_SECRET_ACCESS_KEY`/`AWS_SESSION_TOKEN`, or from a
+//! profile in `~/.aws/credentials` (`AWS_SHARED_CREDENTIALS_FILE` to move it).
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::PathBuf;
+
+pub const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Hash that stands for an empty payload.
+pub const EMPTY_PAYLOAD_HASH: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// Headers left out of the signature: set or rewritten on the way, or the signature itself.
+const UNSIGNED_HEADERS: &[&str] = &["authorization", "user-agent", "expect", "x-amzn-trace-id", "connection"];
+
+#[derive(Clone, PartialEq)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("session_token", &self.session_token.as_ref().map(|_| "<redacted>"))
+            .finish_non_exhaustive()
+    }
+}
+
+impl Credentials {
+    /// The credentials of `profile` when one is given; otherwise the environment's, then
+    /// those of `AWS_PROFILE` (or `default`) in the credentials file.
+    pub fn load(profile: Option<&str>) -> Result<Self, String> {
+        if profile.is_none() {
+            if let Some(credentials) = Self::from_env() {
+                return Ok(credentials);
+            }
+        }
+        let profile = profile.map(str::to_string)
+            .or_else(|| std::env::var("AWS_PROFILE").ok().filter(|p| !p.is_empty()))
+            .unwrap_or_else(|| "default".to_string());
+        let file = credentials_file().ok_or("Could not find home directory")?;
+        let contents = std::fs::read_to_string(&file).map_err(|e| format!(
+            "No AWS credentials: set AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY, or add them to {} ({})", file.display(), e
+        ))?;
+        Self::from_profile(&contents, &profile).map_err(|e| format!("{} in {}", e, file.display()))
+    }
+
+    pub fn from_env() -> Option<Self> {
+        let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+        Some(Self {
+            access_key_id: var("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: var("AWS_SECRET_ACCESS_KEY")?,
+            session_token: var("AWS_SESSION_TOKEN"),
+        })
+    }
+
+    /// `profile`'s keys in the INI text of a credentials file.
+    pub fn from_profile(contents: &str, profile: &str) -> Result<Self, String> {
+        let (mut access_key_id, mut secret_access_key, mut session_token) = (None, None, None);
+        let mut found = false;
+        let mut in_profile = false;
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                in_profile = section.trim() == profile;
+                found |= in_profile;
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=').filter(|_| in_profile) else {
+                continue;
+            };
+            let value = Some(value.trim().to_string());
+            match key.trim().to_ascii_lowercase().as_str() {
+                "aws_access_key_id" => access_key_id = value,
+                "aws_secret_access_key" => secret_access_key = value,
+                "aws_session_token" => session_token = value,
+                _ => {}
+            }
+        }
+        if !found {
+            return Err(format!("No AWS profile '{}'", profile));
+        }
+        match (access_key_id, secret_access_key) {
+            (Some(access_key_id), Some(secret_access_key)) => Ok(Self { access_key_id, secret_access_key, session_token }),
+            _ => Err(format!("AWS profile '{}' needs aws_access_key_id and aws_secret_access_key", profile)),
+        }
+    }
+}
+
+fn credentials_file() -> Option<PathBuf> {
+    match std::env::var("AWS_SHARED_CREDENTIALS_FILE") {
+        Ok(file) if !file.is_empty() => Some(PathBuf::from(file)),
+        _ => Some(dirs::home_dir()?.join(".aws").join("credentials")),
+    }
+}
+
+/// Where a signature is valid: `--aws-sigv4 us-east-1:execute-api`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scope {
+    pub region: String,
+    pub service: String,
+}
+
+impl Scope {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        match spec.split_once(':') {
+            Some((region, service)) if !region.is_empty() && !service.is_empty() && !service.contains(':') => {
+                Ok(Self { region: region.to_string(), service: service.to_string() })
+            }
+            _ => Err(format!("--aws-sigv4 needs REGION:SERVICE, e.g. us-east-1:execute-api, got '{}'", spec)),
+        }
+    }
+
+    fn credential_scope(&self, date: &str) -> String {
+        format!("{}/{}/{}/aws4_request", date, self.region, self.service)
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.region, self.service)
+    }
+}
+
+pub struct Signer {
+    pub scope: Scope,
+    pub credentials: Credentials,
+}
+
+impl Signer {
+    pub fn new(scope: Scope, credentials: Credentials) -> Self {
+        Self { scope, credentials }
+    }
+
+    /// The headers to add to a request so it carries a signature made at `time`:
+    /// `x-amz-date`, `x-amz-security-token` with a session token, `x-amz-content-sha256` for
+    /// S3, then `authorization`. `headers` are the ones the request already has.
+    pub fn sign(&self, method: &str, url: &url::Url, headers: &[(String, String)], payload_hash: &str, time: DateTime<Utc>) -> Vec<(String, String)> {
+        let amz_date = time.format("%Y%m%dT%H%M%SZ").to_string();
+        let mut added = vec![("x-amz-date".to_string(), amz_date.clone())];
+        if let Some(token) = &self.credentials.session_token {
+            added.push(("x-amz-security-token".to_string(), token.clone()));
+        }
+        if self.scope.service == "s3" {
+            added.push(("x-amz-content-sha256".to_string(), payload_hash.to_string()));
+        }
+
+        let mut signed: Vec<(String, String)> = headers.iter()
+            .filter(|(name, _)| !added.iter().any(|(added, _)| name.eq_ignore_ascii_case(added)))
+            .chain(added.iter())
+            .map(|(name, value)| (name.to_ascii_lowercase(), value.clone()))
+            .filter(|(name, _)| !UNSIGNED_HEADERS.contains(&name.as_str()))
+            .collect();
+        if !signed.iter().any(|(name, _)| name == "host") {
+            signed.push(("host".to_string(), host_header(url)));
+        }
+
+        let (canonical, signed_headers) = canonical_request(method, url, &signed, payload_hash, self.scope.service != "s3");
+        let date = &amz_date[..8];
+        let scope = self.scope.credential_scope(date);
+        let to_sign = string_to_sign(&amz_date, &scope, &canonical);
+        let key = signing_key(&self.credentials.secret_access_key, date, &self.scope.region, &self.scope.service);
+        let signature = hex(&hmac_sha256(&key, to_sign.as_bytes()));
+        added.push(("authorization".to_string(), format!(
+            "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+            ALGORITHM, self.credentials.access_key_id, scope, signed_headers, signature
+        )));
+        added
+    }
+
+    /// Sign `request` as it is about to be sent. `payload_hash` stands in for the body when
+    /// it is streamed (a file) and so can't be read here.
+    pub fn sign_request(&self, request: &mut reqwest::Request, payload_hash: Option<String>, time: DateTime<Utc>) -> Result<(), String> {
+        let payload_hash = match (payload_hash, request.body()) {
+            (Some(hash), _) => hash,
+            (None, None) => EMPTY_PAYLOAD_HASH.to_string(),
+            (None, Some(body)) => sha256_hex(body.as_bytes().ok_or("--aws-sigv4 can't sign a streamed body such as a multipart upload")?),
+        };
+        let headers: Vec<(String, String)> = request.headers().iter()
+            .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+            .collect();
+        for (name, value) in self.sign(request.method().as_str(), request.url(), &headers, &payload_hash, time) {
+            let value = reqwest::header::HeaderValue::from_str(&value).map_err(|e| format!("Invalid {} header: {}", name, e))?;
+            request.headers_mut().insert(reqwest::header::HeaderName::from_bytes(name.as_bytes()).map_err(|e| e.to_string())?, value);
+        }
+        Ok(())
+    }
+}
+
+/// The Host header as the client sends it: the port only when it isn't the scheme's default.
+fn host_header(url: &url::Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+/// The canonical request and its signed header list. Services other than S3 expect the
+/// (already encoded) path encoded once more.
+pub fn canonical_request(method: &str, url: &url::Url, headers: &[(String, String)], payload_hash: &str, double_encode: bool) -> (String, String) {
+    let path = if url.path().is_empty() { "/" } else { url.path() };
+    let path = if double_encode { uri_encode(path, false) } else { path.to_string() };
+
+    let mut query: Vec<(String, String)> = url.query().unwrap_or_default().split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (uri_encode(&percent_decode(key), true), uri_encode(&percent_decode(value), true))
+        })
+        .collect();
+    query.sort();
+    let query: Vec<String> = query.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+
+    let mut canonical_headers: Vec<(String, String)> = Vec::new();
+    for (name, value) in headers {
+        let name = name.to_ascii_lowercase();
+        let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        match canonical_headers.iter_mut().find(|(existing, _)| *existing == name) {
+            Some((_, existing)) => {
+                existing.push(',');
+                existing.push_str(&value);
+            }
+            None => canonical_headers.push((name, value)),
+        }
+    }
+    canonical_headers.sort();
+    let signed_headers = canonical_headers.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(";");
+    let header_lines: String = canonical_headers.iter().map(|(name, value)| format!("{}:{}\n", name, value)).collect();
+
+    let canonical = format!("{}\n{}\n{}\n{}\n{}\n{}", method, path, query.join("&"), header_lines, signed_headers, payload_hash);
+    (canonical, signed_headers)
+}
+
+pub fn string_to_sign(amz_date: &str, credential_scope: &str, canonical_request: &str) -> String {
+    format!("{}\n{}\n{}\n{}", ALGORITHM, amz_date, credential_scope, sha256_hex(canonical_request.as_bytes()))
+}
+
+/// `kSigning`: the secret narrowed down to one day, region and service.
+pub fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> [u8; 32] {
+    let key = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+/// HMAC (RFC 2104) over SHA-256.
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.iter().map(move |k| k ^ byte).collect::<Vec<u8>>();
+    let inner = Sha256::new().chain_update(pad(0x36)).chain_update(data).finalize();
+    Sha256::new().chain_update(pad(0x5c)).chain_update(inner).finalize().into()
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+/// [`sha256_hex`] of a file, read in chunks.
+pub fn sha256_file(path: &str) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Could not read file '{}': {}", path, e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| format!("Could not read file '{}': {}", path, e))?;
+    Ok(hex(&hasher.finalize()))
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Percent-encode everything but the unreserved characters (and `/` unless `encode_slash`).
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,282 @@
+//! `snippet <lang>`: the last request as code for `curl`, Python `requests`, JavaScript
+//! `fetch` or Rust `reqwest`.
+//!
+//! Every generator renders the same [`SnippetRequest`], built from [`CallOptions`] after
+//! defaults and environment variables are applied, so a snippet sends what NUTS sent.
+
+use crate::commands::call::{graphql_envelope, CallOptions};
+use crate::flows::export::shell_quote;
+use base64::Engine;
+use serde_json::Value;
+use std::error::Error;
+use std::fmt::Write;
+
+/// The user agent NUTS sends unless `-A` says otherwise; left out of snippets.
+const DEFAULT_USER_AGENT: &str = "NUTS/0.1.0 (AI-Powered CURL Killer)";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    Curl,
+    PythonRequests,
+    JavascriptFetch,
+    RustReqwest,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [Language::Curl, Language::PythonRequests, Language::JavascriptFetch, Language::RustReqwest];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Curl => "curl",
+            Language::PythonRequests => "python-requests",
+            Language::JavascriptFetch => "javascript-fetch",
+            Language::RustReqwest => "rust-reqwest",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|language| language.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// A request as every generator sees it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnippetRequest {
+    pub method: String,
+    pub url: String,
+    /// Sorted by name. Includes the `Content-Type` NUTS adds for JSON bodies.
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    /// The body parsed, when it is JSON and sent as such.
+    pub json: Option<Value>,
+    pub basic_auth: Option<(String, String)>,
+    pub bearer_token: Option<String>,
+    pub follow_redirects: bool,
+    pub insecure: bool,
+}
+
+impl SnippetRequest {
+    /// Normalize resolved call options. Form fields and binary bodies are refused.
+    pub fn from_options(options: &CallOptions) -> Result<Self, Box<dyn Error>> {
+        if !options.form_data.is_empty() || options.binary_body.is_some() || options.body_file.is_some() {
+            return Err("Snippets can't hold -F form fields, --data-binary bodies or binary and streamed files".into());
+        }
+        if options.oauth2 {
+            return Err("Snippets can't fetch --auth oauth2 tokens; pass the token with --bearer instead".into());
+        }
+
+        let mut headers: Vec<(String, String)> = options.headers.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        let mut method = options.method.to_uppercase();
+        let body = if options.graphql {
+            let query = options.graphql_query.as_deref().ok_or("--graphql needs --query or --query-file")?;
+            headers.retain(|(name, _)| !name.eq_ignore_ascii_case("content-type"));
+            method = "POST".to_string();
+            Some(graphql_envelope(query, options.graphql_variables.as_deref())?)
+        } else {
+            options.body.clone()
+        };
+        let json = body.as_deref().and_then(|body| serde_json::from_str::<Value>(body).ok());
+        if json.is_some() && !headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("content-type")) {
+            headers.push(("Content-Type".to_string(), "application/json".to_string()));
+        }
+        if let Some(agent) = options.user_agent.as_ref().filter(|agent| *agent != DEFAULT_USER_AGENT) {
+            headers.push(("User-Agent".to_string(), agent.clone()));
+        }
+        headers.sort_by_key(|(name, _)| name.to_ascii_lowercase());
+
+        Ok(Self {
+            method,
+            url: options.url.clone(),
+            headers,
+            body,
+            json,
+            basic_auth: options.auth.clone(),
+            bearer_token: options.bearer_token.clone(),
+            follow_redirects: options.follow_redirects,
+            insecure: options.insecure,
+        })
+    }
+
+    pub fn render(&self, language: Language) -> String {
+        match language {
+            Language::Curl => self.curl(),
+            Language::PythonRequests => self.python_requests(),
+            Language::JavascriptFetch => self.javascript_fetch(),
+            Language::RustReqwest => self.rust_reqwest(),
+        }
+    }
+
+    /// Headers with the bearer token (and, with `basic`, basic credentials) folded in, for
+    /// languages without a helper for them.
+    fn headers_with_auth(&self, basic: bool) -> Vec<(String, String)> {
+        let mut headers = self.headers.clone();
+        if let Some((user, password)) = self.basic_auth.as_ref().filter(|_| basic) {
+            let credentials = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, password));
+            headers.push(("Authorization".to_string(), format!("Basic {}", credentials)));
+        }
+        if let Some(token) = &self.bearer_token {
+            headers.push(("Authorization".to_string(), format!("Bearer {}", token)));
+        }
+        headers
+    }
+
+    pub fn curl(&self) -> String {
+        let mut lines = vec![format!("curl{} {}", if self.method == "GET" { String::new() } else { format!(" -X {}", self.method) }, shell_quote(&self.url))];
+        for (name, value) in &self.headers {
+            lines.push(format!("-H {}", shell_quote(&format!("{}: {}", name, value))));
+        }
+        if let Some((user, password)) = &self.basic_auth {
+            lines.push(format!("-u {}", shell_quote(&format!("{}:{}", user, password))));
+        }
+        if let Some(token) = &self.bearer_token {
+            lines.push(format!("-H {}", shell_quote(&format!("Authorization: Bearer {}", token))));
+        }
+        if self.follow_redirects {
+            lines.push("-L".to_string());
+        }
+        if self.insecure {
+            lines.push("-k".to_string());
+        }
+        if let Some(body) = &self.body {
+            // `--data-raw` so a body starting with `@` isn't read as a file
+            lines.push(format!("--data-raw {}", shell_quote(body)));
+        }
+        format!("{}\n", lines.join(" \\\n  "))
+    }
+
+    pub fn python_requests(&self) -> String {
+        let mut out = String::from("import requests\n\n");
+        let mut arguments = vec![py_string(&self.method), py_string(&self.url)];
+        // requests has a helper for basic auth but not for bearer tokens
+        let headers = self.headers_with_auth(false);
+        if !headers.is_empty() {
+            let mut block = String::from("headers={\n");
+            for (name, value) in &headers {
+                let _ = writeln!(block, "        {}: {},", py_string(name), py_string(value));
+            }
+            block.push_str("    }");
+            arguments.push(block);
+        }
+        if let Some((user, password)) = &self.basic_auth {
+            arguments.push(format!("auth=({}, {})", py_string(user), py_string(password)));
+        }
+        match (&self.json, &self.body) {
+            (Some(json), _) => arguments.push(format!("json={}", py_literal(json))),
+            (None, Some(body)) => arguments.push(format!("data={}", py_string(body))),
+            (None, None) => {}
+        }
+        if !self.follow_redirects {
+            arguments.push("allow_redirects=False".to_string());
+        }
+        if self.insecure {
+            arguments.push("verify=False".to_string());
+        }
+        out.push_str("response = requests.request(\n");
+        for argument in arguments {
+            let _ = writeln!(out, "    {},", argument);
+        }
+        out.push_str(")\nprint(response.status_code)\nprint(response.text)\n");
+        out
+    }
+
+    pub fn javascript_fetch(&self) -> String {
+        let mut out = format!("const response = await fetch({}, {{\n  method: {},\n", js_string(&self.url), js_string(&self.method));
+        let headers = self.headers_with_auth(true);
+        if !headers.is_empty() {
+            out.push_str("  headers: {\n");
+            for (name, value) in &headers {
+                let _ = writeln!(out, "    {}: {},", js_string(name), js_string(value));
+            }
+            out.push_str("  },\n");
+        }
+        match (&self.json, &self.body) {
+            (Some(json), _) => {
+                let _ = writeln!(out, "  body: JSON.stringify({}),", json);
+            }
+            (None, Some(body)) => {
+                let _ = writeln!(out, "  body: {},", js_string(body));
+            }
+            (None, None) => {}
+        }
+        if !self.follow_redirects {
+            out.push_str("  redirect: \"manual\",\n");
+        }
+        out.push_str("});\nconsole.log(response.status);\nconsole.log(await response.text());\n");
+        out
+    }
+
+    pub fn rust_reqwest(&self) -> String {
+        let mut out = String::from("// Cargo.toml: reqwest = { version = \"0.11\", features = [\"blocking\", \"json\"] }");
+        if self.json.is_some() {
+            out.push_str(", serde_json = \"1\"");
+        }
+        out.push_str("\nfn main() -> Result<(), Box<dyn std::error::Error>> {\n    let client = reqwest::blocking::Client::builder()\n");
+        if !self.follow_redirects {
+            out.push_str("        .redirect(reqwest::redirect::Policy::none())\n");
+        }
+        if self.insecure {
+            out.push_str("        .danger_accept_invalid_certs(true)\n");
+        }
+        out.push_str("        .build()?;\n");
+
+        let method = match self.method.as_str() {
+            "GET" | "POST" | "PUT" | "DELETE" | "HEAD" | "OPTIONS" | "CONNECT" | "PATCH" | "TRACE" => format!("reqwest::Method::{}", self.method),
+            other => format!("reqwest::Method::from_bytes({:?}.as_bytes())?", other),
+        };
+        let _ = writeln!(out, "    let response = client\n        .request({}, {:?})", method, self.url);
+        for (name, value) in &self.headers {
+            let _ = writeln!(out, "        .header({:?}, {:?})", name, value);
+        }
+        if let Some((user, password)) = &self.basic_auth {
+            let _ = writeln!(out, "        .basic_auth({:?}, Some({:?}))", user, password);
+        }
+        if let Some(token) = &self.bearer_token {
+            let _ = writeln!(out, "        .bearer_auth({:?})", token);
+        }
+        match (&self.json, &self.body) {
+            (Some(json), _) => {
+                let _ = writeln!(out, "        .json(&serde_json::json!({}))", rust_json(json));
+            }
+            (None, Some(body)) => {
+                let _ = writeln!(out, "        .body({:?})", body);
+            }
+            (None, None) => {}
+        }
+        out.push_str("        .send()?;\n    println!(\"{}\", response.status());\n    println!(\"{}\", response.text()?);\n    Ok(())\n}\n");
+        out
+    }
+}
+
+/// A double-quoted Python string. JSON's escapes are all valid Python ones.
+fn py_string(value: &str) -> String {
+    Value::String(value.to_string()).to_string()
+}
+
+/// A JSON value as a Python literal.
+fn py_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "None".to_string(),
+        Value::Bool(true) => "True".to_string(),
+        Value::Bool(false) => "False".to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => py_string(s),
+        Value::Array(items) => format!("[{}]", items.iter().map(py_literal).collect::<Vec<_>>().join(", ")),
+        Value::Object(map) => format!("{{{}}}", map.iter().map(|(k, v)| format!("{}: {}", py_string(k), py_literal(v))).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+/// A double-quoted JavaScript string.
+fn js_string(value: &str) -> String {
+    Value::String(value.to_string()).to_string()
+}
+
+/// A JSON value as `serde_json::json!` input. Strings go through Rust's own escaping, since
+/// JSON's `\u0001` or `\b` aren't valid in a Rust literal.
+fn rust_json(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("{:?}", s),
+        Value::Array(items) => format!("[{}]", items.iter().map(rust_json).collect::<Vec<_>>().join(", ")),
+        Value::Object(map) => format!("{{{}}}", map.iter().map(|(k, v)| format!("{:?}: {}", k, rust_json(v))).collect::<Vec<_>>().join(", ")),
+        other => other.to_string(),
+    }
+}
@@ -1,25 +1,122 @@
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::time::Duration;
-use crate::commands::call::CallCommand;
-use anthropic::{
-    client::ClientBuilder,
-    types::{Message, ContentBlock, MessagesRequestBuilder, Role},
-};
+use crate::commands::call::{CallCommand, CallOptions};
+use crate::confirm::{run_step, ExecutionPolicy, StepPrompt};
+use crate::shell::split_args;
+use crate::ai;
+use crate::config::Config;
+use crate::output;
 use std::collections::HashMap;
 use crate::flows::{OpenAPISpec, PathItem, Operation, RequestBody, Response, MediaType, Schema};
+use std::path::PathBuf;
 use url::Url;
 
-#[allow(dead_code)]
+/// HTTP methods that start a request line in a suggestion.
+const METHODS: &[&str] = &["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"];
+
+/// One request of a suggested workflow.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoryStep {
+    /// The text of the numbered line the request belongs to.
+    pub description: String,
+    /// `call` arguments for the request.
+    pub args: Vec<String>,
+}
+
+/// Pull the requests out of an AI suggestion: `METHOD URL` lines, optionally followed by a
+/// JSON body spread over the next lines, and `curl ...` command lines.
+pub fn parse_steps(suggestion: &str) -> Vec<StoryStep> {
+    let mut steps = Vec::new();
+    let mut description = String::new();
+    let mut lines = suggestion.lines().map(str::trim).peekable();
+    while let Some(line) = lines.next() {
+        let mut line = line.trim_start_matches("$ ").trim_matches('`');
+        if let Some((number, rest)) = line.trim_start_matches('*').split_once(". ") {
+            if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) {
+                let rest = rest.trim().trim_matches('*').trim();
+                // `1. GET http://...` is a request of its own
+                let is_request = rest.starts_with("curl ") || rest.split_whitespace().next().is_some_and(|word| METHODS.contains(&word));
+                if !is_request {
+                    description = rest.to_string();
+                    continue;
+                }
+                line = rest;
+            }
+        }
+
+        if line.starts_with("curl ") {
+            // Bring `\` continuations onto one line before splitting like the shell does
+            let mut command = line.to_string();
+            while command.ends_with('\\') {
+                command.pop();
+                match lines.next() {
+                    Some(next) => command = format!("{} {}", command.trim_end(), next),
+                    None => break,
+                }
+            }
+            let mut args = split_args(&command);
+            args[0] = "call".to_string();
+            steps.push(StoryStep { description: description.clone(), args });
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        let (Some(method), Some(url)) = (words.next(), words.next()) else { continue };
+        if !METHODS.contains(&method) || !url.contains("://") {
+            continue;
+        }
+        let mut args = vec!["call".to_string(), method.to_string(), url.to_string()];
+        if lines.peek().is_some_and(|next| next.starts_with('{') || next.starts_with('[')) {
+            let mut body = String::new();
+            while let Some(next) = lines.peek() {
+                if next.is_empty() && serde_json::from_str::<serde_json::Value>(&body).is_ok() {
+                    break;
+                }
+                body.push_str(lines.next().unwrap_or_default());
+                body.push('\n');
+                if serde_json::from_str::<serde_json::Value>(&body).is_ok() {
+                    break;
+                }
+            }
+            args.push(body.trim().to_string());
+        }
+        steps.push(StoryStep { description: description.clone(), args });
+    }
+    steps
+}
+
 pub struct StoryMode {
     flow: String,
-    api_key: String,
+    /// AI settings for the suggestions.
+    config: Config,
+    /// Where `<flow>.yaml` is kept.
+    flows_dir: PathBuf,
+    /// Sends the steps, with the session's defaults, environment and cookies.
+    call: CallCommand,
+    /// Whether steps are sent, confirmed one by one or only printed.
+    policy: ExecutionPolicy,
 }
 
-#[allow(dead_code)]
 impl StoryMode {
-    pub fn new(flow: String, api_key: String) -> Self {
-        Self { flow, api_key }
+    pub fn new(flow: String, config: Config) -> Self {
+        let flows_dir = dirs::home_dir().unwrap_or_default().join(".nuts").join("flows");
+        Self { flow, config, flows_dir, call: CallCommand::new(), policy: ExecutionPolicy::default() }
+    }
+
+    pub fn with_flows_dir(mut self, dir: PathBuf) -> Self {
+        self.flows_dir = dir;
+        self
+    }
+
+    pub fn with_call_command(mut self, call: CallCommand) -> Self {
+        self.call = call;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: ExecutionPolicy) -> Self {
+        self.policy = policy;
+        self
     }
 
     pub async fn start(&self, editor: &mut rustyline::Editor<crate::completer::NutsCompleter, rustyline::history::DefaultHistory>) -> Result<(), Box<dyn std::error::Error>> {
@@ -34,27 +131,30 @@ impl StoryMode {
                     if line.trim().eq_ignore_ascii_case("exit") {
                         break;
                     }
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let _ = editor.add_history_entry(line.as_str());
 
                     let spinner = self.show_thinking_spinner();
                     
-                    if let Some(suggestion) = self.get_suggestion(&line).await {
-                        spinner.finish_with_message("Got it! 🚀");
-                        
-                        println!("\n📝 {}", style("Suggested workflow:").blue());
-                        println!("{}", suggestion);
-                        
-                        let execute = editor.readline("\n🚀 Execute this workflow? (y/n): ");
-                        if let Ok(response) = execute {
-                            if response.trim().eq_ignore_ascii_case("y") {
-                                println!("\n🏃 Executing workflow...");
-                                if let Err(e) = self.execute_flow(&suggestion).await {
-                                    println!("❌ Error executing workflow: {}", e);
-                                }
+                    match self.get_suggestion(&line).await {
+                        Ok(suggestion) => {
+                            spinner.finish_with_message("Got it! 🚀");
+
+                            println!("\n📝 {}", style("Suggested workflow:").blue());
+                            println!("{}", suggestion);
+
+                            // Each step is confirmed, sent or printed as the policy says
+                            println!("\n🏃 {}", if self.policy.dry_run { "Dry run of the workflow..." } else { "Executing workflow..." });
+                            if let Err(e) = self.execute_flow(&suggestion, editor).await {
+                                println!("❌ Error executing workflow: {}", e);
                             }
                         }
-                    } else {
-                        spinner.finish_with_message("Failed to get suggestion");
-                        println!("❌ Unable to get AI suggestion. Check your API key.");
+                        Err(e) => {
+                            spinner.finish_with_message("Failed to get suggestion");
+                            println!("❌ Unable to get AI suggestion: {}", e);
+                        }
                     }
                 }
                 Err(_) => break,
@@ -65,18 +165,15 @@ impl StoryMode {
     }
 
     fn show_thinking_spinner(&self) -> ProgressBar {
-        let spinner = ProgressBar::new_spinner()
+        let spinner = output::progress(ProgressBar::new_spinner()
             .with_style(ProgressStyle::default_spinner()
-                .template("{spinner} Thinking...").unwrap());
+                .template("{spinner} Thinking...").unwrap()));
         spinner.enable_steady_tick(Duration::from_millis(100));
         spinner
     }
 
-    async fn get_suggestion(&self, goal: &str) -> Option<String> {
-        let ai_client = ClientBuilder::default()
-            .api_key(self.api_key.clone())
-            .build()
-            .ok()?;
+    async fn get_suggestion(&self, goal: &str) -> Result<String, ai::AiError> {
+        let ai_client = ai::client(&self.config, "flow story")?;
 
         let prompt = format!(
             "You are an API workflow assistant. Help the user achieve their goal:\n\
@@ -98,157 +195,83 @@ impl StoryMode {
             self.flow, goal
         );
 
-        match ai_client.messages(MessagesRequestBuilder::default()
-            .messages(vec![Message {
-                role: Role::User,
-                content: vec![ContentBlock::Text { text: prompt }],
-            }])
-            .model("claude-3-sonnet-20240229".to_string())
-            .max_tokens(2000_usize)
-            .build()
-            .ok()?
-        ).await {
-            Ok(response) => response.content.first()
-                .and_then(|block| {
-                    if let ContentBlock::Text { text } = block {
-                        Some(text.clone())
-                    } else {
-                        None
-                    }
-                }),
-            Err(_) => None
-        }
+        ai_client.ask(&prompt).await
     }
 
-    async fn execute_flow(&self, flow: &str) -> Result<(), Box<dyn std::error::Error>> {
-        // Skip if input is just "y" (from previous prompt)
-        if flow.trim().eq_ignore_ascii_case("y") {
-            return Ok(());
-        }
-
-        let steps: Vec<&str> = flow.lines()
-            .filter(|line| line.contains("curl") || line.contains("http"))
-            .collect();
-
+    /// Send the requests found in `suggestion` one after another, stopping at the first that
+    /// fails or answers with an error status, then add the ones sent to the flow. Steps are
+    /// confirmed through `prompt` or only printed, as the policy says.
+    pub async fn execute_flow(&self, suggestion: &str, prompt: &mut dyn StepPrompt) -> Result<(), Box<dyn std::error::Error>> {
+        let steps = parse_steps(suggestion);
         if steps.is_empty() {
             println!("No executable steps found in the flow");
             return Ok(());
         }
 
+        let mut sent = Vec::new();
         for (i, step) in steps.iter().enumerate() {
-            println!("\n📍 Step {}/{}", i + 1, steps.len());
-            
-            if let Some(url) = step.find("http") {
-                let url_end = step[url..].find(' ').unwrap_or(step.len() - url);
-                let url = &step[url..url + url_end];
-                
-                let method = if step.contains("POST") {
-                    "POST"
-                } else if step.contains("PUT") {
-                    "PUT"
-                } else if step.contains("DELETE") {
-                    "DELETE"
-                } else {
-                    "GET"
-                };
-
-                let body = if step.contains("'{") {
-                    step.rfind("'{").map(|i| &step[i + 1..step.len() - 1])
-                } else {
-                    None
-                };
-
-                println!("Executing {} {}", style(method).cyan(), style(url).green());
-                CallCommand::new().execute(&[method, url, body.unwrap_or("")]).await?;
+            println!("\n📍 Step {}/{}{}", i + 1, steps.len(), if step.description.is_empty() { String::new() } else { format!(": {}", step.description) });
+            let response = run_step(&self.call, &self.policy, step.args.clone(), prompt).await
+                .map_err(|e| format!("Step {} ({}): {}", i + 1, step.args[1..].join(" "), e))?;
+            let Some(response) = response else { continue };
+            if response.status >= 400 {
+                return Err(format!("Step {} ({} {}): answered {}", i + 1, response.method, response.url, response.status).into());
             }
+            sent.push((step.description.clone(), CallOptions { method: response.method, url: response.url, body: response.request_body, ..Default::default() }));
         }
 
-        self.save_story(&flow).await?;
+        if !sent.is_empty() {
+            self.save_story(&sent)?;
+        }
         Ok(())
     }
 
-    async fn save_story(&self, flow: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let mut paths = HashMap::new();
-        let mut current_path = None;
-        let mut current_method = None;
-        let mut description = String::new();
-
-        for line in flow.lines() {
-            if line.starts_with(|c: char| c.is_digit(10)) {
-                // Start of new step - capture description
-                description = line.splitn(2, '.').nth(1)
-                    .unwrap_or("").trim().to_string();
-            } else if line.contains("http") {
-                // Parse method and path
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 2 {
-                    current_method = Some(parts[0].to_uppercase());
-                    if let Ok(url) = Url::parse(parts[1]) {
-                        current_path = Some(url.path().to_string());
-                    }
-                }
-            } else if line.starts_with('{') && current_path.is_some() && current_method.is_some() {
-                // Found request body - create operation
-                let path = current_path.take().unwrap();
-                let method = current_method.take().unwrap();
-                
-                let path_item = paths.entry(path).or_insert(PathItem::new());
-                let operation = Operation {
-                    summary: Some(description.clone()),
-                    description: Some("Generated from Story Mode".to_string()),
-                    parameters: None,
-                    request_body: if line.trim().is_empty() {
-                        None
-                    } else {
-                        Some(RequestBody {
-                            description: Some("Request payload".to_string()),
-                            required: Some(true),
-                            content: {
-                                let mut content = HashMap::new();
-                                content.insert("application/json".to_string(), MediaType {
-                                    schema: Schema {
-                                        schema_type: "object".to_string(),
-                                        format: None,
-                                        properties: None,
-                                        items: None,
-                                    },
-                                    example: serde_json::from_str(line).ok(),
-                                });
-                                content
+    /// Add the steps sent to the flow, one operation per method and path.
+    fn save_story(&self, steps: &[(String, CallOptions)]) -> Result<(), Box<dyn std::error::Error>> {
+        let spec_path = self.flows_dir.join(format!("{}.yaml", self.flow));
+        let mut spec = OpenAPISpec::load(&spec_path)?;
+        for (description, options) in steps {
+            let Ok(url) = Url::parse(&options.url) else { continue };
+            let example = options.body.as_deref().and_then(|body| serde_json::from_str(body).ok());
+            let operation = Operation {
+                summary: Some(description.clone()).filter(|d| !d.is_empty()),
+                description: Some("Generated from Story Mode".to_string()),
+                parameters: None,
+                request_body: example.map(|example| RequestBody {
+                    description: Some("Request payload".to_string()),
+                    required: Some(true),
+                    content: {
+                        let mut content = HashMap::new();
+                        content.insert("application/json".to_string(), MediaType {
+                            schema: Schema {
+                                schema_type: "object".into(),
+                                ..Default::default()
                             },
-                        })
-                    },
-                    responses: {
-                        let mut responses = HashMap::new();
-                        responses.insert("200".to_string(), Response {
-                            description: "Successful response".to_string(),
-                            content: None,
+                            example: Some(example),
+                            ..Default::default()
                         });
-                        responses
+                        content
                     },
                     ..Default::default()
-                };
+                }),
+                responses: {
+                    let mut responses = HashMap::new();
+                    responses.insert("200".to_string(), Response {
+                        description: "Successful response".to_string(),
+                        content: None,
+                        ..Default::default()
+                    });
+                    responses
+                },
+                ..Default::default()
+            };
 
-                match method.as_str() {
-                    "GET" => path_item.get = Some(operation),
-                    "POST" => path_item.post = Some(operation),
-                    "PUT" => path_item.put = Some(operation),
-                    "DELETE" => path_item.delete = Some(operation),
-                    "PATCH" => path_item.patch = Some(operation),
-                    _ => {}
-                }
+            // Other operations already on the path are kept
+            let path_item = spec.paths.entry(url.path().to_string()).or_insert(PathItem::new());
+            if let Some(slot) = path_item.operation_mut(&options.method) {
+                *slot = Some(operation);
             }
         }
-
-        // Save to flow file
-        let spec_path = dirs::home_dir()
-            .ok_or("Could not find home directory")?
-            .join(".nuts")
-            .join("flows")
-            .join(format!("{}.yaml", self.flow));
-
-        let mut spec = OpenAPISpec::load(&spec_path)?;
-        spec.paths.extend(paths);
         spec.save(&spec_path)?;
 
         println!("\n✅ Saved API flow to flow {}", style(&self.flow).green());
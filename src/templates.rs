@@ -0,0 +1,271 @@
+//! Request templates: `template save <name>` keeps the last `call` (method, URL, headers,
+//! body and options) and `template run <name> [overrides...]` sends it again, patched by
+//! `key=value` overrides such as `url=...`, `header.X-Team=core` or `body.user.email=a@b.c`.
+//!
+//! Templates are stored before environments and config defaults are applied, so
+//! `{{placeholders}}` stay placeholders and resolve against whatever is active at run time.
+
+use crate::commands::call::CallOptions;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// The parts of [`CallOptions`] a template keeps.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallTemplate {
+    pub method: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// `-u user:password`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub basic_auth: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bearer_token: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub oauth2: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub follow_redirects: bool,
+    /// `--timeout`, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// `--timeout` in whole seconds, as older templates kept it; read but never written.
+    #[serde(default, skip_serializing)]
+    pub timeout_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub insecure: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub max_retries: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retry_on_status: Vec<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub no_cookies: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+impl CallTemplate {
+    /// Keep `options` as a template. Requests a template can't express are refused.
+    pub fn from_options(options: &CallOptions) -> Result<Self, Box<dyn Error>> {
+        if !options.form_data.is_empty() || options.binary_body.is_some() || options.body_file.is_some() {
+            return Err("Templates can't hold -F form fields, --data-binary bodies or binary and streamed files".into());
+        }
+        if options.graphql {
+            return Err("Templates can't hold --graphql requests".into());
+        }
+        let default_timeout = CallOptions::default().timeout;
+        Ok(Self {
+            method: options.method.clone(),
+            url: options.url.clone(),
+            headers: options.headers.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            body: options.body.clone(),
+            basic_auth: options.auth.as_ref().map(|(user, password)| format!("{}:{}", user, password)),
+            bearer_token: options.bearer_token.clone(),
+            oauth2: options.oauth2,
+            follow_redirects: options.follow_redirects,
+            // Below a millisecond rounds up, so a timeout never turns into zero
+            timeout_ms: options.timeout.filter(|t| Some(*t) != default_timeout).map(|t| t.as_micros().div_ceil(1000) as u64),
+            timeout_secs: None,
+            insecure: options.insecure,
+            max_retries: options.max_retries,
+            retry_on_status: options.retry_on_status.clone(),
+            proxy: options.proxy.clone(),
+            no_cookies: options.no_cookies,
+        })
+    }
+
+    /// Apply one `key=value` override: `method`, `url`, `body` (the whole body),
+    /// `header.<Name>` (an empty value removes the header) or `body.<path>`.
+    ///
+    /// Body path values are read as JSON when they parse (`body.count=5`, `body.tags=["a"]`)
+    /// and as strings otherwise; quote them to force a string (`body.zip="01234"`).
+    pub fn apply_override(&mut self, assignment: &str) -> Result<(), String> {
+        let (key, value) = assignment.split_once('=')
+            .ok_or_else(|| format!("Override '{}' must look like key=value", assignment))?;
+        match key {
+            "method" => self.method = value.to_uppercase(),
+            "url" => self.url = value.to_string(),
+            "body" => self.body = Some(value.to_string()),
+            _ => {
+                if let Some(name) = key.strip_prefix("header.") {
+                    self.headers.retain(|existing, _| !existing.eq_ignore_ascii_case(name));
+                    if !value.is_empty() {
+                        self.headers.insert(name.to_string(), value.to_string());
+                    }
+                } else if let Some(path) = key.strip_prefix("body.") {
+                    let mut body = match self.body.as_deref() {
+                        None => Value::Object(Default::default()),
+                        Some(body) => serde_json::from_str(body)
+                            .map_err(|_| format!("Can't set {}: the template body isn't JSON", key))?,
+                    };
+                    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+                    set_path(&mut body, path, value)?;
+                    self.body = Some(body.to_string());
+                } else {
+                    return Err(format!("Unknown override '{}': use method=, url=, body=, header.<Name>= or body.<path>=", key));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The `call` command line for this template, so a run is kept in history and can be
+    /// replayed like any other call.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = vec!["call".to_string(), self.method.clone(), self.url.clone()];
+        let mut flag = |name: &str, value: Option<String>| {
+            args.push(name.to_string());
+            args.extend(value);
+        };
+        for (name, value) in &self.headers {
+            flag("-H", Some(format!("{}: {}", name, value)));
+        }
+        if let Some(credentials) = &self.basic_auth {
+            flag("-u", Some(credentials.clone()));
+        }
+        if let Some(token) = &self.bearer_token {
+            flag("--bearer", Some(token.clone()));
+        }
+        if self.oauth2 {
+            flag("--auth", Some("oauth2".to_string()));
+        }
+        if self.follow_redirects {
+            flag("-L", None);
+        }
+        if let Some(ms) = self.timeout_ms {
+            flag("--timeout", Some(format!("{}ms", ms)));
+        } else if let Some(secs) = self.timeout_secs {
+            flag("--timeout", Some(secs.to_string()));
+        }
+        if self.insecure {
+            flag("-k", None);
+        }
+        if self.max_retries > 0 {
+            flag("--retry", Some(self.max_retries.to_string()));
+        }
+        if !self.retry_on_status.is_empty() {
+            let statuses: Vec<String> = self.retry_on_status.iter().map(u16::to_string).collect();
+            flag("--retry-on-status", Some(statuses.join(",")));
+        }
+        if let Some(proxy) = &self.proxy {
+            flag("-x", Some(proxy.clone()));
+        }
+        if self.no_cookies {
+            flag("--no-cookies", None);
+        }
+        // Positional rather than `-d`, which would read `@...` bodies as files and turn GET into POST
+        args.extend(self.body.clone());
+        args
+    }
+}
+
+/// Set the value at a dotted `path` (`user.emails.0` or `user.emails[0]`) inside `target`,
+/// creating objects for missing keys. An array index may be one past the end to append.
+pub fn set_path(target: &mut Value, path: &str, value: Value) -> Result<(), String> {
+    let segments: Vec<&str> = path.split(['.', '[', ']']).filter(|s| !s.is_empty()).collect();
+    let Some((last, parents)) = segments.split_last() else {
+        return Err("Empty body path".to_string());
+    };
+
+    let mut current = target;
+    let mut walked = String::from("body");
+    for segment in parents {
+        current = child(current, segment, &walked)?;
+        walked = format!("{}.{}", walked, segment);
+    }
+    *child(current, last, &walked)? = value;
+    Ok(())
+}
+
+/// The value under `segment` in `parent` (at `walked`), added when missing.
+fn child<'a>(parent: &'a mut Value, segment: &str, walked: &str) -> Result<&'a mut Value, String> {
+    if parent.is_null() {
+        *parent = Value::Object(Default::default());
+    }
+    match parent {
+        Value::Object(map) => Ok(map.entry(segment.to_string()).or_insert(Value::Null)),
+        Value::Array(items) => {
+            let index = segment.parse::<usize>()
+                .map_err(|_| format!("Can't set {}.{}: '{}' is not an array index", walked, segment, segment))?;
+            if index > items.len() {
+                return Err(format!("Can't set {}.{}: index {} is past the end of an array of {}", walked, segment, index, items.len()));
+            }
+            if index == items.len() {
+                items.push(Value::Null);
+            }
+            Ok(&mut items[index])
+        }
+        _ => Err(format!("Can't set {}.{}: {} is not an object or array", walked, segment, walked)),
+    }
+}
+
+/// Templates kept as `<dir>/<name>.yaml`.
+pub struct TemplateStore {
+    dir: PathBuf,
+}
+
+impl TemplateStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `~/.nuts/templates`.
+    pub fn default_dir() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(dirs::home_dir()
+            .ok_or("Could not find home directory")?
+            .join(".nuts")
+            .join("templates"))
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.yaml", name))
+    }
+
+    pub fn save(&self, name: &str, template: &CallTemplate) -> Result<PathBuf, Box<dyn Error>> {
+        if name.is_empty() || name.contains(['/', '\\']) {
+            return Err(format!("Invalid template name '{}'", name).into());
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(name);
+        std::fs::write(&path, serde_yaml::to_string(template)?)?;
+        Ok(path)
+    }
+
+    pub fn load(&self, name: &str) -> Result<CallTemplate, Box<dyn Error>> {
+        let path = self.path(name);
+        let content = std::fs::read_to_string(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => format!("No template named '{}' in {}", name, self.dir.display()),
+            _ => format!("Could not read {}: {}", path.display(), e),
+        })?;
+        serde_yaml::from_str(&content).map_err(|e| format!("Invalid template {}: {}", path.display(), e).into())
+    }
+
+    /// Names of saved templates with their templates, sorted by name. Unreadable files are skipped.
+    pub fn list(&self) -> Result<Vec<(String, CallTemplate)>, Box<dyn Error>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut templates: Vec<(String, CallTemplate)> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "yaml"))
+            .filter_map(|path| path.file_stem().and_then(|s| s.to_str()).map(str::to_string))
+            .filter_map(|name| self.load(&name).ok().map(|template| (name, template)))
+            .collect();
+        templates.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(templates)
+    }
+}
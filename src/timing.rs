@@ -0,0 +1,117 @@
+//! Where the time of a `call -v` went: DNS lookup, TCP connect, TLS handshake, time to first
+//! byte and body download.
+//!
+//! reqwest only tells us when the response headers arrived, so DNS, connect and TLS are timed
+//! on a probe connection to the same host opened just before the request. Time to first byte
+//! is the rest of the wait for headers once those phases are taken out. A phase that can't be
+//! measured (behind a proxy, a failed probe, TLS on plain HTTP) is left out and shown as `n/a`.
+
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::net::{lookup_host, TcpStream};
+use tokio_native_tls::{native_tls, TlsConnector};
+
+/// Phase timings in milliseconds.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttfb_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_ms: Option<f64>,
+    pub total_ms: f64,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl Timings {
+    /// Time DNS, TCP connect and (for `https`) the TLS handshake against `url`'s host,
+    /// stopping at the first phase that fails or takes longer than `timeout`.
+    pub async fn probe(url: &str, insecure: bool, timeout: Duration) -> Self {
+        Self::probe_via(url, None, insecure, timeout).await
+    }
+
+    /// [`Timings::probe`], connecting to `pinned` without a DNS lookup when given
+    /// (`call --resolve`); DNS is then shown as `n/a`.
+    pub async fn probe_via(url: &str, pinned: Option<IpAddr>, insecure: bool, timeout: Duration) -> Self {
+        let mut timings = Self::default();
+        let Ok(url) = url::Url::parse(url) else {
+            return timings;
+        };
+        let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+            return timings;
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        let addr = match pinned {
+            Some(ip) => SocketAddr::new(ip, port),
+            None => {
+                let started = Instant::now();
+                let Ok(Ok(mut addrs)) = tokio::time::timeout(timeout, lookup_host((host, port))).await else {
+                    return timings;
+                };
+                timings.dns_ms = Some(millis(started.elapsed()));
+                let Some(addr) = addrs.next() else {
+                    return timings;
+                };
+                addr
+            }
+        };
+
+        let started = Instant::now();
+        let Ok(Ok(stream)) = tokio::time::timeout(timeout, TcpStream::connect(addr)).await else {
+            return timings;
+        };
+        timings.connect_ms = Some(millis(started.elapsed()));
+
+        if url.scheme() == "https" {
+            let Ok(connector) = native_tls::TlsConnector::builder()
+                .danger_accept_invalid_certs(insecure)
+                .danger_accept_invalid_hostnames(insecure)
+                .build()
+            else {
+                return timings;
+            };
+            let started = Instant::now();
+            if let Ok(Ok(_)) = tokio::time::timeout(timeout, TlsConnector::from(connector).connect(host, stream)).await {
+                timings.tls_ms = Some(millis(started.elapsed()));
+            }
+        }
+        timings
+    }
+
+    /// Complete the probe with the request's own timings: `headers_after` from sending the
+    /// request to its response headers, and `download` for reading the body.
+    pub fn finish(mut self, headers_after: Duration, download: Duration) -> Self {
+        let setup: f64 = [self.dns_ms, self.connect_ms, self.tls_ms].iter().flatten().sum();
+        self.ttfb_ms = Some((millis(headers_after) - setup).max(0.0));
+        self.download_ms = Some(millis(download));
+        self.total_ms = millis(headers_after + download);
+        self
+    }
+
+    /// An aligned table of every phase and the total.
+    pub fn table(&self) -> String {
+        let phase = |value: Option<f64>| value.map_or("n/a".to_string(), |ms| format!("{:.1}ms", ms));
+        [
+            ("DNS lookup", phase(self.dns_ms)),
+            ("TCP connect", phase(self.connect_ms)),
+            ("TLS handshake", phase(self.tls_ms)),
+            ("Time to first byte", phase(self.ttfb_ms)),
+            ("Body download", phase(self.download_ms)),
+            ("Total", format!("{:.1}ms", self.total_ms)),
+        ]
+        .iter()
+        .map(|(label, value)| format!("  {:<20}{:>10}", label, value))
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+}
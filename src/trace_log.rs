@@ -0,0 +1,160 @@
+//! `call --trace-log FILE`: every request and response of a call appended to a file as they
+//! went over the wire: request line, headers and body, then status line, headers and body.
+//!
+//! Values of the headers captures redact ([`SENSITIVE_HEADERS`]) and of those listed with
+//! `config redact add` are written as `***len=N***`, here and in `-v`/`-i` output, and
+//! wherever those values show up again in a body (an echo server sends them back).
+//! Bodies that aren't UTF-8 are hex-dumped, up to [`MAX_HEX_DUMP`] bytes.
+
+use crate::capture::SENSITIVE_HEADERS;
+use chrono::Utc;
+use reqwest::header::HeaderMap;
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::io::Write as _;
+
+/// Bytes of a binary body hex-dumped before the rest is only counted.
+pub const MAX_HEX_DUMP: usize = 4096;
+
+/// Secrets shorter than this aren't searched for in bodies, where they'd match by chance.
+const MIN_SECRET_LEN: usize = 6;
+
+/// Which header values are masked: [`SENSITIVE_HEADERS`] plus the configured names.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Redactor {
+    extra: Vec<String>,
+}
+
+impl Redactor {
+    pub fn new(extra: &[String]) -> Self {
+        Self { extra: extra.iter().map(|name| name.to_ascii_lowercase()).collect() }
+    }
+
+    pub fn is_redacted(&self, name: &str) -> bool {
+        SENSITIVE_HEADERS.iter().any(|redacted| name.eq_ignore_ascii_case(redacted))
+            || self.extra.iter().any(|redacted| name.eq_ignore_ascii_case(redacted))
+    }
+
+    /// `value`, or `***len=N***` when `name` is redacted.
+    pub fn header_value<'a>(&self, name: &str, value: &'a str) -> Cow<'a, str> {
+        match self.is_redacted(name) {
+            true => Cow::Owned(mask(value)),
+            false => Cow::Borrowed(value),
+        }
+    }
+
+    /// The redacted values among `headers`, to be masked in bodies too: each whole value and,
+    /// for `Bearer xyz`-style values, the credentials after the scheme.
+    pub fn secrets<'a>(&self, headers: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<String> {
+        let mut secrets = Vec::new();
+        for (_, value) in headers.into_iter().filter(|(name, _)| self.is_redacted(name)) {
+            secrets.push(value.to_string());
+            if let Some((_, credentials)) = value.split_once(' ') {
+                secrets.push(credentials.trim().to_string());
+            }
+        }
+        secrets.retain(|secret| secret.len() >= MIN_SECRET_LEN);
+        // Longest first, so a token is never half-masked by a shorter secret inside it
+        secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+        secrets.dedup();
+        secrets
+    }
+}
+
+pub fn mask(value: &str) -> String {
+    format!("***len={}***", value.len())
+}
+
+/// A body as it goes in the log: text with `secrets` masked, or a hex dump.
+pub fn body_text(body: &[u8], secrets: &[String]) -> String {
+    match std::str::from_utf8(body) {
+        Ok(text) => mask_secrets(text, secrets),
+        Err(_) => hex_dump(body),
+    }
+}
+
+/// `text` with every one of `secrets` masked.
+pub fn mask_secrets(text: &str, secrets: &[String]) -> String {
+    secrets.iter().fold(text.to_string(), |text, secret| text.replace(secret.as_str(), &mask(secret)))
+}
+
+/// `xxd`-style lines of 16 bytes, up to [`MAX_HEX_DUMP`] bytes.
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, line) in bytes[..bytes.len().min(MAX_HEX_DUMP)].chunks(16).enumerate() {
+        let hex: Vec<String> = line.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = line.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }).collect();
+        let _ = writeln!(out, "{:08x}  {:<47}  {}", i * 16, hex.join(" "), ascii);
+    }
+    if bytes.len() > MAX_HEX_DUMP {
+        let _ = writeln!(out, "... {} more bytes", bytes.len() - MAX_HEX_DUMP);
+    }
+    out
+}
+
+/// The request line, `Host`, headers and body of `request`.
+pub fn request_text(request: &reqwest::Request, redactor: &Redactor, secrets: &[String]) -> String {
+    let url = request.url();
+    let target = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    let mut out = format!("--- request {}\n", Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+    let _ = writeln!(out, "{} {} {}", request.method(), target, crate::commands::call::version_name(request.version()));
+    if !request.headers().contains_key(reqwest::header::HOST) {
+        let host = url.host_str().unwrap_or_default();
+        let _ = match url.port() {
+            Some(port) => writeln!(out, "host: {}:{}", host, port),
+            None => writeln!(out, "host: {}", host),
+        };
+    }
+    write_headers(&mut out, request.headers(), redactor);
+    out.push('\n');
+    if let Some(body) = request.body() {
+        match body.as_bytes() {
+            Some(bytes) => push_body(&mut out, &body_text(bytes, secrets)),
+            None => out.push_str("(streamed body, not logged)\n"),
+        }
+    }
+    out
+}
+
+/// The status line, headers and body of a response; `body` is `None` when it wasn't read,
+/// with `missing` saying why.
+pub fn response_text(status: reqwest::StatusCode, version: reqwest::Version, headers: &HeaderMap, body: Option<&[u8]>, missing: &str, redactor: &Redactor, secrets: &[String]) -> String {
+    let mut out = format!("--- response {}\n", Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+    let _ = writeln!(out, "{} {}", crate::commands::call::version_name(version), status);
+    write_headers(&mut out, headers, redactor);
+    out.push('\n');
+    match body {
+        Some(body) => push_body(&mut out, &body_text(body, secrets)),
+        None => {
+            let _ = writeln!(out, "({})", missing);
+        }
+    }
+    out
+}
+
+fn write_headers(out: &mut String, headers: &HeaderMap, redactor: &Redactor) {
+    for (name, value) in headers {
+        let value = String::from_utf8_lossy(value.as_bytes());
+        let _ = writeln!(out, "{}: {}", name, redactor.header_value(name.as_str(), &value));
+    }
+}
+
+fn push_body(out: &mut String, body: &str) {
+    out.push_str(body);
+    if !body.is_empty() && !body.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Add `text` to the end of the trace file at `path`, creating it if needed.
+pub fn append(path: &str, text: &str) -> Result<(), String> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|e| format!("Could not write the trace log '{}': {}", path, e))
+}
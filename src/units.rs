@@ -0,0 +1,69 @@
+//! Durations and byte sizes as typed on the command line: `500ms`, `30s`, `5m`, `1h`, and
+//! `512`, `64k`, `10m`, `1g`.
+//!
+//! A bare number is seconds (or bytes), and fractions such as `1.5s` or `0.5m` are fine.
+//! Anything else, from an unknown unit to a negative number, is an error naming what was
+//! expected, never a value quietly read as something else.
+
+use std::time::Duration;
+
+const DURATION_UNITS: &str = "ms, s, m or h, like 500ms, 30s, 5m or 1h";
+const SIZE_UNITS: &str = "k, m or g, like 512, 64k, 10m or 1g";
+
+/// `500ms`, `30s`, `5m` or `1h`; a bare number is seconds. Zero is allowed.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let (number, unit) = split_unit(value, DURATION_UNITS)?;
+    let seconds = match unit.as_str() {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return Err(format!("'{}' has an unknown unit '{}'; use {}", value.trim(), unit, DURATION_UNITS)),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("'{}' is too long", value.trim()))
+}
+
+/// [`parse_duration`] for waits that make no sense at zero: timeouts, intervals, run lengths.
+pub fn parse_positive_duration(value: &str) -> Result<Duration, String> {
+    match parse_duration(value)? {
+        duration if duration.is_zero() => Err(format!("'{}' must be more than zero", value.trim())),
+        duration => Ok(duration),
+    }
+}
+
+/// Bytes: a bare number, or `k`, `m` or `g` (powers of 1024, optionally followed by `b`
+/// or `ib`). Zero is allowed.
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let (number, unit) = split_unit(value, SIZE_UNITS)?;
+    let scale: u64 = match unit.as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        _ => return Err(format!("'{}' has an unknown unit '{}'; use {}", value.trim(), unit, SIZE_UNITS)),
+    };
+    let bytes = (number * scale as f64).round();
+    // u64::MAX as f64 rounds up to 2^64, so this also rejects that
+    if bytes >= u64::MAX as f64 {
+        return Err(format!("'{}' is too large", value.trim()));
+    }
+    Ok(bytes as u64)
+}
+
+/// The number and the lowercased unit after it, `""` when there is none.
+fn split_unit(value: &str, units: &str) -> Result<(f64, String), String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(format!("a value is required; use {}", units));
+    }
+    let split = value.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.trim().parse()
+        .ok()
+        .filter(|n: &f64| n.is_finite())
+        .ok_or_else(|| format!("'{}' is not a number followed by {}", value, units))?;
+    if number < 0.0 {
+        return Err(format!("'{}' is negative", value));
+    }
+    Ok((number, unit.to_ascii_lowercase()))
+}
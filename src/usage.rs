@@ -0,0 +1,202 @@
+//! Token usage of AI requests, kept per day, command and model in `~/.nuts/usage.json`, and
+//! what it is estimated to cost.
+//!
+//! Every client from [`crate::ai::client`] records the usage its provider reports. Prices are
+//! per million tokens; `config ai price` overrides the built-in ones.
+
+use crate::config::Config;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Serializes read-modify-write of the usage file between concurrent AI requests.
+static LEDGER_LOCK: Mutex<()> = Mutex::new(());
+
+/// Tokens one AI request consumed, as the provider reports them.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// USD per million input and output tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, counts: &UsageCounts) -> f64 {
+        (counts.input_tokens as f64 * self.input + counts.output_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageCounts {
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl UsageCounts {
+    fn add(&mut self, other: &UsageCounts) {
+        self.requests += other.requests;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+    }
+}
+
+/// One line of the `usage` table: totals for a day or a command, and their estimated cost.
+/// `priced` is false when some of the tokens were for a model without a known price.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageRow {
+    pub label: String,
+    pub counts: UsageCounts,
+    pub cost: f64,
+    pub priced: bool,
+}
+
+/// Everything recorded: day (`YYYY-MM-DD`, local time) → command → model → counts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageBook {
+    #[serde(default)]
+    pub days: BTreeMap<String, BTreeMap<String, BTreeMap<String, UsageCounts>>>,
+}
+
+impl UsageBook {
+    pub fn add(&mut self, day: &str, command: &str, model: &str, usage: TokenUsage) {
+        let counts = UsageCounts { requests: 1, input_tokens: usage.input_tokens, output_tokens: usage.output_tokens };
+        self.days.entry(day.to_string()).or_default()
+            .entry(command.to_string()).or_default()
+            .entry(model.to_string()).or_default()
+            .add(&counts);
+    }
+
+    /// Estimated cost of `day`, with `price` giving each model's price.
+    pub fn cost_on(&self, day: &str, price: impl Fn(&str) -> Option<ModelPrice>) -> f64 {
+        self.rows(&[day.to_string()], |_, _| day.to_string(), price).first().map_or(0.0, |row| row.cost)
+    }
+
+    /// Totals per day for the last `days` days recorded, oldest first.
+    pub fn by_day(&self, days: usize, price: impl Fn(&str) -> Option<ModelPrice>) -> Vec<UsageRow> {
+        self.rows(&self.recent_days(days), |day, _| day.to_string(), price)
+    }
+
+    /// Totals per command over the last `days` days recorded, by name.
+    pub fn by_command(&self, days: usize, price: impl Fn(&str) -> Option<ModelPrice>) -> Vec<UsageRow> {
+        self.rows(&self.recent_days(days), |_, command| command.to_string(), price)
+    }
+
+    fn recent_days(&self, days: usize) -> Vec<String> {
+        let all: Vec<String> = self.days.keys().cloned().collect();
+        all[all.len().saturating_sub(days)..].to_vec()
+    }
+
+    fn rows(&self, days: &[String], label: impl Fn(&str, &str) -> String, price: impl Fn(&str) -> Option<ModelPrice>) -> Vec<UsageRow> {
+        let mut rows: BTreeMap<String, UsageRow> = BTreeMap::new();
+        for day in days {
+            for (command, models) in self.days.get(day).into_iter().flatten() {
+                let key = label(day, command);
+                let row = rows.entry(key.clone())
+                    .or_insert_with(|| UsageRow { label: key, counts: UsageCounts::default(), cost: 0.0, priced: true });
+                for (model, counts) in models {
+                    row.counts.add(counts);
+                    match price(model) {
+                        Some(price) => row.cost += price.cost(counts),
+                        None => row.priced = false,
+                    }
+                }
+            }
+        }
+        rows.into_values().collect()
+    }
+}
+
+/// The usage file.
+pub struct UsageLedger {
+    path: PathBuf,
+}
+
+impl UsageLedger {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `~/.nuts/usage.json`.
+    pub fn default_path() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(dirs::home_dir()
+            .ok_or("Could not find home directory")?
+            .join(".nuts")
+            .join("usage.json"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// What has been recorded so far; nothing when the file doesn't exist yet.
+    pub fn load(&self) -> Result<UsageBook, Box<dyn Error>> {
+        if !self.path.exists() {
+            return Ok(UsageBook::default());
+        }
+        let content = std::fs::read_to_string(&self.path)?;
+        serde_json::from_str(&content).map_err(|e| format!("Could not read {}: {}", self.path.display(), e).into())
+    }
+
+    /// Add one request's usage to today's totals and return the updated book.
+    pub fn record(&self, command: &str, model: &str, usage: TokenUsage) -> Result<UsageBook, Box<dyn Error>> {
+        let _guard = LEDGER_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut book = self.load()?;
+        book.add(&today(), command, model, usage);
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&book)?)?;
+        Ok(book)
+    }
+}
+
+/// Today's key in the usage file.
+pub fn today() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// The `usage` tables for the last `days` days recorded, by day and by command, priced with
+/// `config`. Costs marked `+` leave out tokens of models without a price.
+pub fn render(book: &UsageBook, days: usize, config: &Config) -> Vec<String> {
+    let price = |model: &str| config.model_price(model);
+    let table = |title: &str, rows: &[UsageRow]| {
+        let mut lines = vec![format!("   {:<14} {:>9} {:>12} {:>12} {:>10}", title, "Requests", "Input", "Output", "Cost")];
+        for row in rows {
+            let cost = format!("${:.2}{}", row.cost, if row.priced { "" } else { "+" });
+            lines.push(format!(
+                "   {:<14} {:>9} {:>12} {:>12} {:>10}",
+                row.label, row.counts.requests, row.counts.input_tokens, row.counts.output_tokens, cost
+            ));
+        }
+        lines
+    };
+
+    let by_day = book.by_day(days, price);
+    if by_day.is_empty() {
+        return vec!["📭 No AI usage recorded yet".to_string()];
+    }
+    let by_command = book.by_command(days, price);
+    let mut lines = vec![format!("📊 AI usage, last {} day(s) recorded (estimated cost)", by_day.len())];
+    lines.extend(table("Day", &by_day));
+    lines.push(String::new());
+    lines.extend(table("Command", &by_command));
+    if by_day.iter().chain(&by_command).any(|row| !row.priced) {
+        lines.push("   + some models have no price; set one with 'config ai price <model> <input> <output>'".to_string());
+    }
+    if let Some(budget) = config.ai_daily_budget {
+        let spent = book.cost_on(&today(), price);
+        let status = if spent > budget { "⚠️  over budget" } else { "within budget" };
+        lines.push(format!("   Today: ${:.2} of the ${:.2} daily budget, {}", spent, budget, status));
+    }
+    lines
+}
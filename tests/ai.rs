@@ -0,0 +1,139 @@
+mod common;
+
+use common::{isolated_home, FixtureServer, AI_KEY};
+use nuts::ai::{self, AiFailure, AiFailureKind, AiMessage, AiProvider, OpenAiClient};
+use nuts::config::Config;
+use serde_json::json;
+
+fn openai_config(base_url: String, key: &str) -> Config {
+    isolated_home("ai");
+    Config {
+        ai_provider: AiProvider::OpenAi,
+        ai_base_url: Some(base_url),
+        ai_api_key: Some(key.to_string()),
+        ..Config::default()
+    }
+}
+
+#[tokio::test]
+async fn the_openai_provider_sends_chat_completions() {
+    let server = FixtureServer::start().await;
+    let client = ai::client(&openai_config(server.url(""), AI_KEY), "ask").unwrap();
+
+    assert_eq!(client.ask("Say hi").await.unwrap(), "model=gpt-4o-mini messages=1 max_tokens=2000 user: Say hi");
+    let conversation = [AiMessage::user("Give me JSON"), AiMessage::assistant("nope"), AiMessage::user("Only JSON please")];
+    assert_eq!(client.complete(&conversation).await.unwrap(), "model=gpt-4o-mini messages=3 max_tokens=2000 user: Only JSON please");
+
+    let config = Config { ai_model: Some("llama-3-70b".to_string()), ai_max_tokens: Some(300), ..openai_config(server.url("/v1/"), AI_KEY) };
+    assert_eq!(ai::client(&config, "ask").unwrap().ask("x").await.unwrap(), "model=llama-3-70b messages=1 max_tokens=300 user: x");
+
+    let error = ai::client(&openai_config(server.url(""), "wrong"), "ask").unwrap().ask("x").await.unwrap_err().to_string();
+    assert_eq!(error, "API key invalid — run 'config ai key <key>'");
+    assert_eq!(server.hits(), 4);
+}
+
+#[tokio::test]
+async fn overloads_are_retried_and_quota_errors_are_not() {
+    let server = FixtureServer::start().await;
+    let client = ai::client(&openai_config(server.url(""), AI_KEY), "ask").unwrap();
+
+    assert!(client.ask("busy 2").await.unwrap().ends_with("user: busy 2"));
+    assert_eq!(server.hits(), 3);
+
+    let error = client.ask("busy 5").await.unwrap_err();
+    let failure = error.downcast_ref::<AiFailure>().unwrap();
+    assert_eq!((failure.kind, failure.status, failure.attempts), (AiFailureKind::Unavailable, Some(503), ai::MAX_AI_ATTEMPTS));
+    assert_eq!(failure.to_string(), "openai is unavailable (HTTP 503) after 3 attempts — try again later");
+    assert_eq!(server.hits(), 6);
+
+    let error = client.ask("quota").await.unwrap_err();
+    assert_eq!(error.to_string(), "openai quota exhausted — check the plan and billing of your account");
+    assert_eq!(server.hits(), 7);
+
+    let unreachable = ai::client(&openai_config("http://127.0.0.1:9".to_string(), AI_KEY), "ask").unwrap();
+    let failure = unreachable.ask("x").await.unwrap_err().downcast::<AiFailure>().unwrap();
+    assert_eq!((failure.kind, failure.attempts), (AiFailureKind::Network, 3));
+}
+
+#[test]
+fn failures_are_classified_from_provider_payloads() {
+    let classify = |provider, status, body: serde_json::Value| AiFailure::classify(provider, status, &body).kind;
+    let anthropic = |kind: &str, message: &str| json!({ "type": "error", "error": { "type": kind, "message": message } });
+
+    assert_eq!(classify(AiProvider::Anthropic, Some(401), anthropic("authentication_error", "invalid x-api-key")), AiFailureKind::InvalidKey);
+    assert_eq!(classify(AiProvider::Anthropic, Some(429), anthropic("rate_limit_error", "Number of requests has exceeded your rate limit")), AiFailureKind::RateLimited);
+    assert_eq!(classify(AiProvider::Anthropic, Some(529), anthropic("overloaded_error", "Overloaded")), AiFailureKind::Unavailable);
+    assert_eq!(classify(AiProvider::Anthropic, Some(400), anthropic("invalid_request_error", "Your credit balance is too low to access the Anthropic API.")), AiFailureKind::QuotaExhausted);
+    assert_eq!(classify(AiProvider::Anthropic, Some(404), anthropic("not_found_error", "model: claude-9")), AiFailureKind::UnknownModel);
+    assert_eq!(classify(AiProvider::Anthropic, Some(400), anthropic("invalid_request_error", "messages: field required")), AiFailureKind::Other);
+
+    let openai = |code: &str, message: &str| json!({ "error": { "message": message, "type": "invalid_request_error", "code": code } });
+    assert_eq!(classify(AiProvider::OpenAi, Some(429), openai("insufficient_quota", "You exceeded your current quota")), AiFailureKind::QuotaExhausted);
+    assert_eq!(classify(AiProvider::OpenAi, Some(429), openai("rate_limit_exceeded", "Rate limit reached")), AiFailureKind::RateLimited);
+    assert_eq!(classify(AiProvider::OpenAi, Some(400), openai("content_filter", "The response was filtered due to the prompt triggering Azure OpenAI's content management policy.")), AiFailureKind::ContentFiltered);
+    assert_eq!(classify(AiProvider::OpenAi, Some(502), serde_json::Value::Null), AiFailureKind::Unavailable);
+
+    // The Anthropic client only has the error text
+    let failure = AiFailure::from_text(AiProvider::Anthropic, r#"API error: {"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#);
+    assert_eq!(failure.to_string(), "API key invalid — run 'config api-key'");
+    assert_eq!(AiFailure::from_text(AiProvider::Anthropic, "network error: connection refused").kind, AiFailureKind::Network);
+    assert!(!AiFailure::content_filtered(AiProvider::Anthropic).is_retryable());
+}
+
+#[test]
+fn endpoints_and_keys_follow_the_provider() {
+    let endpoint = |base: &str| OpenAiClient::new(base, String::new(), String::new(), 1).endpoint().to_string();
+    assert_eq!(endpoint("https://api.openai.com"), "https://api.openai.com/v1/chat/completions");
+    assert_eq!(endpoint("http://localhost:4000/v1/"), "http://localhost:4000/v1/chat/completions");
+    let azure = "https://acme.openai.azure.com/openai/deployments/gpt4/chat/completions?api-version=2024-02-01";
+    assert_eq!(endpoint(azure), azure);
+
+    // Each provider has its own key, and offline mode follows the selected one
+    let config = Config { anthropic_api_key: Some("sk-ant".to_string()), ..Config::default() };
+    assert_eq!(config.ai_key(), Some("sk-ant"));
+    let openai = Config { ai_provider: AiProvider::OpenAi, ..config.clone() };
+    assert!(openai.is_offline());
+    assert!(ai::client(&openai, "ask").err().unwrap().to_string().contains("config ai key"));
+    assert_eq!(AiProvider::from_name("OpenAI"), Some(AiProvider::OpenAi));
+
+    let saved: Config = serde_json::from_str(&serde_json::to_string(&Config { ai_provider: AiProvider::OpenAi, ..Config::default() }).unwrap()).unwrap();
+    assert_eq!(saved.ai_provider, AiProvider::OpenAi);
+    assert!(!serde_json::to_string(&Config::default()).unwrap().contains("ai_provider"));
+}
+
+#[test]
+fn model_and_token_limit_come_from_config() {
+    let config = Config::default();
+    assert_eq!((config.ai_model(), config.ai_max_tokens()), (ai::DEFAULT_ANTHROPIC_MODEL, ai::DEFAULT_MAX_TOKENS));
+    let openai = Config { ai_provider: AiProvider::OpenAi, ai_max_tokens: Some(0), ..Config::default() };
+    assert_eq!((openai.ai_model(), openai.ai_max_tokens()), (ai::DEFAULT_OPENAI_MODEL, ai::DEFAULT_MAX_TOKENS));
+
+    let mut args: Vec<String> = ["list", "orders", "--model", "claude-haiku-4-5", "--yes"].map(String::from).to_vec();
+    assert_eq!(ai::take_model_flag(&mut args).unwrap().as_deref(), Some("claude-haiku-4-5"));
+    assert_eq!(args, ["list", "orders", "--yes"]);
+    assert!(ai::take_model_flag(&mut vec!["--model".to_string()]).is_err());
+}
+
+/// Model names and token limits live in src/ai.rs and the config; nothing else hardcodes one.
+#[test]
+fn no_command_hardcodes_a_model_or_token_limit() {
+    fn sources(dir: &std::path::Path, files: &mut Vec<std::path::PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                sources(&path, files);
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                files.push(path);
+            }
+        }
+    }
+    let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    let mut files = Vec::new();
+    sources(&src, &mut files);
+    for file in files.iter().filter(|file| !file.ends_with("ai.rs")) {
+        let text = std::fs::read_to_string(file).unwrap();
+        for needle in ["\"claude-", "\"gpt-", ".max_tokens(", ".model("] {
+            assert!(!text.contains(needle), "{} contains {}", file.display(), needle);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::{isolated_home, FixtureServer, AI_KEY};
+use nuts::ai::{self, AiMessage, AiProvider};
+use nuts::ai_cache::{self, AiCache};
+use nuts::config::Config;
+use std::time::Duration;
+
+#[test]
+fn entries_are_keyed_by_model_and_conversation_and_expire() {
+    let key = AiCache::key("gpt-4o-mini", &[AiMessage::user("hi")]);
+    assert_eq!(key.len(), 64);
+    assert_eq!(key, AiCache::key("gpt-4o-mini", &[AiMessage::user("hi")]));
+    assert_ne!(key, AiCache::key("gpt-4o", &[AiMessage::user("hi")]));
+    assert_ne!(key, AiCache::key("gpt-4o-mini", &[AiMessage::assistant("hi")]));
+    assert_ne!(AiCache::key("m", &[AiMessage::user("a"), AiMessage::user("b")]), AiCache::key("m", &[AiMessage::user("ab")]));
+
+    let dir = std::env::temp_dir().join(format!("nuts-ai-cache-{}", std::process::id()));
+    let cache = AiCache::new(&dir, Duration::from_secs(3600));
+    assert_eq!(cache.get(&key), None);
+    cache.put(&key, "gpt-4o-mini", "hello there").unwrap();
+    assert_eq!(cache.get(&key).as_deref(), Some("hello there"));
+
+    let expired = AiCache::new(&dir, Duration::ZERO);
+    assert_eq!(expired.get(&key), None);
+    let stats = expired.stats().unwrap();
+    assert_eq!((stats.entries, stats.expired), (1, 1));
+    assert!(stats.bytes > 0);
+    assert_eq!(cache.clear().unwrap(), 1);
+    assert_eq!(cache.stats().unwrap().entries, 0);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn clients_reuse_answers_unless_bypassed_or_disabled() {
+    let home = isolated_home("ai-cache");
+    let server = FixtureServer::start().await;
+    let config = Config {
+        ai_provider: AiProvider::OpenAi,
+        ai_base_url: Some(server.url("")),
+        ai_api_key: Some(AI_KEY.to_string()),
+        ..Config::default()
+    };
+
+    let first = ai::client(&config, "ask").unwrap().ask("cached question").await.unwrap();
+    assert_eq!(ai::client(&config, "ask").unwrap().ask("cached question").await.unwrap(), first);
+    assert_eq!(server.hits(), 1);
+
+    // A pre-populated entry answers without reaching the provider
+    let key = AiCache::key(config.ai_model(), &[AiMessage::user("recorded question")]);
+    AiCache::configured(&config).unwrap().put(&key, config.ai_model(), "recorded answer").unwrap();
+    assert!(home.join(".nuts/cache/ai").join(format!("{}.json", key)).exists());
+    assert_eq!(ai::client(&config, "ask").unwrap().ask("recorded question").await.unwrap(), "recorded answer");
+    assert_eq!(server.hits(), 1);
+
+    ai_cache::set_bypass(true);
+    let fresh = ai::client(&config, "ask").unwrap().ask("recorded question").await.unwrap();
+    ai_cache::set_bypass(false);
+    assert!(fresh.starts_with("model=gpt-4o-mini"));
+    // ...and refreshes the entry
+    assert_eq!(ai::client(&config, "ask").unwrap().ask("recorded question").await.unwrap(), fresh);
+    assert_eq!(server.hits(), 2);
+
+    let uncached = Config { ai_cache_ttl_days: Some(0), ..config };
+    ai::client(&uncached, "ask").unwrap().ask("cached question").await.unwrap();
+    assert_eq!(server.hits(), 3);
+}
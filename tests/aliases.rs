@@ -0,0 +1,69 @@
+mod common;
+
+use common::{isolated_home, FixtureServer};
+use nuts::aliases::{expand, validate};
+use nuts::config::Config;
+use nuts::shell::NutsShell;
+use std::collections::BTreeMap;
+
+fn macros(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+    entries.iter().map(|(name, body)| (name.to_string(), body.to_string())).collect()
+}
+
+fn words(line: &str) -> Vec<String> {
+    line.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn expands_macros_placeholders_and_built_in_aliases() {
+    let macros = macros(&[
+        ("u", "call GET {{base_url}}/users/$1"),
+        ("swap", "c POST $2/$1 '{\"note\": \"$$1 stays\"}'"),
+        ("smoke", "flow run prod-api --all --fail-fast"),
+        ("prod", "smoke --format junit"),
+    ]);
+    assert_eq!(expand(words("u 42 -v"), &macros).unwrap(), ["call", "GET", "{{base_url}}/users/42", "-v"]);
+    assert_eq!(expand(words("swap items https://x.io"), &macros).unwrap(), ["call", "POST", "https://x.io/items", "{\"note\": \"$items stays\"}"]);
+    assert_eq!(expand(words("prod --out r.xml"), &macros).unwrap(), words("flow run prod-api --all --fail-fast --format junit --out r.xml"));
+    assert_eq!(expand(words("p GET https://x.io"), &macros).unwrap(), words("perf GET https://x.io"));
+    assert_eq!(expand(words("call GET u"), &macros).unwrap(), words("call GET u"));
+    assert_eq!(expand(words("u"), &macros).unwrap_err(), "Alias 'u' needs at least 1 argument(s): call GET {{base_url}}/users/$1");
+}
+
+#[test]
+fn rejects_recursive_and_shadowing_aliases() {
+    let existing = macros(&[("a", "b --x"), ("b", "call GET https://x.io")]);
+    assert_eq!(validate("b", "a", &existing).unwrap_err(), "Alias loop: b → a → b");
+    assert_eq!(validate("self", "self -v", &existing).unwrap_err(), "Alias loop: self → self");
+    assert_eq!(validate("call", "perf GET x", &existing).unwrap_err(), "'call' is a built-in command or alias");
+    assert_eq!(validate("p", "perf GET x", &existing).unwrap_err(), "'p' is a built-in command or alias");
+    assert!(validate("c2", "a", &existing).is_ok());
+
+    // Loops written into the config by hand are caught when run
+    let looped = macros(&[("a", "b"), ("b", "a $1")]);
+    assert_eq!(expand(words("a 1"), &looped).unwrap_err(), "Alias loop: a → b → a");
+}
+
+#[tokio::test]
+async fn saved_aliases_persist_and_run() {
+    let home = isolated_home("aliases");
+    let server = FixtureServer::start().await;
+    let mut shell = NutsShell::with_config(Config::default());
+    shell.process_command(&format!("alias add echo-as \"call PATCH {}/$1 -H 'X-Who: $2'\"", server.url(""))).await.unwrap();
+    shell.process_command("alias add again \"echo-as echo\"").await.unwrap();
+    // Refused: would lead back to itself
+    let refused = shell.process_command("alias add echo-as again").await.unwrap_err();
+    assert!(refused.to_string().contains("loop"), "{}", refused);
+
+    let saved = Config::load().unwrap();
+    assert_eq!(saved.aliases["again"], "echo-as echo");
+    assert!(saved.aliases["echo-as"].starts_with("call PATCH"), "{:?} in {}", saved.aliases, home.display());
+
+    shell.process_command("again ada").await.unwrap();
+    let response: serde_json::Value = serde_json::from_str(shell.last_response().unwrap()).unwrap();
+    assert_eq!((response["method"].as_str(), response["headers"]["x-who"].as_str()), (Some("PATCH"), Some("ada")));
+
+    shell.process_command("alias rm again").await.unwrap();
+    assert!(!Config::load().unwrap().aliases.contains_key("again"));
+    assert!(shell.process_command("again ada").await.unwrap_err().to_string().contains("Unknown command 'again'"));
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{FixtureServer, OAUTH_CLIENT_ID, OAUTH_CLIENT_SECRET};
+use nuts::auth::{AccessToken, OAuth2Config, TokenManager, REFRESH_MARGIN};
+use nuts::commands::call::CallCommand;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn oauth2(server: &FixtureServer, token_path: &str, secret: &str) -> OAuth2Config {
+    OAuth2Config {
+        token_url: server.url(token_path),
+        client_id: OAUTH_CLIENT_ID.to_string(),
+        client_secret: secret.to_string(),
+        scope: Some("read write".to_string()),
+    }
+}
+
+#[test]
+fn refreshes_within_the_margin_of_expiry() {
+    let now = Instant::now();
+    let token = |expires_at| AccessToken { value: "t".to_string(), expires_at };
+
+    assert!(!token(Some(now + REFRESH_MARGIN + Duration::from_secs(1))).needs_refresh(now));
+    assert!(token(Some(now + REFRESH_MARGIN - Duration::from_secs(1))).needs_refresh(now));
+    assert!(token(Some(now)).needs_refresh(now));
+    assert!(!token(None).needs_refresh(now + Duration::from_secs(86_400)));
+}
+
+#[tokio::test]
+async fn caches_tokens_until_they_near_expiry() {
+    let server = FixtureServer::start().await;
+
+    let long_lived = TokenManager::new(oauth2(&server, "/oauth/token?expires_in=3600", OAUTH_CLIENT_SECRET));
+    assert_eq!(long_lived.token().await.unwrap(), "token-1");
+    assert_eq!(long_lived.token().await.unwrap(), "token-1");
+    assert_eq!(server.tokens_issued(), 1);
+
+    // 20s left is inside the 30s margin, so every use fetches a fresh token
+    let short_lived = TokenManager::new(oauth2(&server, "/oauth/token?expires_in=20", OAUTH_CLIENT_SECRET));
+    assert_eq!(short_lived.token().await.unwrap(), "token-2");
+    assert_eq!(short_lived.token().await.unwrap(), "token-3");
+    assert_eq!(server.tokens_issued(), 3);
+}
+
+#[tokio::test]
+async fn call_sends_the_oauth2_token_and_reports_endpoint_errors() {
+    let server = FixtureServer::start().await;
+    let tokens = Arc::new(TokenManager::new(oauth2(&server, "/oauth/token", OAUTH_CLIENT_SECRET)));
+    let call = CallCommand::new().with_token_manager(tokens);
+
+    for _ in 0..2 {
+        let response = call.execute(&["call", "--auth", "oauth2", &server.url("/echo")]).await.unwrap();
+        let echo: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(echo["headers"]["authorization"], "Bearer token-1");
+    }
+    assert_eq!(server.tokens_issued(), 1);
+
+    let rejected = CallCommand::new()
+        .with_token_manager(Arc::new(TokenManager::new(oauth2(&server, "/oauth/token", "wrong"))));
+    let error = rejected.execute(&["call", "--auth", "oauth2", &server.url("/echo")]).await.unwrap_err().to_string();
+    assert!(error.contains("401 Unauthorized"), "{}", error);
+    assert!(error.contains("Client authentication failed"), "{}", error);
+
+    let error = CallCommand::new().execute(&["call", "--auth", "oauth2", &server.url("/echo")]).await.unwrap_err().to_string();
+    assert!(error.contains("config auth oauth2"), "{}", error);
+    let error = CallCommand::new().execute(&["call", "--auth", "digest", &server.url("/echo")]).await.unwrap_err().to_string();
+    assert_eq!(error, "Unsupported auth scheme 'digest'. Supported: oauth2");
+}
+
+#[tokio::test]
+async fn perf_fetches_one_token_for_the_whole_run() {
+    let server = FixtureServer::start().await;
+    let tokens = Arc::new(TokenManager::new(oauth2(&server, "/oauth/token", OAUTH_CLIENT_SECRET)));
+    let perf = nuts::commands::perf::PerfCommand::new(&nuts::config::Config::default()).with_token_manager(tokens);
+
+    let summary = perf
+        .run(&server.url("/echo"), 2, Duration::from_millis(500), "GET", None, &Default::default())
+        .await
+        .unwrap();
+    assert!(summary.total_requests > 0);
+    assert_eq!(summary.error_rate, 0.0);
+    assert_eq!(server.tokens_issued(), 1);
+}
+
+#[test]
+fn parses_oauth2_config_arguments() {
+    let config = OAuth2Config::from_args(&[
+        "--token-url", "https://auth.example.com/token", "--client-id", "app", "--client-secret", "s3cret", "--scope", "read",
+    ])
+    .unwrap();
+    assert_eq!(config.token_url, "https://auth.example.com/token");
+    assert_eq!(config.scope.as_deref(), Some("read"));
+
+    let missing = OAuth2Config::from_args(&["--token-url", "https://auth.example.com/token", "--client-id", "app"]);
+    assert_eq!(missing.unwrap_err().to_string(), "--client-secret is required");
+    assert!(OAuth2Config::from_args(&["--token-url", "not a url", "--client-id", "a", "--client-secret", "b"]).is_err());
+}
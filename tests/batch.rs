@@ -0,0 +1,63 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::commands::batch::{parse_batch, BatchOptions, BatchRequest};
+use nuts::commands::call::CallCommand;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+fn batch_file(name: &str, lines: &[String]) -> String {
+    let path = std::env::temp_dir().join(format!("nuts-batch-{}-{}.jsonl", std::process::id(), name));
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    path.display().to_string()
+}
+
+#[test]
+fn parses_batch_lines_with_defaults() {
+    let requests = parse_batch("{\"url\": \"https://x/a\"}\n\n{\"method\": \"post\", \"url\": \"https://x/b\", \"body\": {\"n\": 1}}\n").unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0], BatchRequest { method: "GET".into(), url: "https://x/a".into(), headers: Default::default(), body: None });
+    assert_eq!(requests[1].body, Some(json!({ "n": 1 })));
+
+    let error = parse_batch("{\"url\": \"https://x\"}\n{\"method\": \"GET\"}").unwrap_err().to_string();
+    assert!(error.starts_with("Line 2 of the batch file: missing field `url`"), "{}", error);
+
+    let options = BatchOptions::from_args(&["call", "--batch", "r.jsonl", "--concurrency", "4", "--batch-out", "out.jsonl"]).unwrap();
+    assert_eq!((options.concurrency, options.out.as_deref()), (4, Some("out.jsonl")));
+    assert!(BatchOptions::from_args(&["call", "--batch", "r.jsonl", "--concurrency", "0"]).is_err());
+}
+
+#[tokio::test]
+async fn runs_a_batch_concurrently_and_keeps_going_after_failures() {
+    let server = FixtureServer::start().await;
+    let mut lines: Vec<String> = (0..4)
+        .map(|i| json!({ "method": "POST", "url": server.url(&format!("/echo?i={}", i)), "body": { "i": i }, "headers": { "X-Index": i.to_string() } }).to_string())
+        .collect();
+    lines.push(json!({ "url": server.url("/status/404") }).to_string());
+    lines.push(json!({ "url": "http://127.0.0.1:9/unreachable" }).to_string());
+    for _ in 0..4 {
+        lines.push(json!({ "url": server.url("/delay/600") }).to_string());
+    }
+    let file = batch_file("mixed", &lines);
+    let out = std::env::temp_dir().join(format!("nuts-batch-{}-out.jsonl", std::process::id()));
+
+    let args = ["call", "--batch", file.as_str(), "--concurrency", "4", "--batch-out", out.to_str().unwrap()];
+    let started = Instant::now();
+    let error = CallCommand::new().execute_batch(&args).await.unwrap_err().to_string();
+    assert_eq!(error, "2 of 10 batch request(s) failed");
+    // One after another the four 600ms delays alone would take 2.4s
+    assert!(started.elapsed() < Duration::from_millis(2000), "{:?}", started.elapsed());
+    assert_eq!(server.hits(), 9);
+
+    let results: Vec<Value> = std::fs::read_to_string(&out).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(results.iter().map(|r| r["index"].as_u64().unwrap()).collect::<Vec<_>>(), (1..=10).collect::<Vec<_>>());
+    assert_eq!(results[2]["status"], 200);
+    assert_eq!(results[2]["body"]["parsed"], json!({ "i": 2 }));
+    assert_eq!(results[2]["body"]["headers"]["x-index"], "2");
+    assert_eq!(results[4]["status"], 404);
+    assert!(results[5].get("status").is_none());
+    assert!(results[5]["error"].as_str().unwrap().contains("127.0.0.1:9"), "{}", results[5]);
+
+    let _ = std::fs::remove_file(&file);
+    let _ = std::fs::remove_file(&out);
+}
@@ -0,0 +1,842 @@
+mod common;
+
+use common::{FixtureServer, BASIC_PASSWORD, BASIC_USER, BEARER_TOKEN};
+use nuts::timing::Timings;
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn sends_method_headers_and_json_body() {
+    let server = FixtureServer::start().await;
+    let url = server.url("/echo?page=2");
+
+    let echo = common::call_json(&["-H", "X-Trace: abc", "PUT", &url, r#"{"name":"nuts"}"#])
+        .await
+        .unwrap();
+
+    assert_eq!(echo["method"], "PUT");
+    assert_eq!(echo["query"]["page"], "2");
+    assert_eq!(echo["headers"]["x-trace"], "abc");
+    assert_eq!(echo["parsed"]["name"], "nuts");
+}
+
+#[tokio::test]
+async fn sends_head_options_and_custom_methods() {
+    let server = FixtureServer::start().await;
+    let url = server.url("/echo");
+
+    assert_eq!(common::call_json(&["PURGE", &url]).await.unwrap()["method"], "PURGE");
+    assert_eq!(common::call_json(&["options", &url]).await.unwrap()["method"], "OPTIONS");
+    assert_eq!(common::call_json(&["-X", "propfind", &url]).await.unwrap()["method"], "PROPFIND");
+    let head = call(&["HEAD", &url]).await.unwrap();
+    assert_eq!((head.method.as_str(), head.status, head.body.as_str()), ("HEAD", 200, ""));
+
+    assert!(call(&["-X", "BAD METHOD", &url]).await.unwrap_err().to_string().contains("Invalid HTTP method"));
+    assert_eq!(nuts::commands::call::parse_method("Purge"), None);
+    assert_eq!(nuts::commands::call::parse_method("trace").as_deref(), Some("TRACE"));
+}
+
+#[tokio::test]
+async fn posts_form_data() {
+    let server = FixtureServer::start().await;
+
+    let echo = common::call_json(&["-F", "user=alice", "-F", "role=admin", &server.url("/echo")])
+        .await
+        .unwrap();
+
+    assert_eq!(echo["method"], "POST");
+    assert_eq!(echo["parsed"]["user"], "alice");
+    assert_eq!(echo["parsed"]["role"], "admin");
+}
+
+#[tokio::test]
+async fn uploads_files_as_multipart_alongside_text_fields() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-upload-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let report = dir.join("report.csv");
+    std::fs::write(&report, "id,name\n1,nuts\n").unwrap();
+    let avatar = dir.join("avatar.bin");
+    std::fs::write(&avatar, [0u8, 159, 146, 150]).unwrap();
+
+    let report_field = format!("report=@{}", report.display());
+    let avatar_field = format!("avatar=@{};type=image/png", avatar.display());
+    let upload = common::call_json(&["-F", "title=Q3", "-F", &report_field, "-F", &avatar_field, &server.url("/upload")])
+        .await
+        .unwrap();
+
+    let parts = upload["parts"].as_array().unwrap();
+    assert_eq!(parts.len(), 3);
+    assert_eq!(parts[0]["name"], "avatar");
+    assert_eq!(parts[0]["file_name"], "avatar.bin");
+    assert_eq!(parts[0]["content_type"], "image/png");
+    assert_eq!(parts[0]["size"], 4);
+    assert_eq!(parts[1]["name"], "report");
+    assert_eq!(parts[1]["file_name"], "report.csv");
+    assert_eq!(parts[1]["content_type"], "text/csv");
+    assert_eq!(parts[1]["size"], 15);
+    assert_eq!(parts[2]["name"], "title");
+    assert_eq!(parts[2]["text"], "Q3");
+
+    let missing = dir.join("missing.txt");
+    let error = common::call_json(&["-F", &format!("doc=@{}", missing.display()), &server.url("/upload")])
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains(&format!("'{}'", missing.display())), "{}", error);
+    assert_eq!(server.hits(), 1);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// What a call prints, rather than the body it saves.
+async fn printed(args: &[&str]) -> String {
+    let (result, printed) = nuts::output::capture(nuts::commands::call::CallCommand::new().execute(args)).await;
+    result.unwrap();
+    printed
+}
+
+#[tokio::test]
+async fn prints_and_saves_arrays_as_tables_and_csv() {
+    let server = FixtureServer::start().await;
+    let url = server.url("/reflect");
+    let body = r#"[{"id": 1, "name": "Ada Lovelace"}, {"id": 2, "name": "Grace Hopper", "team": "navy"}]"#;
+
+    let table = printed(&["call", "--table", "--max-col-width", "8", "POST", &url, body]).await;
+    assert!(table.contains("id  name      team\n──  ────────  ────\n1   Ada Lov…\n2   Grace H…  navy\n"), "{}", table);
+
+    let out = std::env::temp_dir().join(format!("nuts-table-{}.csv", std::process::id()));
+    let out_str = out.to_string_lossy().to_string();
+    printed(&["call", "--csv", "-o", &out_str, "POST", &url, body]).await;
+    assert_eq!(std::fs::read_to_string(&out).unwrap(), "id,name,team\n1,Ada Lovelace,\n2,Grace Hopper,navy\n");
+    let _ = std::fs::remove_file(&out);
+
+    // Anything else is shown as usual, after a notice
+    let other = printed(&["call", "--out-format", "csv", "POST", &url, r#"{"id": 1}"#]).await;
+    assert!(other.contains("isn't a JSON array of objects") && other.contains("\"id\": 1"), "{}", other);
+}
+
+#[tokio::test]
+async fn reads_data_from_files_and_joins_repeated_data() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-data-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let json = dir.join("payload.json");
+    std::fs::write(&json, "{\"name\": \"Ada\"}\n").unwrap();
+    let binary = dir.join("image.bin");
+    let bytes: Vec<u8> = (0..=255).collect();
+    std::fs::write(&binary, &bytes).unwrap();
+
+    let parse = |args: &[&str]| {
+        let args: Vec<&str> = ["call", "POST", "https://api.example.com"].iter().chain(args).copied().collect();
+        nuts::commands::call::CallCommand::new().parse_advanced_args(&args).unwrap()
+    };
+    assert_eq!(parse(&["-d", &format!("@{}", json.display())]).body.as_deref(), Some("{\"name\": \"Ada\"}\n"));
+    assert_eq!(parse(&["-d", "\\@handle"]).body.as_deref(), Some("@handle"));
+    assert_eq!(parse(&["-d", "user=ada", "-d", "\\@x=1", "--data", "lang=en"]).body.as_deref(), Some("user=ada&@x=1&lang=en"));
+    assert_eq!(parse(&["-d", "{\"a\":", "-d", "1}"]).body.as_deref(), Some("{\"a\":1}"));
+    let options = parse(&["-d", &format!("@{}", binary.display())]);
+    assert_eq!((options.body, options.binary_body), (None, Some(bytes.clone())));
+
+    // File content still goes through JSON detection; binary content is sent untouched
+    let echo = common::call_json(&["-d", &format!("@{}", json.display()), &server.url("/echo")]).await.unwrap();
+    assert_eq!((echo["method"].as_str(), echo["parsed"]["name"].as_str()), (Some("POST"), Some("Ada")));
+    assert_eq!(echo["headers"]["content-type"], "application/json");
+    let echo = common::call_json(&["-d", &format!("@{}", binary.display()), &server.url("/size")]).await.unwrap();
+    assert_eq!((echo["size"].as_u64(), echo["last_byte"].as_u64()), (Some(256), Some(255)));
+    assert_eq!(echo["content_type"], "application/octet-stream");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn streams_large_data_files_from_disk() {
+    let server = FixtureServer::start().await;
+    let path = std::env::temp_dir().join(format!("nuts-large-{}.json", std::process::id()));
+    let mut content = String::from("[");
+    while content.len() < nuts::commands::call::STREAM_BODY_THRESHOLD as usize {
+        content.push_str("12345678,");
+    }
+    content.push_str("0]");
+    std::fs::write(&path, &content).unwrap();
+
+    let arg = format!("@{}", path.display());
+    let options = nuts::commands::call::CallCommand::new().parse_advanced_args(&["call", "POST", &server.url("/size"), "-d", &arg]).unwrap();
+    assert_eq!((options.body_file.as_deref(), options.body), (Some(path.to_str().unwrap()), None));
+
+    let echo = common::call_json(&["POST", &server.url("/size"), "-d", &arg]).await.unwrap();
+    assert_eq!(echo["size"].as_u64(), Some(content.len() as u64));
+    assert_eq!(echo["content_length"], content.len().to_string());
+    assert_eq!(echo["content_type"], "application/json");
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn sends_data_binary_files_unparsed() {
+    let server = FixtureServer::start().await;
+    let path = std::env::temp_dir().join(format!("nuts-binary-{}.json", std::process::id()));
+    std::fs::write(&path, "{ \"spaced\" :  true }").unwrap();
+
+    let echo = common::call_json(&["--data-binary", &format!("@{}", path.display()), &server.url("/echo")])
+        .await
+        .unwrap();
+    assert_eq!(echo["method"], "POST");
+    assert_eq!(echo["body"], "{ \"spaced\" :  true }");
+    assert_eq!(echo["headers"]["content-type"], "application/octet-stream");
+    let _ = std::fs::remove_file(&path);
+
+    let error = common::call_json(&["--data-binary", "@/nonexistent/payload.bin", &server.url("/echo")])
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("'/nonexistent/payload.bin'"), "{}", error);
+    assert_eq!(server.hits(), 1);
+}
+
+#[tokio::test]
+async fn converts_yaml_and_toml_body_files_to_json() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-bodies-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let yaml = dir.join("user.yaml");
+    std::fs::write(&yaml, "name: ada\nroles: [admin, dev]\nprofile:\n  age: 36\n").unwrap();
+    let toml = dir.join("user.toml");
+    std::fs::write(&toml, "name = \"ada\"\nroles = [\"admin\", \"dev\"]\n\n[profile]\nage = 36\n").unwrap();
+    let expected = serde_json::json!({ "name": "ada", "roles": ["admin", "dev"], "profile": { "age": 36 } });
+
+    let url = server.url("/echo");
+    let at_toml = format!("@{}", toml.display());
+    for args in [["--data-file", yaml.to_str().unwrap(), &url], ["-d", &at_toml, &url]] {
+        let echo = common::call_json(&args).await.unwrap();
+        assert_eq!(echo["method"], "POST");
+        assert_eq!(echo["headers"]["content-type"], "application/json");
+        assert_eq!(serde_json::from_str::<serde_json::Value>(echo["body"].as_str().unwrap()).unwrap(), expected);
+    }
+
+    std::fs::write(&yaml, "name: ada\nroles: [admin\n").unwrap();
+    let error = common::call_json(&["--data-file", yaml.to_str().unwrap(), &server.url("/echo")]).await.unwrap_err().to_string();
+    assert!(error.starts_with("Invalid YAML: ") && error.contains("at line 3 column 1"), "{}", error);
+    std::fs::write(&toml, "name = \"ada\"\nage = = 3\n").unwrap();
+    let error = common::call_json(&["--data-file", toml.to_str().unwrap(), &server.url("/echo")]).await.unwrap_err().to_string();
+    assert!(error.starts_with("Invalid TOML: ") && error.contains("at line 2 column 7"), "{}", error);
+    assert_eq!(server.hits(), 2);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn converts_between_json_yaml_and_toml() {
+    use nuts::commands::call::{json_to_yaml, toml_to_json, yaml_to_json};
+    let value = serde_json::json!({ "id": 7, "tags": ["a", "b"], "nested": { "ok": true, "ratio": 0.5, "none": null } });
+
+    let yaml = json_to_yaml(&value).unwrap();
+    assert!(yaml.contains("nested:\n  none: null\n  ok: true"), "{}", yaml);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&yaml_to_json(&yaml).unwrap()).unwrap(), value);
+
+    let toml = "id = 7\ntags = [\"a\", \"b\"]\n[nested]\nok = true\nratio = 0.5\n";
+    let converted: serde_json::Value = serde_json::from_str(&toml_to_json(toml).unwrap()).unwrap();
+    assert_eq!(converted, serde_json::json!({ "id": 7, "tags": ["a", "b"], "nested": { "ok": true, "ratio": 0.5 } }));
+}
+
+#[tokio::test]
+async fn authenticates_with_bearer_and_basic() {
+    let server = FixtureServer::start().await;
+
+    let bearer = common::call_json(&["--bearer", BEARER_TOKEN, &server.url("/auth/bearer")])
+        .await
+        .unwrap();
+    assert_eq!(bearer["authenticated"], true);
+
+    let credentials = format!("{}:{}", BASIC_USER, BASIC_PASSWORD);
+    let basic = common::call_json(&["-u", &credentials, &server.url("/auth/basic")])
+        .await
+        .unwrap();
+    assert_eq!(basic["authenticated"], true);
+
+    let denied = common::call_json(&[&server.url("/auth/bearer")]).await.unwrap();
+    assert_eq!(denied["error"], "unauthorized");
+}
+
+#[tokio::test]
+async fn follows_redirects_only_with_location_flag() {
+    let server = FixtureServer::start().await;
+    let url = server.url("/redirect/3");
+
+    let not_followed = common::call_capture(&[&url]).await.unwrap();
+    assert!(not_followed.is_empty());
+    assert_eq!(server.hits(), 1);
+
+    let followed = common::call_json(&["-L", &url]).await.unwrap();
+    assert_eq!(followed["path"], "/echo");
+    // /redirect/3 -> 2 -> 1 -> 0 -> /echo
+    assert_eq!(server.hits(), 1 + 5);
+}
+
+#[tokio::test]
+async fn lists_each_redirect_hop_with_its_cookies() {
+    let server = FixtureServer::start().await;
+    let url = server.url("/goto/302?to=../redirect/1");
+
+    let response = call(&["-L", &url]).await.unwrap();
+    let hops: Vec<(u16, &str)> = response.redirects.iter().map(|hop| (hop.status, hop.location.as_str())).collect();
+    assert_eq!(hops, [
+        (302, server.url("/redirect/1").as_str()),
+        (303, server.url("/redirect/0").as_str()),
+        (303, server.url("/echo").as_str()),
+    ]);
+    assert_eq!(response.redirects[0].set_cookies, ["hop=302; Path=/"]);
+    assert!(response.body.contains(r#""path":"/echo""#), "{}", response.body);
+    assert_eq!(response.to_json()["redirects"][2]["url"], server.url("/redirect/0"));
+
+    let shown = printed(&["call", "-L", &url]).await;
+    assert!(shown.contains(&format!("GET {} → {}", url, server.url("/redirect/1"))), "{}", shown);
+    assert!(shown.contains("Set-Cookie: hop=302"), "{}", shown);
+}
+
+#[tokio::test]
+async fn redirects_keep_or_drop_the_method_by_status() {
+    let server = FixtureServer::start().await;
+
+    let see_other = common::call_json(&["-L", "POST", &server.url("/goto/303?to=/echo"), r#"{"a":1}"#]).await.unwrap();
+    assert_eq!(see_other["method"], "GET");
+    assert_eq!(see_other["body"], "");
+    assert!(see_other["headers"].get("content-type").is_none());
+
+    for code in ["307", "308"] {
+        let url = server.url(&format!("/goto/{}?to=/echo", code));
+        let kept = common::call_json(&["-L", "PUT", &url, r#"{"a":1}"#]).await.unwrap();
+        assert_eq!(kept["method"], "PUT");
+        assert_eq!(kept["parsed"]["a"], 1);
+    }
+}
+
+#[tokio::test]
+async fn stops_at_max_redirects_and_on_loops() {
+    let server = FixtureServer::start().await;
+
+    let error = call(&["-L", "--max-redirs", "2", &server.url("/redirect/3")]).await.unwrap_err().to_string();
+    assert!(error.contains("stopped after 2"), "{}", error);
+    assert_eq!(server.hits(), 3);
+    assert_eq!(call(&["-L", "--max-redirs", "4", &server.url("/redirect/3")]).await.unwrap().redirects.len(), 4);
+
+    let error = call(&["-L", &server.url("/goto/302")]).await.unwrap_err().to_string();
+    assert_eq!(error, format!("Redirect loop: {} → {}", server.url("/goto/302"), server.url("/goto/302")));
+}
+
+#[tokio::test]
+async fn decompresses_gzip_bodies_unless_raw() {
+    let server = FixtureServer::start().await;
+    let url = server.url("/gzip");
+
+    let shown = printed(&["call", "-v", &url]).await;
+    assert!(shown.contains(r#""accept_encoding": """#), "{}", shown);
+    assert!(shown.contains(r#""compressible","#), "{}", shown);
+    assert!(shown.contains("content-encoding: gzip"), "{}", shown);
+    assert!(shown.contains("bytes over the wire as gzip"), "{}", shown);
+
+    let asked = common::call_json(&["--compressed", &url]).await.unwrap();
+    assert_eq!(asked["accept_encoding"], "gzip, deflate, br");
+    assert_eq!(asked["items"].as_array().unwrap().len(), 50);
+
+    let raw = call(&["--raw", &url]).await.unwrap();
+    assert!(serde_json::from_str::<serde_json::Value>(&raw.body).is_err());
+
+    let dir = std::env::temp_dir().join(format!("nuts-gzip-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("items.json");
+    call(&["-o", file.to_str().unwrap(), &url]).await.unwrap();
+    let saved: serde_json::Value = serde_json::from_slice(&std::fs::read(&file).unwrap()).unwrap();
+    assert_eq!(saved["items"][0], "compressible");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn times_out_slow_responses() {
+    let server = FixtureServer::start().await;
+
+    let started = Instant::now();
+    let result = common::call_capture(&["--timeout", "1", &server.url("/delay/3000")]).await;
+
+    assert!(result.is_err());
+    assert!(started.elapsed() < Duration::from_millis(2500));
+}
+
+#[tokio::test]
+async fn retries_transport_errors_before_failing() {
+    // Bind then drop a listener so the port is very likely closed.
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let url = format!("http://127.0.0.1:{}/", port);
+
+    let started = Instant::now();
+    let result = common::call_capture(&["--retry", "1", &url]).await;
+
+    assert!(result.is_err());
+    // One retry waits one second before the second attempt.
+    assert!(started.elapsed() >= Duration::from_millis(1000));
+}
+
+async fn call(args: &[&str]) -> Result<nuts::commands::call::CallResponse, Box<dyn std::error::Error>> {
+    let args: Vec<&str> = std::iter::once("call").chain(args.iter().copied()).collect();
+    nuts::commands::call::CallCommand::new().execute(&args).await
+}
+
+#[tokio::test]
+async fn retries_retryable_statuses_and_honours_retry_after() {
+    let server = FixtureServer::start().await;
+    let started = Instant::now();
+
+    // 503 then 429 carry Retry-After: 0, so neither retry waits for the backoff
+    let response = call(&["--retry", "3", &server.url("/flaky/1?retry_after=0")]).await.unwrap();
+    assert_eq!(response.status, 200);
+    let response = call(&["--retry", "3", &server.url("/flaky/2?status=429&retry_after=0")]).await.unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(server.hits(), 2 + 3);
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn does_not_retry_client_errors_or_unlisted_statuses() {
+    let server = FixtureServer::start().await;
+
+    let response = call(&["--retry", "3", &server.url("/flaky/5?status=401&retry_after=0")]).await.unwrap();
+    assert_eq!(response.status, 401);
+    assert_eq!(server.hits(), 1);
+
+    // Only 502 is listed, so the 503 is returned as is
+    let response = call(&["--retry", "3", "--retry-on-status", "502", &server.url("/flaky/5?retry_after=0")]).await.unwrap();
+    assert_eq!(response.status, 503);
+    assert_eq!(server.hits(), 2);
+
+    let error = call(&["--retry-on-status", "50x", &server.url("/echo")]).await.unwrap_err();
+    assert_eq!(error.to_string(), "Invalid status code '50x' in --retry-on-status");
+}
+
+#[tokio::test]
+async fn returns_the_last_response_once_retries_run_out() {
+    let server = FixtureServer::start().await;
+
+    let response = call(&["--retry", "2", "--retry-on-status", "500,502", &server.url("/flaky/10?status=502&retry_after=0")]).await.unwrap();
+    assert_eq!(response.status, 502);
+    assert_eq!(server.hits(), 3);
+}
+
+#[tokio::test]
+async fn breaks_down_timings_of_verbose_calls() {
+    let server = FixtureServer::start().await;
+
+    let response = call(&["-v", &server.url("/delay/50")]).await.unwrap();
+    let timings = response.timings.unwrap();
+    assert!(timings.dns_ms.is_some() && timings.connect_ms.is_some());
+    assert_eq!(timings.tls_ms, None, "plain HTTP has no handshake");
+    assert!(timings.ttfb_ms.unwrap() >= 40.0, "{:?}", timings);
+    assert!(timings.total_ms >= timings.ttfb_ms.unwrap() + timings.download_ms.unwrap());
+    let table = timings.table();
+    assert!(table.contains("TLS handshake") && table.contains("n/a"), "{}", table);
+
+    assert!(call(&[&server.url("/echo")]).await.unwrap().timings.is_none());
+    // Nothing listens on port 9 locally; the phases that can't be measured stay empty
+    let refused = Timings::probe("http://127.0.0.1:9/", false, Duration::from_secs(2)).await;
+    assert!(refused.dns_ms.is_some());
+    assert_eq!((refused.connect_ms, refused.tls_ms), (None, None));
+}
+
+#[test]
+fn parses_retry_after_and_caps_backoff() {
+    use nuts::commands::call::{is_retryable_status, parse_retry_after, retry_delay, MAX_RETRY_DELAY};
+    let now = chrono::DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z").unwrap().with_timezone(&chrono::Utc);
+
+    assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+    assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:45 GMT", now), Some(Duration::from_secs(45)));
+    assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now), Some(Duration::ZERO));
+    assert_eq!(parse_retry_after("soon", now), None);
+
+    for retry in 1..=3 {
+        let base = Duration::from_secs(1 << (retry - 1));
+        let delay = retry_delay(retry, None);
+        assert!(delay >= base && delay <= base.mul_f64(1.25), "retry {}: {:?}", retry, delay);
+    }
+    assert_eq!(retry_delay(10, None), MAX_RETRY_DELAY);
+    assert_eq!(retry_delay(1, Some(Duration::from_secs(5))), Duration::from_secs(5));
+    assert_eq!(retry_delay(1, Some(Duration::from_secs(3600))), MAX_RETRY_DELAY);
+
+    assert!(is_retryable_status(408, &[]) && is_retryable_status(429, &[]) && is_retryable_status(599, &[]));
+    assert!(!is_retryable_status(401, &[]) && !is_retryable_status(404, &[]));
+    assert!(is_retryable_status(404, &[404]) && !is_retryable_status(503, &[404]));
+}
+
+#[tokio::test]
+async fn reads_chunked_and_binary_bodies() {
+    let server = FixtureServer::start().await;
+
+    let chunked = common::call_capture(&[&server.url("/chunked")]).await.unwrap();
+    assert_eq!(chunked.lines().count(), 5);
+    assert!(chunked.starts_with("chunk-0"));
+
+    let binary = common::call_capture(&[&server.url("/bytes/64")]).await.unwrap();
+    assert!(!binary.is_empty());
+}
+
+#[tokio::test]
+async fn downloads_binary_bodies_intact_and_resumes_partial_files() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-download-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("blob.bin");
+    let path_str = path.to_string_lossy().to_string();
+    let expected: Vec<u8> = (0..300_000).map(|i| (i % 256) as u8).collect();
+    let url = server.url("/bytes/300000");
+
+    call(&["-o", &path_str, &url]).await.unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), expected);
+
+    // Cut the file short, then resume: only the missing tail is requested and appended
+    std::fs::write(&path, &expected[..100_000]).unwrap();
+    call(&["-C", "-", "-o", &path_str, &url]).await.unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), expected);
+
+    // Resuming a complete file is a no-op
+    let response = call(&["--continue-at", "-", "-o", &path_str, &url]).await.unwrap();
+    assert_eq!(response.status, 416);
+    assert_eq!(std::fs::read(&path).unwrap(), expected);
+    assert_eq!(server.hits(), 3);
+
+    let error = call(&["-C", "-", &url]).await.unwrap_err().to_string();
+    assert!(error.contains("-o FILE or --download"), "{}", error);
+
+    let options = |url: &str| nuts::commands::call::CallOptions { url: url.to_string(), download: true, ..Default::default() };
+    assert_eq!(options("https://cdn.example.com/files/report.pdf?v=2").download_path().as_deref(), Some("report.pdf"));
+    assert_eq!(options("https://cdn.example.com/").download_path().as_deref(), Some("download"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn saves_headers_and_metadata_beside_the_body() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-meta-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+    call(&["-o", &file("body.json"), "--dump-headers", &file("headers.txt"), "--meta", &file("meta.json"), &server.url("/items")]).await.unwrap();
+
+    let headers = std::fs::read_to_string(dir.join("headers.txt")).unwrap();
+    assert!(headers.starts_with("HTTP/1.1 200 OK\r\n"), "{}", headers);
+    assert!(headers.contains("content-type: application/json\r\n"), "{}", headers);
+    assert!(headers.ends_with("\r\n\r\n"), "{}", headers);
+
+    let content = std::fs::read_to_string(dir.join("meta.json")).unwrap();
+    let meta: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!((meta["method"].as_str(), meta["status"].as_u64()), (Some("GET"), Some(200)));
+    assert_eq!(meta["url"], server.url("/items"));
+    assert_eq!(meta["headers"]["content-type"], "application/json");
+    assert!(meta.get("body").is_none());
+    let body_file = std::path::PathBuf::from(meta["body_file"].as_str().unwrap());
+    assert_eq!(body_file, std::fs::canonicalize(dir.join("body.json")).unwrap());
+
+    // explain @meta.json explains the saved body, knowing what request it answered
+    let (body, context) = nuts::commands::explain::saved_response(content, &dir).unwrap();
+    assert_eq!(body, std::fs::read_to_string(&body_file).unwrap());
+    assert!(context.unwrap().starts_with(&format!("Response to GET {} with status 200", server.url("/items"))));
+    let (plain, context) = nuts::commands::explain::saved_response("{\"id\": 1}".to_string(), &dir).unwrap();
+    assert_eq!((plain.as_str(), context), ("{\"id\": 1}", None));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn remote_file_names_stay_in_the_current_directory() {
+    use nuts::commands::call::remote_file_name;
+    use reqwest::header::{HeaderMap, HeaderValue, CONTENT_DISPOSITION};
+    let disposition = |value: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_DISPOSITION, HeaderValue::from_str(value).unwrap());
+        headers
+    };
+    let url = "https://cdn.example.com/files/export?id=7";
+
+    assert_eq!(remote_file_name(url, &HeaderMap::new()), "export");
+    assert_eq!(remote_file_name(url, &disposition(r#"attachment; filename="report 2024.csv""#)), "report 2024.csv");
+    assert_eq!(remote_file_name(url, &disposition("attachment; filename=a.txt; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf")), "résumé.pdf");
+    assert_eq!(remote_file_name(url, &disposition(r#"attachment; filename="../../etc/passwd""#)), "passwd");
+    assert_eq!(remote_file_name(url, &disposition(r#"attachment; filename="..\..\boot.ini""#)), "boot.ini");
+    assert_eq!(remote_file_name(url, &disposition(r#"attachment; filename="..""#)), "export");
+}
+
+#[tokio::test]
+async fn explicit_proxy_receives_request_with_credentials() {
+    // The fixture answers absolute-form proxy requests by path, so it doubles as a proxy.
+    let proxy = FixtureServer::start().await;
+    let proxy_url = format!("http://user:pa%20ss@{}", proxy.addr);
+
+    let echo = common::call_json(&["--proxy", &proxy_url, "GET", "http://upstream.invalid/echo"])
+        .await
+        .unwrap();
+
+    assert_eq!(proxy.hits(), 1);
+    assert_eq!(echo["headers"]["host"], "upstream.invalid");
+    // "user:pa ss" in base64
+    assert_eq!(echo["headers"]["proxy-authorization"], "Basic dXNlcjpwYSBzcw==");
+}
+
+#[tokio::test]
+async fn noproxy_ignores_explicit_proxy() {
+    let server = FixtureServer::start().await;
+
+    let echo = common::call_json(&["--proxy", "http://127.0.0.1:1", "--noproxy", "GET", &server.url("/echo")])
+        .await
+        .unwrap();
+    assert_eq!(echo["method"], "GET");
+}
+
+#[tokio::test]
+async fn substitutes_active_environment_variables() {
+    let server = FixtureServer::start().await;
+    let mut config = nuts::config::Config::default();
+    config.set_env_var("dev", "base_url", &server.url(""));
+    config.set_env_var("dev", "tenant", "acme");
+    config.set_env_var("staging", "base_url", "http://127.0.0.1:1");
+    config.use_env("dev").unwrap();
+    let call = nuts::commands::call::CallCommand::new().with_environment(config.active_environment());
+
+    let response = call
+        .execute(&["call", "-H", "X-Tenant: {{tenant}}", "POST", "{{base_url}}/echo", r#"{"tenant":"{{ tenant }}"}"#])
+        .await
+        .unwrap();
+    let echo: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+    assert_eq!(echo["headers"]["x-tenant"], "acme");
+    assert_eq!(echo["parsed"]["tenant"], "acme");
+    assert_eq!(response.url, server.url("/echo"));
+
+    let error = call.execute(&["call", "GET", "{{base_url}}/{{missing}}"]).await.unwrap_err().to_string();
+    assert_eq!(error, "Unknown variable '{{missing}}' in environment 'dev'. Available: base_url, tenant");
+    assert!(config.use_env("prod").unwrap_err().to_string().contains("dev, staging"));
+    assert_eq!(server.hits(), 1);
+}
+
+#[tokio::test]
+async fn merges_default_headers_under_explicit_ones_and_resolves_relative_urls() {
+    let server = FixtureServer::start().await;
+    let mut config = nuts::config::Config::default();
+    config.set_default_header("X-Team", "platform");
+    config.set_default_header("X-Trace", "default");
+    config.set_default_header("x-trace", "from-config");
+    config.set_default_header("Authorization", "Bearer default-token");
+    config.base_url = Some(server.url("/"));
+    let call = nuts::commands::call::CallCommand::new()
+        .with_defaults(config.default_headers.clone(), config.base_url.clone());
+
+    let response = call.execute(&["call", "-H", "X-TRACE: explicit", "GET", "/echo?page=1"]).await.unwrap();
+    let echo: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+    assert_eq!(response.url, server.url("/echo?page=1"));
+    assert_eq!(echo["headers"]["x-team"], "platform");
+    assert_eq!(echo["headers"]["x-trace"], "explicit");
+    assert_eq!(echo["headers"]["authorization"], "Bearer default-token");
+
+    let response = call.execute(&["call", "--bearer", BEARER_TOKEN, &server.url("/auth/bearer")]).await.unwrap();
+    assert_eq!(response.status, 200);
+
+    assert!(config.unset_default_header("X-TEAM"));
+    assert!(!config.unset_default_header("X-Team"));
+    assert_eq!(nuts::config::mask_value("Bearer default-token"), "Beare************");
+
+    let error = nuts::commands::call::CallCommand::new().execute(&["call", "/echo"]).await.unwrap_err().to_string();
+    assert!(error.contains("config base-url"), "{}", error);
+    assert_eq!(server.hits(), 2);
+}
+
+#[tokio::test]
+async fn cookie_jar_carries_cookies_between_calls() {
+    let server = FixtureServer::start().await;
+    let jar = std::sync::Arc::new(nuts::cookies::CookieJar::new());
+    let call = nuts::commands::call::CallCommand::new().with_cookie_jar(jar.clone());
+
+    call.execute(&["call", &server.url("/cookies/set?session=abc&theme=dark")]).await.unwrap();
+    let response = call.execute(&["call", &server.url("/echo")]).await.unwrap();
+    let echo: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+    let mut sent: Vec<&str> = echo["headers"]["cookie"].as_str().unwrap().split("; ").collect();
+    sent.sort_unstable();
+    assert_eq!(sent, ["session=abc", "theme=dark"]);
+
+    let response = call.execute(&["call", "--no-cookies", &server.url("/echo")]).await.unwrap();
+    let echo: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+    assert!(echo["headers"].get("cookie").is_none());
+
+    let cookies = jar.list();
+    assert_eq!(cookies.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["session", "theme"]);
+    assert_eq!((cookies[0].domain.as_str(), cookies[0].path.as_str(), cookies[0].expires.as_ref()), ("127.0.0.1", "/", None));
+
+    let path = std::env::temp_dir().join(format!("nuts-cookies-{}.json", std::process::id()));
+    jar.save(&path).unwrap();
+    let restored = nuts::cookies::CookieJar::load(&path).unwrap();
+    assert_eq!(restored.list(), cookies);
+    let _ = std::fs::remove_file(&path);
+
+    jar.clear();
+    assert!(jar.is_empty());
+    assert!(nuts::cookies::CookieJar::load(&path).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn reuses_one_client_per_set_of_connection_options() {
+    let server = FixtureServer::start().await;
+    let pool = std::sync::Arc::new(nuts::commands::call::ClientPool::default());
+    let call = nuts::commands::call::CallCommand::new().with_client_pool(pool.clone());
+
+    call.execute(&["call", &server.url("/echo")]).await.unwrap();
+    call.execute(&["call", "POST", &server.url("/echo"), "-H", "X-Test: 1", "-d", "{}"]).await.unwrap();
+    assert_eq!(pool.len(), 1);
+
+    // A new command sharing the pool reuses it too
+    nuts::commands::call::CallCommand::new().with_client_pool(pool.clone()).execute(&["call", &server.url("/echo")]).await.unwrap();
+    assert_eq!(pool.len(), 1);
+
+    call.execute(&["call", "-k", &server.url("/echo")]).await.unwrap();
+    call.execute(&["call", "--timeout", "5", &server.url("/echo")]).await.unwrap();
+    assert_eq!(pool.len(), 3);
+}
+
+#[tokio::test]
+async fn resolve_pins_a_fake_host_to_the_fixture_server() {
+    let server = FixtureServer::start().await;
+    let port = url::Url::parse(&server.url("/")).unwrap().port().unwrap();
+    let pin = format!("api.nuts.test:{}:127.0.0.1", port);
+    let url = format!("http://api.nuts.test:{}/echo", port);
+
+    let response = call(&["--resolve", &pin, "-v", &url]).await.unwrap();
+    let echo: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+    // The Host header keeps the original name
+    assert_eq!(echo["headers"]["host"], format!("api.nuts.test:{}", port));
+    assert_eq!(server.hits(), 1);
+
+    // Same pin twice is fine; another address for the same host and port is not
+    let command = nuts::commands::call::CallCommand::new();
+    let options = command.parse_advanced_args(&["call", "--resolve", &pin, "--resolve", &pin, &url]).unwrap();
+    assert_eq!(options.resolve.len(), 1);
+    let conflict = format!("api.nuts.test:{}:127.0.0.2", port);
+    let error = command.parse_advanced_args(&["call", "--resolve", &pin, "--resolve", &conflict, &url]).unwrap_err();
+    assert_eq!(error.to_string(), format!("Conflicting --resolve for api.nuts.test:{}: 127.0.0.1 and 127.0.0.2", port));
+    assert!(command.parse_advanced_args(&["call", "--resolve", "api.nuts.test:443", &url]).is_err());
+
+    // A pin for another port leaves the host to DNS, which doesn't know it
+    let other_port = format!("api.nuts.test:{}:127.0.0.1", port.wrapping_add(1));
+    assert!(call(&["--resolve", &other_port, &url]).await.is_err());
+}
+
+#[tokio::test]
+async fn streams_server_sent_events() {
+    let server = FixtureServer::start().await;
+    let call = nuts::commands::call::CallCommand::new();
+
+    // Detected from the Content-Type and read until the server closes the stream.
+    let response = call.execute(&["call", &server.url("/sse?events=3")]).await.unwrap();
+    assert_eq!(response.body.matches("event: tick").count(), 3);
+
+    // A live feed never closes; --max-events ends the call.
+    let started = Instant::now();
+    let response = tokio::time::timeout(
+        Duration::from_secs(10),
+        call.execute(&["call", "--stream", "--max-events", "2", &server.url("/sse?events=5&hold=true")]),
+    )
+    .await
+    .expect("stream should stop after two events")
+    .unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(response.body.contains("id: 2"));
+    assert!(!response.body.contains("id: 5"));
+
+    // Without --stream the regular timeout still closes an endless stream.
+    let response = call.execute(&["call", "--timeout", "1", &server.url("/sse?events=1&hold=true")]).await.unwrap();
+    assert_eq!(response.body.matches("event: tick").count(), 1);
+}
+
+#[test]
+fn builds_graphql_envelopes_and_reads_errors() {
+    use nuts::commands::call::{graphql_envelope, graphql_errors};
+
+    let envelope: serde_json::Value = serde_json::from_str(&graphql_envelope("{ me { id } }", Some(r#"{"id":1}"#)).unwrap()).unwrap();
+    assert_eq!(envelope, serde_json::json!({ "query": "{ me { id } }", "variables": { "id": 1 } }));
+    let envelope: serde_json::Value = serde_json::from_str(&graphql_envelope("{ me { id } }", None).unwrap()).unwrap();
+    assert!(envelope.get("variables").is_none());
+    assert!(graphql_envelope("{ me }", Some("[1]")).is_err());
+
+    assert_eq!(
+        graphql_errors(r#"{"errors":[{"message":"Not found","path":["user",0]},{"message":"Denied"}]}"#),
+        ["Not found (at user.0)", "Denied"]
+    );
+    assert!(graphql_errors(r#"{"data":{"me":null},"errors":[]}"#).is_empty());
+    assert!(graphql_errors("not json").is_empty());
+}
+
+#[tokio::test]
+async fn graphql_mode_posts_envelope_and_fails_on_errors() {
+    let server = FixtureServer::start().await;
+    let query_file = std::env::temp_dir().join(format!("nuts-query-{}.graphql", std::process::id()));
+    std::fs::write(&query_file, "query User($id: ID!) { user(id: $id) { name } }").unwrap();
+
+    let response = common::call_json(&[
+        "--query-file", query_file.to_str().unwrap(),
+        "--variables", r#"{"id": 7}"#,
+        "GET", &server.url("/graphql"),
+    ])
+    .await
+    .unwrap();
+    let _ = std::fs::remove_file(&query_file);
+    assert_eq!(response["data"]["query"], "query User($id: ID!) { user(id: $id) { name } }");
+    assert_eq!(response["data"]["variables"]["id"], 7);
+
+    let error = common::call_json(&["--graphql", "--query", "{ boom }", &server.url("/graphql")]).await.unwrap_err();
+    assert_eq!(error.to_string(), "GraphQL returned 1 error(s): Boom (at user.name)");
+    assert!(common::call_json(&["--graphql", &server.url("/graphql")]).await.is_err());
+}
+
+#[tokio::test]
+async fn huge_bodies_are_printed_cut_short() {
+    let server = FixtureServer::start().await;
+    let url = server.url("/large/8192");
+
+    let started = std::time::Instant::now();
+    let (response, shown) = nuts::output::capture(nuts::commands::call::CallCommand::new().execute(&["call", &url])).await;
+    let response = response.unwrap();
+    assert!(started.elapsed() < std::time::Duration::from_secs(5), "{:?}", started.elapsed());
+    assert!(response.body.len() > 8 * 1024 * 1024);
+    assert!(shown.len() < 1024 * 1024, "{}", shown.len());
+    assert!(shown.contains("[\n  {\n    \"id\": 10000000,"), "{}", &shown[..200]);
+    assert!(shown.contains("✂️  Showing the first 256 KB of 8.0 MB; save it all with -o FILE or print it with --full"));
+    // What the shell keeps for `explain` is cut the same way
+    let kept = response.kept_body();
+    assert!(kept.len() < 300 * 1024 && kept.ends_with("Showing the first 256 KB of 8.0 MB"), "{}", kept.len());
+
+    let shown = printed(&["call", &url, "--max-display", "1024"]).await;
+    assert!(shown.contains("Showing the first 1 KB of"));
+    let shown = printed(&["call", &server.url("/large/64"), "--full"]).await;
+    assert!(!shown.contains("Showing the first") && shown.trim_end().ends_with(']'));
+    // -o still saves every byte
+    let saved = common::call_capture(&[&url]).await.unwrap();
+    assert_eq!(saved.len(), response.body.len());
+
+    assert_eq!(nuts::commands::call::reindent_json(r#"{"a":[],"b":{"c":"x, {y}"},"d":[1,"#), "{\n  \"a\": [],\n  \"b\": {\n    \"c\": \"x, {y}\"\n  },\n  \"d\": [\n    1,\n    ");
+    assert_eq!(nuts::commands::call::truncate_at("héllo", 2), "h");
+}
+
+#[tokio::test]
+async fn forces_and_reports_the_http_version() {
+    let server = FixtureServer::start().await;
+    let url = server.url("/echo");
+    let call = nuts::commands::call::CallCommand::new();
+
+    let response = call.execute(&["call", "--http2-prior-knowledge", &url]).await.unwrap();
+    assert_eq!(response.http_version, "HTTP/2.0");
+    let response = call.execute(&["call", "--http1.1", &url]).await.unwrap();
+    assert_eq!(response.http_version, "HTTP/1.1");
+
+    // Plain http has no ALPN, so HTTP/2 can't be negotiated
+    let error = call.execute(&["call", "--http2", &url]).await.unwrap_err().to_string();
+    assert!(error.starts_with("Server answered with HTTP/1.1 instead of HTTP/2.0"), "{}", error);
+
+    // A server that only speaks HTTP/1 answers the HTTP/2 preface with garbage
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buffer = [0; 1024];
+        let _ = socket.read(&mut buffer).await;
+        let _ = socket.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n").await;
+    });
+    let error = call.execute(&["call", "--http2-prior-knowledge", &format!("http://{}/", address)]).await.unwrap_err().to_string();
+    assert!(error.contains("http2"), "{}", error);
+}
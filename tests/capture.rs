@@ -0,0 +1,51 @@
+mod common;
+
+use common::{FixtureServer, BEARER_TOKEN};
+use nuts::capture;
+
+// Capture state is process-wide, so everything runs in a single test.
+#[tokio::test]
+async fn records_session_as_har() {
+    let server = FixtureServer::start().await;
+    let har_path = std::env::temp_dir().join(format!("nuts-capture-{}.har", std::process::id()));
+
+    capture::start(&har_path, false).unwrap();
+    common::call_capture(&["--bearer", BEARER_TOKEN, &server.url("/auth/bearer?x=1")]).await.unwrap();
+
+    // The file must stay loadable while the capture is still running.
+    let partial: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&har_path).unwrap()).unwrap();
+    assert_eq!(partial["log"]["entries"].as_array().unwrap().len(), 1);
+
+    common::call_capture(&["-d", r#"{"a":1}"#, &server.url("/echo")]).await.unwrap();
+    common::call_capture(&[&server.url("/bytes/1500000")]).await.unwrap();
+    let (path, entries) = capture::stop().unwrap();
+    assert_eq!(path, har_path);
+    assert_eq!(entries, 3);
+
+    // Requests after stop are not recorded.
+    common::call_capture(&[&server.url("/echo")]).await.unwrap();
+
+    let har: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&har_path).unwrap()).unwrap();
+    let _ = std::fs::remove_file(&har_path);
+    assert_eq!(har["log"]["version"], "1.2");
+    let entries = har["log"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 3);
+
+    let auth = &entries[0];
+    assert_eq!(auth["request"]["method"], "GET");
+    assert_eq!(auth["request"]["queryString"][0]["name"], "x");
+    assert_eq!(auth["response"]["status"], 200);
+    let authorization = auth["request"]["headers"].as_array().unwrap().iter()
+        .find(|h| h["name"] == "authorization")
+        .unwrap();
+    assert_eq!(authorization["value"], "[REDACTED]");
+
+    let post = &entries[1];
+    assert_eq!(post["request"]["method"], "POST");
+    assert_eq!(post["request"]["postData"]["text"], r#"{"a":1}"#);
+    assert_eq!(post["request"]["postData"]["mimeType"], "application/json");
+
+    let large = &entries[2]["response"]["content"];
+    assert_eq!(large["text"].as_str().unwrap().len(), capture::MAX_CAPTURED_BODY);
+    assert!(large["comment"].as_str().unwrap().contains("truncated"));
+}
@@ -0,0 +1,360 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::config::Config;
+use nuts::flows::variables::{extract, SharedVariables};
+use nuts::flows::manager::{budget_table, run_table};
+use nuts::flows::CollectionManager;
+use nuts::shell::NutsShell;
+use serde_json::json;
+use std::path::PathBuf;
+
+/// Write a login -> create -> fetch flow pointing at the fixture server.
+fn chained_flow(server: &FixtureServer, name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nuts-chaining-{}-{}", std::process::id(), name));
+    std::fs::create_dir_all(&dir).unwrap();
+    let spec = format!(
+        r#"openapi: 3.0.0
+info:
+  title: {name}
+  version: 1.0.0
+servers:
+  - url: {base}
+paths:
+  /echo/login:
+    post:
+      requestBody:
+        content:
+          application/json:
+            example:
+              session:
+                auth:
+                  token: abc123
+      x-extract:
+        token: $.parsed.session.auth.token
+  /items:
+    post:
+      parameters:
+        - name: X-Session
+          in: header
+          example: "{{{{token}}}}"
+      requestBody:
+        content:
+          application/json:
+            example:
+              name: widget
+              owner: "{{{{token}}}}"
+      x-extract:
+        item_id: $.id
+  /items/{{item_id}}:
+    get:
+      x-extract:
+        owner: owner
+"#,
+        name = name,
+        base = server.url(""),
+    );
+    std::fs::write(dir.join(format!("{}.yaml", name)), spec).unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn login_create_fetch_chain() {
+    let server = FixtureServer::start().await;
+    let dir = chained_flow(&server, "chain");
+    let manager = CollectionManager::new(dir, Config::default());
+
+    manager.run_endpoint("chain", "/echo/login", &[]).await.unwrap();
+    manager.run_endpoint("chain", "/items", &["POST".to_string()]).await.unwrap();
+    manager.run_endpoint("chain", "/items/{item_id}", &[]).await.unwrap();
+
+    assert_eq!(server.hits(), 3);
+    let item = common::call_json(&["GET", &server.url("/items/1")]).await.unwrap();
+    assert_eq!(item, json!({ "id": 1, "name": "widget", "owner": "abc123" }));
+}
+
+#[tokio::test]
+async fn missing_variable_names_the_producing_step() {
+    let server = FixtureServer::start().await;
+    let dir = chained_flow(&server, "missing");
+    let manager = CollectionManager::new(dir, Config::default());
+
+    let error = manager.run_endpoint("missing", "/items", &["POST".to_string()]).await.unwrap_err();
+    let message = error.to_string();
+    assert!(message.contains("'token'"), "{}", message);
+    assert!(message.contains("POST /echo/login"), "{}", message);
+    assert_eq!(server.hits(), 0);
+}
+
+#[tokio::test]
+async fn shell_keeps_captured_values_between_flow_runs() {
+    let server = FixtureServer::start().await;
+    let flows = common::isolated_home("chaining").join(".nuts").join("flows");
+    std::fs::create_dir_all(&flows).unwrap();
+    let dir = chained_flow(&server, "shell");
+    std::fs::copy(dir.join("shell.yaml"), flows.join("shell.yaml")).unwrap();
+    let mut shell = NutsShell::with_config(Config::default());
+    let args = |line: &str| line.split(' ').map(str::to_string).collect::<Vec<_>>();
+
+    // Following the missing-variable advice works: run the login, then the step again
+    assert!(shell.process_args(args("flow run shell /items POST")).await.is_err());
+    shell.process_args(args("flow run shell /echo/login")).await.unwrap();
+    shell.process_args(args("flow run shell /items POST")).await.unwrap();
+    shell.process_args(args("flow run shell /items/{item_id}")).await.unwrap();
+
+    assert_eq!(server.hits(), 3);
+    let item = common::call_json(&["GET", &server.url("/items/1")]).await.unwrap();
+    assert_eq!(item["owner"], "abc123");
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn values_are_escaped_in_bodies_and_paths() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-chaining-{}-escape", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let spec = format!(
+        r#"openapi: 3.0.0
+info:
+  title: escape
+  version: 1.0.0
+servers:
+  - url: {base}
+paths:
+  /echo/login:
+    post:
+      requestBody:
+        content:
+          application/json:
+            example:
+              token: 'a "b c/d'
+      x-extract:
+        token: $.parsed.token
+  /items:
+    post:
+      requestBody:
+        content:
+          application/json:
+            example:
+              name: "{{{{token}}}} widget"
+              owner: "{{{{token}}}}"
+  /echo/{{token}}:
+    get:
+      x-extract:
+        path: $.path
+"#,
+        base = server.url(""),
+    );
+    std::fs::write(dir.join("escape.yaml"), spec).unwrap();
+    let variables = SharedVariables::default();
+    let manager = CollectionManager::new(dir.clone(), Config::default()).with_variables(variables.clone());
+
+    manager.run_endpoint("escape", "/echo/login", &[]).await.unwrap();
+    manager.run_endpoint("escape", "/items", &["POST".to_string()]).await.unwrap();
+    manager.run_endpoint("escape", "/echo/{token}", &[]).await.unwrap();
+
+    let item = common::call_json(&["GET", &server.url("/items/1")]).await.unwrap();
+    assert_eq!(item, json!({ "id": 1, "name": "a \"b c/d widget", "owner": "a \"b c/d" }));
+    assert_eq!(variables.lock().await.get("path").unwrap().value, "/echo/a%20%22b%20c%2Fd");
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn extracts_nested_paths() {
+    let body = json!({ "data": { "user": { "id": 7 } }, "items": [{ "id": "a" }, { "id": "b" }] });
+    assert_eq!(extract(&body, "$.data.user.id"), Some(&json!(7)));
+    assert_eq!(extract(&body, "items[1].id"), Some(&json!("b")));
+    assert_eq!(extract(&body, "$.data.missing"), None);
+}
+
+#[tokio::test]
+async fn flow_check_compares_endpoints_against_budgets() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-budget-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let spec = format!(r#"openapi: 3.0.0
+info:
+  title: slo
+  version: 1.0.0
+servers:
+  - url: {}
+paths:
+  /delay/10:
+    get:
+      x-budget:
+        max_latency_ms: 5000
+        max_status: 299
+  /items:
+    get:
+      summary: no budget
+"#, server.url(""));
+    std::fs::write(dir.join("slo.yaml"), &spec).unwrap();
+    std::fs::write(dir.join("broken.yaml"), spec.replace("/delay/10:", "/status/503:")).unwrap();
+    let manager = CollectionManager::new(dir.clone(), Config::default());
+
+    let checks = manager.check_budgets("slo").await.unwrap();
+    assert_eq!(checks.len(), 2);
+    assert_eq!(checks[0].endpoint, "GET /delay/10");
+    assert!(checks[0].failures.is_empty() && checks[0].latency_ms >= 10);
+    assert!(checks[1].budget.is_none());
+    let table = budget_table(&checks);
+    assert!(table.contains("PASS") && table.contains("no budget"), "{}", table);
+
+    let error = manager.check_budgets("broken").await.unwrap_err();
+    assert!(error.to_string().contains("1 of 2 endpoint(s) in 'broken' failed"), "{}", error);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn run_all_orders_crud_steps_and_collects_failures() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-run-all-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let spec = format!(r#"openapi: 3.0.0
+info:
+  title: crud
+  version: 1.0.0
+servers:
+  - url: {}
+paths:
+  /items/{{item_id}}:
+    delete:
+      responses:
+        "204":
+          description: deleted
+    put:
+      requestBody:
+        content:
+          application/json:
+            example:
+              name: gadget
+    get:
+      responses:
+        "200":
+          description: the item
+          content:
+            application/json:
+              schema:
+                type: object
+                required: [id, owner]
+  /items:
+    get: {{}}
+    post:
+      requestBody:
+        content:
+          application/json:
+            example:
+              owner: "{{{{token}}}}"
+      responses:
+        2XX:
+          description: created
+      x-extract:
+        item_id: $.id
+  /echo/login:
+    post:
+      x-order: 1
+      requestBody:
+        content:
+          application/json:
+            example:
+              token: abc123
+      x-extract:
+        token: $.parsed.token
+"#, server.url(""));
+    std::fs::write(dir.join("crud.yaml"), &spec).unwrap();
+    let manager = CollectionManager::new(dir.clone(), Config::default());
+
+    let steps = manager.run_all("crud", false).await.unwrap();
+    let endpoints: Vec<&str> = steps.iter().map(|s| s.endpoint.as_str()).collect();
+    assert_eq!(endpoints, [
+        "POST /echo/login", "POST /items", "GET /items", "GET /items/{item_id}", "PUT /items/{item_id}", "DELETE /items/{item_id}",
+    ]);
+    assert_eq!(steps.iter().map(|s| s.status.unwrap()).collect::<Vec<_>>(), [200, 201, 200, 200, 200, 204]);
+    let table = run_table(&steps);
+    assert_eq!(table.matches("PASS").count(), 6, "{}", table);
+    assert_eq!(common::call_json(&["GET", &server.url("/items")]).await.unwrap(), json!([]));
+
+    // Ordered before the create, the PUT has no item id yet; the steps after it still run
+    let reordered = spec.replace("    put:\n", "    put:\n      x-order: 2\n");
+    std::fs::write(dir.join("reordered.yaml"), reordered).unwrap();
+    let manager = CollectionManager::new(dir.clone(), Config::default());
+    let error = manager.run_all("reordered", false).await.unwrap_err();
+    assert!(error.to_string().contains("1 of 6 step(s) in 'reordered' failed"), "{}", error);
+
+    let failing = format!(
+        "openapi: 3.0.0\ninfo:\n  title: failing\n  version: 1.0.0\nservers:\n  - url: {}\npaths:\n  /status/500:\n    get:\n      x-order: 1\n  /items:\n    get: {{}}\n",
+        server.url("")
+    );
+    std::fs::write(dir.join("failing.yaml"), failing).unwrap();
+    let before = server.hits();
+    let error = manager.run_all("failing", false).await.unwrap_err();
+    assert!(error.to_string().contains("1 of 2 step(s)"), "{}", error);
+    assert_eq!(server.hits() - before, 2);
+    let before = server.hits();
+    manager.run_all("failing", true).await.unwrap_err();
+    assert_eq!(server.hits() - before, 1);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn flow_variables_and_named_servers() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-chaining-{}-vars", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let spec = format!(
+        r#"openapi: 3.0.0
+info:
+  title: vars
+  version: 1.0.0
+servers:
+  - url: http://127.0.0.1:9
+    name: prod
+  - url: {base}
+    name: staging
+    x-region: eu
+x-variables:
+  owner: flow-owner
+x-team: payments
+paths:
+  /items:
+    post:
+      requestBody:
+        content:
+          application/json:
+            example:
+              name: "{{{{tag}}}}"
+              owner: "{{{{owner}}}}"
+"#,
+        base = server.url(""),
+    );
+    std::fs::write(dir.join("vars.yaml"), spec).unwrap();
+    let mut config = Config::default();
+    config.set_env_var("dev", "owner", "env-owner");
+    config.set_env_var("dev", "tag", "env-tag");
+    config.active_env = Some("dev".to_string());
+
+    // Flow variables win over the environment's
+    let manager = CollectionManager::new(dir.clone(), config.clone()).with_server(Some("staging".to_string()));
+    manager.run_endpoint("vars", "/items", &["POST".to_string()]).await.unwrap();
+    let item = common::call_json(&["GET", &server.url("/items/1")]).await.unwrap();
+    assert_eq!(item, json!({ "id": 1, "name": "env-tag", "owner": "flow-owner" }));
+
+    let error = CollectionManager::new(dir.clone(), config.clone()).with_server(Some("qa".to_string()))
+        .run_endpoint("vars", "/items", &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error.to_string(), "No server named 'qa'; this flow has prod, staging");
+
+    // Saving keeps extensions the structs don't know
+    manager.set_variable("vars", "tag", "v2").unwrap();
+    assert_eq!(manager.variables("vars").unwrap().into_iter().collect::<Vec<_>>(), [
+        ("owner".to_string(), "flow-owner".to_string()),
+        ("tag".to_string(), "v2".to_string()),
+    ]);
+    let saved: serde_yaml::Value = serde_yaml::from_str(&std::fs::read_to_string(dir.join("vars.yaml")).unwrap()).unwrap();
+    assert_eq!(saved["x-team"], "payments");
+    assert_eq!(saved["servers"][1]["x-region"], "eu");
+    assert_eq!(saved["servers"][1]["name"], "staging");
+    assert!(manager.set_variable("vars", "bad name", "x").is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
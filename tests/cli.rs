@@ -0,0 +1,204 @@
+//! Runs the `nuts` binary in non-interactive mode.
+mod common;
+
+use common::FixtureServer;
+use std::process::Output;
+
+async fn nuts(args: &[&str]) -> Output {
+    nuts_with_env(args, &[]).await
+}
+
+async fn nuts_with_env(args: &[&str], env: &[(&str, &str)]) -> Output {
+    let home = std::env::temp_dir().join(format!("nuts-cli-home-{}", std::process::id()));
+    std::fs::create_dir_all(&home).unwrap();
+    tokio::process::Command::new(env!("CARGO_BIN_EXE_nuts"))
+        .args(args)
+        .env("HOME", &home)
+        .env_remove("NUTS_LOG")
+        .envs(env.iter().copied())
+        .output()
+        .await
+        .expect("run nuts binary")
+}
+
+#[tokio::test]
+async fn call_post_keeps_quoted_json_body_intact() {
+    let server = FixtureServer::start().await;
+    let out = std::env::temp_dir().join(format!("nuts-cli-{}.json", std::process::id()));
+    let out_str = out.to_string_lossy().to_string();
+
+    let output = nuts(&["call", "-o", &out_str, "POST", &server.url("/echo"), r#"{"a":1}"#]).await;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+
+    let echo: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    let _ = std::fs::remove_file(&out);
+    assert_eq!(echo["method"], "POST");
+    assert_eq!(echo["body"], r#"{"a":1}"#);
+    assert_eq!(echo["parsed"]["a"], 1);
+}
+
+#[tokio::test]
+async fn data_at_dash_reads_the_body_from_stdin() {
+    use tokio::io::AsyncWriteExt;
+    let server = FixtureServer::start().await;
+    let out = std::env::temp_dir().join(format!("nuts-cli-stdin-{}.json", std::process::id()));
+    let out_str = out.to_string_lossy().to_string();
+
+    let mut child = tokio::process::Command::new(env!("CARGO_BIN_EXE_nuts"))
+        .args(["call", "-o", &out_str, "-d", "@-", "POST", &server.url("/echo")])
+        .env("HOME", std::env::temp_dir().join(format!("nuts-cli-home-{}", std::process::id())))
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .expect("run nuts binary");
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"{\"from\": \"stdin\"}\n").await.unwrap();
+    drop(stdin);
+    let output = child.wait_with_output().await.unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+
+    let echo: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    let _ = std::fs::remove_file(&out);
+    assert_eq!(echo["parsed"]["from"], "stdin");
+    assert_eq!(echo["headers"]["content-type"], "application/json");
+}
+
+#[tokio::test]
+async fn failures_exit_non_zero() {
+    let server = FixtureServer::start().await;
+
+    let output = nuts(&["perf", &server.url("/status/500"), "--users", "1", "--duration", "1s", "--fail-error-rate", "0"]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("error rate 100.0% exceeds 0%"));
+
+    // Limits that can never be checked are refused before any request is sent
+    let hits = server.hits();
+    for (flag, value) in [("--fail-error-rate", "-1"), ("--fail-error-rate", "NaN"), ("--fail-p95", "inf")] {
+        let output = nuts(&["perf", &server.url("/status/500"), "--users", "1", "--duration", "1s", flag, value]).await;
+        assert_eq!(output.status.code(), Some(1), "{} {}", flag, value);
+    }
+    assert_eq!(server.hits(), hits);
+
+    // Latency limits take units; a bare number is milliseconds
+    let output = nuts(&["perf", &server.url("/delay/20"), "--users", "1", "--duration", "1s", "--fail-p95", "5ms"]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("exceeds 5ms"));
+    let output = nuts(&["perf", &server.url("/delay/20"), "--users", "1", "--duration", "1s", "--fail-p95", "5"]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("exceeds 5ms"));
+
+    let output = nuts(&["definitely-not-a-command"]).await;
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[tokio::test]
+async fn usage_errors_and_invalid_values_exit_non_zero() {
+    let output = nuts(&["call"]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Usage: call"), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let output = nuts(&["perf", "GET", "http://127.0.0.1:9/", "--fail-p95", "abc"]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid --fail-p95"), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[tokio::test]
+async fn quiet_prints_only_the_result() {
+    let server = FixtureServer::start().await;
+    let output = nuts(&["--quiet", "call", "GET", &server.url("/echo")]).await;
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"method\": \"GET\""), "{}", stdout);
+    assert!(!stdout.contains("🌐") && !stdout.contains("📦 Response"), "{}", stdout);
+}
+
+#[tokio::test]
+async fn successful_command_exits_zero() {
+    let server = FixtureServer::start().await;
+    let output = nuts(&["call", "GET", &server.url("/echo")]).await;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\"method\": \"GET\""));
+}
+
+#[tokio::test]
+async fn honours_proxy_environment() {
+    let proxy = FixtureServer::start().await;
+    let proxy_url = proxy.url("");
+
+    let output = nuts_with_env(&["call", "-v", "GET", "http://upstream.invalid/echo"], &[("HTTP_PROXY", &proxy_url)]).await;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("(HTTP_PROXY)"));
+    assert_eq!(proxy.hits(), 1);
+
+    let output = nuts_with_env(
+        &["call", "GET", "http://upstream.invalid/echo"],
+        &[("HTTP_PROXY", &proxy_url), ("NO_PROXY", "upstream.invalid")],
+    )
+    .await;
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(proxy.hits(), 1);
+}
+
+#[tokio::test]
+async fn switches_environments_from_the_command_line() {
+    let server = FixtureServer::start().await;
+    let home = std::env::temp_dir().join(format!("nuts-cli-env-{}", std::process::id()));
+    let env = [("HOME", home.to_str().unwrap())];
+
+    for args in [
+        ["config", "env", "add", "local", "base_url", &server.url("")].as_slice(),
+        &["config", "env", "add", "broken", "base_url", "http://127.0.0.1:1"],
+        &["config", "env", "use", "local"],
+    ] {
+        assert!(nuts_with_env(args, &env).await.status.success());
+    }
+    let listing = nuts_with_env(&["config", "env", "list"], &env).await;
+    assert!(String::from_utf8_lossy(&listing.stdout).contains("* local"));
+
+    assert!(nuts_with_env(&["call", "GET", "{{base_url}}/echo"], &env).await.status.success());
+    assert_eq!(server.hits(), 1);
+
+    assert!(nuts_with_env(&["config", "env", "use", "broken"], &env).await.status.success());
+    assert_eq!(nuts_with_env(&["call", "GET", "{{base_url}}/echo"], &env).await.status.code(), Some(1));
+    assert_eq!(server.hits(), 1);
+    let _ = std::fs::remove_dir_all(home);
+}
+
+fn keys(value: &serde_json::Value) -> Vec<&str> {
+    value.as_object().unwrap().keys().map(String::as_str).collect()
+}
+
+fn json_stdout(output: &Output) -> serde_json::Value {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).expect("stdout is a single JSON document")
+}
+
+#[tokio::test]
+async fn json_mode_prints_one_document_per_command() {
+    let server = FixtureServer::start().await;
+
+    let call = json_stdout(&nuts(&["--json", "call", "POST", &server.url("/echo"), r#"{"a":1}"#]).await);
+    assert_eq!(keys(&call), ["body", "headers", "http_version", "method", "redirects", "status", "time_ms", "timings", "url"]);
+    assert_eq!((call["method"].as_str(), call["status"].as_u64()), (Some("POST"), Some(200)));
+    assert_eq!(call["body"]["parsed"]["a"], 1);
+    assert_eq!(call["headers"]["content-type"], "application/json");
+    assert!(call["time_ms"].as_f64().unwrap() > 0.0);
+
+    // The flag works anywhere on the line, and the usual output moves to stderr
+    let output = nuts(&["call", "GET", &server.url("/status/404"), "--json"]).await;
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Status: 404"));
+    assert_eq!(json_stdout(&output)["status"], 404);
+
+    let security = json_stdout(&nuts(&["security", &server.url("/echo"), "--json"]).await);
+    assert_eq!(keys(&security), ["endpoints", "findings", "issues", "timestamp", "url"]);
+    assert_eq!(keys(&security["findings"][0]), ["evidence", "id", "remediation", "severity", "title"]);
+    assert!(security["findings"].as_array().unwrap().iter().any(|f| f["id"] == "plain-http"));
+
+    let predict = json_stdout(&nuts(&["predict", &server.url("/echo"), "--probe-users", "2", "--probe-duration", "500ms", "--json"]).await);
+    assert_eq!(keys(&predict), ["health_score", "measured_score", "performance_forecast", "predicted_issues", "recommendations", "security_alerts"]);
+    assert_eq!(keys(&predict["performance_forecast"]), ["bottlenecks", "capacity_limit", "expected_response_time_ms"]);
+
+    let output = nuts(&["perf", &server.url("/echo"), "--json"]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--json is supported by call, security and predict"));
+}
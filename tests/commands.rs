@@ -0,0 +1,923 @@
+mod common;
+
+use common::{isolated_home, FixtureServer, BEARER_TOKEN};
+use nuts::commands::call::HttpProtocol;
+use nuts::commands::fix::{spec_edits, undescribed_operations, yaml_diff, FixCommand, FixOptions, SpecEdit};
+use nuts::commands::monitor::{flow_targets, status_change, Dashboard, MonitorCommand, MonitorHistory, MonitorOptions, MonitorReport, MonitorTarget};
+use nuts::commands::predict::{self, health_score, is_rate_limit_header, HealthSignals, PredictCommand, PredictOptions, PredictionHistory, PredictionRecord};
+use nuts::commands::perf::{parse_stages, LoadSchedule, PerfCommand, PerfThresholds, RateScheduler, Stage};
+use nuts::models::metrics::{classify_error, error_chain, ConnectionStats, ErrorKind, MetricsSummary};
+use nuts::commands::security::{check_response, diff_issues, parse_ai_issues, ScannedResponse, MAX_ACTIVE_PROBES, SecurityCommand, SecurityIssue, SecurityReport, Severity};
+use nuts::config::Config;
+use nuts::confirm::StepPrompt;
+use nuts::flows::OpenAPISpec;
+use nuts::flows::manager::{endpoint_perf_table, EndpointPerf};
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[tokio::test]
+async fn perf_metrics_match_server_hits() {
+    let server = FixtureServer::start().await;
+    let perf = PerfCommand::new(&Config::default());
+
+    perf.run(&server.url("/delay/20"), 4, Duration::from_secs(1), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+    let summary = perf.summary();
+
+    assert!(summary.total_requests > 0);
+    assert_eq!(summary.total_requests, server.hits());
+    assert_eq!(summary.error_rate, 0.0);
+    assert!(summary.avg_latency >= Duration::from_millis(20));
+    assert!(summary.p95_latency >= summary.median_latency);
+    assert!(summary.p99_latency >= summary.p95_latency);
+}
+
+#[tokio::test]
+async fn perf_uses_the_forced_protocol() {
+    let server = FixtureServer::start().await;
+    let perf = PerfCommand::new(&Config::default()).with_protocol(HttpProtocol::Http2PriorKnowledge);
+
+    perf.run(&server.url("/delay/20"), 2, Duration::from_millis(500), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+    let protocols = perf.protocols();
+    assert_eq!(protocols.keys().collect::<Vec<_>>(), ["HTTP/2.0"]);
+    assert_eq!(protocols["HTTP/2.0"], perf.summary().total_requests);
+}
+
+#[tokio::test]
+async fn perf_leaves_warmup_requests_out_of_the_results() {
+    let server = FixtureServer::start().await;
+    let perf = PerfCommand::new(&Config::default()).with_warmup(Duration::from_millis(400)).with_trimmed_outliers(0.1);
+
+    let summary = perf.run(&server.url("/delay/20"), 2, Duration::from_millis(600), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+    assert!(summary.warmup_requests > 0);
+    assert!(summary.total_requests > 0);
+    assert_eq!(summary.total_requests + summary.warmup_requests, server.hits());
+    assert!(summary.trimmed.unwrap().avg_latency >= Duration::from_millis(20));
+}
+
+#[tokio::test]
+async fn perf_counts_new_and_reused_connections() {
+    let server = FixtureServer::start().await;
+    let perf = PerfCommand::new(&Config::default());
+    let summary = perf.run(&server.url("/delay/5"), 2, Duration::from_millis(500), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+    let connections = summary.connections;
+    assert_eq!(connections.new + connections.reused, summary.total_requests);
+    // One connection per user, kept alive for the rest of the run
+    assert!(connections.new <= 2, "{:?}", connections);
+    assert!(connections.reused > connections.new);
+
+    let perf = PerfCommand::new(&Config::default()).with_keepalive(false);
+    let summary = perf.run(&server.url("/delay/5"), 2, Duration::from_millis(500), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+    assert!(summary.total_requests > 2);
+    assert_eq!((summary.connections.new, summary.connections.reused), (summary.total_requests, 0));
+}
+
+#[tokio::test]
+async fn perf_records_slow_responses_as_timeouts() {
+    let server = FixtureServer::start().await;
+    let perf = PerfCommand::new(&Config::default()).with_timeouts(Duration::from_secs(1), Some(Duration::from_secs(1)));
+    let summary = perf.run(&server.url("/delay/5000"), 1, Duration::from_millis(1500), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+
+    // Not one response: every request ran out of time and says so
+    assert_eq!(summary.total_requests, 0);
+    assert!(summary.timeouts >= 1, "{:?}", summary.errors);
+    assert_eq!(summary.errors.keys().collect::<Vec<_>>(), [&ErrorKind::Timeout]);
+    assert_eq!(summary.errors[&ErrorKind::Timeout].count, summary.timeouts);
+}
+
+#[tokio::test]
+async fn perf_counts_error_statuses() {
+    let server = FixtureServer::start().await;
+    let perf = PerfCommand::new(&Config::default());
+
+    perf.run(&server.url("/status/500"), 2, Duration::from_millis(500), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+    let summary = perf.summary();
+
+    assert!(summary.total_requests > 0);
+    assert_eq!(summary.error_rate, 1.0);
+}
+
+#[derive(Debug)]
+struct Wrapped(&'static str, Option<std::io::Error>);
+
+impl std::fmt::Display for Wrapped {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for Wrapped {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.1.as_ref().map(|e| e as _)
+    }
+}
+
+#[tokio::test]
+async fn errors_are_classified_from_their_cause_chain() {
+    use std::io::{Error, ErrorKind as Io};
+    let reset = Wrapped("error sending request", Some(Error::new(Io::ConnectionReset, "reset by peer")));
+    assert_eq!(classify_error(&reset), ErrorKind::ConnectionReset);
+    assert_eq!(error_chain(&reset), "error sending request: reset by peer");
+    assert_eq!(classify_error(&Error::new(Io::ConnectionRefused, "refused")), ErrorKind::ConnectionRefused);
+    assert_eq!(classify_error(&Error::new(Io::TimedOut, "timed out")), ErrorKind::Timeout);
+    assert_eq!(classify_error(&Wrapped("dns error: failed to lookup address information", None)), ErrorKind::Dns);
+    assert_eq!(classify_error(&Wrapped("invalid peer certificate: UnknownIssuer", None)), ErrorKind::Tls);
+    assert_eq!(classify_error(&Wrapped("something odd", Some(Error::other("inner")))), ErrorKind::Other);
+
+    let server = FixtureServer::start().await;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(100))
+        .redirect(reqwest::redirect::Policy::limited(2))
+        .build()
+        .unwrap();
+    let refused = client.get("http://127.0.0.1:9/").send().await.unwrap_err();
+    assert_eq!(classify_error(&refused), ErrorKind::ConnectionRefused);
+    assert!(error_chain(&refused).contains("refused"), "{}", error_chain(&refused));
+    let timeout = client.get(server.url("/delay/500")).send().await.unwrap_err();
+    assert_eq!(classify_error(&timeout), ErrorKind::Timeout);
+    let redirects = client.get(server.url("/redirect/5")).send().await.unwrap_err();
+    assert_eq!(classify_error(&redirects), ErrorKind::TooManyRedirects);
+}
+
+#[tokio::test]
+async fn perf_breaks_errors_down_by_type() {
+    let perf = PerfCommand::new(&Config::default());
+    perf.run("http://127.0.0.1:9/", 2, Duration::from_millis(300), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+    let summary = perf.summary();
+
+    assert_eq!(summary.errors.keys().collect::<Vec<_>>(), [&ErrorKind::ConnectionRefused]);
+    let refused = &summary.errors[&ErrorKind::ConnectionRefused];
+    assert!(refused.count > 0);
+    assert!(refused.example.contains("127.0.0.1:9"), "{}", refused.example);
+    let json = serde_json::to_value(&summary).unwrap();
+    assert_eq!(json["errors"]["connection_refused"]["count"], refused.count);
+}
+
+#[tokio::test]
+async fn perf_sends_headers_on_every_request() {
+    let server = FixtureServer::start().await;
+    let perf = PerfCommand::new(&Config::default());
+    let headers = HashMap::from([
+        ("Authorization".to_string(), format!("Bearer {}", common::BEARER_TOKEN)),
+        ("X-Tenant".to_string(), "foo".to_string()),
+    ]);
+
+    perf.run(&server.url("/auth/bearer"), 2, Duration::from_millis(300), "GET", None, &headers)
+        .await
+        .unwrap();
+    let summary = perf.summary();
+
+    assert!(summary.total_requests > 0);
+    assert_eq!(summary.error_rate, 0.0);
+}
+
+#[tokio::test]
+async fn perf_sends_bodies_for_put() {
+    let server = FixtureServer::start().await;
+    let created = common::call_json(&["POST", &server.url("/items"), r#"{"name":"a"}"#]).await.unwrap();
+    let perf = PerfCommand::new(&Config::default());
+
+    let url = server.url(&format!("/items/{}", created["id"]));
+    perf.run(&url, 1, Duration::from_millis(300), "PUT", Some(r#"{"name":"b"}"#), &HashMap::new())
+        .await
+        .unwrap();
+
+    assert_eq!(perf.summary().error_rate, 0.0);
+    let item = common::call_json(&["GET", &url]).await.unwrap();
+    assert_eq!(item["name"], "b");
+}
+
+fn synthetic_summary(avg_ms: u64, p95_ms: u64, error_rate: f64) -> MetricsSummary {
+    MetricsSummary {
+        avg_latency: Duration::from_millis(avg_ms),
+        p95_latency: Duration::from_millis(p95_ms),
+        p99_latency: Duration::from_millis(p95_ms),
+        median_latency: Duration::from_millis(avg_ms),
+        std_dev_latency: 0.0,
+        total_requests: 100,
+        error_rate,
+        status_codes: Default::default(),
+        min_latency: Duration::from_millis(avg_ms),
+        max_latency: Duration::from_millis(p95_ms),
+        histogram: Default::default(),
+        errors: Default::default(),
+        timeouts: 0,
+        requests_per_second: Vec::new(),
+        peak_rps: 10,
+        warmup_requests: 0,
+        trimmed: None,
+        connections: ConnectionStats::default(),
+    }
+}
+
+#[test]
+fn thresholds_report_every_violation() {
+    let thresholds = PerfThresholds {
+        max_p95: Some(Duration::from_millis(300)),
+        max_avg: Some(Duration::from_millis(100)),
+        max_error_rate_pct: Some(1.0),
+    };
+
+    assert!(thresholds.check(&synthetic_summary(100, 300, 0.01)).is_empty());
+
+    let violations = thresholds.check(&synthetic_summary(150, 450, 0.05));
+    assert_eq!(violations, [
+        "p95 latency 450ms exceeds 300ms",
+        "average latency 150ms exceeds 100ms",
+        "error rate 5.0% exceeds 1%",
+    ]);
+
+    assert!(PerfThresholds::default().check(&synthetic_summary(9999, 9999, 1.0)).is_empty());
+}
+
+#[tokio::test]
+async fn perf_run_fails_when_thresholds_are_violated() {
+    let server = FixtureServer::start().await;
+    let perf = PerfCommand::new(&Config::default()).with_thresholds(PerfThresholds {
+        max_error_rate_pct: Some(0.0),
+        ..Default::default()
+    });
+
+    let error = perf
+        .run(&server.url("/status/503"), 1, Duration::from_millis(300), "GET", None, &HashMap::new())
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("error rate 100.0% exceeds 0%"));
+}
+
+#[tokio::test]
+async fn perf_run_returns_the_summary_of_that_run_only() {
+    let server = FixtureServer::start().await;
+    let perf = PerfCommand::new(&Config::default());
+
+    let errors = perf.run(&server.url("/status/500"), 1, Duration::from_millis(300), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+    let ok = perf.run(&server.url("/echo"), 1, Duration::from_millis(300), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+
+    assert_eq!(errors.error_rate, 1.0);
+    assert_eq!(ok.error_rate, 0.0);
+    assert_eq!(errors.total_requests + ok.total_requests, server.hits());
+    assert_eq!(perf.summary().total_requests, ok.total_requests);
+}
+
+#[test]
+fn endpoint_table_lists_slowest_p95_first() {
+    let mut results = vec![
+        EndpointPerf { method: "GET".into(), path: "/fast".into(), summary: synthetic_summary(5, 10, 0.0) },
+        EndpointPerf { method: "POST".into(), path: "/users/{id}".into(), summary: synthetic_summary(80, 250, 0.02) },
+        EndpointPerf { method: "GET".into(), path: "/medium".into(), summary: synthetic_summary(30, 90, 0.0) },
+    ];
+
+    let table = endpoint_perf_table(&mut results);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("ENDPOINT") && lines[0].contains("PEAK RPS"));
+    assert!(lines[1].starts_with("POST /users/{id}"));
+    assert!(lines[1].contains("2.0%") && lines[1].contains("250ms"));
+    assert!(lines[2].starts_with("GET /medium"));
+    assert!(lines[3].starts_with("GET /fast"));
+}
+
+#[test]
+fn stages_parse_and_drive_the_target_concurrency() {
+    let stages = parse_stages("2:1s, 6:500ms,1:1m").unwrap();
+    assert_eq!(stages, vec![
+        Stage { users: 2, duration: Duration::from_secs(1) },
+        Stage { users: 6, duration: Duration::from_millis(500) },
+        Stage { users: 1, duration: Duration::from_secs(60) },
+    ]);
+    assert!(parse_stages("10").is_err());
+    assert!(parse_stages("ten:30s").is_err());
+    assert!(parse_stages("10:30x").is_err());
+    assert!(parse_stages("10:0s").is_err());
+    assert_eq!(parse_stages("1:1h").unwrap()[0].duration, Duration::from_secs(3600));
+
+    let schedule = LoadSchedule { stages: parse_stages("10:10s,4:5s").unwrap(), ramp: Duration::from_secs(5) };
+    assert_eq!(schedule.total_duration(), Duration::from_secs(15));
+    assert_eq!(schedule.target_at(Duration::ZERO), 1);
+    assert_eq!(schedule.target_at(Duration::from_millis(2500)), 5);
+    assert_eq!(schedule.target_at(Duration::from_secs(7)), 10);
+    assert_eq!(schedule.target_at(Duration::from_secs(12)), 4);
+    assert_eq!(schedule.stage_at(Duration::from_secs(99)), 1);
+}
+
+#[tokio::test]
+async fn perf_stages_record_metrics_per_stage() {
+    let server = FixtureServer::start().await;
+    let perf = PerfCommand::new(&Config::default())
+        .with_ramp(Duration::from_millis(200))
+        .with_stages(parse_stages("1:400ms,4:400ms").unwrap());
+
+    perf.run(&server.url("/delay/20"), 1, Duration::from_secs(30), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+
+    let stages = perf.stage_summaries();
+    assert_eq!(stages.len(), 2);
+    let (first, second) = (&stages[0].1, &stages[1].1);
+    assert!(first.total_requests > 0);
+    // Four users for the same time send clearly more than one
+    assert!(second.total_requests > first.total_requests * 2, "{} vs {}", second.total_requests, first.total_requests);
+    assert_eq!(perf.summary().total_requests, first.total_requests + second.total_requests);
+    assert_eq!(server.hits(), perf.summary().total_requests);
+}
+
+#[tokio::test]
+async fn monitor_health_check_reports_status() {
+    let server = FixtureServer::start().await;
+    let monitor = MonitorCommand::new(Config::default());
+
+    let healthy = monitor.perform_health_check(&server.url("/items")).await.unwrap();
+    assert_eq!(healthy.status, "healthy");
+
+    let slow = monitor.perform_health_check(&server.url("/delay/1100")).await.unwrap();
+    assert_eq!(slow.status, "slow");
+    assert!(slow.response_time >= Duration::from_millis(1100));
+}
+
+#[test]
+fn monitor_options_and_alert_transitions() {
+    let options = MonitorOptions::from_args(&["monitor", "https://x.io", "--interval", "10s", "--duration", "1h", "--alert-webhook", "https://hooks/x", "--smart"]).unwrap();
+    assert_eq!(options.interval, Duration::from_secs(10));
+    assert_eq!(options.duration, Some(Duration::from_secs(3600)));
+    assert_eq!((options.alert_webhook.as_deref(), options.smart), (Some("https://hooks/x"), true));
+    assert_eq!(MonitorOptions::from_args(&["monitor", "https://x.io", "--count", "3"]).unwrap().count, Some(3));
+    assert!(MonitorOptions::from_args(&["monitor", "https://x.io", "--interval", "0s"]).is_err());
+    let flow = MonitorOptions::from_args(&["monitor", "--flow", "shop", "--count", "2"]).unwrap();
+    assert_eq!((flow.flow.as_deref(), flow.count), (Some("shop"), Some(2)));
+
+    assert_eq!(status_change(None, "healthy"), None);
+    assert_eq!(status_change(None, "error"), Some("alert"));
+    assert_eq!(status_change(Some("healthy"), "slow"), None);
+    assert_eq!(status_change(Some("warning"), "error"), Some("alert"));
+    assert_eq!(status_change(Some("error"), "error"), None);
+    assert_eq!(status_change(Some("error"), "slow"), Some("recovered"));
+    assert_eq!(MonitorHistory::host_key("http://127.0.0.1:8080/health"), "127.0.0.1:8080");
+    assert_eq!(MonitorHistory::host_key("https://api.example.com/health"), "api.example.com");
+}
+
+#[tokio::test]
+async fn monitor_alerts_on_transitions_and_keeps_history() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-monitor-{}", std::process::id()));
+    let monitor = MonitorCommand::new(Config::default()).with_history_dir(&dir);
+    let options = MonitorOptions {
+        interval: Duration::from_millis(50),
+        count: Some(4),
+        alert_webhook: Some(server.url("/hooks")),
+        ..Default::default()
+    };
+
+    // Fails twice with a 503, then recovers
+    let session = monitor.monitor(&server.url("/flaky/2"), &options).await.unwrap();
+    assert_eq!(session.checks, 4);
+    assert_eq!(session.uptime_pct, 50.0);
+    assert_eq!(session.statuses, [("error".to_string(), 2), ("healthy".to_string(), 2)]);
+
+    let payloads = server.hook_payloads();
+    assert_eq!(payloads.len(), 2, "{:?}", payloads);
+    assert_eq!((payloads[0]["status"].as_str(), payloads[0]["previous_status"].as_str()), (Some("error"), None));
+    assert!(payloads[0]["text"].as_str().unwrap().contains("HTTP 503"), "{}", payloads[0]);
+    assert_eq!(payloads[1]["status"], "healthy");
+    assert_eq!(payloads[1]["previous_status"], "error");
+    assert!(payloads[1]["timestamp"].is_string() && payloads[1]["latency_ms"].is_u64());
+
+    // A later session appends to the same history
+    let options = MonitorOptions { interval: Duration::from_millis(300), duration: Some(Duration::from_millis(1000)), ..Default::default() };
+    let started = std::time::Instant::now();
+    let second = monitor.monitor(&server.url("/flaky/2"), &options).await.unwrap();
+    // Four ticks fit in the second; a loaded machine may fit fewer, never more
+    assert!((1..=4).contains(&second.checks), "{}", second.checks);
+    assert!(started.elapsed() < Duration::from_millis(1500), "{:?}", started.elapsed());
+    let history = MonitorHistory::new(&dir).load(&MonitorHistory::host_key(&server.url(""))).unwrap();
+    assert_eq!(history.len(), 4 + second.checks);
+    let report = MonitorReport::new(&history);
+    let expected = 100.0 * (2 + second.checks) as f64 / history.len() as f64;
+    assert!((report.uptime_pct - expected).abs() < 1e-9, "{}", report.uptime_pct);
+    assert!(report.from < report.to);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn flow_targets_are_the_plain_get_endpoints() {
+    let spec: nuts::flows::OpenAPISpec = serde_yaml::from_str(r#"
+openapi: 3.0.0
+info: { title: shop, version: "1" }
+servers: [{ url: "https://shop.example.com/" }]
+paths:
+  /users: { get: {}, post: {} }
+  /users/{id}: { get: {} }
+  /health: { get: {} }
+  /orders: { post: {} }
+"#).unwrap();
+    let (targets, skipped) = flow_targets(&spec);
+    assert_eq!(targets, [
+        MonitorTarget { name: "GET /health".into(), url: "https://shop.example.com/health".into() },
+        MonitorTarget { name: "GET /users".into(), url: "https://shop.example.com/users".into() },
+    ]);
+    assert_eq!(skipped, ["GET /users/{id}"]);
+
+    let dashboard = Dashboard::new(&targets);
+    let lines = dashboard.render();
+    assert_eq!(lines[1], format!("⏳ {:<44} {:>9} {:>9} {:>7}", "GET /health", "-", "-", 0));
+    assert!(lines.iter().all(|line| console::measure_text_width(line) <= 80), "{:#?}", lines);
+}
+
+#[tokio::test]
+async fn monitor_checks_every_flow_target_on_a_staggered_schedule() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-monitor-flow-{}", std::process::id()));
+    let monitor = MonitorCommand::new(Config::default()).with_history_dir(&dir);
+    let targets = [
+        MonitorTarget { name: "GET /items".into(), url: server.url("/items") },
+        MonitorTarget { name: "GET /status/500".into(), url: server.url("/status/500") },
+    ];
+    let options = MonitorOptions { interval: Duration::from_millis(400), count: Some(2), ..Default::default() };
+
+    let reports = monitor.monitor_targets(&targets, &options).await.unwrap();
+    assert_eq!(reports.iter().map(|r| (r.checks, r.uptime_pct)).collect::<Vec<_>>(), [(2, 100.0), (2, 0.0)]);
+
+    let history = MonitorHistory::new(&dir).load(&MonitorHistory::host_key(&server.url(""))).unwrap();
+    assert_eq!(history.len(), 4);
+    let first = |path: &str| history.iter().find(|r| r.url.ends_with(path)).unwrap().timestamp;
+    // Two targets over a 400ms interval: the second starts about 200ms after the first
+    let offset = (first("/status/500") - first("/items")).num_milliseconds();
+    assert!((150..400).contains(&offset), "{}", offset);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn security_collects_evidence_for_each_probe() {
+    let server = FixtureServer::start().await;
+    let url = server.url("/echo");
+
+    let basic = SecurityCommand::new(Config::default());
+    let evidence = basic.collect_evidence(&url).await.unwrap();
+    assert_eq!(evidence.len(), 1);
+    assert!(evidence[0].contains("Status: 200 OK"));
+    assert!(evidence[0].contains("content-type: application/json"));
+
+    let deep = SecurityCommand::new(Config::default()).with_deep_scan(true);
+    let evidence = deep.collect_evidence(&url).await.unwrap();
+    // Main endpoint, three well-known files and three extra methods.
+    assert_eq!(evidence.len(), 7);
+}
+
+fn issue(severity: Severity, title: &str) -> SecurityIssue {
+    SecurityIssue { severity, title: title.to_string() }
+}
+
+#[test]
+fn security_diff_splits_new_resolved_and_unchanged() {
+    let old = vec![
+        issue(Severity::Low, "Missing x-frame-options header"),
+        issue(Severity::High, "Stack traces in error bodies"),
+    ];
+    let new = vec![
+        issue(Severity::Medium, "Missing content-security-policy header"),
+        issue(Severity::Low, "missing  X-Frame-Options header"),
+        issue(Severity::Critical, "Admin route reachable without auth"),
+    ];
+
+    let diff = diff_issues(&old, &new);
+    assert_eq!(diff.new, vec![
+        issue(Severity::Critical, "Admin route reachable without auth"),
+        issue(Severity::Medium, "Missing content-security-policy header"),
+    ]);
+    assert_eq!(diff.resolved, vec![issue(Severity::High, "Stack traces in error bodies")]);
+    assert_eq!(diff.unchanged, vec![issue(Severity::Low, "missing  X-Frame-Options header")]);
+    assert_eq!(diff_issues(&new, &new).new, Vec::new());
+}
+
+#[test]
+fn ai_bullets_with_a_severity_become_issues() {
+    let analysis = "1. Response headers security:\n        - High: no HSTS on an authenticated API\n        - Headers look consistent\n\n        2. Data exposure risks:\n        * **Critical** - internal IPs leak in X-Backend\n        - Low risk: verbose Server header\n        A highlighted paragraph that is not a bullet";
+
+    assert_eq!(parse_ai_issues(analysis), vec![
+        issue(Severity::High, "High: no HSTS on an authenticated API"),
+        issue(Severity::Critical, "Critical - internal IPs leak in X-Backend"),
+        issue(Severity::Low, "Low risk: verbose Server header"),
+    ]);
+}
+
+#[tokio::test]
+async fn security_reports_are_saved_and_diffed() {
+    let server = FixtureServer::start().await;
+    let url = server.url("/echo");
+    let dir = std::env::temp_dir().join(format!("nuts-security-{}", std::process::id()));
+    let saved = dir.join("before.json");
+
+    SecurityCommand::new(Config::default())
+        .with_save_file(Some(saved.display().to_string()))
+        .execute(&["security".to_string(), url.clone()])
+        .await
+        .unwrap();
+
+    let report = SecurityReport::load(&saved).unwrap();
+    assert_eq!(report.url, url);
+    assert_eq!(report.endpoints.len(), 1);
+    assert!(report.endpoints[0].evidence.contains("Status: 200 OK"));
+    assert!(report.issues.contains(&issue(Severity::High, "Endpoint served over plain HTTP")));
+    assert!(report.issues.contains(&issue(Severity::Medium, "Missing Strict-Transport-Security header")));
+    assert!(report.findings.iter().any(|f| f.id == "missing-csp" && !f.remediation.is_empty()));
+
+    SecurityCommand::new(Config::default())
+        .with_diff_file(Some(saved.display().to_string()))
+        .execute(&["security".to_string(), url])
+        .await
+        .unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn active_probes_only_run_with_active_and_record_their_request() {
+    let server = FixtureServer::start().await;
+    let url = server.url("/search");
+    let active_ids = ["reflected-xss", "sql-injection", "verbose-error", "auth-not-enforced", "verb-tampering"];
+
+    let deep = SecurityCommand::new(Config::default()).with_deep_scan(true).with_auth(Some(BEARER_TOKEN.to_string()));
+    let report = deep.execute(&["security".to_string(), url.clone()]).await.unwrap();
+    assert!(report.findings.iter().all(|f| !active_ids.contains(&f.id.as_str()) && f.request.is_none()));
+    let passive_hits = server.hits();
+
+    let active = SecurityCommand::new(Config::default())
+        .with_deep_scan(true)
+        .with_active(true)
+        .with_auth(Some(BEARER_TOKEN.to_string()));
+    let report = active.execute(&["security".to_string(), url.clone()]).await.unwrap();
+    assert!(server.hits() - 2 * passive_hits <= MAX_ACTIVE_PROBES);
+    let finding = |id: &str| report.findings.iter().find(|f| f.id == id).unwrap_or_else(|| panic!("no {} finding", id));
+    for id in active_ids {
+        assert!(finding(id).request.is_some(), "{}", id);
+    }
+    assert_eq!(finding("reflected-xss").request.as_deref(), Some(format!("GET {}?q=%3Cscript%3Ealert%28%27nuts%27%29%3C%2Fscript%3E", url).as_str()));
+    assert_eq!(finding("sql-injection").severity, Severity::Critical);
+    assert_eq!(finding("verbose-error").title, "Error page reveals a SQL error");
+    assert_eq!(finding("auth-not-enforced").request.as_deref(), Some(format!("GET {} without the Authorization header", url).as_str()));
+    assert_eq!(finding("verb-tampering").request.as_deref(), Some(format!("POST {} with an empty body", url).as_str()));
+
+    // Authentication is enforced and POST is refused with 405
+    let protected = SecurityCommand::new(Config::default())
+        .with_active(true)
+        .with_auth(Some(BEARER_TOKEN.to_string()));
+    let report = protected.execute(&["security".to_string(), server.url("/auth/bearer")]).await.unwrap();
+    assert!(report.findings.iter().all(|f| f.request.is_none()), "{:?}", report.findings);
+}
+
+fn scanned(url: &str, headers: &[(&str, &str)], body: &str) -> ScannedResponse {
+    ScannedResponse {
+        url: url.to_string(),
+        status: 200,
+        headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        body: body.to_string(),
+    }
+}
+
+fn rule_ids(response: &ScannedResponse) -> Vec<String> {
+    check_response(response).into_iter().map(|f| f.id).collect()
+}
+
+#[test]
+fn hardened_https_response_has_no_findings() {
+    let response = scanned("https://api.example.com", &[
+        ("strict-transport-security", "max-age=31536000"),
+        ("content-security-policy", "default-src 'none'"),
+        ("x-frame-options", "DENY"),
+        ("x-content-type-options", "nosniff"),
+        ("server", "nginx"),
+        ("set-cookie", "sid=abc; Path=/; Secure; HttpOnly; SameSite=Lax"),
+    ], "{}");
+    assert!(check_response(&response).is_empty());
+}
+
+#[test]
+fn security_rules_flag_concrete_problems() {
+    let response = scanned("http://api.example.com", &[
+        ("Server", "Apache/2.4.41 (Ubuntu)"),
+        ("X-Powered-By", "PHP/7.4.3"),
+        ("Set-Cookie", "sid=abc; Path=/; SameSite=Strict"),
+        ("Set-Cookie", "theme=dark; Secure; HttpOnly"),
+        ("Access-Control-Allow-Origin", "*"),
+        ("Access-Control-Allow-Credentials", "true"),
+    ], "<html><head><title>Index of /backup</title></head></html>");
+
+    let findings = check_response(&response);
+    let ids: Vec<&str> = findings.iter().map(|f| f.id.as_str()).collect();
+    assert_eq!(ids, vec![
+        "plain-http",
+        "missing-hsts",
+        "missing-csp",
+        "missing-x-frame-options",
+        "missing-x-content-type-options",
+        "server-version-disclosure",
+        "x-powered-by-disclosure",
+        "insecure-cookie",
+        "insecure-cookie",
+        "cors-wildcard-credentials",
+        "directory-listing",
+    ]);
+    let cookies: Vec<_> = findings.iter().filter(|f| f.id == "insecure-cookie").collect();
+    assert_eq!(cookies[0].title, "Cookie 'sid' is missing Secure, HttpOnly");
+    assert_eq!(cookies[0].severity, Severity::Medium);
+    assert_eq!(cookies[1].title, "Cookie 'theme' is missing SameSite");
+    assert_eq!(cookies[1].severity, Severity::Low);
+    assert_eq!(findings[9].severity, Severity::High);
+
+    // Same input, same findings
+    assert_eq!(check_response(&response), findings);
+    let wildcard = scanned("https://x", &[("access-control-allow-origin", "*")], "");
+    assert!(rule_ids(&wildcard).contains(&"cors-wildcard".to_string()));
+}
+
+#[test]
+fn rate_scheduler_paces_requests_on_a_fake_clock() {
+    let ms = Duration::from_millis;
+    let mut scheduler = RateScheduler::new(10.0, Duration::from_secs(1));
+    assert_eq!(scheduler.scheduled(), 10);
+
+    // On time for the first five, then the sender stalls for 250ms and catches up
+    for (i, now) in [0, 100, 200, 300, 400, 650, 650, 700, 800, 900].into_iter().enumerate() {
+        assert_eq!(scheduler.next_due(), Some(ms(i as u64 * 100)));
+        scheduler.dispatch(ms(now));
+    }
+    assert_eq!(scheduler.next_due(), None);
+    assert_eq!(scheduler.dispatched(), 10);
+
+    let summary = scheduler.summary(10);
+    assert_eq!((summary.lag_p50, summary.lag_max), (Duration::ZERO, ms(150)));
+    assert_eq!((summary.lag_p95, summary.lag_p99), (ms(50), ms(50)));
+    assert!(summary.target_met());
+
+    let mut behind = RateScheduler::new(10.0, Duration::from_secs(1));
+    behind.dispatch(Duration::ZERO);
+    let summary = behind.summary(1);
+    assert_eq!((summary.dispatched, summary.scheduled), (1, 10));
+    assert!(!summary.target_met());
+}
+
+#[tokio::test]
+async fn perf_holds_a_fixed_request_rate() {
+    let server = FixtureServer::start().await;
+    // Each request takes 100ms, so 40 req/s needs several requests in flight at once
+    let perf = PerfCommand::new(&Config::default()).with_rate(40.0, 100);
+
+    perf.run(&server.url("/delay/100"), 1, Duration::from_secs(1), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+    let rate = perf.rate_summary().unwrap();
+    assert_eq!((rate.scheduled, rate.dispatched), (40, 40));
+    assert_eq!(server.hits(), 40);
+    assert!(rate.target_met(), "{:?}", rate);
+
+    // With one request in flight at a time the schedule can't be kept
+    let perf = PerfCommand::new(&Config::default()).with_rate(40.0, 1);
+    perf.run(&server.url("/delay/100"), 1, Duration::from_secs(1), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+    let rate = perf.rate_summary().unwrap();
+    assert!(rate.dispatched <= 11, "{:?}", rate);
+    assert!(!rate.target_met());
+    assert!(rate.lag_max >= Duration::from_millis(500));
+}
+
+#[tokio::test]
+async fn predict_measures_the_api_instead_of_assuming() {
+    let home = isolated_home("commands");
+    let server = FixtureServer::start().await;
+    let options = PredictOptions::from_args(&["predict", "URL", "--probe-users", "2", "--probe-duration", "300ms"]).unwrap();
+    assert_eq!(options, PredictOptions { probe_users: 2, probe_duration: Duration::from_millis(300), trend: None });
+    assert!(PredictOptions::from_args(&["predict", "URL", "--probe-users", "0"]).is_err());
+    assert_eq!(PredictOptions::from_args(&["predict", "URL", "--trend"]).unwrap().trend, Some(10));
+    assert_eq!(PredictOptions::from_args(&["predict", "URL", "--trend", "3"]).unwrap().trend, Some(3));
+
+    let predict = PredictCommand::new(Config::default()).with_options(options.clone());
+    let prediction = predict.predict_health(&server.url("/hardened")).await.unwrap();
+    // The baseline GET plus a real load test
+    assert!(server.hits() > 2, "{}", server.hits());
+    assert!(prediction.security_alerts.contains(&"Only 2 of 5 recommended security headers present".to_string()), "{:?}", prediction.security_alerts);
+    assert!(prediction.performance_forecast.capacity_limit > 0);
+
+    let prediction = PredictCommand::new(Config::default()).with_options(options).predict_health(&server.url("/status/503")).await.unwrap();
+    assert!(prediction.predicted_issues.iter().any(|issue| issue == "The base URL answers 503 to a plain GET"), "{:?}", prediction.predicted_issues);
+    assert!(prediction.predicted_issues.iter().any(|issue| issue.starts_with("Error rate of 100.0%")), "{:?}", prediction.predicted_issues);
+    // Offline, the measured score is the score
+    assert_eq!(prediction.health_score, prediction.measured_score);
+
+    let history = PredictionHistory::new(home.join(".nuts").join("predictions"));
+    let runs = history.load(&server.url("/")).unwrap();
+    assert_eq!(runs.len(), 2);
+    assert!(runs[0].url.ends_with("/hardened") && runs[1].error_rate == 1.0);
+}
+
+fn signals() -> HealthSignals {
+    HealthSignals { p95_latency: Duration::from_millis(100), error_rate: 0.0, https: true, security_headers: 5, rate_limited: true }
+}
+
+#[test]
+fn health_score_is_full_for_a_fast_secure_error_free_api() {
+    assert_eq!(health_score(&signals()), 100.0);
+    let worst = HealthSignals { p95_latency: Duration::from_secs(5), error_rate: 1.0, https: false, security_headers: 0, rate_limited: false };
+    assert_eq!(health_score(&worst), 0.0);
+}
+
+#[test]
+fn health_score_latency_boundaries() {
+    let at = |ms: u64| health_score(&HealthSignals { p95_latency: Duration::from_millis(ms), ..signals() });
+    assert_eq!(at(0), 100.0);
+    assert_eq!(at(200), 100.0);
+    assert_eq!(at(1100), 85.0);
+    assert_eq!(at(2000), 70.0);
+    assert_eq!(at(60_000), 70.0);
+    assert!(at(201) < 100.0 && at(1999) > 70.0);
+}
+
+#[test]
+fn health_score_error_rate_boundaries() {
+    let at = |error_rate: f64| health_score(&HealthSignals { error_rate, ..signals() });
+    assert_eq!(at(0.0), 100.0);
+    assert!((at(0.05) - 85.0).abs() < 1e-9);
+    assert!((at(0.10) - 70.0).abs() < 1e-9);
+    assert_eq!(at(1.0), 70.0);
+    assert!(at(0.001) < 100.0);
+}
+
+#[test]
+fn health_score_https_headers_and_rate_limits() {
+    assert_eq!(health_score(&HealthSignals { https: false, ..signals() }), 85.0);
+    assert_eq!(health_score(&HealthSignals { rate_limited: false, ..signals() }), 90.0);
+    assert_eq!(health_score(&HealthSignals { security_headers: 0, ..signals() }), 85.0);
+    assert_eq!(health_score(&HealthSignals { security_headers: 2, ..signals() }), 91.0);
+    // More headers than are scored count as all five
+    assert_eq!(health_score(&HealthSignals { security_headers: 9, ..signals() }), 100.0);
+}
+
+#[test]
+fn recognizes_rate_limit_headers() {
+    for name in ["RateLimit", "ratelimit-limit", "RateLimit-Policy", "X-RateLimit-Remaining", "x-rate-limit-limit"] {
+        assert!(is_rate_limit_header(name), "{}", name);
+    }
+    for name in ["retry-after", "x-limit", "content-length"] {
+        assert!(!is_rate_limit_header(name), "{}", name);
+    }
+}
+
+#[test]
+fn trend_shows_the_last_runs() {
+    assert_eq!(predict::trend_lines("api.example.com", &[], 10), ["📭 No predictions recorded for api.example.com yet; run 'predict <url>' first"]);
+
+    let run = |day: u32, score: f64, p95_ms: u64| PredictionRecord {
+        timestamp: format!("2026-10-{:02}T09:30:00Z", day).parse().unwrap(),
+        url: "https://api.example.com".to_string(),
+        health_score: score,
+        measured_score: score - 5.0,
+        avg_ms: p95_ms / 2,
+        p95_ms,
+        error_rate: 0.01,
+        rps: 40.0,
+    };
+    let records = [run(1, 90.0, 100), run(8, 80.0, 200), run(15, 60.0, 400)];
+    let lines = predict::trend_lines("api.example.com", &records, 2);
+    assert_eq!(lines[0], "📈 Health trend for api.example.com, last 2 run(s)");
+    assert_eq!(lines.len(), 5);
+    assert!(lines[2].starts_with("   2026-10-08 09:30") && lines[2].contains("80.0") && lines[2].contains("200ms"), "{}", lines[2]);
+    assert_eq!(lines[4], "   Score █▆   p95 ▅█");
+}
+
+const UNFIXED_FLOW: &str = r#"openapi: 3.0.0
+info:
+  title: shop
+  version: 1.0.0
+paths:
+  /items:
+    get:
+      summary: List items
+      responses:
+        '200':
+          description: OK
+    post:
+      requestBody:
+        content:
+          application/json: {}
+      responses:
+        '201':
+          description: Created
+  /items/{id}:
+    delete:
+      description: Remove an item
+      responses:
+        '204':
+          description: Deleted
+        '404':
+          description: Not found
+  /health:
+    post:
+      summary: Ping
+      security: []
+      responses:
+        '200':
+          description: OK
+"#;
+
+/// Answers confirmations from a script, in order.
+struct Answers(Vec<&'static str>);
+
+impl StepPrompt for Answers {
+    fn read(&mut self, _prompt: &str, _initial: &str) -> Option<String> {
+        (!self.0.is_empty()).then(|| self.0.remove(0).to_string())
+    }
+}
+
+fn operation(spec: &OpenAPISpec, path: &str, method: &str) -> serde_json::Value {
+    let item = &spec.paths[path];
+    let operation = item.operations().find(|(m, _)| *m == method).unwrap().1;
+    serde_json::to_value(operation).unwrap()
+}
+
+#[test]
+fn fix_options_need_a_flow_to_apply() {
+    let options = FixOptions::from_args(&["fix", "https://api.example.com", "--flow", "shop", "--apply", "-y"]).unwrap();
+    assert_eq!(options.url.as_deref(), Some("https://api.example.com"));
+    assert_eq!((options.flow.as_deref(), options.apply, options.yes), (Some("shop"), true, true));
+    assert!(FixOptions::from_args(&["fix", "--flow", "shop"]).unwrap().url.is_none());
+    assert!(FixOptions::from_args(&["fix", "https://api.example.com", "--apply"]).unwrap_err().to_string().contains("--flow"));
+    assert!(FixOptions::from_args(&["fix"]).is_err());
+    assert!(FixOptions::from_args(&["fix", "--flow"]).is_err());
+}
+
+#[test]
+fn spec_edits_cover_missing_errors_and_unsecured_writes() {
+    let spec: OpenAPISpec = serde_yaml::from_str(UNFIXED_FLOW).unwrap();
+    let summaries: Vec<String> = spec_edits(&spec).iter().map(SpecEdit::summary).collect();
+    assert_eq!(summaries, [
+        "Add a 400 response to POST /health",
+        "Add a 400 response to GET /items",
+        "Add a 400 response to POST /items",
+        "Require 'bearerAuth' on POST /items",
+        "Require 'bearerAuth' on DELETE /items/{id}",
+    ]);
+    assert_eq!(undescribed_operations(&spec), [("POST".to_string(), "/items".to_string())]);
+
+    // A flow-wide requirement covers the writes, and an existing scheme is reused
+    let secured = UNFIXED_FLOW.replace("paths:", "security:\n  - apiKey: []\ncomponents:\n  securitySchemes:\n    apiKey: { type: apiKey, in: header, name: X-Key }\npaths:");
+    let spec: OpenAPISpec = serde_yaml::from_str(&secured).unwrap();
+    assert!(!spec_edits(&spec).iter().any(|edit| matches!(edit, SpecEdit::SecurityPlaceholder { .. })));
+}
+
+#[test]
+fn yaml_diff_shows_changes_with_context() {
+    let before = "a: 1\nb: 2\nc: 3\nd: 4\ne: 5\nf: 6\n";
+    let after = "a: 1\nb: 2\nc: 3\nd: 4\ne: 5\nf: 7\n";
+    assert_eq!(yaml_diff(before, after), ["   d: 4", "   e: 5", " - f: 6", " + f: 7"]);
+    assert!(yaml_diff(before, before).is_empty());
+}
+
+#[tokio::test]
+async fn fix_apply_edits_the_flow_only_where_confirmed() {
+    let dir = std::env::temp_dir().join(format!("nuts-fix-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("shop.yaml");
+    std::fs::write(&path, UNFIXED_FLOW).unwrap();
+
+    let options = FixOptions::from_args(&["fix", "--flow", "shop", "--apply"]).unwrap();
+    let fix = FixCommand::new(Config::default()).with_options(options).with_flows_dir(dir.clone());
+    // Decline only the 400 on GET /items
+    fix.auto_fix(&mut Answers(vec!["y", "n", "y", "yes", "y"])).await.unwrap();
+
+    let spec = OpenAPISpec::load(&path).unwrap();
+    assert!(operation(&spec, "/items", "GET")["responses"].get("400").is_none());
+    assert_eq!(operation(&spec, "/items", "POST")["responses"]["400"]["description"], "Invalid request");
+    assert_eq!(operation(&spec, "/items", "POST")["security"], serde_json::json!([{ "bearerAuth": [] }]));
+    assert_eq!(operation(&spec, "/items/{id}", "DELETE")["security"], serde_json::json!([{ "bearerAuth": [] }]));
+    assert_eq!(operation(&spec, "/health", "POST")["security"], serde_json::json!([]));
+    assert_eq!(spec.extras["components"]["securitySchemes"]["bearerAuth"]["scheme"], "bearer");
+    // Offline, descriptions are left for the AI
+    assert!(operation(&spec, "/items", "POST").get("description").is_none());
+
+    // Only the declined change is left, and --yes makes it without asking
+    assert_eq!(spec_edits(&spec).len(), 1);
+    let options = FixOptions::from_args(&["fix", "--flow", "shop", "--apply", "--yes"]).unwrap();
+    FixCommand::new(Config::default()).with_options(options).with_flows_dir(dir.clone())
+        .auto_fix(&mut Answers(Vec::new())).await.unwrap();
+    assert!(spec_edits(&OpenAPISpec::load(&path).unwrap()).is_empty());
+    let _ = std::fs::remove_dir_all(dir);
+}
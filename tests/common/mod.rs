@@ -0,0 +1,661 @@
+//! In-process HTTP fixture server shared by the integration tests.
+//!
+//! Every test spawns its own server on an ephemeral port so tests can run in parallel
+//! without sharing state.
+#![allow(dead_code)]
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, Method, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+    routing::{any, get},
+    Json, Router,
+};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Bearer token accepted by `/auth/bearer`.
+pub const BEARER_TOKEN: &str = "fixture-token";
+/// Credentials accepted by `/auth/basic`.
+pub const BASIC_USER: &str = "fixture";
+pub const BASIC_PASSWORD: &str = "secret";
+/// Client credentials accepted by `/oauth/token`.
+pub const OAUTH_CLIENT_ID: &str = "fixture-client";
+pub const OAUTH_CLIENT_SECRET: &str = "fixture-client-secret";
+/// Key accepted by the OpenAI-compatible `/v1/chat/completions`.
+pub const AI_KEY: &str = "fixture-ai-key";
+
+/// Point HOME at an empty directory for this test binary, so nothing reads or writes the
+/// real `~/.nuts`.
+pub fn isolated_home(name: &str) -> PathBuf {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    let home = std::env::temp_dir().join(format!("nuts-{}-home-{}", name, std::process::id()));
+    INIT.call_once(|| {
+        std::fs::create_dir_all(&home).unwrap();
+        std::env::set_var("HOME", &home);
+    });
+    home
+}
+
+#[derive(Default)]
+struct FixtureState {
+    hits: AtomicUsize,
+    items: Mutex<BTreeMap<u64, Value>>,
+    next_id: AtomicUsize,
+    tokens_issued: AtomicUsize,
+    flaky_hits: Mutex<HashMap<String, usize>>,
+    hooks: Mutex<Vec<Value>>,
+}
+
+/// Handle to a running fixture server.
+pub struct FixtureServer {
+    pub addr: SocketAddr,
+    state: Arc<FixtureState>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl FixtureServer {
+    /// Start the fixture server on `127.0.0.1:0`.
+    pub async fn start() -> Self {
+        let state = Arc::new(FixtureState::default());
+        let app = router(state.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind fixture listener");
+        let addr = listener.local_addr().expect("fixture address");
+        let handle = tokio::spawn(async move {
+            axum::serve(listener, app).await.expect("fixture server");
+        });
+
+        Self { addr, state, handle }
+    }
+
+    /// Absolute URL for `path` on this server.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    /// Number of access tokens `/oauth/token` has handed out.
+    pub fn tokens_issued(&self) -> usize {
+        self.state.tokens_issued.load(Ordering::SeqCst)
+    }
+
+    /// JSON bodies posted to `/hooks`, oldest first.
+    pub fn hook_payloads(&self) -> Vec<Value> {
+        self.state.hooks.lock().unwrap().clone()
+    }
+
+    /// Number of requests served so far.
+    pub fn hits(&self) -> usize {
+        self.state.hits.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+fn router(state: Arc<FixtureState>) -> Router {
+    Router::new()
+        .route("/echo", any(echo))
+        .route("/echo/*rest", any(echo))
+        .route("/delay/:ms", get(delay))
+        .route("/status/:code", any(status))
+        .route("/auth/bearer", get(auth_bearer))
+        .route("/auth/basic", get(auth_basic))
+        .route("/redirect/:n", get(redirect))
+        .route("/goto/:code", any(goto))
+        .route("/chunked", get(chunked))
+        .route("/gzip", get(gzip))
+        .route("/sse", get(sse))
+        .route("/graphql", axum::routing::post(graphql))
+        .route("/bytes/:n", get(bytes))
+        .route("/large/:kb", get(large))
+        .route("/cookies/set", get(set_cookies))
+        .route("/oauth/token", axum::routing::post(oauth_token))
+        .route("/flaky/:failures", any(flaky))
+        .route("/upload", axum::routing::post(upload))
+        .route("/size", axum::routing::post(size))
+        .route("/reflect", axum::routing::post(reflect))
+        .route("/hooks", axum::routing::post(hook))
+        .route("/search", any(search))
+        .route("/hardened", get(hardened))
+        .route("/site", get(site))
+        .route("/site/*rest", get(site))
+        .route("/sitemap.xml", get(sitemap))
+        .route("/specs/:name", get(spec))
+        .route("/v1/chat/completions", axum::routing::post(chat_completions))
+        .route("/items", get(list_items).post(create_item))
+        .route("/items/:id", get(get_item).put(update_item).delete(delete_item))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), count_hits))
+        .with_state(state)
+}
+
+async fn count_hits(
+    State(state): State<Arc<FixtureState>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    state.hits.fetch_add(1, Ordering::SeqCst);
+    next.run(request).await
+}
+
+/// Reflect the request back as JSON: method, path, query, headers, raw body and parsed form/JSON.
+async fn echo(
+    method: Method,
+    uri: Uri,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Json<Value> {
+    let headers: BTreeMap<String, String> = headers
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect();
+    let text = String::from_utf8_lossy(&body).to_string();
+    let content_type = headers.get("content-type").cloned().unwrap_or_default();
+
+    let parsed = if content_type.starts_with("application/json") {
+        serde_json::from_str(&text).unwrap_or(Value::Null)
+    } else if content_type.starts_with("application/x-www-form-urlencoded") {
+        let form: HashMap<String, String> = url::form_urlencoded::parse(text.as_bytes())
+            .into_owned()
+            .collect();
+        json!(form)
+    } else {
+        Value::Null
+    };
+
+    Json(json!({
+        "method": method.as_str(),
+        "path": uri.path(),
+        "query": query,
+        "headers": headers,
+        "body": text,
+        "parsed": parsed,
+    }))
+}
+
+async fn delay(Path(ms): Path<u64>) -> Json<Value> {
+    tokio::time::sleep(Duration::from_millis(ms)).await;
+    Json(json!({ "delayed_ms": ms }))
+}
+
+async fn status(Path(code): Path<u16>) -> Response {
+    let status = StatusCode::from_u16(code).unwrap_or(StatusCode::BAD_REQUEST);
+    (status, Json(json!({ "status": code }))).into_response()
+}
+
+async fn auth_bearer(headers: HeaderMap) -> Response {
+    let expected = format!("Bearer {}", BEARER_TOKEN);
+    match headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()) {
+        Some(value) if value == expected => Json(json!({ "authenticated": true })).into_response(),
+        _ => unauthorized("Bearer"),
+    }
+}
+
+/// Sends two of the five security headers `predict` scores.
+async fn hardened() -> Response {
+    ([("x-frame-options", "DENY"), ("x-content-type-options", "nosniff")], Json(json!({ "ok": true }))).into_response()
+}
+
+/// Writes `q` into HTML unescaped and into a "SQL query", like the APIs `security --active` catches.
+async fn search(Query(query): Query<HashMap<String, String>>) -> Response {
+    let q = query.get("q").cloned().unwrap_or_default();
+    if q.contains('\'') {
+        let error = format!("You have an error in your SQL syntax near '{}'", q);
+        return (StatusCode::INTERNAL_SERVER_ERROR, error).into_response();
+    }
+    ([(header::CONTENT_TYPE, "text/html")], format!("<p>Results for {}</p>", q)).into_response()
+}
+
+async fn auth_basic(headers: HeaderMap) -> Response {
+    let expected = format!("Basic {}", base64(&format!("{}:{}", BASIC_USER, BASIC_PASSWORD)));
+    match headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()) {
+        Some(value) if value == expected => Json(json!({ "authenticated": true })).into_response(),
+        _ => unauthorized("Basic"),
+    }
+}
+
+fn unauthorized(scheme: &str) -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, scheme.to_string())],
+        Json(json!({ "error": "unauthorized" })),
+    )
+        .into_response()
+}
+
+/// `/redirect/n` redirects n times before landing on `/echo`.
+async fn redirect(Path(n): Path<u32>) -> Redirect {
+    if n == 0 {
+        Redirect::to("/echo")
+    } else {
+        Redirect::to(&format!("/redirect/{}", n - 1))
+    }
+}
+
+/// `/goto/code?to=location` answers any method with that redirect status and a `hop` cookie;
+/// without `to` it points back at itself.
+async fn goto(Path(code): Path<u16>, uri: Uri, Query(query): Query<HashMap<String, String>>) -> Response {
+    let location = query.get("to").cloned().unwrap_or_else(|| uri.to_string());
+    let status = StatusCode::from_u16(code).unwrap_or(StatusCode::FOUND);
+    (status, [(header::LOCATION, location), (header::SET_COOKIE, format!("hop={}; Path=/", code))]).into_response()
+}
+
+/// A gzipped JSON body, whatever `Accept-Encoding` says, reporting the one it got.
+async fn gzip(headers: HeaderMap) -> Response {
+    use std::io::Write;
+    let accept = headers.get(header::ACCEPT_ENCODING).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    let body = json!({ "accept_encoding": accept, "items": vec!["compressible"; 50] });
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(body.to_string().as_bytes()).unwrap();
+    ([(header::CONTENT_TYPE, "application/json"), (header::CONTENT_ENCODING, "gzip")], encoder.finish().unwrap()).into_response()
+}
+
+/// Webhook receiver: keeps every JSON body for `FixtureServer::hook_payloads`.
+async fn hook(State(state): State<Arc<FixtureState>>, Json(payload): Json<Value>) -> StatusCode {
+    state.hooks.lock().unwrap().push(payload);
+    StatusCode::OK
+}
+
+/// A small web app for `discover` to crawl: HTML pages linking to JSON endpoints, JSON
+/// endpoints with `Link` headers, and a chain reaching past the default depth of 2.
+async fn site(uri: Uri) -> Response {
+    let html = |body: &str| ([(header::CONTENT_TYPE, "text/html")], body.to_string()).into_response();
+    let json_with_link = |link: &str| ([(header::LINK, link.to_string())], Json(json!({ "path": uri.path() }))).into_response();
+    match uri.path() {
+        "/site" => html(r##"<html><body>
+            <a href="/site/about">About</a> <A HREF='/site/api/widgets?page=1'>Widgets</A>
+            <a href="https://elsewhere.example/api/x">Elsewhere</a> <a href="#top">Top</a>
+            <link rel="stylesheet" href="/site/style.css"> <a href=/site/logo.png>Logo</a>
+            <form class="signup" action="/site/api/signup" method="post"><input name="email"></form>
+        </body></html>"##),
+        "/site/about" => html(r#"<p>Read the <a href="/site/api/deep">deep API</a>.</p>"#),
+        "/site/api/widgets" => json_with_link(r#"</site/api/widgets?page=2>; rel="next", </site/catalog>; rel="related""#),
+        "/site/api/deep" => json_with_link(r#"</site/more>; rel="next""#),
+        "/site/openapi.json" => Json(json!({
+            "openapi": "3.0.0",
+            "info": { "title": "Site", "version": "1" },
+            "paths": {
+                "/site/api/widgets": { "get": {
+                    "summary": "List widgets",
+                    "parameters": [{ "name": "page", "in": "query", "schema": { "type": "integer" } }]
+                } },
+                "/site/api/widgets/{id}": {
+                    "parameters": [{ "name": "id", "in": "path", "required": true }],
+                    "delete": { "summary": "Delete a widget" }
+                }
+            }
+        })).into_response(),
+        "/site/feed" | "/site/catalog" | "/site/more" => Json(json!({ "path": uri.path() })).into_response(),
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn sitemap(headers: HeaderMap) -> Response {
+    let host = headers.get(header::HOST).and_then(|h| h.to_str().ok()).unwrap_or_default();
+    let xml = format!(
+        "<?xml version=\"1.0\"?><urlset><url><loc>http://{host}/site/about</loc></url>\
+         <url><loc> http://{host}/site/feed </loc></url><url><loc>https://elsewhere.example/site/x</loc></url></urlset>"
+    );
+    ([(header::CONTENT_TYPE, "application/xml")], xml).into_response()
+}
+
+/// `ETag` of `/specs/openapi.json` and `Last-Modified` of `/specs/swagger.yaml`.
+pub const SPEC_ETAG: &str = "\"spec-v1\"";
+pub const SPEC_LAST_MODIFIED: &str = "Tue, 01 Sep 2026 10:00:00 GMT";
+
+/// API descriptions to import: OpenAPI 3 JSON revalidated by `ETag`, Swagger 2.0 YAML by
+/// `Last-Modified`, and JSON whose operation parameters have the wrong shape.
+async fn spec(Path(name): Path<String>, headers: HeaderMap) -> Response {
+    let header = |name| headers.get(name).and_then(|v: &header::HeaderValue| v.to_str().ok());
+    match name.as_str() {
+        "openapi.json" if header(header::IF_NONE_MATCH) == Some(SPEC_ETAG) => StatusCode::NOT_MODIFIED.into_response(),
+        "openapi.json" => ([(header::ETAG, SPEC_ETAG)], Json(json!({
+            "openapi": "3.0.3",
+            "info": { "title": "Pet Store", "version": "1.0" },
+            "servers": [{ "url": "/v1" }],
+            "paths": {
+                "/pets": { "get": { "summary": "List pets" }, "post": { "summary": "Add a pet" } },
+                "/pets/{id}": { "get": { "parameters": [{ "name": "id", "in": "path", "required": true }] } }
+            }
+        }))).into_response(),
+        "swagger.yaml" if header(header::IF_MODIFIED_SINCE) == Some(SPEC_LAST_MODIFIED) => StatusCode::NOT_MODIFIED.into_response(),
+        "swagger.yaml" => (
+            [(header::CONTENT_TYPE, "application/yaml"), (header::LAST_MODIFIED, SPEC_LAST_MODIFIED)],
+            "swagger: '2.0'\ninfo:\n  title: Legacy Orders\n  version: '2'\nhost: orders.example.com\nbasePath: /api\n\
+             schemes: [http]\npaths:\n  /orders:\n    get:\n      summary: List orders\n",
+        ).into_response(),
+        "broken.json" => Json(json!({
+            "openapi": "3.0.0",
+            "info": { "title": "Broken", "version": "1" },
+            "paths": { "/a/b": { "get": { "parameters": [{ "name": "x", "required": "yes" }] } } }
+        })).into_response(),
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// One session cookie per query parameter, scoped to the whole host.
+async fn set_cookies(Query(query): Query<BTreeMap<String, String>>) -> Response {
+    let mut response = Json(json!({ "set": query.len() })).into_response();
+    for (name, value) in query {
+        let cookie = format!("{}={}; Path=/", name, value);
+        response.headers_mut().append(header::SET_COOKIE, cookie.parse().unwrap());
+    }
+    response
+}
+
+/// Client-credentials token endpoint. Tokens are numbered `token-1`, `token-2`, ... and
+/// last `?expires_in=` seconds (default 3600).
+async fn oauth_token(
+    State(state): State<Arc<FixtureState>>,
+    Query(query): Query<HashMap<String, String>>,
+    axum::Form(form): axum::Form<HashMap<String, String>>,
+) -> Response {
+    let authorized = form.get("grant_type").map(String::as_str) == Some("client_credentials")
+        && form.get("client_id").map(String::as_str) == Some(OAUTH_CLIENT_ID)
+        && form.get("client_secret").map(String::as_str) == Some(OAUTH_CLIENT_SECRET);
+    if !authorized {
+        let error = json!({ "error": "invalid_client", "error_description": "Client authentication failed" });
+        return (StatusCode::UNAUTHORIZED, Json(error)).into_response();
+    }
+    let n = state.tokens_issued.fetch_add(1, Ordering::SeqCst) + 1;
+    let expires_in: u64 = query.get("expires_in").and_then(|s| s.parse().ok()).unwrap_or(3600);
+    Json(json!({
+        "access_token": format!("token-{}", n),
+        "token_type": "Bearer",
+        "expires_in": expires_in,
+        "scope": form.get("scope"),
+    }))
+    .into_response()
+}
+
+/// OpenAI-style chat completion whose answer describes the request: model, number of
+/// messages, max_tokens and the last message.
+/// Echoes the request as the answer, reporting 10 input tokens per message and 5 output
+/// tokens. A last message `busy N` is answered 503 (with `Retry-After: 0`) its first N
+/// times; `quota` always gets OpenAI's quota error.
+async fn chat_completions(State(state): State<Arc<FixtureState>>, headers: HeaderMap, Json(request): Json<Value>) -> Response {
+    if headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()) != Some(&format!("Bearer {}", AI_KEY)) {
+        let error = json!({ "error": { "message": "Incorrect API key provided", "type": "invalid_request_error", "code": "invalid_api_key" } });
+        return (StatusCode::UNAUTHORIZED, Json(error)).into_response();
+    }
+    let messages = request["messages"].as_array().cloned().unwrap_or_default();
+    let last = messages.last().cloned().unwrap_or(Value::Null);
+    let prompt = last["content"].as_str().unwrap_or_default();
+    if prompt == "quota" {
+        let error = json!({ "error": { "message": "You exceeded your current quota", "type": "insufficient_quota", "code": "insufficient_quota" } });
+        return (StatusCode::TOO_MANY_REQUESTS, Json(error)).into_response();
+    }
+    if let Some(failures) = prompt.strip_prefix("busy ").and_then(|n| n.parse::<usize>().ok()) {
+        let attempt = {
+            let mut hits = state.flaky_hits.lock().unwrap();
+            let count = hits.entry(format!("chat:{}", prompt)).or_default();
+            *count += 1;
+            *count
+        };
+        if attempt <= failures {
+            let error = json!({ "error": { "message": "The server is overloaded", "type": "server_error" } });
+            return (StatusCode::SERVICE_UNAVAILABLE, [(header::RETRY_AFTER, "0")], Json(error)).into_response();
+        }
+    }
+    let content = format!(
+        "model={} messages={} max_tokens={} {}: {}",
+        request["model"].as_str().unwrap_or_default(),
+        messages.len(),
+        request["max_tokens"],
+        last["role"].as_str().unwrap_or_default(),
+        last["content"].as_str().unwrap_or_default(),
+    );
+    Json(json!({
+        "id": "chatcmpl-fixture",
+        "object": "chat.completion",
+        "choices": [{ "index": 0, "message": { "role": "assistant", "content": content }, "finish_reason": "stop" }],
+        "usage": { "prompt_tokens": 10 * messages.len(), "completion_tokens": 5, "total_tokens": 10 * messages.len() + 5 },
+    }))
+    .into_response()
+}
+
+/// Fail the first `failures` requests to this exact URI with `?status=` (default 503), then
+/// answer 200. `?retry_after=` is sent back as the `Retry-After` header on failures.
+async fn flaky(
+    State(state): State<Arc<FixtureState>>,
+    uri: Uri,
+    Path(failures): Path<usize>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let attempt = {
+        let mut hits = state.flaky_hits.lock().unwrap();
+        let count = hits.entry(uri.to_string()).or_default();
+        *count += 1;
+        *count
+    };
+    if attempt > failures {
+        return Json(json!({ "attempt": attempt })).into_response();
+    }
+    let status = query.get("status").and_then(|s| s.parse().ok()).unwrap_or(503);
+    let mut response = (StatusCode::from_u16(status).unwrap(), Json(json!({ "attempt": attempt }))).into_response();
+    if let Some(retry_after) = query.get("retry_after") {
+        response.headers_mut().insert(header::RETRY_AFTER, retry_after.parse().unwrap());
+    }
+    response
+}
+
+/// Describe each multipart part: name, file name, content type and size in bytes.
+async fn upload(mut multipart: axum::extract::Multipart) -> Response {
+    let mut parts = Vec::new();
+    while let Some(field) = multipart.next_field().await.unwrap() {
+        let name = field.name().map(String::from);
+        let file_name = field.file_name().map(String::from);
+        let content_type = field.content_type().map(String::from);
+        let bytes = field.bytes().await.unwrap();
+        parts.push(json!({
+            "name": name,
+            "file_name": file_name,
+            "content_type": content_type,
+            "size": bytes.len(),
+            "text": file_name.is_none().then(|| String::from_utf8_lossy(&bytes).to_string()),
+        }));
+    }
+    Json(json!({ "parts": parts })).into_response()
+}
+
+/// The request body sent back as JSON, for responses shaped by the test.
+async fn reflect(body: Bytes) -> Response {
+    Response::builder().header(header::CONTENT_TYPE, "application/json").body(Body::from(body)).unwrap()
+}
+
+/// Size and framing of a request body of any length, which `/echo` would refuse past 2 MB.
+async fn size(headers: HeaderMap, body: Body) -> Json<Value> {
+    let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+    let header = |name: header::HeaderName| headers.get(name).and_then(|v| v.to_str().ok()).map(String::from);
+    Json(json!({
+        "size": bytes.len(),
+        "content_length": header(header::CONTENT_LENGTH),
+        "content_type": header(header::CONTENT_TYPE),
+        "last_byte": bytes.last(),
+    }))
+}
+
+/// Body streamed in small pieces without a content length, so it goes out chunked.
+async fn chunked() -> Response {
+    let text: String = (0..5).map(|i| format!("chunk-{}\n", i)).collect();
+    let stream = tokio_util::io::ReaderStream::with_capacity(std::io::Cursor::new(text.into_bytes()), 8);
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(Body::from_stream(stream))
+        .unwrap()
+}
+
+/// `events` server-sent events 20ms apart, preceded by a keep-alive comment. With
+/// `hold=true` the connection then stays open, like a live feed.
+async fn sse(Query(query): Query<HashMap<String, String>>) -> Response {
+    let events: usize = query.get("events").and_then(|n| n.parse().ok()).unwrap_or(3);
+    let hold = query.get("hold").is_some_and(|h| h == "true");
+    let (mut writer, reader) = tokio::io::duplex(1024);
+
+    tokio::spawn(async move {
+        use tokio::io::AsyncWriteExt;
+        let _ = writer.write_all(b": keep-alive\r\n\r\n").await;
+        for i in 1..=events {
+            let event = format!("event: tick\r\nid: {}\r\ndata: {{\"n\":{}}}\r\n\r\n", i, i);
+            let _ = writer.write_all(event.as_bytes()).await;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        if hold {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+        }
+    });
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .body(Body::from_stream(tokio_util::io::ReaderStream::new(reader)))
+        .unwrap()
+}
+
+/// Minimal GraphQL endpoint: echoes the query and variables as `data`, and fails any
+/// query mentioning `boom` with a GraphQL error.
+async fn graphql(Json(request): Json<Value>) -> Json<Value> {
+    let query = request["query"].as_str().unwrap_or_default();
+    if query.contains("boom") {
+        return Json(json!({
+            "data": null,
+            "errors": [{ "message": "Boom", "path": ["user", "name"] }]
+        }));
+    }
+    Json(json!({ "data": { "query": query, "variables": request["variables"] } }))
+}
+
+/// `n` bytes counting 0..=255 over and over; honours `Range: bytes=N-` with a 206.
+/// A compact JSON array of about `kb` kilobytes.
+async fn large(Path(kb): Path<usize>) -> Response {
+    let items: Vec<String> = (0..kb * 16).map(|i| format!(r#"{{"id":{},"name":"item {:016}","tags":["a","b"]}}"#, 10_000_000 + i, i)).collect();
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(format!("[{}]", items.join(","))))
+        .unwrap()
+}
+
+async fn bytes(Path(n): Path<usize>, headers: HeaderMap) -> Response {
+    let data: Vec<u8> = (0..n).map(|i| (i % 256) as u8).collect();
+    let start = headers.get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("bytes="))
+        .and_then(|v| v.strip_suffix('-'))
+        .and_then(|v| v.parse::<usize>().ok());
+    let builder = Response::builder().header(header::CONTENT_TYPE, "application/octet-stream");
+    match start {
+        Some(start) if start >= n => builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", n))
+            .body(Body::empty())
+            .unwrap(),
+        Some(start) => builder
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, n - 1, n))
+            .body(Body::from(data[start..].to_vec()))
+            .unwrap(),
+        None => builder.body(Body::from(data)).unwrap(),
+    }
+}
+
+async fn list_items(State(state): State<Arc<FixtureState>>) -> Json<Value> {
+    let items = state.items.lock().unwrap();
+    Json(Value::Array(items.values().cloned().collect()))
+}
+
+async fn create_item(State(state): State<Arc<FixtureState>>, Json(mut item): Json<Value>) -> Response {
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst) as u64 + 1;
+    if let Value::Object(map) = &mut item {
+        map.insert("id".to_string(), json!(id));
+    }
+    state.items.lock().unwrap().insert(id, item.clone());
+    (StatusCode::CREATED, Json(item)).into_response()
+}
+
+async fn get_item(State(state): State<Arc<FixtureState>>, Path(id): Path<u64>) -> Response {
+    match state.items.lock().unwrap().get(&id) {
+        Some(item) => Json(item.clone()).into_response(),
+        None => not_found(),
+    }
+}
+
+async fn update_item(
+    State(state): State<Arc<FixtureState>>,
+    Path(id): Path<u64>,
+    Json(mut item): Json<Value>,
+) -> Response {
+    let mut items = state.items.lock().unwrap();
+    if !items.contains_key(&id) {
+        return not_found();
+    }
+    if let Value::Object(map) = &mut item {
+        map.insert("id".to_string(), json!(id));
+    }
+    items.insert(id, item.clone());
+    Json(item).into_response()
+}
+
+async fn delete_item(State(state): State<Arc<FixtureState>>, Path(id): Path<u64>) -> Response {
+    match state.items.lock().unwrap().remove(&id) {
+        Some(_) => StatusCode::NO_CONTENT.into_response(),
+        None => not_found(),
+    }
+}
+
+fn not_found() -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({ "error": "not found" }))).into_response()
+}
+
+fn base64(input: &str) -> String {
+    const TABLE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let bytes = input.as_bytes();
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[((n >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Run `call <args...>` with the response body redirected to a temp file and return that body.
+pub async fn call_capture(args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "nuts-test-{}-{}.out",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    let path_str = path.to_string_lossy().to_string();
+
+    let mut full = vec!["call", "-o", path_str.as_str()];
+    full.extend_from_slice(args);
+    let result = nuts::commands::call::CallCommand::new().execute(&full).await;
+
+    let body = std::fs::read_to_string(&path).unwrap_or_default();
+    let _ = std::fs::remove_file(&path);
+    result.map(|_| body)
+}
+
+/// Like [`call_capture`], parsing the body as JSON.
+pub async fn call_json(args: &[&str]) -> Result<Value, Box<dyn std::error::Error>> {
+    let body = call_capture(args).await?;
+    Ok(serde_json::from_str(&body)?)
+}
@@ -0,0 +1,75 @@
+use nuts::completer::{resolve_alias, NutsCompleter, SHELL_COMMANDS};
+use nuts::config::Config;
+
+fn completer() -> NutsCompleter {
+    let flows = std::env::temp_dir().join(format!("nuts-completer-flows-{}", std::process::id()));
+    std::fs::create_dir_all(&flows).unwrap();
+    for file in ["users.yaml", "orders.yaml", "notes.txt"] {
+        std::fs::write(flows.join(file), "").unwrap();
+    }
+    let mut config = Config::default();
+    config.set_env_var("staging", "base", "https://staging.example.com");
+    config.set_env_var("prod", "base", "https://api.example.com");
+    NutsCompleter::new(flows, &config)
+}
+
+#[test]
+fn completes_commands_and_subcommands() {
+    let completer = completer();
+    assert_eq!(completer.candidates("co"), ["collection", "config", "configure", "cookies"]);
+    assert_eq!(completer.candidates("flow "), ["check", "contract", "docs", "export", "from-url", "import", "mock", "run", "story", "validate", "vars"]);
+    assert_eq!(completer.candidates("flow im"), ["import"]);
+    assert_eq!(completer.candidates("flow import "), ["postman"]);
+    assert_eq!(completer.candidates("flow vars "), ["list", "set"]);
+    assert_eq!(completer.candidates("config env "), ["add", "list", "use"]);
+    assert_eq!(completer.candidates("call P"), ["PATCH", "POST", "PURGE", "PUT"]);
+    assert_eq!(completer.candidates("perf GET https://x --us"), ["--users"]);
+    assert!(completer.candidates("call GET ").is_empty());
+    assert!(completer.candidates("nonsense ").is_empty());
+}
+
+#[test]
+fn completes_flow_and_environment_names() {
+    let mut completer = completer();
+    assert_eq!(completer.candidates("flow mock "), ["orders", "users"]);
+    assert_eq!(completer.candidates("collection export u"), ["users"]);
+    assert_eq!(completer.candidates("save o"), ["orders"]);
+    assert!(completer.candidates("flow mock users ").is_empty());
+
+    assert_eq!(completer.candidates("config env use "), ["prod", "staging"]);
+    assert_eq!(completer.candidates("config env use st"), ["staging"]);
+
+    let mut config = Config::default();
+    config.set_env_var("dev", "base", "http://localhost:3000");
+    completer.update_config(&config);
+    assert_eq!(completer.candidates("config env use "), ["dev"]);
+}
+
+#[test]
+fn command_table_is_sorted_and_aliases_resolve() {
+    let names: Vec<&str> = SHELL_COMMANDS.iter().map(|command| command.name).collect();
+    let mut sorted = names.clone();
+    sorted.sort_unstable();
+    assert_eq!(names, sorted);
+
+    assert_eq!(resolve_alias("collection"), "flow");
+    assert_eq!(resolve_alias("c"), "call");
+    assert_eq!(resolve_alias("diff"), "diff");
+}
+
+#[test]
+fn completes_user_aliases_from_the_config() {
+    let mut completer = completer();
+    let mut config = Config::default();
+    config.aliases.insert("smoke".to_string(), "flow run prod-api --all --fail-fast".to_string());
+    config.aliases.insert("u".to_string(), "c GET {{base_url}}/users/$1".to_string());
+    completer.update_config(&config);
+
+    assert_eq!(completer.candidates("sm"), ["smoke"]);
+    assert_eq!(completer.candidates("c"), ["cache", "call", "capture", "collection", "config", "configure", "cookies", "curl"]);
+    assert_eq!(completer.candidates("u"), ["u", "usage"]);
+    assert_eq!(completer.candidates("alias rm "), ["smoke", "u"]);
+    // A macro completes the options of the command it runs
+    assert_eq!(completer.candidates("u 42 --ret"), ["--retry", "--retry-on-status"]);
+    assert!(completer.candidates("u ").is_empty());
+}
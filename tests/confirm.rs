@@ -0,0 +1,201 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::ai::{AiAnswer, AiClient, AiError, AiMessage};
+use nuts::commands::ask::AskCommand;
+use nuts::commands::call::CallCommand;
+use nuts::config::Config;
+use nuts::confirm::{command_line, describe, run_step, ExecutionPolicy, StepDecision, StepPrompt};
+use nuts::shell::split_args;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+
+/// Answers prompts from a script and records what was asked.
+struct Script {
+    answers: Vec<String>,
+    asked: Vec<(String, String)>,
+}
+
+impl Script {
+    fn new(answers: &[&str]) -> Self {
+        Self { answers: answers.iter().rev().map(|a| a.to_string()).collect(), asked: Vec::new() }
+    }
+}
+
+impl StepPrompt for Script {
+    fn read(&mut self, prompt: &str, initial: &str) -> Option<String> {
+        self.asked.push((prompt.to_string(), initial.to_string()));
+        self.answers.pop()
+    }
+}
+
+fn args(line: &[&str]) -> Vec<String> {
+    line.iter().map(|a| a.to_string()).collect()
+}
+
+#[test]
+fn decides_per_method_from_flags_and_config() {
+    use StepDecision::*;
+    let policy = |dry_run, yes, auto_execute| ExecutionPolicy { dry_run, yes, auto_execute };
+    // (dry_run, yes, auto_execute) => [GET, POST, PATCH, PUT, DELETE]
+    let matrix = [
+        ((false, false, false), [Confirm, Confirm, Confirm, Confirm, Confirm]),
+        ((false, false, true), [Send, Send, Send, Confirm, Confirm]),
+        ((false, true, false), [Send, Send, Send, Send, Send]),
+        ((false, true, true), [Send, Send, Send, Send, Send]),
+        ((true, false, false), [Print, Print, Print, Print, Print]),
+        ((true, true, true), [Print, Print, Print, Print, Print]),
+    ];
+    for ((dry_run, yes, auto_execute), expected) in matrix {
+        let decisions: Vec<_> = ["GET", "POST", "patch", "PUT", "delete"].iter().map(|m| policy(dry_run, yes, auto_execute).decide(m)).collect();
+        assert_eq!(decisions, expected, "dry_run={} yes={} auto_execute={}", dry_run, yes, auto_execute);
+    }
+
+    let mut words = args(&["create", "a", "user", "--yes", "--dry-run"]);
+    let policy = ExecutionPolicy::take_flags(&mut words, true);
+    assert_eq!(words, ["create", "a", "user"]);
+    assert_eq!(policy, ExecutionPolicy { dry_run: true, yes: true, auto_execute: true });
+}
+
+#[test]
+fn describes_and_round_trips_requests() {
+    let options = CallCommand::new().parse_advanced_args(&["call", "POST", "http://x.io/users", "-H", "X-B: 2", "-H", "a-first: 1", "--bearer", "secret-token-value", "{\"name\": \"O'Brien\"}"]).unwrap();
+    assert_eq!(describe(&options), "POST http://x.io/users\na-first: 1\nX-B: 2\nAuthorization: Bearer secr************\n\n{\"name\": \"O'Brien\"}");
+
+    let step = args(&["call", "POST", "http://x.io/users?a=1&b=2", "-H", "X-Note: hi", "{\n  \"name\": \"O'Brien\"\n}"]);
+    let line = command_line(&step);
+    assert_eq!(line, r#"call POST http://x.io/users?a=1&b=2 -H 'X-Note: hi' '{"name":"O'\''Brien"}'"#);
+    assert_eq!(split_args(&line)[5], "{\"name\":\"O'Brien\"}");
+}
+
+#[tokio::test]
+async fn confirms_edits_skips_or_only_prints() {
+    let server = FixtureServer::start().await;
+    let call = CallCommand::new();
+    let step = args(&["call", "DELETE", &server.url("/echo/users/1")]);
+
+    // Dry run: nothing is asked or sent
+    let mut script = Script::new(&[]);
+    let dry_run = ExecutionPolicy { dry_run: true, ..Default::default() };
+    assert!(run_step(&call, &dry_run, step.clone(), &mut script).await.unwrap().is_none());
+    assert!(script.asked.is_empty());
+
+    // DELETE is confirmed even with auto-execute; anything but y skips it
+    let auto = ExecutionPolicy { auto_execute: true, ..Default::default() };
+    let mut script = Script::new(&[""]);
+    assert!(run_step(&call, &auto, step.clone(), &mut script).await.unwrap().is_none());
+    assert!(script.asked[0].0.contains("(y/N/edit)"));
+    assert_eq!(server.hits(), 0);
+
+    // Edit opens the call line; the edited request is decided again, and a PATCH needs no
+    // confirmation with auto-execute
+    let edited = format!("call PATCH {} '{{\"name\": \"Ann\"}}'", server.url("/echo/users/2"));
+    let mut script = Script::new(&["edit", &edited]);
+    let response = run_step(&call, &auto, step.clone(), &mut script).await.unwrap().unwrap();
+    assert_eq!(script.asked[1].1, format!("call DELETE {}", server.url("/echo/users/1")));
+    assert_eq!(script.asked.len(), 2);
+    assert_eq!((response.method.as_str(), response.status), ("PATCH", 200));
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&response.body).unwrap()["parsed"], json!({ "name": "Ann" }));
+
+    // With auto-execute a GET goes out unasked
+    let mut script = Script::new(&[]);
+    run_step(&call, &auto, args(&["call", &server.url("/echo")]), &mut script).await.unwrap().unwrap();
+    assert!(script.asked.is_empty());
+    assert_eq!(server.hits(), 2);
+}
+
+#[tokio::test]
+async fn ask_only_prints_the_proposed_request_in_a_dry_run() {
+    let server = FixtureServer::start().await;
+    let proposal = json!({ "method": "POST", "url": server.url("/echo/users"), "headers": { "X-Trace": "1" }, "body": { "name": "Ann" } });
+
+    let ask = AskCommand::new(Config::default()).with_policy(ExecutionPolicy { dry_run: true, ..Default::default() });
+    ask.execute_api_call(&proposal, &mut Script::new(&[])).await.unwrap();
+    assert_eq!(server.hits(), 0);
+
+    let ask = AskCommand::new(Config::default()).with_policy(ExecutionPolicy { yes: true, ..Default::default() });
+    ask.execute_api_call(&proposal, &mut Script::new(&[])).await.unwrap();
+    assert_eq!(server.hits(), 1);
+}
+
+/// Answers with `answers` in turn and keeps every conversation it was sent.
+struct ScriptedAi {
+    answers: Mutex<Vec<String>>,
+    seen: Mutex<Vec<Vec<AiMessage>>>,
+}
+
+impl ScriptedAi {
+    fn new(answers: &[Value]) -> Self {
+        Self { answers: Mutex::new(answers.iter().map(Value::to_string).collect()), seen: Mutex::new(Vec::new()) }
+    }
+}
+
+#[async_trait::async_trait]
+impl AiClient for ScriptedAi {
+    async fn answer(&self, messages: &[AiMessage]) -> Result<AiAnswer, AiError> {
+        self.seen.lock().unwrap().push(messages.to_vec());
+        Ok(AiAnswer { text: self.answers.lock().unwrap().remove(0), usage: None })
+    }
+}
+
+#[tokio::test]
+async fn ask_runs_a_plan_feeding_each_response_to_the_next_step() {
+    let server = FixtureServer::start().await;
+    let items = server.url("/items");
+    let plan = vec![
+        json!({ "method": "POST", "url": items, "body": { "name": "Ann" }, "explanation": "create the item" }),
+        json!({ "method": "GET", "url": format!("{}/{{id}}", items), "explanation": "fetch it" }),
+        json!({ "method": "DELETE", "url": format!("{}/{{id}}", items), "explanation": "clean up" }),
+    ];
+    let conversation = vec![AiMessage::user("create an item then fetch it"), AiMessage::assistant("(the plan)")];
+
+    // A dry run shows the plan only
+    let ai = ScriptedAi::new(&[]);
+    let ask = AskCommand::new(Config::default()).with_policy(ExecutionPolicy { dry_run: true, ..Default::default() });
+    let mut script = Script::new(&[]);
+    assert_eq!(ask.execute_plan(&ai, conversation.clone(), plan.clone(), &mut script).await.unwrap(), 0);
+    assert!(script.asked.is_empty());
+    assert_eq!(server.hits(), 0);
+
+    // The plan is confirmed once; the DELETE is asked about again, and declining it stops
+    // the plan after two steps
+    let ai = ScriptedAi::new(&[
+        json!({ "method": "GET", "url": format!("{}/1", items) }),
+        json!({ "method": "DELETE", "url": format!("{}/1", items) }),
+    ]);
+    let ask = AskCommand::new(Config::default());
+    let mut script = Script::new(&["y", "n"]);
+    assert_eq!(ask.execute_plan(&ai, conversation.clone(), plan.clone(), &mut script).await.unwrap(), 2);
+    assert!(script.asked[0].0.contains("Run these 3 steps? ⚠️"), "{:?}", script.asked);
+    assert!(script.asked[1].0.contains("Send this request? ⚠️"), "{:?}", script.asked);
+    assert_eq!(server.hits(), 2);
+
+    // Step 2 is written from the response to step 1, in the same conversation
+    let seen = ai.seen.lock().unwrap();
+    assert_eq!(seen[0].len(), 3);
+    assert!(seen[0][2].text.contains(r#""id":1"#), "{}", seen[0][2].text);
+    assert!(seen[0][2].text.contains("step 2 of the plan"), "{}", seen[0][2].text);
+    assert_eq!(seen[1].len(), 5);
+    assert!(seen[1][4].text.contains(&format!("GET {}/1 answered 200", items)), "{}", seen[1][4].text);
+}
+
+#[tokio::test]
+async fn ask_plans_stop_at_error_statuses_and_the_step_limit() {
+    let server = FixtureServer::start().await;
+    let plan = vec![
+        json!({ "method": "GET", "url": server.url("/echo/first") }),
+        json!({ "method": "GET", "url": server.url("/echo/second") }),
+    ];
+    let yes = ExecutionPolicy { yes: true, ..Default::default() };
+
+    let ai = ScriptedAi::new(&[json!({ "method": "GET", "url": server.url("/status/404") })]);
+    let ask = AskCommand::new(Config::default()).with_policy(yes);
+    let error = ask.execute_plan(&ai, Vec::new(), plan.clone(), &mut Script::new(&[])).await.unwrap_err();
+    assert!(error.to_string().ends_with("answered 404"), "{}", error);
+
+    let config = Config { ask_max_steps: Some(1), ..Config::default() };
+    let ai = ScriptedAi::new(&[]);
+    let ask = AskCommand::new(config).with_policy(yes);
+    assert_eq!(ask.execute_plan(&ai, Vec::new(), plan, &mut Script::new(&[])).await.unwrap(), 1);
+    assert!(ai.seen.lock().unwrap().is_empty());
+}
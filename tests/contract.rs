@@ -0,0 +1,121 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::config::Config;
+use nuts::flows::contract::{check_response, media_type_matches, request_example, Severity};
+use nuts::flows::manager::contract_table;
+use nuts::flows::{CollectionManager, Operation};
+
+fn operation(yaml: &str) -> Operation {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+fn severities(operation: &Operation, status: u16, content_type: Option<&str>, body: &str) -> Vec<(Severity, String)> {
+    check_response(operation, status, content_type, body, None).into_iter().map(|v| (v.severity, v.message)).collect()
+}
+
+const USER: &str = r#"
+responses:
+  "200":
+    description: A user
+    content:
+      application/json:
+        schema:
+          type: object
+          required: [id]
+          properties:
+            id: { type: integer }
+            tags: { type: array, items: { type: object, properties: { name: { type: string } } } }
+  4XX:
+    description: Client error
+"#;
+
+#[test]
+fn grades_statuses_content_types_and_bodies() {
+    let user = operation(USER);
+    assert!(severities(&user, 200, Some("application/json; charset=utf-8"), r#"{"id": 1, "tags": [{"name": "a"}]}"#).is_empty());
+    // Documented by range, without content to check
+    assert!(severities(&user, 404, Some("text/html"), "<h1>Not found</h1>").is_empty());
+
+    assert_eq!(severities(&user, 500, Some("application/json"), "{}"), [(Severity::High, "status 500 is undocumented (documented: 200, 4XX)".to_string())]);
+    assert_eq!(
+        severities(&user, 200, Some("text/plain"), r#"{"id": "1", "extra": true, "tags": [{"name": "a", "color": "red"}]}"#),
+        [
+            (Severity::Medium, "Content-Type text/plain is not documented for 200 (documented: application/json)".to_string()),
+            (Severity::High, "$.id: expected integer, got string".to_string()),
+            (Severity::Low, "$.extra: field is not in the schema".to_string()),
+            (Severity::Low, "$.tags[0].color: field is not in the schema".to_string()),
+        ]
+    );
+    assert_eq!(severities(&user, 200, None, "nope")[1].0, Severity::High);
+    assert_eq!(severities(&operation("summary: x"), 200, None, "")[0].1, "status 200 is undocumented: the operation documents no responses");
+
+    // additionalProperties documents whatever else is there
+    let open = operation("responses: { default: { description: any, content: { application/json: { schema: { type: object, properties: { id: {} }, additionalProperties: true } } } } }");
+    assert!(severities(&open, 201, Some("application/json"), r#"{"id": 1, "more": 2}"#).is_empty());
+
+    assert!(media_type_matches("application/*", "application/problem+json"));
+    assert!(media_type_matches("*/*", "image/png"));
+    assert!(!media_type_matches("application/json", "application/xml"));
+}
+
+#[test]
+fn picks_request_body_examples() {
+    let named = operation("requestBody: { content: { text/plain: { example: hello }, application/json: { examples: { first: { value: { name: widget } } } } } }");
+    assert_eq!(request_example(&named), Some(("application/json".to_string(), r#"{"name":"widget"}"#.to_string())));
+    let from_schema = operation("requestBody: { content: { application/json: { schema: { type: object, example: { a: 1 } } } } }");
+    assert_eq!(request_example(&from_schema).unwrap().1, r#"{"a":1}"#);
+    let text = operation("requestBody: { content: { text/plain: { example: hello } } }");
+    assert_eq!(request_example(&text), Some(("text/plain".to_string(), "hello".to_string())));
+    assert_eq!(request_example(&operation("requestBody: { content: { application/json: { schema: { type: object } } } }")), None);
+}
+
+#[tokio::test]
+async fn flow_contract_checks_a_live_server() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-contract-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let paths = r#"
+  /items:
+    post:
+      requestBody:
+        content:
+          application/json:
+            examples:
+              widget: { value: { name: widget } }
+      responses:
+        "201":
+          description: Created
+          content:
+            application/json:
+              schema: { type: object, required: [id, name], properties: { id: { type: integer }, name: { type: string } } }
+  /status/200:
+    get:
+      responses:
+        "200":
+          description: OK
+          content:
+            application/json:
+              schema: { type: object, properties: { code: { type: integer } } }
+  /status/201:
+    get:
+      responses:
+        "201": { description: Created, content: { text/plain: {} } }
+"#;
+    // The spec's server is never used: --base-url wins
+    let flow = format!("openapi: 3.0.0\ninfo: {{ title: shop, version: 1.0.0 }}\nservers:\n  - url: http://127.0.0.1:1\npaths:{}", paths);
+    std::fs::write(dir.join("shop.yaml"), &flow).unwrap();
+    std::fs::write(dir.join("broken.yaml"), format!("{}  /status/500:\n    get:\n      responses:\n        \"200\": {{ description: OK }}\n", flow)).unwrap();
+    let manager = CollectionManager::new(dir.clone(), Config::default()).with_base_url(Some(server.url("/")));
+
+    let checks = manager.contract_test("shop").await.unwrap();
+    let results: Vec<(&str, Option<u16>, Option<Severity>)> = checks.iter().map(|c| (c.endpoint.as_str(), c.status, c.worst())).collect();
+    assert_eq!(results, [("POST /items", Some(201), None), ("GET /status/200", Some(200), Some(Severity::Low)), ("GET /status/201", Some(201), Some(Severity::Medium))]);
+    let table = contract_table(&checks);
+    assert!(table.contains("WARN: 1 violation(s)\n    • [low] $.status: field is not in the schema\n"), "{}", table);
+    assert!(table.contains("• [medium] Content-Type application/json is not documented for 201 (documented: text/plain)"), "{}", table);
+
+    let error = manager.contract_test("broken").await.unwrap_err().to_string();
+    assert_eq!(error, "1 of 4 endpoint(s) in 'broken' break the contract");
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,184 @@
+mod common;
+
+use common::{isolated_home, FixtureServer};
+use nuts::commands::call::{CallCommand, CallOptions};
+use nuts::commands::curl::translate;
+use nuts::config::Config;
+use nuts::shell::{split_args, NutsShell};
+
+/// Translate a pasted curl command and parse the result as `call` would.
+fn call(line: &str) -> (Vec<String>, CallOptions, Vec<String>) {
+    let translation = translate(&split_args(line)).unwrap();
+    let args: Vec<&str> = translation.args.iter().map(String::as_str).collect();
+    let options = CallCommand::new().parse_advanced_args(&args).unwrap();
+    (translation.args, options, translation.warnings)
+}
+
+fn header<'a>(options: &'a CallOptions, name: &str) -> Option<&'a str> {
+    options.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+}
+
+#[test]
+fn plain_get() {
+    let (args, options, warnings) = call("curl https://api.example.com/users");
+    assert_eq!(args, ["call", "GET", "https://api.example.com/users"]);
+    assert_eq!(options.method, "GET");
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn json_post_with_headers() {
+    let (_, options, _) = call(r#"curl -X POST https://api.example.com/users -H "Content-Type: application/json" -H 'Authorization: Bearer abc' -d '{"name":"Ada","tags":["x"]}'"#);
+    assert_eq!(options.method, "POST");
+    assert_eq!(header(&options, "content-type"), Some("application/json"));
+    assert_eq!(header(&options, "authorization"), Some("Bearer abc"));
+    assert_eq!(options.body.as_deref(), Some(r#"{"name":"Ada","tags":["x"]}"#));
+}
+
+#[test]
+fn escaped_quotes_inside_double_quotes() {
+    let (_, options, _) = call(r#"curl "https://api.example.com/notes" --data "{\"text\": \"say \\\"hi\\\"\"}" -H "Content-Type: application/json""#);
+    assert_eq!(options.body.as_deref(), Some(r#"{"text": "say \"hi\""}"#));
+    // -d alone means POST
+    assert_eq!(options.method, "POST");
+}
+
+#[test]
+fn browser_copy_as_curl() {
+    let pasted = "curl 'https://app.example.com/api/search?q=nuts' \\\n  -H 'accept: */*' \\\n  -H 'cookie: session=abc; theme=dark' \\\n  --data-raw $'{\"q\":\"it\\'s\\\\n\"}' \\\n  --compressed";
+    let (args, options, warnings) = call(pasted);
+    assert_eq!(options.url, "https://app.example.com/api/search?q=nuts");
+    assert_eq!(header(&options, "cookie"), Some("session=abc; theme=dark"));
+    assert_eq!(options.body.as_deref(), Some("{\"q\":\"it's\\n\"}"));
+    assert!(options.compressed);
+    assert!(warnings.is_empty());
+    assert_eq!(&args[args.len() - 2..], ["-d", "{\"q\":\"it's\\n\"}"]);
+}
+
+#[test]
+fn bundled_and_attached_short_options() {
+    let (args, options, _) = call("curl -sSLk -XPUT -HAccept:text/plain -u admin:s3cret api.example.com/items/1 -d name=new");
+    assert_eq!(options.method, "PUT");
+    assert!(options.follow_redirects && options.insecure);
+    assert_eq!(header(&options, "accept"), Some("text/plain"));
+    assert_eq!(options.auth, Some(("admin".to_string(), "s3cret".to_string())));
+    // curl's default scheme, and its default body type
+    assert_eq!(options.url, "http://api.example.com/items/1");
+    assert_eq!(header(&options, "content-type"), Some("application/x-www-form-urlencoded"));
+    assert_eq!(&args[..3], ["call", "PUT", "http://api.example.com/items/1"]);
+}
+
+#[test]
+fn repeated_data_is_joined_with_ampersands() {
+    let (_, options, _) = call("curl https://api.example.com/login -d user=ada -d 'pass=p w' --data-urlencode 'note=a&b c'");
+    assert_eq!(options.body.as_deref(), Some("user=ada&pass=p w&note=a%26b+c"));
+    assert_eq!(options.method, "POST");
+}
+
+#[test]
+fn get_moves_data_into_the_query() {
+    let (_, options, _) = call("curl -G https://api.example.com/search?lang=en -d q=rust -d page=2");
+    assert_eq!(options.url, "https://api.example.com/search?lang=en&q=rust&page=2");
+    assert_eq!((options.method.as_str(), options.body.as_deref()), ("GET", None));
+}
+
+#[test]
+fn multipart_form_upload() {
+    let file = std::env::temp_dir().join(format!("nuts-curl-upload-{}.txt", std::process::id()));
+    std::fs::write(&file, "hello").unwrap();
+    let (_, options, _) = call(&format!("curl -F 'title=Report' -F 'file=@{};type=text/plain' https://api.example.com/upload", file.display()));
+    assert_eq!(options.method, "POST");
+    assert_eq!(options.form_data["title"], "Report");
+    assert!(options.form_data["file"].starts_with('@'));
+    let _ = std::fs::remove_file(file);
+}
+
+#[test]
+fn head_user_agent_referer_timeout_and_output() {
+    let (_, options, _) = call("curl -I -A 'nuts-test/1.0' -e https://ref.example.com --max-time 2.5 --connect-timeout 0.5 --max-redirs 3 -D headers.txt -o out.html -i https://example.com");
+    assert_eq!(options.method, "HEAD");
+    assert_eq!(options.user_agent.as_deref(), Some("nuts-test/1.0"));
+    assert_eq!(header(&options, "referer"), Some("https://ref.example.com"));
+    assert_eq!(options.timeout, Some(std::time::Duration::from_secs(3)));
+    assert_eq!(options.connect_timeout, Some(std::time::Duration::from_secs(1)));
+    assert_eq!(options.max_redirects, 3);
+    assert_eq!(options.dump_headers.as_deref(), Some("headers.txt"));
+    assert_eq!(options.output_file.as_deref(), Some("out.html"));
+    assert!(options.include_headers);
+}
+
+#[test]
+fn json_flag_sets_both_headers() {
+    let (_, options, _) = call(r#"curl --json '{"a":1}' https://api.example.com/things"#);
+    assert_eq!(options.method, "POST");
+    assert_eq!(header(&options, "content-type"), Some("application/json"));
+    assert_eq!(header(&options, "accept"), Some("application/json"));
+}
+
+#[test]
+fn unsupported_options_warn_and_are_skipped_with_their_values() {
+    let (args, options, warnings) = call("curl --capath certs -w '%{http_code}' --frobnicate -v https://api.example.com -b cookies.txt");
+    assert_eq!(args, ["call", "GET", "https://api.example.com", "-v"]);
+    assert!(options.verbose);
+    assert_eq!(warnings, [
+        "Ignoring unsupported curl option --capath certs",
+        "Ignoring unsupported curl option -w %{http_code}",
+        "Ignoring unsupported curl option --frobnicate",
+        "Ignoring -b cookies.txt: cookie files aren't supported, pass 'name=value' instead",
+    ]);
+}
+
+#[test]
+fn literal_at_and_url_flag() {
+    let (args, options, _) = call("curl --url https://api.example.com/handles -H 'Content-Type: text/plain' --data-raw '@nuts' --oauth2-bearer tok");
+    assert_eq!(options.body.as_deref(), Some("@nuts"));
+    assert_eq!(options.bearer_token.as_deref(), Some("tok"));
+    assert_eq!(args.last().unwrap(), "@nuts");
+}
+
+#[test]
+fn client_certificates_carry_over() {
+    let (args, options, warnings) = call("curl -E client.pem --key client.key --cacert ca.pem https://internal.example.com");
+    assert_eq!(&args[3..], ["--cert", "client.pem", "--key", "client.key", "--cacert", "ca.pem"]);
+    assert_eq!((options.tls.cert.as_deref(), options.tls.cacert.as_deref()), (Some("client.pem"), Some("ca.pem")));
+    assert!(warnings.is_empty());
+
+    let (args, _, warnings) = call("curl --cert client.pem:s3cret https://internal.example.com");
+    assert_eq!(args.len(), 3);
+    assert_eq!(warnings, ["Ignoring --cert client.pem:s3cret: certificate passwords aren't supported, use an unencrypted PEM key"]);
+}
+
+#[test]
+fn aws_sigv4_keeps_region_and_service() {
+    let (args, options, warnings) = call("curl --aws-sigv4 aws:amz:eu-west-1:execute-api -u AKID:secret https://abc.execute-api.eu-west-1.amazonaws.com/prod/items");
+    assert_eq!(&args[3..], ["--aws-sigv4", "eu-west-1:execute-api"]);
+    assert_eq!(options.aws_sigv4.unwrap().to_string(), "eu-west-1:execute-api");
+    assert!(options.auth.is_none());
+    assert_eq!(warnings, ["Dropped -u: --aws-sigv4 signs with AWS_ACCESS_KEY_ID/AWS_SECRET_ACCESS_KEY or ~/.aws/credentials"]);
+
+    let (args, _, warnings) = call("curl --aws-sigv4 aws:amz https://abc.execute-api.eu-west-1.amazonaws.com");
+    assert_eq!(args.len(), 3);
+    assert_eq!(warnings, ["Ignoring --aws-sigv4 aws:amz: NUTS needs the region and service, as in aws:amz:us-east-1:execute-api"]);
+}
+
+#[test]
+fn missing_url_or_value_is_an_error() {
+    assert!(translate(&split_args("curl -H 'Accept: */*'")).unwrap_err().to_string().contains("No URL"));
+    assert!(translate(&split_args("curl https://x.io -H")).unwrap_err().to_string().contains("-H needs a value"));
+}
+
+#[tokio::test]
+async fn shell_runs_the_translated_call() {
+    isolated_home("curl");
+    let server = FixtureServer::start().await;
+    let mut shell = NutsShell::with_config(Config::default());
+    shell
+        .process_command(&format!("curl -s -X PATCH {} -H 'X-Test: yes' -H 'Content-Type: application/json' -d '{{\"a\": 1}}'", server.url("/echo")))
+        .await
+        .unwrap();
+
+    let response: serde_json::Value = serde_json::from_str(shell.last_response().unwrap()).unwrap();
+    assert_eq!(response["method"], "PATCH");
+    assert_eq!(response["headers"]["x-test"], "yes");
+    assert_eq!(response["parsed"]["a"], 1);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::commands::diff::{diff_json, diff_lines, path_matches, DiffCommand, Difference, LineChange};
+use serde_json::json;
+
+#[test]
+fn diffs_json_structurally_ignoring_key_order() {
+    let left = json!({ "id": 1, "user": { "name": "ada", "role": "admin" }, "tags": ["a", "b"], "old": true });
+    let right = json!({ "tags": ["a", "c", "d"], "user": { "role": "admin", "name": "grace" }, "id": 1, "new": null });
+
+    assert_eq!(diff_json(&left, &right, &[]), vec![
+        Difference::Added { path: "$.new".into(), value: json!(null) },
+        Difference::Removed { path: "$.old".into(), value: json!(true) },
+        Difference::Changed { path: "$.tags[1]".into(), left: json!("b"), right: json!("c") },
+        Difference::Added { path: "$.tags[2]".into(), value: json!("d") },
+        Difference::Changed { path: "$.user.name".into(), left: json!("ada"), right: json!("grace") },
+    ]);
+    assert!(diff_json(&left, &left, &[]).is_empty());
+}
+
+#[test]
+fn ignores_paths_and_everything_below_them() {
+    let left = json!({ "meta": { "request_id": "a", "page": 1 }, "items": [{ "id": 1, "updated_at": "x" }, { "id": 2, "updated_at": "y" }] });
+    let right = json!({ "meta": { "request_id": "b", "page": 1 }, "items": [{ "id": 1, "updated_at": "z" }, { "id": 2, "updated_at": "w" }] });
+
+    let ignore = vec!["$.meta.request_id".to_string(), "$.items[*].updated_at".to_string()];
+    assert!(diff_json(&left, &right, &ignore).is_empty());
+    assert_eq!(diff_json(&left, &right, &ignore[..1]).len(), 2);
+    assert!(diff_json(&left, &right, &["$.meta".to_string(), "$.items".to_string()]).is_empty());
+
+    assert!(path_matches("$.a.*.c", "$.a.b.c"));
+    assert!(path_matches("$.items[0]", "$.items[0].name"));
+    assert!(!path_matches("$.items[0]", "$.items[1].name"));
+    assert!(!path_matches("$.a.b", "$.a"));
+}
+
+#[test]
+fn falls_back_to_a_line_diff() {
+    let changes = diff_lines("one\ntwo\nthree", "one\n2\nthree\nfour");
+    assert_eq!(changes, vec![
+        LineChange::Same("one"),
+        LineChange::Removed("two"),
+        LineChange::Added("2"),
+        LineChange::Same("three"),
+        LineChange::Added("four"),
+    ]);
+}
+
+#[tokio::test]
+async fn compares_two_live_endpoints() {
+    let server = FixtureServer::start().await;
+    let diff = DiffCommand::new();
+
+    let args = ["diff", "POST", &server.url("/echo?env=prod"), &server.url("/echo?env=staging"), "-d", r#"{"a":1}"#];
+    let options = DiffCommand::parse_args(&args).unwrap();
+    let report = diff.run(&options).await.unwrap();
+    assert_eq!(report.differences, vec![
+        Difference::Changed { path: "$.query.env".into(), left: json!("prod"), right: json!("staging") },
+    ]);
+    let error = diff.execute(&args).await.unwrap_err().to_string();
+    assert!(error.starts_with("Found 1 difference(s)"), "{}", error);
+
+    let mut ignoring = args.to_vec();
+    ignoring.extend(["--ignore-path", "$.query.env"]);
+    assert!(diff.execute(&ignoring).await.unwrap().is_identical());
+
+    let statuses = DiffCommand::parse_args(&["diff", &server.url("/status/200"), &server.url("/status/503")]).unwrap();
+    let report = diff.run(&statuses).await.unwrap();
+    assert_eq!((report.left_status, report.right_status), (200, 503));
+    assert_eq!(report.differences.iter().map(Difference::path).collect::<Vec<_>>(), ["status", "$.status"]);
+
+    let text = DiffCommand::parse_args(&["diff", &server.url("/chunked"), &server.url("/bytes/4"), "--headers-too"]).unwrap();
+    let report = diff.run(&text).await.unwrap();
+    assert!(report.line_changes > 0);
+    assert!(report.differences.iter().any(|d| d.path() == "headers.content-type"));
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::commands::discover::{html_targets, link_targets, sitemap_urls, DiscoverCommand, DiscoverOptions};
+use nuts::config::Config;
+use nuts::flows::{MockDataConfig, OpenAPISpec};
+use std::time::{Duration, Instant};
+
+#[test]
+fn extracts_targets_from_links_sitemaps_and_html() {
+    assert_eq!(
+        link_targets(r#"<https://api.example.com/items?page=2>; rel="next", </docs>; rel="help""#),
+        ["https://api.example.com/items?page=2", "/docs"]
+    );
+    assert_eq!(
+        sitemap_urls("<urlset><url><loc> https://x.io/a?b=1&amp;c=2 </loc></url><url><loc>https://x.io/b</loc></url></urlset>"),
+        ["https://x.io/a?b=1&c=2", "https://x.io/b"]
+    );
+    let html = r##"<a class=nav href=/one>1</a><a data-href="/no">x</a><FORM Method='put' ACTION="/api/two"></FORM>
+        <a href="mailto:a@b.c">m</a><form action="/search"><a href="#top">t</a>"##;
+    assert_eq!(html_targets(html), [
+        ("GET".to_string(), "/one".to_string()),
+        ("PUT".to_string(), "/api/two".to_string()),
+        ("GET".to_string(), "/search".to_string()),
+    ]);
+
+    let options = DiscoverOptions::from_args(&["discover", "https://x.io", "--depth", "3", "--max-requests", "10", "--delay", "250ms"]).unwrap();
+    assert_eq!(options, DiscoverOptions { depth: 3, max_requests: 10, delay: Duration::from_millis(250) });
+    assert_eq!(DiscoverOptions::from_args(&["discover", "https://x.io", "--delay", "1.5s"]).unwrap().delay, Duration::from_millis(1500));
+    assert!(DiscoverOptions::from_args(&["discover", "https://x.io", "--max-requests", "0"]).is_err());
+}
+
+#[tokio::test]
+async fn crawls_same_origin_links_up_to_the_depth() {
+    let server = FixtureServer::start().await;
+    let options = DiscoverOptions { delay: Duration::ZERO, ..Default::default() };
+    let api_map = DiscoverCommand::new(Config::default()).with_options(options).discover(&server.url("/site")).await.unwrap();
+
+    let mut found: Vec<String> = api_map.endpoints.iter().map(|e| format!("{} {}", e.method, e.path)).collect();
+    found.sort();
+    assert_eq!(found, [
+        "DELETE /site/api/widgets/{id}",
+        "GET /site/api/deep",
+        "GET /site/api/widgets",
+        "GET /site/catalog",
+        "GET /site/feed",
+        "POST /site/api/signup",
+    ]);
+    let widgets = api_map.endpoints.iter().find(|e| e.path == "/site/api/widgets").unwrap();
+    assert_eq!(widgets.parameters, ["page"]);
+
+    // /site/more is three links away; nothing off-origin, no assets, forms never submitted
+    let crawled = api_map.crawled.join(" ");
+    assert!(crawled.contains("/site/api/widgets?page=2"), "{}", crawled);
+    assert!(!crawled.contains("/site/more"), "{}", crawled);
+    assert!(!crawled.contains("elsewhere") && !crawled.contains(".css") && !crawled.contains(".png"), "{}", crawled);
+    assert!(!crawled.contains("signup"), "{}", crawled);
+}
+
+#[tokio::test]
+async fn crawl_stops_at_max_requests_and_waits_between_them() {
+    let server = FixtureServer::start().await;
+    let options = DiscoverOptions { depth: 2, max_requests: 3, delay: Duration::from_millis(100) };
+    let started = Instant::now();
+    let api_map = DiscoverCommand::new(Config::default()).with_options(options).discover(&server.url("/site")).await.unwrap();
+
+    assert_eq!(api_map.crawled.len(), 3);
+    assert!(api_map.crawled[0].ends_with("/sitemap.xml"));
+    assert!(started.elapsed() >= Duration::from_millis(200), "{:?}", started.elapsed());
+}
+
+#[tokio::test]
+async fn discovery_is_saved_and_merged_as_a_flow() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-discover-flow-{}", std::process::id()));
+    let command = DiscoverCommand::new(Config::default()).with_options(DiscoverOptions { delay: Duration::ZERO, ..Default::default() });
+    let api_map = command.discover(&server.url("/site")).await.unwrap();
+
+    let path = command.generate_flow(&api_map, &dir, "site").await.unwrap();
+    assert_eq!(path, dir.join("site.yaml"));
+    let yaml = std::fs::read_to_string(&path).unwrap();
+    for route in ["/site/api/widgets:", "/site/api/widgets/{id}:", "/site/api/signup:", "/site/feed:", "/site/catalog:"] {
+        assert!(yaml.contains(route), "{} missing from\n{}", route, yaml);
+    }
+    assert!(yaml.contains(&server.url("/site")), "{}", yaml);
+
+    let spec = OpenAPISpec::load(&path).unwrap();
+    let widget = spec.paths["/site/api/widgets/{id}"].delete.as_ref().unwrap();
+    let id = &widget.parameters.as_ref().unwrap()[0];
+    assert_eq!((id.name.as_str(), id.in_.as_str(), id.required), ("id", "path", true));
+    let feed = spec.paths["/site/feed"].get.as_ref().unwrap();
+    assert!(feed.responses["200"].content.as_ref().unwrap().contains_key("application/json"));
+
+    // A second run keeps what was added to the flow in the meantime
+    let mut edited = spec;
+    edited.paths.get_mut("/site/feed").unwrap().get.as_mut().unwrap().mock_data = Some(MockDataConfig {
+        description: "Hand-written".to_string(),
+        schema: None,
+        examples: Some(vec![r#"{"items": []}"#.to_string()]),
+    });
+    edited.paths.remove("/site/catalog");
+    edited.save(&path).unwrap();
+
+    command.generate_flow(&api_map, &dir, "site").await.unwrap();
+    let merged = OpenAPISpec::load(&path).unwrap();
+    let feed = merged.paths["/site/feed"].get.as_ref().unwrap();
+    assert_eq!(feed.mock_data.as_ref().unwrap().description, "Hand-written");
+    assert!(merged.paths.contains_key("/site/catalog"));
+    assert_eq!(merged.servers.len(), 1);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
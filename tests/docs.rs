@@ -0,0 +1,109 @@
+use nuts::config::Config;
+use nuts::flows::{docs, CollectionManager, OpenAPISpec};
+
+const FLOW: &str = r#"openapi: 3.0.3
+info:
+  title: shop
+  version: 2.0.0
+  description: Users & orders <internal>
+servers:
+  - url: https://api.example.com
+    description: Production
+paths:
+  /users/{id}:
+    get:
+      summary: Fetch a user
+      description: Returns one user by id.
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: integer
+            format: int64
+        - name: X-Tenant
+          in: header
+          description: Tenant slug
+      responses:
+        "404":
+          description: No such user
+        "200":
+          description: The user
+          content:
+            application/json:
+              schema:
+                type: object
+                properties:
+                  id:
+                    type: integer
+                    example: 42
+      mock_data:
+        description: a user
+        examples:
+          - '{"id": 1, "name": "Ada"}'
+    delete:
+      summary: Delete a user
+  /users:
+    post:
+      summary: Create a user
+      requestBody:
+        required: true
+        content:
+          application/json:
+            example:
+              name: O'Brien
+      responses:
+        "201":
+          description: created
+"#;
+
+#[test]
+fn renders_the_index_like_the_golden_file() {
+    let spec: OpenAPISpec = serde_yaml::from_str(FLOW).unwrap();
+    let pages = docs::render(&spec).unwrap();
+    let names: Vec<&str> = pages.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["index.html", "post-users.html", "get-users-id.html", "delete-users-id.html"]);
+    assert_eq!(pages[0].1, include_str!("fixtures/docs/index.html"));
+}
+
+#[test]
+fn endpoint_pages_show_parameters_examples_and_mock_data() {
+    let spec: OpenAPISpec = serde_yaml::from_str(FLOW).unwrap();
+    let pages = docs::render(&spec).unwrap();
+    let get = &pages.iter().find(|(name, _)| name == "get-users-id.html").unwrap().1;
+    assert!(get.contains(r#"<a class="back" href="index.html">"#));
+    assert!(get.contains("<p>Returns one user by id.</p>"));
+    assert!(get.contains(r#"<td><code>id</code> <span class="required">required</span></td><td>path</td><td>integer (int64)</td>"#), "{}", get);
+    assert!(get.contains("<td>Tenant slug</td>"));
+    // Responses by status, the example built from the schema
+    let ok = get.find(r#"<span class="status">200</span>"#).unwrap();
+    assert!(ok < get.find(r#"<span class="status">404</span>"#).unwrap());
+    assert!(get[ok..].contains("<pre>{\n  &quot;id&quot;: 42\n}</pre>"), "{}", get);
+    assert!(get.contains("<h2>Mock examples</h2>\n<p class=\"muted\">a user</p>"));
+    assert!(get.contains("&quot;name&quot;: &quot;Ada&quot;"));
+
+    let post = &pages.iter().find(|(name, _)| name == "post-users.html").unwrap().1;
+    assert!(post.contains(r#"<h2>Request body <span class="required">required</span></h2>"#));
+    assert!(post.contains("<h3>application/json</h3>\n<pre>{\n  &quot;name&quot;: &quot;O&#x27;Brien&quot;\n}</pre>"), "{}", post);
+    // Nothing is fetched from anywhere
+    assert!(pages.iter().all(|(_, html)| !html.contains("src=") && !html.contains("http://") && !html.contains("<link")));
+}
+
+#[tokio::test]
+async fn flow_docs_writes_the_site_without_an_ai_key() {
+    let dir = std::env::temp_dir().join(format!("nuts-docs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("shop.yaml"), FLOW).unwrap();
+    let manager = CollectionManager::new(dir.clone(), Config::default());
+
+    let out = dir.join("site");
+    manager.generate_docs("shop", &out).await.unwrap();
+    let mut files: Vec<String> = std::fs::read_dir(&out).unwrap().map(|entry| entry.unwrap().file_name().to_string_lossy().to_string()).collect();
+    files.sort();
+    assert_eq!(files, ["delete-users-id.html", "get-users-id.html", "index.html", "post-users.html"]);
+    // Without a key the pages say what the spec says
+    let delete = std::fs::read_to_string(out.join("delete-users-id.html")).unwrap();
+    assert!(delete.contains(r#"<p class="summary">Delete a user</p>"#) && !delete.contains("<p></p>"));
+    assert_eq!(std::fs::read_to_string(dir.join("shop.yaml")).unwrap(), FLOW);
+    let _ = std::fs::remove_dir_all(dir);
+}
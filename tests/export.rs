@@ -0,0 +1,89 @@
+use nuts::config::Config;
+use nuts::flows::{export, CollectionManager, OpenAPISpec};
+
+const FLOW: &str = r#"openapi: 3.0.3
+info:
+  title: shop
+  version: 2.0.0
+servers:
+  - url: https://api.example.com/
+paths:
+  /users/{id}:
+    get:
+      summary: Fetch a user
+      parameters:
+        - name: id
+          in: path
+          required: true
+          example: 42
+        - name: X-Tenant
+          in: header
+          example: acme
+      mock_data:
+        description: a user
+        examples:
+          - '{"id": 1}'
+  /users:
+    post:
+      summary: Create a user
+      requestBody:
+        content:
+          application/json:
+            example:
+              name: O'Brien
+      responses:
+        "201":
+          description: created
+"#;
+
+fn write_flow(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("nuts-export-{}-{}", std::process::id(), name));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("shop.yaml"), FLOW).unwrap();
+    dir
+}
+
+#[test]
+fn openapi_json_round_trips() {
+    let spec: OpenAPISpec = serde_yaml::from_str(FLOW).unwrap();
+    let json = export::to_openapi_json(&spec).unwrap();
+
+    let raw: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(raw["openapi"], "3.0.3");
+    assert!(raw["paths"]["/users/{id}"]["get"].get("mock_data").is_none());
+    assert_eq!(raw["paths"]["/users/{id}"]["get"]["x-mock-data"]["examples"][0], r#"{"id": 1}"#);
+
+    let back: OpenAPISpec = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_value(&back).unwrap(), serde_json::to_value(&spec).unwrap());
+    let get = back.paths["/users/{id}"].get.as_ref().unwrap();
+    assert_eq!(get.mock_data.as_ref().unwrap().description, "a user");
+    assert_eq!(back.paths["/users"].post.as_ref().unwrap().responses["201"].description, "created");
+}
+
+#[test]
+fn curl_script_covers_every_operation() {
+    let dir = write_flow("curl");
+    let manager = CollectionManager::new(dir.clone(), Config::default());
+    let out = dir.join("out").join("smoke.sh");
+
+    manager.export_flow("shop", "curl", &out).unwrap();
+    let script = std::fs::read_to_string(&out).unwrap();
+
+    assert!(script.starts_with("#!/bin/sh\n"));
+    assert!(script.contains("\nset -e\n"));
+    assert!(script.contains("curl -sS -f -X GET 'https://api.example.com/users/42' \\\n  -H 'X-Tenant: acme'"));
+    assert!(script.contains(
+        "curl -sS -f -X POST 'https://api.example.com/users' \\\n  -H 'Content-Type: application/json' \\\n  -d '{\"name\":\"O'\\''Brien\"}'"
+    ));
+    assert!(std::process::Command::new("sh").arg("-n").arg(&out).status().unwrap().success());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&out).unwrap().permissions().mode() & 0o111, 0o111);
+    }
+
+    manager.export_flow("shop", "openapi-json", &dir.join("api.json")).unwrap();
+    assert!(OpenAPISpec::load(&dir.join("api.json")).is_ok());
+    assert!(manager.export_flow("shop", "har", &dir.join("x.har")).is_err());
+    let _ = std::fs::remove_dir_all(dir);
+}
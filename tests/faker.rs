@@ -0,0 +1,57 @@
+use nuts::commands::generate::GenerateOptions;
+use nuts::services::faker::Faker;
+use serde_json::Value;
+
+fn keys(record: &Value) -> Vec<&str> {
+    record.as_object().unwrap().keys().map(String::as_str).collect()
+}
+
+#[test]
+fn same_seed_same_records() {
+    for data_type in ["users", "products", "orders", "widgets"] {
+        let first = Faker::new(Some(42)).records(data_type, 20);
+        assert_eq!(first, Faker::new(Some(42)).records(data_type, 20), "{}", data_type);
+        assert_ne!(first, Faker::new(Some(43)).records(data_type, 20), "{}", data_type);
+    }
+    // A longer run starts with the records of a shorter one
+    assert_eq!(Faker::new(Some(7)).records("users", 3)[..], Faker::new(Some(7)).records("users", 10)[..3]);
+}
+
+#[test]
+fn records_have_the_fields_the_ai_is_asked_for() {
+    let users = Faker::new(Some(1)).records("users", 50);
+    assert_eq!(keys(&users[0]), ["address", "age", "email", "id", "name", "phone", "registration_date"]);
+    for (i, user) in users.iter().enumerate() {
+        assert_eq!(user["id"], i as u64 + 1);
+        let email = user["email"].as_str().unwrap();
+        let (local, domain) = email.split_once('@').unwrap();
+        assert!(!local.is_empty() && local.chars().all(|c| c.is_ascii_alphanumeric() || c == '.'), "{}", email);
+        assert!(domain.contains('.'), "{}", email);
+        assert!((18..=90).contains(&user["age"].as_u64().unwrap()));
+        assert!(chrono::DateTime::parse_from_rfc3339(user["registration_date"].as_str().unwrap()).is_ok());
+        assert!(user["address"]["city"].is_string());
+    }
+
+    let product = &Faker::new(Some(1)).records("product", 1)[0];
+    assert_eq!(keys(product), ["category", "created_at", "description", "id", "in_stock", "name", "price"]);
+    assert!(product["price"].as_f64().unwrap() > 0.0 && product["in_stock"].is_boolean());
+
+    for order in Faker::new(Some(1)).records("Orders", 20) {
+        assert_eq!(keys(&order), ["id", "order_date", "products", "status", "total", "user_id"]);
+        let lines = order["products"].as_array().unwrap();
+        assert!(!lines.is_empty());
+        let total: f64 = lines.iter().map(|l| l["unit_price"].as_f64().unwrap() * l["quantity"].as_f64().unwrap()).sum();
+        assert!((total - order["total"].as_f64().unwrap()).abs() < 0.005, "{}", order);
+    }
+
+    assert_eq!(keys(&Faker::new(None).records("widgets", 1)[0]), ["created_at", "description", "id", "name"]);
+}
+
+#[test]
+fn seed_implies_local() {
+    let options = GenerateOptions::from_args(&["generate", "users", "--seed", "42"]).unwrap();
+    assert_eq!((options.seed, options.local), (Some(42), true));
+    let options = GenerateOptions::from_args(&["generate", "users", "10", "--local", "--post", "http://x"]).unwrap();
+    assert_eq!((options.count, options.local, options.seed), (10, true, None));
+    assert!(GenerateOptions::from_args(&["generate", "users", "--seed", "x"]).is_err());
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::commands::call::CallCommand;
+use nuts::ai::{AiAnswer, AiClient, AiError, AiMessage};
+use nuts::commands::generate::{ask_for_records, parse_records, records_prompt, GenerateCommand, GenerateOptions, RecordShape};
+use nuts::config::Config;
+use nuts::flows::{CollectionManager, OpenAPISpec};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[test]
+fn parses_options_and_strict_record_arrays() {
+    let options = GenerateOptions::from_args(&["generate", "users", "3", "--post", "http://x/users", "--delay", "250ms", "--concurrency", "2", "--into-flow", "shop:/users"]).unwrap();
+    assert_eq!(options.count, 3);
+    assert_eq!(options.post.as_deref(), Some("http://x/users"));
+    assert_eq!((options.delay, options.concurrency), (Duration::from_millis(250), 2));
+    assert_eq!(options.into_flow, Some(("shop".to_string(), "/users".to_string())));
+
+    let defaults = GenerateOptions::from_args(&["generate", "orders", "--into-flow", "shop:/orders/{id}"]).unwrap();
+    assert_eq!((defaults.count, defaults.concurrency, defaults.delay), (5, 1, Duration::ZERO));
+    assert_eq!(defaults.into_flow.unwrap().1, "/orders/{id}");
+    for bad in [&["generate", "users", "0"][..], &["generate", "users", "--into-flow", "shop"], &["generate", "users", "--concurrency", "0"], &["generate", "users", "--post"]] {
+        assert!(GenerateOptions::from_args(bad).is_err(), "{:?}", bad);
+    }
+
+    assert_eq!(parse_records("\n [{\"id\": 1}, {\"id\": 2}] \n").unwrap(), [json!({ "id": 1 }), json!({ "id": 2 })]);
+    assert_eq!(parse_records("{\"id\": 1}").unwrap_err(), "expected a JSON array, got object");
+    assert!(parse_records("Here are your users: [{\"id\": 1}]").unwrap_err().starts_with("not valid JSON"));
+    assert!(parse_records("[{\"id\": 1},]").is_err());
+}
+
+#[tokio::test]
+async fn posts_each_record_and_counts_failures() {
+    let server = FixtureServer::start().await;
+    let records: Vec<Value> = (0..4).map(|i| json!({ "id": i, "name": format!("user {}", i) })).collect();
+    let generate = GenerateCommand::new(Config::default()).with_call_command(CallCommand::new());
+
+    let options = GenerateOptions::from_args(&["generate", "users", "--delay", "200ms", "--concurrency", "2"]).unwrap();
+    let url = server.url("/echo/users");
+    let started = Instant::now();
+    let results = generate.post_records(&records, &url, &options).await.unwrap();
+    // Two slots, each waiting 200ms once between its two records
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(200) && elapsed < Duration::from_millis(1000), "{:?}", elapsed);
+    assert_eq!(results.iter().map(|r| r.index).collect::<Vec<_>>(), [1, 2, 3, 4]);
+    assert!(results.iter().all(|r| r.status == Some(200)));
+    assert_eq!(results[2].body.as_ref().unwrap()["method"], "POST");
+    assert_eq!(results[2].body.as_ref().unwrap()["parsed"], records[2]);
+    assert_eq!(results[2].body.as_ref().unwrap()["headers"]["content-type"], "application/json");
+
+    let options = GenerateOptions::from_args(&["generate", "users"]).unwrap();
+    let results = generate.post_records(&records[..2], &server.url("/status/422"), &options).await.unwrap();
+    assert!(results.iter().all(|r| r.is_failure() && r.status == Some(422)));
+    let results = generate.post_records(&records[..1], "http://127.0.0.1:9/users", &options).await.unwrap();
+    assert!(results[0].status.is_none() && results[0].error.is_some());
+}
+
+#[test]
+fn stores_records_as_mock_examples_of_the_flow_operation() {
+    let dir = std::env::temp_dir().join(format!("nuts-generate-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("shop.yaml"), "openapi: 3.0.0\ninfo: { title: shop, version: 1.0.0 }\npaths:\n  /users:\n    post: {}\n    get:\n      mock_data:\n        description: old\n        examples: ['{\"id\": 0}']\n  /orders:\n    post: {}\n").unwrap();
+    let generate = GenerateCommand::new(Config::default()).with_flows_dir(dir.clone());
+    let records = [json!({ "id": 1, "email": "a@x.io" }), json!({ "id": 2, "email": "b@x.io" })];
+
+    assert_eq!(generate.store_in_flow("shop", "/users", &records, "users").unwrap(), "GET /users");
+    assert_eq!(generate.store_in_flow("shop", "/orders", &records[..1], "orders").unwrap(), "POST /orders");
+
+    let spec = OpenAPISpec::load(&dir.join("shop.yaml")).unwrap();
+    let examples = |path: &str, method: &str| {
+        let item = &spec.paths[path];
+        let operation = item.operations().find(|(m, _)| *m == method).unwrap().1;
+        operation.mock_data.as_ref().and_then(|m| m.examples.clone())
+    };
+    let stored: Vec<Value> = examples("/users", "GET").unwrap().iter().map(|e| serde_json::from_str(e).unwrap()).collect();
+    assert_eq!(stored, records);
+    assert_eq!(examples("/users", "POST"), None);
+    assert_eq!(examples("/orders", "POST").unwrap().len(), 1);
+
+    assert_eq!(generate.store_in_flow("shop", "/nope", &records, "users").unwrap_err().to_string(), "No path /nope in flow 'shop'");
+    assert!(generate.store_in_flow("missing", "/users", &records, "users").unwrap_err().to_string().starts_with("Could not load flow 'missing'"));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn parses_generate_for_an_operation() {
+    let options = GenerateOptions::from_args(&["generate", "for", "shop:post", "/users/{id}/orders", "--count", "3", "--save"]).unwrap();
+    assert_eq!(options.operation, Some(("shop".to_string(), "POST".to_string(), "/users/{id}/orders".to_string())));
+    assert_eq!((options.data_type.as_str(), options.count, options.save), ("orders", 3, true));
+    assert_eq!(GenerateOptions::from_args(&["generate", "users", "--count", "7"]).unwrap().count, 7);
+
+    for bad in [
+        &["generate", "for", "shop", "/users"][..],
+        &["generate", "for", "shop:POST"],
+        &["generate", "for", "shop:POST", "users"],
+        &["generate", "for", "shop:POST", "/users", "--local"],
+        &["generate", "for", "shop:POST", "/users", "--save", "--into-flow", "shop:/users"],
+        &["generate", "users", "--save"],
+    ] {
+        assert!(GenerateOptions::from_args(bad).is_err(), "{:?}", bad);
+    }
+}
+
+/// Answers with `answers` in turn and keeps every conversation it was sent.
+struct ScriptedAi {
+    answers: Mutex<Vec<&'static str>>,
+    seen: Mutex<Vec<Vec<AiMessage>>>,
+}
+
+#[async_trait::async_trait]
+impl AiClient for ScriptedAi {
+    async fn answer(&self, messages: &[AiMessage]) -> Result<AiAnswer, AiError> {
+        self.seen.lock().unwrap().push(messages.to_vec());
+        Ok(AiAnswer { text: self.answers.lock().unwrap().remove(0).to_string(), usage: None })
+    }
+}
+
+const SHOP: &str = r#"openapi: 3.0.0
+info: { title: shop, version: 1.0.0 }
+paths:
+  /orders:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema: { $ref: '#/components/schemas/Order' }
+            example: { sku: A-1, quantity: 2 }
+components:
+  schemas:
+    Order:
+      type: object
+      required: [sku, quantity]
+      properties:
+        sku: { type: string }
+        quantity: { type: integer }
+"#;
+
+#[tokio::test]
+async fn schema_guided_records_are_validated_and_fixed_once() {
+    let spec: OpenAPISpec = serde_yaml::from_str(SHOP).unwrap();
+    let operation = spec.paths["/orders"].post.as_ref().unwrap();
+    let shape = RecordShape::request(operation, spec.extras.get("components")).unwrap();
+    assert_eq!(shape.examples, [json!({ "sku": "A-1", "quantity": 2 })]);
+    assert_eq!(shape.violations(&[json!({ "sku": "B" }), json!({ "sku": 1, "quantity": 1 })]), [
+        "record 1: $.quantity: required property is missing",
+        "record 2: $.sku: expected string, got integer",
+    ]);
+    let prompt = records_prompt("JSON request bodies for POST /orders", 2, Some(&shape));
+    assert!(prompt.contains("#/components/schemas/Order") && prompt.contains("\"quantity\"") && prompt.contains(r#"{"quantity":2,"sku":"A-1"}"#), "{}", prompt);
+
+    let ai = ScriptedAi {
+        answers: Mutex::new(vec![r#"[{"sku": "B-2"}, {"sku": "C-3", "quantity": 1}]"#, r#"[{"sku": "B-2", "quantity": 4}, {"sku": "C-3", "quantity": 1}]"#]),
+        seen: Mutex::default(),
+    };
+    let records = ask_for_records(&ai, prompt.clone(), 2, Some(&shape)).await.unwrap();
+    assert_eq!(records[0], json!({ "sku": "B-2", "quantity": 4 }));
+    let seen = ai.seen.into_inner().unwrap();
+    assert_eq!(seen[1].len(), 3);
+    assert!(seen[1][2].text.contains("record 1: $.quantity: required property is missing"), "{}", seen[1][2].text);
+
+    let ai = ScriptedAi { answers: Mutex::new(vec![r#"[{"sku": 1}]"#, r#"[{"sku": 2}]"#]), seen: Mutex::default() };
+    let error = ask_for_records(&ai, prompt, 1, Some(&shape)).await.unwrap_err().to_string();
+    assert!(error.starts_with("The AI records still don't match the schema: record 1:"), "{}", error);
+}
+
+#[test]
+fn stores_records_for_a_chosen_method() {
+    let dir = std::env::temp_dir().join(format!("nuts-generate-method-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("shop.yaml"), SHOP.replace("    post:", "    get: {}\n    post:")).unwrap();
+    let manager = CollectionManager::new(dir.clone(), Config::default());
+    let records = [json!({ "sku": "A-1", "quantity": 1 })];
+
+    assert_eq!(manager.store_operation_examples("shop", Some("post"), "/orders", &records, "orders").unwrap(), "POST /orders");
+    let spec = OpenAPISpec::load(&dir.join("shop.yaml")).unwrap();
+    assert!(spec.paths["/orders"].get.as_ref().unwrap().mock_data.is_none());
+    assert_eq!(spec.paths["/orders"].post.as_ref().unwrap().mock_data.as_ref().unwrap().examples.as_ref().unwrap().len(), 1);
+    assert_eq!(manager.store_operation_examples("shop", Some("PUT"), "/orders", &records, "orders").unwrap_err().to_string(), "No PUT operation on /orders in flow 'shop'");
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,83 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::FixtureServer;
+use nuts::commands::call::{CallCommand, CallOptions};
+use nuts::hooks::HookConfig;
+use std::path::{Path, PathBuf};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("nuts-hooks-{}-{}", std::process::id(), name))
+}
+
+fn write_script(name: &str, script: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+    let path = temp_path(name);
+    std::fs::write(&path, script).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+fn options(url: String, output: &Path) -> CallOptions {
+    let mut options = CallOptions {
+        url,
+        output_file: Some(output.to_string_lossy().to_string()),
+        ..CallOptions::default()
+    };
+    options.headers.insert("X-Base".to_string(), "1".to_string());
+    options
+}
+
+#[tokio::test]
+async fn pre_request_hook_injects_signature_header() {
+    let server = FixtureServer::start().await;
+    let hook = write_script(
+        "sign.sh",
+        "#!/bin/sh\nsed 's/\"headers\":{/\"headers\":{\"X-Signature\":\"sig-123\",/'\n",
+    );
+    let output = temp_path("sign.out");
+
+    let call = CallCommand::new().with_hooks(HookConfig {
+        pre_request: Some(hook.to_string_lossy().to_string()),
+        post_response: None,
+    });
+    call.execute_with_options(options(server.url("/echo"), &output)).await.unwrap();
+
+    let echo: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(echo["headers"]["x-signature"], "sig-123");
+    assert_eq!(echo["headers"]["x-base"], "1");
+}
+
+#[tokio::test]
+async fn post_response_hook_receives_status_and_body() {
+    let server = FixtureServer::start().await;
+    let captured = temp_path("post.json");
+    let output = temp_path("post.out");
+
+    let call = CallCommand::new().with_hooks(HookConfig {
+        pre_request: None,
+        post_response: Some(format!("cat > {}", captured.display())),
+    });
+    call.execute_with_options(options(server.url("/status/201"), &output)).await.unwrap();
+
+    let payload: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&captured).unwrap()).unwrap();
+    assert_eq!(payload["response"]["status"], 201);
+    assert_eq!(payload["request"]["method"], "GET");
+    assert!(payload["response"]["body"].as_str().unwrap().contains("201"));
+}
+
+#[tokio::test]
+async fn failing_hook_fails_request_with_stderr() {
+    let server = FixtureServer::start().await;
+    let output = temp_path("fail.out");
+
+    let call = CallCommand::new().with_hooks(HookConfig {
+        pre_request: Some("echo 'missing signing key' >&2; exit 3".to_string()),
+        post_response: None,
+    });
+    let err = call.execute_with_options(options(server.url("/echo"), &output)).await.unwrap_err();
+
+    assert!(err.to_string().contains("missing signing key"));
+    assert_eq!(server.hits(), 0);
+}
@@ -0,0 +1,46 @@
+use nuts::logging::redact;
+
+#[test]
+fn masks_keys_tokens_and_credentials() {
+    assert_eq!(redact("key=sk-ant-api03-AbC_123 used", &[]), "key=[REDACTED] used");
+    assert_eq!(redact("using sk-ant-api03-AbC_123", &[]), "using [REDACTED]");
+
+    assert_eq!(redact("sent Bearer eyJhbGciOi.payload.sig to api", &[]), "sent Bearer [REDACTED] to api");
+    assert_eq!(redact("sent bearer eyJhbGciOi.payload.sig to api", &[]), "sent bearer [REDACTED] to api");
+    assert_eq!(redact("authorization: Basic dXNlcjpwYXNz done", &[]), "authorization: Basic [REDACTED] done");
+    assert_eq!(redact("Authorization: Bearer abc.def", &[]), "Authorization: Bearer [REDACTED]");
+
+    assert_eq!(redact(r#"{"api_key": "k-123456", "name": "Ann"}"#, &[]), r#"{"api_key": "[REDACTED]", "name": "Ann"}"#);
+    assert_eq!(redact("login user=ann password=hunter22&next=/", &[]), "login user=ann password=[REDACTED]&next=/");
+    assert_eq!(redact("X-Api-Key: abcdef", &[]), "X-Api-Key: [REDACTED]");
+    // A key name without a value is left alone
+    assert_eq!(redact("the password field is empty", &[]), "the password field is empty");
+}
+
+#[test]
+fn masks_explicit_secrets_anywhere() {
+    let secrets = vec!["tok-9f8e7d".to_string()];
+    assert_eq!(redact("url=https://x.io/?t=tok-9f8e7d&a=1", &secrets), "url=https://x.io/?t=[REDACTED]&a=1");
+    assert_eq!(redact("tok-9f8e7dtok-9f8e7d", &secrets), "[REDACTED][REDACTED]");
+}
+
+#[test]
+fn multibyte_neighbours_never_split_a_character() {
+    assert_eq!(redact("é password=ünïcødé€ done", &[]), "é password=[REDACTED] done");
+    assert_eq!(redact("日本 Bearer 秘密トークン 終わり", &[]), "日本 Bearer [REDACTED] 終わり");
+    assert_eq!(redact("€sk-ant-ключ€", &[]), "€[REDACTED]");
+    assert_eq!(redact("api_key:€", &[]), "api_key:[REDACTED]");
+    assert_eq!(redact(r#"{"password":"😀😀"}"#, &[]), r#"{"password":"[REDACTED]"}"#);
+    // Markers at the very end of the text
+    assert_eq!(redact("trailing Bearer ", &[]), "trailing Bearer ");
+    assert_eq!(redact("ends with password=", &[]), "ends with password=");
+    assert_eq!(redact("ünï sk-ant-", &[]), "ünï [REDACTED]");
+}
+
+proptest::proptest! {
+    #[test]
+    fn any_text_is_redacted_without_panicking(text in "(\\PC|Bearer |bearer |sk-ant-|password=|\"api_key\": |authorization: Basic ){0,16}") {
+        let cleaned = redact(&text, &["secret-value".to_string()]);
+        proptest::prop_assert!(!cleaned.contains("secret-value"));
+    }
+}
@@ -0,0 +1,376 @@
+use nuts::commands::mock::{MockOptions, MockRequest, MockServer, SchemaSampler, UnmatchedRequest};
+use nuts::flows::{OpenAPISpec, Schema};
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+const SPEC: &str = r#"openapi: 3.0.0
+info:
+  title: users
+  version: 1.0.0
+paths:
+  /users/{id}:
+    get:
+      mock_data:
+        description: a user
+        examples:
+          - '{"id": 1, "name": "Ada"}'
+    put:
+      mock_data:
+        description: updated user
+        examples:
+          - '{"id": 1, "name": "Grace"}'
+    delete:
+      mock_data:
+        description: deleted
+        examples:
+          - '{"deleted": true}'
+    patch:
+      mock_data:
+        description: patched user
+        examples:
+          - '{"id": 1, "patched": true}'
+    options:
+      mock_data:
+        description: allowed methods
+        examples:
+          - '{"methods": ["GET", "PUT"]}'
+    x-methods:
+      PURGE:
+        mock_data:
+          description: purged
+          examples:
+            - '{"purged": true}'
+  /orgs/{org}/members/{user_id}:
+    get:
+      mock_data:
+        description: membership
+        examples:
+          - '{"org": "x", "user_id": 0, "role": "admin"}'
+  /health:
+    get:
+      mock_data:
+        description: health
+        examples:
+          - '{"ok": true}'
+  /accounts/{id}:
+    get:
+      responses:
+        '200':
+          description: an account
+          content:
+            application/json:
+              schema:
+                type: object
+                properties:
+                  id: { type: integer }
+                  email: { type: string, format: email }
+                  tags: { type: array, items: { type: string } }
+                  plan: { type: string, enum: [free, pro] }
+  /empty:
+    get:
+      responses:
+        '204':
+          description: nothing to see
+"#;
+
+/// Serve the mock router on an ephemeral port and return its base URL.
+async fn start_mock() -> String {
+    start_mock_with(MockOptions::default()).await
+}
+
+async fn start_mock_with(options: MockOptions) -> String {
+    let spec: OpenAPISpec = serde_yaml::from_str(SPEC).unwrap();
+    let router = MockServer::new(spec, 0).with_options(options).router();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+async fn send(method: reqwest::Method, url: String) -> (u16, Value) {
+    let response = reqwest::Client::new().request(method, url).send().await.unwrap();
+    (response.status().as_u16(), response.json().await.unwrap())
+}
+
+#[tokio::test]
+async fn path_params_are_templated_into_examples() {
+    let base = start_mock().await;
+
+    let (status, body) = send(reqwest::Method::GET, format!("{}/users/42", base)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body, json!({ "id": 42, "name": "Ada" }));
+
+    let (_, body) = send(reqwest::Method::GET, format!("{}/orgs/acme/members/7", base)).await;
+    assert_eq!(body, json!({ "org": "acme", "user_id": 7, "role": "admin" }));
+
+    let (_, body) = send(reqwest::Method::GET, format!("{}/health", base)).await;
+    assert_eq!(body, json!({ "ok": true }));
+}
+
+#[tokio::test]
+async fn serves_every_method_in_the_spec() {
+    let base = start_mock().await;
+    let url = format!("{}/users/abc", base);
+
+    let (_, body) = send(reqwest::Method::PUT, url.clone()).await;
+    assert_eq!(body, json!({ "id": "abc", "name": "Grace" }));
+    let (_, body) = send(reqwest::Method::PATCH, url.clone()).await;
+    assert_eq!(body, json!({ "id": "abc", "patched": true }));
+    let (_, body) = send(reqwest::Method::DELETE, url.clone()).await;
+    assert_eq!(body, json!({ "deleted": true }));
+    let (_, body) = send(reqwest::Method::OPTIONS, url.clone()).await;
+    assert_eq!(body, json!({ "methods": ["GET", "PUT"] }));
+    let (_, body) = send(reqwest::Method::from_bytes(b"PURGE").unwrap(), url.clone()).await;
+    assert_eq!(body, json!({ "purged": true }));
+    let response = reqwest::Client::new().head(url.clone()).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let (status, _) = send(reqwest::Method::from_bytes(b"BAN").unwrap(), url.clone()).await;
+    assert_eq!(status, 405);
+
+    let response = reqwest::Client::new().post(url).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 405);
+}
+
+const USER_SCHEMA: &str = r#"
+type: object
+properties:
+  name: { type: string }
+  age: { type: integer }
+  score: { type: number }
+  active: { type: boolean }
+  verified: { type: boolean }
+  address:
+    properties:
+      city: { type: string }
+  tags:
+    type: array
+    items: { type: string }
+"#;
+
+#[test]
+fn sampler_is_deterministic_for_a_seed() {
+    let schema: Schema = serde_yaml::from_str(USER_SCHEMA).unwrap();
+
+    let first = SchemaSampler::new(7).sample(&schema);
+    assert_eq!(first, SchemaSampler::new(7).sample(&schema));
+
+    assert_eq!(first["address"], json!({ "city": "string-1" }));
+    assert_eq!(first["name"], "string-2");
+    assert_eq!(first["tags"], json!(["string-3", "string-4", "string-5"]));
+    assert_eq!((first["active"].clone(), first["verified"].clone()), (json!(true), json!(false)));
+    assert!((1..=100).contains(&first["age"].as_i64().unwrap()));
+    assert!(first["score"].is_f64());
+
+    assert_eq!(SchemaSampler::new(0).sample(&Schema::default()), json!({}));
+}
+
+#[tokio::test]
+async fn operations_without_examples_answer_from_the_schema() {
+    let base = start_mock().await;
+
+    let (status, body) = send(reqwest::Method::GET, format!("{}/accounts/9", base)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["id"], 9);
+    assert_eq!(body["email"], "user1@example.com");
+    assert_eq!(body["plan"], "free");
+    assert_eq!(body["tags"].as_array().unwrap().len(), 3);
+    let (_, again) = send(reqwest::Method::GET, format!("{}/accounts/9", base)).await;
+    assert_eq!(again, body);
+
+    let (status, body) = send(reqwest::Method::GET, format!("{}/empty", base)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body, json!({}));
+}
+
+#[test]
+fn mock_options_parse_from_flags() {
+    let args: Vec<String> = ["--latency", "200ms", "--jitter", "0.1s", "--error-rate", "25%", "--error-status", "429"]
+        .iter().map(|s| s.to_string()).collect();
+    let options = MockOptions::from_args(&args).unwrap();
+    assert_eq!(options.latency, Duration::from_millis(200));
+    assert_eq!(options.jitter, Duration::from_millis(100));
+    assert_eq!(options.error_rate, 0.25);
+    assert_eq!(options.error_status, 429);
+
+    assert!(MockOptions::from_args(&["--error-rate".to_string(), "2".to_string()]).is_err());
+    assert!(MockOptions::from_args(&["--latency".to_string()]).is_err());
+    assert!(MockOptions::from_args(&["--bogus".to_string(), "1".to_string()]).is_err());
+
+    let args: Vec<String> = ["--no-admin", "--record-unmatched", "unmatched.jsonl", "--stateful"].iter().map(|s| s.to_string()).collect();
+    let options = MockOptions::from_args(&args).unwrap();
+    assert!(!options.admin && options.stateful);
+    assert_eq!(options.record_unmatched, Some("unmatched.jsonl".into()));
+    assert!(!options.simulates_faults());
+}
+
+#[tokio::test]
+async fn status_header_forces_a_response_status() {
+    let base = start_mock().await;
+    let client = reqwest::Client::new();
+
+    let response = client.get(format!("{}/users/1", base)).header("X-Nuts-Mock-Status", "404").send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 404);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["simulated"], true);
+
+    // Success statuses keep the example body
+    let response = client.get(format!("{}/users/1", base)).header("X-Nuts-Mock-Status", "202").send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 202);
+    assert_eq!(response.json::<Value>().await.unwrap()["name"], "Ada");
+}
+
+#[tokio::test]
+async fn simulates_latency_and_errors() {
+    let base = start_mock_with(MockOptions {
+        latency: Duration::from_millis(150),
+        error_rate: 1.0,
+        error_status: 503,
+        ..Default::default()
+    })
+    .await;
+
+    let start = Instant::now();
+    let (status, body) = send(reqwest::Method::GET, format!("{}/health", base)).await;
+    assert!(start.elapsed() >= Duration::from_millis(150));
+    assert_eq!(status, 503);
+    assert_eq!(body["status"], 503);
+
+    // The header still wins over the error rate
+    let response = reqwest::Client::new().get(format!("{}/health", base)).header("X-Nuts-Mock-Status", "200").send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn reloads_the_spec_file_when_it_changes() {
+    let path = std::env::temp_dir().join(format!("nuts-mock-reload-{}.yaml", std::process::id()));
+    std::fs::write(&path, SPEC).unwrap();
+    let server = MockServer::new(OpenAPISpec::load(&path).unwrap(), 0).with_spec_file(&path);
+    let router = server.router();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    let watcher = server.watch().unwrap();
+
+    let (status, _) = send(reqwest::Method::GET, format!("{}/teams", base)).await;
+    assert_eq!(status, 404);
+
+    let edited = SPEC.replace(r#"'{"ok": true}'"#, r#"'{"ok": false}'"#)
+        + "  /teams:\n    get:\n      mock_data:\n        description: teams\n        examples:\n          - '[\"core\"]'\n";
+    std::fs::write(&path, edited).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while send(reqwest::Method::GET, format!("{}/teams", base)).await.0 == 404 && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(send(reqwest::Method::GET, format!("{}/teams", base)).await, (200, json!(["core"])));
+    assert_eq!(send(reqwest::Method::GET, format!("{}/health", base)).await.1, json!({ "ok": false }));
+
+    // A broken edit keeps what was served before
+    std::fs::write(&path, "paths: [").unwrap();
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    assert_eq!(send(reqwest::Method::GET, format!("{}/teams", base)).await.0, 200);
+
+    watcher.abort();
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn lists_recent_requests_and_records_unmatched_ones() {
+    let file = std::env::temp_dir().join(format!("nuts-mock-unmatched-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&file);
+    let base = start_mock_with(MockOptions { record_unmatched: Some(file.clone()), ..Default::default() }).await;
+    let client = reqwest::Client::new();
+
+    send(reqwest::Method::GET, format!("{}/users/7", base)).await;
+    let response = client.post(format!("{}/orders?dry=1", base)).header("X-Trace", "abc").body(r#"{"sku":"A1"}"#).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 404);
+    send(reqwest::Method::POST, format!("{}/health", base)).await;
+
+    let recent: Vec<MockRequest> = client.get(format!("{}/__nuts/requests", base)).send().await.unwrap().json().await.unwrap();
+    let summary: Vec<_> = recent.iter().map(|r| (r.method.as_str(), r.path.as_str(), r.status, r.matched, r.example)).collect();
+    assert_eq!(summary, [
+        ("GET", "/users/7", 200, true, Some(0)),
+        ("POST", "/orders?dry=1", 404, false, None),
+        ("POST", "/health", 405, false, None),
+    ]);
+
+    // Only the unknown path is recorded, not the unknown method
+    let recorded: Vec<UnmatchedRequest> = std::fs::read_to_string(&file).unwrap().lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let _ = std::fs::remove_file(&file);
+    assert_eq!(recorded.len(), 1);
+    assert_eq!((recorded[0].method.as_str(), recorded[0].path.as_str(), recorded[0].query.as_deref()), ("POST", "/orders", Some("dry=1")));
+    assert_eq!(recorded[0].headers["x-trace"], "abc");
+    assert_eq!(recorded[0].body, r#"{"sku":"A1"}"#);
+
+    let base = start_mock_with(MockOptions { admin: false, ..Default::default() }).await;
+    let (status, _) = send(reqwest::Method::GET, format!("{}/__nuts/requests", base)).await;
+    assert_eq!(status, 404);
+}
+
+const CRUD_SPEC: &str = r#"openapi: 3.0.0
+info:
+  title: shop
+  version: 1.0.0
+paths:
+  /products:
+    get:
+      mock_data:
+        description: products
+        examples:
+          - '[{"id": 1, "name": "Desk"}, {"id": 2, "name": "Lamp"}]'
+    post:
+      mock_data:
+        description: created
+        examples:
+          - '{"id": 99}'
+  /products/{product_id}:
+    get:
+      mock_data:
+        description: a product
+        examples:
+          - '{"id": 3, "name": "Chair"}'
+    patch: {}
+    delete: {}
+  /health:
+    get:
+      mock_data:
+        description: health
+        examples:
+          - '{"ok": true}'
+"#;
+
+#[tokio::test]
+async fn stateful_mode_creates_reads_updates_and_deletes() {
+    let spec: OpenAPISpec = serde_yaml::from_str(CRUD_SPEC).unwrap();
+    let router = MockServer::new(spec, 0).with_options(MockOptions { stateful: true, ..Default::default() }).router();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    let client = reqwest::Client::new();
+
+    // Seeded from the GET examples of the collection and the item
+    let (_, listed) = send(reqwest::Method::GET, format!("{}/products", base)).await;
+    assert_eq!(listed.as_array().unwrap().len(), 3);
+
+    let response = client.post(format!("{}/products", base)).json(&json!({ "name": "Shelf" })).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 201);
+    assert_eq!(response.json::<Value>().await.unwrap(), json!({ "id": 4, "name": "Shelf" }));
+    assert_eq!(send(reqwest::Method::GET, format!("{}/products/4", base)).await, (200, json!({ "id": 4, "name": "Shelf" })));
+
+    let response = client.patch(format!("{}/products/4", base)).json(&json!({ "price": 20, "id": 7 })).send().await.unwrap();
+    assert_eq!(response.json::<Value>().await.unwrap(), json!({ "id": 4, "name": "Shelf", "price": 20 }));
+
+    let (_, page) = send(reqwest::Method::GET, format!("{}/products?offset=2&limit=5", base)).await;
+    assert_eq!(page, json!([{ "id": 3, "name": "Chair" }, { "id": 4, "name": "Shelf", "price": 20 }]));
+
+    let response = client.delete(format!("{}/products/4", base)).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 204);
+    let (status, _) = send(reqwest::Method::GET, format!("{}/products/4", base)).await;
+    assert_eq!(status, 404);
+
+    let response = client.post(format!("{}/products", base)).body("not json").send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 400);
+    // Paths that aren't collections keep answering with their examples
+    assert_eq!(send(reqwest::Method::GET, format!("{}/health", base)).await.1, json!({ "ok": true }));
+}
@@ -0,0 +1,114 @@
+//! Offline mode: no config file, no Anthropic key. The non-AI core of every command must still run.
+mod common;
+
+use common::FixtureServer;
+use nuts::commands::generate::{GenerateCommand, GenerateOptions};
+use nuts::commands::perf::PerfCommand;
+use nuts::commands::security::{check_responses, SecurityCommand};
+use nuts::completer::closest_command;
+use nuts::config::Config;
+use nuts::flows::manager::CollectionManager;
+use nuts::flows::OpenAPISpec;
+use std::path::PathBuf;
+use std::sync::Once;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Point HOME at an empty directory so no `~/.nuts/config.json` is found.
+fn isolated_home() -> PathBuf {
+    static INIT: Once = Once::new();
+    let home = std::env::temp_dir().join(format!("nuts-offline-home-{}", std::process::id()));
+    INIT.call_once(|| {
+        std::fs::create_dir_all(&home).unwrap();
+        std::env::set_var("HOME", &home);
+    });
+    home
+}
+
+fn offline_config() -> Config {
+    isolated_home();
+    Config::load().unwrap()
+}
+
+#[test]
+fn missing_config_file_means_offline() {
+    let config = offline_config();
+    assert!(config.anthropic_api_key.is_none());
+    assert!(config.is_offline());
+
+    let blank = Config { anthropic_api_key: Some("  ".to_string()), ..Config::default() };
+    assert!(blank.is_offline());
+}
+
+#[tokio::test]
+async fn call_and_perf_run_without_a_key() {
+    let server = FixtureServer::start().await;
+    let config = offline_config();
+
+    let echo = common::call_json(&["GET", &server.url("/echo")]).await.unwrap();
+    assert_eq!(echo["method"], "GET");
+
+    let perf = PerfCommand::new(&config);
+    perf.run(&server.url("/echo"), 2, Duration::from_millis(300), "GET", None, &HashMap::new())
+        .await
+        .unwrap();
+    assert!(perf.summary().total_requests > 0);
+}
+
+#[tokio::test]
+async fn security_scan_runs_local_checks_offline() {
+    let server = FixtureServer::start().await;
+    let security = SecurityCommand::new(offline_config());
+
+    security.execute(&["security".to_string(), server.url("/echo")]).await.unwrap();
+
+    let responses = security.collect_responses(&server.url("/echo")).await.unwrap();
+    let findings = check_responses(&responses);
+    assert!(findings.iter().any(|finding| finding.id == "missing-hsts"));
+    assert!(findings.iter().any(|finding| finding.title.contains("plain HTTP")));
+}
+
+#[tokio::test]
+async fn flows_work_offline() {
+    let server = FixtureServer::start().await;
+    let home = isolated_home();
+    let dir = home.join("flows-offline");
+    std::fs::create_dir_all(&dir).unwrap();
+    let manager = CollectionManager::new(dir.clone(), offline_config());
+
+    manager.create_collection("demo").unwrap();
+    let path = dir.join("demo.yaml");
+    let mut spec = OpenAPISpec::load(&path).unwrap();
+    spec.servers[0].url = server.url("");
+    spec.save(&path).unwrap();
+
+    manager.add_endpoint("demo", "GET", "/echo").await.unwrap();
+    manager.run_endpoint("demo", "/echo", &[]).await.unwrap();
+    manager.generate_openapi("demo", "yaml").await.unwrap();
+    assert!(server.hits() >= 1);
+}
+
+#[tokio::test]
+async fn ai_only_commands_explain_offline_mode() {
+    let generate = GenerateCommand::new(offline_config());
+    let error = generate.generate_for_endpoint("shop", "POST", "/users", 3).await.unwrap_err();
+    assert!(error.to_string().contains("offline mode"));
+}
+
+#[tokio::test]
+async fn generate_falls_back_to_local_records() {
+    let server = FixtureServer::start().await;
+    let generate = GenerateCommand::new(offline_config());
+    let url = server.url("/echo/users");
+    let options = GenerateOptions::from_args(&["generate", "users", "3", "--post", &url]).unwrap();
+    generate.execute(&options).await.unwrap();
+    assert_eq!(server.hits(), 3);
+}
+
+#[test]
+fn unknown_commands_get_local_suggestions() {
+    assert_eq!(closest_command("cal GET /x"), Some("call"));
+    assert_eq!(closest_command("secrity"), Some("security"));
+    assert_eq!(closest_command("prf"), Some("perf"));
+    assert_eq!(closest_command("zzzzzz"), None);
+}
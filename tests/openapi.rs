@@ -0,0 +1,164 @@
+use nuts::flows::{Budget, OpenAPISpec, SchemaType};
+use std::path::PathBuf;
+
+fn fixture() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/openapi-3.1.yaml")
+}
+
+fn as_json(path: &PathBuf) -> serde_json::Value {
+    let yaml: serde_yaml::Value = serde_yaml::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    serde_json::to_value(yaml).unwrap()
+}
+
+#[test]
+fn loads_openapi_31_constructs() {
+    let spec = OpenAPISpec::load(&fixture()).unwrap();
+
+    assert_eq!(spec.openapi, "3.1.0");
+    assert!(spec.extras.contains_key("webhooks"));
+    assert!(spec.extras.contains_key("components"));
+    assert_eq!(spec.extras["x-owner"], "platform-team");
+
+    let items = &spec.paths["/items"];
+    let list = items.get.as_ref().unwrap();
+    assert_eq!(list.extras["operationId"], "list_items");
+    let params = list.parameters.as_ref().unwrap();
+    assert_eq!(params[0].schema.enum_values.as_ref().unwrap().len(), 2);
+    assert_eq!(params[1].extras["$ref"], "#/components/parameters/Limit");
+    assert!(items.head.is_some() && !items.extras.contains_key("head"));
+
+    let item = &spec.extras["components"]["schemas"]["Item"];
+    let note: nuts::flows::Schema = serde_json::from_value(item["properties"]["note"].clone()).unwrap();
+    assert_eq!(note.schema_type, SchemaType::Multiple(vec!["string".into(), "null".into()]));
+    assert_eq!(note.schema_type.primary(), "string");
+    assert!(note.is_nullable());
+
+    let name: nuts::flows::Schema = serde_json::from_value(item["properties"]["name"].clone()).unwrap();
+    assert_eq!(name.examples.unwrap().len(), 2);
+    let id: nuts::flows::Schema = serde_json::from_value(item["properties"]["id"].clone()).unwrap();
+    assert_eq!(id.const_value, Some(serde_json::json!(1)));
+    let legacy: nuts::flows::Schema = serde_json::from_value(item["properties"]["legacy"].clone()).unwrap();
+    assert!(legacy.is_nullable());
+}
+
+#[test]
+fn round_trips_without_losing_fields() {
+    let spec = OpenAPISpec::load(&fixture()).unwrap();
+    let out = std::env::temp_dir().join(format!("nuts-openapi-{}.yaml", std::process::id()));
+    spec.save(&out).unwrap();
+
+    let original = as_json(&fixture());
+    let saved = as_json(&out);
+    let _ = std::fs::remove_file(&out);
+
+    assert_eq!(saved["openapi"], "3.1.0");
+    assert_eq!(original, saved);
+}
+
+#[test]
+fn loads_older_field_spellings_and_saves_the_openapi_ones() {
+    let yaml = r#"openapi: 3.0.0
+info: { title: Old, version: "1" }
+paths:
+  /users:
+    post:
+      parameters:
+        - { name: X-Trace, in: header, required: null }
+        - { name: tenant, in: query, required: true }
+      request_body:
+        required: true
+        content:
+          application/json: { example: { name: Ann } }
+    x-mock-data: { description: Users, schema: null, examples: null }
+"#;
+    let spec: OpenAPISpec = serde_yaml::from_str(yaml).unwrap();
+    let users = &spec.paths["/users"];
+    let post = users.post.as_ref().unwrap();
+    let required: Vec<bool> = post.parameters.as_ref().unwrap().iter().map(|p| p.required).collect();
+    assert_eq!(required, [false, true]);
+    assert_eq!(post.request_body.as_ref().unwrap().required, Some(true));
+    assert!(post.extras.is_empty(), "{:?}", post.extras);
+    assert_eq!(users.mock_data.as_ref().unwrap().description, "Users");
+
+    let saved: serde_json::Value = serde_yaml::from_str(&serde_yaml::to_string(&spec).unwrap()).unwrap();
+    let post = &saved["paths"]["/users"]["post"];
+    assert_eq!(post["requestBody"]["content"]["application/json"]["example"]["name"], "Ann");
+    assert!(post.get("request_body").is_none() && post["parameters"][0].get("required").is_none());
+    let reloaded: OpenAPISpec = serde_json::from_value(saved).unwrap();
+    assert!(reloaded.paths["/users"].post.as_ref().unwrap().request_body.is_some());
+}
+
+#[test]
+fn budgets_round_trip_through_yaml() {
+    let yaml = r#"openapi: 3.0.0
+info:
+  title: slo
+  version: 1.0.0
+paths:
+  /health:
+    get:
+      x-budget:
+        max_latency_ms: 250
+        max_status: 299
+      x-owner: platform
+  /search:
+    get:
+      x-budget:
+        max_latency_ms: 800
+  /legacy:
+    get:
+      summary: no budget here
+"#;
+    let spec: OpenAPISpec = serde_yaml::from_str(yaml).unwrap();
+    let health = spec.paths["/health"].get.as_ref().unwrap();
+    assert_eq!(health.budget, Some(Budget { max_latency_ms: Some(250), max_status: Some(299) }));
+    assert!(health.extras.contains_key("x-owner"));
+    assert!(!health.extras.contains_key("x-budget"));
+    assert_eq!(spec.paths["/search"].get.as_ref().unwrap().budget, Some(Budget { max_latency_ms: Some(800), max_status: None }));
+    assert_eq!(spec.paths["/legacy"].get.as_ref().unwrap().budget, None);
+
+    let saved = serde_yaml::to_string(&spec).unwrap();
+    assert!(saved.contains("x-budget"));
+    assert!(!saved.contains("max_status: null"));
+    let reloaded: OpenAPISpec = serde_yaml::from_str(&saved).unwrap();
+    assert_eq!(reloaded.paths["/health"].get.as_ref().unwrap().budget, health.budget);
+    assert_eq!(reloaded.paths["/legacy"].get.as_ref().unwrap().budget, None);
+}
+
+#[test]
+fn head_options_and_custom_methods_are_operations() {
+    let yaml = r#"openapi: 3.0.0
+info:
+  title: cache
+  version: 1.0.0
+paths:
+  /users:
+    head:
+      summary: freshness
+    options:
+      summary: preflight
+    x-methods:
+      PURGE:
+        summary: evict
+"#;
+    let mut spec: OpenAPISpec = serde_yaml::from_str(yaml).unwrap();
+    let users = spec.paths.get_mut("/users").unwrap();
+    assert_eq!(users.operations().map(|(method, _)| method).collect::<Vec<_>>(), ["HEAD", "OPTIONS", "PURGE"]);
+    assert_eq!(users.get_operation().unwrap().0, "HEAD");
+    assert!(users.operation_mut("propfind").unwrap().is_none());
+    assert!(users.operation_mut("NOT A METHOD").is_none());
+
+    // The empty PROPFIND slot is not saved
+    let saved = serde_yaml::to_string(&spec).unwrap();
+    assert!(saved.contains("x-methods") && saved.contains("PURGE") && !saved.contains("PROPFIND"), "{}", saved);
+    let reloaded: OpenAPISpec = serde_yaml::from_str(&saved).unwrap();
+    assert_eq!(reloaded.paths["/users"].operations().count(), 3);
+}
+
+#[test]
+fn budget_violations_name_what_was_exceeded() {
+    let budget = Budget { max_latency_ms: Some(100), max_status: Some(299) };
+    assert!(budget.violations(200, 100).is_empty());
+    assert_eq!(budget.violations(503, 150), vec!["latency 150ms exceeds 100ms", "status 503 exceeds 299"]);
+    assert!(Budget::default().violations(500, 10_000).is_empty());
+}
@@ -0,0 +1,67 @@
+use nuts::output::{self, Table};
+use nuts::{noteln, outln};
+
+#[test]
+fn quiet_output_drops_leading_emoji() {
+    assert_eq!(output::strip_emoji("🌐 GET https://api.example.com\n"), "GET https://api.example.com\n");
+    assert_eq!(output::strip_emoji("⚠️  3 slow requests, ✅ 97 ok"), " 3 slow requests, 97 ok");
+    assert_eq!(output::strip_emoji("p95 → 120ms"), "p95 → 120ms");
+}
+
+#[tokio::test]
+async fn quiet_leaves_out_notes_but_keeps_results() {
+    let print = || async {
+        noteln!("🚀 Performance Test Configuration");
+        outln!("✅ 200 OK");
+    };
+
+    let ((), loud) = output::capture(print()).await;
+    assert_eq!(loud, "🚀 Performance Test Configuration\n✅ 200 OK\n");
+
+    output::set_quiet(true);
+    let ((), quiet) = output::capture(print()).await;
+    output::set_quiet(false);
+    assert_eq!(quiet, "200 OK\n");
+}
+
+#[test]
+fn no_color_overrides_the_configured_color() {
+    // The only test here that touches the environment
+    std::env::remove_var("NO_COLOR");
+    assert!(output::color_wanted(None));
+    assert!(!output::color_wanted(Some(false)));
+    std::env::set_var("NO_COLOR", "");
+    assert!(output::color_wanted(Some(true)));
+    std::env::set_var("NO_COLOR", "1");
+    assert!(!output::color_wanted(Some(true)));
+    std::env::remove_var("NO_COLOR");
+}
+
+#[test]
+fn tables_keep_key_order_and_line_up_wide_characters() {
+    let json = r#"[{"name": "Ada", "id": 1, "tags": ["x"]}, {"id": 2, "city": "東京", "name": "🦀 Ferris", "meta": {"b": 1, "a": null}}, {"id": 3, "name": null}]"#;
+    let table = Table::from_json(json).unwrap();
+    assert_eq!(table.columns, ["name", "id", "tags", "city", "meta"]);
+    assert_eq!(table.rows[1], [Some("🦀 Ferris".into()), Some("2".into()), None, Some("東京".into()), Some(r#"{"a":null,"b":1}"#.into())]);
+
+    assert_eq!(table.render(None), [
+        "name       id  tags   city  meta",
+        "─────────  ──  ─────  ────  ────────────────",
+        "Ada        1   [\"x\"]",
+        "🦀 Ferris  2          東京  {\"a\":null,\"b\":1}",
+        "           3",
+    ].join("\n"));
+    // Cut to the width on screen: a wide character never straddles the limit
+    let table = Table::from_json(r#"[{"note": "日本語のテキスト", "n": "abcdefgh"}]"#).unwrap();
+    assert_eq!(table.render(Some(6)), ["note   n", "─────  ──────", "日本…  abcde…"].join("\n"));
+
+    assert!(Table::from_json(r#"{"id": 1}"#).is_none());
+    assert!(Table::from_json(r#"[{"id": 1}, 2]"#).is_none());
+    assert_eq!(Table::from_json("[]").unwrap().render(None), "(no rows)");
+}
+
+#[test]
+fn csv_quotes_only_what_needs_it() {
+    let table = Table::from_json(r#"[{"id": 1, "text": "a, b", "quote": "say \"hi\"", "note": "two\nlines", "pad": " x"}, {"id": 2}]"#).unwrap();
+    assert_eq!(table.to_csv(), "id,text,quote,note,pad\n1,\"a, b\",\"say \"\"hi\"\"\",\"two\nlines\",\" x\"\n2,,,,\n");
+}
@@ -0,0 +1,76 @@
+use nuts::config::Config;
+use nuts::flows::{postman, CollectionManager, OpenAPISpec};
+use std::path::PathBuf;
+
+fn fixture() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/postman-collection.json")
+}
+
+#[test]
+fn converts_requests_folders_and_bodies() {
+    let import = postman::convert(&std::fs::read_to_string(fixture()).unwrap()).unwrap();
+    let spec = &import.spec;
+
+    assert_eq!(spec.info.title, "Shop API (v2)");
+    assert_eq!(spec.info.description.as_deref(), Some("Orders and users"));
+    let servers: Vec<&str> = spec.servers.iter().map(|s| s.url.as_str()).collect();
+    assert_eq!(servers, ["{{baseUrl}}", "https://auth.example.com:8443"]);
+    assert_eq!(import.converted, ["GET /users", "GET /users/{id}", "POST /users", "POST /login"]);
+    assert_eq!(import.variables, ["baseUrl", "limit"]);
+
+    let list = spec.paths["/users"].get.as_ref().unwrap();
+    assert_eq!(list.tags.as_deref(), Some(&["Users".to_string()][..]));
+    let params = list.parameters.as_ref().unwrap();
+    let summary: Vec<(&str, &str, &serde_json::Value)> = params.iter()
+        .map(|p| (p.name.as_str(), p.in_.as_str(), &p.extras["example"]))
+        .collect();
+    assert_eq!(summary, [
+        ("page", "query", &serde_json::json!("1")),
+        ("limit", "query", &serde_json::json!("{{limit}}")),
+        ("Authorization", "header", &serde_json::json!("Bearer {{token}}")),
+    ]);
+
+    let get = spec.paths["/users/{id}"].get.as_ref().unwrap();
+    let id = &get.parameters.as_ref().unwrap()[0];
+    assert_eq!((id.name.as_str(), id.in_.as_str(), id.required), ("id", "path", true));
+
+    let create = spec.paths["/users"].post.as_ref().unwrap();
+    assert_eq!(create.tags.as_deref(), Some(&["Users".to_string(), "Admin".to_string()][..]));
+    let example = create.request_body.as_ref().unwrap().content["application/json"].example.as_ref().unwrap();
+    assert_eq!(example, &serde_json::json!({ "name": "Ada", "tenant": "{{tenant}}" }));
+
+    let login = spec.paths["/login"].post.as_ref().unwrap();
+    assert!(login.request_body.as_ref().unwrap().content.contains_key("application/x-www-form-urlencoded"));
+
+    let skipped: Vec<(&str, &str)> = import.skipped.iter().map(|(n, r)| (n.as_str(), r.as_str())).collect();
+    assert_eq!(skipped, [
+        ("Upload avatar", "file upload bodies are not supported yet"),
+        ("Search", "GraphQL requests are not supported yet"),
+        ("Preflight", "OPTIONS requests are not supported in flows"),
+    ]);
+    assert!(!spec.paths.contains_key("/avatar") && !spec.paths.contains_key("/graphql"));
+}
+
+#[test]
+fn import_writes_a_loadable_flow() {
+    let dir = std::env::temp_dir().join(format!("nuts-postman-{}", std::process::id()));
+    let manager = CollectionManager::new(dir.clone(), Config::default());
+
+    manager.import_postman(&fixture(), None, false).unwrap();
+    let path = dir.join("shop-api-v2.yaml");
+    let mut spec = OpenAPISpec::load(&path).unwrap();
+    assert_eq!(spec.paths.len(), 3);
+
+    // Importing again keeps what was added to the flow since; --force starts over
+    spec.paths.insert("/health".to_string(), Default::default());
+    spec.save(&path).unwrap();
+    manager.import_postman(&fixture(), None, false).unwrap();
+    let spec = OpenAPISpec::load(&path).unwrap();
+    assert!(spec.paths.contains_key("/health"));
+    assert_eq!(spec.paths.len(), 4);
+    manager.import_postman(&fixture(), None, true).unwrap();
+    assert!(!OpenAPISpec::load(&path).unwrap().paths.contains_key("/health"));
+
+    assert!(postman::convert(r#"{"openapi": "3.0.0"}"#).is_err());
+    let _ = std::fs::remove_dir_all(dir);
+}
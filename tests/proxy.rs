@@ -0,0 +1,84 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::commands::proxy::RecordingProxy;
+use nuts::flows::OpenAPISpec;
+use serde_json::{json, Value};
+
+/// Serve the proxy router in front of the fixture server and return its base URL.
+async fn start_proxy(proxy: &RecordingProxy) -> String {
+    let router = proxy.router();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn forwards_traffic_and_records_it_into_a_flow() {
+    let server = FixtureServer::start().await;
+    let proxy = RecordingProxy::new(&server.url(""), OpenAPISpec::new("recorded")).unwrap();
+    let base = start_proxy(&proxy).await;
+    let client = reqwest::Client::new();
+
+    let created: Value = client.post(format!("{}/items", base))
+        .json(&json!({ "name": "widget" }))
+        .send().await.unwrap()
+        .json().await.unwrap();
+    let id = created["id"].as_i64().unwrap();
+    for path in [format!("/items/{}?verbose=true", id), "/items/999".to_string()] {
+        client.get(format!("{}{}", base, path)).send().await.unwrap();
+    }
+    let response = client.get(format!("{}/status/418", base)).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 418);
+    assert_eq!(server.hits(), 4);
+
+    let recording = proxy.recording();
+    let recording = recording.lock().unwrap();
+    assert_eq!(recording.requests, 4);
+    let endpoints: Vec<&str> = recording.endpoints.iter().map(String::as_str).collect();
+    assert_eq!(endpoints, vec!["GET /items/{id}", "GET /status/{id}", "POST /items"]);
+
+    let spec = &recording.spec;
+    assert_eq!(spec.servers[0].url, server.url(""));
+    let post = spec.paths["/items"].post.as_ref().unwrap();
+    let request_example = post.request_body.as_ref().unwrap().content["application/json"].example.clone();
+    assert_eq!(request_example, Some(json!({ "name": "widget" })));
+
+    let get = spec.paths["/items/{id}"].get.as_ref().unwrap();
+    let parameters = get.parameters.as_ref().unwrap();
+    assert!(parameters.iter().any(|p| p.in_ == "path" && p.name == "id"));
+    let verbose = parameters.iter().find(|p| p.in_ == "query").unwrap();
+    assert_eq!((verbose.name.as_str(), verbose.extras["example"].clone()), ("verbose", json!("true")));
+    // Both item responses end up as mock examples of the same endpoint
+    assert_eq!(get.mock_data.as_ref().unwrap().examples.as_ref().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn large_bodies_are_truncated_in_the_recording() {
+    let server = FixtureServer::start().await;
+    let proxy = RecordingProxy::new(&server.url(""), OpenAPISpec::new("big")).unwrap();
+    let base = start_proxy(&proxy).await;
+
+    let body = "x".repeat(1024 * 1024 + 10);
+    let response = reqwest::Client::new().post(format!("{}/echo", base)).body(body.clone()).send().await.unwrap();
+    // The client still gets the full response
+    assert!(response.text().await.unwrap().len() > body.len());
+
+    let recording = proxy.recording();
+    let recording = recording.lock().unwrap();
+    let post = recording.spec.paths["/echo"].post.as_ref().unwrap();
+    let example = post.request_body.as_ref().unwrap().content["application/json"].example.clone().unwrap();
+    let example = example.as_str().unwrap();
+    assert!(example.ends_with("[truncated by NUTS: 1048576 of 1048586 bytes]"));
+}
+
+#[tokio::test]
+async fn unreachable_targets_answer_502() {
+    let proxy = RecordingProxy::new("http://127.0.0.1:9", OpenAPISpec::new("down")).unwrap();
+    let base = start_proxy(&proxy).await;
+
+    let response = reqwest::get(format!("{}/anything", base)).await.unwrap();
+    assert_eq!(response.status().as_u16(), 502);
+    assert!(proxy.recording().lock().unwrap().endpoints.is_empty());
+}
@@ -0,0 +1,110 @@
+use nuts::query::Query;
+use serde_json::{json, Value};
+
+fn run(filter: &str, input: &Value) -> Vec<Value> {
+    Query::parse(filter).unwrap().apply(input).unwrap()
+}
+
+fn sample() -> Value {
+    json!({
+        "data": {"items": [
+            {"id": 1, "name": "Ada", "tags": ["math"]},
+            {"id": 2, "name": "Grace", "tags": []},
+            {"id": 3, "name": "Linus"},
+        ]},
+        "total": 3,
+        "content-type": "users",
+        "word": "héllo",
+    })
+}
+
+#[test]
+fn fields_and_identity() {
+    let input = sample();
+    assert_eq!(run(".", &input), vec![input.clone()]);
+    assert_eq!(run(".total", &input), [json!(3)]);
+    assert_eq!(run(".data.items[0].name", &input), [json!("Ada")]);
+    assert_eq!(run(r#"."content-type""#, &input), [json!("users")]);
+    assert_eq!(run(r#".["content-type"]"#, &input), [json!("users")]);
+    assert_eq!(run(".data.\"items\".[1].id", &input), [json!(2)]);
+    // Missing keys and steps on null give null, as in jq
+    assert_eq!(run(".missing.deeper[0]", &input), [Value::Null]);
+}
+
+#[test]
+fn indexes_and_slices() {
+    let input = json!([10, 20, 30, 40, 50]);
+    assert_eq!(run(".[0]", &input), [json!(10)]);
+    assert_eq!(run(".[-1]", &input), [json!(50)]);
+    assert_eq!(run(".[9]", &input), [Value::Null]);
+    assert_eq!(run(".[-9]", &input), [Value::Null]);
+    assert_eq!(run(".[2:4]", &input), [json!([30, 40])]);
+    assert_eq!(run(".[:2]", &input), [json!([10, 20])]);
+    assert_eq!(run(".[3:]", &input), [json!([40, 50])]);
+    assert_eq!(run(".[-2:]", &input), [json!([40, 50])]);
+    assert_eq!(run(".[4:1]", &input), [json!([])]);
+    assert_eq!(run(".[1:100]", &input), [json!([20, 30, 40, 50])]);
+    assert_eq!(run(".word[1:3]", &sample()), [json!("él")]);
+}
+
+#[test]
+fn iteration_pipes_and_commas() {
+    let input = sample();
+    assert_eq!(run(".data.items[].name", &input), [json!("Ada"), json!("Grace"), json!("Linus")]);
+    assert_eq!(run(".data.items[] | .id", &input), [json!(1), json!(2), json!(3)]);
+    assert_eq!(run(".data.items[1:] | .[].name", &input), [json!("Grace"), json!("Linus")]);
+    assert_eq!(run(".total, .data.items[0].id", &input), [json!(3), json!(1)]);
+    assert_eq!(run("(.data.items[0], .data.items[2]) | .name", &input), [json!("Ada"), json!("Linus")]);
+    // Object values in key order
+    assert_eq!(run(".[]", &json!({"b": 2, "a": 1})), [json!(1), json!(2)]);
+}
+
+#[test]
+fn optional_steps_and_functions() {
+    let input = sample();
+    // Linus has no tags: null iterates to an error unless skipped
+    assert!(Query::parse(".data.items[].tags[]").unwrap().apply(&input).unwrap_err().contains("cannot iterate over null"));
+    assert_eq!(run(".data.items[].tags[]?", &input), [json!("math")]);
+    assert_eq!(run(".total.name?", &input), Vec::<Value>::new());
+    assert_eq!(run(".data.items | length", &input), [json!(3)]);
+    assert_eq!(run(".data.items[] | .tags | length", &input), [json!(1), json!(0), json!(0)]);
+    assert_eq!(run(".word | length", &input), [json!(5)]);
+    assert_eq!(run("length", &json!(-7)), [json!(7)]);
+    assert_eq!(run(".data.items[0] | keys", &input), [json!(["id", "name", "tags"])]);
+    assert_eq!(run(".data.items | keys", &input), [json!([0, 1, 2])]);
+}
+
+#[test]
+fn type_errors_name_the_value() {
+    let input = sample();
+    let error = |filter: &str| Query::parse(filter).unwrap().apply(&input).unwrap_err();
+    assert_eq!(error(".total.name"), "cannot index number with \"name\"");
+    assert_eq!(error(".data.items.name"), "cannot index array with \"name\"");
+    assert_eq!(error(".data[0]"), "cannot index object with a number");
+    assert_eq!(error(".total[1:2]"), "cannot slice number");
+    assert_eq!(error(".total | keys"), "number has no keys");
+}
+
+#[test]
+fn invalid_filters_fail_to_parse_with_a_column() {
+    let error = |filter: &str| Query::parse(filter).unwrap_err();
+    assert_eq!(error(".items["), "expected an index, a slice or a quoted key at column 8");
+    assert_eq!(error(".items[0"), "expected ']' at column 9");
+    assert_eq!(error(".[:]"), "a slice needs a start or an end at column 4");
+    assert_eq!(error(".a | "), "expected a filter at column 6");
+    assert_eq!(error(".a .b"), "unexpected '.' at column 4");
+    assert_eq!(error("..a"), "recursive descent '..' isn't supported at column 2");
+    assert_eq!(error("map(.a)"), "unknown function 'map', only length and keys are supported at column 4");
+    assert_eq!(error("(.a"), "expected ')' at column 4");
+    assert_eq!(error(r#"."open"#), "unterminated string at column 7");
+    assert_eq!(error("?"), "unexpected '?' at column 1");
+}
+
+#[test]
+fn results_print_like_jq() {
+    let query = Query::parse(".items[].id").unwrap();
+    assert_eq!(query.apply_to_text(r#"{"items": [{"id": 1}, {"id": "b"}]}"#).unwrap(), "1\n\"b\"");
+    assert_eq!(Query::parse(".items").unwrap().apply_to_text(r#"{"items": [1]}"#).unwrap(), "[\n  1\n]");
+    assert!(query.apply_to_text("<html>").unwrap_err().starts_with("--jq needs a JSON response"));
+    assert_eq!(query.to_string(), ".items[].id");
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::commands::call::CallCommand;
+use nuts::recording::{find_value, replay, Recorder, RecordingStore};
+use serde_json::json;
+
+async fn record_crud(server: &FixtureServer) -> Recorder {
+    let call = CallCommand::new();
+    let mut recorder = Recorder::new("crud");
+    let items = server.url("/items");
+    let steps: [&[&str]; 3] = [
+        &["call", "POST", &items, "-H", "Authorization: Bearer secret", "-H", "X-Trace: on", r#"{"name": "widget"}"#],
+        &["call", "GET", &format!("{}/1", items)],
+        &["call", "DELETE", &format!("{}/1", items)],
+    ];
+    for args in steps {
+        recorder.record(&call.execute(args).await.unwrap());
+    }
+    recorder
+}
+
+#[test]
+fn finds_values_in_responses() {
+    let body = json!({ "data": { "items": [{ "id": 7 }, { "id": "abc-12" }] } });
+    assert_eq!(find_value(&body, "7", "$").as_deref(), Some("$.data.items[0].id"));
+    assert_eq!(find_value(&body, "abc-12", "$").as_deref(), Some("$.data.items[1].id"));
+    assert_eq!(find_value(&body, "8", "$"), None);
+}
+
+#[tokio::test]
+async fn records_calls_with_ids_templated_and_credentials_dropped() {
+    let server = FixtureServer::start().await;
+    let recording = record_crud(&server).await.finish();
+
+    let steps = &recording.steps;
+    assert_eq!(steps.len(), 3);
+    assert_eq!((steps[0].method.as_str(), steps[0].expect_status), ("POST", 201));
+    assert_eq!(steps[0].headers.get("X-Trace").map(String::as_str), Some("on"));
+    assert!(!steps[0].headers.keys().any(|name| name.eq_ignore_ascii_case("authorization")));
+    assert_eq!(steps[0].extract.get("step1_id").map(String::as_str), Some("$.id"));
+
+    assert_eq!(steps[1].url, server.url("/items/{{step1_id}}"));
+    assert_eq!(steps[1].expect_status, 200);
+    // The GET response holds the same id, so the DELETE picks it up from there
+    assert_eq!(steps[2].url, server.url("/items/{{step2_id}}"));
+    assert_eq!(steps[2].expect_status, 204);
+}
+
+#[tokio::test]
+async fn saves_and_replays_recordings() {
+    let server = FixtureServer::start().await;
+    let recording = record_crud(&server).await.finish();
+
+    let dir = std::env::temp_dir().join(format!("nuts-recordings-{}", std::process::id()));
+    let store = RecordingStore::new(&dir);
+    let path = store.save(&recording).unwrap();
+    assert!(path.ends_with("crud.yaml"));
+    assert_eq!(store.load("crud").unwrap(), recording);
+    assert_eq!(store.list().unwrap(), ["crud"]);
+    assert!(store.load("missing").unwrap_err().to_string().starts_with("No recording named 'missing'"));
+
+    // The replay creates item 2, so it only passes if the later steps follow the new id
+    let outcomes = replay(&CallCommand::new(), &recording).await;
+    assert!(outcomes.iter().all(|outcome| outcome.passed()), "{:?}", outcomes);
+    assert_eq!(outcomes[1].url, server.url("/items/2"));
+
+    let mut changed = recording.clone();
+    changed.steps[1].expect_status = 201;
+    let outcomes = replay(&CallCommand::new(), &changed).await;
+    assert_eq!(outcomes.iter().map(|outcome| outcome.passed()).collect::<Vec<_>>(), [true, false, true]);
+    assert_eq!((outcomes[1].expected, outcomes[1].actual), (201, Some(200)));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
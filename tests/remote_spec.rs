@@ -0,0 +1,78 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::config::Config;
+use nuts::flows::remote::{fetch_spec, parse_document, spec_from_document, SpecCache};
+use nuts::flows::{CollectionManager, OpenAPISpec};
+use serde_json::json;
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("nuts-remote-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn converts_swagger_hosts_and_points_at_shape_errors() {
+    let swagger = parse_document("swagger: '2.0'\ninfo: {title: Old, version: '1'}\nhost: api.example.com\nbasePath: /v2\npaths: {}\n", None).unwrap();
+    let spec = spec_from_document(swagger, "https://docs.example.com/swagger.yaml").unwrap();
+    assert_eq!(spec.openapi, "2.0");
+    assert_eq!(spec.servers[0].url, "https://api.example.com/v2");
+    assert!(!spec.extras.contains_key("host") && !spec.extras.contains_key("swagger"));
+
+    let no_host = json!({ "swagger": "2.0", "info": {}, "paths": {}, "basePath": "/api" });
+    assert_eq!(spec_from_document(no_host, "http://localhost:8080/docs/swagger.json").unwrap().servers[0].url, "http://localhost:8080/api");
+
+    let error = spec_from_document(json!({ "openapi": "3.0.0", "paths": {} }), "https://x.io").unwrap_err().to_string();
+    assert_eq!(error, "Not an OpenAPI document: missing the `info` object");
+    let error = spec_from_document(json!({ "openapi": "3.0.0", "info": {}, "paths": { "/a/b": { "get": { "tags": "pets" } } } }), "https://x.io")
+        .unwrap_err().to_string();
+    assert!(error.starts_with("Document doesn't match a NUTS flow at /paths/~1a~1b/get/tags: invalid type"), "{}", error);
+}
+
+#[tokio::test]
+async fn revalidates_cached_specs_with_etag_and_last_modified() {
+    let server = FixtureServer::start().await;
+    let dir = temp_dir("cache");
+    let cache = SpecCache::new(&dir);
+    let client = reqwest::Client::new();
+
+    for name in ["openapi.json", "swagger.yaml"] {
+        let url = server.url(&format!("/specs/{}", name));
+        let first = fetch_spec(&client, &url, Some(&cache)).await.unwrap();
+        let second = fetch_spec(&client, &url, Some(&cache)).await.unwrap();
+        assert!(!first.from_cache && second.from_cache, "{}", name);
+        assert_eq!(first.body, second.body);
+        assert_eq!(first.content_type, second.content_type);
+    }
+    assert!(!fetch_spec(&client, &server.url("/specs/openapi.json"), None).await.unwrap().from_cache);
+    assert_eq!(fetch_spec(&client, &server.url("/specs/missing"), Some(&cache)).await.unwrap_err().to_string(),
+        format!("{} returned 404 Not Found", server.url("/specs/missing")));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn imports_a_spec_url_as_a_flow() {
+    let server = FixtureServer::start().await;
+    let flows = temp_dir("flows");
+    let cache_dir = temp_dir("import-cache");
+    let cache = SpecCache::new(&cache_dir);
+    let manager = CollectionManager::new(flows.clone(), Config::default());
+
+    let spec = manager.import_url(&server.url("/specs/openapi.json"), None, Some(&cache)).await.unwrap();
+    assert_eq!(spec.servers[0].url, server.url("/v1"));
+    let saved = OpenAPISpec::load(&flows.join("pet-store.yaml")).unwrap();
+    assert_eq!(saved.paths.len(), 2);
+    assert!(saved.paths["/pets"].post.is_some());
+
+    manager.import_url(&server.url("/specs/swagger.yaml"), Some("orders"), Some(&cache)).await.unwrap();
+    let orders = OpenAPISpec::load(&flows.join("orders.yaml")).unwrap();
+    assert_eq!(orders.servers[0].url, "http://orders.example.com/api");
+    assert_eq!(orders.paths["/orders"].get.as_ref().unwrap().summary.as_deref(), Some("List orders"));
+
+    let error = manager.import_url(&server.url("/specs/broken.json"), None, None).await.unwrap_err().to_string();
+    assert!(error.starts_with("Document doesn't match a NUTS flow at /paths/~1a~1b/get/parameters/0/required: invalid type"), "{}", error);
+    assert!(!flows.join("broken.yaml").exists());
+
+    let _ = std::fs::remove_dir_all(&flows);
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}
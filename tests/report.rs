@@ -0,0 +1,142 @@
+use nuts::models::metrics::{sparkline, trim_latencies, LatencyHistogram, Metrics, RequestMetric};
+use nuts::report::{self, PerfReport};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+fn sample_report() -> PerfReport {
+    let metrics = Metrics::new();
+    for (ms, status) in [(100, 200), (200, 200), (900, 500), (2500, 200)] {
+        metrics.record(RequestMetric {
+            duration: Duration::from_millis(ms),
+            status,
+            timestamp: SystemTime::now(),
+        });
+    }
+    PerfReport::new("https://api.example.com/users?a=1&b=2", "get", 5, Duration::from_secs(10), metrics.summary())
+}
+
+#[test]
+fn json_report_is_self_describing() {
+    let json: serde_json::Value = serde_json::from_str(&sample_report().to_json().unwrap()).unwrap();
+
+    assert_eq!(json["config"]["url"], "https://api.example.com/users?a=1&b=2");
+    assert_eq!(json["config"]["method"], "GET");
+    assert_eq!(json["config"]["users"], 5);
+    assert_eq!(json["config"]["duration_secs"], 10.0);
+    assert_eq!(json["summary"]["total_requests"], 4);
+    assert_eq!(json["summary"]["error_rate"], 0.25);
+    assert_eq!(json["summary"]["status_codes"]["200"], 3);
+    assert_eq!(json["summary"]["status_codes"]["500"], 1);
+    assert_eq!(json["summary"]["histogram"][7], serde_json::json!({ "below_ms": 200, "count": 1 }));
+    assert_eq!(json["summary"]["histogram"][11]["count"], 1);
+    assert_eq!(json["summary"]["histogram"][16], serde_json::json!({ "below_ms": null, "count": 0 }));
+    assert_eq!(json["summary"]["max_latency_ms"], 2500.0);
+    assert_eq!(json["summary"]["avg_latency_ms"], 925.0);
+    assert!(json["generated_at"].is_string());
+}
+
+#[test]
+fn junit_report_escapes_and_lists_failures() {
+    let mut report = sample_report();
+    let xml = report.to_junit();
+
+    assert!(xml.starts_with("<?xml"));
+    assert!(xml.contains("name=\"GET https://api.example.com/users?a=1&amp;b=2\""));
+    assert!(xml.contains("<property name=\"users\" value=\"5\"/>"));
+    assert!(xml.contains("<property name=\"status.500\" value=\"1\"/>"));
+    assert!(xml.contains("<property name=\"latency.&lt;200ms\" value=\"1\"/>"));
+    assert!(xml.contains("<property name=\"latency.&lt;5s\" value=\"1\"/>"));
+    assert!(!xml.contains("latency.&lt;2s"));
+    assert!(xml.contains("failures=\"0\""));
+    assert!(!xml.contains("<failure"));
+
+    report.failures.push("error rate 25.0% > 1%".to_string());
+    let xml = report.to_junit();
+    assert!(xml.contains("failures=\"1\""));
+    assert!(xml.contains("<failure message=\"error rate 25.0% &gt; 1%\" type=\"threshold\"/>"));
+}
+
+#[test]
+fn format_follows_extension() {
+    let dir = std::env::temp_dir().join(format!("nuts-report-{}", std::process::id()));
+    let report = sample_report();
+
+    report.write(&dir.join("perf.json")).unwrap();
+    report.write(&dir.join("perf.XML")).unwrap();
+    assert!(std::fs::read_to_string(dir.join("perf.json")).unwrap().starts_with('{'));
+    assert!(std::fs::read_to_string(dir.join("perf.XML")).unwrap().starts_with("<?xml"));
+
+    assert!(report.write(&dir.join("perf.txt")).is_err());
+    assert!(!report::is_supported(Path::new("perf.csv")));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn trimming_drops_the_slowest_responses_from_avg_and_std_dev() {
+    let ms = Duration::from_millis;
+    let trimmed = trim_latencies(&[ms(40), ms(1000), ms(10), ms(30), ms(20)], 0.2);
+    assert_eq!((trimmed.dropped, trimmed.avg_latency), (1, ms(25)));
+    assert!((trimmed.std_dev_latency - 0.000125_f64.sqrt()).abs() < 1e-9, "{}", trimmed.std_dev_latency);
+    assert_eq!(trim_latencies(&[ms(10), ms(20)], 0.1).dropped, 0);
+
+    let metrics = Metrics::new().with_trimmed_outliers(0.2);
+    for latency in [40, 1000, 10, 30, 20] {
+        metrics.record(RequestMetric { duration: ms(latency), status: 200, timestamp: SystemTime::now() });
+    }
+    let summary = metrics.summary();
+    assert_eq!(summary.trimmed.as_ref().unwrap().avg_latency, ms(25));
+    // Raw numbers and percentiles still see the outlier
+    assert_eq!(summary.avg_latency, ms(220));
+    assert_eq!(summary.p99_latency, ms(40));
+    assert_eq!(summary.total_requests, 5);
+}
+
+#[test]
+fn requests_started_during_warmup_are_only_counted() {
+    let now = SystemTime::now();
+    let metrics = Metrics::new().with_warmup(now + Duration::from_secs(1));
+    metrics.record(RequestMetric { duration: Duration::from_millis(900), status: 500, timestamp: now });
+    metrics.record(RequestMetric { duration: Duration::from_millis(50), status: 200, timestamp: now + Duration::from_secs(2) });
+
+    let summary = metrics.summary();
+    assert_eq!((summary.total_requests, summary.warmup_requests), (1, 1));
+    assert_eq!((summary.avg_latency, summary.error_rate), (Duration::from_millis(50), 0.0));
+    assert!(summary.trimmed.is_none());
+}
+
+#[test]
+fn histogram_renders_log_scale_bars() {
+    let latencies: Vec<Duration> = [3, 15, 15, 15, 15, 30, 30, 30, 30, 30, 30, 30, 30, 150, 150]
+        .into_iter()
+        .map(Duration::from_millis)
+        .collect();
+    let histogram = LatencyHistogram::from_latencies(&latencies);
+    assert_eq!(histogram.total(), 15);
+
+    let expected = [
+        "   <5ms │██               1   6.7%",
+        "  <10ms │                 0   0.0%",
+        "  <20ms │████████         4  26.7%",
+        "  <50ms │████████████████ 8  53.3%",
+        " <100ms │                 0   0.0%",
+        " <200ms │████             2  13.3%",
+    ];
+    assert_eq!(histogram.render(16), expected.join("\n"));
+    assert_eq!(LatencyHistogram::default().render(16), "");
+
+    let slow = LatencyHistogram::from_latencies(&[Duration::from_millis(1500), Duration::from_secs(90)]);
+    assert_eq!(slow.buckets.iter().filter(|b| b.count > 0).map(|b| b.label()).collect::<Vec<_>>(), ["<2s", "≥60s"]);
+}
+
+#[test]
+fn sparkline_fills_gaps_and_fits_the_width() {
+    let start = SystemTime::now();
+    let at = |secs| start + Duration::from_secs(secs);
+    assert_eq!(sparkline(&[(at(0), 10), (at(1), 20), (at(3), 40)], 60), "▃▅▁█");
+    assert_eq!(sparkline(&[], 60), "");
+
+    let long: Vec<(SystemTime, usize)> = (0..120).map(|s| (at(s), if s < 60 { 1 } else { 2 })).collect();
+    let line = sparkline(&long, 60);
+    assert_eq!(line.chars().count(), 60);
+    assert_eq!(line, format!("{}{}", "▅".repeat(30), "█".repeat(30)));
+}
@@ -0,0 +1,216 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::commands::explain::{ExplainArgs, ExplainTarget};
+use nuts::config::Config;
+use nuts::history::RequestHistory;
+use nuts::shell::{split_args, NutsShell};
+use nuts::templates::TemplateStore;
+
+fn temp_history(name: &str) -> RequestHistory {
+    let path = std::env::temp_dir().join(format!("nuts-shell-{}-{}.jsonl", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    RequestHistory::new(path)
+}
+
+#[tokio::test]
+async fn advanced_call_is_remembered_for_explain_and_save() {
+    let server = FixtureServer::start().await;
+    let mut shell = NutsShell::with_config(Config::default()).with_history(temp_history("explain"));
+
+    shell
+        .process_command(&format!("call -H 'X-Test: yes' POST {} '{{\"a\": 1}}'", server.url("/echo")))
+        .await
+        .unwrap();
+
+    let response: serde_json::Value = serde_json::from_str(shell.last_response().unwrap()).unwrap();
+    assert_eq!(response["headers"]["x-test"], "yes");
+    assert_eq!(response["parsed"]["a"], 1);
+
+    let (method, url, body) = shell.last_request().unwrap();
+    assert_eq!(method, "POST");
+    assert_eq!(url, &server.url("/echo"));
+    assert_eq!(body.as_deref(), Some("{\"a\": 1}"));
+
+    // Without an AI key explain reports offline mode instead of a missing response.
+    let error = shell.process_command("explain").await.unwrap_err();
+    assert!(error.to_string().contains("offline mode"), "{}", error);
+}
+
+#[tokio::test]
+async fn jq_filters_what_is_shown_and_kept() {
+    let server = FixtureServer::start().await;
+    let mut shell = NutsShell::with_config(Config::default()).with_history(temp_history("jq"));
+    let url = server.url("/reflect");
+
+    shell
+        .process_command(&format!(r#"call --jq '.items[] | .name' POST {} '{{"items": [{{"name": "Ada"}}, {{"name": "Grace"}}]}}'"#, url))
+        .await
+        .unwrap();
+    assert_eq!(shell.last_response(), Some("\"Ada\"\n\"Grace\""));
+
+    // Filtered, then laid out
+    let out = std::env::temp_dir().join(format!("nuts-jq-{}.csv", std::process::id()));
+    shell
+        .process_command(&format!(r#"call --filter .data --csv -o {} POST {} '{{"data": [{{"id": 7}}]}}'"#, out.display(), url))
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&out).unwrap(), "id\n7\n");
+    let _ = std::fs::remove_file(&out);
+
+    // A bad filter is caught before anything is sent
+    let hits = server.hits();
+    let error = shell.process_command(&format!("call --jq '.items[' POST {} '[]'", url)).await.unwrap_err();
+    assert!(error.to_string().contains("Invalid --jq filter '.items[': expected an index"), "{}", error);
+    assert_eq!(server.hits(), hits);
+}
+
+#[test]
+fn explain_arguments_pick_one_target() {
+    let parse = |line: &str| ExplainArgs::parse(&split_args(line).iter().map(String::as_str).collect::<Vec<_>>());
+    assert_eq!(parse("explain").unwrap(), ExplainArgs { target: ExplainTarget::Last(1), context: None });
+    assert_eq!(parse("explain @out/response.json").unwrap().target, ExplainTarget::File("out/response.json".into()));
+    assert_eq!(
+        parse("explain --status 403 --context 'calling /admin as a viewer role'").unwrap(),
+        ExplainArgs { target: ExplainTarget::Status(403), context: Some("calling /admin as a viewer role".to_string()) }
+    );
+    assert_eq!(parse("explain --last 3").unwrap().target, ExplainTarget::Last(3));
+    assert!(parse("explain --status 42").is_err());
+    assert!(parse("explain --last 0").is_err());
+    assert!(parse("explain --last 2 --status 500").is_err());
+}
+
+#[tokio::test]
+async fn explain_says_what_is_missing() {
+    let server = FixtureServer::start().await;
+    let mut shell = NutsShell::with_config(Config::default()).with_history(temp_history("explain-missing"));
+
+    let error = shell.process_command("explain").await.unwrap_err().to_string();
+    assert!(error.contains("no response to explain yet"), "{}", error);
+    let error = shell.process_command("explain @/no/such/response.json").await.unwrap_err().to_string();
+    assert!(error.contains("no file at /no/such/response.json"), "{}", error);
+
+    shell.process_command(&format!("call GET {}", server.url("/items"))).await.unwrap();
+    shell.process_command(&format!("call GET {}", server.url("/echo"))).await.unwrap();
+    let error = shell.process_command("explain --last 3").await.unwrap_err().to_string();
+    assert!(error.contains("only the last 2 response(s)"), "{}", error);
+
+    // With enough responses the request gets as far as needing the AI
+    let error = shell.process_command("explain --last 2").await.unwrap_err().to_string();
+    assert!(error.contains("offline mode"), "{}", error);
+}
+
+#[tokio::test]
+async fn calls_are_recorded_and_replayed() {
+    let server = FixtureServer::start().await;
+    let history = temp_history("replay");
+    let mut shell = NutsShell::with_config(Config::default()).with_history(history.clone());
+
+    shell.process_command(&format!("call POST {} '{{\"n\": 1}}'", server.url("/items"))).await.unwrap();
+    shell.process_command(&format!("call GET {}", server.url("/items/1"))).await.unwrap();
+
+    let entries = history.entries().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].method, "POST");
+    assert_eq!(entries[0].status, 201);
+    assert!(entries[0].body_hash.is_some());
+    assert_eq!(entries[1].url, server.url("/items/1"));
+    assert_eq!(entries[1].body_hash, None);
+    assert_eq!(history.search("post").unwrap().len(), 1);
+
+    shell.process_command("replay 1").await.unwrap();
+    assert_eq!(server.hits(), 3);
+    let replayed = history.get(3).unwrap();
+    assert_eq!(replayed.args, entries[0].args);
+    assert_eq!(replayed.body_hash, entries[0].body_hash);
+
+    assert!(shell.process_command("replay 9").await.unwrap_err().to_string().contains("No history entry #9"));
+
+    // An entry with masked credentials is handed back for the user to complete, not sent
+    shell.process_command(&format!("call --bearer s3cret-token GET {}", server.url("/items/1"))).await.unwrap();
+    assert_eq!(server.hits(), 4);
+    let masked = history.get(4).unwrap();
+    assert_eq!(masked.masked, ["--bearer"]);
+    assert!(!std::fs::read_to_string(history.path()).unwrap().contains("s3cret-token"));
+    let refused = shell.process_command("replay 4").await.unwrap_err();
+    assert!(refused.to_string().contains("masked (--bearer)"), "{}", refused);
+    assert_eq!(server.hits(), 4);
+    let _ = std::fs::remove_file(history.path());
+}
+
+#[tokio::test]
+async fn templates_save_the_last_call_and_run_it_with_overrides() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-shell-templates-{}", std::process::id()));
+    let mut shell = NutsShell::with_config(Config::default())
+        .with_history(temp_history("templates"))
+        .with_templates(TemplateStore::new(&dir));
+
+    shell.process_command(&format!(
+        "call -H 'X-Team: core' -H 'X-Trace: on' --timeout 1500ms POST {} '{{\"user\": {{\"email\": \"a@x.io\"}}, \"tags\": [\"t1\"]}}'",
+        server.url("/echo/users")
+    )).await.unwrap();
+    shell.process_command("template save create-user").await.unwrap();
+    assert_eq!(TemplateStore::new(&dir).load("create-user").unwrap().timeout_ms, Some(1500));
+
+    shell.process_command(&format!("template run create-user body.user.email=b@x.io body.tags.1=t2 header.X-Trace= url={}", server.url("/echo/v2")))
+        .await.unwrap();
+    let echoed: serde_json::Value = serde_json::from_str(shell.last_response().unwrap()).unwrap();
+    assert_eq!(echoed["path"], "/echo/v2");
+    assert_eq!(echoed["headers"]["x-team"], "core");
+    assert!(echoed["headers"].get("x-trace").is_none());
+    assert_eq!(echoed["parsed"], serde_json::json!({ "user": { "email": "b@x.io" }, "tags": ["t1", "t2"] }));
+
+    let error = shell.process_command("template run missing").await.unwrap_err().to_string();
+    assert!(error.starts_with("Template failed: No template named 'missing'"), "{}", error);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn snippets_render_the_last_call_or_a_history_entry_with_defaults_applied() {
+    let server = FixtureServer::start().await;
+    let config = Config { base_url: Some(server.url("")), ..Config::default() };
+    let mut shell = NutsShell::with_config(config).with_history(temp_history("snippet"));
+    let out = std::env::temp_dir().join(format!("nuts-shell-snippet-{}.py", std::process::id()));
+
+    shell.process_command("call GET /items").await.unwrap();
+    shell.process_command("call --bearer s3cret POST /echo/users '{\"name\": \"Ann\"}'").await.unwrap();
+    shell.process_command(&format!("snippet python-requests --out {}", out.display())).await.unwrap();
+    let snippet = std::fs::read_to_string(&out).unwrap();
+    assert!(snippet.contains(&format!("    \"{}\",\n", server.url("/echo/users"))), "{}", snippet);
+    assert!(snippet.contains("\"Authorization\": \"Bearer s3cret\""), "{}", snippet);
+    assert!(snippet.contains("json={\"name\": \"Ann\"}"), "{}", snippet);
+
+    shell.process_command(&format!("snippet curl --from-history 1 --out {}", out.display())).await.unwrap();
+    assert_eq!(std::fs::read_to_string(&out).unwrap(), format!("curl '{}'\n", server.url("/items")));
+
+    let error = shell.process_command("snippet curl --from-history 9").await.unwrap_err().to_string();
+    assert!(error.starts_with("Snippet failed:"), "{}", error);
+    let _ = std::fs::remove_file(out);
+}
+
+#[test]
+fn split_args_honours_quotes() {
+    assert_eq!(
+        split_args(r#"perf GET https://x --bearer abc -H "X-Tenant: foo""#),
+        ["perf", "GET", "https://x", "--bearer", "abc", "-H", "X-Tenant: foo"]
+    );
+    assert_eq!(split_args(r#"call POST u '{"a": 1}'"#), ["call", "POST", "u", r#"{"a": 1}"#]);
+    assert_eq!(split_args(r#"say "a \"quoted\" word" ''"#), ["say", r#"a "quoted" word"#, ""]);
+    // As pasted from a browser: line continuations and ANSI-C strings
+    assert_eq!(split_args("curl -s \\\n  -d $'it\\'s\\n' \\\r\n u"), ["curl", "-s", "-d", "it's\n", "u"]);
+}
+
+#[tokio::test]
+async fn perf_refuses_thresholds_it_cannot_check() {
+    let server = FixtureServer::start().await;
+    let mut shell = NutsShell::with_config(Config::default()).with_history(temp_history("perf-thresholds"));
+    let url = server.url("/echo");
+
+    for (flag, value) in [("--fail-p95", "inf"), ("--fail-avg", "1e300"), ("--fail-p95", "-5"), ("--fail-error-rate", "NaN"), ("--fail-error-rate", "-1")] {
+        let args = ["perf", "GET", &url, "--users", "1", "--duration", "1s", flag, value];
+        let refused = shell.process_args(args.iter().map(|a| a.to_string()).collect()).await.unwrap_err();
+        assert!(refused.to_string().contains(flag), "{}", refused);
+    }
+    assert_eq!(server.hits(), 0);
+}
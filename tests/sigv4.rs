@@ -0,0 +1,150 @@
+mod common;
+
+use chrono::{DateTime, Utc};
+use common::FixtureServer;
+use nuts::sigv4::{canonical_request, hmac_sha256, hex, sha256_hex, signing_key, Credentials, Scope, Signer, EMPTY_PAYLOAD_HASH};
+
+// Credentials, scope and time of the AWS SigV4 test suite and the IAM examples in the docs
+const ACCESS_KEY: &str = "AKIDEXAMPLE";
+const SECRET_KEY: &str = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY";
+
+fn signer(service: &str, session_token: Option<&str>) -> Signer {
+    Signer::new(
+        Scope { region: "us-east-1".to_string(), service: service.to_string() },
+        Credentials { access_key_id: ACCESS_KEY.to_string(), secret_access_key: SECRET_KEY.to_string(), session_token: session_token.map(str::to_string) },
+    )
+}
+
+fn suite_time() -> DateTime<Utc> {
+    "2015-08-30T12:36:00Z".parse().unwrap()
+}
+
+fn authorization(signed: &[(String, String)]) -> &str {
+    &signed.iter().find(|(name, _)| name == "authorization").unwrap().1
+}
+
+#[test]
+fn hmac_and_signing_key_match_published_vectors() {
+    // RFC 4231, test case 1
+    assert_eq!(hex(&hmac_sha256(&[0x0b; 20], b"Hi There")), "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7");
+    // RFC 4231, test case 6: a key longer than the block is hashed first
+    assert_eq!(
+        hex(&hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First")),
+        "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+    );
+    assert_eq!(hex(&signing_key(SECRET_KEY, "20150830", "us-east-1", "iam")), "c4afb1cc5771d871763a393e44b703571b55cc28424d1a5e86da6ed3c154a4b9");
+    assert_eq!(sha256_hex(b""), EMPTY_PAYLOAD_HASH);
+}
+
+#[test]
+fn signs_the_aws_test_suite_requests() {
+    let host = [("Host".to_string(), "example.amazonaws.com".to_string())];
+    let cases = [
+        // get-vanilla
+        ("GET", "https://example.amazonaws.com/", "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"),
+        // post-vanilla
+        ("POST", "https://example.amazonaws.com/", "5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b"),
+        // get-vanilla-query-order-key-case
+        ("GET", "https://example.amazonaws.com/?Param2=value2&Param1=value1", "b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"),
+    ];
+    for (method, url, signature) in cases {
+        let signed = signer("service", None).sign(method, &url.parse().unwrap(), &host, EMPTY_PAYLOAD_HASH, suite_time());
+        assert_eq!(signed[0], ("x-amz-date".to_string(), "20150830T123600Z".to_string()));
+        assert_eq!(
+            authorization(&signed),
+            format!("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, Signature={}", signature),
+            "{} {}", method, url
+        );
+    }
+
+    // post-x-www-form-urlencoded: the body is hashed as sent
+    let headers = [host[0].clone(), ("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string())];
+    let signed = signer("service", None).sign("POST", &"https://example.amazonaws.com/".parse().unwrap(), &headers, &sha256_hex(b"Param1=value1"), suite_time());
+    assert!(authorization(&signed).ends_with("SignedHeaders=content-type;host;x-amz-date, Signature=ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a"), "{}", authorization(&signed));
+}
+
+#[test]
+fn signs_the_iam_documentation_example() {
+    let url = "https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08".parse().unwrap();
+    let headers = [("Content-Type".to_string(), "application/x-www-form-urlencoded; charset=utf-8".to_string())];
+    let signed = signer("iam", None).sign("GET", &url, &headers, EMPTY_PAYLOAD_HASH, suite_time());
+    assert_eq!(
+        authorization(&signed),
+        "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, SignedHeaders=content-type;host;x-amz-date, Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+    );
+
+    let with_host = [headers[0].clone(), ("host".to_string(), "iam.amazonaws.com".to_string()), ("x-amz-date".to_string(), "20150830T123600Z".to_string())];
+    let (canonical, _) = canonical_request("GET", &url, &with_host, EMPTY_PAYLOAD_HASH, true);
+    assert_eq!(sha256_hex(canonical.as_bytes()), "f536975d06c0309214f805bb90ccff089219ecd68b2577efef23edd43b7e1a59");
+}
+
+#[test]
+fn canonical_requests_encode_paths_and_queries() {
+    let url = "https://example.amazonaws.com/a b/~c?b=2&a=x y&a=%2F".parse().unwrap();
+    let (canonical, signed) = canonical_request("GET", &url, &[("My-Header".to_string(), "  one   two ".to_string()), ("my-header".to_string(), "three".to_string())], "hash", true);
+    assert_eq!(canonical, "GET\n/a%2520b/~c\na=%2F&a=x%20y&b=2\nmy-header:one two,three\n\nmy-header\nhash");
+    assert_eq!(signed, "my-header");
+    // S3 takes the path as it is sent
+    let (canonical, _) = canonical_request("GET", &url, &[], "hash", false);
+    assert!(canonical.starts_with("GET\n/a%20b/~c\n"), "{}", canonical);
+}
+
+#[test]
+fn session_tokens_and_s3_payload_hashes_are_signed() {
+    let url = "https://example.amazonaws.com:8443/".parse().unwrap();
+    let signed = signer("s3", Some("session/token")).sign("PUT", &url, &[], "abc123", suite_time());
+    let names: Vec<&str> = signed.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["x-amz-date", "x-amz-security-token", "x-amz-content-sha256", "authorization"]);
+    assert_eq!(signed[1].1, "session/token");
+    assert_eq!(signed[2].1, "abc123");
+    assert!(authorization(&signed).contains("/us-east-1/s3/aws4_request, SignedHeaders=host;x-amz-content-sha256;x-amz-date;x-amz-security-token, "));
+}
+
+#[test]
+fn reads_credentials_profiles() {
+    let file = "[default]\naws_access_key_id = AKIDDEFAULT\naws_secret_access_key = s1\n\n# staging\n[staging]\naws_access_key_id=AKIDSTAGING\naws_secret_access_key=s2\naws_session_token = t2\n[broken]\naws_access_key_id=x\n";
+    let default = Credentials::from_profile(file, "default").unwrap();
+    assert_eq!((default.access_key_id.as_str(), default.session_token), ("AKIDDEFAULT", None));
+    let staging = Credentials::from_profile(file, "staging").unwrap();
+    assert_eq!((staging.secret_access_key.as_str(), staging.session_token.as_deref()), ("s2", Some("t2")));
+    assert!(!format!("{:?}", staging).contains("s2"));
+    assert_eq!(Credentials::from_profile(file, "prod").unwrap_err(), "No AWS profile 'prod'");
+    assert!(Credentials::from_profile(file, "broken").unwrap_err().contains("needs aws_access_key_id and aws_secret_access_key"));
+
+    assert_eq!(Scope::parse("eu-west-1:execute-api").unwrap().to_string(), "eu-west-1:execute-api");
+    for bad in ["eu-west-1", ":execute-api", "a:b:c"] {
+        assert!(Scope::parse(bad).is_err(), "{}", bad);
+    }
+}
+
+#[tokio::test]
+async fn call_signs_requests_with_the_bytes_it_sends() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-sigv4-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let credentials = dir.join("credentials");
+    std::fs::write(&credentials, format!("[ci]\naws_access_key_id = {}\naws_secret_access_key = {}\naws_session_token = tok\n", ACCESS_KEY, SECRET_KEY)).unwrap();
+    std::env::set_var("AWS_SHARED_CREDENTIALS_FILE", &credentials);
+
+    let body = r#"{"b": 1, "a": [1, 2]}"#;
+    let echoed = common::call_json(&["POST", &server.url("/echo/items?z=1&a=2"), "-d", body, "--aws-sigv4", "us-east-1:execute-api", "--aws-profile", "ci"]).await.unwrap();
+    let headers = &echoed["headers"];
+    assert_eq!(headers["x-amz-security-token"], "tok");
+    let authorization = headers["authorization"].as_str().unwrap();
+    assert!(authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"), "{}", authorization);
+    assert!(authorization.contains("/us-east-1/execute-api/aws4_request, SignedHeaders=content-type;host;x-amz-date;x-amz-security-token, "), "{}", authorization);
+
+    // Re-sign what the server received: same signature only if the hashed bytes were the sent ones
+    let url = server.url("/echo/items?z=1&a=2").parse().unwrap();
+    let received = echoed["body"].as_str().unwrap();
+    let time = DateTime::parse_from_str(&format!("{} +0000", headers["x-amz-date"].as_str().unwrap()), "%Y%m%dT%H%M%SZ %z").unwrap().with_timezone(&Utc);
+    let resigned = Signer::new(Scope::parse("us-east-1:execute-api").unwrap(), Credentials::from_profile(&std::fs::read_to_string(&credentials).unwrap(), "ci").unwrap())
+        .sign("POST", &url, &[("content-type".to_string(), "application/json".to_string())], &sha256_hex(received.as_bytes()), time);
+    assert_eq!(authorization, resigned.last().unwrap().1);
+
+    let error = common::call_capture(&["GET", &server.url("/echo"), "--aws-sigv4", "us-east-1:execute-api", "--aws-profile", "nope"]).await.unwrap_err().to_string();
+    assert!(error.contains("No AWS profile 'nope'"), "{}", error);
+    let error = common::call_capture(&["GET", &server.url("/echo"), "--aws-sigv4", "us-east-1"]).await.unwrap_err().to_string();
+    assert!(error.contains("--aws-sigv4 needs REGION:SERVICE"), "{}", error);
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::commands::diff::{diff_json, normalize, Difference};
+use nuts::commands::snapshot::{Snapshot, SnapshotCommand, SnapshotStore};
+use serde_json::json;
+
+fn store(name: &str) -> SnapshotStore {
+    let dir = std::env::temp_dir().join(format!("nuts-snapshots-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    SnapshotStore::new(dir)
+}
+
+#[test]
+fn normalizes_nested_objects_and_drops_ignored_paths() {
+    let response = json!({
+        "user": { "profile": { "name": "ada", "updated_at": "x" }, "id": 7 },
+        "items": [{ "sku": "a", "etag": "1" }, { "etag": "2", "sku": "b" }],
+        "meta": { "request_id": "r-1" },
+    });
+    let ignore = vec!["$.meta.request_id".to_string(), "$.items[*].etag".to_string(), "$.user.*.updated_at".to_string()];
+
+    let normalized = normalize(&response, &ignore);
+    assert_eq!(normalized, json!({
+        "items": [{ "sku": "a" }, { "sku": "b" }],
+        "meta": {},
+        "user": { "id": 7, "profile": { "name": "ada" } },
+    }));
+    assert_eq!(
+        serde_json::to_string(&normalized["user"]).unwrap(),
+        r#"{"id":7,"profile":{"name":"ada"}}"#
+    );
+
+    let changed = json!({
+        "user": { "id": 7, "profile": { "name": "grace", "updated_at": "y" } },
+        "items": [{ "sku": "a", "etag": "3" }],
+        "meta": { "request_id": "r-2" },
+    });
+    assert_eq!(diff_json(&normalized, &normalize(&changed, &ignore), &ignore), vec![
+        Difference::Removed { path: "$.items[1]".into(), value: json!({ "sku": "b" }) },
+        Difference::Changed { path: "$.user.profile.name".into(), left: json!("ada"), right: json!("grace") },
+    ]);
+}
+
+#[test]
+fn stores_snapshots_by_name() {
+    let store = store("store");
+    assert!(store.list().unwrap().is_empty());
+
+    let mut snapshot = SnapshotCommand::parse_request("users", &["POST", "https://api.example.com/users", "-d", "{}", "--ignore-path", "$.id"]).unwrap();
+    snapshot.response = json!({ "a": { "b": [1, 2] } });
+    store.save(&snapshot).unwrap();
+    store.save(&Snapshot { name: "accounts".into(), ..snapshot.clone() }).unwrap();
+
+    assert_eq!(store.load("users").unwrap(), snapshot);
+    assert_eq!(store.list().unwrap().iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["accounts", "users"]);
+    assert!(store.load("missing").unwrap_err().to_string().contains("No snapshot named 'missing'"));
+    assert!(store.load("../etc/passwd").is_err());
+}
+
+#[tokio::test]
+async fn checks_and_updates_a_live_endpoint() {
+    let server = FixtureServer::start().await;
+    let item = common::call_json(&["POST", &server.url("/items"), r#"{"name":"widget","stock":{"count":3,"checked_at":"t1"}}"#]).await.unwrap();
+    let url = server.url(&format!("/items/{}", item["id"]));
+    let command = SnapshotCommand::new(store("live"));
+
+    command.execute(&["snapshot", "save", "widget", "GET", &url, "--ignore-path", "$.stock.checked_at"]).await.unwrap();
+    command.execute(&["snapshot", "check", "widget"]).await.unwrap();
+
+    // Only the ignored field moves: still a match
+    common::call_json(&["PUT", &url, r#"{"name":"widget","stock":{"count":3,"checked_at":"t2"}}"#]).await.unwrap();
+    assert!(command.check("widget").await.unwrap().is_empty());
+
+    common::call_json(&["PUT", &url, r#"{"name":"widget","stock":{"count":0,"checked_at":"t3"}}"#]).await.unwrap();
+    assert_eq!(command.check("widget").await.unwrap(), vec![
+        Difference::Changed { path: "$.stock.count".into(), left: json!(3), right: json!(0) },
+    ]);
+    let error = command.execute(&["snapshot", "check", "widget"]).await.unwrap_err().to_string();
+    assert!(error.starts_with("Snapshot 'widget' changed: 1 difference(s)"), "{}", error);
+
+    command.execute(&["snapshot", "update", "widget"]).await.unwrap();
+    assert!(command.check("widget").await.unwrap().is_empty());
+
+    common::call_capture(&["DELETE", &url]).await.unwrap();
+    let paths: Vec<String> = command.check("widget").await.unwrap().iter().map(|d| d.path().to_string()).collect();
+    assert_eq!(paths[0], "status");
+}
@@ -0,0 +1,122 @@
+use nuts::commands::call::CallCommand;
+use nuts::snippet::{Language, SnippetRequest};
+
+fn request(args: &[&str]) -> SnippetRequest {
+    let options = CallCommand::new().parse_advanced_args(args).unwrap();
+    SnippetRequest::from_options(&options).unwrap()
+}
+
+fn json_post() -> SnippetRequest {
+    request(&["call", "POST", "https://api.example.com/users", "-H", r#"X-Note: say "hi" it's me"#, "--bearer", "t0k\"en", r#"{"name":"O'Brien","tags":["a\"b"],"admin":false,"manager":null}"#])
+}
+
+#[test]
+fn normalizes_call_options() {
+    let request = json_post();
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.headers, [
+        ("Content-Type".to_string(), "application/json".to_string()),
+        ("X-Note".to_string(), r#"say "hi" it's me"#.to_string()),
+    ]);
+    assert_eq!(request.bearer_token.as_deref(), Some("t0k\"en"));
+    assert!(request.json.is_some());
+
+    // An explicit content type wins, and a text body isn't JSON
+    let text = self::request(&["call", "PUT", "https://x.io/a", "-H", "Content-Type: text/plain", "hello"]);
+    assert_eq!(text.headers, [("Content-Type".to_string(), "text/plain".to_string())]);
+    assert_eq!(text.json, None);
+
+    assert_eq!(Language::from_name("Python-Requests"), Some(Language::PythonRequests));
+    assert_eq!(Language::from_name("go"), None);
+    let form = CallCommand::new().parse_advanced_args(&["call", "POST", "https://x.io", "-F", "a=b"]).unwrap();
+    assert!(SnippetRequest::from_options(&form).unwrap_err().to_string().contains("-F form fields"));
+}
+
+#[test]
+fn curl_single_quotes_everything() {
+    assert_eq!(json_post().render(Language::Curl), concat!(
+        "curl -X POST 'https://api.example.com/users' \\\n",
+        "  -H 'Content-Type: application/json' \\\n",
+        "  -H 'X-Note: say \"hi\" it'\\''s me' \\\n",
+        "  -H 'Authorization: Bearer t0k\"en' \\\n",
+        "  --data-raw '{\"name\":\"O'\\''Brien\",\"tags\":[\"a\\\"b\"],\"admin\":false,\"manager\":null}'\n",
+    ));
+
+    let get = request(&["call", "https://x.io/a b", "-u", "ann:p'w", "-L", "-k"]).render(Language::Curl);
+    assert_eq!(get, "curl 'https://x.io/a b' \\\n  -u 'ann:p'\\''w' \\\n  -L \\\n  -k\n");
+}
+
+#[test]
+fn python_uses_json_literals_and_a_bearer_header() {
+    assert_eq!(json_post().render(Language::PythonRequests), concat!(
+        "import requests\n\n",
+        "response = requests.request(\n",
+        "    \"POST\",\n",
+        "    \"https://api.example.com/users\",\n",
+        "    headers={\n",
+        "        \"Content-Type\": \"application/json\",\n",
+        "        \"X-Note\": \"say \\\"hi\\\" it's me\",\n",
+        "        \"Authorization\": \"Bearer t0k\\\"en\",\n",
+        "    },\n",
+        "    json={\"admin\": False, \"manager\": None, \"name\": \"O'Brien\", \"tags\": [\"a\\\"b\"]},\n",
+        "    allow_redirects=False,\n",
+        ")\n",
+        "print(response.status_code)\n",
+        "print(response.text)\n",
+    ));
+
+    let basic = request(&["call", "GET", "https://x.io", "-u", "ann:secret", "-L"]).render(Language::PythonRequests);
+    assert!(basic.contains("    auth=(\"ann\", \"secret\"),\n"), "{}", basic);
+    assert!(!basic.contains("headers=") && !basic.contains("allow_redirects"), "{}", basic);
+}
+
+#[test]
+fn javascript_stringifies_json_and_encodes_basic_auth() {
+    assert_eq!(json_post().render(Language::JavascriptFetch), concat!(
+        "const response = await fetch(\"https://api.example.com/users\", {\n",
+        "  method: \"POST\",\n",
+        "  headers: {\n",
+        "    \"Content-Type\": \"application/json\",\n",
+        "    \"X-Note\": \"say \\\"hi\\\" it's me\",\n",
+        "    \"Authorization\": \"Bearer t0k\\\"en\",\n",
+        "  },\n",
+        "  body: JSON.stringify({\"admin\":false,\"manager\":null,\"name\":\"O'Brien\",\"tags\":[\"a\\\"b\"]}),\n",
+        "  redirect: \"manual\",\n",
+        "});\n",
+        "console.log(response.status);\n",
+        "console.log(await response.text());\n",
+    ));
+
+    let basic = request(&["call", "PUT", "https://x.io", "-u", "ann:secret", "-L", "line\none"]).render(Language::JavascriptFetch);
+    assert!(basic.contains("\"Authorization\": \"Basic YW5uOnNlY3JldA==\""), "{}", basic);
+    assert!(basic.contains("  body: \"line\\none\",\n"), "{}", basic);
+}
+
+#[test]
+fn rust_uses_reqwest_helpers_and_rust_escapes() {
+    assert_eq!(json_post().render(Language::RustReqwest), concat!(
+        "// Cargo.toml: reqwest = { version = \"0.11\", features = [\"blocking\", \"json\"] }, serde_json = \"1\"\n",
+        "fn main() -> Result<(), Box<dyn std::error::Error>> {\n",
+        "    let client = reqwest::blocking::Client::builder()\n",
+        "        .redirect(reqwest::redirect::Policy::none())\n",
+        "        .build()?;\n",
+        "    let response = client\n",
+        "        .request(reqwest::Method::POST, \"https://api.example.com/users\")\n",
+        "        .header(\"Content-Type\", \"application/json\")\n",
+        "        .header(\"X-Note\", \"say \\\"hi\\\" it's me\")\n",
+        "        .bearer_auth(\"t0k\\\"en\")\n",
+        "        .json(&serde_json::json!({\"admin\": false, \"manager\": null, \"name\": \"O'Brien\", \"tags\": [\"a\\\"b\"]}))\n",
+        "        .send()?;\n",
+        "    println!(\"{}\", response.status());\n",
+        "    println!(\"{}\", response.text()?);\n",
+        "    Ok(())\n",
+        "}\n",
+    ));
+
+    // JSON's `\u0001` isn't a Rust escape; custom methods need `from_bytes`
+    let mut purge = request(&["call", "POST", "https://x.io", "-L", "{\"a\":\"\\u0001\"}"]);
+    purge.method = "PURGE".to_string();
+    let purge = purge.render(Language::RustReqwest);
+    assert!(purge.contains(".request(reqwest::Method::from_bytes(\"PURGE\".as_bytes())?, \"https://x.io\")"), "{}", purge);
+    assert!(purge.contains("serde_json::json!({\"a\": \"\\u{1}\"})"), "{}", purge);
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::commands::call::CallCommand;
+use nuts::config::Config;
+use nuts::confirm::{ExecutionPolicy, StepPrompt};
+use nuts::flows::OpenAPISpec;
+use nuts::story::{parse_steps, StoryMode};
+
+/// Answers every confirmation with `y`.
+struct AlwaysYes;
+
+impl StepPrompt for AlwaysYes {
+    fn read(&mut self, _prompt: &str, _initial: &str) -> Option<String> {
+        Some("y".to_string())
+    }
+}
+
+fn args(step: &nuts::story::StoryStep) -> Vec<&str> {
+    step.args.iter().map(String::as_str).collect()
+}
+
+#[test]
+fn finds_requests_bodies_and_curl_commands_in_a_suggestion() {
+    let suggestion = r#"Here's a workflow:
+
+1. Create user account
+POST http://localhost:3000/users
+{
+  "name": "O'Brien",
+  "tags": ["a", "b"]
+}
+
+Expected: 201 with the user
+
+2. Get user details
+GET http://localhost:3000/users/123
+
+**3. Rename them**
+```
+curl -X PATCH http://localhost:3000/users/123 \
+  -H 'Content-Type: application/json' \
+  -d '{"name": "Grace Hopper"}'
+```
+4. DELETE http://localhost:3000/users/123
+"#;
+    let steps = parse_steps(suggestion);
+    assert_eq!(steps.len(), 4, "{:?}", steps);
+    assert_eq!(steps[0].description, "Create user account");
+    assert_eq!(args(&steps[0]), ["call", "POST", "http://localhost:3000/users", "{\n\"name\": \"O'Brien\",\n\"tags\": [\"a\", \"b\"]\n}"]);
+    assert_eq!(args(&steps[1]), ["call", "GET", "http://localhost:3000/users/123"]);
+    assert_eq!(steps[2].description, "Rename them");
+    assert_eq!(args(&steps[2]), ["call", "-X", "PATCH", "http://localhost:3000/users/123", "-H", "Content-Type: application/json", "-d", "{\"name\": \"Grace Hopper\"}"]);
+    assert_eq!(args(&steps[3]), ["call", "DELETE", "http://localhost:3000/users/123"]);
+
+    // Every step goes through the `call` parser
+    let parser = CallCommand::new();
+    let options = parser.parse_advanced_args(&args(&steps[2])).unwrap();
+    assert_eq!((options.method.as_str(), options.body.as_deref()), ("PATCH", Some("{\"name\": \"Grace Hopper\"}")));
+
+    assert!(parse_steps("Visit http://example.com for docs\nGET /relative/path").is_empty());
+}
+
+#[tokio::test]
+async fn runs_the_steps_and_adds_them_to_the_flow() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-story-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut spec = OpenAPISpec::new("shop");
+    spec.paths.insert("/echo/users".to_string(), serde_yaml::from_str("get: { summary: list users }").unwrap());
+    spec.save(&dir.join("shop.yaml")).unwrap();
+
+    let story = StoryMode::new("shop".to_string(), Config::default()).with_flows_dir(dir.clone());
+    let suggestion = format!("1. Create a user\nPOST {}\n{{\"name\": \"Ann\"}}\n\n2. Fetch it\nGET {}\n", server.url("/echo/users"), server.url("/echo/users/1"));
+    story.execute_flow(&suggestion, &mut AlwaysYes).await.unwrap();
+    assert_eq!(server.hits(), 2);
+
+    let spec = OpenAPISpec::load(&dir.join("shop.yaml")).unwrap();
+    let users = &spec.paths["/echo/users"];
+    assert_eq!(users.get.as_ref().unwrap().summary.as_deref(), Some("list users"));
+    let create = users.post.as_ref().unwrap();
+    assert_eq!(create.summary.as_deref(), Some("Create a user"));
+    assert_eq!(create.request_body.as_ref().unwrap().content["application/json"].example, Some(serde_json::json!({ "name": "Ann" })));
+    assert!(spec.paths["/echo/users/1"].get.is_some());
+
+    // A failing step stops the run and names the step
+    let error = story.execute_flow(&format!("GET {}\nGET {}", server.url("/status/500"), server.url("/echo")), &mut AlwaysYes).await.unwrap_err().to_string();
+    assert!(error.starts_with("Step 1 (GET ") && error.ends_with("answered 500"), "{}", error);
+
+    // A dry run sends nothing and leaves the flow alone
+    let dry_run = StoryMode::new("shop".to_string(), Config::default())
+        .with_flows_dir(dir.clone())
+        .with_policy(ExecutionPolicy { dry_run: true, ..Default::default() });
+    dry_run.execute_flow(&format!("DELETE {}", server.url("/echo/users/1")), &mut AlwaysYes).await.unwrap();
+    assert_eq!(server.hits(), 3);
+    assert!(OpenAPISpec::load(&dir.join("shop.yaml")).unwrap().paths["/echo/users/1"].delete.is_none());
+    let _ = std::fs::remove_dir_all(dir);
+}
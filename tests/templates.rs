@@ -0,0 +1,73 @@
+use nuts::templates::{set_path, CallTemplate, TemplateStore};
+use serde_json::json;
+
+#[test]
+fn sets_nested_object_and_array_paths() {
+    let mut body = json!({ "user": { "name": "Ann", "emails": ["a@x.io"] } });
+    set_path(&mut body, "user.name", json!("Bo")).unwrap();
+    set_path(&mut body, "user.emails.0", json!("b@x.io")).unwrap();
+    set_path(&mut body, "user.emails[1]", json!("c@x.io")).unwrap();
+    set_path(&mut body, "user.address.city", json!("Lyon")).unwrap();
+    set_path(&mut body, "tags", json!(["new"])).unwrap();
+    assert_eq!(body, json!({
+        "user": { "name": "Bo", "emails": ["b@x.io", "c@x.io"], "address": { "city": "Lyon" } },
+        "tags": ["new"]
+    }));
+
+    let mut items = json!({ "items": [{ "qty": 1 }] });
+    set_path(&mut items, "items.0.qty", json!(3)).unwrap();
+    set_path(&mut items, "items.1.qty", json!(4)).unwrap();
+    assert_eq!(items, json!({ "items": [{ "qty": 3 }, { "qty": 4 }] }));
+
+    assert_eq!(set_path(&mut items, "items.5.qty", json!(1)).unwrap_err(), "Can't set body.items.5: index 5 is past the end of an array of 2");
+    assert_eq!(set_path(&mut items, "items.first", json!(1)).unwrap_err(), "Can't set body.items.first: 'first' is not an array index");
+    assert_eq!(set_path(&mut items, "items.0.qty.unit", json!("kg")).unwrap_err(), "Can't set body.items.0.qty.unit: body.items.0.qty is not an object or array");
+}
+
+#[test]
+fn overrides_patch_a_template_and_round_trip_through_the_store() {
+    let mut template = CallTemplate {
+        method: "POST".to_string(),
+        url: "{{base}}/users".to_string(),
+        headers: [("X-Team".to_string(), "core".to_string()), ("X-Debug".to_string(), "1".to_string())].into_iter().collect(),
+        body: Some(r#"{"email":"old@x.io","roles":["viewer"]}"#.to_string()),
+        basic_auth: None,
+        bearer_token: Some("t0k".to_string()),
+        oauth2: false,
+        follow_redirects: true,
+        timeout_ms: Some(5000),
+        timeout_secs: None,
+        insecure: false,
+        max_retries: 2,
+        retry_on_status: vec![502, 503],
+        proxy: None,
+        no_cookies: false,
+    };
+    for assignment in ["body.email=new@x.io", "body.roles.1=admin", "body.age=42", r#"body.zip="01234""#, "header.x-debug=", "header.X-Trace=on", "method=put"] {
+        template.apply_override(assignment).unwrap();
+    }
+    assert_eq!(template.method, "PUT");
+    let body: serde_json::Value = serde_json::from_str(template.body.as_deref().unwrap()).unwrap();
+    assert_eq!(body, json!({ "email": "new@x.io", "roles": ["viewer", "admin"], "age": 42, "zip": "01234" }));
+    assert_eq!(template.headers.keys().collect::<Vec<_>>(), ["X-Team", "X-Trace"]);
+    assert!(template.apply_override("nothing").is_err());
+    assert!(template.apply_override("cookie.a=b").unwrap_err().starts_with("Unknown override 'cookie.a'"));
+
+    assert_eq!(template.to_args(), [
+        "call", "PUT", "{{base}}/users", "-H", "X-Team: core", "-H", "X-Trace: on", "--bearer", "t0k", "-L",
+        "--timeout", "5000ms", "--retry", "2", "--retry-on-status", "502,503", template.body.as_deref().unwrap(),
+    ]);
+
+    let dir = std::env::temp_dir().join(format!("nuts-templates-{}", std::process::id()));
+    let store = TemplateStore::new(&dir);
+    store.save("create-user", &template).unwrap();
+    assert_eq!(store.load("create-user").unwrap(), template);
+    assert_eq!(store.list().unwrap().into_iter().map(|(name, _)| name).collect::<Vec<_>>(), ["create-user"]);
+    assert!(store.save("../escape", &template).is_err());
+    assert!(store.load("missing").unwrap_err().to_string().starts_with("No template named 'missing'"));
+
+    // Templates saved with whole seconds still run with their timeout
+    std::fs::write(dir.join("old.yaml"), "method: GET\nurl: http://x.io\ntimeout_secs: 5\n").unwrap();
+    assert_eq!(store.load("old").unwrap().to_args(), ["call", "GET", "http://x.io", "--timeout", "5"]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
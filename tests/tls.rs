@@ -0,0 +1,122 @@
+mod common;
+
+use nuts::commands::call::CallCommand;
+use nuts::commands::perf::PerfCommand;
+use nuts::commands::security::SecurityCommand;
+use nuts::config::Config;
+use nuts::tls::TlsOptions;
+use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslVerifyMode};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn fixture(name: &str) -> String {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls").join(name).to_string_lossy().into_owned()
+}
+
+/// HTTPS server on `localhost` that only talks to clients with a certificate from the test
+/// CA, and answers every request with the client certificate's common name.
+fn start_mtls_server() -> String {
+    let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+    acceptor.set_certificate_chain_file(fixture("server.pem")).unwrap();
+    acceptor.set_private_key_file(fixture("server.key"), SslFiletype::PEM).unwrap();
+    acceptor.set_ca_file(fixture("ca.pem")).unwrap();
+    acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+    let acceptor = Arc::new(acceptor.build());
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let acceptor = acceptor.clone();
+            std::thread::spawn(move || {
+                let Ok(mut stream) = acceptor.accept(stream) else { return };
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buffer[..n]),
+                    }
+                }
+                let name = stream.ssl().peer_certificate()
+                    .and_then(|cert| cert.subject_name().entries().next().and_then(|entry| entry.data().to_string().ok()))
+                    .unwrap_or_default();
+                let body = format!("hello {}", name);
+                let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+                let _ = stream.shutdown();
+            });
+        }
+    });
+    format!("https://localhost:{}/", port)
+}
+
+fn client_tls() -> TlsOptions {
+    TlsOptions { cert: Some(fixture("client.pem")), key: Some(fixture("client.key")), cacert: Some(fixture("ca.pem")) }
+}
+
+#[tokio::test]
+async fn call_presents_the_client_certificate() {
+    let url = start_mtls_server();
+    let (cert, key, ca) = (fixture("client.pem"), fixture("client.key"), fixture("ca.pem"));
+
+    let response = CallCommand::new().execute(&["call", "--cert", &cert, "--key", &key, "--cacert", &ca, &url]).await.unwrap();
+    assert_eq!(response.body, "hello nuts-client");
+
+    // Without a certificate the server ends the handshake
+    assert!(CallCommand::new().execute(&["call", "--cacert", &ca, &url]).await.is_err());
+
+    // The key may follow the certificate in one file
+    let combined = std::env::temp_dir().join(format!("nuts-tls-combined-{}.pem", std::process::id()));
+    std::fs::write(&combined, std::fs::read_to_string(&cert).unwrap() + &std::fs::read_to_string(&key).unwrap()).unwrap();
+    let response = CallCommand::new().execute(&["call", "--cert", &combined.to_string_lossy(), "--cacert", &ca, &url]).await.unwrap();
+    assert_eq!(response.body, "hello nuts-client");
+    let _ = std::fs::remove_file(combined);
+}
+
+#[tokio::test]
+async fn configured_defaults_apply_when_no_flags_are_given() {
+    let url = start_mtls_server();
+    let response = CallCommand::new().with_tls_defaults(client_tls()).execute(&["call", &url]).await.unwrap();
+    assert_eq!(response.body, "hello nuts-client");
+
+    // A command's own --cert comes with its own key, never the configured one
+    let own = TlsOptions { cert: Some("mine.pem".to_string()), ..TlsOptions::default() };
+    let merged = own.or(&client_tls());
+    assert_eq!((merged.cert.as_deref(), merged.key, merged.cacert), (Some("mine.pem"), None, Some(fixture("ca.pem"))));
+}
+
+#[test]
+fn unreadable_or_mismatched_files_are_named_in_the_error() {
+    let missing = TlsOptions { cert: Some("/nonexistent/client.pem".to_string()), ..TlsOptions::default() };
+    let error = missing.load().err().unwrap().to_string();
+    assert!(error.starts_with("Could not read client certificate '/nonexistent/client.pem': "), "{}", error);
+
+    let mismatched = TlsOptions { key: Some(fixture("other.key")), ..client_tls() };
+    let error = mismatched.load().err().unwrap().to_string();
+    assert!(error.contains(&fixture("client.pem")) && error.contains(&fixture("other.key")), "{}", error);
+    assert!(error.contains("key values mismatch"), "{}", error);
+
+    let no_key = TlsOptions { cert: Some(fixture("ca.pem")), ..TlsOptions::default() };
+    assert!(no_key.load().err().unwrap().to_string().starts_with(&format!("No private key in '{}'", fixture("ca.pem"))));
+
+    let bad_ca = TlsOptions { cacert: Some(fixture("client.key")), ..TlsOptions::default() };
+    assert!(bad_ca.load().err().unwrap().to_string().contains(&fixture("client.key")));
+}
+
+#[tokio::test]
+async fn perf_and_security_use_the_client_certificate() {
+    let url = start_mtls_server();
+    let tls = client_tls().load().unwrap();
+
+    let perf = PerfCommand::new(&Config::default()).with_tls(tls.clone());
+    let summary = perf.run(&url, 2, Duration::from_millis(300), "GET", None, &HashMap::new()).await.unwrap();
+    assert!(summary.total_requests > 0);
+    assert_eq!(summary.error_rate, 0.0);
+    assert!(summary.errors.is_empty(), "{:?}", summary.errors);
+
+    let responses = SecurityCommand::new(Config::default()).with_tls(&tls).collect_responses(&url).await.unwrap();
+    assert_eq!(responses[0].status, 200);
+}
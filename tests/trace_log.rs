@@ -0,0 +1,106 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::commands::call::CallCommand;
+use nuts::trace_log::{body_text, hex_dump, Redactor};
+
+const TOKEN: &str = "s3cr3t-bearer-token-4242";
+
+fn trace_path(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("nuts-trace-{}-{}.log", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path.to_string_lossy().to_string()
+}
+
+#[test]
+fn masks_redacted_headers_and_their_values_in_bodies() {
+    let redactor = Redactor::new(&["X-Api-Key".to_string()]);
+    assert_eq!(redactor.header_value("Authorization", "Bearer abcdef"), "***len=13***");
+    assert_eq!(redactor.header_value("x-api-key", "k123456"), "***len=7***");
+    assert_eq!(redactor.header_value("Accept", "*/*"), "*/*");
+
+    let secrets = redactor.secrets([("authorization", "Bearer abcdefgh"), ("accept", "application/json")]);
+    assert_eq!(secrets, vec!["Bearer abcdefgh".to_string(), "abcdefgh".to_string()]);
+    assert_eq!(body_text(br#"{"token":"abcdefgh"}"#, &secrets), r#"{"token":"***len=8***"}"#);
+
+    let dump = hex_dump(&[0xff, 0x00, b'A']);
+    assert!(dump.starts_with("00000000  ff 00 41"), "{}", dump);
+    assert!(dump.trim_end().ends_with("..A"), "{}", dump);
+}
+
+#[tokio::test]
+async fn bearer_token_never_reaches_the_log_or_verbose_output() {
+    let server = FixtureServer::start().await;
+    let url = server.url("/echo/private");
+    let log = trace_path("bearer");
+
+    // The echoed body carries the header back, and is printed with the token masked
+    let args = ["call", "-v", "--trace-log", &log, "--bearer", TOKEN, "POST", &url, "-d", r#"{"hello":"world"}"#];
+    let (result, printed) = nuts::output::capture(CallCommand::new().execute(&args)).await;
+    assert!(result.unwrap().body.contains(TOKEN));
+    let args = ["call", "-v", "-i", "--trace-log", &log, "-H", &format!("Authorization: Bearer {}", TOKEN), &url];
+    let (result, printed_again) = nuts::output::capture(CallCommand::new().execute(&args)).await;
+    result.unwrap();
+
+    let trace = std::fs::read_to_string(&log).unwrap();
+    let _ = std::fs::remove_file(&log);
+    assert!(!trace.contains(TOKEN), "{}", trace);
+    assert!(!printed.contains(TOKEN), "{}", printed);
+    assert!(!printed_again.contains(TOKEN), "{}", printed_again);
+    assert!(printed.contains(r#""authorization": "***len=31***""#), "{}", printed);
+    assert!(trace.contains("authorization: ***len=31***"), "{}", trace);
+    assert!(printed_again.contains("Authorization: ***len=31***"), "{}", printed_again);
+
+    // Both requests and both responses, request line and body included
+    assert_eq!(trace.matches("--- request ").count(), 2);
+    assert_eq!(trace.matches("--- response ").count(), 2);
+    assert!(trace.contains("POST /echo/private HTTP/1.1"), "{}", trace);
+    assert!(trace.contains(r#"{"hello":"world"}"#), "{}", trace);
+    assert!(trace.contains("HTTP/1.1 200 OK"), "{}", trace);
+}
+
+#[tokio::test]
+async fn masks_set_cookie_and_configured_headers() {
+    let server = FixtureServer::start().await;
+    let log = trace_path("cookies");
+
+    let cookies = server.url("/cookies/set?session=abcdef123456");
+    CallCommand::new().execute(&["call", "--trace-log", &log, &cookies]).await.unwrap();
+    let echo = server.url("/echo");
+    CallCommand::new()
+        .with_redacted_headers(&["X-Api-Key".to_string()])
+        .execute(&["call", "--trace-log", &log, "-H", "X-Api-Key: key-0123456789", &echo])
+        .await
+        .unwrap();
+
+    let trace = std::fs::read_to_string(&log).unwrap();
+    let _ = std::fs::remove_file(&log);
+    assert!(!trace.contains("session=abcdef123456; Path=/"), "{}", trace);
+    assert!(trace.contains("set-cookie: ***len="), "{}", trace);
+    assert!(!trace.contains("key-0123456789"), "{}", trace);
+    assert!(trace.contains("x-api-key: ***len=14***"), "{}", trace);
+
+    // Headers captures redact are masked without any config, as are their echoes under -v
+    let args = ["call", "-v", "--trace-log", &log, "-H", "X-Api-Key: key-9876543210", &echo];
+    let (result, printed) = nuts::output::capture(CallCommand::new().execute(&args)).await;
+    result.unwrap();
+    let trace = std::fs::read_to_string(&log).unwrap();
+    let _ = std::fs::remove_file(&log);
+    assert!(!trace.contains("key-9876543210"), "{}", trace);
+    assert!(trace.contains("x-api-key: ***len=14***"), "{}", trace);
+    assert!(!printed.contains("key-9876543210"), "{}", printed);
+    assert!(printed.contains("X-Api-Key: ***len=14***"), "{}", printed);
+}
+
+#[tokio::test]
+async fn binary_bodies_are_hex_dumped() {
+    let server = FixtureServer::start().await;
+    let log = trace_path("bytes");
+
+    common::call_capture(&["--trace-log", &log, &server.url("/bytes/300")]).await.unwrap();
+
+    let trace = std::fs::read_to_string(&log).unwrap();
+    let _ = std::fs::remove_file(&log);
+    assert!(trace.contains("00000000  00 01 02 03"), "{}", trace);
+    assert!(trace.contains("00000120  20 21 22"), "{}", trace);
+}
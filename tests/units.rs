@@ -0,0 +1,81 @@
+use nuts::commands::call::CallCommand;
+use nuts::commands::monitor::MonitorOptions;
+use nuts::units::{parse_duration, parse_positive_duration, parse_size};
+use proptest::prelude::*;
+use std::time::Duration;
+
+#[test]
+fn reads_durations_and_sizes_with_units() {
+    assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+    assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+    assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(1800)));
+    assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+    assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+    assert_eq!(parse_duration(" 10 "), Ok(Duration::from_secs(10)));
+    assert_eq!(parse_duration("0"), Ok(Duration::ZERO));
+
+    assert_eq!(parse_size("512"), Ok(512));
+    assert_eq!(parse_size("64k"), Ok(64 * 1024));
+    assert_eq!(parse_size("10MB"), Ok(10 * 1024 * 1024));
+    assert_eq!(parse_size("1g"), Ok(1 << 30));
+    assert_eq!(parse_size("1.5k"), Ok(1536));
+}
+
+#[test]
+fn says_what_is_wrong_with_a_value() {
+    assert_eq!(parse_duration("30x").unwrap_err(), "'30x' has an unknown unit 'x'; use ms, s, m or h, like 500ms, 30s, 5m or 1h");
+    assert_eq!(parse_duration("-5s").unwrap_err(), "'-5s' is negative");
+    assert_eq!(parse_duration("soon").unwrap_err(), "'soon' is not a number followed by ms, s, m or h, like 500ms, 30s, 5m or 1h");
+    assert!(parse_duration("").unwrap_err().starts_with("a value is required"));
+    assert!(parse_duration("1e400s").is_err());
+    assert_eq!(parse_positive_duration("0s").unwrap_err(), "'0s' must be more than zero");
+    assert_eq!(parse_size("10q").unwrap_err(), "'10q' has an unknown unit 'q'; use k, m or g, like 512, 64k, 10m or 1g");
+    assert!(parse_size("99999999999g").is_err());
+}
+
+#[test]
+fn commands_use_the_shared_parser() {
+    let call = CallCommand::new();
+    let options = call.parse_advanced_args(&["call", "--timeout", "2m", "--connect-timeout", "500ms", "--max-display", "64k", "http://x.io"]).unwrap();
+    assert_eq!(options.timeout, Some(Duration::from_secs(120)));
+    assert_eq!(options.connect_timeout, Some(Duration::from_millis(500)));
+    assert_eq!(options.max_display, Some(64 * 1024));
+    let error = call.parse_advanced_args(&["call", "--timeout", "0", "http://x.io"]).unwrap_err();
+    assert_eq!(error.to_string(), "Invalid --timeout: '0' must be more than zero");
+
+    // 30m used to be read as 30 seconds
+    let options = MonitorOptions::from_args(&["monitor", "https://x.io", "--interval", "30m"]).unwrap();
+    assert_eq!(options.interval, Duration::from_secs(1800));
+    assert!(MonitorOptions::from_args(&["monitor", "https://x.io", "--duration", "1d"]).is_err());
+}
+
+proptest! {
+    #[test]
+    fn durations_scale_by_their_unit(n in 0u64..1_000_000, unit in prop::sample::select(vec![("ms", 1u64), ("s", 1000), ("m", 60_000), ("h", 3_600_000), ("", 1000)])) {
+        let (suffix, millis) = unit;
+        prop_assert_eq!(parse_duration(&format!("{}{}", n, suffix)), Ok(Duration::from_millis(n * millis)));
+        prop_assert_eq!(parse_duration(&format!("{}{}", n, suffix.to_uppercase())), Ok(Duration::from_millis(n * millis)));
+    }
+
+    #[test]
+    fn sizes_scale_by_powers_of_1024(n in 0u64..1_000_000, unit in prop::sample::select(vec![("", 0u32), ("b", 0), ("k", 10), ("kb", 10), ("m", 20), ("mib", 20), ("g", 30)])) {
+        let (suffix, shift) = unit;
+        prop_assert_eq!(parse_size(&format!("{}{}", n, suffix)), Ok(n << shift));
+    }
+
+    #[test]
+    fn unknown_units_and_negative_values_are_refused(n in 1u64..1_000_000, unit in "[a-z]{1,3}") {
+        prop_assume!(!["ms", "s", "m", "h"].contains(&unit.as_str()));
+        let (unknown, negative, negative_size) = (format!("{}{}", n, unit), format!("-{}s", n), format!("-{}k", n));
+        prop_assert!(parse_duration(&unknown).is_err(), "{}", unknown);
+        prop_assert!(parse_duration(&negative).is_err(), "{}", negative);
+        prop_assert!(parse_size(&negative_size).is_err(), "{}", negative_size);
+    }
+
+    #[test]
+    fn any_input_is_parsed_or_refused_without_panicking(text in "\\PC{0,12}") {
+        let _ = parse_duration(&text);
+        let _ = parse_positive_duration(&text);
+        let _ = parse_size(&text);
+    }
+}
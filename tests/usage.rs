@@ -0,0 +1,112 @@
+mod common;
+
+use common::{isolated_home, FixtureServer, AI_KEY};
+use nuts::ai::{self, AiProvider};
+use nuts::config::Config;
+use nuts::usage::{self, ModelPrice, TokenUsage, UsageBook, UsageLedger};
+
+fn tokens(input_tokens: u64, output_tokens: u64) -> TokenUsage {
+    TokenUsage { input_tokens, output_tokens }
+}
+
+#[test]
+fn totals_are_priced_per_day_and_command() {
+    let mut book = UsageBook::default();
+    book.add("2026-10-14", "ask", "claude-sonnet-4-5-20250929", tokens(1_000_000, 100_000));
+    book.add("2026-10-15", "ask", "claude-sonnet-4-5", tokens(500_000, 0));
+    book.add("2026-10-15", "perf", "gpt-4o-mini", tokens(1_000_000, 1_000_000));
+    book.add("2026-10-15", "perf", "local-llama", tokens(10, 10));
+
+    let config = Config::default();
+    let price = |model: &str| config.model_price(model);
+    // Dated snapshots share their family's price: $3 in, $15 out per million
+    assert!((book.cost_on("2026-10-14", price) - 4.5).abs() < 1e-9);
+    assert!((book.cost_on("2026-10-15", price) - (1.5 + 0.75)).abs() < 1e-9);
+    assert_eq!(book.cost_on("2026-10-16", price), 0.0);
+
+    let days = book.by_day(1, price);
+    assert_eq!(days.len(), 1);
+    assert_eq!((days[0].label.as_str(), days[0].counts.requests, days[0].priced), ("2026-10-15", 3, false));
+    let commands = book.by_command(7, price);
+    assert_eq!(commands.iter().map(|row| row.label.as_str()).collect::<Vec<_>>(), ["ask", "perf"]);
+    assert_eq!((commands[0].counts.input_tokens, commands[0].counts.output_tokens, commands[0].priced), (1_500_000, 100_000, true));
+
+    // A configured price wins over the built-in one and prices unknown models
+    let mut config = Config::default();
+    config.ai_prices.insert("local-llama".to_string(), ModelPrice { input: 0.0, output: 0.0 });
+    config.ai_prices.insert("gpt-4o-mini".to_string(), ModelPrice { input: 1.0, output: 1.0 });
+    let commands = book.by_command(7, |model| config.model_price(model));
+    assert!(commands[1].priced);
+    assert!((commands[1].cost - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn concurrent_requests_all_land_in_the_file() {
+    let dir = std::env::temp_dir().join(format!("nuts-usage-{}", std::process::id()));
+    let path = dir.join("usage.json");
+    let _ = std::fs::remove_file(&path);
+
+    let threads: Vec<_> = (0..16)
+        .map(|i| {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                let command = if i % 2 == 0 { "ask" } else { "perf" };
+                UsageLedger::new(path).record(command, "gpt-4o-mini", tokens(100, 10)).unwrap();
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    let book = UsageLedger::new(&path).load().unwrap();
+    let today = &book.days[&usage::today()];
+    assert_eq!((today["ask"]["gpt-4o-mini"].requests, today["perf"]["gpt-4o-mini"].requests), (8, 8));
+    assert_eq!(today["ask"]["gpt-4o-mini"].input_tokens, 800);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn renders_tables_and_the_budget() {
+    assert_eq!(usage::render(&UsageBook::default(), 7, &Config::default()), ["📭 No AI usage recorded yet"]);
+
+    let mut book = UsageBook::default();
+    book.add(&usage::today(), "ask", "gpt-4o", tokens(1_000_000, 0));
+    book.add(&usage::today(), "explain", "mystery-model", tokens(5, 5));
+    let config = Config { ai_daily_budget: Some(2.0), ..Config::default() };
+    let lines = usage::render(&book, 7, &config);
+    assert_eq!(lines[0], "📊 AI usage, last 1 day(s) recorded (estimated cost)");
+    assert!(lines[2].ends_with("$2.50+"), "{}", lines[2]);
+    assert!(lines.iter().any(|line| line.trim_start().starts_with("explain") && line.ends_with("$0.00+")));
+    assert!(lines.iter().any(|line| line.contains("config ai price")));
+    assert_eq!(lines.last().unwrap(), "   Today: $2.50 of the $2.00 daily budget, ⚠️  over budget");
+}
+
+#[tokio::test]
+async fn clients_record_usage_and_an_enforced_budget_skips_optional_calls() {
+    let home = isolated_home("usage");
+    let server = FixtureServer::start().await;
+    let config = Config {
+        ai_provider: AiProvider::OpenAi,
+        ai_base_url: Some(server.url("")),
+        ai_api_key: Some(AI_KEY.to_string()),
+        ai_prices: [("gpt-4o-mini".to_string(), ModelPrice { input: 100_000.0, output: 0.0 })].into(),
+        ai_daily_budget: Some(1.0),
+        ..Config::default()
+    };
+
+    ai::client(&config, "ask").unwrap().ask("hi").await.unwrap();
+    ai::optional_client(&config, "perf").unwrap().ask("summarize").await.unwrap();
+    // Answered from the cache, so free
+    ai::client(&config, "ask").unwrap().ask("hi").await.unwrap();
+    let book = UsageLedger::new(home.join(".nuts").join("usage.json")).load().unwrap();
+    let today = &book.days[&usage::today()];
+    assert_eq!((today["ask"]["gpt-4o-mini"].input_tokens, today["ask"]["gpt-4o-mini"].output_tokens), (10, 5));
+    assert_eq!(today["perf"]["gpt-4o-mini"].requests, 1);
+
+    // $2 spent of a $1 budget: only warned about until it is enforced
+    let enforced = Config { ai_enforce_budget: true, ..config };
+    let error = ai::optional_client(&enforced, "perf").err().unwrap().to_string();
+    assert_eq!(error, "daily AI budget of $1.00 reached (about $2.00 spent today) — raise it with 'config ai budget <usd>'");
+    assert!(ai::client(&enforced, "ask").is_ok());
+}
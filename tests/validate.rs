@@ -0,0 +1,168 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::commands::call::CallCommand;
+use nuts::config::Config;
+use nuts::flows::manager::schema_table;
+use nuts::flows::validate::{response_schema, validate, Violation};
+use nuts::flows::{CollectionManager, Operation, Schema};
+use serde_json::{json, Value};
+
+fn schema(value: Value) -> Schema {
+    serde_json::from_value(value).unwrap()
+}
+
+fn messages(violations: Vec<Violation>) -> Vec<String> {
+    violations.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn checks_types_required_properties_and_nesting() {
+    let order = schema(json!({
+        "type": "object",
+        "required": ["id", "items"],
+        "properties": {
+            "id": { "type": "integer" },
+            "note": { "type": "string", "nullable": true },
+            "status": { "type": "string", "enum": ["open", "paid"] },
+            "items": { "type": "array", "items": {
+                "type": "object",
+                "required": ["price"],
+                "properties": { "price": { "type": "number" }, "tags": { "type": "array", "items": { "type": "string" } } }
+            } }
+        }
+    }));
+
+    let good = json!({ "id": 7, "note": null, "status": "paid", "items": [{ "price": 1.5, "tags": ["a"] }, { "price": 2 }] });
+    assert!(validate(&good, &order, None).is_empty());
+
+    let bad = json!({ "id": 7.5, "status": "lost", "items": [{ "price": 1 }, { "tags": ["a", 3] }, {}, { "price": "9.99" }] });
+    assert_eq!(messages(validate(&bad, &order, None)), [
+        "$.id: expected integer, got number",
+        "$.items[1].price: required property is missing",
+        "$.items[1].tags[1]: expected string, got integer",
+        "$.items[2].price: required property is missing",
+        "$.items[3].price: expected number, got string",
+        "$.status: \"lost\" is not one of \"open\", \"paid\"",
+    ]);
+
+    assert_eq!(messages(validate(&json!([]), &order, None)), ["$: expected object, got array"]);
+    assert_eq!(messages(validate(&json!({ "items": [] }), &order, None)), ["$.id: required property is missing"]);
+    // `2.0` counts as an integer, and a schema without a type accepts anything
+    assert!(validate(&json!({ "id": 2.0, "items": [] }), &order, None).is_empty());
+    assert!(validate(&json!("anything"), &Schema::default(), None).is_empty());
+}
+
+#[test]
+fn follows_refs_type_lists_and_combinators() {
+    let components = json!({ "schemas": {
+        "Money": { "type": "object", "required": ["amount"], "properties": { "amount": { "type": "number" } } },
+        "Node": { "type": "object", "properties": { "child": { "$ref": "#/components/schemas/Node" }, "name": { "type": "string" } } }
+    } });
+    let price = schema(json!({ "$ref": "#/components/schemas/Money" }));
+    assert!(validate(&json!({ "amount": 3 }), &price, Some(&components)).is_empty());
+    assert_eq!(messages(validate(&json!({ "amount": "3" }), &price, Some(&components))), ["$.amount: expected number, got string"]);
+    // Unresolvable references and recursion don't fail or loop
+    assert!(validate(&json!(1), &schema(json!({ "$ref": "#/components/schemas/Missing" })), Some(&components)).is_empty());
+    let deep = json!({ "child": { "child": { "child": { "name": 1 } } } });
+    assert_eq!(messages(validate(&deep, &schema(json!({ "$ref": "#/components/schemas/Node" })), Some(&components))),
+        ["$.child.child.child.name: expected string, got integer"]);
+
+    let optional = schema(json!({ "type": ["string", "null"] }));
+    assert!(validate(&Value::Null, &optional, None).is_empty());
+    assert_eq!(messages(validate(&json!(true), &optional, None)), ["$: expected string or null, got boolean"]);
+
+    let id = schema(json!({ "oneOf": [{ "type": "integer" }, { "type": "string" }] }));
+    assert!(validate(&json!("abc"), &id, None).is_empty());
+    assert_eq!(messages(validate(&json!([1]), &id, None)), ["$: matches none of the 2 oneOf schemas"]);
+    let both = schema(json!({ "allOf": [{ "required": ["a"] }, { "properties": { "b": { "const": 1 } } }] }));
+    assert_eq!(messages(validate(&json!({ "b": 2 }), &both, None)), ["$.a: required property is missing", "$.b: expected 1, got 2"]);
+}
+
+#[test]
+fn picks_the_schema_for_the_status_received() {
+    let operation: Operation = serde_json::from_value(json!({ "responses": {
+        "200": { "content": { "application/json": { "schema": { "type": "array" } } } },
+        "4XX": { "content": { "application/problem+json": { "schema": { "type": "object" } } } },
+        "500": { "description": "no body" }
+    } })).unwrap();
+    let type_of = |status| response_schema(&operation, status).map(|s| s.schema_type.primary().to_string());
+    assert_eq!(type_of(200).as_deref(), Some("array"));
+    assert_eq!(type_of(201).as_deref(), Some("array"));
+    assert_eq!(type_of(404).as_deref(), Some("object"));
+    assert_eq!(type_of(500), None);
+}
+
+#[tokio::test]
+async fn flow_validate_reports_violations_per_endpoint() {
+    let server = FixtureServer::start().await;
+    let dir = std::env::temp_dir().join(format!("nuts-validate-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let spec = format!(r#"openapi: 3.0.0
+info: {{ title: shop, version: 1.0.0 }}
+servers:
+  - url: {}
+paths:
+  /echo/orders:
+    post:
+      requestBody:
+        content:
+          application/json:
+            example: {{ "items": [{{ "price": 1 }}, {{ "price": "2" }}] }}
+      responses:
+        "200":
+          content:
+            application/json:
+              schema:
+                type: object
+                required: [method, parsed, signature]
+                properties:
+                  parsed:
+                    type: object
+                    properties:
+                      items: {{ type: array, items: {{ type: object, properties: {{ price: {{ type: number }} }} }} }}
+  /items:
+    get:
+      responses:
+        "200":
+          content:
+            application/json:
+              schema: {{ type: array, items: {{ type: object }} }}
+  /status/204:
+    get: {{}}
+"#, server.url(""));
+    std::fs::write(dir.join("shop.yaml"), &spec).unwrap();
+    let manager = CollectionManager::new(dir.clone(), Config::default());
+
+    let error = manager.validate_schemas("shop", None).await.unwrap_err().to_string();
+    assert_eq!(error, "1 of 3 endpoint(s) in 'shop' don't match their schema");
+
+    let checks = manager.validate_schemas("shop", Some("/items")).await.unwrap();
+    assert_eq!(checks.len(), 1);
+    assert!(checks[0].schema && checks[0].failures.is_empty());
+
+    let error = manager.validate_schemas("shop", Some("/echo")).await.unwrap_err();
+    assert!(error.to_string().starts_with("1 of 1"), "{}", error);
+    assert!(manager.validate_schemas("shop", Some("/nothing")).await.unwrap_err().to_string().starts_with("No endpoint matching '/nothing'"));
+
+    let table = schema_table(&[nuts::flows::manager::SchemaCheck {
+        endpoint: "POST /echo/orders".to_string(),
+        status: Some(200),
+        schema: true,
+        failures: vec!["$.parsed.items[1].price: expected number, got string".to_string(), "$.signature: required property is missing".to_string()],
+    }]);
+    assert!(table.contains("FAIL: 2 violation(s)\n    • $.parsed.items[1].price: expected number, got string\n"), "{}", table);
+
+    // A single call against the same operation, with the flow given as a file
+    let target = format!("{}:/echo/orders", dir.join("shop.yaml").display());
+    let call = CallCommand::new();
+    let url = server.url("/echo/orders");
+    let error = call.execute(&["call", "--validate-against", &target, "POST", &url, r#"{"items": [{"price": "x"}]}"#])
+        .await.unwrap_err().to_string();
+    assert_eq!(error, format!("2 schema violation(s) against {}", target));
+    let error = call.execute(&["call", "--validate-against", &format!("{}:/missing", dir.join("shop.yaml").display()), "GET", &url])
+        .await.unwrap_err().to_string();
+    assert!(error.starts_with("No path /missing in"), "{}", error);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::FixtureServer;
+use nuts::webhook::{constant_time_eq, verify, Encoding, WebhookSigner};
+
+const FOX: &[u8] = b"The quick brown fox jumps over the lazy dog";
+// HMAC-SHA256 with the key "key" (the Wikipedia HMAC example)
+const FOX_HEX: &str = "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8";
+const FOX_BASE64: &str = "97yD9DBThCSxMpjmqm+xQ+9NWaFJRhdZl0edvC0aPNg=";
+
+const EVENT: &[u8] = br#"{"id":"evt_1","type":"charge.succeeded"}"#;
+const STRIPE_SECRET: &str = "whsec_test_secret";
+// HMAC-SHA256 of `1492774577.<EVENT>` with STRIPE_SECRET
+const STRIPE_V1: &str = "4ee5e8d695c9619e003fd480d3d51700d924e29697337d379a6ef6e06e703ab4";
+
+#[test]
+fn signs_plain_and_stripe_schemes() {
+    let signer = WebhookSigner::parse("hmac-sha256:key:X-Signature").unwrap();
+    assert_eq!((signer.header.as_str(), signer.encoding), ("X-Signature", Encoding::Hex));
+    assert_eq!(signer.sign(FOX, 0), FOX_HEX);
+    assert_eq!(WebhookSigner::parse("hmac-sha256:base64:key:X-Signature").unwrap().sign(FOX, 0), FOX_BASE64);
+
+    let mut stripe = WebhookSigner::parse(&format!("hmac-sha256:{}:Stripe-Signature", STRIPE_SECRET)).unwrap();
+    stripe.timestamped = true;
+    assert_eq!(stripe.sign(EVENT, 1492774577), format!("t=1492774577,v1={}", STRIPE_V1));
+
+    // The secret may contain colons; the header is after the last one
+    assert_eq!(WebhookSigner::parse("hmac-sha256:a:b:c:X-Hub").unwrap().secret, "a:b:c");
+    assert!(!format!("{:?}", signer).contains("key\""));
+    for bad in ["hmac-sha1:key:X-Sig", "hmac-sha256:key", "hmac-sha256::X-Sig", "hmac-sha256:key:", "hmac-sha256:key:Bad Header"] {
+        assert!(WebhookSigner::parse(bad).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn verifies_every_signature_form() {
+    for signature in [FOX_HEX.to_string(), FOX_HEX.to_uppercase(), format!("sha256={}", FOX_HEX), FOX_BASE64.to_string(), format!("base64:{}", FOX_BASE64)] {
+        let verification = verify(FOX, &signature, "key").unwrap();
+        assert!(verification.matches, "{}", signature);
+    }
+    let wrong = verify(FOX, FOX_HEX, "other").unwrap();
+    assert_eq!((wrong.scheme, wrong.matches), ("hex", false));
+    assert_ne!(wrong.expected, FOX_HEX);
+    assert!(!verify(b"The quick brown fox jumps over the lazy cat", FOX_BASE64, "key").unwrap().matches);
+
+    let stripe = verify(EVENT, &format!("t=1492774577,v1={}", STRIPE_V1), STRIPE_SECRET).unwrap();
+    assert_eq!((stripe.scheme, stripe.matches, stripe.timestamp), ("stripe", true, Some(1492774577)));
+    // Any v1 may match, as during a secret rotation; v0 and unknown keys are ignored
+    let rotated = format!("t=1492774577,v1={},v1={},v0=abc", "0".repeat(64), STRIPE_V1);
+    assert!(verify(EVENT, &rotated, STRIPE_SECRET).unwrap().matches);
+    // The timestamp is part of what is signed
+    let replayed = verify(EVENT, &format!("t=1492774578,v1={}", STRIPE_V1), STRIPE_SECRET).unwrap();
+    assert!(!replayed.matches);
+    assert!(replayed.expected.starts_with("t=1492774578,v1="));
+
+    assert_eq!(verify(EVENT, "t=1492774577", STRIPE_SECRET).unwrap_err(), "The signature has no v1= entry");
+    assert!(verify(EVENT, "t=soon,v1=abc", STRIPE_SECRET).is_err());
+    assert!(verify(EVENT, "not a signature!", STRIPE_SECRET).is_err());
+
+    assert!(constant_time_eq(b"abc", b"abc"));
+    assert!(!constant_time_eq(b"abc", b"abd"));
+    assert!(!constant_time_eq(b"abc", b"abcd"));
+}
+
+#[tokio::test]
+async fn call_signs_the_body_it_sends() {
+    let server = FixtureServer::start().await;
+    let url = server.url("/echo/hooks");
+    let echoed = common::call_json(&["POST", &url, "-d", r#"{"b": 1, "a": 2}"#, "--sign", "hmac-sha256:key:X-Signature"]).await.unwrap();
+    let sent = echoed["body"].as_str().unwrap();
+    assert!(verify(sent.as_bytes(), echoed["headers"]["x-signature"].as_str().unwrap(), "key").unwrap().matches);
+
+    let echoed = common::call_json(&["POST", &url, "--sign-timestamp", "-d", "plain text", "--sign", "hmac-sha256:whsec_1:Stripe-Signature"]).await.unwrap();
+    let header = echoed["headers"]["stripe-signature"].as_str().unwrap();
+    assert!(header.starts_with("t="), "{}", header);
+    let verification = verify(b"plain text", header, "whsec_1").unwrap();
+    assert!(verification.matches && verification.timestamp.is_some());
+
+    let error = common::call_capture(&["POST", &url, "-d", "x", "--sign-timestamp"]).await.unwrap_err();
+    assert_eq!(error.to_string(), "--sign-timestamp needs --sign hmac-sha256:SECRET:HEADER");
+}
@@ -0,0 +1,66 @@
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::{response::Response, routing::get, Router};
+use nuts::commands::ws::{WsCommand, WsEvent};
+use std::time::{Duration, Instant};
+
+/// Echo server: `binary` replies with bytes, `ping` pings, `close` closes with 4001.
+async fn start_ws_server() -> String {
+    async fn upgrade(ws: WebSocketUpgrade) -> Response {
+        ws.on_upgrade(handle)
+    }
+    async fn handle(mut socket: WebSocket) {
+        while let Some(Ok(message)) = socket.recv().await {
+            let Message::Text(text) = message else { continue };
+            let reply = match text.as_str() {
+                "binary" => Message::Binary(vec![0, 1, 2, 255]),
+                "ping" => Message::Ping(b"hi".to_vec()),
+                "close" => Message::Close(Some(CloseFrame { code: 4001, reason: "bye".into() })),
+                _ => Message::Text(format!("echo: {}", text)),
+            };
+            if socket.send(reply).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, Router::new().route("/ws", get(upgrade))).await.unwrap() });
+    format!("ws://{}/ws", addr)
+}
+
+#[tokio::test]
+async fn sends_messages_and_records_frames_until_close() {
+    let url = start_ws_server().await;
+
+    let events = WsCommand::new()
+        .execute(&["ws", &url, "--send", r#"{"a":1}"#, "--send", "binary", "--send", "ping", "--send", "close"])
+        .await
+        .unwrap();
+
+    assert_eq!(events[..4], [
+        WsEvent::Sent(r#"{"a":1}"#.into()),
+        WsEvent::Sent("binary".into()),
+        WsEvent::Sent("ping".into()),
+        WsEvent::Sent("close".into()),
+    ]);
+    assert_eq!(events[4..], [
+        WsEvent::Text(r#"echo: {"a":1}"#.into()),
+        WsEvent::Binary(vec![0, 1, 2, 255]),
+        WsEvent::Ping(b"hi".to_vec()),
+        WsEvent::Closed { code: 4001, reason: "bye".into() },
+    ]);
+}
+
+#[tokio::test]
+async fn stops_listening_at_the_timeout() {
+    let url = start_ws_server().await;
+
+    let started = Instant::now();
+    let events = WsCommand::new().execute(&["ws", &url, "--send", "hello", "--timeout", "0.3"]).await.unwrap();
+    assert!(started.elapsed() < Duration::from_secs(3));
+    assert_eq!(events, [WsEvent::Sent("hello".into()), WsEvent::Text("echo: hello".into())]);
+
+    let error = WsCommand::new().execute(&["ws", "ws://127.0.0.1:1/ws", "--send", "x"]).await.unwrap_err();
+    assert!(error.to_string().contains("connection to ws://127.0.0.1:1/ws failed"), "{}", error);
+}